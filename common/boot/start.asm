@@ -11,6 +11,9 @@ extern alt_init_paging
 ; extern alt_acpi
 
 %define MULTIBOOT_INFOS_LEN 128
+; Modules are described by 16 bytes: mod_start, mod_end, string, reserved
+%define MULTIBOOT_MODS_FLAG (1 << 3)
+%define MULTIBOOT_MODS_MAX 8
 
 ; Declare constants used for creating a multiboot header.
 %define MEMINFO   (1 << 0)
@@ -92,6 +95,22 @@ _init:
 	cld
 	rep movsb
 
+	; Store the list of modules too: the bootloader may have put it anywhere in memory
+	test dword [multiboot_infos], MULTIBOOT_MODS_FLAG
+	jz .no_modules
+	mov ecx, [multiboot_infos + 20]
+	cmp ecx, MULTIBOOT_MODS_MAX
+	jbe .copy_modules
+	mov ecx, MULTIBOOT_MODS_MAX
+	mov [multiboot_infos + 20], ecx
+.copy_modules:
+	shl ecx, 4
+	mov esi, [multiboot_infos + 24]
+	mov edi, multiboot_modules
+	rep movsb
+	mov dword [multiboot_infos + 24], multiboot_modules
+.no_modules:
+
 	; Set up a early GDT
 	; reserve 8 bytes for structure pointer (need six bytes)
 	sub esp, 8
@@ -169,3 +188,7 @@ temporary_stack:
 ; Early backup of multiboot info structure
 multiboot_infos:
 times MULTIBOOT_INFOS_LEN db 0xff
+
+; Early backup of the multiboot modules list, mods_addr of the backup points here
+multiboot_modules:
+times MULTIBOOT_MODS_MAX * 16 db 0
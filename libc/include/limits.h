@@ -118,6 +118,10 @@
  * {STREAM_MAX}
  *     Maximum number of streams that one process can have open at one time. If defined, it has the same value as {FOPEN_MAX} (see <stdio.h>).
  *     Minimum Acceptable Value: {_POSIX_STREAM_MAX}
+ */
+# define RTSIG_MAX 32
+# define SIGQUEUE_MAX 32
+/*
  * {SYMLOOP_MAX}
  *     Maximum number of symbolic links that can be reliably traversed in the resolution of a pathname in the absence of a loop.
  *     Minimum Acceptable Value: {_POSIX_SYMLOOP_MAX} */
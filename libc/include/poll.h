@@ -0,0 +1,47 @@
+#ifndef __POLL_H__
+# define __POLL_H__
+
+#include <signal.h>
+#include <time.h>
+
+//The <poll.h> header shall define the pollfd structure, which shall include at least the following members:
+struct pollfd {
+	int    fd      ;// The following descriptor being polled.
+	short  events  ;// The input event flags (see below).
+	short  revents ;// The output event flags (see below).
+};
+
+//The <poll.h> header shall define the following type through typedef:
+typedef unsigned int nfds_t;
+//    An unsigned integer type used for the number of file descriptors.
+
+//The following symbolic constants shall be defined, zero or more of which may be OR'ed together to form the events or revents members in the pollfd structure:
+#define POLLIN     0x001
+//    Data other than high-priority data may be read without blocking.
+#define POLLPRI    0x002
+//    High priority data may be read without blocking.
+#define POLLOUT    0x004
+//    Normal data may be written without blocking.
+#define POLLERR    0x008
+//    An error has occurred (revents only).
+#define POLLHUP    0x010
+//    Device has been disconnected (revents only).
+#define POLLNVAL   0x020
+//    Invalid fd member (revents only).
+#define POLLRDNORM 0x040
+//    Normal data may be read without blocking.
+#define POLLRDBAND 0x080
+//    Priority data may be read without blocking.
+#define POLLWRNORM 0x100
+//    Equivalent to POLLOUT.
+#define POLLWRBAND 0x200
+//    Priority data may be written.
+
+//The following shall be declared as a function and may also be defined as a macro. A function prototype shall be provided.
+int poll(struct pollfd fds[], nfds_t nfds, int timeout);
+
+// Linux extension: poll with a nanosecond timeout and a temporary signal mask
+int ppoll(struct pollfd fds[], nfds_t nfds,
+	  const struct timespec *timeout, const sigset_t *sigmask);
+
+#endif
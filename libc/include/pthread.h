@@ -0,0 +1,41 @@
+#ifndef __PTHREAD_H__
+# define __PTHREAD_H__
+
+#include <sys/types.h>
+#include <sched.h>
+#include <time.h>
+
+/*
+ * The threads are created with clone(CLONE_THREAD), their TLS segment
+ * is loaded in gs. The mutexes and the condition variables are futex
+ * words: they are only shared between the threads of a process.
+ *
+ * Note: malloc() is not thread-safe yet, so pthread_create() and
+ * pthread_join() should only be called by one thread at a time.
+ */
+
+#define PTHREAD_MUTEX_INITIALIZER 0
+#define PTHREAD_COND_INITIALIZER  0
+
+int       pthread_create(pthread_t *thread, const pthread_attr_t *attr,
+			 void *(*start_routine)(void *), void *arg);
+int       pthread_join(pthread_t thread, void **retval);
+void      pthread_exit(void *retval) __attribute__ ((noreturn));
+pthread_t pthread_self(void);
+int       pthread_equal(pthread_t t1, pthread_t t2);
+
+int       pthread_mutex_init(pthread_mutex_t *mutex, const pthread_mutexattr_t *attr);
+int       pthread_mutex_destroy(pthread_mutex_t *mutex);
+int       pthread_mutex_lock(pthread_mutex_t *mutex);
+int       pthread_mutex_trylock(pthread_mutex_t *mutex);
+int       pthread_mutex_unlock(pthread_mutex_t *mutex);
+
+int       pthread_cond_init(pthread_cond_t *cond, const pthread_condattr_t *attr);
+int       pthread_cond_destroy(pthread_cond_t *cond);
+int       pthread_cond_wait(pthread_cond_t *cond, pthread_mutex_t *mutex);
+int       pthread_cond_timedwait(pthread_cond_t *cond, pthread_mutex_t *mutex,
+				 const struct timespec *abstime);
+int       pthread_cond_signal(pthread_cond_t *cond);
+int       pthread_cond_broadcast(pthread_cond_t *cond);
+
+#endif
@@ -28,14 +28,42 @@
 #define CLONE_NEWNET         0x40000000	/* New network namespace */
 #define CLONE_IO             0x80000000	/* Clone io context */
 
+#define SCHED_OTHER          0	/* time sharing, weighted by the nice value */
+#define SCHED_FIFO           1	/* real time, first in first out */
+#define SCHED_RR             2	/* real time, round robin */
+
 struct sched_param
 {
 	int sched_priority;
 };
 
+/*
+ * Description of a TLS segment, as given to set_thread_area() and to
+ * clone() with CLONE_SETTLS. An entry_number of -1 lets the kernel
+ * choose the GDT entry, its selector is ((entry_number << 3) | 3)
+ */
+struct user_desc
+{
+	unsigned int entry_number;
+	unsigned int base_addr;
+	unsigned int limit;
+	unsigned int seg_32bit:1;
+	unsigned int contents:2;
+	unsigned int read_exec_only:1;
+	unsigned int limit_in_pages:1;
+	unsigned int seg_not_present:1;
+	unsigned int useable:1;
+};
+
 int	clone(int (*fn)(void *), void *child_stack,
-		  int flags, void *arg/*, pid_t *ptid, void *newtls, pid_t *ctid*/);
+		  int flags, void *arg, .../* pid_t *ptid, struct user_desc *newtls, pid_t *ctid */);
+int	set_thread_area(struct user_desc *u_info);
 
 int	sched_setscheduler(pid_t pid, int policy,
 		       const struct sched_param *param);
+int	sched_getscheduler(pid_t pid);
+int	sched_getparam(pid_t pid, struct sched_param *param);
+int	sched_get_priority_max(int policy);
+int	sched_get_priority_min(int policy);
+int	sched_yield(void);
 #endif
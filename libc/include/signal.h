@@ -40,6 +40,10 @@
 #define SIGSYS        31     /* Bad system call.  */
 #define SIGUNUSED     31
 
+/* Real-time signals: queued, ordered and carrying a value.  */
+#define SIGRTMIN      32
+#define SIGRTMAX      63
+
 typedef void (*sighandler_t)(int);
 
 /*
@@ -116,7 +120,7 @@ typedef void (*sighandler_t)(int);
 
 #define sig_atomic_t int
 //    Possibly volatile-qualified integer type of an object that can be accessed as an atomic entity, even in the presence of asynchronous interrupts.
-#define sigset_t unsigned int
+#define sigset_t unsigned long long
     //[CX] [Option Start] Integer or structure type of an object used to represent sets of signals. [Option End]
 //pid_t
     //[CX] [Option Start] As described in <sys/types.h>. [Option End]
@@ -189,6 +193,13 @@ typedef struct ucontext {
 							 //context. 
 } ucontext_t;
 
+/* ss_flags values of stack_t */
+#define SS_ONSTACK    1 /* Process is executing on an alternate signal stack. */
+#define SS_DISABLE    2 /* Alternate signal stack is disabled. */
+
+#define MINSIGSTKSZ   2048 /* Minimum stack size for a signal handler. */
+#define SIGSTKSZ      8192 /* Default size in bytes for the alternate signal stack. */
+
 //[Option End]
 
 //[CX] [Option Start] The <signal.h> header shall define the siginfo_t type as a structure, which shall include at least the following members: [Option End]
@@ -271,6 +282,28 @@ struct sigaction {
 
 //[CX] [Option Start] The <signal.h> header shall define the symbolic constants in the Code column of the following table for use as values of si_code that are signal-specific or non-signal-specific reasons why the signal was generated. [Option End]
 
+/* Non-signal-specific codes */
+#define SI_USER       0    /* Signal sent by kill(). */
+#define SI_QUEUE      -1   /* Signal sent by sigqueue(). */
+#define SI_TIMER      -2   /* Signal generated by expiration of a timer. */
+#define SI_KERNEL     0x80 /* Signal sent by the kernel. */
+
+/* SIGSEGV codes */
+#define SEGV_MAPERR   1    /* Address not mapped to object. */
+#define SEGV_ACCERR   2    /* Invalid permissions for mapped object. */
+
+/* SIGTRAP codes */
+#define TRAP_BRKPT    1    /* Process breakpoint. */
+#define TRAP_TRACE    2    /* Process trace trap. */
+
+/* SIGCHLD codes */
+#define CLD_EXITED    1    /* Child has exited. */
+#define CLD_KILLED    2    /* Child has terminated abnormally and did not create a core file. */
+#define CLD_DUMPED    3    /* Child has terminated abnormally and created a core file. */
+#define CLD_TRAPPED   4    /* Traced child has trapped. */
+#define CLD_STOPPED   5    /* Child has stopped. */
+#define CLD_CONTINUED 6    /* Stopped child has continued. */
+
 //If si_code is equal to CLD_EXITED, then si_status holds the exit value of the process; otherwise, it is equal to the signal that caused the process to change state. The exit value in si_status shall be equal to the full exit value (that is, the value passed to _exit(), _Exit(), or exit(), or returned from main()); it shall not be limited to the least significant eight bits of the value.
 
 //Band event for POLL_IN, POLL_OUT, or POLL_MSG.
@@ -337,6 +370,6 @@ int    sigwaitinfo(const sigset_t *restrict, siginfo_t *restrict);
 //[CX] [Option Start] Inclusion of the <signal.h> header may make visible all symbols from the <time.h> header. [Option End]
 
 //TODO: check NON POSIX
-#define NSIG 42
+#define NSIG 64
 
 #endif
@@ -2,6 +2,11 @@
 # define __STROPTS_H__
 
 # define TIOCGWINSZ           0x5413
+# define TIOCSWINSZ           0x5414
+// Get the number N of the pseudo-terminal /dev/pts/N
+# define TIOCGPTN             0x5430
+// Lock or unlock the slave side of a pseudo-terminal
+# define TIOCSPTLCK           0x5431
 
 # define RAW_SCANCODE_MODE    0x1
 # define GET_FRAME_BUFFER_PTR 0x3
@@ -0,0 +1,23 @@
+#ifndef __FUTEX_H__
+# define __FUTEX_H__
+
+#include <time.h>
+
+/*
+ * Fast user-space locking: a futex is an aligned int in user memory,
+ * the kernel only intervenes to put threads to sleep and to wake them
+ */
+
+#define FUTEX_WAIT           0	/* sleep while *uaddr == val, until a wake or the timeout */
+#define FUTEX_WAKE           1	/* wake at most val threads waiting on uaddr */
+#define FUTEX_REQUEUE        3	/* wake val waiters, move at most val2 of the others on uaddr2 */
+#define FUTEX_CMP_REQUEUE    4	/* same as FUTEX_REQUEUE, if *uaddr is still equal to val3 */
+
+#define FUTEX_PRIVATE_FLAG   128	/* the futex is not shared with other processes */
+#define FUTEX_CMD_MASK       (~FUTEX_PRIVATE_FLAG)
+
+int futex(int *uaddr, int futex_op, int val,
+	  const struct timespec *timeout, /* or: unsigned int val2 */
+	  int *uaddr2, int val3);
+
+#endif
@@ -26,11 +26,11 @@
 
 //[XSI|SIO] [Option Start] The <sys/mman.h> header shall define the following symbolic constants for the msync() function:
 //
-//MS_ASYNC
+#define MS_ASYNC (1 << 0)
 //    Perform asynchronous writes.
-//MS_INVALIDATE
+#define MS_INVALIDATE (1 << 1)
 //    Invalidate mappings.
-//MS_SYNC
+#define MS_SYNC (1 << 2)
 //    Perform synchronous writes.
 
 //[Option End]
@@ -1,9 +1,25 @@
 #ifndef __SYS_MOUNT_H__
 #define __SYS_MOUNT_H__
 
+/* Mount read-only */
+#define MS_RDONLY	1
+/* Ignore the set-user-ID and set-group-ID bits of the files */
+#define MS_NOSUID	2
+/* Disallow the access to the device files */
+#define MS_NODEV	4
+/* Disallow the execution of the programs */
+#define MS_NOEXEC	8
+/* Change the flags of an existing mount */
+#define MS_REMOUNT	32
+/* Make a directory tree visible at another place */
+#define MS_BIND		4096
+/* Move an existing mount to another place */
+#define MS_MOVE		8192
+
 int mount(const char *source, const char *target,
 		  const char *filesystemtype, unsigned long mountflags,
 		  const void *data);
 int umount(const char *target);
+int pivot_root(const char *new_root, const char *put_old);
 
 #endif
@@ -0,0 +1,36 @@
+#ifndef __SYS_PTRACE_H__
+# define __SYS_PTRACE_H__
+
+#include <sys/types.h>
+#include <sys/user.h>
+
+/*
+ * Process tracing: the tracer observes and controls the execution of
+ * its tracee, which stops on the delivery of its signals, around its
+ * syscalls or after each instruction. The stops are reported by waitpid()
+ */
+
+#define PTRACE_TRACEME      0	/* the calling process is traced by its parent */
+#define PTRACE_PEEKTEXT     1	/* read a word at addr in the tracee memory */
+#define PTRACE_PEEKDATA     2
+#define PTRACE_PEEKUSER     3	/* read the word at offset addr in its struct user_regs_struct */
+#define PTRACE_POKETEXT     4	/* write the word data at addr in the tracee memory */
+#define PTRACE_POKEDATA     5
+#define PTRACE_POKEUSER     6	/* write the word data at offset addr in its struct user_regs_struct */
+#define PTRACE_CONT         7	/* resume the tracee, delivering it the signal data if not 0 */
+#define PTRACE_KILL         8	/* send SIGKILL to the tracee */
+#define PTRACE_SINGLESTEP   9	/* resume the tracee, which stops after one instruction */
+#define PTRACE_GETREGS      12	/* copy the tracee registers in the struct user_regs_struct at data */
+#define PTRACE_SETREGS      13	/* set the tracee registers from the struct user_regs_struct at data */
+#define PTRACE_ATTACH       16	/* trace the process pid, which is sent SIGSTOP */
+#define PTRACE_DETACH       17	/* stop tracing the tracee, resuming it like PTRACE_CONT */
+#define PTRACE_SYSCALL      24	/* resume the tracee, which stops at its next syscall entry or exit */
+#define PTRACE_SETOPTIONS   0x4200	/* set the PTRACE_O_* options given in data */
+#define PTRACE_GETSIGINFO   0x4202	/* copy the siginfo_t of the stop signal at data */
+
+/* The syscall stops are reported with the stop signal SIGTRAP | 0x80 */
+#define PTRACE_O_TRACESYSGOOD 1
+
+long ptrace(int request, pid_t pid, void *addr, void *data);
+
+#endif
@@ -14,13 +14,13 @@
 //
 //The <sys/resource.h> header shall define the following type through typedef:
 //
-typedef int rlim_t;
+typedef unsigned int rlim_t;
 //    Unsigned integer type used for limit values.
 //
 //The <sys/resource.h> header shall define the following symbolic constants, which shall have values suitable for use in #if preprocessing directives:
 //
 //    A value of rlim_t indicating no limit.
-#define RLIM_INFINITY (~0UL)
+#define RLIM_INFINITY (~0U)
 //RLIM_SAVED_MAX
 //    A value of type rlim_t indicating an unrepresentable saved hard limit.
 //RLIM_SAVED_CUR
@@ -67,6 +67,10 @@ struct rusage {
 //    Limit on stack size.
 #define RLIMIT_AS 6
 //    Limit on address space size.
+#define RLIMIT_NPROC 7
+//    Limit on number of processes of the real user ID.
+#define RLIM_NLIMITS 8
+//    Number of resources.
 //
 //The following shall be declared as functions and may also be defined as macros. Function prototypes shall be provided.
 
@@ -75,6 +79,7 @@ int getrlimit(int, struct rlimit *);
 int getrusage(int, struct rusage *);
 int setpriority(int, id_t, int);
 int setrlimit(int, const struct rlimit *);
+int prlimit(pid_t pid, int resource, const struct rlimit *new_limit, struct rlimit *old_limit);
 
 //The <sys/resource.h> header shall define the id_t type through typedef, as described in <sys/types.h>.
 //
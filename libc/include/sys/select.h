@@ -22,13 +22,19 @@ struct timeval {
 //
 //The <sys/select.h> header shall define the fd_set type as a structure.
 
-typedef struct _fd_set {
-} fd_set;
 //
 //The <sys/select.h> header shall define the following symbolic constant, which shall have a value suitable for use in #if preprocessing directives:
 //
-#define FD_SETSIZE 0
+#define FD_SETSIZE 1024
 //    Maximum number of file descriptors in an fd_set structure.
+
+typedef unsigned int __fd_mask;
+// Number of file descriptors in a __fd_mask
+#define __NFDBITS 32
+
+typedef struct _fd_set {
+	__fd_mask fds_bits[FD_SETSIZE / __NFDBITS];
+} fd_set;
 //
 //The following shall be declared as functions, defined as macros, or both. If functions are declared, function prototypes shall be provided.
 //
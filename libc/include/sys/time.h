@@ -19,12 +19,12 @@ struct itimerval {
 
 //[OB] [Option Start] The <sys/time.h> header shall define the following symbolic constants for the which argument of getitimer() and setitimer():
 //
-//ITIMER_REAL
 //    Decrements in real time.
-//ITIMER_VIRTUAL
+#define ITIMER_REAL    0
 //    Decrements in process virtual time.
-//ITIMER_PROF
+#define ITIMER_VIRTUAL 1
 //    Decrements both in process virtual time and when the system is running on behalf of the process.
+#define ITIMER_PROF    2
 //
 //[Option End]
 
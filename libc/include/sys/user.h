@@ -0,0 +1,28 @@
+#ifndef __SYS_USER_H__
+# define __SYS_USER_H__
+
+/*
+ * The registers of a traced process, as read and written by
+ * PTRACE_GETREGS, PTRACE_SETREGS, PTRACE_PEEKUSER and PTRACE_POKEUSER
+ */
+struct user_regs_struct {
+	long ebx;
+	long ecx;
+	long edx;
+	long esi;
+	long edi;
+	long ebp;
+	long eax;
+	long xds;
+	long xes;
+	long xfs;
+	long xgs;
+	long orig_eax;	/* the syscall number, at a syscall stop */
+	long eip;
+	long xcs;
+	long eflags;
+	long esp;
+	long xss;
+};
+
+#endif
@@ -9,9 +9,9 @@
 
 /*
  * Overview of exit status in turbofish:
- * bit 0..7   : basic return value
+ * bit 0..7   : basic return value, or the stop signal
  * bit 8..12  : signal exit value
- * bit 13     : signal stoped state    (with WUNTRACED)
+ * bit 13     : signal stoped state    (with WUNTRACED, or for a traced process)
  * bit 14     : signal continue state  (with WCONTINUED)
  */
 
@@ -61,7 +61,7 @@
  * TRUE if STATUS indicates normal termination by exit(n) or return(n) from main.
  * In the others cases, by a signal terminaison for exemple, this macro returns FALSE
  */
-#define	WIFEXITED(status)	(WTERMSIG(status) == 0)
+#define	WIFEXITED(status)	(((status) & ~EXITED_STATUS_BITS) == 0)
 
 /*
  * returns true if the child process was stopped by delivery of a signal;
@@ -73,8 +73,9 @@
 /*
  * returns the number of the signal which caused the child to stop.
  * This macro should be employed only if WIFSTOPPED returned true.
+ * The syscall stops of a traced process may report SIGTRAP | 0x80 (see ptrace(2)).
  */
-#define	WSTOPSIG(status)	WTERMSIG(status)
+#define	WSTOPSIG(status)	((status) & EXITED_STATUS_BITS)
 
 /*
  * Since turbofish 0.5: returns true if the child process was resumed by delivery of SIGCONT
@@ -120,15 +120,15 @@ struct termios {
 
 //    BRKINT
 //        Signal interrupt on break.
-//    ICRNL
+#define ICRNL (1 << 0)
 //        Map CR to NL on input.
 //    IGNBRK
 //        Ignore break condition.
-//    IGNCR
+#define IGNCR (1 << 1)
 //        Ignore CR.
 //    IGNPAR
 //        Ignore characters with parity errors.
-//    INLCR
+#define INLCR (1 << 2)
 //        Map NL to CR on input.
 //    INPCK
 //        Enable input parity check.
@@ -147,11 +147,11 @@ struct termios {
 //
 //    The <termios.h> header shall define the following symbolic constants for use as flags in the c_oflag field. The c_oflag field specifies the system treatment of output.
 //
-//    OPOST
+#define OPOST (1 << 0)
 //        Post-process output.
-//    ONLCR
+#define ONLCR (1 << 1)
 //        [XSI] [Option Start] Map NL to CR-NL on output. [Option End]
-//    OCRNL
+#define OCRNL (1 << 2)
 //        [XSI] [Option Start] Map CR to NL on output. [Option End]
 //    ONOCR
 //        [XSI] [Option Start] No CR output at column 0. [Option End]
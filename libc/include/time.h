@@ -53,22 +53,22 @@ struct itimerspec {
 
 //[CX] [Option Start] The <time.h> header shall define the following symbolic constants. The values shall have a type that is assignment-compatible with clockid_t. [Option End]
 
-//CLOCK_MONOTONIC
 //    [MON] [Option Start]
 //    The identifier for the system-wide monotonic clock, which is defined as a clock measuring real time, whose value cannot be set via clock_settime() and which cannot have negative clock jumps. The maximum possible clock jump shall be implementation-defined. [Option End]
-//CLOCK_PROCESS_CPUTIME_ID
+#define CLOCK_MONOTONIC          1
 //    [CPT] [Option Start]
 //    The identifier of the CPU-time clock associated with the process making a clock() or timer*() function call. [Option End]
-//CLOCK_REALTIME
+#define CLOCK_PROCESS_CPUTIME_ID 2
 //    [CX] [Option Start] The identifier of the system-wide clock measuring real time. [Option End]
+#define CLOCK_REALTIME           0
 //CLOCK_THREAD_CPUTIME_ID
 //    [TCT] [Option Start]
 //    The identifier of the CPU-time clock associated with the thread making a clock() or timer*() function call. [Option End]
 //
 //[CX] [Option Start] The <time.h> header shall define the following symbolic constant:
 //
-//TIMER_ABSTIME
 //    Flag indicating time is absolute. For functions taking timer objects, this refers to the clock associated with the timer.
+#define TIMER_ABSTIME 1
 //
 //[Option End]
 //
@@ -87,10 +87,10 @@ char      *asctime(const struct tm *);
 //int        clock_getcpuclockid(pid_t, clockid_t *);
 //[Option End]
 //[CX][Option Start]
-//int        clock_getres(clockid_t, struct timespec *);
-//int        clock_gettime(clockid_t, struct timespec *);
-//int        clock_nanosleep(clockid_t, int, const struct timespec *,
-//               struct timespec *);
+int        clock_getres(clockid_t, struct timespec *);
+int        clock_gettime(clockid_t, struct timespec *);
+int        clock_nanosleep(clockid_t, int, const struct timespec *,
+               struct timespec *);
 //int        clock_settime(clockid_t, const struct timespec *);
 //[Option End]
 //[OB][Option Start]
@@ -24,10 +24,13 @@ int _user_syscall(u32 syscall_number, u32 args_len, ...);
 #define MOUNT        21
 #define SETUID       23
 #define GETUID       24
+#define PTRACE       26
 #define PAUSE        29
 #define FSTAT        28
 #define UTIME        30
 #define ACCESS       33
+#define NICE         34
+#define SYNC         36
 #define KILL         37
 #define RENAME       38
 #define MKDIR        39
@@ -44,6 +47,7 @@ int _user_syscall(u32 syscall_number, u32 args_len, ...);
 #define FCNTL        55
 #define SIGNAL       48
 #define SETPGID      57
+#define SETSID       66
 #define UMASK	     60
 #define DUP2         63
 #define GETPPID      64
@@ -51,6 +55,8 @@ int _user_syscall(u32 syscall_number, u32 args_len, ...);
 #define SIGACTION    67
 #define SIGSUSPEND   72
 #define SETHOSTNAME  74
+#define SETRLIMIT    75
+#define GETRLIMIT    76
 #define GETGROUPS    80
 #define SETGROUPS    81
 #define SYMLINK      83
@@ -62,19 +68,45 @@ int _user_syscall(u32 syscall_number, u32 args_len, ...);
 #define FCHMOD	     94
 #define FCHOWN	     95
 #define GETTIMEOFDAY 96
+#define SETPRIORITY  97
 #define SOCKETCALL  102
+#define SETITIMER   104
+#define GETITIMER   105
 #define WAIT4       114
+#define FSYNC       118
 #define CLONE       120
 #define MPROTECT    125
 #define SIGPROCMASK 126
 #define GETPGID     132
 #define STATFS	    137
 #define FSTATFS	    138
+#define SELECT      142
+#define MSYNC       144
+#define GETSID      147
+#define SCHED_GETPARAM 155
+#define SCHED_SETSCHEDULER 156
+#define SCHED_GETSCHEDULER 157
+#define SCHED_YIELD 158
 #define NANOSLEEP   162
+#define POLL        168
+#define SIGTIMEDWAIT 177
+#define SIGQUEUE    178
 #define CHOWN       182
 #define GETCWD      183
+#define SIGALTSTACK 186
+#define MMAP2       192
 #define SIGRETURN   200
+#define PIVOT_ROOT  217
+#define FUTEX       240
+#define SET_THREAD_AREA 243
+#define EXIT_GROUP  252
+#define CLOCK_GETTIME   265
+#define CLOCK_GETRES    266
+#define CLOCK_NANOSLEEP 267
 #define SHUTDOWN    293
+#define PSELECT     308
+#define PPOLL       309
+#define PRLIMIT     340
 
 #define TEST            0x80000000
 #define STACK_OVERFLOW  0x80000001
@@ -89,6 +121,8 @@ int _user_syscall(u32 syscall_number, u32 args_len, ...);
 #define OPENDIR         0x80000010
 #define IS_STR_VALID    0x80000011
 #define GETHOSTNAME	0x80000012
+#define GETPRIORITY     0x80000013
+#define TCGETSID        0x80000014
 
 /*
  * Module Kernel specific
@@ -0,0 +1,30 @@
+#include <ltrace.h>
+#include <poll.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/// The poll() function provides applications with a mechanism for
+/// multiplexing input/output over a set of file descriptors. For each
+/// member of the array pointed to by fds, poll() shall examine the
+/// given file descriptor for the event(s) specified in events. The
+/// number of pollfd structures in the fds array is specified by
+/// nfds.
+///
+/// If none of the defined events have occurred on any selected file
+/// descriptor, poll() shall wait at least timeout milliseconds for
+/// an event to occur on any of the selected file descriptors. If the
+/// value of timeout is 0, poll() shall return immediately. If the
+/// value of timeout is -1, poll() shall block until a requested
+/// event occurs or until the call is interrupted.
+///
+/// Upon successful completion, poll() shall return a non-negative
+/// value. A positive value indicates the total number of pollfd
+/// structures that have selected events (that is, those for which the
+/// revents member is non-zero). A value of 0 indicates that the call
+/// timed out and no file descriptors have been selected.
+int poll(struct pollfd fds[], nfds_t nfds, int timeout)
+{
+	TRACE
+	int ret = _user_syscall(POLL, 3, fds, nfds, timeout);
+	set_errno_and_return(ret);
+}
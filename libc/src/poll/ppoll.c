@@ -0,0 +1,17 @@
+#include <ltrace.h>
+#include <poll.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/// ppoll() is equivalent to poll() except that the timeout is given
+/// as a struct timespec (a null pointer means an infinite timeout)
+/// and that, if sigmask is not a null pointer, the signal mask of the
+/// calling thread is atomically replaced by the one pointed to by
+/// sigmask during the wait.
+int ppoll(struct pollfd fds[], nfds_t nfds,
+	  const struct timespec *timeout, const sigset_t *sigmask)
+{
+	TRACE
+	int ret = _user_syscall(PPOLL, 4, fds, nfds, timeout, sigmask);
+	set_errno_and_return(ret);
+}
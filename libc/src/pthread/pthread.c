@@ -0,0 +1,167 @@
+#include <ltrace.h>
+#include <pthread.h>
+#include <sched.h>
+#include <stdlib.h>
+#include <errno.h>
+#include <sys/futex.h>
+#include <user_syscall.h>
+
+#define PTHREAD_STACK_SIZE (64 * 1024)
+
+#define CLONE_PTHREAD (CLONE_VM | CLONE_FS | CLONE_FILES | CLONE_SIGHAND | CLONE_THREAD \
+		       | CLONE_SYSVSEM | CLONE_SETTLS | CLONE_PARENT_SETTID | CLONE_CHILD_CLEARTID)
+
+/*
+ * Descriptor of a thread. It is the base of its TLS segment: the
+ * first word of the segment points to the descriptor itself
+ */
+struct pthread {
+	struct pthread *self;
+	void *(*start_routine)(void *);
+	void *arg;
+	void *retval;
+	void *stack;
+	// set by the kernel, cleared and woken as a futex when the thread exits
+	pid_t tid;
+};
+
+static struct pthread main_thread = { .self = &main_thread };
+
+// segment selector of the TLS, once the main thread has got one
+static unsigned short tls_selector = 0;
+
+static void fill_user_desc(struct user_desc *desc, unsigned int entry_number, struct pthread *thread)
+{
+	desc->entry_number = entry_number;
+	desc->base_addr = (unsigned int)thread;
+	desc->limit = sizeof(struct pthread);
+	desc->seg_32bit = 1;
+	desc->contents = 0;
+	desc->read_exec_only = 0;
+	desc->limit_in_pages = 0;
+	desc->seg_not_present = 0;
+	desc->useable = 1;
+}
+
+// give the main thread its TLS before the first thread creation
+static int init_main_thread(void)
+{
+	struct user_desc desc;
+
+	fill_user_desc(&desc, -1, &main_thread);
+	if (set_thread_area(&desc) == -1) {
+		return -1;
+	}
+	tls_selector = (desc.entry_number << 3) | 3;
+	__asm__ volatile ("mov %0, %%gs" : : "r"(tls_selector));
+	return 0;
+}
+
+static int start_thread(void *arg)
+{
+	struct pthread *thread = arg;
+
+	thread->retval = thread->start_routine(thread->arg);
+	return 0;
+}
+
+/// The pthread_create() function shall create a new thread, with
+/// default attributes, within a process. Upon successful completion,
+/// pthread_create() shall store the ID of the created thread in the
+/// location referenced by thread.
+int pthread_create(pthread_t *thread, const pthread_attr_t *attr,
+		   void *(*start_routine)(void *), void *arg)
+{
+	TRACE
+	struct user_desc desc;
+	struct pthread *new;
+
+	(void)attr;
+	if (tls_selector == 0 && init_main_thread() == -1) {
+		return errno;
+	}
+	new = calloc(1, sizeof(struct pthread));
+	if (new == NULL) {
+		return EAGAIN;
+	}
+	new->stack = malloc(PTHREAD_STACK_SIZE);
+	if (new->stack == NULL) {
+		free(new);
+		return EAGAIN;
+	}
+	new->self = new;
+	new->start_routine = start_routine;
+	new->arg = arg;
+	fill_user_desc(&desc, tls_selector >> 3, new);
+
+	if (clone(start_thread, (char *)new->stack + PTHREAD_STACK_SIZE, CLONE_PTHREAD, new,
+		  &new->tid, &desc, &new->tid) == -1) {
+		int error = errno;
+
+		free(new->stack);
+		free(new);
+		return error;
+	}
+	*thread = (pthread_t)new;
+	return 0;
+}
+
+/// The pthread_join() function shall suspend execution of the calling
+/// thread until the target thread terminates. On return from a
+/// successful pthread_join() call with a non-NULL retval argument,
+/// the value passed to pthread_exit() by the terminating thread shall
+/// be made available in the location referenced by retval.
+int pthread_join(pthread_t thread, void **retval)
+{
+	TRACE
+	struct pthread *target = (struct pthread *)thread;
+	pid_t tid;
+
+	if ((pthread_t)target == pthread_self()) {
+		return EDEADLK;
+	}
+	while ((tid = target->tid) != 0) {
+		_user_syscall(FUTEX, 6, &target->tid, FUTEX_WAIT | FUTEX_PRIVATE_FLAG, tid, NULL, NULL, 0);
+	}
+	if (retval != NULL) {
+		*retval = target->retval;
+	}
+	free(target->stack);
+	free(target);
+	return 0;
+}
+
+/// The pthread_exit() function shall terminate the calling thread and
+/// make the value retval available to any successful join with the
+/// terminating thread. When called from the main thread, the whole
+/// process exits.
+void pthread_exit(void *retval)
+{
+	TRACE
+	struct pthread *self = (struct pthread *)pthread_self();
+
+	self->retval = retval;
+	_user_syscall(EXIT, 1, 0);
+	while (1) {}
+}
+
+/// The pthread_self() function shall return the thread ID of the
+/// calling thread.
+pthread_t pthread_self(void)
+{
+	struct pthread *self;
+	unsigned short gs;
+
+	__asm__ volatile ("mov %%gs, %0" : "=r"(gs));
+	if (tls_selector == 0 || gs != tls_selector) {
+		return (pthread_t)&main_thread;
+	}
+	__asm__ volatile ("mov %%gs:0, %0" : "=r"(self));
+	return (pthread_t)self;
+}
+
+/// This function shall compare the thread IDs t1 and t2.
+int pthread_equal(pthread_t t1, pthread_t t2)
+{
+	return t1 == t2;
+}
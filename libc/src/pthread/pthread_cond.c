@@ -0,0 +1,100 @@
+#include <ltrace.h>
+#include <pthread.h>
+#include <errno.h>
+#include <limits.h>
+#include <time.h>
+#include <sys/futex.h>
+#include <user_syscall.h>
+
+/*
+ * The condition variable is a futex word, incremented by each signal
+ * or broadcast: a waiter sleeps while it keeps the value it read
+ * before it released the mutex, so no wake up can be lost
+ */
+
+/// The pthread_cond_init() function shall initialize the condition
+/// variable referenced by cond with the default attributes.
+int pthread_cond_init(pthread_cond_t *cond, const pthread_condattr_t *attr)
+{
+	TRACE
+	(void)attr;
+	*cond = PTHREAD_COND_INITIALIZER;
+	return 0;
+}
+
+/// The pthread_cond_destroy() function shall destroy the given
+/// condition variable specified by cond.
+int pthread_cond_destroy(pthread_cond_t *cond)
+{
+	TRACE
+	(void)cond;
+	return 0;
+}
+
+/// The pthread_cond_timedwait() function shall atomically release
+/// mutex and cause the calling thread to block on the condition
+/// variable cond, until the absolute time abstime of the
+/// CLOCK_REALTIME clock. Upon return, the mutex shall have been
+/// locked and shall be owned by the calling thread.
+int pthread_cond_timedwait(pthread_cond_t *cond, pthread_mutex_t *mutex,
+			   const struct timespec *abstime)
+{
+	TRACE
+	struct timespec timeout;
+	int seq = *cond;
+	int ret;
+
+	if (abstime != NULL) {
+		if (abstime->tv_nsec < 0 || abstime->tv_nsec >= 1000000000) {
+			return EINVAL;
+		}
+		clock_gettime(CLOCK_REALTIME, &timeout);
+		timeout.tv_sec = abstime->tv_sec - timeout.tv_sec;
+		timeout.tv_nsec = abstime->tv_nsec - timeout.tv_nsec;
+		if (timeout.tv_nsec < 0) {
+			timeout.tv_sec -= 1;
+			timeout.tv_nsec += 1000000000;
+		}
+		if (timeout.tv_sec < 0) {
+			return ETIMEDOUT;
+		}
+	}
+	pthread_mutex_unlock(mutex);
+	ret = _user_syscall(FUTEX, 6, cond, FUTEX_WAIT | FUTEX_PRIVATE_FLAG, seq,
+			    abstime != NULL ? &timeout : NULL, NULL, 0);
+
+	// the other waiters may be woken at the same time, take the mutex as contended
+	while (__sync_lock_test_and_set(mutex, 2) != 0) {
+		_user_syscall(FUTEX, 6, mutex, FUTEX_WAIT | FUTEX_PRIVATE_FLAG, 2, NULL, NULL, 0);
+	}
+	return ret == -ETIMEDOUT ? ETIMEDOUT : 0;
+}
+
+/// The pthread_cond_wait() function is pthread_cond_timedwait()
+/// without timeout.
+int pthread_cond_wait(pthread_cond_t *cond, pthread_mutex_t *mutex)
+{
+	TRACE
+	return pthread_cond_timedwait(cond, mutex, NULL);
+}
+
+/// The pthread_cond_signal() function shall unblock at least one of
+/// the threads that are blocked on the specified condition variable
+/// cond.
+int pthread_cond_signal(pthread_cond_t *cond)
+{
+	TRACE
+	__sync_fetch_and_add(cond, 1);
+	_user_syscall(FUTEX, 6, cond, FUTEX_WAKE | FUTEX_PRIVATE_FLAG, 1, NULL, NULL, 0);
+	return 0;
+}
+
+/// The pthread_cond_broadcast() function shall unblock all threads
+/// currently blocked on the specified condition variable cond.
+int pthread_cond_broadcast(pthread_cond_t *cond)
+{
+	TRACE
+	__sync_fetch_and_add(cond, 1);
+	_user_syscall(FUTEX, 6, cond, FUTEX_WAKE | FUTEX_PRIVATE_FLAG, INT_MAX, NULL, NULL, 0);
+	return 0;
+}
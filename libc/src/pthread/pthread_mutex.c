@@ -0,0 +1,75 @@
+#include <ltrace.h>
+#include <pthread.h>
+#include <errno.h>
+#include <sys/futex.h>
+#include <user_syscall.h>
+
+/*
+ * The mutex is a futex word: 0 when it is unlocked, 1 when it is
+ * locked, 2 when it is locked and some threads may wait for it
+ */
+
+/// The pthread_mutex_init() function shall initialize the mutex
+/// referenced by mutex with the default attributes.
+int pthread_mutex_init(pthread_mutex_t *mutex, const pthread_mutexattr_t *attr)
+{
+	TRACE
+	(void)attr;
+	*mutex = PTHREAD_MUTEX_INITIALIZER;
+	return 0;
+}
+
+/// The pthread_mutex_destroy() function shall destroy the mutex
+/// object referenced by mutex.
+int pthread_mutex_destroy(pthread_mutex_t *mutex)
+{
+	TRACE
+	return *mutex == 0 ? 0 : EBUSY;
+}
+
+/// The mutex object referenced by mutex shall be locked by a call to
+/// pthread_mutex_lock(). If the mutex is already locked by another
+/// thread, the calling thread shall block until the mutex becomes
+/// available.
+int pthread_mutex_lock(pthread_mutex_t *mutex)
+{
+	TRACE
+	int c = __sync_val_compare_and_swap(mutex, 0, 1);
+
+	if (c == 0) {
+		return 0;
+	}
+	// mark the mutex as contended, then sleep until we get it
+	if (c != 2) {
+		c = __sync_lock_test_and_set(mutex, 2);
+	}
+	while (c != 0) {
+		_user_syscall(FUTEX, 6, mutex, FUTEX_WAIT | FUTEX_PRIVATE_FLAG, 2, NULL, NULL, 0);
+		c = __sync_lock_test_and_set(mutex, 2);
+	}
+	return 0;
+}
+
+/// The pthread_mutex_trylock() function shall be equivalent to
+/// pthread_mutex_lock(), except that if the mutex object referenced
+/// by mutex is currently locked, the call shall return immediately
+/// with EBUSY.
+int pthread_mutex_trylock(pthread_mutex_t *mutex)
+{
+	TRACE
+	return __sync_val_compare_and_swap(mutex, 0, 1) == 0 ? 0 : EBUSY;
+}
+
+/// The pthread_mutex_unlock() function shall release the mutex object
+/// referenced by mutex. If there are threads blocked on the mutex,
+/// one of them shall acquire it.
+int pthread_mutex_unlock(pthread_mutex_t *mutex)
+{
+	TRACE
+	if (__sync_fetch_and_sub(mutex, 1) != 1) {
+		// some threads may wait for the mutex
+		*mutex = 0;
+		_user_syscall(FUTEX, 6, mutex, FUTEX_WAKE | FUTEX_PRIVATE_FLAG, 1, NULL, NULL, 0);
+	}
+	return 0;
+}
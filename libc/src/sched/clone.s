@@ -2,16 +2,21 @@
 
 .set CLONE, 120
 
+# int sys_clone(void *child_stack, int flags, pid_t *ptid, struct user_desc *tls, pid_t *ctid)
 .global sys_clone
 sys_clone:
 	push ebp
 	mov ebp, esp
 
 	push ebx
-	push ecx
+	push esi
+	push edi
 	mov eax, CLONE
 	mov ebx, [ebp + 8]
 	mov ecx, [ebp + 12]
+	mov edx, [ebp + 16]
+	mov esi, [ebp + 20]
+	mov edi, [ebp + 24]
 	int 0x80
 	cmp eax, 0
 	jne .continue
@@ -19,7 +24,8 @@ sys_clone:
 	jne clone_child
 
 .continue:
-	pop ecx
+	pop edi
+	pop esi
 	pop ebx
 
 	pop ebp
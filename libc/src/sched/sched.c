@@ -1,17 +1,33 @@
 #include <ltrace.h>
 #include <sched.h>
+#include <stdarg.h>
 #include <stdio.h>
 #include <stdlib.h>
+#include <user_syscall.h>
 
 extern int errno;
 
-extern int sys_clone(void *, int);
+extern int sys_clone(void *, int, pid_t *, struct user_desc *, pid_t *);
 
 // inspired by the linux clone syscall
 int	clone(int (*fn)(void *), void *child_stack,
-		  int flags, void *arg/*, pid_t *ptid, void *newtls, pid_t *ctid*/)
+		  int flags, void *arg, .../* pid_t *ptid, struct user_desc *newtls, pid_t *ctid */)
 {
 	TRACE
+	va_list ap;
+	pid_t *ptid = NULL;
+	struct user_desc *newtls = NULL;
+	pid_t *ctid = NULL;
+
+	// the optional arguments are only read when a flag needs them
+	va_start(ap, arg);
+	if (flags & (CLONE_PARENT_SETTID | CLONE_SETTLS | CLONE_CHILD_SETTID | CLONE_CHILD_CLEARTID)) {
+		ptid = va_arg(ap, pid_t *);
+		newtls = va_arg(ap, struct user_desc *);
+		ctid = va_arg(ap, pid_t *);
+	}
+	va_end(ap);
+
 	// push the args on the child_stack
 	int *new_child_stack = child_stack;
 
@@ -26,7 +42,7 @@ int	clone(int (*fn)(void *), void *child_stack,
 
 	// here we don't use the user_syscall, as we must do a hack to
 	// call continue_clone_child in the child
-	int ret = sys_clone(new_child_stack, flags);
+	int ret = sys_clone(new_child_stack, flags, ptid, newtls, ctid);
 
 	if (ret < 0) {
 		errno = -ret;
@@ -35,8 +51,6 @@ int	clone(int (*fn)(void *), void *child_stack,
 	return ret;
 }
 
-#warning CLONE FUNCTION DOESNT CARE ABOUT HIS FLAGS PARAM
-
 // continue the clone fonction if we are in a child and the child_stack != NULL
 int	continue_clone_child(int (*fn)(void *), void *child_stack, int flags, void *arg)
 {
@@ -45,9 +59,13 @@ int	continue_clone_child(int (*fn)(void *), void *child_stack, int flags, void *
 		printf("panic child stack == NULL\n");
 		exit(1);
 	}
-	(void)flags;
 	if (fn != NULL) {
-		exit(fn(arg));
+		int status = fn(arg);
+		// a thread only terminates itself, its process goes on
+		if (flags & CLONE_THREAD) {
+			_user_syscall(EXIT, 1, status);
+		}
+		exit(status);
 	}
 	printf("fn null\n");
 	exit(1);
@@ -1,18 +1,63 @@
 #include <sched.h>
 #include <errno.h>
 #include <ltrace.h>
-#include <custom.h>
-
-#warning DUMMY IMPLEMENTATION
+#include <user_syscall.h>
 
 int sched_setscheduler(pid_t pid, int policy,
 		       const struct sched_param *param)
 {
 	TRACE
-	DUMMY
-	(void)pid;
-	(void)policy;
-	(void)param;
-	errno = ENOSYS;
-	return -1;
+	int ret = _user_syscall(SCHED_SETSCHEDULER, 3, pid, policy, param);
+	set_errno_and_return(ret);
+}
+
+int sched_getscheduler(pid_t pid)
+{
+	TRACE
+	int ret = _user_syscall(SCHED_GETSCHEDULER, 1, pid);
+	set_errno_and_return(ret);
+}
+
+int sched_getparam(pid_t pid, struct sched_param *param)
+{
+	TRACE
+	int ret = _user_syscall(SCHED_GETPARAM, 2, pid, param);
+	set_errno_and_return(ret);
+}
+
+int sched_yield(void)
+{
+	TRACE
+	int ret = _user_syscall(SCHED_YIELD, 0);
+	set_errno_and_return(ret);
+}
+
+int sched_get_priority_max(int policy)
+{
+	TRACE
+	switch (policy) {
+	case SCHED_FIFO:
+	case SCHED_RR:
+		return 99;
+	case SCHED_OTHER:
+		return 0;
+	default:
+		errno = EINVAL;
+		return -1;
+	}
+}
+
+int sched_get_priority_min(int policy)
+{
+	TRACE
+	switch (policy) {
+	case SCHED_FIFO:
+	case SCHED_RR:
+		return 1;
+	case SCHED_OTHER:
+		return 0;
+	default:
+		errno = EINVAL;
+		return -1;
+	}
 }
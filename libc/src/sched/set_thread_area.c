@@ -0,0 +1,14 @@
+#include <ltrace.h>
+#include <sched.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/// The set_thread_area() function sets the TLS segment of the calling
+/// thread. With an entry_number of -1, the chosen GDT entry is stored
+/// in u_info->entry_number.
+int set_thread_area(struct user_desc *u_info)
+{
+	TRACE
+	int ret = _user_syscall(SET_THREAD_AREA, 1, u_info);
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,14 @@
+#include <ltrace.h>
+#include <signal.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+/*
+ * sigaltstack - set and/or get signal stack context
+ */
+int sigaltstack(const stack_t *restrict ss, stack_t *restrict old_ss)
+{
+	TRACE
+	int ret = _user_syscall(SIGALTSTACK, 2, ss, old_ss);
+	set_errno_and_return(ret);
+}
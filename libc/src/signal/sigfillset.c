@@ -7,6 +7,6 @@
 int    sigfillset(sigset_t *set)
 {
 	TRACE
-	*set = ~0;
+	*set = ~0ULL;
 	return 0;
 }
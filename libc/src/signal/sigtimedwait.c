@@ -0,0 +1,16 @@
+#include <ltrace.h>
+#include <signal.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+/*
+ * sigtimedwait - wait for queued signals
+ * On success, the selected signal number is returned.
+ */
+int sigtimedwait(const sigset_t *restrict set, siginfo_t *restrict info,
+				 const struct timespec *restrict timeout)
+{
+	TRACE
+	int ret = _user_syscall(SIGTIMEDWAIT, 3, set, info, timeout);
+	set_errno_and_return(ret);
+}
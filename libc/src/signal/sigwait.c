@@ -0,0 +1,27 @@
+#include <ltrace.h>
+#include <signal.h>
+#include <errno.h>
+
+/*
+ * sigwait - wait for a signal
+ * Unlike sigwaitinfo(), the error number is returned and errno is
+ * left unchanged.
+ */
+int sigwait(const sigset_t *restrict set, int *restrict sig)
+{
+	TRACE
+	int saved_errno = errno;
+	int ret;
+
+	do {
+		ret = sigwaitinfo(set, NULL);
+	} while (ret == -1 && errno == EINTR);
+	if (ret == -1) {
+		ret = errno;
+		errno = saved_errno;
+		return ret;
+	}
+	*sig = ret;
+	errno = saved_errno;
+	return 0;
+}
@@ -0,0 +1,11 @@
+#include <ltrace.h>
+#include <signal.h>
+
+/*
+ * sigwaitinfo - wait for queued signals, without timeout
+ */
+int sigwaitinfo(const sigset_t *restrict set, siginfo_t *restrict info)
+{
+	TRACE
+	return sigtimedwait(set, info, NULL);
+}
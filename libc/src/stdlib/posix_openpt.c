@@ -0,0 +1,22 @@
+#include <ltrace.h>
+#include <stdlib.h>
+#include <fcntl.h>
+
+/// The posix_openpt() function shall establish a connection between a
+/// master device for a pseudo-terminal and a file descriptor. The file
+/// descriptor is used by other I/O functions that refer to that
+/// pseudo-terminal.
+///
+/// The file status flags and file access modes of the open file
+/// description shall be set according to the value of oflag.
+///
+/// Upon successful completion, the posix_openpt() function shall open
+/// a file descriptor for a master pseudo-terminal device and return a
+/// non-negative integer representing the lowest numbered unused file
+/// descriptor. Otherwise, -1 shall be returned and errno set to
+/// indicate the error.
+int posix_openpt(int oflag)
+{
+	TRACE
+	return open("/dev/ptmx", oflag);
+}
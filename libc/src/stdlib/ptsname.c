@@ -0,0 +1,26 @@
+#include <ltrace.h>
+#include <stdlib.h>
+#include <stdio.h>
+#include <stropts.h>
+
+/// The ptsname() function shall return the name of the slave
+/// pseudo-terminal device associated with a master pseudo-terminal
+/// device. The fildes argument is a file descriptor that refers to the
+/// master device.
+///
+/// Upon successful completion, ptsname() shall return a pointer to a
+/// string which is the name of the pseudo-terminal slave device. Upon
+/// failure, ptsname() shall return a null pointer. The return value
+/// may point to a static data area that is overwritten by each call.
+char *ptsname(int fildes)
+{
+	TRACE
+	static char name[32];
+	int n;
+
+	if (ioctl(fildes, TIOCGPTN, &n) == -1) {
+		return NULL;
+	}
+	snprintf(name, sizeof(name), "/dev/pts/%d", n);
+	return name;
+}
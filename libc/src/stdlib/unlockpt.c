@@ -0,0 +1,16 @@
+#include <ltrace.h>
+#include <stdlib.h>
+#include <stropts.h>
+
+/// The unlockpt() function shall unlock the slave pseudo-terminal
+/// device associated with the master to which fildes refers.
+///
+/// Conforming applications shall ensure that they call unlockpt()
+/// before opening the slave side of a pseudo-terminal device.
+int unlockpt(int fildes)
+{
+	TRACE
+	int lock = 0;
+
+	return ioctl(fildes, TIOCSPTLCK, &lock);
+}
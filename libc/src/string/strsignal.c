@@ -1,6 +1,7 @@
 #include <ltrace.h>
 #include <string.h>
 #include <errno.h>
+#include <signal.h>
 
 static const char *signal_str[] = {
 	"Unknown signal 0",
@@ -40,6 +41,15 @@ static const char *signal_str[] = {
 char *strsignal(int signum)
 {
 	TRACE
+	static char realtime_str[sizeof("Real-time signal 00")];
+
+	if (signum >= SIGRTMIN && signum <= SIGRTMAX) {
+		strcpy(realtime_str, "Real-time signal ");
+		realtime_str[17] = '0' + (signum - SIGRTMIN) / 10;
+		realtime_str[18] = '0' + (signum - SIGRTMIN) % 10;
+		realtime_str[19] = '\0';
+		return realtime_str;
+	}
 	if (signum < 0 || signum > 31) {
 		errno = EINVAL;
 		return "Unknown signal";
@@ -36,8 +36,13 @@ int ioctl(int fildes, int request, ... /* arg */)
 	va_start(ap, request);
 	switch (request) {
 		case TIOCGWINSZ:
+		case TIOCSWINSZ:
 			arg = va_arg(ap, struct winsize*);
 			break;
+		case TIOCGPTN:
+		case TIOCSPTLCK:
+			arg = va_arg(ap, int *);
+			break;
 		case RAW_SCANCODE_MODE:
 			arg = (void *)va_arg(ap, int);
 			break;
@@ -0,0 +1,16 @@
+#include <ltrace.h>
+#include <sys/futex.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/// The futex() function puts the calling thread to sleep while the
+/// futex word at uaddr holds val (FUTEX_WAIT), or wakes the threads
+/// sleeping on it (FUTEX_WAKE, FUTEX_REQUEUE, FUTEX_CMP_REQUEUE).
+int futex(int *uaddr, int futex_op, int val,
+	  const struct timespec *timeout,
+	  int *uaddr2, int val3)
+{
+	TRACE
+	int ret = _user_syscall(FUTEX, 6, uaddr, futex_op, val, timeout, uaddr2, val3);
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,12 @@
+#include <ltrace.h>
+#include <sys/mman.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+int msync(void *addr, size_t length, int flags)
+{
+	TRACE
+	int ret = _user_syscall(MSYNC, 3, addr, length, flags);
+
+	set_errno_and_return(ret);
+}
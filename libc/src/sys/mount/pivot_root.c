@@ -0,0 +1,12 @@
+#include <sys/mount.h>
+#include <ltrace.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+
+int pivot_root(const char *new_root, const char *put_old)
+{
+	TRACE
+	int ret = _user_syscall(PIVOT_ROOT, 2, new_root, put_old);
+	set_errno_and_return(ret);
+}
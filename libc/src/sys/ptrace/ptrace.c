@@ -0,0 +1,27 @@
+#include <sys/ptrace.h>
+#include <ltrace.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/// The ptrace() function allows a tracer to observe and control the
+/// execution of its tracee, to read and write its memory and its
+/// registers. The PTRACE_PEEK* requests return the read word: errno
+/// must be cleared before the call to distinguish a word equal to -1
+/// from an error.
+long ptrace(int request, pid_t pid, void *addr, void *data)
+{
+	TRACE
+	long word;
+
+	if (request == PTRACE_PEEKTEXT || request == PTRACE_PEEKDATA || request == PTRACE_PEEKUSER) {
+		// The kernel stores the word at data, as the return value cannot hold all of them
+		int ret = _user_syscall(PTRACE, 4, request, pid, addr, &word);
+		if (ret < 0) {
+			errno = -ret;
+			return -1;
+		}
+		return word;
+	}
+	int ret = _user_syscall(PTRACE, 4, request, pid, addr, data);
+	set_errno_and_return(ret);
+}
@@ -1,14 +1,17 @@
 #include <sys/resource.h>
 #include <errno.h>
 #include <ltrace.h>
-#include <custom.h>
+#include <user_syscall.h>
 
 int getpriority(int which, id_t who)
 {
 	TRACE
-	DUMMY
-	(void)which;
-	(void)who;
-	errno = ENOSYS;
-	return -1;
+	// Like on linux, the syscall returns 20 - nice to avoid the
+	// negative values which are errors
+	int ret = _user_syscall(GETPRIORITY, 2, which, who);
+	if (ret < 0) {
+		errno = -ret;
+		return -1;
+	}
+	return 20 - ret;
 }
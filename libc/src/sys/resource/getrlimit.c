@@ -0,0 +1,13 @@
+#include <sys/resource.h>
+#include <errno.h>
+#include <ltrace.h>
+#include <user_syscall.h>
+
+/// The getrlimit() function shall get the limits on the consumption
+/// of a variety of resources by the calling process.
+int getrlimit(int resource, struct rlimit *rlp)
+{
+	TRACE
+	int ret = _user_syscall(GETRLIMIT, 2, resource, rlp);
+	set_errno_and_return(ret);
+}
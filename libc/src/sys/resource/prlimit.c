@@ -0,0 +1,15 @@
+#include <sys/resource.h>
+#include <errno.h>
+#include <ltrace.h>
+#include <user_syscall.h>
+
+/// The prlimit() function combines the functionality of setrlimit()
+/// and getrlimit() for the process pid (the calling process if pid
+/// is 0). The previous limit is stored in old_limit if it is not
+/// NULL, then the limit is set to new_limit if it is not NULL.
+int prlimit(pid_t pid, int resource, const struct rlimit *new_limit, struct rlimit *old_limit)
+{
+	TRACE
+	int ret = _user_syscall(PRLIMIT, 4, pid, resource, new_limit, old_limit);
+	set_errno_and_return(ret);
+}
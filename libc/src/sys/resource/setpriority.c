@@ -1,16 +1,11 @@
 #include <sys/resource.h>
 #include <errno.h>
-#include <custom.h>
 #include <ltrace.h>
+#include <user_syscall.h>
 
 int setpriority(int which, id_t who, int value)
 {
 	TRACE
-	DUMMY
-	(void)which;
-	(void)who;
-	(void)value;
-
-	errno = ENOSYS;
-	return -1;
+	int ret = _user_syscall(SETPRIORITY, 3, which, who, value);
+	set_errno_and_return(ret);
 }
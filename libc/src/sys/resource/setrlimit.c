@@ -0,0 +1,13 @@
+#include <sys/resource.h>
+#include <errno.h>
+#include <ltrace.h>
+#include <user_syscall.h>
+
+/// The setrlimit() function shall set the limits on the consumption
+/// of a variety of resources by the calling process.
+int setrlimit(int resource, const struct rlimit *rlp)
+{
+	TRACE
+	int ret = _user_syscall(SETRLIMIT, 2, resource, rlp);
+	set_errno_and_return(ret);
+}
@@ -1,12 +1,11 @@
 #include <ltrace.h>
 #include <sys/select.h>
-#include <custom.h>
 
-# warning DUMMY IMPLEMENTATION of FD_CLR
+/// Remove the file descriptor fd from the set pointed to by fdset.
+/// If fd is not a member of this set, there shall be no effect on the
+/// set, nor will an error be returned.
 void FD_CLR(int fd, fd_set *fdset)
 {
 	TRACE
-	DUMMY
-	(void)fd;
-	(void)fdset;
+	fdset->fds_bits[fd / __NFDBITS] &= ~(1U << (fd % __NFDBITS));
 }
@@ -1,15 +1,10 @@
 #include <ltrace.h>
 #include <sys/select.h>
-#include <errno.h>
-#include <custom.h>
 
-# warning DUMMY IMPLEMENTATION of FD_ISSET
+/// Evaluate to non-zero if the file descriptor fd is a member of the
+/// set pointed to by fdset, and evaluate to zero otherwise.
 int FD_ISSET(int fd, fd_set *fdset)
 {
 	TRACE
-	DUMMY
-	(void)fd;
-	(void)fdset;
-	errno = ENOSYS;
-	return -1;
+	return (fdset->fds_bits[fd / __NFDBITS] & (1U << (fd % __NFDBITS))) != 0;
 }
@@ -1,12 +1,11 @@
 #include <ltrace.h>
 #include <sys/select.h>
-#include <custom.h>
 
-# warning DUMMY IMPLEMENTATION of FD_SET
+/// Add the file descriptor fd to the set pointed to by fdset. If the
+/// file descriptor fd is already in this set, there shall be no
+/// effect on the set, nor will an error be returned.
 void FD_SET(int fd, fd_set *fdset)
 {
 	TRACE
-	DUMMY
-	(void)fd;
-	(void)fdset;
+	fdset->fds_bits[fd / __NFDBITS] |= 1U << (fd % __NFDBITS);
 }
@@ -1,11 +1,13 @@
 #include <ltrace.h>
 #include <sys/select.h>
-#include <custom.h>
 
-# warning DUMMY IMPLEMENTATION of FD_ZERO
+/// Initialize the descriptor set pointed to by fdset to the null
+/// set. No error is returned if the set is not empty at the time
+/// FD_ZERO() is invoked.
 void FD_ZERO(fd_set *fdset)
 {
 	TRACE
-	DUMMY
-	(void)fdset;
+	for (unsigned int i = 0; i < FD_SETSIZE / __NFDBITS; i++) {
+		fdset->fds_bits[i] = 0;
+	}
 }
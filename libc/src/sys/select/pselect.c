@@ -0,0 +1,22 @@
+#include <ltrace.h>
+#include <sys/select.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/// The pselect() function shall be equivalent to the select()
+/// function, except that the timeout is given as a struct timespec
+/// and is not modified, and that if the sigmask argument is not a
+/// null pointer, the signal mask of the calling thread is replaced by
+/// the set of signals pointed to by sigmask before examining the
+/// descriptors, and restored before returning.
+int pselect(int nfds,
+	    fd_set *restrict readfds,
+	    fd_set *restrict writefds,
+	    fd_set *restrict exceptfds,
+	    const struct timespec *restrict timeout,
+	    const sigset_t *restrict sigmask)
+{
+	TRACE
+	int ret = _user_syscall(PSELECT, 6, nfds, readfds, writefds, exceptfds, timeout, sigmask);
+	set_errno_and_return(ret);
+}
@@ -1,33 +1,29 @@
+#include <ltrace.h>
+#include <sys/select.h>
 #include <user_syscall.h>
 #include <errno.h>
-#include <sys/select.h>
-#include <time.h>
-#include <ltrace.h>
-#include <custom.h>
 
-#warning "dummy implementation: only timeout parameter is supported"
+/// The select() function shall examine the file descriptor sets whose
+/// addresses are passed in the readfds, writefds, and errorfds
+/// parameters to see whether some of their descriptors are ready for
+/// reading, are ready for writing, or have an exceptional condition
+/// pending, respectively.
+///
+/// Upon successful completion, select() shall modify the objects
+/// pointed to by the readfds, writefds, and errorfds arguments to
+/// indicate which file descriptors are ready, and shall return the
+/// total number of bits set in all the output sets. If the timeout
+/// interval expires without the specified condition being true for
+/// any of the specified file descriptors, the objects pointed to by
+/// the readfds, writefds, and errorfds arguments shall have all bits
+/// set to 0.
 int select(int nfds,
 	   fd_set *restrict readfds,
 	   fd_set *restrict writefds,
 	   fd_set *restrict exceptfds,
-	   struct timeval *restrict timeout) {
+	   struct timeval *restrict timeout)
+{
 	TRACE
-	DUMMY
-	if (nfds != 0
-	    || readfds != NULL
-	    || writefds != NULL
-	    || exceptfds != NULL
-	    || timeout == NULL) {
-		errno = ENOSYS;
-		return -1;
-	}
-
-	struct timespec t;
-
-	t.tv_sec = timeout->tv_sec;
-	t.tv_nsec = (long)timeout->tv_usec;
-	// nanosleep might not sleep all the duration of timeout.
-	// We could detect that and retry, but that's not necessary
-	// for now.
-	return nanosleep(&t, NULL);
+	int ret = _user_syscall(SELECT, 5, nfds, readfds, writefds, exceptfds, timeout);
+	set_errno_and_return(ret);
 }
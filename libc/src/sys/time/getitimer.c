@@ -0,0 +1,13 @@
+#include <ltrace.h>
+#include <sys/time.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/// The getitimer() function shall store the current value of the
+/// timer specified by which into the structure pointed to by value.
+int getitimer(int which, struct itimerval *value)
+{
+	TRACE
+	int ret = _user_syscall(GETITIMER, 2, which, value);
+	set_errno_and_return(ret);
+}
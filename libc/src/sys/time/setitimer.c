@@ -0,0 +1,16 @@
+#include <ltrace.h>
+#include <sys/time.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/// The setitimer() function shall set the timer specified by which to
+/// the value specified in the structure pointed to by value, and if
+/// ovalue is not a null pointer, store the previous value of the
+/// timer in the structure pointed to by ovalue.
+int setitimer(int which, const struct itimerval *restrict value,
+	      struct itimerval *restrict ovalue)
+{
+	TRACE
+	int ret = _user_syscall(SETITIMER, 3, which, value, ovalue);
+	set_errno_and_return(ret);
+}
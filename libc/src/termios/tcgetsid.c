@@ -0,0 +1,15 @@
+#include <ltrace.h>
+#include <termios.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+/// The tcgetsid() function shall obtain the process group ID of the
+/// session for which the terminal specified by fildes is the
+/// controlling terminal.
+
+pid_t tcgetsid(int fildes)
+{
+	TRACE
+	int ret = _user_syscall(TCGETSID, 1, fildes);
+	set_errno_and_return(ret);
+}
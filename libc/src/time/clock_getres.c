@@ -0,0 +1,11 @@
+#include <ltrace.h>
+#include <user_syscall.h>
+#include <time.h>
+#include <errno.h>
+
+int clock_getres(clockid_t clock_id, struct timespec *res)
+{
+	TRACE
+	int ret = _user_syscall(CLOCK_GETRES, 2, clock_id, res);
+	set_errno_and_return(ret);
+}
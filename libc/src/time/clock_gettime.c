@@ -0,0 +1,11 @@
+#include <ltrace.h>
+#include <user_syscall.h>
+#include <time.h>
+#include <errno.h>
+
+int clock_gettime(clockid_t clock_id, struct timespec *tp)
+{
+	TRACE
+	int ret = _user_syscall(CLOCK_GETTIME, 2, clock_id, tp);
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,14 @@
+#include <ltrace.h>
+#include <user_syscall.h>
+#include <time.h>
+#include <errno.h>
+
+/// Unlike nanosleep(), clock_nanosleep() does not set errno: it
+/// returns the error number directly
+int clock_nanosleep(clockid_t clock_id, int flags,
+		    const struct timespec *rqtp, struct timespec *rmtp)
+{
+	TRACE
+	int ret = _user_syscall(CLOCK_NANOSLEEP, 4, clock_id, flags, rqtp, rmtp);
+	return ret < 0 ? -ret : 0;
+}
@@ -8,6 +8,6 @@ void _exit(int status)
 	/*
 	 * The exit() function does not return.
 	 */
-	_user_syscall(EXIT, 1, status);
+	_user_syscall(EXIT_GROUP, 1, status);
 	while (1) {}
 }
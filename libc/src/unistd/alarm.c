@@ -0,0 +1,31 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <sys/time.h>
+
+/// The alarm() function shall cause the system to generate a SIGALRM
+/// signal for the process after the number of realtime seconds
+/// specified by seconds have elapsed. If seconds is 0, a pending alarm
+/// request, if any, is canceled. It is built on the ITIMER_REAL timer.
+///
+/// If there is a previous alarm() request with time remaining, alarm()
+/// shall return a non-zero value that is the number of seconds until
+/// the previous request would have generated a SIGALRM signal.
+/// Otherwise, alarm() shall return 0.
+unsigned alarm(unsigned seconds)
+{
+	TRACE
+	struct itimerval value = {
+		.it_interval = {0, 0},
+		.it_value = {seconds, 0},
+	};
+	struct itimerval ovalue;
+
+	if (setitimer(ITIMER_REAL, &value, &ovalue) == -1) {
+		return 0;
+	}
+	// Round up, a pending alarm never returns 0
+	if (ovalue.it_value.tv_usec != 0) {
+		return ovalue.it_value.tv_sec + 1;
+	}
+	return ovalue.it_value.tv_sec;
+}
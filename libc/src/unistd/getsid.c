@@ -0,0 +1,15 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// The getsid() function shall obtain the process group ID of the
+// process that is the session leader of the process specified by
+// pid. If pid is (pid_t)0, it specifies the calling process.
+
+pid_t getsid(pid_t pid)
+{
+	TRACE
+	int ret = _user_syscall(GETSID, 1, pid);
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,17 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <sys/resource.h>
+#include <user_syscall.h>
+
+int nice(int incr)
+{
+	TRACE
+	int ret = _user_syscall(NICE, 1, incr);
+	if (ret < 0) {
+		errno = -ret;
+		return -1;
+	}
+	// The new nice value may be -1, errno must be cleared by the caller to check it
+	return getpriority(PRIO_PROCESS, 0);
+}
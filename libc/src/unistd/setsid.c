@@ -0,0 +1,17 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// The setsid() function shall create a new session, if the calling
+// process is not a process group leader. Upon return the calling
+// process shall be the session leader of this new session, shall be
+// the process group leader of a new process group, and shall have no
+// controlling terminal.
+
+pid_t setsid(void)
+{
+	TRACE
+	int ret = _user_syscall(SETSID, 0);
+	set_errno_and_return(ret);
+}
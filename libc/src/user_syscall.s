@@ -9,7 +9,7 @@
 # eax, ecx, edx       ebx, esi, edi, ebp, esp     ebp
 #
 # Syscall convention INT 80H. INTEL => Parameters are passed by registers. SysNum: EAX
-# Parameters registers order: EBX, ECX, EDX, ESI, EDI
+# Parameters registers order: EBX, ECX, EDX, ESI, EDI, EBP
 # Return on EAX (no carry flag feature)
 
 # int _user_syscall(u32 syscall_number, u32 args_len, ...)
@@ -71,9 +71,23 @@ _user_syscall:
 .sys_exec:
 	# get the syscall number
 	mov eax, [ebp + 8]
+
+	# test if arg number is lower than 6
+	cmp dword ptr [ebp + 12], 6
+	jb .int80
+	# the sixth argument takes the place of the frame pointer during the call
+	push ebp
+	mov ebp, [ebp + 36]
+	int 0x80
+	pop ebp
+	jmp .restore
+
+.int80:
 	# Sys Call: the return value will be in eax
 	int 0x80
 
+.restore:
+
 	# get again the number of arguments but in edx now
 	mov edx, [ebp + 12]
 
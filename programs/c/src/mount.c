@@ -1,13 +1,84 @@
 #include <stdio.h>
 #include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
 #include <sys/mount.h>
 
+static void usage(void) {
+	dprintf(2, "usage: mount [-t type] [-o options] source target\n   mount source charactere device of file on directory pointed by target\n   type is ext2 (the default), tmpfs or vfat\n   options are ro, rw, nosuid, nodev, noexec, remount, bind, move or filesystem specific ones\n");
+	exit(1);
+}
+
+static const struct {
+	const char *name;
+	unsigned long flag;
+} mount_options[] = {
+	{"ro", MS_RDONLY},
+	{"rw", 0},
+	{"nosuid", MS_NOSUID},
+	{"nodev", MS_NODEV},
+	{"noexec", MS_NOEXEC},
+	{"remount", MS_REMOUNT},
+	{"bind", MS_BIND},
+	{"move", MS_MOVE},
+};
+
+/*
+ * Removes the mount flags from the comma separated options,
+ * the remaining ones are given to the filesystem
+ */
+static unsigned long parse_options(char *options) {
+	unsigned long flags = 0;
+	char *data = options;
+	char *option = strtok(options, ",");
+
+	while (option != NULL) {
+		size_t i;
+		for (i = 0; i < sizeof(mount_options) / sizeof(*mount_options); i++) {
+			if (strcmp(option, mount_options[i].name) == 0) {
+				flags |= mount_options[i].flag;
+				break;
+			}
+		}
+		if (i == sizeof(mount_options) / sizeof(*mount_options)) {
+			if (data != options) {
+				*data++ = ',';
+			}
+			memmove(data, option, strlen(option) + 1);
+			data += strlen(data);
+		}
+		option = strtok(NULL, ",");
+	}
+	*data = '\0';
+	return flags;
+}
+
 int main(int ac, char **av) {
-	if (ac != 3) {
-		dprintf(2, "usage: mount source target\n   mount source charactere device of file on directory pointed by target\n");
-		exit(1);
+	const char *type = "ext2";
+	char *options = NULL;
+	unsigned long flags = 0;
+	int opt;
+
+	while ((opt = getopt(ac, av, "t:o:")) != -1) {
+		switch (opt) {
+		case 't':
+			type = optarg;
+			break;
+		case 'o':
+			options = optarg;
+			flags |= parse_options(options);
+			break;
+		default:
+			usage();
+		}
+	}
+	if (ac - optind != 2) {
+		usage();
+	}
+	if (options != NULL && *options == '\0') {
+		options = NULL;
 	}
-	int ret = mount(av[1], av[2], "ext2", 0, NULL);
+	int ret = mount(av[optind], av[optind + 1], type, flags, options);
 	if (ret == -1) {
 		perror("mount");
 	}
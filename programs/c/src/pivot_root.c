@@ -0,0 +1,15 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/mount.h>
+
+int main(int ac, char **av) {
+	if (ac != 3) {
+		dprintf(2, "usage: pivot_root new_root put_old\n   the mount on new_root becomes the root mount,\n   the old root mount is moved on put_old, a directory under new_root\n");
+		exit(1);
+	}
+	if (pivot_root(av[1], av[2]) == -1) {
+		perror("pivot_root");
+		return 1;
+	}
+	return 0;
+}
@@ -19,7 +19,6 @@ pub fn shell() -> ! {
     loop {
         // Display prompt
         print!("{}", PROMPT);
-        print!("{}", CursorMove::Forward(0));
         // Call to blocked read_line function
         let line = read_line();
         // Make a line jump
@@ -96,10 +95,13 @@ fn read_line() -> String {
                 graphical_cursor_offset += 1;
                 graphical_len += 1;
 
-                print!(
-                    "{}",
-                    CursorMove::Backward(graphical_len - graphical_cursor_offset)
-                );
+                // CSI 0D moves of one column like CSI 1D
+                if graphical_len != graphical_cursor_offset {
+                    print!(
+                        "{}",
+                        CursorMove::Backward(graphical_len - graphical_cursor_offset)
+                    );
+                }
             }
             27 => {
                 let ret = unsafe { read(0, buf[1..].as_mut_ptr() as *mut u8, 2) };
@@ -110,8 +112,8 @@ fn read_line() -> String {
                     continue;
                 }
                 match buf[1..3] {
-                    // left
-                    [79, 68] => {
+                    // left, in application or normal cursor keys mode
+                    [79, 68] | [91, 68] => {
                         if cursor_pos > 0 {
                             while !line.is_char_boundary(cursor_pos - 1) {
                                 cursor_pos -= 1;
@@ -124,7 +126,7 @@ fn read_line() -> String {
                     }
 
                     //right
-                    [79, 67] => {
+                    [79, 67] | [91, 67] => {
                         if cursor_pos < line.len() {
                             while !line.is_char_boundary(cursor_pos + 1) {
                                 cursor_pos += 1;
@@ -26,14 +26,16 @@ pid_t init_forker(const char *tty_device, int argc, char *argv[], char *envp[])
 		perror("fork failed");
 		return -1;
 	} else if (pid == 0) {
+		// The tty becomes the controlling terminal of the new session
+		pid_t sid = setsid();
 		int fd = open_tty_device(tty_device);
 
 		if (argc < 2) {
 			dprintf(fd, "bad argument number %i: should be at least 2\n", argc);
 			return -1;
 		}
-		if (setpgid(0, 0) < 0) {
-			perror("setpgid failed");
+		if (sid < 0) {
+			perror("setsid failed");
 			return -1;
 		}
 		if (tcsetpgrp(fd, getpgid(0)) < 0) {
@@ -65,6 +67,9 @@ int main(int argc, char **argv, char **envp)
 {
 	char buf[BUF_LEN];
 	pid_t p[MAX_TTY];
+	// The console chosen on the kernel command line (ex: /dev/ttyS0)
+	char *console = getenv("CONSOLE");
+	pid_t console_pid = -1;
 
 	// Create all the process
 	for (int i = 0; i < MAX_TTY; i++) {
@@ -75,6 +80,9 @@ int main(int argc, char **argv, char **envp)
 			while (1) {}
 		}
 	}
+	if (console != NULL) {
+		console_pid = init_forker(console, argc, argv, envp);
+	}
 
 	int status;
 
@@ -96,6 +104,9 @@ int main(int argc, char **argv, char **envp)
 				break;
 			}
 		}
+		if (console != NULL && console_pid == ret) {
+			console_pid = init_forker(console, argc, argv, envp);
+		}
 
 	}
 	return 0;
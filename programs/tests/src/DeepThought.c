@@ -124,6 +124,7 @@ static struct program_test TEST_PROGRAMS[] = {
 	{.path = "/bin/DeepTests/wait/wuntraced"},
 	{.path = "/bin/DeepTests/mprotect/mprotect"},
 	{.path = "/bin/DeepTests/mmap/mmap"},
+	{.path = "/bin/DeepTests/mmap/mmap_shared_file"},
 	{.path = "/bin/DeepTests/atexit/atexit"},
 	{.path = "/bin/DeepTests/munmap/munmap"},
 	{.path = "/bin/DeepTests/sigprocmask/sigprocmask"},
@@ -151,6 +152,24 @@ static struct program_test TEST_PROGRAMS[] = {
 	{.path = "/bin/DeepTests/chown_tests/chown_basic"},
 	{.path = "/bin/DeepTests/fchown/fchown_basic"},
 	{.path = "/bin/DeepTests/syscalls/wrong_syscall"},
+	{.path = "/bin/DeepTests/fsync/fsync"},
+	{.path = "/bin/DeepTests/poll/poll"},
+	{.path = "/bin/DeepTests/pty/pty"},
+	{.path = "/bin/DeepTests/serial/serial_tty"},
+	{.path = "/bin/DeepTests/termios/termios_raw"},
+	{.path = "/bin/DeepTests/priority/priority"},
+	{.path = "/bin/DeepTests/timer/itimer"},
+	{.path = "/bin/DeepTests/timer/clock"},
+	{.path = "/bin/DeepTests/signal/siginfo"},
+	{.path = "/bin/DeepTests/signal/rtsig"},
+	{.path = "/bin/DeepTests/pthread/mutex_cond"},
+	{.path = "/bin/DeepTests/rlimit/rlimit"},
+	{.path = "/bin/DeepTests/session/session"},
+	{.path = "/bin/DeepTests/tmpfs/tmpfs"},
+	{.path = "/bin/DeepTests/mount/mount"},
+	{.path = "/bin/DeepTests/mount/pivot_root"},
+	{.path = "/bin/DeepTests/ptrace/ptrace"},
+	{.path = "/bin/DeepTests/fork/cow"},
 };
 
 #define TEST_PROGRAMS_LEN sizeof(TEST_PROGRAMS) / sizeof(struct program_test)
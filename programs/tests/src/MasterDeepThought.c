@@ -40,9 +40,11 @@ pid_t init_forker(const char *tty_device, int argc, char *argv[], char *envp[])
 		perror("fork failed");
 		exit(1);
 	} else if (pid == 0) {
+		// The tty becomes the controlling terminal of the new session
+		pid_t sid = setsid();
 		int fd = open_tty_device(tty_device);
-		if (setpgid(0, 0) < 0) {
-			perror("setpgid failed");
+		if (sid < 0) {
+			perror("setsid failed");
 			exit(1);
 		}
 		if (tcsetpgrp(fd, getpgid(0)) < 0) {
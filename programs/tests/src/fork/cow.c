@@ -0,0 +1,94 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <stdbool.h>
+#include <wait.h>
+
+#define PAGE_SIZE 4096
+#define NBR_PAGES 2048
+/*
+ * The memory of a child is freed at its exit, the pages which stay
+ * shared after a fork would exhaust the 512 MB of the machine
+ */
+#define ITERATIONS 128
+
+static void check(int cond, const char *msg) {
+	if (!cond) {
+		dprintf(2, "%s\n", msg);
+		exit(1);
+	}
+}
+
+static void fill(char *buf, char c) {
+	for (int i = 0; i < NBR_PAGES; i++) {
+		buf[i * PAGE_SIZE] = c;
+		buf[i * PAGE_SIZE + PAGE_SIZE - 1] = c;
+	}
+}
+
+static bool is_filled(const char *buf, char c) {
+	for (int i = 0; i < NBR_PAGES; i++) {
+		if (buf[i * PAGE_SIZE] != c || buf[i * PAGE_SIZE + PAGE_SIZE - 1] != c) {
+			return false;
+		}
+	}
+	return true;
+}
+
+static void child(char *buf, int to_child, int to_parent) {
+	char c;
+
+	// The child sees the memory of the parent at the fork
+	if (!is_filled(buf, 'p')) {
+		exit(2);
+	}
+	if (write(to_parent, "", 1) != 1 || read(to_child, &c, 1) != 1) {
+		exit(3);
+	}
+	// The writes of the parent after the fork stay private
+	if (!is_filled(buf, 'p')) {
+		exit(4);
+	}
+	// The parent has its own copy, the child writes the original pages
+	fill(buf, 'c');
+	if (!is_filled(buf, 'c')) {
+		exit(5);
+	}
+	exit(0);
+}
+
+int main() {
+	char *buf = malloc(NBR_PAGES * PAGE_SIZE);
+	int to_child[2];
+	int to_parent[2];
+	int status;
+	char c;
+
+	check(buf != NULL, "malloc failed");
+	for (int i = 0; i < ITERATIONS; i++) {
+		fill(buf, 'p');
+		check(pipe(to_child) == 0 && pipe(to_parent) == 0, "pipe failed");
+		pid_t pid = fork();
+		check(pid != -1, "fork failed");
+		if (pid == 0) {
+			close(to_child[1]);
+			close(to_parent[0]);
+			child(buf, to_child[0], to_parent[1]);
+		}
+		close(to_child[0]);
+		close(to_parent[1]);
+		check(read(to_parent[0], &c, 1) == 1, "the child did not read the memory of the parent");
+		// The parent gets its own copy of the pages
+		fill(buf, 'q');
+		check(write(to_child[1], "", 1) == 1, "write to the child failed");
+		check(waitpid(pid, &status, 0) == pid, "waitpid failed");
+		check(WIFEXITED(status), "the child was killed");
+		check(WEXITSTATUS(status) == 0, "the child saw a write of the parent or lost the memory of the fork");
+		// The writes of the child stay private
+		check(is_filled(buf, 'q'), "the parent saw a write of the child");
+		close(to_child[1]);
+		close(to_parent[0]);
+	}
+	free(buf);
+	return 0;
+}
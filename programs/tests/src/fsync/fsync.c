@@ -0,0 +1,56 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <fcntl.h>
+#include <stdlib.h>
+#include <string.h>
+#include <errno.h>
+
+int main() {
+	char filename[100];
+	char buf[64];
+	const char *content = "synchronized with the disk";
+
+	sprintf(filename, "./fsync_file_%d", getpid());
+	int fd = open(filename, O_RDWR | O_CREAT | O_TRUNC, 0644);
+	if (fd == -1) {
+		perror("open");
+		exit(1);
+	}
+	if (write(fd, content, strlen(content)) != (ssize_t)strlen(content)) {
+		perror("write");
+		exit(1);
+	}
+	if (fsync(fd) == -1) {
+		perror("fsync");
+		exit(1);
+	}
+	sync();
+
+	if (lseek(fd, 0, SEEK_SET) == -1) {
+		perror("lseek");
+		exit(1);
+	}
+	memset(buf, 0, sizeof(buf));
+	if (read(fd, buf, sizeof(buf)) != (ssize_t)strlen(content) || strcmp(buf, content) != 0) {
+		dprintf(2, "content changed after fsync: %s\n", buf);
+		exit(1);
+	}
+	close(fd);
+	unlink(filename);
+
+	if (fsync(42) != -1 || errno != EBADF) {
+		dprintf(2, "fsync on a bad file descriptor should fail with EBADF\n");
+		exit(1);
+	}
+
+	int pipefd[2];
+	if (pipe(pipefd) == -1) {
+		perror("pipe");
+		exit(1);
+	}
+	if (fsync(pipefd[1]) != -1 || errno != EINVAL) {
+		dprintf(2, "fsync on a pipe should fail with EINVAL\n");
+		exit(1);
+	}
+	return 0;
+}
@@ -0,0 +1,65 @@
+#include <sys/mman.h>
+#include <unistd.h>
+#include <stdio.h>
+#include <fcntl.h>
+#include <stdlib.h>
+#include <string.h>
+#include <assert.h>
+#include <errno.h>
+
+int main() {
+	char filename[100];
+
+	pid_t pid = getpid();
+	sprintf(filename, "./test_mmap_shared_file_%d", pid);
+
+	int fd = open(filename, O_RDWR | O_CREAT, 0644);
+	if (fd == -1) {
+		perror("open");
+		exit(1);
+	}
+	char banane[] = "banane";
+	assert(write(fd, banane, sizeof(banane)) == sizeof(banane));
+
+	char *addr = mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+	if (addr == MAP_FAILED) {
+		perror("mmap");
+		exit(1);
+	}
+	// The mapping stays valid after the close
+	close(fd);
+	assert(strcmp(addr, banane) == 0);
+	// The end of the page after the end of the file is zeroed
+	for (int i = sizeof(banane); i < 4096; i++) {
+		assert(addr[i] == 0);
+	}
+
+	memcpy(addr, "ananas", 6);
+	assert(msync(addr, 4096, MS_SYNC) == 0);
+	assert(munmap(addr, 4096) == 0);
+
+	fd = open(filename, O_RDONLY);
+	char buf[100];
+	int count = read(fd, buf, sizeof(buf));
+	// The file is never extended by a mapping
+	assert(count == sizeof(banane));
+	assert(strcmp(buf, "ananas") == 0);
+
+	// A private mapping does not modify the file
+	addr = mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_PRIVATE, fd, 0);
+	assert(addr != MAP_FAILED);
+	addr[0] = 'b';
+	assert(munmap(addr, 4096) == 0);
+	lseek(fd, 0, SEEK_SET);
+	assert(read(fd, buf, sizeof(buf)) == sizeof(banane));
+	assert(strcmp(buf, "ananas") == 0);
+
+	// A shared writable mapping needs a file open for writing
+	assert(mmap(NULL, 4096, PROT_WRITE, MAP_SHARED, fd, 0) == MAP_FAILED);
+	close(fd);
+	assert(unlink(filename) == 0);
+
+	// The anonymous pages cannot be shared
+	assert(mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_SHARED | MAP_ANONYMOUS, -1, 0) == MAP_FAILED);
+	assert(errno == EINVAL);
+}
@@ -0,0 +1,125 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <sys/stat.h>
+#include <sys/mount.h>
+
+static void check(int cond, const char *msg) {
+	if (!cond) {
+		dprintf(2, "%s (errno: %d)\n", msg, errno);
+		exit(1);
+	}
+}
+
+static int same_file(const char *a, const char *b) {
+	struct stat st_a;
+	struct stat st_b;
+
+	return stat(a, &st_a) == 0 && stat(b, &st_b) == 0
+		&& st_a.st_dev == st_b.st_dev && st_a.st_ino == st_b.st_ino;
+}
+
+/* Returns whether the line of /proc/mounts for target contains options */
+static int has_mount_options(const char *target, const char *options) {
+	char buf[4096];
+	char pattern[256];
+	int fd = open("/proc/mounts", O_RDONLY);
+	int len = 0;
+	int ret;
+
+	if (fd == -1) {
+		return 0;
+	}
+	while ((ret = read(fd, buf + len, sizeof(buf) - 1 - len)) > 0) {
+		len += ret;
+	}
+	close(fd);
+	buf[len] = '\0';
+	snprintf(pattern, sizeof(pattern), " %s tmpfs %s 0 0\n", target, options);
+	return strstr(buf, pattern) != NULL;
+}
+
+#define DIR "/tmp/mount_test"
+
+int main() {
+	int fd;
+
+	check(mkdir(DIR, 0755) == 0, "mkdir");
+	check(mkdir(DIR "/mnt", 0755) == 0, "mkdir mnt");
+	check(mkdir(DIR "/bind", 0755) == 0, "mkdir bind");
+
+	// Unknown filesystem types are refused
+	check(mount("none", DIR "/mnt", "nofs", 0, NULL) == -1 && errno == ENODEV, "mount of an unknown type should fail");
+	check(mount("none", DIR "/mnt", "proc", 0, NULL) == -1 && errno == ENODEV, "the procfs is only mounted at boot");
+	check(mount(DIR, DIR "/mnt", "ext2", 0, NULL) == -1 && errno == ENOTBLK, "the source of an ext2 should be a device");
+
+	// A read-only mount
+	check(mount("none", DIR "/mnt", "tmpfs", MS_RDONLY | MS_NOEXEC, NULL) == 0, "mount read-only");
+	check(has_mount_options(DIR "/mnt", "ro,noexec"), "/proc/mounts should show ro,noexec");
+	check(open(DIR "/mnt/file", O_WRONLY | O_CREAT, 0755) == -1 && errno == EROFS, "creat on a read-only mount");
+	check(mkdir(DIR "/mnt/dir", 0755) == -1 && errno == EROFS, "mkdir on a read-only mount");
+	check(chmod(DIR "/mnt", 0700) == -1 && errno == EROFS, "chmod on a read-only mount");
+	check(access(DIR "/mnt", W_OK) == -1 && errno == EROFS, "access W_OK on a read-only mount");
+
+	// The parent of the root of a mount is the parent of its mount dir
+	check(same_file(DIR "/mnt/..", DIR), ".. of a mount root");
+	check(rmdir(DIR "/mnt") == -1 && errno == EBUSY, "rmdir of a mount point");
+
+	// Remount read-write, but still noexec
+	check(mount(NULL, DIR "/mnt", NULL, MS_REMOUNT | MS_NOEXEC, NULL) == 0, "remount");
+	check(has_mount_options(DIR "/mnt", "rw,noexec"), "/proc/mounts should show rw,noexec");
+	fd = open(DIR "/mnt/file", O_WRONLY | O_CREAT, 0755);
+	check(fd != -1, "creat after remount");
+	check(write(fd, "#!/bin/sh\n", 10) == 10, "write after remount");
+	close(fd);
+	check(mkdir(DIR "/mnt/dir", 0755) == 0, "mkdir after remount");
+	check(access(DIR "/mnt/file", X_OK) == -1 && errno == EACCES, "access X_OK on a noexec mount");
+	char *argv[] = {DIR "/mnt/file", NULL};
+	check(execve(argv[0], argv, NULL) == -1 && errno == EACCES, "execve on a noexec mount");
+	check(mount(NULL, DIR "/bind", NULL, MS_REMOUNT, NULL) == -1 && errno == EINVAL, "remount of a directory which is not a mount");
+	check(mount(NULL, "/", NULL, MS_REMOUNT, NULL) == 0, "remount of the root");
+
+	// A read-only bind mount of a part of the tmpfs
+	check(mount(DIR "/mnt/dir", DIR "/bind", NULL, MS_BIND | MS_RDONLY, NULL) == 0, "bind mount");
+	check(same_file(DIR "/mnt/dir", DIR "/bind"), "the bind mount should show the source");
+	check(same_file(DIR "/bind/..", DIR), ".. of a bind mount root");
+	fd = open(DIR "/mnt/dir/file", O_WRONLY | O_CREAT, 0644);
+	check(fd != -1, "creat in the source of a bind mount");
+	close(fd);
+	check(same_file(DIR "/mnt/dir/file", DIR "/bind/file"), "a file should be visible through the bind mount");
+	check(open(DIR "/bind/file", O_WRONLY) == -1 && errno == EROFS, "open for write through a read-only bind mount");
+	check(unlink(DIR "/bind/file") == -1 && errno == EROFS, "unlink through a read-only bind mount");
+	// The flags of the mount through which a file is opened apply to the open file
+	fd = open(DIR "/bind/file", O_RDONLY);
+	check(fd != -1, "open through a read-only bind mount");
+	check(fchmod(fd, 0600) == -1 && errno == EROFS, "fchmod through a read-only bind mount");
+	check(fchown(fd, 0, 0) == -1 && errno == EROFS, "fchown through a read-only bind mount");
+	close(fd);
+	fd = open(DIR "/mnt/dir/file", O_RDONLY);
+	check(fd != -1, "open in the source of a bind mount");
+	check(fchmod(fd, 0600) == 0, "fchmod in the source of a bind mount");
+	check(fchown(fd, 0, 0) == 0, "fchown in the source of a bind mount");
+	close(fd);
+	check(umount(DIR "/mnt") == -1 && errno == EBUSY, "umount of a bound filesystem");
+	check(rmdir(DIR "/mnt/dir") == -1, "rmdir of the root of a bind mount");
+	check(umount(DIR "/bind") == 0, "umount of the bind mount");
+	check(access(DIR "/bind/file", F_OK) == -1 && errno == ENOENT, "the bind mount should be gone");
+	check(access(DIR "/mnt/dir/file", F_OK) == 0, "the source should stay after the umount of a bind mount");
+
+	// Device files are not accessible on a nodev mount
+	check(mount("/dev", DIR "/bind", NULL, MS_BIND | MS_NODEV, NULL) == 0, "bind mount of /dev");
+	check(open(DIR "/bind/null", O_RDONLY) == -1 && errno == EACCES, "open of a device on a nodev mount");
+	fd = open("/dev/null", O_RDONLY);
+	check(fd != -1, "open of a device on its own mount");
+	close(fd);
+	check(umount(DIR "/bind") == 0, "umount of the bind mount of /dev");
+
+	check(umount(DIR "/mnt") == 0, "umount");
+	check(rmdir(DIR "/mnt") == 0, "rmdir mnt");
+	check(rmdir(DIR "/bind") == 0, "rmdir bind");
+	check(rmdir(DIR) == 0, "rmdir");
+	return 0;
+}
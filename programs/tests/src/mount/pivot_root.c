@@ -0,0 +1,61 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <sys/stat.h>
+#include <sys/mount.h>
+
+static void check(int cond, const char *msg) {
+	if (!cond) {
+		dprintf(2, "%s (errno: %d)\n", msg, errno);
+		exit(1);
+	}
+}
+
+#define DIR "/tmp/pivot_root_test"
+
+int main() {
+	int fd;
+
+	check(mkdir(DIR, 0755) == 0, "mkdir");
+	check(mkdir(DIR "/mnt", 0755) == 0, "mkdir mnt");
+	check(mkdir(DIR "/moved", 0755) == 0, "mkdir moved");
+	check(mount("none", DIR "/mnt", "tmpfs", 0, NULL) == 0, "mount");
+	check(mkdir(DIR "/mnt/old", 0755) == 0, "mkdir old");
+	fd = open(DIR "/mnt/file", O_WRONLY | O_CREAT, 0644);
+	check(fd != -1, "creat");
+	close(fd);
+
+	// Move a mount to another directory and back
+	check(mount(DIR, DIR "/moved", NULL, MS_MOVE, NULL) == -1 && errno == EINVAL, "move of a directory which is not a mount");
+	check(mount("/", DIR "/moved", NULL, MS_MOVE, NULL) == -1 && errno == EINVAL, "move of the root mount");
+	check(mount(DIR "/mnt", DIR "/mnt/old", NULL, MS_MOVE, NULL) == -1 && errno == EINVAL, "move of a mount under itself");
+	check(mount(DIR "/mnt", DIR "/moved", NULL, MS_MOVE, NULL) == 0, "move");
+	check(access(DIR "/moved/file", F_OK) == 0, "the file should be on the new mount dir");
+	check(access(DIR "/mnt/file", F_OK) == -1 && errno == ENOENT, "the old mount dir should be empty");
+	check(rmdir(DIR "/moved") == -1 && errno == EBUSY, "rmdir of the new mount dir");
+	check(mount(DIR "/moved", DIR "/mnt", NULL, MS_MOVE, NULL) == 0, "move back");
+	check(access(DIR "/mnt/file", F_OK) == 0, "the file should be back");
+
+	// Invalid pivots
+	check(pivot_root(DIR, DIR "/mnt/old") == -1 && errno == EINVAL, "pivot_root on a directory which is not a mount");
+	check(pivot_root(DIR "/mnt", DIR "/moved") == -1 && errno == EINVAL, "pivot_root with put_old outside of new_root");
+	check(pivot_root("/", DIR "/moved") == -1 && errno == EBUSY, "pivot_root on the current root");
+	check(pivot_root(DIR "/none", DIR "/mnt/old") == -1 && errno == ENOENT, "pivot_root on a missing directory");
+
+	// Swap the root with the tmpfs, then restore it
+	check(pivot_root(DIR "/mnt", DIR "/mnt/old") == 0, "pivot_root");
+	check(access("/file", F_OK) == 0, "the tmpfs should be the root");
+	check(access("/old/bin", F_OK) == 0, "the old root should be on put_old");
+	check(umount("/") == -1 && errno == EBUSY, "umount of the new root");
+	check(pivot_root("/old", "/old" DIR "/mnt") == 0, "pivot_root back");
+	check(access("/bin", F_OK) == 0, "the old root should be restored");
+	check(access(DIR "/mnt/file", F_OK) == 0, "the tmpfs should be back on its mount dir");
+
+	check(umount(DIR "/mnt") == 0, "umount");
+	check(rmdir(DIR "/mnt") == 0, "rmdir mnt");
+	check(rmdir(DIR "/moved") == 0, "rmdir moved");
+	check(rmdir(DIR) == 0, "rmdir");
+	return 0;
+}
@@ -0,0 +1,82 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <errno.h>
+#include <poll.h>
+#include <sys/select.h>
+#include <sys/wait.h>
+
+int main() {
+	int pipefd[2];
+	struct pollfd fds[2];
+
+	if (pipe(pipefd) == -1) {
+		perror("pipe");
+		exit(1);
+	}
+	fds[0].fd = pipefd[0];
+	fds[0].events = POLLIN;
+	fds[1].fd = pipefd[1];
+	fds[1].events = POLLOUT;
+
+	// An empty pipe is writable but not readable
+	if (poll(fds, 2, 0) != 1 || fds[0].revents != 0 || !(fds[1].revents & POLLOUT)) {
+		dprintf(2, "empty pipe: bad poll result\n");
+		exit(1);
+	}
+
+	// A select with a timeout on an empty pipe expires
+	fd_set readfds;
+	struct timeval timeout = {0, 100000};
+	FD_ZERO(&readfds);
+	FD_SET(pipefd[0], &readfds);
+	if (select(pipefd[0] + 1, &readfds, NULL, NULL, &timeout) != 0 || FD_ISSET(pipefd[0], &readfds)) {
+		dprintf(2, "select on an empty pipe should time out\n");
+		exit(1);
+	}
+
+	// The reader is woken up when the child writes into the pipe
+	pid_t pid = fork();
+	if (pid == -1) {
+		perror("fork");
+		exit(1);
+	} else if (pid == 0) {
+		sleep(1);
+		write(pipefd[1], "x", 1);
+		exit(0);
+	}
+	if (poll(fds, 1, -1) != 1 || !(fds[0].revents & POLLIN)) {
+		dprintf(2, "poll should report the pipe as readable\n");
+		exit(1);
+	}
+	FD_ZERO(&readfds);
+	FD_SET(pipefd[0], &readfds);
+	if (select(pipefd[0] + 1, &readfds, NULL, NULL, NULL) != 1 || !FD_ISSET(pipefd[0], &readfds)) {
+		dprintf(2, "select should report the pipe as readable\n");
+		exit(1);
+	}
+	wait(NULL);
+
+	// Closing the writer hangs up the reader
+	close(pipefd[1]);
+	char c;
+	read(pipefd[0], &c, 1);
+	if (poll(fds, 1, 0) != 1 || !(fds[0].revents & POLLHUP)) {
+		dprintf(2, "poll should report POLLHUP when the writer is closed\n");
+		exit(1);
+	}
+
+	// Invalid file descriptors
+	fds[0].fd = 42;
+	if (poll(fds, 1, 0) != 1 || fds[0].revents != POLLNVAL) {
+		dprintf(2, "poll should report POLLNVAL on a bad file descriptor\n");
+		exit(1);
+	}
+	FD_ZERO(&readfds);
+	FD_SET(42, &readfds);
+	if (select(43, &readfds, NULL, NULL, NULL) != -1 || errno != EBADF) {
+		dprintf(2, "select on a bad file descriptor should fail with EBADF\n");
+		exit(1);
+	}
+	return 0;
+}
@@ -0,0 +1,68 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <errno.h>
+#include <sched.h>
+#include <sys/resource.h>
+#include <sys/wait.h>
+
+static void check(int cond, const char *msg) {
+	if (!cond) {
+		dprintf(2, "%s (errno: %d)\n", msg, errno);
+		exit(1);
+	}
+}
+
+int main() {
+	struct sched_param param;
+
+	/* NICE VALUES */
+	check(getpriority(PRIO_PROCESS, 0) == 0, "the default nice value should be 0");
+	check(nice(5) == 5, "nice(5) should return the new nice value");
+	check(getpriority(PRIO_PROCESS, getpid()) == 5, "getpriority() should get the new nice value");
+	check(setpriority(PRIO_PROCESS, 0, 10) == 0, "setpriority()");
+	check(getpriority(PRIO_PGRP, 0) == 10, "getpriority() on the process group");
+	// The nice values are clamped
+	check(nice(100) == 19, "nice() should clamp to 19");
+	check(setpriority(PRIO_PROCESS, 0, -100) == 0, "setpriority() to a very low value");
+	check(getpriority(PRIO_PROCESS, 0) == -20, "setpriority() should clamp to -20");
+	check(setpriority(PRIO_PROCESS, 0, 0) == 0, "setpriority() back to 0");
+	check(getpriority(PRIO_PROCESS, 0x7fff) == -1 && errno == ESRCH, "getpriority() on an unknown process should fail with ESRCH");
+	check(setpriority(42, 0, 0) == -1 && errno == EINVAL, "setpriority() with a bad which should fail with EINVAL");
+
+	/* SCHEDULING POLICIES */
+	check(sched_getscheduler(0) == SCHED_OTHER, "the default policy should be SCHED_OTHER");
+	check(sched_get_priority_min(SCHED_FIFO) == 1 && sched_get_priority_max(SCHED_RR) == 99, "real time priority range");
+	check(sched_get_priority_max(42) == -1 && errno == EINVAL, "sched_get_priority_max() with a bad policy");
+	param.sched_priority = 0;
+	check(sched_setscheduler(0, 42, &param) == -1 && errno == EINVAL, "a bad policy should fail with EINVAL");
+	check(sched_setscheduler(0, SCHED_FIFO, &param) == -1 && errno == EINVAL, "a real time priority of 0 should fail with EINVAL");
+	param.sched_priority = 10;
+	check(sched_setscheduler(0, SCHED_OTHER, &param) == -1 && errno == EINVAL, "SCHED_OTHER only accepts the priority 0");
+	check(sched_setscheduler(0, SCHED_RR, &param) == 0, "sched_setscheduler(SCHED_RR)");
+	check(sched_getscheduler(0) == SCHED_RR, "sched_getscheduler() should get SCHED_RR");
+	param.sched_priority = 0;
+	check(sched_getparam(0, &param) == 0 && param.sched_priority == 10, "sched_getparam() should get 10");
+	check(sched_yield() == 0, "sched_yield()");
+	param.sched_priority = 0;
+	check(sched_setscheduler(0, SCHED_OTHER, &param) == 0, "sched_setscheduler(SCHED_OTHER)");
+
+	/* PERMISSIONS */
+	pid_t pid = fork();
+	check(pid != -1, "fork");
+	if (pid == 0) {
+		// The scheduling parameters are inherited
+		check(getpriority(PRIO_PROCESS, 0) == 0, "the child should inherit the nice value");
+		check(setuid(1000) == 0, "setuid");
+		check(nice(1) == 1, "an unprivileged process can increase its nice value");
+		check(nice(-1) == -1 && errno == EPERM, "an unprivileged process cannot decrease its nice value");
+		check(setpriority(PRIO_PROCESS, getppid(), 19) == -1 && errno == EPERM, "an unprivileged process cannot change the nice value of root");
+		param.sched_priority = 1;
+		check(sched_setscheduler(0, SCHED_FIFO, &param) == -1 && errno == EPERM, "an unprivileged process cannot get a real time policy");
+		exit(0);
+	}
+	int status;
+	check(waitpid(pid, &status, 0) == pid, "waitpid");
+	check(WIFEXITED(status) && WEXITSTATUS(status) == 0, "the child failed");
+	return 0;
+}
@@ -0,0 +1,82 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <errno.h>
+#include <time.h>
+#include <pthread.h>
+#include <sys/futex.h>
+
+#define NB_THREADS 4
+#define NB_LOOPS 10000
+
+static pthread_mutex_t mutex = PTHREAD_MUTEX_INITIALIZER;
+static pthread_cond_t cond = PTHREAD_COND_INITIALIZER;
+static int counter = 0;
+static int ready = 0;
+
+static void check(int cond, const char *msg) {
+	if (!cond) {
+		dprintf(2, "%s (errno: %d)\n", msg, errno);
+		exit(1);
+	}
+}
+
+/* Increment the shared counter under the mutex */
+static void *increment(void *arg) {
+	int i;
+
+	for (i = 0; i < NB_LOOPS; i++) {
+		pthread_mutex_lock(&mutex);
+		counter++;
+		pthread_mutex_unlock(&mutex);
+	}
+	return arg;
+}
+
+/* Wait for the main thread to be ready and answer it */
+static void *handshake(void *arg) {
+	pthread_mutex_lock(&mutex);
+	while (ready == 0) {
+		pthread_cond_wait(&cond, &mutex);
+	}
+	ready = 2;
+	pthread_cond_signal(&cond);
+	pthread_mutex_unlock(&mutex);
+	pthread_exit(arg);
+	return NULL;
+}
+
+int main() {
+	struct timespec timeout = {0, 10000000};
+	pthread_t threads[NB_THREADS];
+	void *retval;
+	int word = 42;
+	int i;
+
+	// A futex wait on a changed value fails immediately, otherwise it times out
+	check(futex(&word, FUTEX_WAIT, 0, NULL, NULL, 0) == -1 && errno == EAGAIN, "futex should fail with EAGAIN");
+	check(futex(&word, FUTEX_WAIT, 42, &timeout, NULL, 0) == -1 && errno == ETIMEDOUT, "futex should time out");
+	check(futex(&word, FUTEX_WAKE, 1, NULL, NULL, 0) == 0, "nobody should be woken");
+
+	for (i = 0; i < NB_THREADS; i++) {
+		check(pthread_create(&threads[i], NULL, increment, (void *)(long)i) == 0, "pthread_create");
+	}
+	for (i = 0; i < NB_THREADS; i++) {
+		check(pthread_join(threads[i], &retval) == 0, "pthread_join");
+		check(retval == (void *)(long)i, "bad thread return value");
+	}
+	check(counter == NB_THREADS * NB_LOOPS, "the counter was not protected by the mutex");
+
+	check(pthread_create(&threads[0], NULL, handshake, (void *)0x42) == 0, "pthread_create");
+	check(!pthread_equal(threads[0], pthread_self()), "threads should be different");
+	pthread_mutex_lock(&mutex);
+	ready = 1;
+	pthread_cond_signal(&cond);
+	while (ready != 2) {
+		pthread_cond_wait(&cond, &mutex);
+	}
+	pthread_mutex_unlock(&mutex);
+	check(pthread_join(threads[0], &retval) == 0, "pthread_join");
+	check(retval == (void *)0x42, "bad pthread_exit value");
+	return 0;
+}
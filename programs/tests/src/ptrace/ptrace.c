@@ -0,0 +1,185 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <errno.h>
+#include <signal.h>
+#include <stddef.h>
+#include <sys/wait.h>
+#include <sys/ptrace.h>
+#include <user_syscall.h>
+
+static void check(int cond, const char *msg) {
+	if (!cond) {
+		dprintf(2, "%s (errno: %d)\n", msg, errno);
+		exit(1);
+	}
+}
+
+static char *g_self;
+static volatile long g_word = 0x1234;
+static volatile int g_loop = 1;
+
+/* Fork a child which stops itself with SIGSTOP as a tracee of its parent, then calls fn */
+static pid_t traced_child(int (*fn)(void)) {
+	int status;
+	pid_t pid = fork();
+
+	check(pid != -1, "fork");
+	if (pid == 0) {
+		check(ptrace(PTRACE_TRACEME, 0, NULL, NULL) == 0, "PTRACE_TRACEME");
+		raise(SIGSTOP);
+		exit(fn());
+	}
+	// The stops of a tracee are reported without WUNTRACED
+	check(waitpid(pid, &status, 0) == pid, "waitpid");
+	check(WIFSTOPPED(status) && WSTOPSIG(status) == SIGSTOP, "the tracee should stop on SIGSTOP");
+	return pid;
+}
+
+/* Resume the tracee until it exits, and check its exit status */
+static void check_exit(pid_t pid, int expected) {
+	int status;
+
+	check(ptrace(PTRACE_CONT, pid, NULL, NULL) == 0, "PTRACE_CONT");
+	check(waitpid(pid, &status, 0) == pid, "waitpid exit");
+	check(WIFEXITED(status) && WEXITSTATUS(status) == expected, "the tracee should exit");
+}
+
+static int word_was_poked(void) {
+	return g_word == 0x5678 ? 0 : 1;
+}
+
+static void peek_poke(void) {
+	struct user_regs_struct regs;
+	long word;
+	pid_t pid = traced_child(word_was_poked);
+
+	errno = 0;
+	word = ptrace(PTRACE_PEEKDATA, pid, (void *)&g_word, NULL);
+	check(errno == 0 && word == 0x1234, "PTRACE_PEEKDATA");
+	check(ptrace(PTRACE_POKEDATA, pid, (void *)&g_word, (void *)0x5678) == 0, "PTRACE_POKEDATA");
+	errno = 0;
+	word = ptrace(PTRACE_PEEKTEXT, pid, (void *)&word_was_poked, NULL);
+	check(errno == 0, "PTRACE_PEEKTEXT");
+	// The text is read-only for the tracee, not for its tracer
+	check(ptrace(PTRACE_POKETEXT, pid, (void *)&word_was_poked, (void *)word) == 0, "PTRACE_POKETEXT");
+	check(ptrace(PTRACE_PEEKDATA, pid, NULL, NULL) == -1 && errno == EIO, "PTRACE_PEEKDATA on NULL should fail");
+
+	check(ptrace(PTRACE_GETREGS, pid, NULL, &regs) == 0, "PTRACE_GETREGS");
+	check(regs.eip != 0 && regs.esp != 0, "the registers should be set");
+	errno = 0;
+	word = ptrace(PTRACE_PEEKUSER, pid, (void *)offsetof(struct user_regs_struct, eip), NULL);
+	check(errno == 0 && word == regs.eip, "PTRACE_PEEKUSER");
+	check(ptrace(PTRACE_PEEKUSER, pid, (void *)sizeof(regs), NULL) == -1 && errno == EIO, "PTRACE_PEEKUSER out of the user area should fail");
+	check_exit(pid, 0);
+}
+
+static int getpid_result(void) {
+	return getpid() == 42 ? 0 : 1;
+}
+
+static void syscall_stops(void) {
+	struct user_regs_struct regs;
+	int status;
+	int entered = 0;
+	pid_t pid = traced_child(getpid_result);
+
+	check(ptrace(PTRACE_SETOPTIONS, pid, NULL, (void *)PTRACE_O_TRACESYSGOOD) == 0, "PTRACE_SETOPTIONS");
+	for (;;) {
+		check(ptrace(PTRACE_SYSCALL, pid, NULL, NULL) == 0, "PTRACE_SYSCALL");
+		check(waitpid(pid, &status, 0) == pid, "waitpid syscall stop");
+		check(WIFSTOPPED(status) && WSTOPSIG(status) == (SIGTRAP | 0x80), "the tracee should stop at its syscalls");
+		check(ptrace(PTRACE_GETREGS, pid, NULL, &regs) == 0, "PTRACE_GETREGS");
+		if (regs.orig_eax != GETPID) {
+			continue;
+		}
+		if (!entered) {
+			entered = 1;
+			continue;
+		}
+		// The tracer changes the return value at the exit of the syscall
+		check(regs.eax == pid, "getpid should return the pid of the tracee");
+		check(ptrace(PTRACE_POKEUSER, pid, (void *)offsetof(struct user_regs_struct, eax), (void *)42) == 0, "PTRACE_POKEUSER");
+		break;
+	}
+	check_exit(pid, 0);
+}
+
+static int nothing(void) {
+	return 0;
+}
+
+static void single_step(void) {
+	struct user_regs_struct regs;
+	siginfo_t info;
+	int status;
+	long eip;
+	pid_t pid = traced_child(nothing);
+
+	check(ptrace(PTRACE_GETREGS, pid, NULL, &regs) == 0, "PTRACE_GETREGS");
+	eip = regs.eip;
+	for (int i = 0; i < 10; i++) {
+		check(ptrace(PTRACE_SINGLESTEP, pid, NULL, NULL) == 0, "PTRACE_SINGLESTEP");
+		check(waitpid(pid, &status, 0) == pid, "waitpid single step");
+		check(WIFSTOPPED(status) && WSTOPSIG(status) == SIGTRAP, "the tracee should stop after one instruction");
+		check(ptrace(PTRACE_GETSIGINFO, pid, NULL, &info) == 0, "PTRACE_GETSIGINFO");
+		check(info.si_signo == SIGTRAP && info.si_code == TRAP_TRACE, "the SIGTRAP should come from the trap flag");
+		check(ptrace(PTRACE_GETREGS, pid, NULL, &regs) == 0, "PTRACE_GETREGS");
+		check(regs.eip != eip, "eip should move");
+		eip = regs.eip;
+	}
+	check_exit(pid, 0);
+}
+
+static int exec_self(void) {
+	execl(g_self, g_self, "exec", NULL);
+	return 1;
+}
+
+static void exec_trap(void) {
+	int status;
+	pid_t pid = traced_child(exec_self);
+
+	check(ptrace(PTRACE_CONT, pid, NULL, NULL) == 0, "PTRACE_CONT");
+	check(waitpid(pid, &status, 0) == pid, "waitpid exec");
+	check(WIFSTOPPED(status) && WSTOPSIG(status) == SIGTRAP, "the tracee should stop after execve");
+	check_exit(pid, 0);
+}
+
+static void attach_detach(void) {
+	int status;
+	pid_t pid = fork();
+
+	check(pid != -1, "fork");
+	if (pid == 0) {
+		while (g_loop)
+			;
+		exit(0);
+	}
+	check(ptrace(PTRACE_PEEKDATA, pid, (void *)&g_loop, NULL) == -1 && errno == ESRCH, "a process which is not traced cannot be peeked");
+	check(ptrace(PTRACE_ATTACH, pid, NULL, NULL) == 0, "PTRACE_ATTACH");
+	check(ptrace(PTRACE_ATTACH, pid, NULL, NULL) == -1 && errno == EPERM, "a tracee cannot be attached twice");
+	check(waitpid(pid, &status, 0) == pid, "waitpid attach");
+	check(WIFSTOPPED(status) && WSTOPSIG(status) == SIGSTOP, "the tracee should stop on attach");
+	check(ptrace(PTRACE_POKEDATA, pid, (void *)&g_loop, (void *)0) == 0, "PTRACE_POKEDATA");
+	// The SIGSTOP is suppressed: the process runs to its end
+	check(ptrace(PTRACE_DETACH, pid, NULL, NULL) == 0, "PTRACE_DETACH");
+	check(ptrace(PTRACE_CONT, pid, NULL, NULL) == -1 && errno == ESRCH, "a detached process cannot be resumed");
+	check(waitpid(pid, &status, 0) == pid, "waitpid exit");
+	check(WIFEXITED(status) && WEXITSTATUS(status) == 0, "the detached process should exit");
+}
+
+int main(int argc, char **argv) {
+	if (argc > 1 && strcmp(argv[1], "exec") == 0) {
+		return 0;
+	}
+	g_self = argv[0];
+	check(ptrace(PTRACE_ATTACH, getpid(), NULL, NULL) == -1 && errno == EPERM, "a process cannot trace itself");
+	peek_poke();
+	syscall_stops();
+	single_step();
+	exec_trap();
+	attach_detach();
+	return 0;
+}
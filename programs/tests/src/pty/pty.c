@@ -0,0 +1,80 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <errno.h>
+#include <fcntl.h>
+
+int main() {
+	char buf[64];
+
+	int master = posix_openpt(O_RDWR);
+	if (master == -1) {
+		perror("posix_openpt");
+		exit(1);
+	}
+	if (grantpt(master) == -1) {
+		perror("grantpt");
+		exit(1);
+	}
+	char *name = ptsname(master);
+	if (name == NULL) {
+		perror("ptsname");
+		exit(1);
+	}
+	// The slave cannot be opened before unlockpt()
+	if (open(name, O_RDWR) != -1 || errno != EIO) {
+		dprintf(2, "opening a locked slave should fail with EIO\n");
+		exit(1);
+	}
+	if (unlockpt(master) == -1) {
+		perror("unlockpt");
+		exit(1);
+	}
+	int slave = open(name, O_RDWR);
+	if (slave == -1) {
+		perror("open slave");
+		exit(1);
+	}
+	if (!isatty(master) || !isatty(slave)) {
+		dprintf(2, "both sides of a pty should be terminals\n");
+		exit(1);
+	}
+
+	// What is written on the master is echoed back and read by the slave
+	if (write(master, "hello\n", 6) != 6) {
+		dprintf(2, "write on the master failed\n");
+		exit(1);
+	}
+	// The new line of the echo is translated by ONLCR
+	int ret = read(master, buf, sizeof(buf));
+	if (ret != 7 || memcmp(buf, "hello\r\n", 7) != 0) {
+		dprintf(2, "the master should read the echo of its input\n");
+		exit(1);
+	}
+	ret = read(slave, buf, sizeof(buf));
+	if (ret != 6 || memcmp(buf, "hello\n", 6) != 0) {
+		dprintf(2, "the slave should read the master input\n");
+		exit(1);
+	}
+
+	// What is written on the slave is read by the master
+	if (write(slave, "world", 5) != 5) {
+		dprintf(2, "write on the slave failed\n");
+		exit(1);
+	}
+	ret = read(master, buf, sizeof(buf));
+	if (ret != 5 || memcmp(buf, "world", 5) != 0) {
+		dprintf(2, "the master should read the slave output\n");
+		exit(1);
+	}
+
+	// Closing the last slave hangs up the master
+	close(slave);
+	if (read(master, buf, sizeof(buf)) != -1 || errno != EIO) {
+		dprintf(2, "reading a hung up master should fail with EIO\n");
+		exit(1);
+	}
+	close(master);
+	return 0;
+}
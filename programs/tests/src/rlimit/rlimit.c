@@ -0,0 +1,109 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <sys/mman.h>
+#include <sys/resource.h>
+#include <sys/wait.h>
+
+static void check(int cond, const char *msg) {
+	if (!cond) {
+		dprintf(2, "%s (errno: %d)\n", msg, errno);
+		exit(1);
+	}
+}
+
+/* Use about 1K of stack per call */
+static int recurse(int depth) {
+	volatile char buf[1024];
+
+	buf[0] = depth;
+	if (depth == 0) {
+		return buf[0];
+	}
+	return recurse(depth - 1) + buf[0] - depth;
+}
+
+/* Run fn in a child and return its wait status */
+static int in_child(void (*fn)(void)) {
+	int status;
+	pid_t pid = fork();
+
+	check(pid != -1, "fork");
+	if (pid == 0) {
+		fn();
+		exit(0);
+	}
+	check(waitpid(pid, &status, 0) == pid, "waitpid");
+	return status;
+}
+
+static void stack_overflow(void) {
+	struct rlimit rl = {256 * 1024, RLIM_INFINITY};
+
+	check(setrlimit(RLIMIT_STACK, &rl) == 0, "setrlimit RLIMIT_STACK");
+	recurse(1024);
+}
+
+static void nproc_limit(void) {
+	struct rlimit rl = {1, 1};
+
+	check(setuid(1000) == 0, "setuid");
+	check(setrlimit(RLIMIT_NPROC, &rl) == 0, "setrlimit RLIMIT_NPROC");
+	check(fork() == -1 && errno == EAGAIN, "fork should fail with EAGAIN");
+	// The hard limit cannot be raised by an unprivileged process
+	rl.rlim_max = 2;
+	check(setrlimit(RLIMIT_NPROC, &rl) == -1 && errno == EPERM, "setrlimit should fail with EPERM");
+}
+
+int main() {
+	struct rlimit rl;
+	struct rlimit old;
+	void *addr;
+	int status;
+	int fd;
+
+	check(getrlimit(RLIMIT_STACK, &rl) == 0, "getrlimit RLIMIT_STACK");
+	check(rl.rlim_cur == 8 * 1024 * 1024, "bad default RLIMIT_STACK");
+	check(getrlimit(42, &rl) == -1 && errno == EINVAL, "getrlimit should fail with EINVAL");
+	rl.rlim_cur = RLIM_INFINITY;
+	rl.rlim_max = 10;
+	check(setrlimit(RLIMIT_CORE, &rl) == -1 && errno == EINVAL, "soft limit above the hard limit");
+
+	// The stack grows far beyond its initial 64K
+	check(recurse(1024) == 0, "deep recursion");
+
+	// Beyond RLIMIT_STACK, the stack does not grow anymore
+	status = in_child(stack_overflow);
+	check(WIFSIGNALED(status) && WTERMSIG(status) == SIGSEGV, "the stack overflow should raise SIGSEGV");
+
+	// RLIMIT_NOFILE bounds the file descriptor numbers
+	rl.rlim_cur = 8;
+	rl.rlim_max = 8;
+	check(prlimit(0, RLIMIT_NOFILE, &rl, &old) == 0, "prlimit RLIMIT_NOFILE");
+	check(old.rlim_cur == 128, "bad default RLIMIT_NOFILE");
+	while ((fd = open("/dev/null", O_RDONLY)) != -1) {
+		check(fd < 8, "fd above RLIMIT_NOFILE");
+	}
+	check(errno == EMFILE, "open should fail with EMFILE");
+	check(dup2(0, 8) == -1 && errno == EBADF, "dup2 should fail with EBADF");
+	for (fd = 3; fd < 8; fd++) {
+		close(fd);
+	}
+
+	// RLIMIT_AS bounds the size of the address space
+	rl.rlim_cur = 16 * 1024 * 1024;
+	rl.rlim_max = RLIM_INFINITY;
+	check(setrlimit(RLIMIT_AS, &rl) == 0, "setrlimit RLIMIT_AS");
+	addr = mmap(NULL, 32 * 1024 * 1024, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	check(addr == MAP_FAILED && errno == ENOMEM, "mmap should fail with ENOMEM");
+	addr = mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	check(addr != MAP_FAILED, "small mmap");
+
+	// RLIMIT_NPROC bounds the number of processes of an user
+	status = in_child(nproc_limit);
+	check(WIFEXITED(status) && WEXITSTATUS(status) == 0, "RLIMIT_NPROC");
+	return 0;
+}
@@ -0,0 +1,41 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <fcntl.h>
+#include <termios.h>
+#include <stropts.h>
+
+int main() {
+	int fd = open("/dev/ttyS0", O_RDWR);
+	if (fd == -1) {
+		perror("open /dev/ttyS0");
+		exit(1);
+	}
+	if (!isatty(fd)) {
+		dprintf(2, "/dev/ttyS0 should be a terminal\n");
+		exit(1);
+	}
+	struct termios termios;
+	if (tcgetattr(fd, &termios) == -1) {
+		perror("tcgetattr");
+		exit(1);
+	}
+
+	// The size of the remote terminal is given by TIOCSWINSZ
+	struct winsize ws = {.ws_row = 42, .ws_col = 132};
+	if (ioctl(fd, TIOCSWINSZ, &ws) == -1) {
+		perror("ioctl TIOCSWINSZ");
+		exit(1);
+	}
+	struct winsize got;
+	if (ioctl(fd, TIOCGWINSZ, &got) == -1) {
+		perror("ioctl TIOCGWINSZ");
+		exit(1);
+	}
+	if (got.ws_row != 42 || got.ws_col != 132) {
+		dprintf(2, "TIOCGWINSZ should give the size set by TIOCSWINSZ\n");
+		exit(1);
+	}
+	close(fd);
+	return 0;
+}
@@ -0,0 +1,103 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <errno.h>
+#include <signal.h>
+#include <termios.h>
+#include <sys/wait.h>
+
+static void check(int cond, const char *msg) {
+	if (!cond) {
+		dprintf(2, "%s (errno: %d)\n", msg, errno);
+		exit(1);
+	}
+}
+
+/* Run fn in a child and return its exit status */
+static int in_child(int (*fn)(void)) {
+	int status;
+	pid_t pid = fork();
+
+	check(pid != -1, "fork");
+	if (pid == 0) {
+		exit(fn());
+	}
+	check(waitpid(pid, &status, 0) == pid, "waitpid");
+	check(WIFEXITED(status), "the child should exit");
+	return WEXITSTATUS(status);
+}
+
+static int new_session(void) {
+	pid_t pid = getpid();
+
+	if (setsid() != pid || getsid(0) != pid || getpgid(0) != pid) {
+		return 1;
+	}
+	// A second setsid fails: the process is a process group leader
+	if (setsid() != -1 || errno != EPERM) {
+		return 2;
+	}
+	// The terminal of the old session is not the controlling terminal anymore
+	if (isatty(0) && (tcgetpgrp(0) != -1 || errno != ENOTTY)) {
+		return 3;
+	}
+	if (isatty(0) && (tcgetsid(0) != -1 || errno != ENOTTY)) {
+		return 4;
+	}
+	// A session leader cannot change its process group
+	if (setpgid(0, 0) != -1 || errno != EPERM) {
+		return 5;
+	}
+	return 0;
+}
+
+static int background_read_ignored(void) {
+	char c;
+
+	check(setpgid(0, 0) == 0, "setpgid");
+	signal(SIGTTIN, SIG_IGN);
+	if (read(0, &c, 1) != -1 || errno != EIO) {
+		return 1;
+	}
+	return 0;
+}
+
+int main() {
+	pid_t pid;
+	pid_t sid = getsid(0);
+	int status;
+
+	check(sid > 0, "getsid");
+	check(getsid(getpid()) == sid, "getsid(getpid())");
+	check(getsid(-1) == -1 && errno == ESRCH, "getsid should fail with ESRCH");
+	check(in_child(new_session) == 0, "new session");
+	check(getsid(0) == sid, "the session of the parent should not change");
+
+	check(setpgid(0, -1) == -1 && errno == EINVAL, "setpgid should fail with EINVAL");
+	check(setpgid(1, 0) == -1 && errno == ESRCH, "setpgid should fail with ESRCH");
+
+	// The following tests need the terminal to be our controlling terminal
+	if (!isatty(0) || tcgetsid(0) != sid) {
+		return 0;
+	}
+
+	// A background process reading its controlling terminal is stopped by SIGTTIN
+	pid = fork();
+	check(pid != -1, "fork");
+	if (pid == 0) {
+		char c;
+
+		check(setpgid(0, 0) == 0, "setpgid");
+		read(0, &c, 1);
+		exit(1);
+	}
+	check(waitpid(pid, &status, WUNTRACED) == pid, "waitpid");
+	check(WIFSTOPPED(status) && WSTOPSIG(status) == SIGTTIN, "the background reader should be stopped by SIGTTIN");
+	check(kill(pid, SIGKILL) == 0, "kill");
+	check(waitpid(pid, &status, 0) == pid, "waitpid");
+	check(WIFSIGNALED(status) && WTERMSIG(status) == SIGKILL, "the background reader should be killed");
+
+	// When SIGTTIN is ignored, the read fails with EIO
+	check(in_child(background_read_ignored) == 0, "background read with SIGTTIN ignored");
+	return 0;
+}
@@ -0,0 +1,95 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <errno.h>
+#include <limits.h>
+#include <signal.h>
+#include <time.h>
+#include <sys/wait.h>
+
+static void check(int cond, const char *msg) {
+	if (!cond) {
+		dprintf(2, "%s (errno: %d)\n", msg, errno);
+		exit(1);
+	}
+}
+
+/* Accept a pending signal of set without blocking */
+static int poll_signal(const sigset_t *set, siginfo_t *info) {
+	struct timespec zero = {0, 0};
+
+	return sigtimedwait(set, info, &zero);
+}
+
+static void check_queued(const sigset_t *set, int signum, int value) {
+	siginfo_t info;
+
+	check(poll_signal(set, &info) == signum, "bad accepted signal");
+	check(info.si_signo == signum, "bad si_signo");
+	check(info.si_code == SI_QUEUE, "bad si_code");
+	check(info.si_pid == getpid(), "bad si_pid");
+	check(info.si_value.sival_int == value, "bad si_value");
+}
+
+int main() {
+	struct timespec timeout = {0, 100000000};
+	siginfo_t info;
+	sigset_t set;
+	pid_t pid;
+	int status;
+	int signum;
+	int i;
+
+	sigemptyset(&set);
+	sigaddset(&set, SIGUSR1);
+	sigaddset(&set, SIGRTMIN);
+	sigaddset(&set, SIGRTMIN + 1);
+	sigaddset(&set, SIGRTMAX);
+	check(sigismember(&set, SIGRTMAX) == 1, "sigismember SIGRTMAX");
+	check(sigprocmask(SIG_BLOCK, &set, NULL) == 0, "sigprocmask");
+
+	// The real-time signals are queued and accepted lowest number first,
+	// the standard signals are not queued
+	check(sigqueue(getpid(), SIGRTMIN + 1, (union sigval){.sival_int = 10}) == 0, "sigqueue");
+	check(sigqueue(getpid(), SIGRTMIN, (union sigval){.sival_int = 20}) == 0, "sigqueue");
+	check(sigqueue(getpid(), SIGRTMIN, (union sigval){.sival_int = 21}) == 0, "sigqueue");
+	check(kill(getpid(), SIGUSR1) == 0, "kill");
+	check(kill(getpid(), SIGUSR1) == 0, "kill");
+
+	check(poll_signal(&set, &info) == SIGUSR1, "SIGUSR1 should be accepted first");
+	check(info.si_code == SI_USER, "bad si_code for kill");
+	check_queued(&set, SIGRTMIN, 20);
+	check_queued(&set, SIGRTMIN, 21);
+	check_queued(&set, SIGRTMIN + 1, 10);
+	check(poll_signal(&set, &info) == -1 && errno == EAGAIN, "no signal should be pending");
+
+	// The timeout expires without signal
+	check(sigtimedwait(&set, &info, &timeout) == -1 && errno == EAGAIN, "timeout should expire");
+
+	// At most SIGQUEUE_MAX signals are queued
+	for (i = 0; sigqueue(getpid(), SIGRTMAX, (union sigval){.sival_int = i}) == 0; i++) {
+		check(i < SIGQUEUE_MAX, "too many queued signals");
+	}
+	check(errno == EAGAIN, "sigqueue should give EAGAIN");
+	check(i == SIGQUEUE_MAX, "not enough queued signals");
+	for (i = 0; i < SIGQUEUE_MAX; i++) {
+		check_queued(&set, SIGRTMAX, i);
+	}
+
+	// Wait for a value sent by a child
+	pid = fork();
+	check(pid != -1, "fork");
+	if (pid == 0) {
+		usleep(100000);
+		check(sigqueue(getppid(), SIGRTMIN, (union sigval){.sival_int = 42}) == 0, "sigqueue");
+		exit(0);
+	}
+	check(sigwaitinfo(&set, &info) == SIGRTMIN, "sigwaitinfo");
+	check(info.si_pid == pid, "bad si_pid from the child");
+	check(info.si_value.sival_int == 42, "bad si_value from the child");
+	check(waitpid(pid, &status, 0) == pid, "waitpid");
+
+	check(kill(getpid(), SIGUSR1) == 0, "kill");
+	check(sigwait(&set, &signum) == 0 && signum == SIGUSR1, "sigwait");
+	return 0;
+}
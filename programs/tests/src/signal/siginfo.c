@@ -0,0 +1,123 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <errno.h>
+#include <signal.h>
+#include <sys/wait.h>
+
+#define FAULT_ADDRESS ((volatile int *)0x42)
+
+static volatile siginfo_t last_info;
+static volatile int handled = 0;
+
+static char alt_stack[SIGSTKSZ];
+
+static void check(int cond, const char *msg) {
+	if (!cond) {
+		dprintf(2, "%s (errno: %d)\n", msg, errno);
+		exit(1);
+	}
+}
+
+static void info_handler(int signum, siginfo_t *info, void *ucontext) {
+	(void)ucontext;
+	last_info = *info;
+	handled = signum;
+}
+
+static void segv_handler(int signum, siginfo_t *info, void *ucontext) {
+	stack_t current;
+	int local;
+
+	(void)ucontext;
+	if (signum != SIGSEGV || info->si_code != SEGV_MAPERR
+		|| info->si_addr != (void *)FAULT_ADDRESS) {
+		_exit(2);
+	}
+	// The handler must run on the alternate stack, which cannot be modified now
+	if ((char *)&local < alt_stack || (char *)&local >= alt_stack + sizeof(alt_stack)) {
+		_exit(3);
+	}
+	if (sigaltstack(NULL, &current) != 0 || !(current.ss_flags & SS_ONSTACK)) {
+		_exit(4);
+	}
+	current.ss_flags = SS_DISABLE;
+	if (sigaltstack(&current, NULL) != -1 || errno != EPERM) {
+		_exit(5);
+	}
+	_exit(0);
+}
+
+static void set_handler(int signum, void (*handler)(int, siginfo_t *, void *), int flags) {
+	struct sigaction act;
+
+	act.sa_sigaction = handler;
+	sigemptyset(&act.sa_mask);
+	act.sa_flags = SA_SIGINFO | flags;
+	check(sigaction(signum, &act, NULL) == 0, "sigaction");
+}
+
+int main() {
+	sigset_t mask, oldmask;
+	stack_t ss;
+	int status;
+	pid_t pid;
+
+	// kill() fills the sender informations
+	set_handler(SIGUSR1, info_handler, 0);
+	check(kill(getpid(), SIGUSR1) == 0, "kill");
+	check(handled == SIGUSR1, "SIGUSR1 not handled");
+	check(last_info.si_signo == SIGUSR1, "bad si_signo");
+	check(last_info.si_code == SI_USER, "bad si_code for kill");
+	check(last_info.si_pid == getpid(), "bad si_pid for kill");
+	check(last_info.si_uid == getuid(), "bad si_uid for kill");
+
+	// SIGCHLD gives the exit status of the child
+	handled = 0;
+	set_handler(SIGCHLD, info_handler, 0);
+	sigemptyset(&mask);
+	sigaddset(&mask, SIGCHLD);
+	check(sigprocmask(SIG_BLOCK, &mask, &oldmask) == 0, "sigprocmask");
+	pid = fork();
+	check(pid != -1, "fork");
+	if (pid == 0) {
+		exit(42);
+	}
+	while (handled != SIGCHLD) {
+		sigsuspend(&oldmask);
+	}
+	check(sigprocmask(SIG_SETMASK, &oldmask, NULL) == 0, "sigprocmask");
+	check(last_info.si_code == CLD_EXITED, "bad si_code for SIGCHLD");
+	check(last_info.si_pid == pid, "bad si_pid for SIGCHLD");
+	check(last_info.si_status == 42, "bad si_status for SIGCHLD");
+	check(waitpid(pid, &status, 0) == pid, "waitpid");
+
+	// sigaltstack() arguments
+	check(sigaltstack(NULL, &ss) == 0, "sigaltstack get");
+	check(ss.ss_flags == SS_DISABLE, "alternate stack should be disabled");
+	ss.ss_sp = alt_stack;
+	ss.ss_size = MINSIGSTKSZ - 1;
+	ss.ss_flags = 0;
+	check(sigaltstack(&ss, NULL) == -1 && errno == ENOMEM, "small stack should give ENOMEM");
+	ss.ss_size = sizeof(alt_stack);
+	ss.ss_flags = 42;
+	check(sigaltstack(&ss, NULL) == -1 && errno == EINVAL, "bad flags should give EINVAL");
+	ss.ss_flags = 0;
+	check(sigaltstack(&ss, NULL) == 0, "sigaltstack set");
+	check(sigaltstack(NULL, &ss) == 0, "sigaltstack get");
+	check(ss.ss_sp == alt_stack && ss.ss_size == sizeof(alt_stack) && ss.ss_flags == 0,
+		  "bad alternate stack");
+
+	// SIGSEGV gives the fault address and is handled on the alternate stack
+	pid = fork();
+	check(pid != -1, "fork");
+	if (pid == 0) {
+		set_handler(SIGSEGV, segv_handler, SA_ONSTACK);
+		*FAULT_ADDRESS = 42;
+		_exit(6);
+	}
+	check(waitpid(pid, &status, 0) == pid, "waitpid");
+	check(WIFEXITED(status), "the SIGSEGV handler did not exit");
+	check(WEXITSTATUS(status) == 0, "bad SIGSEGV handling");
+	return 0;
+}
@@ -0,0 +1,117 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <fcntl.h>
+#include <termios.h>
+
+static int master;
+static int slave;
+
+static void set_attr(tcflag_t iflag, tcflag_t oflag, tcflag_t lflag, cc_t vmin, cc_t vtime) {
+	struct termios termios;
+
+	if (tcgetattr(slave, &termios) == -1) {
+		perror("tcgetattr");
+		exit(1);
+	}
+	termios.c_iflag = iflag;
+	termios.c_oflag = oflag;
+	termios.c_lflag = lflag;
+	termios.c_cc[VMIN] = vmin;
+	termios.c_cc[VTIME] = vtime;
+	if (tcsetattr(slave, TCSANOW, &termios) == -1) {
+		perror("tcsetattr");
+		exit(1);
+	}
+}
+
+static void expect_read(int fd, size_t count, const char *expected, int expected_len, const char *msg) {
+	char buf[256];
+
+	int ret = read(fd, buf, count);
+	if (ret != expected_len || memcmp(buf, expected, expected_len) != 0) {
+		dprintf(2, "%s: read returned %d\n", msg, ret);
+		exit(1);
+	}
+}
+
+int main() {
+	char binary[256];
+
+	master = posix_openpt(O_RDWR);
+	if (master == -1 || grantpt(master) == -1 || unlockpt(master) == -1) {
+		perror("posix_openpt");
+		exit(1);
+	}
+	slave = open(ptsname(master), O_RDWR);
+	if (slave == -1) {
+		perror("open slave");
+		exit(1);
+	}
+
+	// The default input and output modes translate the new lines
+	struct termios termios;
+	if (tcgetattr(slave, &termios) == -1) {
+		perror("tcgetattr");
+		exit(1);
+	}
+	if (!(termios.c_iflag & ICRNL) || !(termios.c_oflag & OPOST) || !(termios.c_oflag & ONLCR)) {
+		dprintf(2, "ICRNL, OPOST and ONLCR should be set by default\n");
+		exit(1);
+	}
+
+	/* OUTPUT POST-PROCESSING */
+	set_attr(ICRNL, OPOST | ONLCR, ICANON, 1, 0);
+	write(slave, "a\nb", 3);
+	expect_read(master, 256, "a\r\nb", 4, "ONLCR");
+	set_attr(ICRNL, OPOST | OCRNL, ICANON, 1, 0);
+	write(slave, "a\rb", 3);
+	expect_read(master, 256, "a\nb", 3, "OCRNL");
+	// Without OPOST, any byte goes through
+	set_attr(ICRNL, ONLCR, ICANON, 1, 0);
+	for (int i = 0; i < 256; i++) {
+		binary[i] = i;
+	}
+	if (write(slave, binary, 256) != 256) {
+		dprintf(2, "a binary write should be complete\n");
+		exit(1);
+	}
+	expect_read(master, 256, binary, 256, "binary output");
+
+	/* INPUT TRANSLATION */
+	set_attr(ICRNL, 0, ICANON, 1, 0);
+	write(master, "x\r", 2);
+	expect_read(slave, 256, "x\n", 2, "ICRNL");
+	set_attr(IGNCR, 0, ICANON, 1, 0);
+	write(master, "y\r\n", 3);
+	expect_read(slave, 256, "y\n", 2, "IGNCR");
+	set_attr(INLCR, 0, 0, 1, 0);
+	write(master, "z\n", 2);
+	expect_read(slave, 256, "z\r", 2, "INLCR");
+
+	/* NON CANONICAL READ */
+	// MIN = 0, TIME = 0: a read never blocks
+	set_attr(0, 0, 0, 0, 0);
+	expect_read(slave, 256, "", 0, "MIN = 0, TIME = 0");
+	// MIN = 0, TIME > 0: a read returns 0 when the timer expires
+	set_attr(0, 0, 0, 0, 2);
+	expect_read(slave, 256, "", 0, "MIN = 0, TIME > 0 without input");
+	write(master, "abc", 3);
+	expect_read(slave, 256, "abc", 3, "MIN = 0, TIME > 0");
+	// MIN > 0, TIME = 0: a read returns when MIN bytes are received
+	set_attr(0, 0, 0, 3, 0);
+	write(master, "defg", 4);
+	expect_read(slave, 256, "defg", 4, "MIN > 0, TIME = 0");
+	// The read is complete when the buffer is smaller than MIN
+	write(master, "hi", 2);
+	expect_read(slave, 2, "hi", 2, "MIN greater than the buffer");
+	// MIN > 0, TIME > 0: the inter-byte timer expires before MIN bytes
+	set_attr(0, 0, 0, 10, 2);
+	write(master, "jk", 2);
+	expect_read(slave, 256, "jk", 2, "MIN > 0, TIME > 0");
+
+	close(slave);
+	close(master);
+	return 0;
+}
@@ -0,0 +1,56 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <errno.h>
+#include <time.h>
+#include <sys/time.h>
+
+static void check(int cond, const char *msg) {
+	if (!cond) {
+		dprintf(2, "%s (errno: %d)\n", msg, errno);
+		exit(1);
+	}
+}
+
+static long long elapsed_ms(struct timespec *start, struct timespec *end) {
+	return (end->tv_sec - start->tv_sec) * 1000LL + (end->tv_nsec - start->tv_nsec) / 1000000;
+}
+
+int main() {
+	struct timespec start, end, res, request;
+
+	/* CLOCK_GETTIME */
+	check(clock_gettime(CLOCK_REALTIME, &start) == 0, "clock_gettime(CLOCK_REALTIME)");
+	time_t now = time(NULL);
+	check(start.tv_sec >= now - 1 && start.tv_sec <= now + 1, "CLOCK_REALTIME should be the time since the Epoch");
+	check(start.tv_nsec >= 0 && start.tv_nsec < 1000000000, "tv_nsec should be valid");
+	check(clock_gettime(CLOCK_MONOTONIC, &start) == 0, "clock_gettime(CLOCK_MONOTONIC)");
+	check(clock_gettime(CLOCK_MONOTONIC, &end) == 0, "clock_gettime(CLOCK_MONOTONIC)");
+	check(elapsed_ms(&start, &end) >= 0, "CLOCK_MONOTONIC should never go back");
+	check(clock_gettime(CLOCK_PROCESS_CPUTIME_ID, &start) == 0, "clock_gettime(CLOCK_PROCESS_CPUTIME_ID)");
+	check(clock_gettime(42, &start) == -1 && errno == EINVAL, "an unknown clock should fail with EINVAL");
+	check(clock_getres(CLOCK_MONOTONIC, &res) == 0 && res.tv_sec == 0 && res.tv_nsec > 0, "clock_getres");
+
+	/* CLOCK_NANOSLEEP */
+	request.tv_sec = 0;
+	request.tv_nsec = 200000000;
+	clock_gettime(CLOCK_MONOTONIC, &start);
+	check(clock_nanosleep(CLOCK_MONOTONIC, 0, &request, NULL) == 0, "clock_nanosleep()");
+	clock_gettime(CLOCK_MONOTONIC, &end);
+	check(elapsed_ms(&start, &end) >= 150, "clock_nanosleep() should sleep 200ms");
+
+	// An absolute time in the past returns immediately
+	check(clock_nanosleep(CLOCK_MONOTONIC, TIMER_ABSTIME, &start, NULL) == 0, "clock_nanosleep() in the past");
+	request = end;
+	request.tv_sec += 1;
+	check(clock_nanosleep(CLOCK_MONOTONIC, TIMER_ABSTIME, &request, NULL) == 0, "clock_nanosleep(TIMER_ABSTIME)");
+	clock_gettime(CLOCK_MONOTONIC, &start);
+	check(elapsed_ms(&end, &start) >= 950, "clock_nanosleep(TIMER_ABSTIME) should sleep until the requested time");
+
+	// clock_nanosleep() returns the error number
+	request.tv_nsec = 1000000000;
+	check(clock_nanosleep(CLOCK_MONOTONIC, 0, &request, NULL) == EINVAL, "a bad request should fail with EINVAL");
+	request.tv_nsec = 0;
+	check(clock_nanosleep(CLOCK_PROCESS_CPUTIME_ID, 0, &request, NULL) == EINVAL, "sleeping on a CPU-time clock is not supported");
+	return 0;
+}
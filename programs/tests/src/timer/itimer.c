@@ -0,0 +1,88 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <errno.h>
+#include <signal.h>
+#include <time.h>
+#include <sys/time.h>
+#include <sys/wait.h>
+
+static volatile int alarm_count = 0;
+static volatile int vtalarm_count = 0;
+static volatile int prof_count = 0;
+
+static void handler(int signum) {
+	if (signum == SIGALRM) {
+		alarm_count++;
+	} else if (signum == SIGVTALRM) {
+		vtalarm_count++;
+	} else if (signum == SIGPROF) {
+		prof_count++;
+	}
+}
+
+static void check(int cond, const char *msg) {
+	if (!cond) {
+		dprintf(2, "%s (errno: %d)\n", msg, errno);
+		exit(1);
+	}
+}
+
+static void set_timer(int which, long value_usec, long interval_usec) {
+	struct itimerval value = {
+		.it_interval = {interval_usec / 1000000, interval_usec % 1000000},
+		.it_value = {value_usec / 1000000, value_usec % 1000000},
+	};
+	check(setitimer(which, &value, NULL) == 0, "setitimer");
+}
+
+int main() {
+	struct itimerval value;
+
+	signal(SIGALRM, handler);
+	signal(SIGVTALRM, handler);
+	signal(SIGPROF, handler);
+
+	/* ALARM */
+	check(alarm(10) == 0, "alarm() without a pending alarm should return 0");
+	check(alarm(1) == 10, "alarm() should return the remaining seconds");
+	pause();
+	check(alarm_count == 1, "pause() should be interrupted by SIGALRM");
+	check(alarm(0) == 0, "the alarm should be expired");
+
+	/* ITIMER_REAL */
+	set_timer(ITIMER_REAL, 200000, 100000);
+	check(getitimer(ITIMER_REAL, &value) == 0, "getitimer");
+	check(value.it_interval.tv_usec == 100000 && value.it_value.tv_sec == 0 && value.it_value.tv_usec > 0, "getitimer() should get the remaining time");
+	while (alarm_count < 4) {
+		pause();
+	}
+	set_timer(ITIMER_REAL, 0, 0);
+	check(getitimer(ITIMER_REAL, &value) == 0 && value.it_value.tv_sec == 0 && value.it_value.tv_usec == 0, "the timer should be disarmed");
+
+	/* ITIMER_VIRTUAL AND ITIMER_PROF */
+	set_timer(ITIMER_VIRTUAL, 100000, 0);
+	set_timer(ITIMER_PROF, 100000, 0);
+	while (vtalarm_count == 0 || prof_count == 0) {
+		// consume CPU time
+	}
+	check(vtalarm_count == 1 && prof_count == 1, "the one-shot CPU timers should expire once");
+
+	/* ERRORS */
+	check(setitimer(42, &value, NULL) == -1 && errno == EINVAL, "a bad timer should fail with EINVAL");
+	value.it_value.tv_usec = 1000000;
+	check(setitimer(ITIMER_REAL, &value, NULL) == -1 && errno == EINVAL, "a non canonical value should fail with EINVAL");
+
+	/* THE TIMERS ARE NOT INHERITED BY FORK */
+	alarm(10);
+	pid_t pid = fork();
+	check(pid != -1, "fork");
+	if (pid == 0) {
+		check(alarm(0) == 0, "the child should not inherit the alarm");
+		exit(0);
+	}
+	int status;
+	check(waitpid(pid, &status, 0) == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0, "the child failed");
+	alarm(0);
+	return 0;
+}
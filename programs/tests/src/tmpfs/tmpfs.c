@@ -0,0 +1,109 @@
+#include <unistd.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <utime.h>
+#include <sys/stat.h>
+#include <sys/statfs.h>
+#include <sys/mount.h>
+
+static void check(int cond, const char *msg) {
+	if (!cond) {
+		dprintf(2, "%s (errno: %d)\n", msg, errno);
+		exit(1);
+	}
+}
+
+#define DIR "/tmp/tmpfs_test"
+
+int main() {
+	struct statfs fs;
+	struct stat st;
+	struct utimbuf times = {42, 4242};
+	char page[4096];
+	char buf[64];
+	int fd;
+
+	check(statfs("/tmp", &fs) == 0, "statfs /tmp");
+	check(fs.f_type == TMPFS_MAGIC, "/tmp should be a tmpfs");
+	check(mkdir(DIR, 0755) == 0, "mkdir");
+
+	// Write, seek and read back
+	fd = open(DIR "/file", O_RDWR | O_CREAT | O_EXCL, 0644);
+	check(fd != -1, "open O_CREAT");
+	check(write(fd, "hello world", 11) == 11, "write");
+	check(lseek(fd, 0, SEEK_END) == 11, "lseek SEEK_END");
+	check(lseek(fd, 6, SEEK_SET) == 6, "lseek SEEK_SET");
+	check(read(fd, buf, sizeof(buf)) == 5 && memcmp(buf, "world", 5) == 0, "read");
+	check(read(fd, buf, sizeof(buf)) == 0, "read at the end of file");
+
+	// A hole reads as zeros
+	check(lseek(fd, 3 * 4096, SEEK_SET) == 3 * 4096, "lseek beyond the end");
+	check(write(fd, "end", 3) == 3, "write after a hole");
+	check(fstat(fd, &st) == 0 && st.st_size == 3 * 4096 + 3, "the size should include the hole");
+	check(lseek(fd, 4096, SEEK_SET) == 4096, "lseek in the hole");
+	check(read(fd, buf, 8) == 8, "read in the hole");
+	for (int i = 0; i < 8; i++) {
+		check(buf[i] == 0, "the hole should contain zeros");
+	}
+	close(fd);
+
+	// O_TRUNC
+	fd = open(DIR "/file", O_RDWR | O_TRUNC);
+	check(fd != -1, "open O_TRUNC");
+	check(fstat(fd, &st) == 0 && st.st_size == 0, "the file should be truncated");
+	check(write(fd, "abc", 3) == 3, "write after truncate");
+	close(fd);
+
+	// Hard links, renames and symbolic links
+	check(link(DIR "/file", DIR "/link") == 0, "link");
+	check(stat(DIR "/file", &st) == 0 && st.st_nlink == 2, "the file should have 2 links");
+	check(rename(DIR "/link", DIR "/renamed") == 0, "rename");
+	check(access(DIR "/link", F_OK) == -1 && errno == ENOENT, "the old name should not exist");
+	check(unlink(DIR "/file") == 0, "unlink");
+	fd = open(DIR "/renamed", O_RDONLY);
+	check(fd != -1 && read(fd, buf, sizeof(buf)) == 3 && memcmp(buf, "abc", 3) == 0, "read through the other link");
+	close(fd);
+	check(symlink("renamed", DIR "/symlink") == 0, "symlink");
+	check(readlink(DIR "/symlink", buf, sizeof(buf)) == 7 && memcmp(buf, "renamed", 7) == 0, "readlink");
+	check(stat(DIR "/symlink", &st) == 0 && st.st_size == 3, "stat through the symlink");
+
+	// Metadata
+	check(chmod(DIR "/renamed", 0600) == 0, "chmod");
+	check(chown(DIR "/renamed", 42, 43) == 0, "chown");
+	check(utime(DIR "/renamed", &times) == 0, "utime");
+	check(stat(DIR "/renamed", &st) == 0, "stat");
+	check((st.st_mode & 0777) == 0600 && S_ISREG(st.st_mode), "bad mode");
+	check(st.st_uid == 42 && st.st_gid == 43, "bad owner");
+	check(st.st_atime == 42 && st.st_mtime == 4242, "bad times");
+
+	// Directories
+	check(mkdir(DIR "/dir", 0755) == 0, "mkdir in tmpfs");
+	check(rename(DIR "/renamed", DIR "/dir/file") == 0, "rename in another directory");
+	check(rmdir(DIR "/dir") == -1 && errno == ENOTEMPTY, "rmdir should fail with ENOTEMPTY");
+	check(unlink(DIR "/dir/file") == 0, "unlink in the directory");
+	check(rmdir(DIR "/dir") == 0, "rmdir");
+	check(unlink(DIR "/symlink") == 0, "unlink the symlink");
+
+	// The size limit of a new tmpfs
+	check(mkdir(DIR "/small", 0755) == 0, "mkdir mount point");
+	check(mount("tmpfs", DIR "/small", "tmpfs", 0, "size=8k,mode=700") == 0, "mount tmpfs");
+	check(stat(DIR "/small", &st) == 0 && (st.st_mode & 07777) == 0700, "bad mode of the tmpfs root");
+	check(statfs(DIR "/small", &fs) == 0 && fs.f_blocks == 2, "bad size of the tmpfs");
+	fd = open(DIR "/small/file", O_WRONLY | O_CREAT, 0644);
+	check(fd != -1, "open in the new tmpfs");
+	memset(page, 'a', sizeof(page));
+	check(write(fd, page, sizeof(page)) == sizeof(page), "write the first page");
+	check(write(fd, page, sizeof(page)) == sizeof(page), "write the second page");
+	check(write(fd, page, sizeof(page)) == -1 && errno == ENOSPC, "write should fail with ENOSPC");
+	close(fd);
+	check(unlink(DIR "/small/file") == 0, "unlink in the new tmpfs");
+	check(statfs(DIR "/small", &fs) == 0 && fs.f_bfree == 2, "the pages should be freed");
+	check(mount("tmpfs", DIR "/small", "nofs", 0, NULL) == -1 && errno == ENODEV, "mount should fail with ENODEV");
+	check(umount(DIR "/small") == 0, "umount");
+	check(rmdir(DIR "/small") == 0, "rmdir mount point");
+	check(rmdir(DIR) == 0, "rmdir test directory");
+	return 0;
+}
@@ -8,9 +8,9 @@ use core::{fmt, fmt::Display};
 /// (0 ≤ r, g, b ≤ 5)
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct AnsiRGB {
-    r: u8,
-    g: u8,
-    b: u8,
+    pub(crate) r: u8,
+    pub(crate) g: u8,
+    pub(crate) b: u8,
 }
 
 /// minimal set of colors for VGA compatibility
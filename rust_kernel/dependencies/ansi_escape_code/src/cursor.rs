@@ -26,10 +26,13 @@ pub enum CursorMove {
     ///No special effect
     Backward(usize),
 
-    ///Moves the cursor to column n
+    ///Moves the cursor to column n (0-based, the escape sequence is 1-based)
     HorizontalAbsolute(usize),
 
-    ///Moves the cursor to row n, column m. The values are 1-based, and default to 1 (top left corner) if omitted. A sequence such as CSI ;5H is a synonym for CSI 1;5H as well as CSI 17;H is the same as CSI 17H and CSI 17;1H
+    ///Moves the cursor to row n (0-based, the escape sequence is 1-based)
+    VerticalAbsolute(usize),
+
+    ///Moves the cursor to row n, column m. The values are 0-based, the escape sequence is 1-based and default to 1 (top left corner) if omitted. A sequence such as CSI ;5H is a synonym for CSI 1;5H as well as CSI 17;H is the same as CSI 17H and CSI 17;1H
     Pos(Pos),
 }
 
@@ -41,8 +44,9 @@ impl Display for CursorMove {
             Down(x) => write!(f, "{}{}B", CSI, x),
             Forward(x) => write!(f, "{}{}C", CSI, x),
             Backward(x) => write!(f, "{}{}D", CSI, x),
-            HorizontalAbsolute(x) => write!(f, "{}{}G", CSI, x),
-            Pos(crate::Pos { line, column }) => write!(f, "{}{};{}H", CSI, line, column),
+            HorizontalAbsolute(x) => write!(f, "{}{}G", CSI, x + 1),
+            VerticalAbsolute(x) => write!(f, "{}{}d", CSI, x + 1),
+            Pos(crate::Pos { line, column }) => write!(f, "{}{};{}H", CSI, line + 1, column + 1),
         }
     }
 }
@@ -77,7 +81,10 @@ impl FromStr for CursorMove {
                     .get_substr(off + 1..s.len() - 1)?
                     .parse()
                     .map_err(|_e| ParseCursorError)?;
-                Ok(Pos(crate::Pos { line, column }))
+                Ok(Pos(crate::Pos {
+                    line: line.saturating_sub(1),
+                    column: column.saturating_sub(1),
+                }))
             }),
             _ => {
                 let nb: usize = s
@@ -89,7 +96,8 @@ impl FromStr for CursorMove {
                     "B" => Down(nb),
                     "C" => Forward(nb),
                     "D" => Backward(nb),
-                    "G" => HorizontalAbsolute(nb),
+                    "G" => HorizontalAbsolute(nb.saturating_sub(1)),
+                    "d" => VerticalAbsolute(nb.saturating_sub(1)),
                     _ => Err(ParseCursorError)?,
                 })
             }
@@ -117,6 +125,7 @@ mod test {
             Forward(84),
             Backward(128),
             HorizontalAbsolute(16),
+            VerticalAbsolute(0),
         ];
 
         for cursor in cursors.iter() {
@@ -7,6 +7,12 @@ pub use color::{AnsiColor, Colored, StandardColor};
 pub mod cursor;
 pub use cursor::{CursorMove, Pos};
 
+pub mod sgr;
+pub use sgr::{parse_sgr, GraphicRendition, Rendition};
+
+pub mod parser;
+pub use parser::{EraseMode, Mode, Parser, Perform};
+
 use core::slice::SliceIndex;
 use core::str::FromStr;
 
@@ -35,6 +41,43 @@ pub enum EscapedCode {
     CursorMove(CursorMove),
     ///Text color
     Color(AnsiColor),
+    ///SGR attribute
+    GraphicRendition(GraphicRendition),
+    ///ED: Erase in display
+    EraseInDisplay(EraseMode),
+    ///EL: Erase in line
+    EraseInLine(EraseMode),
+    ///ECH: Erase characters from the cursor, without moving it
+    EraseCharacters(usize),
+    ///IL: Insert blank lines at the cursor line
+    InsertLines(usize),
+    ///DL: Delete lines from the cursor line
+    DeleteLines(usize),
+    ///ICH: Insert blank characters at the cursor
+    InsertCharacters(usize),
+    ///DCH: Delete characters from the cursor
+    DeleteCharacters(usize),
+    ///SU: Scroll the scroll region up
+    ScrollUp(usize),
+    ///SD: Scroll the scroll region down
+    ScrollDown(usize),
+    ///DECSTBM: Set the scroll region, lines are 0-based and included,
+    ///None for bottom is the last line
+    SetScrollRegion { top: usize, bottom: Option<usize> },
+    ///DECSC: Save the cursor position and the rendition
+    SaveCursor,
+    ///DECRC: Restore the cursor position and the rendition
+    RestoreCursor,
+    ///IND: Move the cursor down, scrolling at the bottom of the region
+    Index,
+    ///RI: Move the cursor up, scrolling at the top of the region
+    ReverseIndex,
+    ///NEL: Carriage return and index
+    NextLine,
+    ///SM and RM: Set or reset a mode
+    SetMode(Mode, bool),
+    ///RIS: Reset to the initial state
+    Reset,
 }
 
 ///Comand Sequence Introducer
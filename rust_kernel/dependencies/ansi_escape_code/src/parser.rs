@@ -0,0 +1,405 @@
+//! A VT100/xterm escape sequences state machine, after the
+//! [DEC ANSI parser](https://vt100.net/emu/dec_ansi_parser)
+use super::cursor::{CursorMove, Pos};
+use super::sgr::parse_sgr;
+use super::EscapedCode;
+
+/// Part of the screen or of the line erased by ED and EL
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EraseMode {
+    /// 0: From the cursor to the end
+    Below,
+    /// 1: From the beginning to the cursor
+    Above,
+    /// 2: Everything
+    All,
+    /// 3: The scrollback history (ED only)
+    SavedLines,
+}
+
+/// Modes set by `CSI n h` and reset by `CSI n l`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Mode {
+    /// 4 (IRM): The printed characters shift the end of the line
+    Insert,
+    /// 20 (LNM): A line feed also does a carriage return
+    LineFeedNewLine,
+    /// ?1 (DECCKM): The cursor keys send application sequences
+    ApplicationCursorKeys,
+    /// ?6 (DECOM): The cursor positions are relative to the scroll region
+    Origin,
+    /// ?7 (DECAWM): Printing in the last column wraps to the next line
+    AutoWrap,
+    /// ?25 (DECTCEM): The cursor is visible
+    CursorVisible,
+    /// ?47 and ?1047: Use the alternate screen buffer
+    AlternateScreen,
+    /// ?1049: Save the cursor and use a cleared alternate screen buffer
+    AlternateScreenSaveCursor,
+}
+
+impl Mode {
+    fn from_param(param: usize, private: bool) -> Option<Self> {
+        use Mode::*;
+        Some(match (private, param) {
+            (false, 4) => Insert,
+            (false, 20) => LineFeedNewLine,
+            (true, 1) => ApplicationCursorKeys,
+            (true, 6) => Origin,
+            (true, 7) => AutoWrap,
+            (true, 25) => CursorVisible,
+            (true, 47) | (true, 1047) => AlternateScreen,
+            (true, 1049) => AlternateScreenSaveCursor,
+            _ => return None,
+        })
+    }
+}
+
+/// The receiver of the parsed characters and escape sequences
+pub trait Perform {
+    /// Display a character
+    fn print(&mut self, c: char);
+    /// Execute a control character (\n, \r, \x08, \t ...)
+    fn execute(&mut self, c: char);
+    /// Apply an escape sequence
+    fn escaped(&mut self, code: EscapedCode);
+}
+
+/// Maximum number of parameters of a control sequence, the next are ignored
+const MAX_PARAMS: usize = 16;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum State {
+    Ground,
+    Escape,
+    /// ESC followed by an intermediate character (character set selection ...)
+    EscapeIntermediate,
+    /// CSI: reading the parameters
+    Csi,
+    /// An invalid control sequence, ignored until its final character
+    CsiIgnore,
+    /// OSC: Operating System Command (window title ...), ignored until BEL or ST
+    Osc,
+}
+
+/// The state machine, which keeps its state between two writes
+#[derive(Copy, Clone, Debug)]
+pub struct Parser {
+    state: State,
+    params: [usize; MAX_PARAMS],
+    nb_params: usize,
+    /// The parameter being read
+    current_param: Option<usize>,
+    /// The control sequence began with a '?'
+    private: bool,
+    /// The control sequence began with an other private marker ('>', '=', '<')
+    unknown_private: bool,
+    intermediate: Option<char>,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser {
+    /// Create a parser in the ground state
+    pub const fn new() -> Self {
+        Self {
+            state: State::Ground,
+            params: [0; MAX_PARAMS],
+            nb_params: 0,
+            current_param: None,
+            private: false,
+            unknown_private: false,
+            intermediate: None,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.nb_params = 0;
+        self.current_param = None;
+        self.private = false;
+        self.unknown_private = false;
+        self.intermediate = None;
+    }
+
+    fn push_param(&mut self) {
+        if self.nb_params < MAX_PARAMS {
+            self.params[self.nb_params] = self.current_param.unwrap_or(0);
+            self.nb_params += 1;
+        }
+        self.current_param = None;
+    }
+
+    /// Get the parameter `i`, 0 or an absent parameter is `default`
+    fn param(&self, i: usize, default: usize) -> usize {
+        match self.params[..self.nb_params].get(i) {
+            None | Some(0) => default,
+            Some(param) => *param,
+        }
+    }
+
+    /// Give a character to the state machine
+    pub fn advance<P: Perform>(&mut self, performer: &mut P, c: char) {
+        match c {
+            // CAN and SUB abort the sequence
+            '\x18' | '\x1a' => {
+                self.state = State::Ground;
+                return;
+            }
+            // ESC begins a new sequence everywhere, it also ends an OSC
+            '\x1b' => {
+                self.clear();
+                self.state = State::Escape;
+                return;
+            }
+            _ => {}
+        }
+        match self.state {
+            State::Osc => {
+                if c == '\x07' {
+                    self.state = State::Ground;
+                }
+            }
+            // The control characters are executed even inside a sequence
+            _ if c < ' ' => performer.execute(c),
+            State::Ground => {
+                if c != '\x7f' {
+                    performer.print(c);
+                }
+            }
+            State::Escape => self.escape_dispatch(performer, c),
+            State::EscapeIntermediate => {
+                if !(' '..='/').contains(&c) {
+                    self.state = State::Ground;
+                }
+            }
+            State::Csi => match c {
+                '0'..='9' => {
+                    let digit = c as usize - '0' as usize;
+                    self.current_param = Some(
+                        self.current_param
+                            .unwrap_or(0)
+                            .saturating_mul(10)
+                            .saturating_add(digit),
+                    );
+                }
+                ';' => self.push_param(),
+                '?' if self.nb_params == 0 && self.current_param.is_none() => self.private = true,
+                '<' | '=' | '>' if self.nb_params == 0 && self.current_param.is_none() => {
+                    self.unknown_private = true
+                }
+                ' '..='/' => self.intermediate = Some(c),
+                '@'..='~' => {
+                    self.push_param();
+                    self.state = State::Ground;
+                    self.csi_dispatch(performer, c);
+                }
+                _ => self.state = State::CsiIgnore,
+            },
+            State::CsiIgnore => {
+                if ('@'..='~').contains(&c) {
+                    self.state = State::Ground;
+                }
+            }
+        }
+    }
+
+    /// Handle the character following an ESC
+    fn escape_dispatch<P: Perform>(&mut self, performer: &mut P, c: char) {
+        use EscapedCode::*;
+        self.state = State::Ground;
+        let code = match c {
+            '[' => {
+                self.state = State::Csi;
+                return;
+            }
+            ']' => {
+                self.state = State::Osc;
+                return;
+            }
+            ' '..='/' => {
+                self.state = State::EscapeIntermediate;
+                return;
+            }
+            '7' => SaveCursor,
+            '8' => RestoreCursor,
+            'D' => Index,
+            'E' => NextLine,
+            'M' => ReverseIndex,
+            'c' => Reset,
+            // Keypad modes, tabulation set, string terminator ...
+            _ => return,
+        };
+        performer.escaped(code);
+    }
+
+    /// Handle a complete control sequence
+    fn csi_dispatch<P: Perform>(&mut self, performer: &mut P, c: char) {
+        use EscapedCode::*;
+        if self.unknown_private {
+            return;
+        }
+        let n = self.param(0, 1);
+        let code = match (self.private, self.intermediate, c) {
+            (false, None, 'A') => CursorMove(self::CursorMove::Up(n)),
+            (false, None, 'B') | (false, None, 'e') => CursorMove(self::CursorMove::Down(n)),
+            (false, None, 'C') | (false, None, 'a') => CursorMove(self::CursorMove::Forward(n)),
+            (false, None, 'D') => CursorMove(self::CursorMove::Backward(n)),
+            (false, None, 'E') | (false, None, 'F') => {
+                performer.escaped(CursorMove(if c == 'E' {
+                    self::CursorMove::Down(n)
+                } else {
+                    self::CursorMove::Up(n)
+                }));
+                CursorMove(self::CursorMove::HorizontalAbsolute(0))
+            }
+            (false, None, 'G') | (false, None, '`') => {
+                CursorMove(self::CursorMove::HorizontalAbsolute(n - 1))
+            }
+            (false, None, 'd') => CursorMove(self::CursorMove::VerticalAbsolute(n - 1)),
+            (false, None, 'H') | (false, None, 'f') => CursorMove(self::CursorMove::Pos(Pos {
+                line: n - 1,
+                column: self.param(1, 1) - 1,
+            })),
+            (_, None, 'J') | (_, None, 'K') => {
+                let mode = match self.param(0, 0) {
+                    0 => EraseMode::Below,
+                    1 => EraseMode::Above,
+                    2 => EraseMode::All,
+                    3 if c == 'J' => EraseMode::SavedLines,
+                    _ => return,
+                };
+                if c == 'J' {
+                    EraseInDisplay(mode)
+                } else {
+                    EraseInLine(mode)
+                }
+            }
+            (false, None, 'X') => EraseCharacters(n),
+            (false, None, 'L') => InsertLines(n),
+            (false, None, 'M') => DeleteLines(n),
+            (false, None, '@') => InsertCharacters(n),
+            (false, None, 'P') => DeleteCharacters(n),
+            (false, None, 'S') => ScrollUp(n),
+            (false, None, 'T') if self.nb_params <= 1 => ScrollDown(n),
+            (false, None, 'r') => SetScrollRegion {
+                top: n - 1,
+                bottom: match self.param(1, 0) {
+                    0 => None,
+                    bottom => Some(bottom - 1),
+                },
+            },
+            (false, None, 's') => SaveCursor,
+            (false, None, 'u') => RestoreCursor,
+            (false, None, 'm') => {
+                parse_sgr(&self.params[..self.nb_params], |sgr| {
+                    performer.escaped(GraphicRendition(sgr))
+                });
+                return;
+            }
+            (private, None, 'h') | (private, None, 'l') => {
+                for i in 0..self.nb_params {
+                    if let Some(mode) = Mode::from_param(self.params[i], private) {
+                        performer.escaped(SetMode(mode, c == 'h'));
+                    }
+                }
+                return;
+            }
+            // Device status reports, soft reset, cursor style ...
+            _ => return,
+        };
+        performer.escaped(code);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sgr::GraphicRendition;
+    use crate::AnsiColor;
+
+    #[derive(Debug, PartialEq)]
+    enum Item {
+        Print(char),
+        Execute(char),
+        Escaped(EscapedCode),
+    }
+
+    impl Perform for Vec<Item> {
+        fn print(&mut self, c: char) {
+            self.push(Item::Print(c));
+        }
+        fn execute(&mut self, c: char) {
+            self.push(Item::Execute(c));
+        }
+        fn escaped(&mut self, code: EscapedCode) {
+            self.push(Item::Escaped(code));
+        }
+    }
+
+    fn parse(parser: &mut Parser, s: &str) -> Vec<Item> {
+        let mut items = Vec::new();
+        for c in s.chars() {
+            parser.advance(&mut items, c);
+        }
+        items
+    }
+
+    #[test]
+    fn test_parser() {
+        use EscapedCode::*;
+        use Item::*;
+        let mut parser = Parser::new();
+        assert_eq!(
+            parse(&mut parser, "a\x1b[2J\x1b[;5H\x1b[1;31mb\r\n"),
+            [
+                Print('a'),
+                Escaped(EraseInDisplay(EraseMode::All)),
+                Escaped(CursorMove(self::CursorMove::Pos(Pos {
+                    line: 0,
+                    column: 4
+                }))),
+                Escaped(GraphicRendition(self::GraphicRendition::Bold)),
+                Escaped(GraphicRendition(self::GraphicRendition::Foreground(
+                    AnsiColor::RED
+                ))),
+                Print('b'),
+                Execute('\r'),
+                Execute('\n'),
+            ]
+        );
+        assert_eq!(
+            parse(&mut parser, "\x1b[?1049h\x1b[?25;7l\x1b[3;20r\x1b7\x1bM"),
+            [
+                Escaped(SetMode(Mode::AlternateScreenSaveCursor, true)),
+                Escaped(SetMode(Mode::CursorVisible, false)),
+                Escaped(SetMode(Mode::AutoWrap, false)),
+                Escaped(SetScrollRegion {
+                    top: 2,
+                    bottom: Some(19)
+                }),
+                Escaped(SaveCursor),
+                Escaped(ReverseIndex),
+            ]
+        );
+        // An OSC (window title) and the unknown sequences are ignored
+        assert_eq!(
+            parse(&mut parser, "\x1b]0;title\x07\x1b[>c\x1b(Bx"),
+            [Print('x')]
+        );
+    }
+
+    #[test]
+    fn test_split_sequence() {
+        use EscapedCode::*;
+        let mut parser = Parser::new();
+        assert_eq!(parse(&mut parser, "\x1b[1"), []);
+        assert_eq!(
+            parse(&mut parser, "2X"),
+            [Item::Escaped(EraseCharacters(12))]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! Select Graphic Rendition: the `CSI n m` escape sequences
+use super::color::{AnsiColor, AnsiRGB};
+
+/// An attribute set by a SGR escape sequence
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GraphicRendition {
+    /// 0: All attributes off
+    Reset,
+    /// 1: Bold or increased intensity
+    Bold,
+    /// 2: Faint or decreased intensity
+    Faint,
+    /// 4: Underline
+    Underline,
+    /// 5: Slow blink
+    Blink,
+    /// 7: Swap foreground and background colors
+    Reverse,
+    /// 8: Not visible characters
+    Hidden,
+    /// 22: Neither bold nor faint
+    NormalIntensity,
+    /// 24: Underline off
+    NoUnderline,
+    /// 25: Blink off
+    NoBlink,
+    /// 27: Reverse off
+    NoReverse,
+    /// 28: Visible characters
+    Visible,
+    /// 30–37, 38;5;n, 38;2;r;g;b, 90–97: Set the foreground color
+    Foreground(AnsiColor),
+    /// 39: Default foreground color
+    DefaultForeground,
+    /// 40–47, 48;5;n, 48;2;r;g;b, 100–107: Set the background color
+    Background(AnsiColor),
+    /// 49: Default background color
+    DefaultBackground,
+}
+
+/// The current graphic rendition of the written characters
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rendition {
+    /// Color of the characters
+    pub foreground: AnsiColor,
+    /// Color of the cells, None is the default background
+    pub background: Option<AnsiColor>,
+    #[allow(missing_docs)]
+    pub bold: bool,
+    #[allow(missing_docs)]
+    pub underline: bool,
+    #[allow(missing_docs)]
+    pub reverse: bool,
+    #[allow(missing_docs)]
+    pub hidden: bool,
+}
+
+impl Default for Rendition {
+    fn default() -> Self {
+        Self {
+            foreground: AnsiColor::default(),
+            background: None,
+            bold: false,
+            underline: false,
+            reverse: false,
+            hidden: false,
+        }
+    }
+}
+
+impl Rendition {
+    /// Apply a SGR attribute
+    pub fn apply(&mut self, sgr: GraphicRendition) {
+        use GraphicRendition::*;
+        match sgr {
+            Reset => *self = Self::default(),
+            Bold => self.bold = true,
+            Faint | NormalIntensity => self.bold = false,
+            Underline => self.underline = true,
+            NoUnderline => self.underline = false,
+            Blink | NoBlink => {}
+            Reverse => self.reverse = true,
+            NoReverse => self.reverse = false,
+            Hidden => self.hidden = true,
+            Visible => self.hidden = false,
+            Foreground(color) => self.foreground = color,
+            DefaultForeground => self.foreground = AnsiColor::default(),
+            Background(color) => self.background = Some(color),
+            DefaultBackground => self.background = None,
+        }
+    }
+}
+
+/// Parse the extended colors `5;n` and `2;r;g;b` following a 38 or a
+/// 48, returns the color and the number of parameters used
+fn parse_extended_color(params: &[usize]) -> Option<(AnsiColor, usize)> {
+    match params.get(0)? {
+        5 => {
+            let n = *params.get(1)?;
+            if n < 256 {
+                Some(((n as u8).into(), 2))
+            } else {
+                None
+            }
+        }
+        2 if params.len() >= 4 => {
+            // The true colors are approximated in the 6x6x6 color cube
+            let cube = |c: usize| (c.min(255) * 6 / 256) as u8;
+            Some((
+                AnsiColor::AnsiRGB(AnsiRGB {
+                    r: cube(params[1]),
+                    g: cube(params[2]),
+                    b: cube(params[3]),
+                }),
+                4,
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Call `f` for each attribute of the parameters of a SGR sequence,
+/// the unknown attributes are ignored
+pub fn parse_sgr<F>(params: &[usize], mut f: F)
+where
+    F: FnMut(GraphicRendition),
+{
+    use GraphicRendition::*;
+    if params.is_empty() {
+        return f(Reset);
+    }
+    let mut i = 0;
+    while i < params.len() {
+        let param = params[i];
+        i += 1;
+        let sgr = match param {
+            0 => Reset,
+            1 => Bold,
+            2 => Faint,
+            4 => Underline,
+            5 | 6 => Blink,
+            7 => Reverse,
+            8 => Hidden,
+            22 => NormalIntensity,
+            24 => NoUnderline,
+            25 => NoBlink,
+            27 => NoReverse,
+            28 => Visible,
+            30..=37 => Foreground(((param - 30) as u8).into()),
+            39 => DefaultForeground,
+            40..=47 => Background(((param - 40) as u8).into()),
+            49 => DefaultBackground,
+            90..=97 => Foreground(AnsiColor::HighIntensity((param - 90) as u8)),
+            100..=107 => Background(AnsiColor::HighIntensity((param - 100) as u8)),
+            38 | 48 => match parse_extended_color(&params[i..]) {
+                Some((color, used)) => {
+                    i += used;
+                    if param == 38 {
+                        Foreground(color)
+                    } else {
+                        Background(color)
+                    }
+                }
+                // The rest of the sequence cannot be understood
+                None => return,
+            },
+            _ => continue,
+        };
+        f(sgr);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_sgr() {
+        use GraphicRendition::*;
+        let mut sgrs = Vec::new();
+        parse_sgr(&[1, 31, 48, 5, 17, 7, 38, 2, 255, 0, 0, 0], |sgr| {
+            sgrs.push(sgr)
+        });
+        assert_eq!(
+            sgrs,
+            [
+                Bold,
+                Foreground(AnsiColor::RED),
+                Background(AnsiColor::from(17u8)),
+                Reverse,
+                Foreground(AnsiColor::AnsiRGB(AnsiRGB { r: 5, g: 0, b: 0 })),
+                Reset,
+            ]
+        );
+
+        let mut rendition = Rendition::default();
+        for sgr in sgrs[..4].iter() {
+            rendition.apply(*sgr);
+        }
+        assert!(rendition.bold && rendition.reverse);
+        assert_eq!(rendition.background, Some(AnsiColor::from(17u8)));
+        rendition.apply(Reset);
+        assert_eq!(rendition, Rendition::default());
+    }
+}
@@ -0,0 +1,173 @@
+//! This file describe the boot sector and the FSInfo sector models
+
+/// The BIOS Parameter Block, at the start of the boot sector
+#[derive(Debug, Copy, Clone, Default)]
+#[repr(packed)]
+pub struct BiosParameterBlock {
+    /// Jump instruction to the boot code
+    /*0 	2 	3*/
+    jump_boot: [u8; 3],
+    /// Name of the system which formatted the volume
+    /*3 	10 	8*/
+    oem_name: [u8; 8],
+    /// Count of bytes per sector: 512, 1024, 2048 or 4096
+    /*11 	12 	2*/
+    pub bytes_per_sector: u16,
+    /// Number of sectors per cluster, a power of two
+    /*13 	13 	1*/
+    pub sectors_per_cluster: u8,
+    /// Number of sectors before the first FAT, including the boot sector
+    /*14 	15 	2*/
+    pub reserved_sectors: u16,
+    /// Number of copies of the FAT
+    /*16 	16 	1*/
+    pub nbr_fats: u8,
+    /// Number of 32 bytes entries of the fixed root directory, 0 on FAT32
+    /*17 	18 	2*/
+    pub root_entries: u16,
+    /// Total number of sectors if it fits, else 0
+    /*19 	20 	2*/
+    total_sectors_16: u16,
+    /// Media descriptor
+    /*21 	21 	1*/
+    media: u8,
+    /// Number of sectors of a FAT on FAT12/16, 0 on FAT32
+    /*22 	23 	2*/
+    fat_size_16: u16,
+    /// Sectors per track, for the BIOS
+    /*24 	25 	2*/
+    sectors_per_track: u16,
+    /// Number of heads, for the BIOS
+    /*26 	27 	2*/
+    nbr_heads: u16,
+    /// Number of sectors before the partition
+    /*28 	31 	4*/
+    hidden_sectors: u32,
+    /// Total number of sectors when total_sectors_16 is 0
+    /*32 	35 	4*/
+    total_sectors_32: u32,
+    // The fields below only exist on FAT32
+    /// Number of sectors of a FAT
+    /*36 	39 	4*/
+    fat_size_32: u32,
+    /// Bits 0-3: the active FAT, bit 7: the FATs are not mirrored, only the active one is used
+    /*40 	41 	2*/
+    pub ext_flags: u16,
+    /// Version of the FAT32 format, 0.0
+    /*42 	43 	2*/
+    pub fs_version: u16,
+    /// First cluster of the root directory
+    /*44 	47 	4*/
+    pub root_cluster: u32,
+    /// Sector of the FSInfo structure
+    /*48 	49 	2*/
+    pub fs_info_sector: u16,
+    /// Sector of the copy of the boot sector
+    /*50 	51 	2*/
+    backup_boot_sector: u16,
+}
+
+/// Offset of the 0x55 0xAA signature in the boot sector
+pub const BOOT_SIGNATURE_OFFSET: u64 = 510;
+pub const BOOT_SIGNATURE: u16 = 0xAA55;
+
+impl BiosParameterBlock {
+    pub fn total_sectors(&self) -> u32 {
+        if self.total_sectors_16 != 0 {
+            self.total_sectors_16 as u32
+        } else {
+            self.total_sectors_32
+        }
+    }
+
+    /// Number of sectors of a FAT
+    pub fn fat_size(&self) -> u32 {
+        if self.fat_size_16 != 0 {
+            self.fat_size_16 as u32
+        } else {
+            self.fat_size_32
+        }
+    }
+
+    /// Number of sectors of the fixed root directory of FAT12/16
+    pub fn root_dir_sectors(&self) -> u32 {
+        let bytes_per_sector = self.bytes_per_sector as u32;
+        (self.root_entries as u32 * 32 + bytes_per_sector - 1) / bytes_per_sector
+    }
+
+    /// Check that the fields used to find the regions of the volume are sane
+    pub fn is_valid(&self) -> bool {
+        let bytes_per_sector = self.bytes_per_sector;
+        let sectors_per_cluster = self.sectors_per_cluster;
+
+        bytes_per_sector.is_power_of_two()
+            && bytes_per_sector >= 512
+            && bytes_per_sector <= 4096
+            && sectors_per_cluster.is_power_of_two()
+            && self.reserved_sectors != 0
+            && self.nbr_fats != 0
+            && self.fat_size() != 0
+            && self.total_sectors() != 0
+    }
+}
+
+/// The variant of the FAT, which only depends on the number of clusters
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FatType {
+    Fat12,
+    Fat16,
+    Fat32,
+}
+
+impl FatType {
+    pub fn from_nbr_clusters(nbr_clusters: u32) -> Self {
+        if nbr_clusters < 4085 {
+            FatType::Fat12
+        } else if nbr_clusters < 65525 {
+            FatType::Fat16
+        } else {
+            FatType::Fat32
+        }
+    }
+
+    /// The smallest value of a FAT entry which marks the end of a cluster chain
+    pub fn end_of_chain(&self) -> u32 {
+        match self {
+            FatType::Fat12 => 0xff8,
+            FatType::Fat16 => 0xfff8,
+            FatType::Fat32 => 0x0fff_fff8,
+        }
+    }
+
+    /// The value written to mark the end of a cluster chain
+    pub fn end_of_chain_mark(&self) -> u32 {
+        match self {
+            FatType::Fat12 => 0xfff,
+            FatType::Fat16 => 0xffff,
+            FatType::Fat32 => 0x0fff_ffff,
+        }
+    }
+}
+
+/// Signature at the start of the FSInfo sector
+pub const FS_INFO_LEAD_SIGNATURE: u32 = 0x4161_5252;
+/// Offset of the FSInfo structure in its sector
+pub const FS_INFO_OFFSET: u64 = 484;
+pub const FS_INFO_SIGNATURE: u32 = 0x6141_7272;
+/// The free count and next free fields are unknown
+pub const FS_INFO_UNKNOWN: u32 = 0xffff_ffff;
+
+/// The hints of the FAT32 FSInfo sector about the free clusters
+#[derive(Debug, Copy, Clone, Default)]
+#[repr(packed)]
+pub struct FsInfo {
+    /// FS_INFO_SIGNATURE
+    /*484 	487 	4*/
+    pub signature: u32,
+    /// Last known number of free clusters, or FS_INFO_UNKNOWN
+    /*488 	491 	4*/
+    pub free_count: u32,
+    /// Cluster from which to look for a free one, or FS_INFO_UNKNOWN
+    /*492 	495 	4*/
+    pub next_free: u32,
+}
@@ -0,0 +1,249 @@
+//! The directories are arrays of 32 bytes entries, in the clusters of their
+//! chain, or in the fixed root directory region of FAT12/16
+
+use super::{FatFilesystem, IoResult, ROOT_INODE};
+use crate::entry::{
+    encode_long_name, exact_short_name, from_bytes, generated_short_name, nbr_long_entries,
+};
+use crate::entry::{LongNameEntry, LongNameParser, ShortEntry};
+use crate::entry::{DELETED_ENTRY, END_OF_DIRECTORY, ENTRY_SIZE};
+use alloc::string::String;
+use alloc::vec::Vec;
+use fallible_collections::{try_vec, tryformat, FallibleVec};
+use libc_binding::Errno;
+
+/// The raw content of a directory slot
+type Slot = [u8; ENTRY_SIZE as usize];
+
+/// A directory has at most 65536 entries
+const MAX_DIRECTORY_ENTRIES: usize = 65536;
+
+/// A file found in a directory
+#[derive(Debug)]
+pub struct DirEntry {
+    /// The long name if there is one, else the short name
+    pub name: String,
+    pub entry: ShortEntry,
+    /// The disk offsets of the long name entries then of the short entry
+    pub slots: Vec<u64>,
+}
+
+impl DirEntry {
+    /// The disk offset of the short entry
+    pub fn location(&self) -> u64 {
+        *self
+            .slots
+            .last()
+            .expect("a directory entry has a short entry")
+    }
+}
+
+/// Compare two names as FAT does, without taking care of the case
+fn same_name(a: &str, b: &str) -> bool {
+    a.chars()
+        .flat_map(char::to_uppercase)
+        .eq(b.chars().flat_map(char::to_uppercase))
+}
+
+impl FatFilesystem {
+    /// The disk regions (offset, len) which contain the entries of the directory `inode_nbr`
+    fn directory_regions(&mut self, inode_nbr: u32) -> IoResult<Vec<(u64, u64)>> {
+        let entry = self.get_node(inode_nbr)?.entry;
+        if !entry.is_directory() {
+            return Err(Errno::ENOTDIR);
+        }
+        let mut regions = Vec::new();
+        if inode_nbr == ROOT_INODE && entry.first_cluster() == 0 {
+            regions.try_push(self.root_dir)?;
+        } else {
+            for cluster in self.chain(entry.first_cluster())? {
+                regions.try_push((self.cluster_offset(cluster), self.cluster_size as u64))?;
+            }
+        }
+        Ok(regions)
+    }
+
+    /// All the slots of the directory `inode_nbr` with their disk offset
+    fn read_slots(&mut self, inode_nbr: u32) -> IoResult<Vec<(u64, Slot)>> {
+        let mut slots = Vec::new();
+        for (offset, len) in self.directory_regions(inode_nbr)? {
+            let mut buf = try_vec![0; len as usize]?;
+            self.disk.read_exact(offset, &mut buf)?;
+            for (i, chunk) in buf.chunks_exact(ENTRY_SIZE as usize).enumerate() {
+                let mut slot = [0; ENTRY_SIZE as usize];
+                slot.copy_from_slice(chunk);
+                if slot[0] == END_OF_DIRECTORY {
+                    return Ok(slots);
+                }
+                slots.try_push((offset + i as u64 * ENTRY_SIZE, slot))?;
+            }
+        }
+        Ok(slots)
+    }
+
+    /// Parse the files of the directory `inode_nbr`, including its '.' and '..' entries
+    pub(crate) fn read_directory(&mut self, inode_nbr: u32) -> IoResult<Vec<DirEntry>> {
+        let mut entries = Vec::new();
+        let mut parser = LongNameParser::default();
+        // The offsets of the long name entries since the last short entry
+        let mut long_slots = Vec::new();
+
+        for (offset, slot) in self.read_slots(inode_nbr)? {
+            if slot[0] == DELETED_ENTRY {
+                parser.reset();
+                long_slots.clear();
+                continue;
+            }
+            let entry: ShortEntry = from_bytes(&slot);
+            if entry.is_long_name() {
+                parser.push(&from_bytes::<LongNameEntry>(&slot));
+                long_slots.try_push(offset)?;
+                continue;
+            }
+            if entry.is_volume_label() {
+                parser.reset();
+                long_slots.clear();
+                continue;
+            }
+            let mut slots = Vec::new();
+            let name = match parser.take(&entry) {
+                Some(name) => {
+                    let nbr_long = nbr_long_entries(name.encode_utf16().count());
+                    let start = long_slots.len().saturating_sub(nbr_long);
+                    slots.try_extend_from_slice(&long_slots[start..])?;
+                    name
+                }
+                None => entry.get_filename(),
+            };
+            long_slots.clear();
+            slots.try_push(offset)?;
+            entries.try_push(DirEntry { name, entry, slots })?;
+        }
+        Ok(entries)
+    }
+
+    /// Find the file `name` in the directory `dir_inode_nbr`, the case
+    /// of the name only matters when several files match
+    pub(crate) fn find_entry(&mut self, dir_inode_nbr: u32, name: &str) -> IoResult<DirEntry> {
+        let mut entries = self.read_directory(dir_inode_nbr)?;
+        entries.retain(|dir_entry| !dir_entry.entry.is_dot());
+        let index = entries
+            .iter()
+            .position(|dir_entry| dir_entry.name == name)
+            .or_else(|| {
+                entries
+                    .iter()
+                    .position(|dir_entry| same_name(&dir_entry.name, name))
+            })
+            .ok_or(Errno::ENOENT)?;
+        Ok(entries.swap_remove(index))
+    }
+
+    /// Add the file `name` with the short entry `entry` in the directory `dir_inode_nbr`,
+    /// the directory grows when there is no room for its long name and short entries
+    pub(crate) fn add_entry(
+        &mut self,
+        dir_inode_nbr: u32,
+        name: &str,
+        mut entry: ShortEntry,
+    ) -> IoResult<DirEntry> {
+        let chars = encode_long_name(name)?;
+        let existing = self.read_directory(dir_inode_nbr)?;
+        if existing
+            .iter()
+            .any(|dir_entry| !dir_entry.entry.is_dot() && same_name(&dir_entry.name, name))
+        {
+            return Err(Errno::EEXIST);
+        }
+        let short_name_used = |short_name: &[u8; 11]| {
+            existing
+                .iter()
+                .any(|dir_entry| &dir_entry.entry.name == short_name)
+        };
+
+        // A long name is only written when the name does not fit in an 8.3 name
+        let nbr_long = match exact_short_name(name) {
+            Some((short_name, case)) if !short_name_used(&short_name) => {
+                entry.set_short_name((short_name, case));
+                0
+            }
+            _ => {
+                let short_name = (1..MAX_DIRECTORY_ENTRIES)
+                    .map(|n| generated_short_name(name, n))
+                    .find(|short_name| !short_name_used(short_name))
+                    .ok_or(Errno::ENOSPC)?;
+                entry.set_short_name((short_name, 0));
+                nbr_long_entries(chars.len())
+            }
+        };
+        drop(existing);
+
+        let slots = self.find_free_slots(dir_inode_nbr, nbr_long + 1)?;
+        let checksum = entry.checksum();
+        for (i, &offset) in slots[..nbr_long].iter().enumerate() {
+            let long_entry = LongNameEntry::new(&chars, nbr_long - i, checksum);
+            self.disk.write_struct(offset, &long_entry)?;
+        }
+        self.disk.write_struct(slots[nbr_long], &entry)?;
+        let len = name.len();
+        Ok(DirEntry {
+            name: tryformat!(len, "{}", name)?,
+            entry,
+            slots,
+        })
+    }
+
+    /// Find `count` consecutive free slots in the directory `dir_inode_nbr`,
+    /// allocating new clusters for it when needed
+    fn find_free_slots(&mut self, dir_inode_nbr: u32, count: usize) -> IoResult<Vec<u64>> {
+        let mut free = Vec::new();
+        let mut last_cluster = None;
+        let mut nbr_slots = 0;
+        for (offset, len) in self.directory_regions(dir_inode_nbr)? {
+            let mut buf = try_vec![0; len as usize]?;
+            self.disk.read_exact(offset, &mut buf)?;
+            for (i, chunk) in buf.chunks_exact(ENTRY_SIZE as usize).enumerate() {
+                if chunk[0] == DELETED_ENTRY || chunk[0] == END_OF_DIRECTORY {
+                    free.try_push(offset + i as u64 * ENTRY_SIZE)?;
+                    if free.len() == count {
+                        return Ok(free);
+                    }
+                } else {
+                    free.clear();
+                }
+                nbr_slots += 1;
+            }
+            if offset >= self.data_offset {
+                last_cluster =
+                    Some(((offset - self.data_offset) / self.cluster_size as u64) as u32 + 2);
+            }
+        }
+        // The fixed root directory of FAT12/16 cannot grow
+        let mut last_cluster = last_cluster.ok_or(Errno::ENOSPC)?;
+        let slots_per_cluster = (self.cluster_size as u64 / ENTRY_SIZE) as usize;
+        while free.len() < count {
+            if nbr_slots + slots_per_cluster > MAX_DIRECTORY_ENTRIES {
+                return Err(Errno::ENOSPC);
+            }
+            let cluster = self.allocate_cluster(Some(last_cluster))?;
+            self.zero_cluster(cluster)?;
+            let offset = self.cluster_offset(cluster);
+            for i in 0..slots_per_cluster as u64 {
+                free.try_push(offset + i * ENTRY_SIZE)?;
+            }
+            nbr_slots += slots_per_cluster;
+            last_cluster = cluster;
+        }
+        self.write_fs_info()?;
+        free.truncate(count);
+        Ok(free)
+    }
+
+    /// Mark the slots of a file as deleted
+    pub(crate) fn remove_slots(&mut self, slots: &[u64]) -> IoResult<()> {
+        for &offset in slots {
+            self.disk.write_struct(offset, &DELETED_ENTRY)?;
+        }
+        Ok(())
+    }
+}
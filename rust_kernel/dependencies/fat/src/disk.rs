@@ -0,0 +1,57 @@
+use crate::IoResult;
+use alloc::boxed::Box;
+use core::mem::size_of;
+use ext2::DiskIo;
+use libc_binding::Errno;
+
+/// The device of the filesystem, its offsets are relative to the boot sector
+#[derive(Debug)]
+pub struct Disk(pub Box<dyn DiskIo>);
+
+impl Disk {
+    pub fn flush(&mut self) -> IoResult<()> {
+        self.0.flush()
+    }
+
+    pub fn read_exact(&mut self, mut offset: u64, mut buf: &mut [u8]) -> IoResult<()> {
+        while !buf.is_empty() {
+            match self.0.read_buffer(offset, buf)? {
+                0 => return Err(Errno::EIO),
+                n => {
+                    offset += n;
+                    let remaining = buf;
+                    buf = &mut remaining[n as usize..];
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write_all(&mut self, mut offset: u64, mut buf: &[u8]) -> IoResult<()> {
+        while !buf.is_empty() {
+            match self.0.write_buffer(offset, buf)? {
+                0 => return Err(Errno::EIO),
+                n => {
+                    offset += n;
+                    buf = &buf[n as usize..];
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a particulary struct at offset
+    pub fn write_struct<T: Copy>(&mut self, offset: u64, t: &T) -> IoResult<()> {
+        let s = unsafe { core::slice::from_raw_parts(t as *const T as *const u8, size_of::<T>()) };
+        self.write_all(offset, s)
+    }
+
+    /// Read a particulary struct at offset
+    pub fn read_struct<T: Copy + Default>(&mut self, offset: u64) -> IoResult<T> {
+        let mut t = T::default();
+        let s =
+            unsafe { core::slice::from_raw_parts_mut(&mut t as *mut T as *mut u8, size_of::<T>()) };
+        self.read_exact(offset, s)?;
+        Ok(t)
+    }
+}
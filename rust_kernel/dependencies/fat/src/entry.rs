@@ -0,0 +1,454 @@
+//! This file describe the 32 bytes directory entries: the short entries, which
+//! hold an 8.3 name and the location of the file, and the long name entries of VFAT
+//! which precede them
+
+use crate::time;
+use crate::IoResult;
+use alloc::string::String;
+use alloc::vec::Vec;
+use bitflags::bitflags;
+use core::char;
+use fallible_collections::FallibleVec;
+use libc_binding::Errno;
+
+/// The size of a directory entry
+pub const ENTRY_SIZE: u64 = 32;
+
+/// The maximum length of a long name, in UTF-16 code units
+pub const MAX_NAME_LEN: usize = 255;
+
+/// First byte of the name of a deleted entry
+pub const DELETED_ENTRY: u8 = 0xe5;
+/// First byte of the name of the entry after the last one
+pub const END_OF_DIRECTORY: u8 = 0;
+
+bitflags! {
+    #[derive(Default)]
+    pub struct Attributes: u8 {
+        const READ_ONLY = 0x01;
+        const HIDDEN = 0x02;
+        const SYSTEM = 0x04;
+        const VOLUME_ID = 0x08;
+        const DIRECTORY = 0x10;
+        /// The file changed since the last backup
+        const ARCHIVE = 0x20;
+        /// The attributes of a long name entry
+        const LONG_NAME = Self::READ_ONLY.bits | Self::HIDDEN.bits | Self::SYSTEM.bits | Self::VOLUME_ID.bits;
+    }
+}
+
+/// The base of the 8.3 name is in lower case
+const LOWER_CASE_BASE: u8 = 0x08;
+/// The extension of the 8.3 name is in lower case
+const LOWER_CASE_EXTENSION: u8 = 0x10;
+
+/// A short directory entry
+#[derive(Debug, Copy, Clone, Default)]
+#[repr(C)]
+pub struct ShortEntry {
+    /// 8.3 name, padded with spaces, without the dot
+    /*0 	10 	11*/
+    pub name: [u8; 11],
+    /*11 	11 	1*/
+    pub attributes: Attributes,
+    /// Case of the name, see LOWER_CASE_BASE and LOWER_CASE_EXTENSION
+    /*12 	12 	1*/
+    pub nt_reserved: u8,
+    /// Hundredths of second of the creation time, from 0 to 199
+    /*13 	13 	1*/
+    creation_time_tenth: u8,
+    /*14 	15 	2*/
+    creation_time: u16,
+    /*16 	17 	2*/
+    creation_date: u16,
+    /*18 	19 	2*/
+    access_date: u16,
+    /// High word of the first cluster, 0 on FAT12/16
+    /*20 	21 	2*/
+    first_cluster_high: u16,
+    /*22 	23 	2*/
+    modification_time: u16,
+    /*24 	25 	2*/
+    modification_date: u16,
+    /*26 	27 	2*/
+    first_cluster_low: u16,
+    /// Size of the file in bytes, 0 for a directory
+    /*28 	31 	4*/
+    pub size: u32,
+}
+
+/// A long name directory entry, which holds 13 UTF-16 code units of a long name.
+/// They are stored in reverse order before the short entry of their file
+#[derive(Debug, Copy, Clone, Default)]
+#[repr(C)]
+pub struct LongNameEntry {
+    /// Position of the entry in the long name, starting from 1, LAST_LONG_ENTRY for the last one
+    /*0 	0 	1*/
+    order: u8,
+    /*1 	10 	10*/
+    name1: [u8; 10],
+    /// Attributes::LONG_NAME
+    /*11 	11 	1*/
+    attributes: Attributes,
+    /// 0
+    /*12 	12 	1*/
+    kind: u8,
+    /// Checksum of the name of the short entry
+    /*13 	13 	1*/
+    checksum: u8,
+    /*14 	25 	12*/
+    name2: [u8; 12],
+    /// 0
+    /*26 	27 	2*/
+    first_cluster_low: u16,
+    /*28 	31 	4*/
+    name3: [u8; 4],
+}
+
+/// Flag of the order of the last long name entry, which is the first one on disk
+const LAST_LONG_ENTRY: u8 = 0x40;
+/// Number of UTF-16 code units in a long name entry
+const CHARS_PER_LONG_ENTRY: usize = 13;
+
+/// Interpret the 32 bytes of a directory slot as an entry
+pub fn from_bytes<T: Copy>(slot: &[u8; ENTRY_SIZE as usize]) -> T {
+    debug_assert!(core::mem::size_of::<T>() == ENTRY_SIZE as usize);
+    unsafe { core::ptr::read_unaligned(slot.as_ptr() as *const T) }
+}
+
+impl ShortEntry {
+    pub fn new(attributes: Attributes, timestamp: u32) -> Self {
+        let mut entry = Self {
+            attributes,
+            ..Default::default()
+        };
+        let (date, time) = time::from_unix(timestamp);
+        entry.creation_date = date;
+        entry.creation_time = time;
+        entry.touch(timestamp);
+        entry
+    }
+
+    pub fn first_cluster(&self) -> u32 {
+        (self.first_cluster_high as u32) << 16 | self.first_cluster_low as u32
+    }
+
+    pub fn set_first_cluster(&mut self, cluster: u32) {
+        self.first_cluster_high = (cluster >> 16) as u16;
+        self.first_cluster_low = cluster as u16;
+    }
+
+    pub fn is_directory(&self) -> bool {
+        self.attributes.contains(Attributes::DIRECTORY)
+    }
+
+    pub fn is_long_name(&self) -> bool {
+        self.attributes & Attributes::LONG_NAME == Attributes::LONG_NAME
+    }
+
+    pub fn is_volume_label(&self) -> bool {
+        !self.is_long_name() && self.attributes.contains(Attributes::VOLUME_ID)
+    }
+
+    /// Is it the '.' or the '..' entry of a directory
+    pub fn is_dot(&self) -> bool {
+        self.name[0] == b'.'
+    }
+
+    pub fn creation_time(&self) -> u32 {
+        time::to_unix(self.creation_date, self.creation_time)
+    }
+
+    pub fn access_time(&self) -> u32 {
+        time::to_unix(self.access_date, 0)
+    }
+
+    pub fn modification_time(&self) -> u32 {
+        time::to_unix(self.modification_date, self.modification_time)
+    }
+
+    /// The file has been modified at `timestamp`
+    pub fn touch(&mut self, timestamp: u32) {
+        let (date, time) = time::from_unix(timestamp);
+        self.modification_date = date;
+        self.modification_time = time;
+        self.access_date = date;
+        if !self.is_directory() {
+            self.attributes.insert(Attributes::ARCHIVE);
+        }
+    }
+
+    pub fn set_times(&mut self, access_time: u32, modification_time: u32) {
+        let (date, time) = time::from_unix(modification_time);
+        self.modification_date = date;
+        self.modification_time = time;
+        self.access_date = time::from_unix(access_time).0;
+    }
+
+    /// The 8.3 name as it is shown, like "readme.txt" or "AUTOEXEC.BAT"
+    pub fn get_filename(&self) -> String {
+        fn push(name: &mut String, part: &[u8], lower_case: bool) {
+            for &c in part.iter().take_while(|&&c| c != b' ') {
+                let c = if lower_case {
+                    c.to_ascii_lowercase()
+                } else {
+                    c
+                };
+                name.push(c as char);
+            }
+        }
+        let mut base = self.name;
+        // 0xE5 is a valid first character in the KANJI character set
+        if base[0] == 0x05 {
+            base[0] = DELETED_ENTRY;
+        }
+        let mut name = String::new();
+        push(
+            &mut name,
+            &base[..8],
+            self.nt_reserved & LOWER_CASE_BASE != 0,
+        );
+        if self.name[8] != b' ' {
+            name.push('.');
+            push(
+                &mut name,
+                &self.name[8..],
+                self.nt_reserved & LOWER_CASE_EXTENSION != 0,
+            );
+        }
+        name
+    }
+
+    /// Set the 8.3 name, with its case flags
+    pub fn set_short_name(&mut self, (name, case): ([u8; 11], u8)) {
+        self.name = name;
+        self.nt_reserved = case;
+    }
+
+    /// The checksum of the 8.3 name, which binds the long name entries to their short entry
+    pub fn checksum(&self) -> u8 {
+        self.name
+            .iter()
+            .fold(0u8, |sum, &c| (sum >> 1 | (sum & 1) << 7).wrapping_add(c))
+    }
+}
+
+impl LongNameEntry {
+    /// Create the long name entry number `order` (from 1) of `name`
+    pub fn new(name: &[u16], order: usize, checksum: u8) -> Self {
+        let mut entry = Self {
+            order: order as u8,
+            attributes: Attributes::LONG_NAME,
+            checksum,
+            ..Default::default()
+        };
+        let start = (order - 1) * CHARS_PER_LONG_ENTRY;
+        if start + CHARS_PER_LONG_ENTRY >= name.len() {
+            entry.order |= LAST_LONG_ENTRY;
+        }
+        for i in 0..CHARS_PER_LONG_ENTRY {
+            let c = match name.get(start + i) {
+                Some(&c) => c,
+                // The name is null terminated when it does not fill the last entry
+                None if start + i == name.len() => 0,
+                None => 0xffff,
+            };
+            let bytes = c.to_le_bytes();
+            *entry.byte_mut(2 * i) = bytes[0];
+            *entry.byte_mut(2 * i + 1) = bytes[1];
+        }
+        entry
+    }
+
+    /// The byte `index` of the part of the name, which is splitted in three fields
+    fn byte_mut(&mut self, index: usize) -> &mut u8 {
+        match index {
+            0..=9 => &mut self.name1[index],
+            10..=21 => &mut self.name2[index - 10],
+            _ => &mut self.name3[index - 22],
+        }
+    }
+
+    /// The UTF-16 code units of this part of the name
+    fn chars(&self) -> [u16; CHARS_PER_LONG_ENTRY] {
+        let mut entry = *self;
+        let mut chars = [0; CHARS_PER_LONG_ENTRY];
+        for (i, c) in chars.iter_mut().enumerate() {
+            *c = u16::from_le_bytes([*entry.byte_mut(2 * i), *entry.byte_mut(2 * i + 1)]);
+        }
+        chars
+    }
+}
+
+/// The number of long name entries needed for a name of `len` UTF-16 code units
+pub fn nbr_long_entries(len: usize) -> usize {
+    (len + CHARS_PER_LONG_ENTRY - 1) / CHARS_PER_LONG_ENTRY
+}
+
+/// Accumulate the long name entries which precede a short entry
+#[derive(Debug, Default)]
+pub struct LongNameParser {
+    chars: Vec<u16>,
+    /// The order of the next expected entry, 0 when there is no long name in progress
+    next_order: u8,
+    checksum: u8,
+}
+
+impl LongNameParser {
+    pub fn push(&mut self, entry: &LongNameEntry) {
+        let order = entry.order & !LAST_LONG_ENTRY;
+        if entry.order & LAST_LONG_ENTRY != 0 {
+            self.reset();
+            if order == 0 || order as usize > nbr_long_entries(MAX_NAME_LEN) {
+                return;
+            }
+            if self
+                .chars
+                .try_resize(order as usize * CHARS_PER_LONG_ENTRY, 0)
+                .is_err()
+            {
+                return;
+            }
+            self.checksum = entry.checksum;
+        } else if order == 0 || order != self.next_order || entry.checksum != self.checksum {
+            self.reset();
+            return;
+        }
+        let start = (order as usize - 1) * CHARS_PER_LONG_ENTRY;
+        self.chars[start..start + CHARS_PER_LONG_ENTRY].copy_from_slice(&entry.chars());
+        self.next_order = order - 1;
+    }
+
+    /// An entry which is neither a long name nor the short entry breaks the sequence
+    pub fn reset(&mut self) {
+        self.next_order = 0;
+        self.chars = Vec::new();
+    }
+
+    /// Take the long name of the short entry `entry`, if a complete one precedes it
+    pub fn take(&mut self, entry: &ShortEntry) -> Option<String> {
+        let complete = !self.chars.is_empty() && self.next_order == 0;
+        let chars = core::mem::replace(&mut self.chars, Vec::new());
+        if !complete || self.checksum != entry.checksum() {
+            return None;
+        }
+        let len = chars.iter().position(|&c| c == 0).unwrap_or(chars.len());
+        let name: String = char::decode_utf16(chars[..len].iter().cloned())
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect();
+        Some(name)
+    }
+}
+
+/// Is `c` allowed in an 8.3 name
+fn is_short_name_char(c: u8) -> bool {
+    match c {
+        b'A'..=b'Z' | b'0'..=b'9' => true,
+        b'!' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'(' | b')' | b'-' | b'@' | b'^' | b'_'
+        | b'`' | b'{' | b'}' | b'~' => true,
+        _ => false,
+    }
+}
+
+/// Check that `name` can be a long name, and encode it in UTF-16
+pub fn encode_long_name(name: &str) -> IoResult<Vec<u16>> {
+    if name.is_empty() || name == "." || name == ".." {
+        return Err(Errno::EINVAL);
+    }
+    // The trailing dots and spaces are stripped by the other systems
+    if name.ends_with('.') || name.ends_with(' ') {
+        return Err(Errno::EINVAL);
+    }
+    let mut chars = Vec::new();
+    for c in name.chars() {
+        if (c as u32) < 0x20 || "\"*/:<>?\\|".contains(c) || (c as u32) > 0xffff {
+            return Err(Errno::EINVAL);
+        }
+        chars.try_push(c as u16)?;
+    }
+    if chars.len() > MAX_NAME_LEN {
+        return Err(Errno::ENAMETOOLONG);
+    }
+    Ok(chars)
+}
+
+/// The 8.3 name of `name`, with its case flags, when no long name is needed to store it
+pub fn exact_short_name(name: &str) -> Option<([u8; 11], u8)> {
+    fn part(part: &str, dest: &mut [u8], lower_case_flag: u8) -> Option<u8> {
+        if part.len() > dest.len() {
+            return None;
+        }
+        let has_lower = part.bytes().any(|c| c.is_ascii_lowercase());
+        let has_upper = part.bytes().any(|c| c.is_ascii_uppercase());
+        if has_lower && has_upper {
+            return None;
+        }
+        for (dest, c) in dest.iter_mut().zip(part.bytes()) {
+            *dest = c.to_ascii_uppercase();
+            if !is_short_name_char(*dest) {
+                return None;
+            }
+        }
+        Some(if has_lower { lower_case_flag } else { 0 })
+    }
+    let mut short_name = [b' '; 11];
+    let (base, extension) = match name.find('.') {
+        Some(index) => (&name[..index], &name[index + 1..]),
+        None => (name, ""),
+    };
+    if base.is_empty() || extension.contains('.') {
+        return None;
+    }
+    let case = part(base, &mut short_name[..8], LOWER_CASE_BASE)?
+        | part(extension, &mut short_name[8..], LOWER_CASE_EXTENSION)?;
+    Some((short_name, case))
+}
+
+/// The 8.3 name generated for a long name, with the numeric tail "~n"
+pub fn generated_short_name(name: &str, n: usize) -> [u8; 11] {
+    fn convert(part: &str, dest: &mut [u8]) -> usize {
+        let mut len = 0;
+        for c in part.chars().filter(|&c| c != ' ' && c != '.') {
+            if len == dest.len() {
+                break;
+            }
+            let c = c.to_ascii_uppercase();
+            dest[len] = if c.is_ascii() && is_short_name_char(c as u8) {
+                c as u8
+            } else {
+                b'_'
+            };
+            len += 1;
+        }
+        len
+    }
+    let mut short_name = [b' '; 11];
+    let name = name.trim_start_matches('.');
+    let (base, extension) = match name.rfind('.') {
+        Some(index) => (&name[..index], &name[index + 1..]),
+        None => (name, ""),
+    };
+    let mut base_len = convert(base, &mut short_name[..8]);
+    convert(extension, &mut short_name[8..]);
+    if base_len == 0 {
+        short_name[0] = b'_';
+        base_len = 1;
+    }
+
+    let mut tail = [0; 8];
+    let mut tail_len = 0;
+    let mut n = n;
+    while n != 0 && tail_len < 7 {
+        tail[7 - tail_len] = b'0' + (n % 10) as u8;
+        n /= 10;
+        tail_len += 1;
+    }
+    tail[7 - tail_len] = b'~';
+    tail_len += 1;
+    let start = base_len.min(8 - tail_len);
+    short_name[start..start + tail_len].copy_from_slice(&tail[8 - tail_len..]);
+    for c in &mut short_name[start + tail_len..8] {
+        *c = b' ';
+    }
+    short_name
+}
@@ -0,0 +1,705 @@
+//! this module contains a FAT12/16/32 driver, with the long file names of VFAT
+//! see [osdev](https://wiki.osdev.org/FAT) and the Microsoft FAT specification
+//!
+//! FAT has no inodes: the files are numbered when they are found in their
+//! directory, and these numbers stay valid until the filesystem is dropped
+
+#![cfg_attr(not(test), no_std)]
+// #![deny(missing_docs)]
+
+extern crate alloc;
+
+mod disk;
+use disk::Disk;
+pub use ext2::{DiskIo, IoResult};
+
+mod boot_sector;
+pub use boot_sector::FatType;
+use boot_sector::{BiosParameterBlock, FsInfo};
+use boot_sector::{BOOT_SIGNATURE, BOOT_SIGNATURE_OFFSET};
+use boot_sector::{FS_INFO_LEAD_SIGNATURE, FS_INFO_OFFSET, FS_INFO_SIGNATURE, FS_INFO_UNKNOWN};
+
+mod entry;
+pub use entry::{Attributes, MAX_NAME_LEN};
+use entry::{ShortEntry, ENTRY_SIZE};
+
+mod directory;
+use directory::DirEntry;
+
+mod table;
+mod time;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::min;
+use fallible_collections::{btree::BTreeMap, try_vec, FallibleVec, TryClone};
+use libc_binding::Errno;
+
+/// The inode number of the root directory
+pub const ROOT_INODE: u32 = 1;
+
+/// A file, found in its directory
+#[derive(Debug)]
+struct Node {
+    /// The disk offsets of the long name entries then of the short entry. Empty for
+    /// the root directory, and for a file which has been unlinked while it is still open
+    slots: Vec<u64>,
+    /// A copy of the short entry, written back on each change
+    entry: ShortEntry,
+}
+
+impl Node {
+    /// The disk offset of the short entry
+    fn location(&self) -> Option<u64> {
+        self.slots.last().cloned()
+    }
+}
+
+/// The information about a file, which is made of its short entry
+#[derive(Debug, Copy, Clone)]
+pub struct FileInfo {
+    pub inode_nbr: u32,
+    pub attributes: Attributes,
+    /// The size of a file, the size of a cluster for a directory
+    pub size: u64,
+    pub creation_time: u32,
+    pub access_time: u32,
+    pub modification_time: u32,
+}
+
+impl FileInfo {
+    pub fn is_directory(&self) -> bool {
+        self.attributes.contains(Attributes::DIRECTORY)
+    }
+}
+
+/// Global structure of a FAT filesystem
+#[derive(Debug)]
+pub struct FatFilesystem {
+    disk: Disk,
+    fat_type: FatType,
+    cluster_size: u32,
+    /// Disk offset of the first FAT
+    fat_offset: u64,
+    /// Size of a FAT in bytes
+    fat_size: u64,
+    nbr_fats: u32,
+    /// The only FAT which is used, when they are not mirrored
+    active_fat: Option<u32>,
+    /// Disk offset and size of the fixed root directory of FAT12/16
+    root_dir: (u64, u64),
+    /// Disk offset of the cluster 2, the first one
+    data_offset: u64,
+    nbr_clusters: u32,
+    free_clusters: u32,
+    /// The cluster from which to look for a free one
+    next_free: u32,
+    /// Disk offset of the FSInfo sector of FAT32
+    fs_info_offset: Option<u64>,
+    nodes: BTreeMap<u32, Node>,
+    /// The inode numbers of the files by the disk offset of their short entry
+    locations: BTreeMap<u64, u32>,
+    next_inode: u32,
+}
+
+impl FatFilesystem {
+    /// Invocation of a new FileSystem instance: take a FD and his reader as parameter
+    pub fn new(disk: Box<dyn DiskIo>) -> IoResult<Self> {
+        let mut disk = Disk(disk);
+        let bpb: BiosParameterBlock = disk.read_struct(0)?;
+        let signature: u16 = disk.read_struct(BOOT_SIGNATURE_OFFSET)?;
+        if signature != BOOT_SIGNATURE || !bpb.is_valid() {
+            return Err(Errno::EINVAL);
+        }
+
+        let bytes_per_sector = bpb.bytes_per_sector as u64;
+        let fat_sectors = bpb.nbr_fats as u64 * bpb.fat_size() as u64;
+        let first_data_sector =
+            bpb.reserved_sectors as u64 + fat_sectors + bpb.root_dir_sectors() as u64;
+        let data_sectors = (bpb.total_sectors() as u64)
+            .checked_sub(first_data_sector)
+            .ok_or(Errno::EINVAL)?;
+        let nbr_clusters = (data_sectors / bpb.sectors_per_cluster as u64) as u32;
+        let fat_type = FatType::from_nbr_clusters(nbr_clusters);
+
+        let fat_offset = bpb.reserved_sectors as u64 * bytes_per_sector;
+        let fat_size = bpb.fat_size() as u64 * bytes_per_sector;
+        // The clusters 0 and 1 are reserved, a FAT may be too small for all the clusters
+        let fat_entries = match fat_type {
+            FatType::Fat12 => fat_size * 2 / 3,
+            FatType::Fat16 => fat_size / 2,
+            FatType::Fat32 => fat_size / 4,
+        };
+        let nbr_clusters = min(nbr_clusters as u64, fat_entries.saturating_sub(2)) as u32;
+        if nbr_clusters == 0 {
+            return Err(Errno::EINVAL);
+        }
+
+        let mut fat = Self {
+            fat_type,
+            cluster_size: bpb.sectors_per_cluster as u32 * bpb.bytes_per_sector as u32,
+            fat_offset,
+            fat_size,
+            nbr_fats: bpb.nbr_fats as u32,
+            active_fat: None,
+            root_dir: (
+                fat_offset + fat_sectors * bytes_per_sector,
+                bpb.root_entries as u64 * ENTRY_SIZE,
+            ),
+            data_offset: first_data_sector * bytes_per_sector,
+            nbr_clusters,
+            free_clusters: 0,
+            next_free: 2,
+            fs_info_offset: None,
+            nodes: BTreeMap::new(),
+            locations: BTreeMap::new(),
+            next_inode: ROOT_INODE + 1,
+            disk,
+        };
+
+        let mut root = ShortEntry::new(Attributes::DIRECTORY, 0);
+        if fat_type == FatType::Fat32 {
+            let ext_flags = bpb.ext_flags;
+            if bpb.fs_version != 0 || bpb.root_entries != 0 || !fat.is_valid(bpb.root_cluster) {
+                return Err(Errno::EINVAL);
+            }
+            if ext_flags & 0x80 != 0 {
+                fat.active_fat = Some(ext_flags as u32 & 0xf);
+            }
+            root.set_first_cluster(bpb.root_cluster);
+            fat.read_fs_info(bpb.fs_info_sector as u64 * bytes_per_sector)?;
+        } else {
+            fat.free_clusters = fat.count_free_clusters()?;
+        }
+        fat.nodes.try_insert(
+            ROOT_INODE,
+            Node {
+                slots: Vec::new(),
+                entry: root,
+            },
+        )?;
+        Ok(fat)
+    }
+
+    /// Get the free clusters hints of the FSInfo sector, they are computed
+    /// again when they are not known
+    fn read_fs_info(&mut self, offset: u64) -> IoResult<()> {
+        let lead_signature: u32 = self.disk.read_struct(offset)?;
+        let fs_info: FsInfo = self.disk.read_struct(offset + FS_INFO_OFFSET)?;
+        let (signature, free_count, next_free) =
+            (fs_info.signature, fs_info.free_count, fs_info.next_free);
+
+        if lead_signature != FS_INFO_LEAD_SIGNATURE || signature != FS_INFO_SIGNATURE {
+            self.free_clusters = self.count_free_clusters()?;
+            return Ok(());
+        }
+        self.fs_info_offset = Some(offset);
+        self.free_clusters = if free_count == FS_INFO_UNKNOWN || free_count > self.nbr_clusters {
+            self.count_free_clusters()?
+        } else {
+            free_count
+        };
+        if self.is_valid(next_free) {
+            self.next_free = next_free;
+        }
+        Ok(())
+    }
+
+    /// Write back the free clusters hints to the FSInfo sector
+    fn write_fs_info(&mut self) -> IoResult<()> {
+        if let Some(offset) = self.fs_info_offset {
+            let fs_info = FsInfo {
+                signature: FS_INFO_SIGNATURE,
+                free_count: self.free_clusters,
+                next_free: self.next_free,
+            };
+            self.disk.write_struct(offset + FS_INFO_OFFSET, &fs_info)?;
+        }
+        Ok(())
+    }
+
+    pub fn fat_type(&self) -> FatType {
+        self.fat_type
+    }
+
+    pub fn cluster_size(&self) -> u32 {
+        self.cluster_size
+    }
+
+    pub fn nbr_clusters(&self) -> u32 {
+        self.nbr_clusters
+    }
+
+    pub fn free_clusters(&self) -> u32 {
+        self.free_clusters
+    }
+
+    /// Write back the FSInfo hints and flush the disk
+    pub fn flush(&mut self) -> IoResult<()> {
+        self.write_fs_info()?;
+        self.disk.flush()
+    }
+
+    fn get_node(&self, inode_nbr: u32) -> IoResult<&Node> {
+        self.nodes.get(&inode_nbr).ok_or(Errno::ENOENT)
+    }
+
+    fn get_node_mut(&mut self, inode_nbr: u32) -> IoResult<&mut Node> {
+        self.nodes.get_mut(&inode_nbr).ok_or(Errno::ENOENT)
+    }
+
+    /// Write back the short entry of a node, if it is still in a directory
+    fn save_node(&mut self, inode_nbr: u32) -> IoResult<()> {
+        let node = self.get_node(inode_nbr)?;
+        if let Some(location) = node.location() {
+            let entry = node.entry;
+            self.disk.write_struct(location, &entry)?;
+        }
+        Ok(())
+    }
+
+    /// Get the inode number of a file found in a directory, it is numbered
+    /// the first time it is seen
+    fn node_of(&mut self, dir_entry: DirEntry) -> IoResult<u32> {
+        let location = dir_entry.location();
+        if let Some(&inode_nbr) = self.locations.get(&location) {
+            return Ok(inode_nbr);
+        }
+        let inode_nbr = self.next_inode;
+        self.nodes.try_insert(
+            inode_nbr,
+            Node {
+                slots: dir_entry.slots,
+                entry: dir_entry.entry,
+            },
+        )?;
+        if let Err(e) = self.locations.try_insert(location, inode_nbr) {
+            self.nodes.remove(&inode_nbr);
+            return Err(e.into());
+        }
+        self.next_inode += 1;
+        Ok(inode_nbr)
+    }
+
+    /// Forget a node which is no more in its directory
+    fn detach_node(&mut self, inode_nbr: u32) -> IoResult<()> {
+        let node = self.get_node_mut(inode_nbr)?;
+        let location = node.location();
+        node.slots = Vec::new();
+        if let Some(location) = location {
+            self.locations.remove(&location);
+        }
+        Ok(())
+    }
+
+    pub fn get_info(&self, inode_nbr: u32) -> IoResult<FileInfo> {
+        let entry = &self.get_node(inode_nbr)?.entry;
+        let size = if inode_nbr == ROOT_INODE && self.fat_type != FatType::Fat32 {
+            self.root_dir.1
+        } else if entry.is_directory() {
+            self.cluster_size as u64
+        } else {
+            entry.size as u64
+        };
+        Ok(FileInfo {
+            inode_nbr,
+            attributes: entry.attributes,
+            size,
+            creation_time: entry.creation_time(),
+            access_time: entry.access_time(),
+            modification_time: entry.modification_time(),
+        })
+    }
+
+    /// The content of a directory changed at `timestamp`
+    fn touch_directory(&mut self, inode_nbr: u32, timestamp: u32) -> IoResult<()> {
+        self.get_node_mut(inode_nbr)?.entry.touch(timestamp);
+        self.save_node(inode_nbr)
+    }
+
+    /// return all the files of the directory `inode_nbr`, with their name
+    pub fn lookup_directory(&mut self, inode_nbr: u32) -> IoResult<Vec<(String, FileInfo)>> {
+        let mut files = Vec::new();
+        for dir_entry in self.read_directory(inode_nbr)? {
+            if dir_entry.entry.is_dot() {
+                continue;
+            }
+            let name = dir_entry.name.clone();
+            let child = self.node_of(dir_entry)?;
+            files.try_push((name, self.get_info(child)?))?;
+        }
+        Ok(files)
+    }
+
+    /// Create a new empty file `filename` in the directory `parent_inode_nbr`
+    pub fn create(
+        &mut self,
+        filename: &str,
+        parent_inode_nbr: u32,
+        timestamp: u32,
+    ) -> IoResult<FileInfo> {
+        let entry = ShortEntry::new(Attributes::ARCHIVE, timestamp);
+        let dir_entry = self.add_entry(parent_inode_nbr, filename, entry)?;
+        let inode_nbr = self.node_of(dir_entry)?;
+        self.touch_directory(parent_inode_nbr, timestamp)?;
+        self.get_info(inode_nbr)
+    }
+
+    /// Create the directory `filename` in the directory `parent_inode_nbr`, with its '.' and '..' entries
+    pub fn create_dir(
+        &mut self,
+        parent_inode_nbr: u32,
+        filename: &str,
+        timestamp: u32,
+    ) -> IoResult<FileInfo> {
+        let parent_cluster = if parent_inode_nbr == ROOT_INODE {
+            // '..' points to the cluster 0 for the root, even on FAT32
+            0
+        } else {
+            self.get_node(parent_inode_nbr)?.entry.first_cluster()
+        };
+        let cluster = self.allocate_cluster(None)?;
+        let mut entry = ShortEntry::new(Attributes::DIRECTORY, timestamp);
+        entry.set_first_cluster(cluster);
+
+        let res = self.zero_cluster(cluster).and_then(|_| {
+            let mut dot = entry;
+            dot.set_short_name((*b".          ", 0));
+            let mut dot_dot = entry;
+            dot_dot.set_short_name((*b"..         ", 0));
+            dot_dot.set_first_cluster(parent_cluster);
+            let offset = self.cluster_offset(cluster);
+            self.disk.write_struct(offset, &dot)?;
+            self.disk.write_struct(offset + ENTRY_SIZE, &dot_dot)?;
+            self.add_entry(parent_inode_nbr, filename, entry)
+        });
+        let dir_entry = match res {
+            Ok(dir_entry) => dir_entry,
+            Err(e) => {
+                self.free_chain(cluster)?;
+                return Err(e);
+            }
+        };
+        let inode_nbr = self.node_of(dir_entry)?;
+        self.touch_directory(parent_inode_nbr, timestamp)?;
+        self.write_fs_info()?;
+        self.get_info(inode_nbr)
+    }
+
+    /// Remove the file `name` from the directory `dir_inode_nbr`. Its clusters are
+    /// kept until remove_inode() when `free_data` is false, because it is still open
+    pub fn unlink(
+        &mut self,
+        dir_inode_nbr: u32,
+        name: &str,
+        free_data: bool,
+        timestamp: u32,
+    ) -> IoResult<()> {
+        let dir_entry = self.find_entry(dir_inode_nbr, name)?;
+        if dir_entry.entry.is_directory() {
+            return Err(Errno::EISDIR);
+        }
+        let inode_nbr = self.node_of(dir_entry)?;
+        let slots = self.get_node(inode_nbr)?.slots.try_clone()?;
+        self.remove_slots(&slots)?;
+        self.detach_node(inode_nbr)?;
+        self.touch_directory(dir_inode_nbr, timestamp)?;
+        if free_data {
+            self.remove_inode(inode_nbr)?;
+        }
+        Ok(())
+    }
+
+    /// Free the clusters of a file which has been unlinked
+    pub fn remove_inode(&mut self, inode_nbr: u32) -> IoResult<()> {
+        let node = self.get_node(inode_nbr)?;
+        if node.location().is_some() || inode_nbr == ROOT_INODE {
+            return Err(Errno::EBUSY);
+        }
+        let cluster = node.entry.first_cluster();
+        self.nodes.remove(&inode_nbr);
+        if cluster != 0 {
+            self.free_chain(cluster)?;
+        }
+        self.write_fs_info()
+    }
+
+    /// Remove the empty directory `filename` of the directory `parent_inode_nbr`
+    pub fn rmdir(&mut self, parent_inode_nbr: u32, filename: &str, timestamp: u32) -> IoResult<()> {
+        let dir_entry = self.find_entry(parent_inode_nbr, filename)?;
+        if !dir_entry.entry.is_directory() {
+            return Err(Errno::ENOTDIR);
+        }
+        let inode_nbr = self.node_of(dir_entry)?;
+        if self
+            .read_directory(inode_nbr)?
+            .iter()
+            .any(|dir_entry| !dir_entry.entry.is_dot())
+        {
+            return Err(Errno::ENOTEMPTY);
+        }
+        let slots = self.get_node(inode_nbr)?.slots.try_clone()?;
+        self.remove_slots(&slots)?;
+        self.detach_node(inode_nbr)?;
+        self.remove_inode(inode_nbr)?;
+        self.touch_directory(parent_inode_nbr, timestamp)
+    }
+
+    /// Move the file `filename` of the directory `parent_inode_nbr` to `new_filename`
+    /// in the directory `new_parent_inode_nbr`, which must not exist
+    pub fn rename(
+        &mut self,
+        parent_inode_nbr: u32,
+        filename: &str,
+        new_parent_inode_nbr: u32,
+        new_filename: &str,
+        timestamp: u32,
+    ) -> IoResult<()> {
+        let dir_entry = self.find_entry(parent_inode_nbr, filename)?;
+        let inode_nbr = self.node_of(dir_entry)?;
+        let node = self.get_node(inode_nbr)?;
+        let (slots, entry) = (node.slots.try_clone()?, node.entry);
+
+        // The old entries are removed first, so that only the case of a name can change
+        let mut old_slots = Vec::new();
+        for &offset in slots.iter() {
+            old_slots.try_push(self.disk.read_struct::<[u8; ENTRY_SIZE as usize]>(offset)?)?;
+        }
+        self.remove_slots(&slots)?;
+        let new_entry = match self.add_entry(new_parent_inode_nbr, new_filename, entry) {
+            Ok(new_entry) => new_entry,
+            Err(e) => {
+                for (offset, slot) in slots.iter().zip(old_slots.iter()) {
+                    self.disk.write_struct(*offset, slot)?;
+                }
+                return Err(e);
+            }
+        };
+        self.detach_node(inode_nbr)?;
+        let new_location = new_entry.location();
+        self.locations.try_insert(new_location, inode_nbr)?;
+        let node = self.get_node_mut(inode_nbr)?;
+        node.slots = new_entry.slots;
+        node.entry = new_entry.entry;
+
+        // The '..' entry of a directory points to its parent
+        if entry.is_directory() && parent_inode_nbr != new_parent_inode_nbr {
+            let parent_cluster = if new_parent_inode_nbr == ROOT_INODE {
+                0
+            } else {
+                self.get_node(new_parent_inode_nbr)?.entry.first_cluster()
+            };
+            let offset = self.cluster_offset(entry.first_cluster()) + ENTRY_SIZE;
+            let mut dot_dot: ShortEntry = self.disk.read_struct(offset)?;
+            if &dot_dot.name == b"..         " {
+                dot_dot.set_first_cluster(parent_cluster);
+                self.disk.write_struct(offset, &dot_dot)?;
+            }
+        }
+        self.touch_directory(parent_inode_nbr, timestamp)?;
+        self.touch_directory(new_parent_inode_nbr, timestamp)
+    }
+
+    /// Read the file `inode_nbr` from `offset`, which is advanced
+    pub fn read(&mut self, inode_nbr: u32, offset: &mut u64, buf: &mut [u8]) -> IoResult<u64> {
+        let entry = self.get_node(inode_nbr)?.entry;
+        if entry.is_directory() {
+            return Err(Errno::EISDIR);
+        }
+        let size = entry.size as u64;
+        if *offset >= size || buf.is_empty() {
+            return Ok(0);
+        }
+        let len = min(buf.len() as u64, size - *offset) as usize;
+        let cluster_size = self.cluster_size as u64;
+
+        let mut cluster = self
+            .cluster_at(entry.first_cluster(), *offset / cluster_size)?
+            .ok_or(Errno::EIO)?;
+        let mut read = 0;
+        loop {
+            let in_cluster = (*offset + read as u64) % cluster_size;
+            let count = min((cluster_size - in_cluster) as usize, len - read);
+            let disk_offset = self.cluster_offset(cluster) + in_cluster;
+            self.disk
+                .read_exact(disk_offset, &mut buf[read..read + count])?;
+            read += count;
+            if read == len {
+                break;
+            }
+            cluster = self.next_cluster(cluster)?.ok_or(Errno::EIO)?;
+        }
+        *offset += read as u64;
+        Ok(read as u64)
+    }
+
+    /// Write `buf` in the clusters of the file from `offset`, allocating the missing ones
+    fn write_data(&mut self, inode_nbr: u32, offset: u64, buf: &[u8]) -> IoResult<u64> {
+        let cluster_size = self.cluster_size as u64;
+        let mut cluster = match self.get_node(inode_nbr)?.entry.first_cluster() {
+            0 => {
+                let cluster = self.allocate_cluster(None)?;
+                self.get_node_mut(inode_nbr)?
+                    .entry
+                    .set_first_cluster(cluster);
+                cluster
+            }
+            cluster => cluster,
+        };
+        for _ in 0..offset / cluster_size {
+            cluster = match self.next_cluster(cluster)? {
+                Some(next) => next,
+                None => self.allocate_cluster(Some(cluster))?,
+            };
+        }
+        let mut written = 0;
+        loop {
+            let in_cluster = (offset + written as u64) % cluster_size;
+            let count = min((cluster_size - in_cluster) as usize, buf.len() - written);
+            let disk_offset = self.cluster_offset(cluster) + in_cluster;
+            self.disk
+                .write_all(disk_offset, &buf[written..written + count])?;
+            written += count;
+            if written == buf.len() {
+                return Ok(written as u64);
+            }
+            cluster = match self.next_cluster(cluster)? {
+                Some(next) => next,
+                None => match self.allocate_cluster(Some(cluster)) {
+                    Ok(next) => next,
+                    Err(Errno::ENOSPC) => return Ok(written as u64),
+                    Err(e) => return Err(e),
+                },
+            };
+        }
+    }
+
+    /// Write zeros in the file from `start` to `end`, FAT has no holes
+    fn write_zeros(&mut self, inode_nbr: u32, start: u64, end: u64) -> IoResult<()> {
+        let zeros = try_vec![0; self.cluster_size as usize]?;
+        let mut offset = start;
+        while offset < end {
+            let len = min(zeros.len() as u64, end - offset) as usize;
+            let written = self.write_data(inode_nbr, offset, &zeros[..len])?;
+            offset += written;
+            self.get_node_mut(inode_nbr)?.entry.size = offset as u32;
+            if written != len as u64 {
+                return Err(Errno::ENOSPC);
+            }
+        }
+        Ok(())
+    }
+
+    /// Write `buf` in the file `inode_nbr` from `offset`, which is advanced
+    pub fn write(
+        &mut self,
+        inode_nbr: u32,
+        offset: &mut u64,
+        buf: &[u8],
+        timestamp: u32,
+    ) -> IoResult<(u64, FileInfo)> {
+        let entry = self.get_node(inode_nbr)?.entry;
+        if entry.is_directory() {
+            return Err(Errno::EISDIR);
+        }
+        // The size of a file is a 32 bits field
+        if *offset + buf.len() as u64 > core::u32::MAX as u64 {
+            return Err(Errno::EFBIG);
+        }
+        if buf.is_empty() {
+            return Ok((0, self.get_info(inode_nbr)?));
+        }
+        let size = entry.size as u64;
+        let res = if *offset > size {
+            self.write_zeros(inode_nbr, size, *offset)
+                .and_then(|_| self.write_data(inode_nbr, *offset, buf))
+        } else {
+            self.write_data(inode_nbr, *offset, buf)
+        };
+        let (written, error) = match res {
+            Ok(written) => (written, None),
+            Err(e) => (0, Some(e)),
+        };
+        let node = self.get_node_mut(inode_nbr)?;
+        if written != 0 {
+            node.entry.size = core::cmp::max(node.entry.size as u64, *offset + written) as u32;
+        }
+        node.entry.touch(timestamp);
+        self.save_node(inode_nbr)?;
+        self.write_fs_info()?;
+        if written == 0 {
+            return Err(error.unwrap_or(Errno::ENOSPC));
+        }
+        *offset += written;
+        Ok((written, self.get_info(inode_nbr)?))
+    }
+
+    /// Change the size of the file `inode_nbr`, freeing the clusters which are
+    /// not needed anymore or filling the new part with zeros
+    pub fn truncate(&mut self, inode_nbr: u32, new_size: u64, timestamp: u32) -> IoResult<()> {
+        let entry = self.get_node(inode_nbr)?.entry;
+        if entry.is_directory() {
+            return Err(Errno::EISDIR);
+        }
+        if new_size > core::u32::MAX as u64 {
+            return Err(Errno::EFBIG);
+        }
+        let size = entry.size as u64;
+        let first_cluster = entry.first_cluster();
+        let res = if new_size > size {
+            self.write_zeros(inode_nbr, size, new_size)
+        } else {
+            let cluster_size = self.cluster_size as u64;
+            let nbr_clusters = (new_size + cluster_size - 1) / cluster_size;
+            if nbr_clusters == 0 {
+                self.get_node_mut(inode_nbr)?.entry.set_first_cluster(0);
+                if first_cluster != 0 {
+                    self.free_chain(first_cluster)?;
+                }
+            } else {
+                let last = self
+                    .cluster_at(first_cluster, nbr_clusters - 1)?
+                    .ok_or(Errno::EIO)?;
+                if let Some(next) = self.next_cluster(last)? {
+                    self.set_end_of_chain(last)?;
+                    self.free_chain(next)?;
+                }
+            }
+            self.get_node_mut(inode_nbr)?.entry.size = new_size as u32;
+            Ok(())
+        };
+        self.get_node_mut(inode_nbr)?.entry.touch(timestamp);
+        self.save_node(inode_nbr)?;
+        self.write_fs_info()?;
+        res
+    }
+
+    /// FAT only knows whether a file is writable, with the read-only attribute
+    pub fn set_read_only(&mut self, inode_nbr: u32, read_only: bool) -> IoResult<()> {
+        if inode_nbr == ROOT_INODE {
+            return Err(Errno::EPERM);
+        }
+        self.get_node_mut(inode_nbr)?
+            .entry
+            .attributes
+            .set(Attributes::READ_ONLY, read_only);
+        self.save_node(inode_nbr)
+    }
+
+    /// Change the access and the modification times of the file `inode_nbr`
+    pub fn utime(
+        &mut self,
+        inode_nbr: u32,
+        access_time: u32,
+        modification_time: u32,
+    ) -> IoResult<()> {
+        if inode_nbr == ROOT_INODE {
+            return Err(Errno::EPERM);
+        }
+        self.get_node_mut(inode_nbr)?
+            .entry
+            .set_times(access_time, modification_time);
+        self.save_node(inode_nbr)
+    }
+}
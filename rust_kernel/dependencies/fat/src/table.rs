@@ -0,0 +1,222 @@
+//! The File Allocation Table: one entry per cluster, which is either free (0),
+//! the next cluster of a file, or the end of its cluster chain
+
+use super::{FatFilesystem, FatType, IoResult};
+use alloc::vec::Vec;
+use core::cmp::min;
+use fallible_collections::{try_vec, FallibleVec};
+use libc_binding::Errno;
+
+/// The size of the chunks of the FAT read to count the free clusters
+const FAT_CHUNK_SIZE: usize = 4096;
+
+impl FatFilesystem {
+    /// Is `cluster` a data cluster of the volume
+    pub(crate) fn is_valid(&self, cluster: u32) -> bool {
+        cluster >= 2 && cluster <= self.nbr_clusters + 1
+    }
+
+    /// Disk offset of the first byte of `cluster`
+    pub(crate) fn cluster_offset(&self, cluster: u32) -> u64 {
+        self.data_offset + (cluster as u64 - 2) * self.cluster_size as u64
+    }
+
+    /// Offset of the entry of `cluster` in a FAT
+    fn entry_offset(&self, cluster: u32) -> u64 {
+        match self.fat_type {
+            FatType::Fat12 => cluster as u64 + cluster as u64 / 2,
+            FatType::Fat16 => cluster as u64 * 2,
+            FatType::Fat32 => cluster as u64 * 4,
+        }
+    }
+
+    /// The index of the FAT which is read
+    fn read_fat(&self) -> u32 {
+        self.active_fat.unwrap_or(0)
+    }
+
+    fn fat_entry(&mut self, cluster: u32) -> IoResult<u32> {
+        let offset =
+            self.fat_offset + self.read_fat() as u64 * self.fat_size + self.entry_offset(cluster);
+        Ok(match self.fat_type {
+            FatType::Fat12 => {
+                let value: u16 = self.disk.read_struct(offset)?;
+                if cluster & 1 != 0 {
+                    (value >> 4) as u32
+                } else {
+                    (value & 0xfff) as u32
+                }
+            }
+            FatType::Fat16 => self.disk.read_struct::<u16>(offset)? as u32,
+            FatType::Fat32 => self.disk.read_struct::<u32>(offset)? & 0x0fff_ffff,
+        })
+    }
+
+    /// Set the entry of `cluster` in all the mirrored FATs
+    fn set_fat_entry(&mut self, cluster: u32, value: u32) -> IoResult<()> {
+        let fats = match self.active_fat {
+            Some(active_fat) => active_fat..active_fat + 1,
+            None => 0..self.nbr_fats,
+        };
+        for fat in fats {
+            let offset = self.fat_offset + fat as u64 * self.fat_size + self.entry_offset(cluster);
+            match self.fat_type {
+                FatType::Fat12 => {
+                    let old: u16 = self.disk.read_struct(offset)?;
+                    let new = if cluster & 1 != 0 {
+                        (old & 0x000f) | (value as u16) << 4
+                    } else {
+                        (old & 0xf000) | (value as u16 & 0x0fff)
+                    };
+                    self.disk.write_struct(offset, &new)?;
+                }
+                FatType::Fat16 => self.disk.write_struct(offset, &(value as u16))?,
+                FatType::Fat32 => {
+                    // The 4 high bits are reserved
+                    let old: u32 = self.disk.read_struct(offset)?;
+                    let new = (old & 0xf000_0000) | (value & 0x0fff_ffff);
+                    self.disk.write_struct(offset, &new)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn set_end_of_chain(&mut self, cluster: u32) -> IoResult<()> {
+        self.set_fat_entry(cluster, self.fat_type.end_of_chain_mark())
+    }
+
+    /// The cluster after `cluster` in its chain, None at the end of the chain
+    pub(crate) fn next_cluster(&mut self, cluster: u32) -> IoResult<Option<u32>> {
+        let next = self.fat_entry(cluster)?;
+        if next >= self.fat_type.end_of_chain() {
+            Ok(None)
+        } else if self.is_valid(next) {
+            Ok(Some(next))
+        } else {
+            // A free or a bad cluster in a chain
+            Err(Errno::EIO)
+        }
+    }
+
+    /// The cluster number `index` of the chain which starts at `first`
+    pub(crate) fn cluster_at(&mut self, first: u32, index: u64) -> IoResult<Option<u32>> {
+        if first == 0 {
+            return Ok(None);
+        }
+        let mut cluster = first;
+        for _ in 0..index {
+            cluster = match self.next_cluster(cluster)? {
+                Some(next) => next,
+                None => return Ok(None),
+            };
+        }
+        Ok(Some(cluster))
+    }
+
+    /// All the clusters of the chain which starts at `first`
+    pub(crate) fn chain(&mut self, first: u32) -> IoResult<Vec<u32>> {
+        let mut chain = Vec::new();
+        let mut cluster = Some(first).filter(|&c| c != 0);
+        while let Some(current) = cluster {
+            // A loop in the chain
+            if chain.len() > self.nbr_clusters as usize {
+                return Err(Errno::EIO);
+            }
+            chain.try_push(current)?;
+            cluster = self.next_cluster(current)?;
+        }
+        Ok(chain)
+    }
+
+    /// Allocate a free cluster at the end of the chain whose last cluster is `previous`
+    pub(crate) fn allocate_cluster(&mut self, previous: Option<u32>) -> IoResult<u32> {
+        if self.free_clusters == 0 {
+            return Err(Errno::ENOSPC);
+        }
+        let start = if self.is_valid(self.next_free) {
+            self.next_free
+        } else {
+            2
+        };
+        for i in 0..self.nbr_clusters {
+            let cluster = 2 + (start - 2 + i) % self.nbr_clusters;
+            if self.fat_entry(cluster)? == 0 {
+                self.set_end_of_chain(cluster)?;
+                if let Some(previous) = previous {
+                    self.set_fat_entry(previous, cluster)?;
+                }
+                self.free_clusters -= 1;
+                self.next_free = cluster + 1;
+                return Ok(cluster);
+            }
+        }
+        // The free clusters count was wrong
+        self.free_clusters = 0;
+        Err(Errno::ENOSPC)
+    }
+
+    /// Free all the clusters of the chain which starts at `first`
+    pub(crate) fn free_chain(&mut self, first: u32) -> IoResult<()> {
+        for cluster in self.chain(first)? {
+            self.set_fat_entry(cluster, 0)?;
+            self.free_clusters += 1;
+        }
+        Ok(())
+    }
+
+    /// Fill `cluster` with zeros
+    pub(crate) fn zero_cluster(&mut self, cluster: u32) -> IoResult<()> {
+        let zeros = try_vec![0; self.cluster_size as usize]?;
+        let offset = self.cluster_offset(cluster);
+        self.disk.write_all(offset, &zeros)
+    }
+
+    /// Count the free entries of the FAT, reading it by chunks
+    pub(crate) fn count_free_clusters(&mut self) -> IoResult<u32> {
+        let offset = self.fat_offset + self.read_fat() as u64 * self.fat_size;
+        let fat_len = self.entry_offset(self.nbr_clusters + 2) as usize;
+        let mut chunk = try_vec![0; FAT_CHUNK_SIZE + 1]?;
+        let mut free = 0;
+        let mut cluster = 2;
+
+        while cluster < self.nbr_clusters + 2 {
+            let chunk_start = self.entry_offset(cluster) as usize;
+            // The extra byte is for a FAT12 entry which is across two chunks
+            let len = min(FAT_CHUNK_SIZE + 1, fat_len + 1 - chunk_start);
+            self.disk
+                .read_exact(offset + chunk_start as u64, &mut chunk[..len])?;
+            while cluster < self.nbr_clusters + 2 {
+                let index = self.entry_offset(cluster) as usize - chunk_start;
+                let value = match self.fat_type {
+                    FatType::Fat12 if index + 1 < len => {
+                        let value = u16::from_le_bytes([chunk[index], chunk[index + 1]]);
+                        if cluster & 1 != 0 {
+                            value >> 4
+                        } else {
+                            value & 0xfff
+                        }
+                    }
+                    FatType::Fat16 if index + 1 < len => {
+                        u16::from_le_bytes([chunk[index], chunk[index + 1]])
+                    }
+                    FatType::Fat32 if index + 3 < len => {
+                        let value = u32::from_le_bytes([
+                            chunk[index],
+                            chunk[index + 1],
+                            chunk[index + 2],
+                            chunk[index + 3],
+                        ]);
+                        (value & 0x0fff_ffff != 0) as u16
+                    }
+                    _ => break,
+                };
+                if value == 0 {
+                    free += 1;
+                }
+                cluster += 1;
+            }
+        }
+        Ok(free)
+    }
+}
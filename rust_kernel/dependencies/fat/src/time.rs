@@ -0,0 +1,61 @@
+//! The FAT dates are local times since 1980 with a precision of two seconds,
+//! the local time is taken as UTC here
+
+/// 1980-01-01 00:00:00, the first FAT date
+const FAT_EPOCH: u32 = 315_532_800;
+
+/// The number of days from 1970-01-01 to the civil date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The civil date (year, month, day) of the number of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = (if days >= 0 { days } else { days - 146_096 }) / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+/// Convert a FAT date and time to a unix timestamp, a null date is the unix epoch
+pub fn to_unix(date: u16, time: u16) -> u32 {
+    if date == 0 {
+        return 0;
+    }
+    let year = 1980 + (date >> 9) as i64;
+    let month = (((date >> 5) & 0xf) as i64).max(1).min(12);
+    let day = ((date & 0x1f) as i64).max(1);
+    let seconds =
+        (time >> 11) as i64 * 3600 + ((time >> 5) & 0x3f) as i64 * 60 + (time & 0x1f) as i64 * 2;
+
+    (days_from_civil(year, month, day) * 86400 + seconds) as u32
+}
+
+/// Convert a unix timestamp to a FAT (date, time), the dates before 1980 are clamped
+pub fn from_unix(timestamp: u32) -> (u16, u16) {
+    let timestamp = timestamp.max(FAT_EPOCH) as i64;
+    let (year, month, day) = civil_from_days(timestamp / 86400);
+    let seconds = timestamp % 86400;
+
+    let date = (((year - 1980).min(127) as u16) << 9) | ((month as u16) << 5) | day as u16;
+    let time = (((seconds / 3600) as u16) << 11)
+        | (((seconds / 60 % 60) as u16) << 5)
+        | ((seconds % 60 / 2) as u16);
+    (date, time)
+}
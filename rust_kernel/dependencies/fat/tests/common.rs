@@ -0,0 +1,79 @@
+#![allow(dead_code)]
+//! The tests need mkfs.fat from dosfstools, each test works on its own disk image
+
+use fat::{DiskIo, FatFilesystem, FatType, IoResult};
+use libc_binding::Errno;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::process::{Command, Stdio};
+
+#[derive(Debug)]
+pub struct StdDiskIo {
+    pub f: File,
+}
+
+impl DiskIo for StdDiskIo {
+    fn flush(&mut self) -> IoResult<()> {
+        self.f.flush().map_err(|_| Errno::EIO)
+    }
+    fn write_buffer(&mut self, offset: u64, buf: &[u8]) -> IoResult<u64> {
+        self.f
+            .seek(SeekFrom::Start(offset))
+            .map_err(|_| Errno::EIO)?;
+        self.f.write(buf).map_err(|_| Errno::EIO).map(|x| x as u64)
+    }
+    fn read_buffer(&mut self, offset: u64, buf: &mut [u8]) -> IoResult<u64> {
+        self.f
+            .seek(SeekFrom::Start(offset))
+            .map_err(|_| Errno::EIO)?;
+        self.f.read(buf).map_err(|_| Errno::EIO).map(|x| x as u64)
+    }
+}
+
+pub fn exec_shell(cmd: &str) {
+    let exit_code = Command::new("bash")
+        .args(&["-c"])
+        .args(&[cmd])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    if !exit_code.success() {
+        eprintln!("command failed while creating disk: {}", cmd);
+    }
+}
+
+/// Create the disk image `name` of `size` bytes, formatted as FAT`fat_bits`
+pub fn create_disk(name: &str, fat_bits: usize, size: usize) {
+    exec_shell(&format!("rm -f {}", name));
+    exec_shell(&format!(
+        "mkfs.fat -F {} -C {} {}",
+        fat_bits,
+        name,
+        size / 1024
+    ));
+}
+
+pub fn new_fat(name: &str) -> FatFilesystem {
+    let f = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(name)
+        .expect("open disk failed");
+    FatFilesystem::new(Box::new(StdDiskIo { f })).expect("init fat filesystem failed")
+}
+
+/// The disks of the tests of each FAT type
+pub const DISKS: [(usize, usize); 3] = [
+    (12, 1024 * 1024),
+    (16, 8 * 1024 * 1024),
+    (32, 40 * 1024 * 1024),
+];
+
+pub fn fat_type(fat_bits: usize) -> FatType {
+    match fat_bits {
+        12 => FatType::Fat12,
+        16 => FatType::Fat16,
+        _ => FatType::Fat32,
+    }
+}
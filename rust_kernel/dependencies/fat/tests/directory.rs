@@ -0,0 +1,142 @@
+use fat::{FatFilesystem, ROOT_INODE};
+use libc_binding::Errno;
+mod common;
+use common::*;
+
+fn names(fat: &mut FatFilesystem, inode_nbr: u32) -> Vec<String> {
+    let mut names: Vec<String> = fat
+        .lookup_directory(inode_nbr)
+        .expect("lookup directory failed")
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn create_files() {
+    for &(fat_bits, size) in DISKS.iter() {
+        let disk = format!("create_files_{}.img", fat_bits);
+        create_disk(&disk, fat_bits, size);
+        let paths = [
+            "SHORT.TXT",
+            "lower.txt",
+            "MixedCase.Txt",
+            "a very long file name with spaces.and.dots",
+            "été",
+        ];
+        {
+            let mut fat = new_fat(&disk);
+            assert_eq!(fat.fat_type(), fat_type(fat_bits));
+            for path in paths.iter() {
+                fat.create(path, ROOT_INODE, 0).expect("create failed");
+            }
+            assert_eq!(
+                fat.create("short.txt", ROOT_INODE, 0).unwrap_err(),
+                Errno::EEXIST
+            );
+            assert_eq!(
+                fat.create("bad:name", ROOT_INODE, 0).unwrap_err(),
+                Errno::EINVAL
+            );
+            fat.flush().unwrap();
+        }
+        let mut fat = new_fat(&disk);
+        let mut expected: Vec<String> = paths.iter().map(|s| s.to_string()).collect();
+        expected.sort();
+        assert_eq!(names(&mut fat, ROOT_INODE), expected);
+    }
+}
+
+#[test]
+fn directories() {
+    for &(fat_bits, size) in DISKS.iter() {
+        let disk = format!("directories_{}.img", fat_bits);
+        create_disk(&disk, fat_bits, size);
+        let mut fat = new_fat(&disk);
+        let free_clusters = fat.free_clusters();
+        let dir = fat.create_dir(ROOT_INODE, "directory", 0).unwrap();
+        assert!(dir.is_directory());
+        let sub = fat.create_dir(dir.inode_nbr, "sub directory", 0).unwrap();
+        fat.create("file", sub.inode_nbr, 0).unwrap();
+
+        assert_eq!(
+            fat.rmdir(ROOT_INODE, "DIRECTORY", 0).unwrap_err(),
+            Errno::ENOTEMPTY
+        );
+        assert_eq!(
+            fat.rmdir(sub.inode_nbr, "file", 0).unwrap_err(),
+            Errno::ENOTDIR
+        );
+        assert_eq!(
+            fat.unlink(dir.inode_nbr, "sub directory", true, 0)
+                .unwrap_err(),
+            Errno::EISDIR
+        );
+
+        fat.rename(sub.inode_nbr, "file", ROOT_INODE, "moved", 0)
+            .unwrap();
+        fat.rename(dir.inode_nbr, "sub directory", ROOT_INODE, "Sub", 0)
+            .unwrap();
+        assert_eq!(
+            names(&mut fat, ROOT_INODE),
+            vec!["Sub", "directory", "moved"]
+        );
+        assert!(names(&mut fat, dir.inode_nbr).is_empty());
+
+        fat.rmdir(ROOT_INODE, "sub", 0).unwrap();
+        fat.rmdir(ROOT_INODE, "directory", 0).unwrap();
+        fat.unlink(ROOT_INODE, "moved", true, 0).unwrap();
+        assert!(names(&mut fat, ROOT_INODE).is_empty());
+        assert_eq!(fat.free_clusters(), free_clusters);
+    }
+}
+
+#[test]
+fn many_entries() {
+    for &(fat_bits, size) in DISKS.iter() {
+        let disk = format!("many_entries_{}.img", fat_bits);
+        create_disk(&disk, fat_bits, size);
+        let paths: Vec<String> = (0..300)
+            .map(|i| format!("a long file name {}", i))
+            .collect();
+        {
+            let mut fat = new_fat(&disk);
+            let dir = fat.create_dir(ROOT_INODE, "dir", 0).unwrap();
+            for path in paths.iter() {
+                fat.create(path, dir.inode_nbr, 0).expect("create failed");
+            }
+            fat.flush().unwrap();
+        }
+        let mut fat = new_fat(&disk);
+        let dir = fat
+            .lookup_directory(ROOT_INODE)
+            .unwrap()
+            .into_iter()
+            .find(|(name, _)| name == "dir")
+            .unwrap()
+            .1;
+        let mut expected = paths.clone();
+        expected.sort();
+        assert_eq!(names(&mut fat, dir.inode_nbr), expected);
+        for path in paths.iter().step_by(2) {
+            fat.unlink(dir.inode_nbr, path, true, 0).unwrap();
+        }
+        assert_eq!(names(&mut fat, dir.inode_nbr).len(), paths.len() / 2);
+    }
+}
+
+#[test]
+fn full_root_directory() {
+    create_disk("full_root_directory.img", 16, 8 * 1024 * 1024);
+    let mut fat = new_fat("full_root_directory.img");
+    // The fixed root directory of FAT16 has 512 entries
+    for i in 0..512 {
+        fat.create(&format!("F{}", i), ROOT_INODE, 0).unwrap();
+    }
+    assert_eq!(
+        fat.create("F512", ROOT_INODE, 0).unwrap_err(),
+        Errno::ENOSPC
+    );
+}
@@ -0,0 +1,124 @@
+use fat::{FatFilesystem, ROOT_INODE};
+use libc_binding::Errno;
+use rand::prelude::*;
+mod common;
+use common::*;
+
+fn find(fat: &mut FatFilesystem, name: &str) -> u32 {
+    fat.lookup_directory(ROOT_INODE)
+        .unwrap()
+        .into_iter()
+        .find(|(filename, _)| filename == name)
+        .expect("file not found")
+        .1
+        .inode_nbr
+}
+
+#[test]
+fn read_write() {
+    for &(fat_bits, size) in DISKS.iter() {
+        let disk = format!("read_write_{}.img", fat_bits);
+        create_disk(&disk, fat_bits, size);
+        let mut rng = rand::thread_rng();
+        let content: Vec<u8> = (0..100_000).map(|_| rng.gen()).collect();
+        {
+            let mut fat = new_fat(&disk);
+            let file = fat.create("file", ROOT_INODE, 0).unwrap();
+            let mut offset = 0;
+            for chunk in content.chunks(7777) {
+                fat.write(file.inode_nbr, &mut offset, chunk, 0).unwrap();
+            }
+            assert_eq!(offset, content.len() as u64);
+            fat.flush().unwrap();
+        }
+        let mut fat = new_fat(&disk);
+        let inode_nbr = find(&mut fat, "file");
+        assert_eq!(fat.get_info(inode_nbr).unwrap().size, content.len() as u64);
+        let mut buf = vec![0; content.len() + 10];
+        let mut offset = 0;
+        assert_eq!(
+            fat.read(inode_nbr, &mut offset, &mut buf).unwrap(),
+            content.len() as u64
+        );
+        assert_eq!(&buf[..content.len()], &content[..]);
+        assert_eq!(fat.read(inode_nbr, &mut offset, &mut buf).unwrap(), 0);
+    }
+}
+
+#[test]
+fn write_after_end() {
+    create_disk("write_after_end.img", 12, 1024 * 1024);
+    let mut fat = new_fat("write_after_end.img");
+    let file = fat.create("file", ROOT_INODE, 0).unwrap();
+    let mut offset = 5000;
+    fat.write(file.inode_nbr, &mut offset, b"end", 0).unwrap();
+    let mut buf = vec![1; 5003];
+    let mut offset = 0;
+    assert_eq!(
+        fat.read(file.inode_nbr, &mut offset, &mut buf).unwrap(),
+        5003
+    );
+    assert!(buf[..5000].iter().all(|&c| c == 0));
+    assert_eq!(&buf[5000..], b"end");
+}
+
+#[test]
+fn truncate() {
+    for &(fat_bits, size) in DISKS.iter() {
+        let disk = format!("truncate_{}.img", fat_bits);
+        create_disk(&disk, fat_bits, size);
+        let mut fat = new_fat(&disk);
+        let free_clusters = fat.free_clusters();
+        let file = fat.create("file", ROOT_INODE, 0).unwrap();
+        let mut offset = 0;
+        fat.write(file.inode_nbr, &mut offset, &[42; 20000], 0)
+            .unwrap();
+        fat.truncate(file.inode_nbr, 10, 0).unwrap();
+        assert_eq!(fat.free_clusters(), free_clusters - 1);
+        fat.truncate(file.inode_nbr, 3000, 0).unwrap();
+        let mut buf = [1; 3000];
+        let mut offset = 0;
+        fat.read(file.inode_nbr, &mut offset, &mut buf).unwrap();
+        assert!(buf[..10].iter().all(|&c| c == 42));
+        assert!(buf[10..].iter().all(|&c| c == 0));
+        fat.truncate(file.inode_nbr, 0, 0).unwrap();
+        assert_eq!(fat.free_clusters(), free_clusters);
+    }
+}
+
+#[test]
+fn unlink_open_file() {
+    create_disk("unlink_open_file.img", 16, 8 * 1024 * 1024);
+    let mut fat = new_fat("unlink_open_file.img");
+    let free_clusters = fat.free_clusters();
+    let file = fat.create("file", ROOT_INODE, 0).unwrap();
+    let mut offset = 0;
+    fat.write(file.inode_nbr, &mut offset, b"still there", 0)
+        .unwrap();
+    fat.unlink(ROOT_INODE, "file", false, 0).unwrap();
+    assert!(fat.lookup_directory(ROOT_INODE).unwrap().is_empty());
+
+    let mut buf = [0; 11];
+    let mut offset = 0;
+    fat.read(file.inode_nbr, &mut offset, &mut buf).unwrap();
+    assert_eq!(&buf, b"still there");
+    fat.remove_inode(file.inode_nbr).unwrap();
+    assert_eq!(fat.free_clusters(), free_clusters);
+    assert_eq!(fat.get_info(file.inode_nbr).unwrap_err(), Errno::ENOENT);
+}
+
+#[test]
+fn no_space_left() {
+    create_disk("no_space_left.img", 12, 1024 * 1024);
+    let mut fat = new_fat("no_space_left.img");
+    let file = fat.create("file", ROOT_INODE, 0).unwrap();
+    let capacity = fat.free_clusters() as u64 * fat.cluster_size() as u64;
+    let mut offset = 0;
+    let buf = vec![1; capacity as usize + 1000];
+    let (written, _) = fat.write(file.inode_nbr, &mut offset, &buf, 0).unwrap();
+    assert_eq!(written, capacity);
+    assert_eq!(
+        fat.write(file.inode_nbr, &mut offset, &buf, 0).unwrap_err(),
+        Errno::ENOSPC
+    );
+}
@@ -137,6 +137,12 @@ impl Eflags {
         self.inner.get_bit(8)
     }
 
+    /// set the state of the trap flag: a debug exception follows each instruction.
+    pub fn set_trap_flag(&mut self, value: bool) -> Self {
+        self.inner.set_bit(8, value);
+        *self
+    }
+
     /// Returns the state of the interrupt flag.
     pub fn interrupt_flag(&self) -> bool {
         self.inner.get_bit(9)
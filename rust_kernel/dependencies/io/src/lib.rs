@@ -8,6 +8,9 @@ use core::cmp::PartialEq;
 pub mod pio;
 pub use pio::{io_wait, Pio};
 
+pub mod mmio;
+pub use mmio::Mmio;
+
 /// The general Io trait, for In/out objects
 pub trait Io {
     /// Type of the values returned by read and write.
@@ -0,0 +1,23 @@
+//! See [Memory mapped I/O](https://wiki.osdev.org/PCI#Base_Address_Registers)
+use super::Io;
+use core::ptr::{read_volatile, write_volatile};
+
+/// This is a generic structure to represent a memory mapped register
+/// It must be placed over the mapped memory and implements the IO Trait with volatile accesses
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct Mmio<T> {
+    value: T,
+}
+
+impl<T: Copy + PartialEq> Io for Mmio<T> {
+    type Value = T;
+
+    fn read(&self) -> Self::Value {
+        unsafe { read_volatile(&self.value) }
+    }
+
+    fn write(&mut self, value: Self::Value) {
+        unsafe { write_volatile(&mut self.value, value) }
+    }
+}
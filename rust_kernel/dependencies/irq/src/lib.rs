@@ -56,3 +56,30 @@ pub enum Irq {
     /// (ATA interface usually serves hard disk drives and CD drives)
     SecondaryATAChannel = 15,
 }
+
+/// Get the IRQ of a legacy interrupt line (as given by the PCI configuration space)
+impl core::convert::TryFrom<u8> for Irq {
+    type Error = ();
+
+    fn try_from(line: u8) -> Result<Self, Self::Error> {
+        Ok(match line {
+            0 => Irq::SystemTimer,
+            1 => Irq::KeyboardController,
+            2 => Irq::SlaveCascadeIRQ,
+            3 => Irq::SerialPortController2,
+            4 => Irq::SerialPortController1,
+            5 => Irq::ParallelPort2And3,
+            6 => Irq::FloppyDiskController,
+            7 => Irq::ParallelPort1,
+            8 => Irq::RealTimeClock,
+            9 => Irq::ACPI,
+            10 => Irq::Irq10,
+            11 => Irq::Irq11,
+            12 => Irq::MouseOnPS2Controller,
+            13 => Irq::Irq13,
+            14 => Irq::PrimaryATAChannel,
+            15 => Irq::SecondaryATAChannel,
+            _ => return Err(()),
+        })
+    }
+}
@@ -13,6 +13,9 @@
 #include <sys/types.h>
 #include <sys/wait.h>
 #include <sys/statfs.h>
+#include <sys/mount.h>
+#include <sys/futex.h>
+#include <sys/ptrace.h>
 
 #include <assert.h>
 #include <ctype.h>
@@ -30,6 +33,7 @@
 #include <math.h>
 /* #include <netdb.h> */
 /* #include <nl_types.h> */
+#include <poll.h>
 #include <pwd.h>
 #include <sched.h>
 #include <setjmp.h>
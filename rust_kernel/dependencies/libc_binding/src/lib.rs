@@ -11,6 +11,7 @@ pub type c_int = i32;
 pub type c_uint = u32;
 pub type c_void = i32;
 pub type c_longlong = i64;
+pub type c_ulonglong = u64;
 pub type c_long = i32;
 pub type c_ulong = u32;
 pub type c_schar = i8;
@@ -28,7 +29,7 @@ use core::mem::transmute;
 impl TryFrom<u32> for Signum {
     type Error = InvalidSignum;
     fn try_from(n: u32) -> Result<Self, Self::Error> {
-        if n >= 32 {
+        if n > SIGRTMAX {
             return Err(InvalidSignum);
         } else {
             Ok(unsafe { transmute(n) })
@@ -194,6 +195,17 @@ impl Signum {
 impl Signum {
     pub const SIGUNUSED: Signum = Signum::SIGSYS;
 }
+impl Signum {
+    pub const SIGRTMIN: Signum = Signum::SIGRT32;
+    pub const SIGRTMAX: Signum = Signum::SIGRT63;
+}
+
+impl Signum {
+    /// Real-time signals are queued instead of being coalesced
+    pub fn is_realtime(self) -> bool {
+        self as u32 >= SIGRTMIN
+    }
+}
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -230,6 +242,38 @@ pub enum Signum {
     SIGIO = SIGIO,
     SIGPWR = SIGPWR,
     SIGSYS = SIGSYS,
+    SIGRT32 = SIGRTMIN,
+    SIGRT33 = 33,
+    SIGRT34 = 34,
+    SIGRT35 = 35,
+    SIGRT36 = 36,
+    SIGRT37 = 37,
+    SIGRT38 = 38,
+    SIGRT39 = 39,
+    SIGRT40 = 40,
+    SIGRT41 = 41,
+    SIGRT42 = 42,
+    SIGRT43 = 43,
+    SIGRT44 = 44,
+    SIGRT45 = 45,
+    SIGRT46 = 46,
+    SIGRT47 = 47,
+    SIGRT48 = 48,
+    SIGRT49 = 49,
+    SIGRT50 = 50,
+    SIGRT51 = 51,
+    SIGRT52 = 52,
+    SIGRT53 = 53,
+    SIGRT54 = 54,
+    SIGRT55 = 55,
+    SIGRT56 = 56,
+    SIGRT57 = 57,
+    SIGRT58 = 58,
+    SIGRT59 = 59,
+    SIGRT60 = 60,
+    SIGRT61 = 61,
+    SIGRT62 = 62,
+    SIGRT63 = SIGRTMAX,
 }
 
 #[repr(u32)]
@@ -319,9 +363,9 @@ const_assert!(PATH_MAX as usize >= _POSIX_PATH_MAX);
 // pub const _POSIX_RE_DUP_MAX: usize = 255;
 // const_assert!(RE_DUP_MAX >= _POSIX_RE_DUP_MAX);
 
-//// The number of realtime signal numbers reserved for application use.
-// pub const _POSIX_RTSIG_MAX: usize = 8;
-// const_assert!(RTSIG_MAX >= _POSIX_RTSIG_MAX);
+/// The number of realtime signal numbers reserved for application use.
+pub const _POSIX_RTSIG_MAX: usize = 8;
+const_assert!(RTSIG_MAX as usize >= _POSIX_RTSIG_MAX);
 
 //// The number of semaphores that a process may have.
 // pub const _POSIX_SEM_NSEMS_MAX: usize = 256;
@@ -331,9 +375,9 @@ const_assert!(PATH_MAX as usize >= _POSIX_PATH_MAX);
 // pub const _POSIX_SEM_VALUE_MAX: usize = 32_767;
 // const_assert!(SEM_VALUE_MAX >= _POSIX_SEM_VALUE_MAX);
 
-//// The number of queued signals that a process may send and have pending at the receiver(s) at any time.
-// pub const _POSIX_SIGQUEUE_MAX: usize = 32;
-// const_assert!(SIGQUEUE_MAX >= _POSIX_SIGQUEUE_MAX);
+/// The number of queued signals that a process may send and have pending at the receiver(s) at any time.
+pub const _POSIX_SIGQUEUE_MAX: usize = 32;
+const_assert!(SIGQUEUE_MAX as usize >= _POSIX_SIGQUEUE_MAX);
 
 //// The value that can be stored in an object of type ssize_t.
 // pub const _POSIX_SSIZE_MAX: usize = 32_767;
@@ -788,6 +832,68 @@ impl From<OpenFlags> for Amode {
     }
 }
 
+bitflags! {
+    /// Events requested in the events member of a struct pollfd
+    /// and reported in its revents member.
+    #[derive(Default)]
+    pub struct PollEvents: u16 {
+        /// Data other than high-priority data may be read without blocking.
+        const POLLIN = POLLIN as u16;
+        /// High priority data may be read without blocking.
+        const POLLPRI = POLLPRI as u16;
+        /// Normal data may be written without blocking.
+        const POLLOUT = POLLOUT as u16;
+        /// An error has occurred (revents only).
+        const POLLERR = POLLERR as u16;
+        /// Device has been disconnected (revents only).
+        const POLLHUP = POLLHUP as u16;
+        /// Invalid fd member (revents only).
+        const POLLNVAL = POLLNVAL as u16;
+        /// Normal data may be read without blocking.
+        const POLLRDNORM = POLLRDNORM as u16;
+        /// Priority data may be read without blocking.
+        const POLLRDBAND = POLLRDBAND as u16;
+        /// Equivalent to POLLOUT.
+        const POLLWRNORM = POLLWRNORM as u16;
+        /// Priority data may be written.
+        const POLLWRBAND = POLLWRBAND as u16;
+
+        /// All the events related to reading
+        const READ = Self::POLLIN.bits() | Self::POLLRDNORM.bits();
+        /// All the events related to writing
+        const WRITE = Self::POLLOUT.bits() | Self::POLLWRNORM.bits();
+        /// Events which are always reported, even if not requested
+        const ALWAYS = Self::POLLERR.bits() | Self::POLLHUP.bits() | Self::POLLNVAL.bits();
+    }
+}
+
+bitflags! {
+    /// The flags given to mount(2)
+    #[derive(Default)]
+    pub struct MountFlags: u32 {
+        /// Mount read-only.
+        const MS_RDONLY = MS_RDONLY;
+        /// Ignore the set-user-ID and set-group-ID bits of the files.
+        const MS_NOSUID = MS_NOSUID;
+        /// Disallow the access to the device files.
+        const MS_NODEV = MS_NODEV;
+        /// Disallow the execution of the programs.
+        const MS_NOEXEC = MS_NOEXEC;
+        /// Change the flags of an existing mount.
+        const MS_REMOUNT = MS_REMOUNT;
+        /// Make a directory tree visible at another place.
+        const MS_BIND = MS_BIND;
+        /// Move an existing mount to another place.
+        const MS_MOVE = MS_MOVE;
+
+        /// The flags which are kept by a mount and may be changed by a remount
+        const PER_MOUNT = Self::MS_RDONLY.bits()
+            | Self::MS_NOSUID.bits()
+            | Self::MS_NODEV.bits()
+            | Self::MS_NOEXEC.bits();
+    }
+}
+
 /// Also known as File Classes in POSIX-2018.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum PermissionClass {
@@ -877,6 +983,9 @@ impl TryFrom<u32> for ShutDownOption {
 #[derive(Debug, PartialEq)]
 pub enum IoctlCmd {
     TIOCGWINSZ = TIOCGWINSZ,
+    TIOCSWINSZ = TIOCSWINSZ,
+    TIOCGPTN = TIOCGPTN,
+    TIOCSPTLCK = TIOCSPTLCK,
     RAW_SCANCODE_MODE = RAW_SCANCODE_MODE,
     REFRESH_SCREEN = REFRESH_SCREEN,
     GET_FRAME_BUFFER_PTR = GET_FRAME_BUFFER_PTR,
@@ -887,6 +996,9 @@ impl TryFrom<u32> for IoctlCmd {
     fn try_from(n: u32) -> Result<Self, Self::Error> {
         Ok(match n {
             TIOCGWINSZ => IoctlCmd::TIOCGWINSZ,
+            TIOCSWINSZ => IoctlCmd::TIOCSWINSZ,
+            TIOCGPTN => IoctlCmd::TIOCGPTN,
+            TIOCSPTLCK => IoctlCmd::TIOCSPTLCK,
             RAW_SCANCODE_MODE => IoctlCmd::RAW_SCANCODE_MODE,
             REFRESH_SCREEN => IoctlCmd::REFRESH_SCREEN,
             GET_FRAME_BUFFER_PTR => IoctlCmd::GET_FRAME_BUFFER_PTR,
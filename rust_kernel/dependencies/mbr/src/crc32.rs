@@ -0,0 +1,38 @@
+//! CRC32 (IEEE 802.3) as used by the GPT headers and partition entry arrays
+
+/// Reversed representation of the polynomial 0x04C11DB7
+const POLYNOMIAL: u32 = 0xEDB8_8320;
+
+/// Incremental CRC32 computation, usefull when the data is read sector by sector
+#[derive(Debug, Copy, Clone)]
+pub struct Crc32(u32);
+
+impl Crc32 {
+    /// Start a new computation
+    pub fn new() -> Self {
+        Crc32(!0)
+    }
+
+    /// Feed the computation with `data`
+    pub fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            self.0 ^= *byte as u32;
+            for _ in 0..8 {
+                let mask = (self.0 & 1).wrapping_neg();
+                self.0 = (self.0 >> 1) ^ (POLYNOMIAL & mask);
+            }
+        }
+    }
+
+    /// Get the CRC32 of all the data fed
+    pub fn finish(&self) -> u32 {
+        !self.0
+    }
+}
+
+/// Compute the CRC32 of `data`
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(data);
+    crc.finish()
+}
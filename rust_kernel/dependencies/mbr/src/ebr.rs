@@ -0,0 +1,62 @@
+//! This module follows the chain of Extended Boot Records of an extended partition. See https://wiki.osdev.org/Partition_Table
+//! Each EBR describes a logical partition (relative to the EBR) and the location of the next EBR (relative to the extended partition)
+
+use super::{Mbr, MbrError, MbrResult, PartitionEntry, PartitionKind, SectorRead, SECTOR_SIZE};
+
+use alloc::vec::Vec;
+use fallible_collections::FallibleVec;
+
+/// Logical partitions are numbered after the four primary ones
+const FIRST_LOGICAL_NUMBER: u32 = 5;
+
+/// The maximum length of a chain
+const MAX_LOGICAL_PARTITIONS: u32 = 128;
+
+/// Push the logical partitions of the extended partition into `partitions`
+pub(crate) fn read_logical_partitions<R: SectorRead>(
+    disk: &mut R,
+    extended_start: u64,
+    extended_size: u64,
+    partitions: &mut Vec<PartitionEntry>,
+) -> MbrResult<()> {
+    let mut sector = [0; SECTOR_SIZE];
+    let mut ebr_lba = extended_start;
+    let mut visited: Vec<u64> = Vec::new();
+
+    for number in FIRST_LOGICAL_NUMBER..FIRST_LOGICAL_NUMBER + MAX_LOGICAL_PARTITIONS {
+        // A chain which links back to one of its EBR ends there
+        if visited.contains(&ebr_lba) {
+            return Ok(());
+        }
+        visited
+            .try_push(ebr_lba)
+            .map_err(|_| MbrError::OutOfMemory)?;
+        disk.read_sector(ebr_lba, &mut sector)?;
+        let ebr = unsafe { Mbr::new(&sector) };
+        if !ebr.bootable {
+            return Err(MbrError::UnknownStructure);
+        }
+
+        let logical = &ebr.parts[0];
+        if logical.is_used() && logical.size != 0 {
+            partitions
+                .try_push(PartitionEntry {
+                    number,
+                    start: ebr_lba + logical.start as u64,
+                    size: logical.size as u64,
+                    kind: PartitionKind::Mbr {
+                        part_type: logical.part_type,
+                        bootable: logical.is_bootable(),
+                    },
+                })
+                .map_err(|_| MbrError::OutOfMemory)?;
+        }
+
+        let next = &ebr.parts[1];
+        if !next.is_used() || next.start == 0 || next.start as u64 >= extended_size {
+            return Ok(());
+        }
+        ebr_lba = extended_start + next.start as u64;
+    }
+    Ok(())
+}
@@ -0,0 +1,303 @@
+//! This module decodes the GUID Partition Table. See https://wiki.osdev.org/GPT
+//! The primary header is at LBA 1, a backup header is on the last sector of the disk
+
+use super::crc32::{crc32, Crc32};
+use super::{MbrError, MbrResult, PartitionEntry, PartitionKind, Scheme, SectorRead, SECTOR_SIZE};
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem::size_of;
+use core::ptr::read_unaligned;
+use fallible_collections::FallibleVec;
+
+/// A Globally Unique Identifier, stored in its on-disk mixed endian layout
+#[derive(Copy, Clone, PartialEq, Eq, Default)]
+#[repr(C)]
+pub struct Guid(pub [u8; 16]);
+
+impl Guid {
+    /// The null GUID marks an unused partition entry
+    pub const UNUSED: Guid = Guid([0; 16]);
+
+    /// Linux filesystem data
+    pub const LINUX_FILESYSTEM: Guid =
+        Guid::new(0x0FC6_3DAF, 0x8483, 0x4772, 0x8E79_3D69_D847_7DE4);
+    /// Linux swap
+    pub const LINUX_SWAP: Guid = Guid::new(0x0657_FD6D, 0xA4AB, 0x43C4, 0x84E5_0933_C84B_4F4F);
+    /// EFI system partition
+    pub const EFI_SYSTEM: Guid = Guid::new(0xC12A_7328, 0xF81F, 0x11D2, 0xBA4B_00A0_C93E_C93B);
+    /// BIOS boot partition (used by grub on GPT disks)
+    pub const BIOS_BOOT: Guid = Guid::new(0x2168_6148, 0x6449, 0x6E6F, 0x744E_6565_6445_4649);
+    /// Microsoft basic data (FAT, NTFS ...)
+    pub const MICROSOFT_BASIC_DATA: Guid =
+        Guid::new(0xEBD0_A0A2, 0xB9E5, 0x4433, 0x87C0_68B6_B726_99C7);
+
+    /// Create a GUID from its textual form XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX
+    /// The three first fields are stored little endian, the last two big endian
+    pub const fn new(data1: u32, data2: u16, data3: u16, data4: u64) -> Self {
+        Guid([
+            data1 as u8,
+            (data1 >> 8) as u8,
+            (data1 >> 16) as u8,
+            (data1 >> 24) as u8,
+            data2 as u8,
+            (data2 >> 8) as u8,
+            data3 as u8,
+            (data3 >> 8) as u8,
+            (data4 >> 56) as u8,
+            (data4 >> 48) as u8,
+            (data4 >> 40) as u8,
+            (data4 >> 32) as u8,
+            (data4 >> 24) as u8,
+            (data4 >> 16) as u8,
+            (data4 >> 8) as u8,
+            data4 as u8,
+        ])
+    }
+}
+
+impl fmt::Display for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let b = &self.0;
+        write!(
+            f,
+            "{:02X}{:02X}{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}-",
+            b[3], b[2], b[1], b[0], b[5], b[4], b[7], b[6]
+        )?;
+        write!(
+            f,
+            "{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
+            b[8], b[9], b[10], b[11], b[12], b[13], b[14], b[15]
+        )
+    }
+}
+
+impl fmt::Debug for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+/// Name of a GPT partition: 36 UTF-16LE code units, padded with zeros
+#[derive(Copy, Clone)]
+pub struct PartitionName(pub [u16; 36]);
+
+impl PartitionName {
+    /// Decode the name, invalid code units are replaced by U+FFFD
+    pub fn chars<'a>(&'a self) -> impl Iterator<Item = char> + 'a {
+        core::char::decode_utf16(self.0.iter().cloned().take_while(|c| *c != 0))
+            .map(|c| c.unwrap_or(core::char::REPLACEMENT_CHARACTER))
+    }
+}
+
+impl fmt::Debug for PartitionName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"")?;
+        for c in self.chars() {
+            write!(f, "{}", c)?;
+        }
+        write!(f, "\"")
+    }
+}
+
+/// Defined real GPT header structure
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+#[repr(packed)]
+struct GptHeader {
+    /// "EFI PART"
+    /*0    */
+    signature: [u8; 8],
+    /// GPT revision, 0x00010000 for 1.0
+    /*8    */
+    revision: u32,
+    /// Size of the header, the CRC32 is computed on these bytes
+    /*C    */
+    header_size: u32,
+    /// CRC32 of the header, computed with this field zeroed
+    /*10   */
+    header_crc32: u32,
+    /*14   */
+    reserved: u32,
+    /// LBA of this header
+    /*18   */
+    current_lba: u64,
+    /// LBA of the other header
+    /*20   */
+    backup_lba: u64,
+    /// First sector usable by the partitions
+    /*28   */
+    first_usable_lba: u64,
+    /// Last sector usable by the partitions
+    /*30   */
+    last_usable_lba: u64,
+    /*38   */
+    disk_guid: Guid,
+    /// Start of the partition entry array
+    /*48   */
+    partition_entries_lba: u64,
+    /*50   */
+    nbr_partition_entries: u32,
+    /// Size of an entry, 128 * 2^n
+    /*54   */
+    partition_entry_size: u32,
+    /// CRC32 of the partition entry array
+    /*58   */
+    partition_entries_crc32: u32,
+    /*5C   */
+}
+
+/// Defined real GPT partition entry structure
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+#[repr(packed)]
+struct GptEntry {
+    /// Partition type, null for an unused entry
+    /*0    */
+    type_guid: Guid,
+    /*10   */
+    unique_guid: Guid,
+    /*20   */
+    first_lba: u64,
+    /// Last sector of the partition (inclusive)
+    /*28   */
+    last_lba: u64,
+    /*30   */
+    attributes: u64,
+    /*38   */
+    name: PartitionName,
+    /*80   */
+}
+
+const GPT_SIGNATURE: [u8; 8] = *b"EFI PART";
+
+/// Offset of the header_crc32 field, zeroed during the CRC32 computation
+const HEADER_CRC32_OFFSET: usize = 0x10;
+
+/// Limit the number of entries to keep a corrupted header from making us read the whole disk
+const MAX_PARTITION_ENTRIES: u32 = 1024;
+
+/// Decode the GPT of the disk, fallback on the backup GPT when the primary is corrupted
+pub(crate) fn read_gpt<R: SectorRead>(
+    disk: &mut R,
+    disk_sectors: u64,
+) -> MbrResult<(Scheme, Vec<PartitionEntry>)> {
+    let primary_error = match read_gpt_at(disk, 1, disk_sectors) {
+        Ok((disk_guid, partitions)) => {
+            return Ok((
+                Scheme::Gpt {
+                    disk_guid,
+                    from_backup: false,
+                },
+                partitions,
+            ))
+        }
+        Err(e) => e,
+    };
+    if let MbrError::OutOfMemory = primary_error {
+        return Err(primary_error);
+    }
+    let backup_lba = disk_sectors.checked_sub(1).ok_or(primary_error)?;
+    let (disk_guid, partitions) = read_gpt_at(disk, backup_lba, disk_sectors)?;
+    Ok((
+        Scheme::Gpt {
+            disk_guid,
+            from_backup: true,
+        },
+        partitions,
+    ))
+}
+
+/// Decode and check the GPT header located at `lba` and its partition entry array
+fn read_gpt_at<R: SectorRead>(
+    disk: &mut R,
+    lba: u64,
+    disk_sectors: u64,
+) -> MbrResult<(Guid, Vec<PartitionEntry>)> {
+    let mut sector = [0; SECTOR_SIZE];
+    disk.read_sector(lba, &mut sector)?;
+    let header = check_header(&mut sector, lba, disk_sectors)?;
+
+    let entry_size = header.partition_entry_size as usize;
+    let array_size = header.nbr_partition_entries as usize * entry_size;
+    let mut crc = Crc32::new();
+    let mut partitions = Vec::new();
+
+    // An entry size is a power of two greater than 128: the entries never straddle two sectors
+    let mut offset = 0;
+    let mut entries_lba = header.partition_entries_lba;
+    while offset < array_size {
+        disk.read_sector(entries_lba, &mut sector)?;
+        let len = core::cmp::min(SECTOR_SIZE, array_size - offset);
+        crc.update(&sector[..len]);
+
+        let mut entry_offset = (entry_size - offset % entry_size) % entry_size;
+        while entry_offset < len {
+            let index = (offset + entry_offset) / entry_size;
+            let entry: GptEntry =
+                unsafe { read_unaligned(sector[entry_offset..].as_ptr() as *const GptEntry) };
+            let type_guid = entry.type_guid;
+            if type_guid != Guid::UNUSED
+                && entry.first_lba <= entry.last_lba
+                && entry.first_lba >= header.first_usable_lba
+                && entry.last_lba <= header.last_usable_lba
+            {
+                partitions
+                    .try_push(PartitionEntry {
+                        number: index as u32 + 1,
+                        start: entry.first_lba,
+                        size: entry.last_lba - entry.first_lba + 1,
+                        kind: PartitionKind::Gpt {
+                            type_guid,
+                            unique_guid: entry.unique_guid,
+                            attributes: entry.attributes,
+                            name: entry.name,
+                        },
+                    })
+                    .map_err(|_| MbrError::OutOfMemory)?;
+            }
+            entry_offset += entry_size;
+        }
+        offset += len;
+        entries_lba += 1;
+    }
+    if crc.finish() != header.partition_entries_crc32 {
+        return Err(MbrError::CorruptedGpt);
+    }
+    Ok((header.disk_guid, partitions))
+}
+
+/// Check the signature, the CRC32 and the consistency of the GPT header contained in `sector`
+fn check_header(
+    sector: &mut [u8; SECTOR_SIZE],
+    lba: u64,
+    disk_sectors: u64,
+) -> MbrResult<GptHeader> {
+    let header: GptHeader = unsafe { read_unaligned(sector.as_ptr() as *const GptHeader) };
+    let signature = header.signature;
+    let header_size = header.header_size as usize;
+    let entry_size = header.partition_entry_size;
+
+    if signature != GPT_SIGNATURE
+        || header_size < size_of::<GptHeader>()
+        || header_size > SECTOR_SIZE
+    {
+        return Err(MbrError::CorruptedGpt);
+    }
+    for byte in &mut sector[HEADER_CRC32_OFFSET..HEADER_CRC32_OFFSET + 4] {
+        *byte = 0;
+    }
+    if crc32(&sector[..header_size]) != header.header_crc32 {
+        return Err(MbrError::CorruptedGpt);
+    }
+    if header.current_lba != lba
+        || header.first_usable_lba > header.last_usable_lba
+        || header.last_usable_lba >= disk_sectors
+        || entry_size < 128
+        || !entry_size.is_power_of_two()
+        || header.nbr_partition_entries > MAX_PARTITION_ENTRIES
+    {
+        return Err(MbrError::CorruptedGpt);
+    }
+    Ok(header)
+}
@@ -1,9 +1,22 @@
-//! This crate provide methods to read Master Boot record
+//! This crate provide methods to read Master Boot record, the extended partitions and the GUID Partition Table
 #![cfg_attr(not(test), no_std)]
 
+extern crate alloc;
+
+mod crc32;
+mod ebr;
+mod gpt;
+pub use crc32::{crc32, Crc32};
+pub use gpt::{Guid, PartitionName};
+
+use alloc::vec::Vec;
 use core::mem;
+use fallible_collections::FallibleVec;
 use raw_data::define_raw_data;
 
+/// Size of a disk sector
+pub const SECTOR_SIZE: usize = 512;
+
 /// Main crate structure
 #[derive(Debug, Copy, Clone)]
 pub struct Mbr {
@@ -31,12 +44,30 @@ impl Partition {
         // an unused entry. from Osdev
         (self.part_type != PartitionType::Empty)
     }
+    /// return the System ID of the partition
+    pub fn part_type(&self) -> PartitionType {
+        self.part_type
+    }
 }
 
+/// System ID of a MBR partition
 #[derive(Debug, Copy, Clone, PartialEq)]
-enum PartitionType {
+pub enum PartitionType {
+    /// Linux native partition (0x83)
     LinuxExtendedPartition,
+    /// Linux swap (0x82)
+    LinuxSwap,
     Dos12bitsFat,
+    /// FAT16 (0x04, 0x06 and 0x0E)
+    Dos16bitsFat,
+    /// FAT32 (0x0B and 0x0C)
+    Fat32,
+    /// NTFS or exFAT (0x07)
+    Ntfs,
+    /// Container of logical partitions (0x05, 0x0F and 0x85)
+    Extended,
+    /// The whole disk is described by a GPT (0xEE)
+    GptProtective,
     Empty,
     Unknown,
 }
@@ -98,6 +129,12 @@ pub type MbrResult<T> = core::result::Result<T, MbrError>;
 pub enum MbrError {
     /// Not a valid MBR structure
     UnknownStructure,
+    /// Neither the primary nor the backup GPT is valid
+    CorruptedGpt,
+    /// The disk cannot be read
+    IoError,
+    /// Cannot allocate the partition list
+    OutOfMemory,
 }
 
 impl Mbr {
@@ -131,15 +168,340 @@ impl From<u8> for PartitionType {
         use PartitionType::*;
         match part_number {
             0x83 => LinuxExtendedPartition,
+            0x82 => LinuxSwap,
             0x01 => Dos12bitsFat,
+            0x04 | 0x06 | 0x0E => Dos16bitsFat,
+            0x0B | 0x0C => Fat32,
+            0x07 => Ntfs,
+            0x05 | 0x0F | 0x85 => Extended,
+            0xEE => GptProtective,
             0x00 => Empty,
             _ => Unknown,
         }
     }
 }
 
+/// Source of the sectors of a disk, used to decode its partition table
+pub trait SectorRead {
+    /// Read the sector `lba` into `buf`
+    fn read_sector(&mut self, lba: u64, buf: &mut [u8; SECTOR_SIZE]) -> MbrResult<()>;
+}
+
+/// Partitioning scheme of a disk
+#[derive(Debug, Copy, Clone)]
+pub enum Scheme {
+    /// MBR with its optional chain of EBR
+    Mbr,
+    /// GPT behind a protective MBR. `from_backup` is set when the primary GPT was corrupted
+    Gpt { disk_guid: Guid, from_backup: bool },
+}
+
+/// Kind of a partition, depending on the scheme
+#[derive(Debug, Copy, Clone)]
+pub enum PartitionKind {
+    Mbr {
+        part_type: PartitionType,
+        bootable: bool,
+    },
+    Gpt {
+        type_guid: Guid,
+        unique_guid: Guid,
+        attributes: u64,
+        name: PartitionName,
+    },
+}
+
+/// A partition of the disk, whatever the scheme
+#[derive(Debug, Copy, Clone)]
+pub struct PartitionEntry {
+    /// Number of the partition, like N in /dev/sdaN: 1 to 4 for the primary partitions
+    /// and 5 and more for the logical ones, the entry index plus one on GPT
+    pub number: u32,
+    /// First sector
+    pub start: u64,
+    /// Number of sectors
+    pub size: u64,
+    pub kind: PartitionKind,
+}
+
+impl PartitionEntry {
+    /// Check if the partition is declared as containing a Linux filesystem
+    pub fn is_linux_filesystem(&self) -> bool {
+        match self.kind {
+            PartitionKind::Mbr { part_type, .. } => {
+                part_type == PartitionType::LinuxExtendedPartition
+            }
+            PartitionKind::Gpt { type_guid, .. } => type_guid == Guid::LINUX_FILESYSTEM,
+        }
+    }
+}
+
+/// The partitions of a disk
+#[derive(Debug)]
+pub struct PartitionTable {
+    pub scheme: Scheme,
+    /// Used partitions, sorted by number
+    pub partitions: Vec<PartitionEntry>,
+}
+
+/// Decode the partition table of a disk of `disk_sectors` sectors:
+/// The primary partitions and the logical ones for a MBR, the partition entries for a GPT
+pub fn read_partition_table<R: SectorRead>(
+    disk: &mut R,
+    disk_sectors: u64,
+) -> MbrResult<PartitionTable> {
+    let mut sector = [0; SECTOR_SIZE];
+    disk.read_sector(0, &mut sector)?;
+    let mbr = unsafe { Mbr::new(&sector) };
+    if !mbr.bootable {
+        return Err(MbrError::UnknownStructure);
+    }
+
+    if mbr
+        .parts
+        .iter()
+        .any(|part| part.part_type == PartitionType::GptProtective)
+    {
+        let (scheme, partitions) = gpt::read_gpt(disk, disk_sectors)?;
+        return Ok(PartitionTable { scheme, partitions });
+    }
+
+    let mut partitions = Vec::new();
+    let mut extended = None;
+    for (i, part) in mbr.parts.iter().enumerate() {
+        match part.part_type {
+            PartitionType::Empty => {}
+            // Only one extended partition is allowed, the others are ignored
+            PartitionType::Extended => {
+                extended = extended.or(Some((part.start as u64, part.size as u64)))
+            }
+            part_type => partitions
+                .try_push(PartitionEntry {
+                    number: i as u32 + 1,
+                    start: part.start as u64,
+                    size: part.size as u64,
+                    kind: PartitionKind::Mbr {
+                        part_type,
+                        bootable: part.is_bootable(),
+                    },
+                })
+                .map_err(|_| MbrError::OutOfMemory)?,
+        }
+    }
+    if let Some((start, size)) = extended {
+        ebr::read_logical_partitions(disk, start, size, &mut partitions)?;
+    }
+    Ok(PartitionTable {
+        scheme: Scheme::Mbr,
+        partitions,
+    })
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    /// A disk in memory
+    struct MemoryDisk(Vec<[u8; SECTOR_SIZE]>);
+
+    impl SectorRead for MemoryDisk {
+        fn read_sector(&mut self, lba: u64, buf: &mut [u8; SECTOR_SIZE]) -> MbrResult<()> {
+            *buf = *self.0.get(lba as usize).ok_or(MbrError::IoError)?;
+            Ok(())
+        }
+    }
+
+    const DISK_SECTORS: u64 = 2048;
+
+    fn set_u32(sector: &mut [u8], offset: usize, value: u32) {
+        sector[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn set_u64(sector: &mut [u8], offset: usize, value: u64) {
+        sector[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Write a MBR partition entry and the boot signature
+    fn set_mbr_entry(sector: &mut [u8], index: usize, part_type: u8, start: u32, size: u32) {
+        let offset = 0x1BE + index * 16;
+        sector[offset + 4] = part_type;
+        set_u32(sector, offset + 8, start);
+        set_u32(sector, offset + 12, size);
+        sector[0x1FE] = 0x55;
+        sector[0x1FF] = 0xAA;
+    }
+
+    fn new_disk() -> MemoryDisk {
+        MemoryDisk(vec![[0; SECTOR_SIZE]; DISK_SECTORS as usize])
+    }
+
+    /// Write a GPT header at `lba` whose 128 entries are at `entries_lba`
+    fn write_gpt(disk: &mut MemoryDisk, lba: u64, backup_lba: u64, entries_lba: u64) {
+        let mut entries = vec![0; 128 * 128];
+        let names = ["boot", "root"];
+        let types = [Guid::BIOS_BOOT, Guid::LINUX_FILESYSTEM];
+        for i in 0..2 {
+            let entry = &mut entries[i * 128..(i + 1) * 128];
+            entry[0..16].copy_from_slice(&types[i].0);
+            entry[16] = i as u8 + 1;
+            set_u64(entry, 0x20, 34 + i as u64 * 100);
+            set_u64(entry, 0x28, 34 + i as u64 * 100 + 99);
+            for (j, c) in names[i].encode_utf16().enumerate() {
+                entry[0x38 + j * 2..0x38 + j * 2 + 2].copy_from_slice(&c.to_le_bytes());
+            }
+        }
+        for (i, chunk) in entries.chunks(SECTOR_SIZE).enumerate() {
+            disk.0[entries_lba as usize + i].copy_from_slice(chunk);
+        }
+
+        let header = &mut disk.0[lba as usize];
+        header[0..8].copy_from_slice(b"EFI PART");
+        set_u32(header, 0x08, 0x0001_0000);
+        set_u32(header, 0x0C, 92);
+        set_u64(header, 0x18, lba);
+        set_u64(header, 0x20, backup_lba);
+        set_u64(header, 0x28, 34);
+        set_u64(header, 0x30, DISK_SECTORS - 34);
+        header[0x38] = 0x42;
+        set_u64(header, 0x48, entries_lba);
+        set_u32(header, 0x50, 128);
+        set_u32(header, 0x54, 128);
+        set_u32(header, 0x58, crc32(&entries));
+        let crc = crc32(&header[..92]);
+        set_u32(header, 0x10, crc);
+    }
+
+    fn gpt_disk() -> MemoryDisk {
+        let mut disk = new_disk();
+        set_mbr_entry(&mut disk.0[0], 0, 0xEE, 1, DISK_SECTORS as u32 - 1);
+        write_gpt(&mut disk, 1, DISK_SECTORS - 1, 2);
+        write_gpt(&mut disk, DISK_SECTORS - 1, 1, DISK_SECTORS - 33);
+        disk
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn guid_display() {
+        assert_eq!(
+            format!("{}", Guid::LINUX_FILESYSTEM),
+            "0FC63DAF-8483-4772-8E79-3D69D8477DE4"
+        );
+    }
+
     #[test]
-    fn it_works() {}
+    fn mbr_with_logical_partitions() {
+        let mut disk = new_disk();
+        set_mbr_entry(&mut disk.0[0], 0, 0x83, 1, 99);
+        set_mbr_entry(&mut disk.0[0], 1, 0x05, 100, 900);
+        // first EBR: a logical partition then a link to the next EBR
+        set_mbr_entry(&mut disk.0[100], 0, 0x83, 1, 199);
+        set_mbr_entry(&mut disk.0[100], 1, 0x05, 200, 300);
+        // second EBR: the last logical partition
+        set_mbr_entry(&mut disk.0[300], 0, 0x82, 1, 99);
+
+        let table = read_partition_table(&mut disk, DISK_SECTORS).unwrap();
+        assert!(match table.scheme {
+            Scheme::Mbr => true,
+            _ => false,
+        });
+        let parts: Vec<(u32, u64, u64)> = table
+            .partitions
+            .iter()
+            .map(|p| (p.number, p.start, p.size))
+            .collect();
+        assert_eq!(parts, vec![(1, 1, 99), (5, 101, 199), (6, 301, 99)]);
+        assert!(table.partitions[1].is_linux_filesystem());
+        assert!(!table.partitions[2].is_linux_filesystem());
+    }
+
+    #[test]
+    fn ebr_loop_stops_the_chain() {
+        let mut disk = new_disk();
+        set_mbr_entry(&mut disk.0[0], 0, 0x05, 100, 900);
+        set_mbr_entry(&mut disk.0[100], 0, 0x83, 1, 9);
+        set_mbr_entry(&mut disk.0[100], 1, 0x05, 100, 10);
+        // the EBR at 300 links back to the one at 200
+        set_mbr_entry(&mut disk.0[200], 0, 0x83, 1, 9);
+        set_mbr_entry(&mut disk.0[200], 1, 0x05, 200, 10);
+        set_mbr_entry(&mut disk.0[300], 0, 0x83, 1, 9);
+        set_mbr_entry(&mut disk.0[300], 1, 0x05, 100, 10);
+        let table = read_partition_table(&mut disk, DISK_SECTORS).unwrap();
+        let parts: Vec<(u32, u64, u64)> = table
+            .partitions
+            .iter()
+            .map(|p| (p.number, p.start, p.size))
+            .collect();
+        assert_eq!(parts, vec![(5, 101, 9), (6, 201, 9), (7, 301, 9)]);
+    }
+
+    #[test]
+    fn not_a_partition_table() {
+        let mut disk = new_disk();
+        assert!(read_partition_table(&mut disk, DISK_SECTORS).is_err());
+    }
+
+    #[test]
+    fn gpt() {
+        let mut disk = gpt_disk();
+        let table = read_partition_table(&mut disk, DISK_SECTORS).unwrap();
+        match table.scheme {
+            Scheme::Gpt {
+                disk_guid,
+                from_backup,
+            } => {
+                assert_eq!(disk_guid.0[0], 0x42);
+                assert!(!from_backup);
+            }
+            _ => panic!("not a GPT"),
+        }
+        assert_eq!(table.partitions.len(), 2);
+        let root = &table.partitions[1];
+        assert_eq!((root.number, root.start, root.size), (2, 134, 100));
+        assert!(root.is_linux_filesystem());
+        match root.kind {
+            PartitionKind::Gpt {
+                unique_guid, name, ..
+            } => {
+                assert_eq!(unique_guid.0[0], 2);
+                assert_eq!(name.chars().collect::<String>(), "root");
+            }
+            _ => panic!("not a GPT partition"),
+        }
+    }
+
+    #[test]
+    fn gpt_corrupted_header_fallback_on_backup() {
+        let mut disk = gpt_disk();
+        disk.0[1][0x28] ^= 1;
+        let table = read_partition_table(&mut disk, DISK_SECTORS).unwrap();
+        assert!(match table.scheme {
+            Scheme::Gpt { from_backup, .. } => from_backup,
+            _ => false,
+        });
+        assert_eq!(table.partitions.len(), 2);
+    }
+
+    #[test]
+    fn gpt_corrupted_entries_fallback_on_backup() {
+        let mut disk = gpt_disk();
+        disk.0[2][0x20] ^= 1;
+        let table = read_partition_table(&mut disk, DISK_SECTORS).unwrap();
+        assert!(match table.scheme {
+            Scheme::Gpt { from_backup, .. } => from_backup,
+            _ => false,
+        });
+        assert_eq!(table.partitions[0].start, 34);
+    }
+
+    #[test]
+    fn gpt_both_corrupted() {
+        let mut disk = gpt_disk();
+        disk.0[1][0x28] ^= 1;
+        disk.0[DISK_SECTORS as usize - 1][0x28] ^= 1;
+        assert!(read_partition_table(&mut disk, DISK_SECTORS).is_err());
+    }
 }
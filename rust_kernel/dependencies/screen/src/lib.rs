@@ -25,12 +25,25 @@ pub enum IoError {
     NotSupported,
 }
 
+/// Graphic attributes of a character cell
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CellStyle {
+    /// Color of the character
+    pub foreground: AnsiColor,
+    /// Color of the cell, None lets the background image visible
+    pub background: Option<AnsiColor>,
+    /// Draw a line under the character
+    pub underline: bool,
+}
+
 /// Drawer is a common trait between VGA and VBE interfaces
 pub trait Drawer {
     /// Return window size in nb char
     fn query_window_size(&self) -> Pos;
     /// Draw a character
     fn draw_character(&mut self, c: char, position: Pos, color: AnsiColor) -> IoResult;
+    /// Draw a character with a background color and attributes
+    fn draw_styled_character(&mut self, c: char, position: Pos, style: CellStyle) -> IoResult;
     /// Erase a cursor (must specify color and character)
     fn clear_cursor(&mut self, c: char, position: Pos, color: AnsiColor) -> IoResult;
     /// Draw a cursor (must specify color and character)
@@ -110,6 +123,13 @@ impl Drawer for ScreenMonad {
             DrawingMode::Vbe(vbe) => vbe.draw_character(c, position, color),
         }
     }
+    fn draw_styled_character(&mut self, c: char, position: Pos, style: CellStyle) -> IoResult {
+        self.check_bound(position)?;
+        match &mut self.drawing_mode {
+            DrawingMode::Vga(vga) => vga.draw_styled_character(c, position, style),
+            DrawingMode::Vbe(vbe) => vbe.draw_styled_character(c, position, style),
+        }
+    }
     fn clear_screen(&mut self) {
         match &mut self.drawing_mode {
             DrawingMode::Vga(vga) => vga.clear_screen(),
@@ -5,7 +5,7 @@ pub use init::*;
 mod rgb;
 use rgb::RGB;
 
-use super::{AdvancedGraphic, Drawer, IoError, IoResult, Pos};
+use super::{AdvancedGraphic, CellStyle, Drawer, IoError, IoResult, Pos};
 use alloc::vec;
 use alloc::vec::Vec;
 use ansi_escape_code::AnsiColor;
@@ -119,6 +119,15 @@ impl VbeMode {
         }
     }
 
+    /// write a line under a character
+    #[inline(always)]
+    fn write_underline(&mut self, line: usize, column: usize, color: RGB) {
+        let y = line * self.char_height + self.char_height - 2;
+        for x in column * self.char_width..(column + 1) * self.char_width {
+            self.put_pixel(y, x, color);
+        }
+    }
+
     /// copy one bounded area line from graphic buffer to db frame buffer
     fn copy_graphic_buffer_line_area(&mut self, line: usize, column_1: usize, column_2: usize) {
         for i in 0..self.char_height {
@@ -148,6 +157,20 @@ impl Drawer for VbeMode {
         Ok(())
     }
 
+    fn draw_styled_character(&mut self, c: char, position: Pos, style: CellStyle) -> IoResult {
+        self.copy_graphic_buffer_line_area(position.line, position.column, position.column + 1);
+
+        let font = unsafe { _font.get_char(c as u8) };
+        if let Some(background) = style.background {
+            self.write_cursor(font, position.line, position.column, background.into());
+        }
+        self.write_char(font, position.line, position.column, style.foreground.into());
+        if style.underline {
+            self.write_underline(position.line, position.column, style.foreground.into());
+        }
+        Ok(())
+    }
+
     fn clear_screen(&mut self) {
         // Copy the entire graphic buffer
         unsafe {
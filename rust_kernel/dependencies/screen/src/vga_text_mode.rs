@@ -1,4 +1,4 @@
-use super::{Drawer, IoResult, Pos};
+use super::{CellStyle, Drawer, IoResult, Pos};
 use ansi_escape_code::{AnsiColor, StandardColor};
 use io::{Io, Pio};
 
@@ -35,6 +35,19 @@ impl Drawer for VgaTextMode {
         Ok(())
     }
 
+    fn draw_styled_character(&mut self, c: char, position: Pos, style: CellStyle) -> IoResult {
+        // The text mode has no underline and only 8 background colors
+        let background = style
+            .background
+            .map(|color| Into::<VgaColor>::into(color).0 & 0x7)
+            .unwrap_or(0);
+        self.memory_location[position.column + position.line * WIDTH] = (
+            c as u8,
+            Into::<VgaColor>::into(style.foreground).0 | background << 4,
+        );
+        Ok(())
+    }
+
     fn clear_screen(&mut self) {
         // get the current cursor position
         Pio::<u8>::new(Self::CURSOR_INDEX_REGISTER).write(0x0F);
@@ -118,6 +131,10 @@ impl From<AnsiColor> for VgaColor {
         match c {
             // Convert only the 8 Standard Ansi color
             AnsiColor::Standard(c) => c.into(),
+            // The bright colors set the intensity bit
+            AnsiColor::HighIntensity(c) => {
+                VgaColor(Into::<VgaColor>::into(AnsiColor::from(c & 0x7)).0 | 0x8)
+            }
             // Otherwise set default to white
             _ => VgaColor(7),
         }
@@ -0,0 +1,36 @@
+//! The locks need to know which processor takes them. The kernel gives the
+//! functions getting the current processor and waiting in a spin loop with
+//! `set_cpu_hooks`; without them, everything runs on the processor 0
+
+use core::sync::atomic::{spin_loop_hint, AtomicUsize, Ordering};
+
+/// The owner of a free lock
+pub(crate) const NO_OWNER: usize = core::usize::MAX;
+
+static CURRENT_CPU_HOOK: AtomicUsize = AtomicUsize::new(0);
+static RELAX_HOOK: AtomicUsize = AtomicUsize::new(0);
+
+/// Set the function which gives the index of the current processor, and the
+/// function called by each iteration of a spin loop (ex: to answer the IPIs
+/// while spinning with the interrupts disabled)
+pub fn set_cpu_hooks(current_cpu: fn() -> usize, relax: fn()) {
+    CURRENT_CPU_HOOK.store(current_cpu as usize, Ordering::SeqCst);
+    RELAX_HOOK.store(relax as usize, Ordering::SeqCst);
+}
+
+/// Get the index of the current processor
+pub fn current_cpu() -> usize {
+    match CURRENT_CPU_HOOK.load(Ordering::Relaxed) {
+        0 => 0,
+        hook => unsafe { core::mem::transmute::<usize, fn() -> usize>(hook)() },
+    }
+}
+
+/// Wait a little in a spin loop
+pub fn relax() {
+    spin_loop_hint();
+    match RELAX_HOOK.load(Ordering::Relaxed) {
+        0 => {}
+        hook => unsafe { core::mem::transmute::<usize, fn()>(hook)() },
+    }
+}
@@ -1,33 +1,39 @@
-use core::sync::atomic::{AtomicBool, Ordering};
+use crate::cpu::{current_cpu, relax, NO_OWNER};
+use core::sync::atomic::{AtomicUsize, Ordering};
 use lock_api::{GuardSend, RawMutex};
 
 // 1. Define our raw lock type
+/// The lock waits while another processor holds it. Taking it again on the
+/// processor which holds it cannot end: that is a dead lock
 #[derive(Debug)]
-pub struct RawDeadMutex(AtomicBool);
+pub struct RawDeadMutex(AtomicUsize);
 
 // 2. Implement RawMutex for this type
 unsafe impl RawMutex for RawDeadMutex {
-    const INIT: RawDeadMutex = RawDeadMutex(AtomicBool::new(false));
+    const INIT: RawDeadMutex = RawDeadMutex(AtomicUsize::new(NO_OWNER));
 
     // A spinlock guard can be sent to another thread and unlocked there
     type GuardMarker = GuardSend;
 
     fn lock(&self) {
-        if !self.try_lock() {
-            panic!("dead lock from {:?}", self);
+        let cpu = current_cpu();
+        loop {
+            match self.0.compare_and_swap(NO_OWNER, cpu, Ordering::Acquire) {
+                NO_OWNER => return,
+                owner if owner == cpu => panic!("dead lock from {:?}", self),
+                _ => relax(),
+            }
         }
     }
 
     fn try_lock(&self) -> bool {
-        if self.0.load(Ordering::SeqCst) {
-            return false;
-        }
-        self.0.swap(true, Ordering::SeqCst);
-        return true;
+        self.0
+            .compare_and_swap(NO_OWNER, current_cpu(), Ordering::Acquire)
+            == NO_OWNER
     }
 
     fn unlock(&self) {
-        self.0.store(false, Ordering::SeqCst);
+        self.0.store(NO_OWNER, Ordering::Release);
     }
 }
 
@@ -1,7 +1,9 @@
 #![cfg_attr(not(test), no_std)]
 extern crate alloc;
+pub mod cpu;
+pub use cpu::set_cpu_hooks;
 pub mod spinlock;
-pub use spinlock::{Spinlock, SpinlockGuard};
+pub use spinlock::{ReentrantSpinlock, ReentrantSpinlockGuard, Spinlock, SpinlockGuard};
 pub mod dead_mutex;
 pub use dead_mutex::{DeadMutex, DeadMutexGuard};
 pub mod lock_forest;
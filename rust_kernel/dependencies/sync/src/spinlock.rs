@@ -1,7 +1,10 @@
+use crate::cpu::{current_cpu, relax};
 use core::sync::atomic::{AtomicBool, Ordering};
-use lock_api::{GuardSend, RawMutex};
+use lock_api::{GetThreadId, GuardSend, RawMutex};
 
 // 1. Define our raw lock type
+/// The lock excludes the other processors and the other threads. It is not reentrant:
+/// taking it again with the interrupts disabled on the processor which holds it never ends
 #[derive(Debug)]
 pub struct RawSpinlock(AtomicBool);
 
@@ -13,20 +16,110 @@ unsafe impl RawMutex for RawSpinlock {
     type GuardMarker = GuardSend;
 
     fn lock(&self) {
-        if !self.try_lock() {
-            panic!("dead lock");
+        while !self.try_lock() {
+            relax();
         }
     }
 
     fn try_lock(&self) -> bool {
-        return true;
+        !self.0.compare_and_swap(false, true, Ordering::Acquire)
     }
 
     fn unlock(&self) {
-        self.0.store(false, Ordering::SeqCst);
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// The owner of a ReentrantSpinlock is the processor which holds it
+pub struct RawCpuId;
+
+unsafe impl GetThreadId for RawCpuId {
+    const INIT: RawCpuId = RawCpuId;
+
+    fn nonzero_thread_id(&self) -> usize {
+        current_cpu() + 1
     }
 }
 
 // 3. Export the wrappers. This are the types that your users will actually use.
 pub type Spinlock<T> = lock_api::Mutex<RawSpinlock, T>;
 pub type SpinlockGuard<'a, T> = lock_api::MutexGuard<'a, RawSpinlock, T>;
+
+/// The processor which holds it can take it again, so it only gives shared references.
+/// It must not be preempted while holding it
+pub type ReentrantSpinlock<T> = lock_api::ReentrantMutex<RawSpinlock, RawCpuId, T>;
+pub type ReentrantSpinlockGuard<'a, T> =
+    lock_api::ReentrantMutexGuard<'a, RawSpinlock, RawCpuId, T>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::set_cpu_hooks;
+    use std::cell::Cell;
+    use std::sync::Arc;
+    use std::thread;
+
+    thread_local! {
+        static CPU: Cell<usize> = Cell::new(0);
+    }
+
+    /// Each thread of the test is a processor
+    fn thread_cpu() -> usize {
+        CPU.with(|cpu| cpu.get())
+    }
+
+    #[test]
+    fn spinlock_excludes_other_cpus() {
+        set_cpu_hooks(thread_cpu, || {});
+        let counter = Arc::new(Spinlock::new(0u64));
+        let threads: Vec<_> = (1..5)
+            .map(|cpu| {
+                let counter = counter.clone();
+                thread::spawn(move || {
+                    CPU.with(|c| c.set(cpu));
+                    for _ in 0..1000 {
+                        let mut counter = counter.lock();
+                        let value = *counter;
+                        thread::yield_now();
+                        *counter = value + 1;
+                    }
+                })
+            })
+            .collect();
+        threads.into_iter().for_each(|t| t.join().unwrap());
+        assert_eq!(*counter.lock(), 4000);
+    }
+
+    #[test]
+    fn spinlock_is_not_reentrant() {
+        set_cpu_hooks(thread_cpu, || {});
+        let lock = Spinlock::new(());
+        let _guard = lock.lock();
+        assert!(lock.try_lock().is_none());
+    }
+
+    /// Try to take the lock from another processor
+    fn locked_by_another_cpu(lock: &Arc<ReentrantSpinlock<()>>) -> bool {
+        let lock = lock.clone();
+        thread::spawn(move || {
+            CPU.with(|c| c.set(7));
+            lock.try_lock().is_none()
+        })
+        .join()
+        .unwrap()
+    }
+
+    #[test]
+    fn reentrant_spinlock_is_reentrant_on_its_cpu() {
+        set_cpu_hooks(thread_cpu, || {});
+        let lock = Arc::new(ReentrantSpinlock::new(()));
+        let outer = lock.lock();
+        {
+            let _inner = lock.lock();
+        }
+        // The inner unlock does not release the lock
+        assert!(locked_by_another_cpu(&lock));
+        drop(outer);
+        assert!(!locked_by_another_cpu(&lock));
+    }
+}
@@ -20,14 +20,13 @@ mod tty;
 pub use tty::{BufferedTty, Scroll, Tty, WriteMode};
 
 mod line_discipline;
-use line_discipline::LineDiscipline;
-pub use line_discipline::ReadResult;
+pub use line_discipline::{LineDiscipline, ReadResult, TtyOutput};
 
 pub mod log;
 
 #[macro_use]
 pub mod uart_16550;
-pub use uart_16550::UART_16550;
+pub use uart_16550::{SerialOutput, UART_16550};
 
 use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
@@ -55,6 +54,8 @@ pub static mut TERMINAL: Option<Terminal> = None;
 #[derive(Debug, Clone)]
 pub struct Terminal {
     ttys: BTreeMap<usize, Box<LineDiscipline>>,
+    /// The line discipline of the serial tty over COM1
+    serial: Box<LineDiscipline<SerialOutput>>,
 }
 
 const MAX_SCREEN_BUFFER: usize = 10;
@@ -77,6 +78,7 @@ impl Terminal {
     pub fn new() -> Self {
         let mut res = Self {
             ttys: BTreeMap::new(),
+            serial: Box::new(LineDiscipline::new(SerialOutput)),
         };
         res.add_tty(SYSTEM_LOG_TTY_IDX);
         res
@@ -162,11 +164,11 @@ impl Terminal {
     }
 
     /// Read a Key from the buffer
-    pub fn read(&mut self, buf: &mut [u8], tty_index: usize) -> ReadResult {
+    pub fn read(&mut self, buf: &mut [u8], tty_index: usize, timer_expired: bool) -> ReadResult {
         self.ttys
             .get_mut(&tty_index)
             .expect("Cannot read from non existant TTY")
-            .read(buf)
+            .read(buf, timer_expired)
     }
 
     /// Get the TTY n
@@ -182,6 +184,11 @@ impl Terminal {
         self.ttys.get_mut(&tty_index).expect("WTF")
     }
 
+    /// Get the line discipline of the serial tty
+    pub fn get_serial_line_discipline(&mut self) -> &mut LineDiscipline<SerialOutput> {
+        &mut self.serial
+    }
+
     /// Handle the ketPressed for special TTY changes. Report a foreground TTY modification
     pub fn handle_key_pressed(
         &mut self,
@@ -1,28 +1,77 @@
 use super::tty::{BufferedTty, Scroll, Tty};
-use ansi_escape_code::CursorMove;
 use arrayvec::{ArrayVec, CapacityError};
 use core::cmp::min;
 use core::convert::TryFrom;
 use core::fmt::Write;
 use keyboard::{KeySymb, ScanCode};
-use libc_binding::{termios, Pid, Signum, ECHO, ICANON, ISIG, TOSTOP};
-use libc_binding::{VEOF, VERASE, VINTR, VKILL, VQUIT, VSUSP};
+use libc_binding::{termios, Pid, Signum, ECHO, ICANON, ISIG};
+use libc_binding::{ICRNL, IGNCR, INLCR, OCRNL, ONLCR, OPOST};
+use libc_binding::{VEOF, VERASE, VINTR, VKILL, VMIN, VQUIT, VSUSP, VTIME};
 use messaging::{MessageTo, ProcessGroupMessage};
 
-// extern "C" {
-//     fn get_current_pgid() -> Pid;
-// }
-// use libc_binding::{VEOL, VMIN, VSTART, VSTOP, VTIME};
+// use libc_binding::{VEOL, VSTART, VSTOP};
+
+/// The device on which a line discipline writes its output and its echo:
+/// the screen for the system ttys or the master side of a pseudo-terminal
+pub trait TtyOutput: core::fmt::Debug {
+    /// write `buf` on the device, returns the number of bytes written
+    fn write_bytes(&mut self, buf: &[u8]) -> usize;
+    /// erase the last echoed character
+    fn erase_char(&mut self);
+    /// erase the `nb_chars` last echoed characters of the current line
+    fn erase_line(&mut self, nb_chars: usize);
+}
+
+impl TtyOutput for BufferedTty {
+    fn write_bytes(&mut self, mut buf: &[u8]) -> usize {
+        let len = buf.len();
+        // The bytes which are not valid utf8 are displayed one by one
+        loop {
+            match core::str::from_utf8(buf) {
+                Ok(valid) => {
+                    self.tty.write_str(valid).expect("write failed");
+                    break;
+                }
+                Err(error) => {
+                    let (valid, after_valid) = buf.split_at(error.valid_up_to());
+                    self.tty
+                        .write_str(core::str::from_utf8(valid).unwrap())
+                        .expect("write failed");
+                    let invalid_len = error.error_len().unwrap_or(after_valid.len());
+                    for byte in &after_valid[..invalid_len] {
+                        self.tty.write_char(*byte as char).expect("write failed");
+                    }
+                    buf = &after_valid[invalid_len..];
+                }
+            }
+        }
+        len
+    }
+
+    fn erase_char(&mut self) {
+        self.as_mut().erase_char();
+    }
+
+    fn erase_line(&mut self, nb_chars: usize) {
+        for _ in 0..nb_chars {
+            self.as_mut().erase_char();
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
-pub struct LineDiscipline {
-    pub tty: BufferedTty,
+pub struct LineDiscipline<T = BufferedTty> {
+    pub tty: T,
     termios: termios,
     read_buffer: ArrayVec<[u8; 4096]>,
     foreground_process_group: Pid,
+    /// the session of which it is the controlling terminal, 0 if none
+    session: Pid,
     end_of_file_set: bool,
     /// raw mode doesn't transform scancode in utf8
     is_raw_mode: bool,
+    /// global ipc uid of the FileOperation reading on the line discipline
+    uid_file_op: Option<usize>,
 }
 
 /// represent a result of the read function to handle the blocking
@@ -34,24 +83,24 @@ pub enum ReadResult {
     NonBlocking(usize),
 }
 
-impl LineDiscipline {
-    pub fn new(tty: BufferedTty) -> Self {
+impl<T: TtyOutput> LineDiscipline<T> {
+    pub fn new(tty: T) -> Self {
         Self {
             termios: termios {
-                c_iflag: 0,
-                c_oflag: 0,
+                c_iflag: ICRNL,
+                c_oflag: (OPOST | ONLCR),
                 c_cflag: 0,
-                c_lflag: (ECHO | ICANON | ISIG | TOSTOP),
+                c_lflag: (ECHO | ICANON | ISIG),
                 c_cc: [
                     /*VEOF  */ KeySymb::Control_d as u32,
                     /*VEOL  */ KeySymb::Return as u32,
                     /*VERASE*/ KeySymb::Delete as u32,
                     /*VINTR */ KeySymb::Control_c as u32,
                     /*VKILL */ KeySymb::Control_u as u32,
-                    /*VMIN  */ KeySymb::Linefeed as u32,
+                    /*VMIN  */ 1,
                     /*VQUIT */ KeySymb::Control_backslash as u32,
                     /*VSUSP */ KeySymb::Control_z as u32,
-                    /*VTIME */ KeySymb::nul as u32,
+                    /*VTIME */ 0,
                     /*VSTART*/ KeySymb::nul as u32,
                     /*VSTOP */ KeySymb::nul as u32,
                 ],
@@ -59,142 +108,165 @@ impl LineDiscipline {
             tty,
             read_buffer: ArrayVec::new(),
             foreground_process_group: 0,
+            session: 0,
             end_of_file_set: false,
             is_raw_mode: false,
+            uid_file_op: None,
         }
     }
 
-    /// handle directly some keysymb to control the terminal
-    pub fn handle_tty_control(&mut self, keysymb: KeySymb) -> bool {
-        match keysymb {
-            KeySymb::Control_p => self.tty.as_mut().scroll(Scroll::Up),
-            KeySymb::Control_n => self.tty.as_mut().scroll(Scroll::Down),
-            KeySymb::Control_b => self.tty.as_mut().scroll(Scroll::HalfScreenUp),
-            KeySymb::Control_v => self.tty.as_mut().scroll(Scroll::HalfScreenDown),
-            _ => {
-                return false;
+    /// wake up the FileOperation reading on the line discipline
+    fn notify_reader(&self) {
+        if let Some(uid_file_op) = self.uid_file_op {
+            unsafe {
+                messaging::send_message(MessageTo::Reader { uid_file_op });
             }
-        };
-        true
+        }
     }
 
-    pub fn handle_scancode(&mut self, scancode: ScanCode) -> Result<(), CapacityError<u8>> {
-        if self.is_raw_mode {
-            // let keycode = KeyCode::from_scancode(scancode);
-            // dbg!(keycode);
-            self.read_buffer.try_push((scancode & 0xff) as u8)?;
-            self.read_buffer
-                .try_push(((scancode & 0xff00) >> 8) as u8)?;
+    /// send the signal `signum` to the foreground process group
+    fn signal_foreground(&self, signum: Signum) {
+        unsafe {
+            messaging::send_message(MessageTo::ProcessGroup {
+                pgid: self.foreground_process_group,
+                content: ProcessGroupMessage::Signal(signum),
+            });
         }
-        Ok(())
     }
-    /// write in the read buffer the keysymb read from the keyboard
-    /// Send a message if read is ready, depending of the lmode
-    pub fn handle_key_pressed(&mut self, key: KeySymb) -> Result<(), CapacityError<u8>> {
-        if !self.handle_tty_control(key) {
-            // Check if tty is attached to a file operator
-            if self.tty.uid_file_op.is_none() {
-                return Ok(());
+
+    /// write in the read buffer the bytes received from the input
+    /// device, handling the special characters of the termios.
+    /// Send a message if read is ready, depending of the lmode.
+    /// Returns the number of bytes consumed, which is less than the
+    /// input len when the read buffer is full
+    pub fn handle_input(&mut self, input: &[u8]) -> usize {
+        let mut echo_buffer: ArrayVec<[u8; 64]> = ArrayVec::new();
+        let mut read_ready = false;
+        let mut consumed = input.len();
+
+        for (i, c) in input.iter().map(|c| *c).enumerate() {
+            let c = match self.translate_input(c) {
+                Some(c) => c,
+                None => continue,
+            };
+            if self.handle_special_char(c) {
+                self.echo(&echo_buffer);
+                echo_buffer.clear();
+                continue;
             }
-            let mut encode_buff = [0; 8];
-            // handle special keys in canonical mode
-            if self.termios.c_lflag & ICANON != 0 {
-                // handle delete key
-                if key as u32 == self.termios.c_cc[VERASE as usize] {
-                    if self.read_buffer.pop().is_some() {
-                        self.tty.as_mut().move_cursor(CursorMove::Backward(1));
-                        self.tty.write_char(' ').unwrap();
-                        self.tty.as_mut().move_cursor(CursorMove::Backward(1));
-                    }
-                    return Ok(());;
-                }
-                // dbg!(key);
-                // handle kill key
-                if key as u32 == self.termios.c_cc[VKILL as usize] {
-                    self.tty
-                        .as_mut()
-                        .move_cursor(CursorMove::HorizontalAbsolute(0));
-                    if let Some(index) = self.read_buffer.iter().position(|c| *c == '\n' as u8) {
-                        self.read_buffer.truncate(index);
-                    } else {
-                        self.read_buffer.clear();
-                    }
+            /* PUSH THE CHARACTER */
+            if self.read_buffer.try_push(c).is_err() {
+                consumed = i;
+                break;
+            }
+            if self.termios.c_lflag & ICANON == 0 || c == '\n' as u8 {
+                read_ready = true;
+            }
+            if echo_buffer.is_full() {
+                self.echo(&echo_buffer);
+                echo_buffer.clear();
+            }
+            echo_buffer.push(c);
+        }
+        self.echo(&echo_buffer);
+        if read_ready {
+            self.notify_reader();
+        }
+        consumed
+    }
 
-                    for _ in 0..self.tty.as_mut().cursor.nb_columns - 1 {
-                        self.tty
-                            .as_mut()
-                            .write_char(' ')
-                            .expect("failed to write \0");
-                    }
-                    self.tty
-                        .as_mut()
-                        .move_cursor(CursorMove::HorizontalAbsolute(0));
+    /// apply the input modes (c_iflag) on the received byte `c`,
+    /// returns None if it must be ignored
+    fn translate_input(&self, c: u8) -> Option<u8> {
+        let iflag = self.termios.c_iflag;
+        match c {
+            b'\r' if iflag & IGNCR != 0 => None,
+            b'\r' if iflag & ICRNL != 0 => Some(b'\n'),
+            b'\n' if iflag & INLCR != 0 => Some(b'\r'),
+            _ => Some(c),
+        }
+    }
 
-                    return Ok(());;
-                }
-                if key as u32 == self.termios.c_cc[VEOF as usize] {
-                    self.end_of_file_set = true;
-                    unsafe {
-                        messaging::send_message(MessageTo::Reader {
-                            uid_file_op: self.tty.uid_file_op.expect("no FileOperation registered"),
-                        });;
-                    }
-                    return Ok(());;
+    /// handle the special characters of the canonical mode and the
+    /// signal generation. Returns true if `c` was consumed
+    fn handle_special_char(&mut self, c: u8) -> bool {
+        let c = c as u32;
+        // handle special keys in canonical mode
+        if self.termios.c_lflag & ICANON != 0 {
+            // handle delete key
+            if c == self.termios.c_cc[VERASE as usize] {
+                if self.read_buffer.pop().is_some() {
+                    self.tty.erase_char();
                 }
+                return true;
             }
-            if self.termios.c_lflag & ISIG != 0 {
-                // handle control_c
-                if key as u32 == self.termios.c_cc[VINTR as usize] {
-                    unsafe {
-                        messaging::send_message(MessageTo::ProcessGroup {
-                            pgid: self.foreground_process_group,
-                            content: ProcessGroupMessage::Signal(Signum::SIGINT),
-                        });
-                    }
-                    return Ok(());;
-                }
-                if key as u32 == self.termios.c_cc[VSUSP as usize] {
-                    unsafe {
-                        messaging::send_message(MessageTo::ProcessGroup {
-                            pgid: self.foreground_process_group,
-                            content: ProcessGroupMessage::Signal(Signum::SIGTSTP),
-                        });
-                    }
-                    return Ok(());;
-                }
-                if key as u32 == self.termios.c_cc[VQUIT as usize] {
-                    unsafe {
-                        messaging::send_message(MessageTo::ProcessGroup {
-                            pgid: self.foreground_process_group,
-                            content: ProcessGroupMessage::Signal(Signum::SIGQUIT),
-                        });
-                    }
-                    return Ok(());;
-                }
+            // handle kill key
+            if c == self.termios.c_cc[VKILL as usize] {
+                let line_start = self
+                    .read_buffer
+                    .iter()
+                    .rposition(|c| *c == '\n' as u8)
+                    .map(|index| index + 1)
+                    .unwrap_or(0);
+                let nb_chars = self.read_buffer.len() - line_start;
+                self.read_buffer.truncate(line_start);
+                self.tty.erase_line(nb_chars);
+                return true;
             }
-            /* PUSH THE KEY */
-            let b = encode_utf8(key, &mut encode_buff);
-            // dbg!((key as i32 & 0xff00) >> 8);
-            // dbg!(key as i32 & 0xff);
-            // dbg!(&b);
-            for elem in b {
-                // dbg!(&b);
-                self.read_buffer.try_push(*elem)?;
+            if c == self.termios.c_cc[VEOF as usize] {
+                self.end_of_file_set = true;
+                self.notify_reader();
+                return true;
             }
-            if (self.termios.c_lflag & ICANON != 0 && key == KeySymb::Return)
-                || !self.termios.c_lflag & ICANON != 0
-            {
-                unsafe {
-                    messaging::send_message(MessageTo::Reader {
-                        uid_file_op: self.tty.uid_file_op.expect("no FileOperation registered"),
-                    });;
-                }
+        }
+        if self.termios.c_lflag & ISIG != 0 {
+            // handle control_c
+            if c == self.termios.c_cc[VINTR as usize] {
+                self.signal_foreground(Signum::SIGINT);
+                return true;
             }
-            if self.termios.c_lflag & ECHO != 0 {
-                self.write(b);
+            if c == self.termios.c_cc[VSUSP as usize] {
+                self.signal_foreground(Signum::SIGTSTP);
+                return true;
+            }
+            if c == self.termios.c_cc[VQUIT as usize] {
+                self.signal_foreground(Signum::SIGQUIT);
+                return true;
             }
         }
-        Ok(())
+        false
+    }
+
+    /// echo the input on the output device if ECHO is set
+    fn echo(&mut self, s: &[u8]) {
+        if self.termios.c_lflag & ECHO != 0 && !s.is_empty() {
+            self.output(s);
+        }
+    }
+
+    /// write `s` on the output device, applying the output modes
+    /// (c_oflag) if OPOST is set. Returns the number of bytes of `s`
+    /// written
+    fn output(&mut self, mut s: &[u8]) -> usize {
+        let oflag = self.termios.c_oflag;
+        if oflag & OPOST == 0 {
+            return self.tty.write_bytes(s);
+        }
+        let mut written = 0;
+        while let Some(index) = s.iter().position(|c| match *c {
+            b'\n' => oflag & ONLCR != 0,
+            b'\r' => oflag & OCRNL != 0,
+            _ => false,
+        }) {
+            let translation: &[u8] = if s[index] == b'\n' { b"\r\n" } else { b"\n" };
+            let len = self.tty.write_bytes(&s[..index]);
+            if len != index || self.tty.write_bytes(translation) != translation.len() {
+                return written + len;
+            }
+            written += index + 1;
+            s = &s[index + 1..];
+        }
+        written + self.tty.write_bytes(s)
     }
 
     /// read maximum `max_len_data_to_read` on the read_buffer
@@ -211,35 +283,21 @@ impl LineDiscipline {
 
     /// open a tty
     pub fn open(&mut self, uid_file_op: usize) {
-        self.tty.uid_file_op = Some(uid_file_op);
+        self.uid_file_op = Some(uid_file_op);
     }
 
     /// read (from a process) on the tty
-    /// return the number of bytes readen
-    pub fn read(&mut self, output: &mut [u8]) -> ReadResult {
+    /// return the number of bytes readen. `timer_expired` is set when
+    /// the delay given by read_timer() elapsed since the last call
+    pub fn read(&mut self, output: &mut [u8], timer_expired: bool) -> ReadResult {
         use ReadResult::*;
         // print!("read buffer: ");
         // for c in &self.read_buffer {
         //     print!("{}", *c as char);
         // }
         // print!("\n");
-        // Any attempts by a process in a background process group to
-        // read from its controlling terminal cause its process group
-        // to be sent a SIGTTIN signal unless one of the following
-        // special cases applies: if the reading process is ignoring
-        // the SIGTTIN signal or the reading thread is blocking the
-        // SIGTTIN signal, or if the process group of the reading
-        // process is orphaned, the read() shall return -1, with errno
-        // set to [EIO] and no signal shall be sent. The default
-        // action of the SIGTTIN signal shall be to stop the process
-        // to which it is sent. See <signal.h>.
-        // let current_pgid = unsafe { get_current_pgid() };
-        // if self.foreground_process_group != current_pgid && (self.termios.c_lflag & TOSTOP != 0) {
-        //     messaging::send_message(MessageTo::ProcessGroup {
-        //         pgid: self.foreground_process_group,
-        //         content: ProcessGroupMessage::Signal(Signum::SIGTTIN),
-        //     });
-        // }
+        // The reads of the background process groups (SIGTTIN) are
+        // checked by the kernel before, it knows the reading process
         if self.termios.c_lflag & ICANON != 0 {
             // dbg!("canonical");
             // if VEOF was pressed, read all
@@ -252,38 +310,58 @@ impl LineDiscipline {
             } else {
                 Blocking
             }
-        } else if self.read_buffer.len() != 0 {
-            NonBlocking(self.read_max(output, self.read_buffer.len()))
         } else {
-            Blocking
+            // In non canonical mode, a read is complete when MIN bytes
+            // are received. With MIN > 0, TIME is an inter-byte timer
+            // started at the first byte. With MIN = 0, TIME is a read
+            // timer started at the call
+            let min = min(self.termios.c_cc[VMIN as usize] as usize, output.len());
+            let available = self.read_buffer.len();
+            let complete = available >= min && (available != 0 || self.read_timer().is_none());
+            let timed_out = timer_expired && (available != 0 || min == 0);
+            if complete || timed_out {
+                NonBlocking(self.read_max(output, available))
+            } else {
+                Blocking
+            }
         }
     }
 
+    /// the delay in tenths of second after which a blocked read
+    /// must be retried with `timer_expired`, cf VTIME in non
+    /// canonical mode
+    pub fn read_timer(&self) -> Option<u32> {
+        let time = self.termios.c_cc[VTIME as usize];
+        if self.termios.c_lflag & ICANON != 0 || time == 0 {
+            None
+        } else if self.termios.c_cc[VMIN as usize] == 0 || self.read_buffer.len() != 0 {
+            Some(time)
+        } else {
+            None
+        }
+    }
+
+    /// check if a read on the tty would not block
+    pub fn is_readable(&self) -> bool {
+        if self.termios.c_lflag & ICANON != 0 {
+            self.end_of_file_set || self.read_buffer.iter().any(|c| *c == '\n' as u8)
+        } else {
+            let min = self.termios.c_cc[VMIN as usize] as usize;
+            self.read_buffer.len() != 0 && self.read_buffer.len() >= min
+        }
+    }
+
+    /// check if the read buffer can receive more input
+    pub fn can_receive(&self) -> bool {
+        !self.read_buffer.is_full()
+    }
+
     /// write on the tty
     pub fn write(&mut self, s: &[u8]) -> usize {
-        //Attempts by a process in a background process group to write
-        // to its controlling terminal shall cause the process group
-        // to be sent a SIGTTOU signal unless one of the following
-        // special cases applies: if TOSTOP is not set, or if TOSTOP
-        // is set and the process is ignoring the SIGTTOU signal or
-        // the writing thread is blocking the SIGTTOU signal, the
-        // process is allowed to write to the terminal and the SIGTTOU
-        // signal is not sent. If TOSTOP is set, the process group of
-        // the writing process is orphaned, the writing process is not
-        // ignoring the SIGTTOU signal, and the writing thread is not
-        // blocking the SIGTTOU signal, the write() shall return -1,
-        // with errno set to [EIO] and no signal shall be sent.
-        let s = core::str::from_utf8(s).expect("bad utf8");
-        // let current_pgid = unsafe { get_current_pgid() };
-        // if self.foreground_process_group != current_pgid && (self.termios.c_lflag & TOSTOP != 0) {
-        //     messaging::send_message(MessageTo::ProcessGroup {
-        //         pgid: self.foreground_process_group,
-        //         content: ProcessGroupMessage::Signal(Signum::SIGTTOU),
-        //     });
-        // }
-
-        self.tty.write_str(s).expect("write failed");
-        s.len()
+        // The writes of the background process groups (SIGTTOU with
+        // TOSTOP) are checked by the kernel before, it knows the
+        // writing process
+        self.output(s)
     }
     pub fn set_raw_mode(&mut self, val: bool) {
         self.is_raw_mode = val;
@@ -293,12 +371,6 @@ impl LineDiscipline {
         self.is_raw_mode
     }
 
-    pub fn get_tty(&self) -> &Tty {
-        &self.tty.tty
-    }
-    pub fn get_tty_mut(&mut self) -> &mut Tty {
-        &mut self.tty.tty
-    }
     pub fn tcsetattr(&mut self, _optional_actions: u32, termios_p: &termios) {
         // dbg!(self.termios.c_lflag);
         self.termios = *termios_p;
@@ -316,6 +388,72 @@ impl LineDiscipline {
     pub fn tcgetpgrp(&mut self) -> Pid {
         self.foreground_process_group
     }
+    /// set the session of which the tty is the controlling
+    /// terminal, 0 when it is released
+    pub fn tcsetsid(&mut self, sid: Pid) {
+        self.session = sid;
+    }
+    pub fn tcgetsid(&self) -> Pid {
+        self.session
+    }
+}
+
+/// The system ttys, attached to the screen and the keyboard
+impl LineDiscipline<BufferedTty> {
+    /// handle directly some keysymb to control the terminal
+    pub fn handle_tty_control(&mut self, keysymb: KeySymb) -> bool {
+        match keysymb {
+            KeySymb::Control_p => self.tty.as_mut().scroll(Scroll::Up),
+            KeySymb::Control_n => self.tty.as_mut().scroll(Scroll::Down),
+            KeySymb::Control_b => self.tty.as_mut().scroll(Scroll::HalfScreenUp),
+            KeySymb::Control_v => self.tty.as_mut().scroll(Scroll::HalfScreenDown),
+            _ => {
+                return false;
+            }
+        };
+        true
+    }
+
+    pub fn handle_scancode(&mut self, scancode: ScanCode) -> Result<(), CapacityError<u8>> {
+        if self.is_raw_mode {
+            // let keycode = KeyCode::from_scancode(scancode);
+            // dbg!(keycode);
+            self.read_buffer.try_push((scancode & 0xff) as u8)?;
+            self.read_buffer
+                .try_push(((scancode & 0xff00) >> 8) as u8)?;
+        }
+        Ok(())
+    }
+
+    /// write in the read buffer the keysymb read from the keyboard
+    /// Send a message if read is ready, depending of the lmode
+    pub fn handle_key_pressed(&mut self, key: KeySymb) -> Result<(), CapacityError<u8>> {
+        if !self.handle_tty_control(key) {
+            // Check if tty is attached to a file operator
+            if self.uid_file_op.is_none() {
+                return Ok(());
+            }
+            let mut encode_buff = [0; 8];
+            let len = encode_utf8(key, &mut encode_buff).len();
+            // The cursor keys send CSI instead of SS3 out of the application mode
+            if len == 3 && encode_buff[0..2] == [27, 79] && !self.tty.tty.application_cursor_keys {
+                encode_buff[1] = '[' as u8;
+            }
+            let b = &encode_buff[..len];
+            let consumed = self.handle_input(b);
+            if consumed != b.len() {
+                return Err(CapacityError::new(b[consumed]));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_tty(&self) -> &Tty {
+        &self.tty.tty
+    }
+    pub fn get_tty_mut(&mut self) -> &mut Tty {
+        &mut self.tty.tty
+    }
 }
 
 pub fn encode_utf8(keysymb: KeySymb, dst: &mut [u8]) -> &[u8] {
@@ -65,7 +65,7 @@ macro_rules! print_bypass_mutex {
                         // I consider it's works !
                         Some(term) => {
                             use core::fmt::Write;
-                            term.get_foreground_tty().tty.tty.write_fmt(a).unwrap();
+                            term.get_foreground_tty().tty.write_fmt(a).unwrap();
                         }
                     }
                 }
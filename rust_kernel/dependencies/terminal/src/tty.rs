@@ -2,88 +2,176 @@ use super::{Cursor, Pos, SCREEN_MONAD};
 use alloc::collections::vec_deque::VecDeque;
 use alloc::vec;
 use alloc::vec::Vec;
-use ansi_escape_code::{AnsiColor, CursorMove, CSI};
-use arrayvec::ArrayString;
+use ansi_escape_code::{
+    AnsiColor, CursorMove, EraseMode, EscapedCode, Mode, Parser, Perform, Rendition,
+};
 use core::fmt::Write;
+use core::ops::Range;
 use libc_binding::local_buffer;
-use screen::{AdvancedGraphic, Drawer};
+use screen::{AdvancedGraphic, CellStyle, Drawer, ScreenMonad};
+
+/// A character of the screen with its graphic rendition
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Cell {
+    c: u8,
+    rendition: Rendition,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self::blank(Rendition::default())
+    }
+}
+
+impl Cell {
+    /// An erased cell keeps the current background color
+    fn blank(rendition: Rendition) -> Self {
+        Self {
+            c: b' ',
+            rendition: Rendition {
+                background: rendition.background,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// The displayed character
+    fn character(&self) -> char {
+        if self.rendition.hidden {
+            ' '
+        } else {
+            self.c as char
+        }
+    }
+
+    /// The colors and attributes to draw the cell with
+    fn style(&self) -> CellStyle {
+        let rendition = &self.rendition;
+        let mut foreground = match rendition.foreground {
+            AnsiColor::Standard(color) if rendition.bold => AnsiColor::HighIntensity(color as u8),
+            color => color,
+        };
+        let mut background = rendition.background;
+        if rendition.reverse {
+            let reversed = background.unwrap_or(AnsiColor::BLACK);
+            background = Some(foreground);
+            foreground = reversed;
+        }
+        CellStyle {
+            foreground,
+            background,
+            underline: rendition.underline,
+        }
+    }
+}
 
 /// Description of a TTY buffer
 #[derive(Debug, Clone)]
 struct TerminalBuffer {
-    buf: VecDeque<Vec<(u8, AnsiColor)>>,
-    fixed_buf: Vec<Option<(u8, AnsiColor)>>,
-    draw_start_pos: usize,
-    write_pos: usize,
-    nb_lines: usize,
+    /// The lines of the screen
+    lines: VecDeque<Vec<Cell>>,
+    /// The lines scrolled out of the top of the screen, without their blank end
+    history: VecDeque<Vec<Cell>>,
+    history_capacity: usize,
     nb_columns: usize,
 }
 
 /// Here a TTY buffer
 impl TerminalBuffer {
-    fn new(nb_lines: usize, nb_columns: usize, buf_max_capacity: usize) -> Self {
+    fn new(nb_lines: usize, nb_columns: usize, history_capacity: usize) -> Self {
         Self {
-            buf: VecDeque::with_capacity(buf_max_capacity),
-            fixed_buf: vec![None; nb_lines * nb_columns],
-            write_pos: 0,
-            nb_lines,
+            lines: (0..nb_lines)
+                .map(|_| vec![Cell::default(); nb_columns])
+                .collect(),
+            history: VecDeque::new(),
+            history_capacity,
             nb_columns,
-            draw_start_pos: 0,
         }
     }
 
-    /// Make some place for a new screen
-    fn make_place(&mut self) {
-        if self.buf.len() < self.buf.capacity() {
-            self.buf
-                .push_back(vec![(0, AnsiColor::BLACK); self.nb_lines * self.nb_columns]);
+    /// Get the line `line` of the screen when the view is `offset` lines up in the history
+    fn get_line(&self, line: usize, offset: usize) -> &[Cell] {
+        let index = self.history.len() - offset + line;
+        if index < self.history.len() {
+            &self.history[index]
         } else {
-            let mut first = self.buf.pop_front().unwrap();
-            // fresh the vec for reuse as last elem
-            for c in &mut first {
-                *c = (0, AnsiColor::BLACK);
+            &self.lines[index - self.history.len()]
+        }
+    }
+
+    /// Keep a line which goes out of the top of the screen
+    fn save_line(&mut self, line: &[Cell]) {
+        if self.history_capacity == 0 {
+            return;
+        }
+        let len = line
+            .iter()
+            .rposition(|cell| *cell != Cell::default())
+            .map(|last| last + 1)
+            .unwrap_or(0);
+        if self.history.len() == self.history_capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(line[..len].to_vec());
+    }
+
+    /// Scroll up the lines `top..=bottom` of `n` lines, the lines going out of
+    /// the top of the screen are saved in the history when `save` is true
+    fn scroll_up(&mut self, top: usize, bottom: usize, n: usize, blank: Cell, save: bool) {
+        for _ in 0..n.min(bottom + 1 - top) {
+            let mut line = self.lines.remove(top).expect("line out of bound");
+            if save && top == 0 {
+                self.save_line(&line);
             }
-            self.buf.push_back(first);
-            self.draw_start_pos -= self.nb_lines * self.nb_columns;
-            self.write_pos -= self.nb_lines * self.nb_columns;
-        }
-    }
-
-    /// Get a specified character from the buffer
-    fn get_char(&self, i: usize) -> Option<(u8, AnsiColor)> {
-        self.buf
-            .get(i / (self.nb_lines * self.nb_columns))
-            .map(|screen| screen[i % (self.nb_lines * self.nb_columns)])
-    }
-
-    /// Write a char into the buffer
-    #[inline(always)]
-    fn write_char(&mut self, c: char, color: AnsiColor) {
-        match self
-            .buf
-            .get_mut(self.write_pos / (self.nb_lines * self.nb_columns))
-        {
-            Some(screen) => {
-                let pos = self.write_pos % (self.nb_lines * self.nb_columns);
-                screen[pos] = (c as u8, color);
-                self.write_pos += match c {
-                    '\n' => self.nb_columns - pos % self.nb_columns,
-                    _ => 1,
-                };
-                if self.write_pos > self.draw_start_pos + self.nb_columns * self.nb_lines {
-                    self.draw_start_pos += self.nb_columns;
-                }
+            for cell in line.iter_mut() {
+                *cell = blank;
             }
-            None => {
-                self.make_place();
-                self.write_char(c, color)
+            self.lines.insert(bottom, line);
+        }
+    }
+
+    /// Scroll down the lines `top..=bottom` of `n` lines
+    fn scroll_down(&mut self, top: usize, bottom: usize, n: usize, blank: Cell) {
+        for _ in 0..n.min(bottom + 1 - top) {
+            let mut line = self.lines.remove(bottom).expect("line out of bound");
+            for cell in line.iter_mut() {
+                *cell = blank;
             }
+            self.lines.insert(top, line);
+        }
+    }
+
+    /// Erase the `columns` of a line
+    fn erase(&mut self, line: usize, columns: Range<usize>, blank: Cell) {
+        for cell in self.lines[line][columns].iter_mut() {
+            *cell = blank;
+        }
+    }
+
+    /// Insert `n` blank cells at `pos`, the end of the line is lost
+    fn insert_cells(&mut self, pos: Pos, n: usize, blank: Cell) {
+        let n = n.min(self.nb_columns - pos.column);
+        let line = &mut self.lines[pos.line][pos.column..];
+        line.rotate_right(n);
+        for cell in line[..n].iter_mut() {
+            *cell = blank;
+        }
+    }
+
+    /// Delete `n` cells at `pos`, the end of the line is filled with blank cells
+    fn delete_cells(&mut self, pos: Pos, n: usize, blank: Cell) {
+        let n = n.min(self.nb_columns - pos.column);
+        let line = &mut self.lines[pos.line][pos.column..];
+        line.rotate_left(n);
+        let len = line.len();
+        for cell in line[len - n..].iter_mut() {
+            *cell = blank;
         }
     }
 }
 
 /// TTY handle many write mode
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum WriteMode {
     /// Scroll may move printed characters
     Dynamic,
@@ -98,13 +186,33 @@ pub struct Tty {
     pub foreground: bool,
     /// Current TTY cursor
     pub cursor: Cursor,
-    /// Current Text Color
-    pub text_color: AnsiColor,
+    /// Current graphic rendition of the written characters
+    pub rendition: Rendition,
     /// current Write mode
     pub write_mode: WriteMode,
+    /// The cursor keys send the application sequences (DECCKM)
+    pub application_cursor_keys: bool,
     buf: TerminalBuffer,
-    scroll_offset: isize,
+    /// The primary screen, kept while the alternate screen is displayed
+    primary_screen: Option<TerminalBuffer>,
+    /// Characters printed at a fixed place, over the buffer
+    fixed_buf: Vec<Option<(u8, AnsiColor)>>,
+    /// Number of history lines displayed on top of the screen
+    scroll_offset: usize,
     background: Option<Vec<u8>>,
+    parser: Parser,
+    saved_cursor: (Pos, Rendition),
+    /// First and last lines of the scroll region
+    scroll_region: (usize, usize),
+    /// A character was printed in the last column, the next one goes on the next line
+    pending_wrap: bool,
+    insert_mode: bool,
+    /// A line feed also does a carriage return
+    newline_mode: bool,
+    auto_wrap: bool,
+    origin_mode: bool,
+    /// The lines to redraw
+    dirty_lines: Vec<bool>,
 }
 
 /// Handle different types of scroll
@@ -132,17 +240,30 @@ impl Tty {
     ) -> Self {
         Self {
             foreground,
-            buf: TerminalBuffer::new(nb_lines, nb_columns, max_screen_buffer),
-            scroll_offset: 0,
             cursor: Cursor {
                 pos: Default::default(),
                 nb_lines,
                 nb_columns,
                 visible: true,
             },
-            text_color: AnsiColor::WHITE,
+            rendition: Default::default(),
             write_mode: WriteMode::Dynamic,
+            application_cursor_keys: false,
+            buf: TerminalBuffer::new(nb_lines, nb_columns, max_screen_buffer * nb_lines),
+            primary_screen: None,
+            fixed_buf: vec![None; nb_lines * nb_columns],
+            scroll_offset: 0,
             background,
+            parser: Parser::new(),
+            saved_cursor: Default::default(),
+            scroll_region: (0, nb_lines - 1),
+            pending_wrap: false,
+            insert_mode: false,
+            // The CR is added by the output processing of the line discipline (ONLCR)
+            newline_mode: false,
+            auto_wrap: true,
+            origin_mode: false,
+            dirty_lines: vec![false; nb_lines],
         }
     }
 
@@ -164,7 +285,8 @@ impl Tty {
             })
             .unwrap();
         if print_char == true {
-            self.print_screen(self.scroll_offset);
+            self.mark_all_dirty();
+            self.refresh_lines();
         } else {
             SCREEN_MONAD.lock().refresh_screen();
         }
@@ -175,260 +297,488 @@ impl Tty {
         self.background = Some(v);
     }
 
-    /// Internal scroll
+    /// Internal scroll in the history
     pub fn scroll(&mut self, scroll: Scroll) {
         use Scroll::*;
-        let add_scroll = match scroll {
-            Up => -(self.buf.nb_columns as isize),
-            Down => self.buf.nb_columns as isize,
-            HalfScreenUp => -((self.buf.nb_lines / 2 * self.buf.nb_columns) as isize),
-            HalfScreenDown => (self.buf.nb_lines / 2 * self.buf.nb_columns) as isize,
-        };
-        self.scroll_offset =
-            if (self.scroll_offset + add_scroll + self.buf.draw_start_pos as isize) < 0 {
-                -(self.buf.draw_start_pos as isize)
-            } else {
-                self.scroll_offset + add_scroll
-            };
-        self.print_screen(self.scroll_offset);
+        let half_screen = self.cursor.nb_lines / 2;
+        self.scroll_offset = match scroll {
+            Up => self.scroll_offset + 1,
+            Down => self.scroll_offset.saturating_sub(1),
+            HalfScreenUp => self.scroll_offset + half_screen,
+            HalfScreenDown => self.scroll_offset.saturating_sub(half_screen),
+        }
+        .min(self.buf.history.len());
+        self.mark_all_dirty();
+        self.refresh_lines();
     }
 
     /// Allow a shell for example to move cursor manually
     pub fn move_cursor(&mut self, direction: CursorMove) {
-        if !self.cursor.visible || !self.foreground {
+        self.mark_dirty(self.cursor.pos.line);
+        self.pending_wrap = false;
+        self.cursor_move(direction);
+        self.mark_dirty(self.cursor.pos.line);
+        self.refresh_lines();
+    }
+
+    /// Erase the character before the cursor, on the previous line if
+    /// the cursor is at the beginning of a line
+    pub fn erase_char(&mut self) {
+        let Pos { line, column } = self.cursor.pos;
+        self.mark_dirty(line);
+        if self.pending_wrap {
+            self.pending_wrap = false;
+        } else if column > 0 {
+            self.cursor.pos.column -= 1;
+        } else if line > 0 {
+            self.cursor.pos = Pos {
+                line: line - 1,
+                column: self.cursor.nb_columns - 1,
+            };
+        } else {
+            return self.refresh_lines();
+        }
+        self.buf.lines[self.cursor.pos.line][self.cursor.pos.column] = self.blank();
+        self.mark_dirty(self.cursor.pos.line);
+        self.refresh_lines();
+    }
+
+    fn blank(&self) -> Cell {
+        Cell::blank(self.rendition)
+    }
+
+    fn mark_dirty(&mut self, line: usize) {
+        self.dirty_lines[line] = true;
+    }
+
+    fn mark_all_dirty(&mut self) {
+        for dirty in self.dirty_lines.iter_mut() {
+            *dirty = true;
+        }
+    }
+
+    fn mark_dirty_range(&mut self, top: usize, bottom: usize) {
+        for dirty in self.dirty_lines[top..=bottom].iter_mut() {
+            *dirty = true;
+        }
+    }
+
+    /// Draw a cell of the screen, a fixed character is drawn over the buffer
+    fn draw_cell(&self, screen: &mut ScreenMonad, pos: Pos, cell: Cell) {
+        match self.fixed_buf[pos.line * self.cursor.nb_columns + pos.column] {
+            Some((c, color)) => screen.draw_character(c as char, pos, color),
+            None => screen.draw_styled_character(cell.character(), pos, cell.style()),
+        }
+        .unwrap();
+    }
+
+    /// Draw the cursor, or hide the hardware cursor of the text mode
+    fn draw_cursor(&self, screen: &mut ScreenMonad) {
+        let pos = self.cursor.pos;
+        let cell = self.buf.lines[pos.line][pos.column];
+        let fixed = self.fixed_buf[pos.line * self.cursor.nb_columns + pos.column].is_some();
+        if self.cursor.visible && self.scroll_offset == 0 && !fixed {
+            screen
+                .draw_cursor(cell.character(), pos, cell.style().foreground)
+                .unwrap();
+        } else {
+            screen
+                .clear_cursor(cell.character(), pos, cell.style().foreground)
+                .unwrap();
+            self.draw_cell(screen, pos, cell);
+        }
+    }
+
+    /// Redraw the modified lines
+    fn refresh_lines(&mut self) {
+        if !self.foreground {
+            for dirty in self.dirty_lines.iter_mut() {
+                *dirty = false;
+            }
             return;
         }
-        // Clear the Old cursor
-        self.clear_cursor();
-        SCREEN_MONAD
-            .lock()
-            .refresh_text_line(self.cursor.pos.line)
-            .unwrap();
+        let mut screen = SCREEN_MONAD.lock();
+        for line in 0..self.cursor.nb_lines {
+            if self.dirty_lines[line] {
+                let cells = self.buf.get_line(line, self.scroll_offset);
+                for column in 0..self.cursor.nb_columns {
+                    let cell = cells.get(column).cloned().unwrap_or_default();
+                    self.draw_cell(&mut screen, Pos { line, column }, cell);
+                }
+            }
+        }
+        if self.write_mode == WriteMode::Dynamic {
+            self.draw_cursor(&mut screen);
+        }
+        for line in 0..self.cursor.nb_lines {
+            if self.dirty_lines[line] {
+                screen.refresh_text_line(line).unwrap();
+                self.dirty_lines[line] = false;
+            }
+        }
+    }
+
+    /// Scroll up the scroll region of `n` lines
+    fn scroll_region_up(&mut self, n: usize) {
+        let (top, bottom) = self.scroll_region;
+        let blank = self.blank();
+        let save = self.primary_screen.is_none();
+        self.buf.scroll_up(top, bottom, n, blank, save);
+        self.mark_dirty_range(top, bottom);
+    }
+
+    /// Scroll down the scroll region of `n` lines
+    fn scroll_region_down(&mut self, n: usize) {
+        let (top, bottom) = self.scroll_region;
+        let blank = self.blank();
+        self.buf.scroll_down(top, bottom, n, blank);
+        self.mark_dirty_range(top, bottom);
+    }
+
+    /// Move the cursor down, scroll up at the bottom of the scroll region
+    fn index(&mut self) {
+        if self.cursor.pos.line == self.scroll_region.1 {
+            self.scroll_region_up(1);
+        } else if self.cursor.pos.line < self.cursor.nb_lines - 1 {
+            self.cursor.pos.line += 1;
+        }
+    }
+
+    /// Move the cursor up, scroll down at the top of the scroll region
+    fn reverse_index(&mut self) {
+        if self.cursor.pos.line == self.scroll_region.0 {
+            self.scroll_region_down(1);
+        } else if self.cursor.pos.line > 0 {
+            self.cursor.pos.line -= 1;
+        }
+    }
+
+    /// The absolute line of `line`, which is relative to the scroll region in origin mode
+    fn origin_line(&self, line: usize) -> usize {
+        if self.origin_mode {
+            (self.scroll_region.0 + line).min(self.scroll_region.1)
+        } else {
+            line.min(self.cursor.nb_lines - 1)
+        }
+    }
 
-        // Apply new cursor direction
+    /// Apply a cursor movement, the cursor stays in the screen and the
+    /// relative movements stop at the margins of the scroll region
+    fn cursor_move(&mut self, direction: CursorMove) {
+        let Pos { line, column } = self.cursor.pos;
+        let (top, bottom) = self.scroll_region;
+        let last_column = self.cursor.nb_columns - 1;
+        let last_line = self.cursor.nb_lines - 1;
         match direction {
-            CursorMove::Forward(q) => {
-                self.buf.write_pos += q;
-                for _i in 0..q {
-                    self.cursor.forward();
-                }
+            CursorMove::Up(n) => {
+                let top = if line >= top { top } else { 0 };
+                self.cursor.pos.line = line.saturating_sub(n).max(top);
             }
-            CursorMove::Backward(q) => {
-                self.buf.write_pos -= q;
-                for _i in 0..q {
-                    self.cursor.backward();
-                }
+            CursorMove::Down(n) => {
+                let bottom = if line <= bottom { bottom } else { last_line };
+                self.cursor.pos.line = (line + n).min(bottom);
             }
-            CursorMove::HorizontalAbsolute(q) => {
-                self.buf.write_pos += q - self.cursor.pos.column;
-                self.cursor.pos.column = q;
+            CursorMove::Forward(n) => self.cursor.pos.column = (column + n).min(last_column),
+            CursorMove::Backward(n) => self.cursor.pos.column = column.saturating_sub(n),
+            CursorMove::HorizontalAbsolute(column) => {
+                self.cursor.pos.column = column.min(last_column)
             }
-            _ => {
-                unimplemented!();
+            CursorMove::VerticalAbsolute(line) => self.cursor.pos.line = self.origin_line(line),
+            CursorMove::Pos(pos) => {
+                self.cursor.pos = Pos {
+                    line: self.origin_line(pos.line),
+                    column: pos.column.min(last_column),
+                }
             }
         }
-
-        // Draw the new cursor
-        self.draw_cursor();
-        SCREEN_MONAD
-            .lock()
-            .refresh_text_line(self.cursor.pos.line)
-            .unwrap();
     }
 
-    /// draw cursor for the character designed by write_pos in coordinate cursor.y and cursor.x
-    fn draw_cursor(&self) {
-        let c = match self.buf.get_char(self.buf.write_pos) {
-            None | Some((0, _)) => (' ' as u8, self.text_color),
-            Some(elem) => elem,
+    /// Erase a part of the screen
+    fn erase_in_display(&mut self, mode: EraseMode) {
+        let Pos { line, column } = self.cursor.pos;
+        let (nb_lines, nb_columns) = (self.cursor.nb_lines, self.cursor.nb_columns);
+        let blank = self.blank();
+        let (lines, cells) = match mode {
+            EraseMode::Below => (line + 1..nb_lines, column..nb_columns),
+            EraseMode::Above => (0..line, 0..column + 1),
+            EraseMode::All => (0..nb_lines, 0..0),
+            EraseMode::SavedLines => {
+                self.buf.history.clear();
+                self.scroll_offset = 0;
+                return;
+            }
         };
-        SCREEN_MONAD
-            .lock()
-            .draw_cursor(c.0 as char, self.cursor.pos, c.1)
-            .unwrap();
+        self.buf.erase(line, cells, blank);
+        for line in lines {
+            self.buf.erase(line, 0..nb_columns, blank);
+        }
+        self.mark_all_dirty();
     }
 
-    /// draw cursor for the character designed by write_pos in coordinate cursor.y and cursor.x
-    fn clear_cursor(&self) {
-        let c = match self.buf.get_char(self.buf.write_pos) {
-            None | Some((0, _)) => (' ' as u8, self.text_color),
-            Some(elem) => elem,
+    /// Erase a part of the cursor line
+    fn erase_in_line(&mut self, mode: EraseMode) {
+        let Pos { line, column } = self.cursor.pos;
+        let nb_columns = self.cursor.nb_columns;
+        let cells = match mode {
+            EraseMode::Below => column..nb_columns,
+            EraseMode::Above => 0..column + 1,
+            EraseMode::All | EraseMode::SavedLines => 0..nb_columns,
         };
-        SCREEN_MONAD
-            .lock()
-            .clear_cursor(c.0 as char, self.cursor.pos, c.1)
-            .unwrap();
+        let blank = self.blank();
+        self.buf.erase(line, cells, blank);
+        self.mark_dirty(line);
     }
 
-    /// re-print the entire screen
-    fn print_screen(&mut self, offset: isize) {
-        SCREEN_MONAD.lock().clear_screen();
-        self.cursor.pos = Default::default();
+    /// Switch between the primary and the alternate screens, the alternate
+    /// screen has no history
+    fn set_alternate_screen(&mut self, alternate: bool) {
+        if alternate && self.primary_screen.is_none() {
+            let alternate_screen =
+                TerminalBuffer::new(self.cursor.nb_lines, self.cursor.nb_columns, 0);
+            self.primary_screen = Some(core::mem::replace(&mut self.buf, alternate_screen));
+        } else if !alternate {
+            if let Some(primary_screen) = self.primary_screen.take() {
+                self.buf = primary_screen;
+            }
+        }
+        self.scroll_offset = 0;
+        self.mark_all_dirty();
+    }
 
-        let mut pos_last_char_writen = self.buf.write_pos;
-        let start_pos = if offset > 0 {
-            self.buf.draw_start_pos + offset as usize
-        } else {
-            self.buf
-                .draw_start_pos
-                .checked_sub((-offset) as usize)
-                .unwrap_or(0)
+    fn save_cursor(&mut self) {
+        self.saved_cursor = (self.cursor.pos, self.rendition);
+    }
+
+    fn restore_cursor(&mut self) {
+        let (pos, rendition) = self.saved_cursor;
+        self.cursor.pos = Pos {
+            line: pos.line.min(self.cursor.nb_lines - 1),
+            column: pos.column.min(self.cursor.nb_columns - 1),
         };
-        for j in (start_pos..start_pos + self.cursor.nb_columns * self.cursor.nb_lines)
-            .step_by(self.cursor.nb_columns)
-        {
-            for i in j..j + self.cursor.nb_columns {
-                if i >= start_pos + self.cursor.nb_columns * self.cursor.nb_lines {
-                    break;
-                }
-                match self.buf.get_char(i) {
-                    None => {
-                        break;
-                    }
-                    Some(n) if n.0 == 0 => {
-                        break;
-                    }
-                    Some(n) if n.0 == '\n' as u8 => {
-                        self.cursor.cariage_return();
-                        pos_last_char_writen =
-                            i + (self.cursor.nb_columns - (i % self.cursor.nb_columns));
-                        break;
-                    }
-                    Some(other) => {
-                        SCREEN_MONAD
-                            .lock()
-                            .draw_character(other.0 as char, self.cursor.pos, other.1)
-                            .unwrap();
-                        self.cursor.forward();
-                        pos_last_char_writen = i + 1;
-                    }
+        self.rendition = rendition;
+    }
+
+    fn set_mode(&mut self, mode: Mode, set: bool) {
+        match mode {
+            Mode::Insert => self.insert_mode = set,
+            Mode::LineFeedNewLine => self.newline_mode = set,
+            Mode::ApplicationCursorKeys => self.application_cursor_keys = set,
+            Mode::Origin => {
+                self.origin_mode = set;
+                self.cursor.pos = Pos {
+                    line: self.origin_line(0),
+                    column: 0,
+                };
+            }
+            Mode::AutoWrap => self.auto_wrap = set,
+            Mode::CursorVisible => self.cursor.visible = set,
+            Mode::AlternateScreen => self.set_alternate_screen(set),
+            Mode::AlternateScreenSaveCursor => {
+                if set {
+                    self.save_cursor();
+                    self.set_alternate_screen(true);
+                    self.erase_in_display(EraseMode::All);
+                } else {
+                    self.set_alternate_screen(false);
+                    self.restore_cursor();
                 }
             }
         }
+    }
 
-        if self.cursor.visible && self.buf.write_pos <= pos_last_char_writen {
-            for _i in 0..pos_last_char_writen - self.buf.write_pos {
-                self.cursor.backward();
+    /// Apply the escape sequences which move the cursor or modify the screen
+    fn edit(&mut self, code: EscapedCode) {
+        use EscapedCode::*;
+        let Pos { line, column } = self.cursor.pos;
+        let blank = self.blank();
+        let (top, bottom) = self.scroll_region;
+        let in_scroll_region = line >= top && line <= bottom;
+        match code {
+            CursorMove(direction) => self.cursor_move(direction),
+            EraseInDisplay(mode) => self.erase_in_display(mode),
+            EraseInLine(mode) => self.erase_in_line(mode),
+            EraseCharacters(n) => {
+                let end = (column + n).min(self.cursor.nb_columns);
+                self.buf.erase(line, column..end, blank);
+                self.mark_dirty(line);
+            }
+            InsertLines(n) if in_scroll_region => {
+                self.buf.scroll_down(line, bottom, n, blank);
+                self.mark_dirty_range(line, bottom);
+                self.cursor.pos.column = 0;
+            }
+            DeleteLines(n) if in_scroll_region => {
+                self.buf.scroll_up(line, bottom, n, blank, false);
+                self.mark_dirty_range(line, bottom);
+                self.cursor.pos.column = 0;
             }
-            self.draw_cursor();
-        }
-
-        // Display all the fixed character buffer
-        for (i, elem) in self.buf.fixed_buf.iter().enumerate() {
-            match *elem {
-                None => {}
-                Some(e) => SCREEN_MONAD
-                    .lock()
-                    .draw_character(
-                        e.0 as char,
-                        Pos {
-                            line: i / self.cursor.nb_columns,
-                            column: i % self.cursor.nb_columns,
-                        },
-                        e.1,
-                    )
-                    .unwrap(),
+            InsertCharacters(n) => {
+                self.buf.insert_cells(self.cursor.pos, n, blank);
+                self.mark_dirty(line);
             }
+            DeleteCharacters(n) => {
+                self.buf.delete_cells(self.cursor.pos, n, blank);
+                self.mark_dirty(line);
+            }
+            ScrollUp(n) => self.scroll_region_up(n),
+            ScrollDown(n) => self.scroll_region_down(n),
+            SetScrollRegion { top, bottom } => {
+                let bottom = bottom
+                    .unwrap_or(self.cursor.nb_lines - 1)
+                    .min(self.cursor.nb_lines - 1);
+                if top < bottom {
+                    self.scroll_region = (top, bottom);
+                    self.cursor.pos = Pos {
+                        line: self.origin_line(0),
+                        column: 0,
+                    };
+                }
+            }
+            RestoreCursor => self.restore_cursor(),
+            Index => self.index(),
+            ReverseIndex => self.reverse_index(),
+            NextLine => {
+                self.cursor.pos.column = 0;
+                self.index();
+            }
+            SetMode(mode, set) => self.set_mode(mode, set),
+            Reset => self.reset(),
+            _ => {}
         }
+    }
 
-        SCREEN_MONAD.lock().refresh_screen();
+    /// Back to the initial state, the history is kept
+    fn reset(&mut self) {
+        self.set_alternate_screen(false);
+        self.rendition = Default::default();
+        self.application_cursor_keys = false;
+        self.saved_cursor = Default::default();
+        self.scroll_region = (0, self.cursor.nb_lines - 1);
+        self.insert_mode = false;
+        self.newline_mode = false;
+        self.auto_wrap = true;
+        self.origin_mode = false;
+        self.cursor.visible = true;
+        self.erase_in_display(EraseMode::All);
+        self.cursor.pos = Default::default();
     }
+}
 
-    /// Refresh line or scroll
-    fn map_line(&mut self, line: usize) {
-        if line == self.cursor.pos.line {
-            self.scroll(Scroll::Down);
-            self.scroll_offset = 0;
+/// The operations of the escape sequences
+impl Perform for Tty {
+    fn print(&mut self, c: char) {
+        if self.write_mode == WriteMode::Fixed {
+            let Pos { line, column } = self.cursor.pos;
+            if column < self.cursor.nb_columns {
+                self.fixed_buf[line * self.cursor.nb_columns + column] =
+                    Some((c as u8, self.rendition.foreground));
+                self.mark_dirty(line);
+                self.cursor.pos.column += 1;
+            }
+            return;
+        }
+        if self.pending_wrap && self.auto_wrap {
+            self.cursor.pos.column = 0;
+            self.index();
+        }
+        let pos = self.cursor.pos;
+        if self.insert_mode {
+            let blank = self.blank();
+            self.buf.insert_cells(pos, 1, blank);
+        }
+        self.buf.lines[pos.line][pos.column] = Cell {
+            c: c as u8,
+            rendition: self.rendition,
+        };
+        self.mark_dirty(pos.line);
+        if pos.column == self.cursor.nb_columns - 1 {
+            self.pending_wrap = true;
         } else {
-            SCREEN_MONAD.lock().refresh_text_line(line).unwrap();
+            self.pending_wrap = false;
+            self.cursor.pos.column += 1;
+        }
+    }
+
+    fn execute(&mut self, c: char) {
+        if self.write_mode == WriteMode::Fixed {
+            return;
+        }
+        match c {
+            '\n' | '\x0b' | '\x0c' => {
+                if self.newline_mode {
+                    self.cursor.pos.column = 0;
+                }
+                self.index();
+            }
+            '\r' => self.cursor.pos.column = 0,
+            '\x08' => self.cursor.pos.column = self.cursor.pos.column.saturating_sub(1),
+            '\t' => {
+                self.cursor.pos.column =
+                    ((self.cursor.pos.column / 8 + 1) * 8).min(self.cursor.nb_columns - 1)
+            }
+            // Bell, shift in and out of the G1 character set ...
+            _ => return,
+        }
+        self.pending_wrap = false;
+        self.mark_dirty(self.cursor.pos.line);
+    }
+
+    fn escaped(&mut self, code: EscapedCode) {
+        match code {
+            EscapedCode::Color(color) => self.rendition.foreground = color,
+            EscapedCode::GraphicRendition(sgr) => self.rendition.apply(sgr),
+            _ if self.write_mode == WriteMode::Fixed => {}
+            EscapedCode::SaveCursor => self.save_cursor(),
+            _ => {
+                self.pending_wrap = false;
+                self.edit(code);
+                self.mark_dirty(self.cursor.pos.line);
+            }
         }
     }
 }
 
 /// TTY implement some methods of writing
-/// Dynamic: Classic behavior with buffering and scroll
-/// Fixed: The text is always printed on screen
+/// Dynamic: Classic behavior with history and scroll
+/// Fixed: The text is always printed on screen, the escape sequences only change the color
 impl Write for Tty {
     fn write_str(&mut self, s: &str) -> core::fmt::Result {
-        if self.cursor.visible {
-            self.clear_cursor();
-        }
-
         // Make the scroll coherency
-        if self.foreground && self.scroll_offset != 0 {
+        if self.scroll_offset != 0 {
             self.scroll_offset = 0;
-            self.print_screen(self.scroll_offset);
-        }
-
-        use ansi_escape_code::*;
-        use EscapedItem::*;
-        for e in iter_escaped(s) {
-            match e {
-                Escaped(e) => match e {
-                    EscapedCode::Color(color) => self.text_color = color.into(),
-                    EscapedCode::CursorMove(cursor_move) => self.move_cursor(cursor_move),
-                },
-                Str(s) => {
-                    for c in s.chars() {
-                        match self.write_mode {
-                            WriteMode::Dynamic => {
-                                // Ignore bullshit characters. Transmute into spaces
-                                // TTY may use 0x0 value to delimitate the screen
-                                if c == '\0' {
-                                    continue;
-                                } else {
-                                    self.buf.write_char(c, self.text_color);
-                                }
-                            }
-                            WriteMode::Fixed => {
-                                self.buf.fixed_buf[self.cursor.pos.line * self.cursor.nb_columns
-                                    + self.cursor.pos.column] = Some((c as u8, self.text_color))
-                            }
-                        };
-
-                        if self.foreground {
-                            if c != '\n' {
-                                SCREEN_MONAD
-                                    .lock()
-                                    .draw_character(c, self.cursor.pos, self.text_color)
-                                    .unwrap();
-                                self.cursor.forward().map(|line| self.map_line(line));
-                            } else {
-                                // clear the old pos of cursor since
-                                // cariage return call will forget the
-                                // position of current cursor
-                                if self.cursor.visible {
-                                    self.clear_cursor();
-                                }
-                                self.cursor.cariage_return().map(|line| self.map_line(line));
-                            }
-                        }
-                    }
+            self.mark_all_dirty();
+        }
+        // The line of the old cursor
+        self.mark_dirty(self.cursor.pos.line);
+
+        match self.write_mode {
+            WriteMode::Dynamic => {
+                // The parser keeps the unfinished sequences between two writes
+                let mut parser = self.parser;
+                for c in s.chars() {
+                    parser.advance(self, c);
                 }
+                self.parser = parser;
             }
-        }
-        if self.foreground {
-            if self.cursor.visible {
-                self.draw_cursor();
+            WriteMode::Fixed => {
+                let rendition = self.rendition;
+                let mut parser = Parser::new();
+                for c in s.chars() {
+                    parser.advance(self, c);
+                }
+                self.rendition = rendition;
             }
-            SCREEN_MONAD
-                .lock()
-                .refresh_text_line(self.cursor.pos.line)
-                .unwrap();
         }
+        self.refresh_lines();
         Ok(())
     }
 }
 
-/// Handle a TTY with a write buffer
+/// Handle a TTY with its graphic buffer
 #[derive(Debug, Clone)]
 pub struct BufferedTty {
     /// TTY contained
     pub tty: Tty,
-    /// contains unfinished escaped sequence, capacity max = 256
-    escaped_buf: ArrayString<[u8; Self::ESCAPED_BUF_CAPACITY]>,
-    /// global ipc uid associated to the tty
-    pub uid_file_op: Option<usize>,
 }
 
 impl AsRef<Tty> for BufferedTty {
@@ -444,41 +794,9 @@ impl AsMut<Tty> for BufferedTty {
 }
 
 impl BufferedTty {
-    const ESCAPED_BUF_CAPACITY: usize = 32;
-
     /// Create a new buffered TTY
     pub fn new(tty: Tty) -> Self {
-        Self {
-            tty,
-            escaped_buf: ArrayString::new(),
-            uid_file_op: None,
-        }
-    }
-
-    /// This method buffers an unfinished escape sequence and print appropriately.
-    /// It appends `part`, a fragment of the current unfinished sequence inside escape_buf.
-    /// If `complete` is true, after appending, the sequence is considered finished and flushed into the tty.
-    /// Upon error appending the `part`, the current escaped_buf and the part are flushed into the tty.
-    /// The buffer is then truncated to zero.
-    #[inline(always)]
-    fn sequence_buffering(&mut self, part: &str, complete: bool) -> core::fmt::Result {
-        let mut err = self.escaped_buf.try_push_str(part).is_err();
-
-        let ret: core::fmt::Result = if err {
-            self.tty
-                .write_str(&self.escaped_buf)
-                .and_then(|_| self.tty.write_str(part))
-        } else if complete {
-            self.tty.write_str(&self.escaped_buf)
-        } else {
-            Ok(())
-        };
-
-        err |= ret.is_err();
-        if err || complete {
-            self.escaped_buf.truncate(0);
-        }
-        ret
+        Self { tty }
     }
 
     pub fn refresh_screen(&mut self, buf: &[u8]) {
@@ -517,33 +835,18 @@ impl BufferedTty {
     }
 }
 
+/// The kernel messages don't go through the line discipline, their
+/// new lines are always translated like with ONLCR
 impl Write for BufferedTty {
-    /// Fill its escaped buf when s has an unfinished escaped sequence
-    /// to assure to call write_str on tty with complete escaped sequence.
-    fn write_str(&mut self, mut s: &str) -> core::fmt::Result {
-        let is_ascii_alphabetic = |c: char| c.is_ascii_alphabetic();
-
-        if self.escaped_buf.len() != 0 {
-            match s.find(is_ascii_alphabetic) {
-                Some(index) => {
-                    let (end_escaped, end_s) = s.split_at(index + 1);
-                    self.sequence_buffering(end_escaped, true)?;
-                    s = end_s;
-                }
-                None => {
-                    self.sequence_buffering(s, false)?;
-                    return Ok(());
-                }
-            }
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let mut lines = s.split('\n');
+        if let Some(first) = lines.next() {
+            self.tty.write_str(first)?;
         }
-
-        // we look for the first char of `CSI` (the escape sequence)
-        if let Some(begin_esc) = s.rfind(&CSI[0..1]) {
-            if let None = s[begin_esc..].find(is_ascii_alphabetic) {
-                self.sequence_buffering(&s[begin_esc..], false)?;
-                s = &s[..begin_esc];
-            }
+        for line in lines {
+            self.tty.write_str("\r\n")?;
+            self.tty.write_str(line)?;
         }
-        self.tty.write_str(s)
+        Ok(())
     }
 }
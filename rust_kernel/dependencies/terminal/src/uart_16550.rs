@@ -1,4 +1,5 @@
 //! impl write on the [Serial Ports](https://wiki.osdev.org/Serial_ports)
+use super::TtyOutput;
 use bitflags::bitflags;
 use core::fmt;
 use io::{Io, Pio};
@@ -53,6 +54,15 @@ impl Uart16550 {
         while !self.line_sts().contains(LineStsFlags::OUTPUT_EMPTY) {}
         self.data.write(byte);
     }
+
+    /// Get a received byte if there is one in the FIFO
+    pub fn receive(&mut self) -> Option<u8> {
+        if self.line_sts().contains(LineStsFlags::INPUT_FULL) {
+            Some(self.data.read())
+        } else {
+            None
+        }
+    }
 }
 
 impl fmt::Write for Uart16550 {
@@ -66,6 +76,32 @@ impl fmt::Write for Uart16550 {
 
 pub static mut UART_16550: Uart16550 = Uart16550::new(0x3F8);
 
+/// The output of the serial tty line discipline: the bytes are sent through COM1
+#[derive(Debug, Default, Clone)]
+pub struct SerialOutput;
+
+impl TtyOutput for SerialOutput {
+    fn write_bytes(&mut self, buf: &[u8]) -> usize {
+        // The carriage returns needed by the remote terminal are added by ONLCR
+        for byte in buf {
+            unsafe {
+                UART_16550.send(*byte);
+            }
+        }
+        buf.len()
+    }
+
+    fn erase_char(&mut self) {
+        self.write_bytes(b"\x08 \x08");
+    }
+
+    fn erase_line(&mut self, nb_chars: usize) {
+        for _ in 0..nb_chars {
+            self.erase_char();
+        }
+    }
+}
+
 /// Prints to the host through the serial interface.
 #[macro_export]
 macro_rules! serial_print {
@@ -161,7 +161,17 @@ fn main() {
 
                 match native {
                     true => {
-                        if feature.contains("hard-drive") {
+                        if feature.contains("hard-drive") && feature.contains("sata") {
+                            // The rainbow disk is connected alone to an AHCI controller
+                            qemu_command
+                                .args(&["-drive", "format=raw,file=../image_disk.img"])
+                                .args(&["-device", "ahci,id=ahci"])
+                                .args(&[
+                                    "-drive",
+                                    "id=rainbow,if=none,format=raw,file=../rainbow_disk.img",
+                                ])
+                                .args(&["-device", "ide-hd,drive=rainbow,bus=ahci.0"])
+                        } else if feature.contains("hard-drive") {
                             qemu_command
                                 .args(&["-drive", "format=raw,file=../image_disk.img"])
                                 .args(&["-drive", "format=raw,file=../rainbow_disk.img"])
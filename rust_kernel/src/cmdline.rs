@@ -0,0 +1,56 @@
+//! The kernel command line given by the bootloader: some words
+//! separated by spaces, an option is a `key=value` word (ex: `console=ttyS0`)
+
+use crate::memory::ffi::{map, unmap};
+use crate::multiboot::MultibootInfo;
+use alloc::string::String;
+
+/// The cmdline field of the multiboot info is valid when this flag is set
+const MULTIBOOT_CMDLINE_FLAG: u32 = 1 << 2;
+
+/// The command line is truncated beyond this length
+const CMDLINE_MAX_LEN: usize = 4096;
+
+static mut CMDLINE: Option<String> = None;
+
+/// Copy the command line of the bootloader, must be called after the memory initialization
+pub unsafe fn init(multiboot_info: &MultibootInfo) {
+    if multiboot_info.flags & MULTIBOOT_CMDLINE_FLAG == 0 {
+        return;
+    }
+    let ptr = map(multiboot_info.cmdline as *mut u8, CMDLINE_MAX_LEN);
+    if ptr.is_null() {
+        log::error!("Cannot map the kernel command line");
+        return;
+    }
+    let len = (0..CMDLINE_MAX_LEN)
+        .find(|i| *ptr.add(*i) == 0)
+        .unwrap_or(CMDLINE_MAX_LEN);
+    let cmdline = String::from_utf8_lossy(core::slice::from_raw_parts(ptr, len)).into_owned();
+    unmap(ptr, CMDLINE_MAX_LEN);
+
+    log::info!("Kernel command line: {}", cmdline);
+    CMDLINE = Some(cmdline);
+}
+
+/// Get the whole command line
+pub fn get_cmdline() -> &'static str {
+    unsafe {
+        CMDLINE
+            .as_ref()
+            .map(|cmdline| cmdline.as_str())
+            .unwrap_or("")
+    }
+}
+
+/// Get the value of the option `key`, an option without value gives an empty string
+pub fn get_option(key: &str) -> Option<&'static str> {
+    get_cmdline().split_whitespace().find_map(|word| {
+        let mut option = word.splitn(2, '=');
+        if option.next() == Some(key) {
+            Some(option.next().unwrap_or(""))
+        } else {
+            None
+        }
+    })
+}
@@ -3,6 +3,12 @@
 pub mod acpi;
 pub use acpi::{Acpi, ACPI};
 
+pub mod apic;
+pub use apic::{Apic, TickSource, APIC};
+
+mod interrupt_controller;
+pub use interrupt_controller::{disable_irq, enable_irq, enable_pci_irq};
+
 mod pci;
 pub use pci::PCI;
 
@@ -12,4 +18,6 @@ pub use pic_8259::{Pic8259, PIC_8259};
 pub mod pit_8253;
 pub use pit_8253::PIT0;
 
+pub mod serial;
+
 pub mod storage;
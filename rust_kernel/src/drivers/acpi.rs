@@ -236,6 +236,229 @@ struct S5Object {
     slp_typ_b_num: u8,
 }
 
+/// [madt](https://wiki.osdev.org/MADT)
+/// Multiple APIC Description Table, followed by its variable length entries
+#[derive(Copy, Clone, Debug)]
+#[repr(packed)]
+struct MadtHeader {
+    /*0  */ h: ACPIRSDTHeader,
+    /*36 */ local_apic_address: u32,
+    /*40 */ flags: u32,
+    /*44 */
+}
+
+/// The header of each entry of the MADT
+#[derive(Copy, Clone, Debug)]
+#[repr(packed)]
+struct MadtEntryHeader {
+    entry_type: u8,
+    length: u8,
+}
+
+/// MADT entry type 0: a processor and its local APIC
+#[derive(Copy, Clone, Debug)]
+#[repr(packed)]
+struct MadtLocalApic {
+    h: MadtEntryHeader,
+    processor_id: u8,
+    apic_id: u8,
+    flags: u32,
+}
+
+/// MADT entry type 1: an IO-APIC
+#[derive(Copy, Clone, Debug)]
+#[repr(packed)]
+struct MadtIoApic {
+    h: MadtEntryHeader,
+    io_apic_id: u8,
+    reserved: u8,
+    io_apic_address: u32,
+    global_system_interrupt_base: u32,
+}
+
+/// MADT entry type 2: an ISA IRQ which is not wired to the IO-APIC input of the same number
+#[derive(Copy, Clone, Debug)]
+#[repr(packed)]
+struct MadtInterruptSourceOverride {
+    h: MadtEntryHeader,
+    bus_source: u8,
+    irq_source: u8,
+    global_system_interrupt: u32,
+    flags: u16,
+}
+
+/// MADT entry type 5: the 64 bits address of the local APICs
+#[derive(Copy, Clone, Debug)]
+#[repr(packed)]
+struct MadtLocalApicAddressOverride {
+    h: MadtEntryHeader,
+    reserved: u16,
+    local_apic_address_0_31: u32,
+    local_apic_address_32_63: u32,
+}
+
+const MADT_LOCAL_APIC: u8 = 0;
+const MADT_IO_APIC: u8 = 1;
+const MADT_INTERRUPT_SOURCE_OVERRIDE: u8 = 2;
+const MADT_LOCAL_APIC_ADDRESS_OVERRIDE: u8 = 5;
+
+/// The MADT flag telling that the system has also a dual 8259 setup
+const MADT_PCAT_COMPAT: u32 = 1 << 0;
+/// The MADT local APIC flag telling that the processor is usable
+const MADT_PROCESSOR_ENABLED: u32 = 1 << 0;
+
+/// The maximum number of processors kept from the MADT
+pub const MADT_MAX_PROCESSORS: usize = 32;
+/// The maximum number of IO-APICs kept from the MADT
+pub const MADT_MAX_IO_APICS: usize = 8;
+/// The maximum number of interrupt source overrides kept from the MADT
+pub const MADT_MAX_OVERRIDES: usize = 16;
+
+/// A processor described by the MADT
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MadtProcessor {
+    /// The ACPI processor ID
+    pub processor_id: u8,
+    /// The ID of its local APIC
+    pub apic_id: u8,
+}
+
+/// An IO-APIC described by the MADT
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MadtIoApicInfo {
+    /// The ID of the IO-APIC
+    pub id: u8,
+    /// The physical address of its registers
+    pub address: u32,
+    /// The global system interrupt of its first input
+    pub gsi_base: u32,
+}
+
+/// An ISA IRQ connected to another global system interrupt, or with another polarity
+/// or trigger mode than the ISA ones (active high, edge triggered)
+#[derive(Copy, Clone, Debug, Default)]
+pub struct InterruptSourceOverride {
+    /// The ISA IRQ
+    pub irq: u8,
+    /// The global system interrupt where it is connected
+    pub gsi: u32,
+    /// The interrupt line is active low
+    pub active_low: bool,
+    /// The interrupt line is level triggered
+    pub level_triggered: bool,
+}
+
+/// The interrupt controllers described by the MADT
+#[derive(Copy, Clone, Debug)]
+pub struct Madt {
+    /// The physical address of the local APICs
+    pub local_apic_address: u32,
+    /// The system has also a dual 8259 setup, which must be masked to use the APICs
+    pub pcat_compat: bool,
+    processors: [MadtProcessor; MADT_MAX_PROCESSORS],
+    nbr_processors: usize,
+    io_apics: [MadtIoApicInfo; MADT_MAX_IO_APICS],
+    nbr_io_apics: usize,
+    overrides: [InterruptSourceOverride; MADT_MAX_OVERRIDES],
+    nbr_overrides: usize,
+}
+
+impl Madt {
+    /// The usable processors
+    pub fn processors(&self) -> &[MadtProcessor] {
+        &self.processors[..self.nbr_processors]
+    }
+
+    /// The IO-APICs
+    pub fn io_apics(&self) -> &[MadtIoApicInfo] {
+        &self.io_apics[..self.nbr_io_apics]
+    }
+
+    /// The interrupt source overrides
+    pub fn overrides(&self) -> &[InterruptSourceOverride] {
+        &self.overrides[..self.nbr_overrides]
+    }
+
+    /// Parse the MADT of `length` bytes at `madt`
+    unsafe fn parse(madt: *const u8, length: usize) -> Self {
+        let header = *(madt as *const MadtHeader);
+        let mut res = Self {
+            local_apic_address: header.local_apic_address,
+            pcat_compat: header.flags & MADT_PCAT_COMPAT != 0,
+            processors: [Default::default(); MADT_MAX_PROCESSORS],
+            nbr_processors: 0,
+            io_apics: [Default::default(); MADT_MAX_IO_APICS],
+            nbr_io_apics: 0,
+            overrides: [Default::default(); MADT_MAX_OVERRIDES],
+            nbr_overrides: 0,
+        };
+
+        let mut offset = size_of::<MadtHeader>();
+        while offset + size_of::<MadtEntryHeader>() <= length {
+            let entry = madt.add(offset);
+            let entry_header = *(entry as *const MadtEntryHeader);
+            let entry_length = entry_header.length as usize;
+            if entry_length < size_of::<MadtEntryHeader>() || offset + entry_length > length {
+                log::warn!("ACPI: malformed MADT entry at offset {}", offset);
+                break;
+            }
+            match entry_header.entry_type {
+                MADT_LOCAL_APIC if entry_length >= size_of::<MadtLocalApic>() => {
+                    let local_apic = *(entry as *const MadtLocalApic);
+                    if local_apic.flags & MADT_PROCESSOR_ENABLED != 0
+                        && res.nbr_processors < MADT_MAX_PROCESSORS
+                    {
+                        res.processors[res.nbr_processors] = MadtProcessor {
+                            processor_id: local_apic.processor_id,
+                            apic_id: local_apic.apic_id,
+                        };
+                        res.nbr_processors += 1;
+                    }
+                }
+                MADT_IO_APIC if entry_length >= size_of::<MadtIoApic>() => {
+                    let io_apic = *(entry as *const MadtIoApic);
+                    if res.nbr_io_apics < MADT_MAX_IO_APICS {
+                        res.io_apics[res.nbr_io_apics] = MadtIoApicInfo {
+                            id: io_apic.io_apic_id,
+                            address: io_apic.io_apic_address,
+                            gsi_base: io_apic.global_system_interrupt_base,
+                        };
+                        res.nbr_io_apics += 1;
+                    }
+                }
+                MADT_INTERRUPT_SOURCE_OVERRIDE
+                    if entry_length >= size_of::<MadtInterruptSourceOverride>() =>
+                {
+                    let source_override = *(entry as *const MadtInterruptSourceOverride);
+                    // Bits 0-1: polarity, 0b11 is active low. Bits 2-3: trigger mode, 0b11 is level
+                    let flags = source_override.flags;
+                    if source_override.bus_source == 0 && res.nbr_overrides < MADT_MAX_OVERRIDES {
+                        res.overrides[res.nbr_overrides] = InterruptSourceOverride {
+                            irq: source_override.irq_source,
+                            gsi: source_override.global_system_interrupt,
+                            active_low: flags & 0b11 == 0b11,
+                            level_triggered: (flags >> 2) & 0b11 == 0b11,
+                        };
+                        res.nbr_overrides += 1;
+                    }
+                }
+                MADT_LOCAL_APIC_ADDRESS_OVERRIDE
+                    if entry_length >= size_of::<MadtLocalApicAddressOverride>() =>
+                {
+                    let address_override = *(entry as *const MadtLocalApicAddressOverride);
+                    // The local APICs cannot be reached above 4GB without PAE
+                    if address_override.local_apic_address_32_63 == 0 {
+                        res.local_apic_address = address_override.local_apic_address_0_31;
+                    }
+                }
+                _ => {}
+            }
+            offset += entry_length;
+        }
+        res
+    }
+}
+
 /// Basics ACPI errors
 #[derive(Copy, Clone, Debug)]
 #[allow(missing_docs)]
@@ -247,6 +470,7 @@ pub enum AcpiError {
     Timeout,
     InternalError,
     BadAcpiVersion,
+    TableAbsent,
 }
 
 /// Standard ACPI type result
@@ -258,6 +482,7 @@ pub type AcpiResult<T> = core::result::Result<T, AcpiError>;
 pub struct Acpi {
     rsdp_descriptor: RSDPDescriptor,
     fadt: FADT,
+    madt: Option<Madt>,
 }
 
 lazy_static! {
@@ -280,6 +505,7 @@ impl Acpi {
     pub fn init() -> AcpiResult<()> {
         let rsdp_descriptor: RSDPDescriptor;
         let fadt;
+        let madt;
 
         unsafe {
             let rsdp_descriptor_ptr: *const RSDPDescriptor10 = get_rsdp_descriptor_ptr()?;
@@ -304,12 +530,22 @@ impl Acpi {
             fadt = find_fadt(virt_addr as *const Rsdt);
 
             unmap(virt_addr as *mut u8, size_of::<ACPIRSDTHeader>());
+
+            madt = match map_table(rsdp_descriptor, b"APIC") {
+                Ok((table, length)) => {
+                    let madt = Madt::parse(table, length);
+                    unmap(table as *mut u8, length);
+                    Some(madt)
+                }
+                Err(_) => None,
+            };
         }
         match fadt {
             Ok(fadt) => {
                 *ACPI.lock() = Some(Self {
                     rsdp_descriptor,
                     fadt,
+                    madt,
                 });
                 Ok(())
             }
@@ -317,6 +553,11 @@ impl Acpi {
         }
     }
 
+    /// Get the interrupt controllers described by the MADT, if the firmware gave one
+    pub fn get_madt(&self) -> Option<Madt> {
+        self.madt
+    }
+
     /// Enable ACPI
     pub fn enable(&mut self) -> AcpiResult<()> {
         // give 3 seconds for ACPI initialization timeout
@@ -451,6 +692,62 @@ unsafe fn get_rsdp_descriptor_ptr() -> AcpiResult<*const RSDPDescriptor10> {
     }
 }
 
+/// Map a whole ACPI table from its header at `address`
+/// Returns the table and its length, which must be given to unmap
+unsafe fn map_sdt(address: u32) -> AcpiResult<(*const u8, usize)> {
+    let header = map(address as *mut u8, size_of::<ACPIRSDTHeader>()) as *const ACPIRSDTHeader;
+    if header.is_null() {
+        return Err(AcpiError::InternalError);
+    }
+    let length = (*header).length as usize;
+    unmap(header as *mut u8, size_of::<ACPIRSDTHeader>());
+    if length < size_of::<ACPIRSDTHeader>() {
+        return Err(AcpiError::InternalError);
+    }
+    let table = map(address as *mut u8, length);
+    if table.is_null() {
+        return Err(AcpiError::InternalError);
+    }
+    Ok((table as *const u8, length))
+}
+
+/// Find the table with `signature` among the entries of the RSDT (or of the XSDT) and map it
+/// Returns the table and its length, which must be given to unmap
+unsafe fn map_table(
+    rsdp_descriptor: RSDPDescriptor,
+    signature: &[u8; 4],
+) -> AcpiResult<(*const u8, usize)> {
+    // The RSDT entries are 32 bits addresses, the XSDT ones are 64 bits addresses
+    let (sdt_address, entry_size) = match rsdp_descriptor {
+        RSDPDescriptor::LegacyRSDPDescriptor(descriptor) => (descriptor.rsdt_address, 4),
+        RSDPDescriptor::AdvancedRSDPDescriptor(descriptor) => (descriptor.xsdt_address_0_31, 8),
+    };
+    let (sdt, sdt_length) = map_sdt(sdt_address)?;
+    let entries = (sdt_length - size_of::<ACPIRSDTHeader>()) / entry_size;
+
+    let mut res = Err(AcpiError::TableAbsent);
+    for i in 0..entries {
+        let entry = sdt.add(size_of::<ACPIRSDTHeader>() + i * entry_size) as *const u32;
+        // Tables above 4GB cannot be reached without PAE
+        if entry_size == 8 && entry.add(1).read_unaligned() != 0 {
+            continue;
+        }
+        let address = entry.read_unaligned();
+        let header = map(address as *mut u8, size_of::<ACPIRSDTHeader>()) as *const ACPIRSDTHeader;
+        if header.is_null() {
+            continue;
+        }
+        let found = (*header).signature == *signature;
+        unmap(header as *mut u8, size_of::<ACPIRSDTHeader>());
+        if found {
+            res = map_sdt(address);
+            break;
+        }
+    }
+    unmap(sdt as *mut u8, sdt_length);
+    res
+}
+
 /// Search the big fucking table
 unsafe fn find_fadt(rsdt: *const Rsdt) -> AcpiResult<FADT> {
     let entries = (((*rsdt).h.length - size_of::<ACPIRSDTHeader>() as u32) / 4) as usize;
@@ -533,3 +830,149 @@ unsafe fn memschr(
     }
     return Err(AcpiError::InternalError);
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::vec::Vec;
+
+    /// A MADT header, without entries
+    fn madt_header(local_apic_address: u32, flags: u32) -> Vec<u8> {
+        let mut madt = vec![0; size_of::<ACPIRSDTHeader>()];
+        madt[..4].copy_from_slice(b"APIC");
+        madt.extend_from_slice(&local_apic_address.to_le_bytes());
+        madt.extend_from_slice(&flags.to_le_bytes());
+        madt
+    }
+
+    /// Append an entry of type `entry_type` to `madt`
+    fn madt_push(madt: &mut Vec<u8>, entry_type: u8, content: &[u8]) {
+        madt.push(entry_type);
+        madt.push((size_of::<MadtEntryHeader>() + content.len()) as u8);
+        madt.extend_from_slice(content);
+    }
+
+    fn madt_push_local_apic(madt: &mut Vec<u8>, processor_id: u8, apic_id: u8, flags: u32) {
+        let mut content = vec![processor_id, apic_id];
+        content.extend_from_slice(&flags.to_le_bytes());
+        madt_push(madt, MADT_LOCAL_APIC, &content);
+    }
+
+    fn madt_push_io_apic(madt: &mut Vec<u8>, id: u8, address: u32, gsi_base: u32) {
+        let mut content = vec![id, 0];
+        content.extend_from_slice(&address.to_le_bytes());
+        content.extend_from_slice(&gsi_base.to_le_bytes());
+        madt_push(madt, MADT_IO_APIC, &content);
+    }
+
+    fn madt_push_override(madt: &mut Vec<u8>, bus: u8, irq: u8, gsi: u32, flags: u16) {
+        let mut content = vec![bus, irq];
+        content.extend_from_slice(&gsi.to_le_bytes());
+        content.extend_from_slice(&flags.to_le_bytes());
+        madt_push(madt, MADT_INTERRUPT_SOURCE_OVERRIDE, &content);
+    }
+
+    fn parse(madt: &[u8]) -> Madt {
+        unsafe { Madt::parse(madt.as_ptr(), madt.len()) }
+    }
+
+    #[test]
+    fn madt_processors_and_io_apics() {
+        let mut madt = madt_header(0xfee0_0000, MADT_PCAT_COMPAT);
+        madt_push_local_apic(&mut madt, 0, 0, MADT_PROCESSOR_ENABLED);
+        madt_push_local_apic(&mut madt, 1, 2, MADT_PROCESSOR_ENABLED);
+        // A disabled processor is not usable
+        madt_push_local_apic(&mut madt, 2, 4, 0);
+        madt_push_io_apic(&mut madt, 1, 0xfec0_0000, 0);
+        madt_push_io_apic(&mut madt, 2, 0xfec0_1000, 24);
+        // An unknown entry is skipped
+        madt_push(&mut madt, 0x7f, &[0; 6]);
+
+        let madt = parse(&madt);
+        assert_eq!(madt.local_apic_address, 0xfee0_0000);
+        assert!(madt.pcat_compat);
+        let processors: Vec<_> = madt
+            .processors()
+            .iter()
+            .map(|p| (p.processor_id, p.apic_id))
+            .collect();
+        assert_eq!(processors, [(0, 0), (1, 2)]);
+        let io_apics: Vec<_> = madt
+            .io_apics()
+            .iter()
+            .map(|io_apic| (io_apic.id, io_apic.address, io_apic.gsi_base))
+            .collect();
+        assert_eq!(io_apics, [(1, 0xfec0_0000, 0), (2, 0xfec0_1000, 24)]);
+        assert!(madt.overrides().is_empty());
+    }
+
+    #[test]
+    fn madt_interrupt_source_overrides() {
+        let mut madt = madt_header(0xfee0_0000, 0);
+        // PIT0 on the input 2, conforming to the ISA bus
+        madt_push_override(&mut madt, 0, 0, 2, 0);
+        // The SCI, level triggered and active high
+        madt_push_override(&mut madt, 0, 9, 9, 0b1101);
+        // A PCI line, level triggered and active low
+        madt_push_override(&mut madt, 0, 11, 20, 0b1111);
+        // Only the ISA bus is overridden
+        madt_push_override(&mut madt, 1, 5, 5, 0b1111);
+
+        let madt = parse(&madt);
+        assert!(!madt.pcat_compat);
+        let overrides: Vec<_> = madt
+            .overrides()
+            .iter()
+            .map(|o| (o.irq, o.gsi, o.active_low, o.level_triggered))
+            .collect();
+        assert_eq!(
+            overrides,
+            [
+                (0, 2, false, false),
+                (9, 9, false, true),
+                (11, 20, true, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn madt_local_apic_address_override() {
+        let mut madt = madt_header(0xfee0_0000, 0);
+        let mut content = vec![0; 2];
+        content.extend_from_slice(&0xfed0_0000u32.to_le_bytes());
+        content.extend_from_slice(&0u32.to_le_bytes());
+        madt_push(&mut madt, MADT_LOCAL_APIC_ADDRESS_OVERRIDE, &content);
+        assert_eq!(parse(&madt).local_apic_address, 0xfed0_0000);
+
+        // An address above 4GB cannot be reached
+        let mut madt = madt_header(0xfee0_0000, 0);
+        let mut content = vec![0; 2];
+        content.extend_from_slice(&0xfed0_0000u32.to_le_bytes());
+        content.extend_from_slice(&1u32.to_le_bytes());
+        madt_push(&mut madt, MADT_LOCAL_APIC_ADDRESS_OVERRIDE, &content);
+        assert_eq!(parse(&madt).local_apic_address, 0xfee0_0000);
+    }
+
+    #[test]
+    fn madt_malformed_entries() {
+        let mut madt = madt_header(0xfee0_0000, 0);
+        madt_push_local_apic(&mut madt, 0, 0, MADT_PROCESSOR_ENABLED);
+        // Too short for an IO-APIC, it is skipped
+        madt_push(&mut madt, MADT_IO_APIC, &[1, 0]);
+        // An entry longer than the table ends the parsing
+        let truncated = madt.len();
+        madt_push_local_apic(&mut madt, 1, 1, MADT_PROCESSOR_ENABLED);
+        madt_push_local_apic(&mut madt, 2, 2, MADT_PROCESSOR_ENABLED);
+        madt[truncated + 1] = 0xff;
+
+        let madt = parse(&madt);
+        assert_eq!(madt.processors().len(), 1);
+        assert!(madt.io_apics().is_empty());
+
+        // An entry of length 0 ends the parsing
+        let mut madt = madt_header(0xfee0_0000, 0);
+        madt.extend_from_slice(&[MADT_LOCAL_APIC, 0]);
+        madt_push_local_apic(&mut madt, 0, 0, MADT_PROCESSOR_ENABLED);
+        assert!(parse(&madt).processors().is_empty());
+    }
+}
@@ -0,0 +1,360 @@
+//! This files contains the code related to the APICs, which replace the 8259 PICs when
+//! the MADT of ACPI describes them: the local APIC of the processor and the IO-APICs.
+//! See [APIC](https://wiki.osdev.org/APIC) and [IOAPIC](https://wiki.osdev.org/IOAPIC).
+//!
+//! The legacy IRQs keep the vectors of the 8259 (0x20 to 0x2f), so they keep their
+//! interrupt service routines, which send their EOI to the local APIC once
+//! `_lapic_eoi_register` is set.
+
+mod io_apic;
+pub use io_apic::{IoApic, RedirectionEntry};
+
+mod local_apic;
+pub use local_apic::LocalApic;
+
+use super::acpi::{Madt, MADT_MAX_IO_APICS};
+use super::pic_8259::{set_irq_handler, KERNEL_PIC_MASTER_IDT_VECTOR, PIC_8259};
+use crate::memory::ffi::map;
+use crate::Spinlock;
+use arrayvec::ArrayVec;
+use bit_field::BitField;
+use core::convert::TryFrom;
+use irq::Irq;
+use lazy_static::lazy_static;
+
+/// The vector raised by the spurious interrupts of the local APIC
+pub const SPURIOUS_VECTOR: u8 = 0xff;
+
+/// The number of legacy IRQs
+const NBR_ISA_IRQS: usize = 16;
+
+/// The legacy IRQs whose vectors can be raised by the PCI interrupts above the ISA ones
+const PCI_IRQS: [Irq; 3] = [Irq::Irq11, Irq::Irq10, Irq::ACPI];
+
+/// The size mapped for the registers of an APIC
+const APIC_REGISTERS_SIZE: usize = 0x1000;
+
+/// The CPUID.01h:EDX bit telling that the processor has a local APIC
+const CPUID_APIC: u32 = 1 << 9;
+
+extern "C" {
+    fn _isr_lapic_spurious();
+    static mut _lapic_eoi_register: u32;
+}
+
+lazy_static! {
+    /// APIC driver, None while the 8259 routes the IRQs
+    pub static ref APIC: Spinlock<Option<Apic>> = Spinlock::new(None);
+}
+
+/// The source of the scheduler tick
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TickSource {
+    /// PIT0, through the IO-APIC
+    Pit,
+    /// The timer of the local APIC, calibrated to tick at the frequency of PIT0
+    LocalApicTimer,
+}
+
+/// Basics APIC errors
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ApicError {
+    NoLocalApic,
+    NoIoApic,
+    CannotMap,
+}
+
+/// Where a legacy IRQ is connected on the IO-APICs
+#[derive(Debug, Copy, Clone)]
+struct IsaRoute {
+    gsi: u32,
+    active_low: bool,
+    level_triggered: bool,
+    /// The route comes from an interrupt source override of the MADT
+    overridden: bool,
+}
+
+/// Main driver structure: the local APIC of the processor and the IO-APICs
+pub struct Apic {
+    local_apic: LocalApic,
+    io_apics: ArrayVec<[IoApic; MADT_MAX_IO_APICS]>,
+    isa_routes: [IsaRoute; NBR_ISA_IRQS],
+    tick_source: TickSource,
+    /// The initial count of the local APIC timer for a tick of PIT0
+    timer_initial_count: u32,
+}
+
+impl Apic {
+    /// Initialize the local APIC and the IO-APICs described by `madt`, then move the
+    /// enabled IRQs of the 8259 to the IO-APICs and mask the 8259.
+    /// PIT0 must tick through the 8259 with the interrupts enabled, as it calibrates
+    /// the local APIC timer
+    pub unsafe fn init(madt: &Madt, tick_source: TickSource) -> Result<(), ApicError> {
+        if core::arch::x86::__cpuid(1).edx & CPUID_APIC == 0 {
+            return Err(ApicError::NoLocalApic);
+        }
+        if madt.io_apics().is_empty() {
+            return Err(ApicError::NoIoApic);
+        }
+
+        let local_apic_base = map(madt.local_apic_address as *mut u8, APIC_REGISTERS_SIZE);
+        if local_apic_base.is_null() {
+            return Err(ApicError::CannotMap);
+        }
+        let mut io_apics = ArrayVec::new();
+        for io_apic in madt.io_apics() {
+            let base = map(io_apic.address as *mut u8, APIC_REGISTERS_SIZE);
+            if base.is_null() {
+                return Err(ApicError::CannotMap);
+            }
+            io_apics.push(IoApic::new(base as usize, io_apic.id, io_apic.gsi_base));
+        }
+
+        // The ISA IRQs are connected to the same global system interrupts, active high
+        // and edge triggered, unless overridden
+        let mut isa_routes = [IsaRoute {
+            gsi: 0,
+            active_low: false,
+            level_triggered: false,
+            overridden: false,
+        }; NBR_ISA_IRQS];
+        for (irq, route) in isa_routes.iter_mut().enumerate() {
+            route.gsi = irq as u32;
+        }
+        for source_override in madt.overrides() {
+            if let Some(route) = isa_routes.get_mut(source_override.irq as usize) {
+                *route = IsaRoute {
+                    gsi: source_override.gsi,
+                    active_low: source_override.active_low,
+                    level_triggered: source_override.level_triggered,
+                    overridden: true,
+                };
+            }
+        }
+
+        let mut apic = Self {
+            local_apic: LocalApic::new(local_apic_base as usize),
+            io_apics,
+            isa_routes,
+            tick_source,
+            timer_initial_count: 0,
+        };
+        set_idt_gate(SPURIOUS_VECTOR, _isr_lapic_spurious);
+        apic.local_apic.enable(SPURIOUS_VECTOR);
+        log::info!(
+            "Local APIC {} version {:#x}, {} IO-APIC(s)",
+            apic.local_apic.id(),
+            apic.local_apic.version(),
+            apic.io_apics.len()
+        );
+        if tick_source == TickSource::LocalApicTimer {
+            apic.timer_initial_count = apic.local_apic.calibrate_timer();
+            log::info!(
+                "Local APIC timer: {} counts per tick",
+                apic.timer_initial_count
+            );
+        }
+
+        without_interrupts!({
+            let masks = {
+                let mut pic_8259 = PIC_8259.lock();
+                let masks = pic_8259.get_masks();
+                pic_8259.disable_all_irqs();
+                masks
+            };
+            apic.io_apics
+                .iter_mut()
+                .for_each(|io_apic| io_apic.mask_all());
+
+            let destination = apic.local_apic.id();
+            for irq in 0..NBR_ISA_IRQS {
+                let route = apic.isa_routes[irq];
+                let entry =
+                    RedirectionEntry::new(KERNEL_PIC_MASTER_IDT_VECTOR + irq as u8, destination)
+                        .set_active_low(route.active_low)
+                        .set_level_triggered(route.level_triggered);
+                if let Some(io_apic) = apic.io_apic(route.gsi) {
+                    io_apic.set_redirection(route.gsi, entry);
+                }
+            }
+            // The cascade line of the 8259 does not exist on the IO-APICs
+            for irq in (0..NBR_ISA_IRQS)
+                .filter(|&irq| !masks.get_bit(irq) && irq != Irq::SlaveCascadeIRQ as usize)
+            {
+                apic.set_isa_irq_masked(irq, false);
+            }
+            apic.start_tick();
+
+            _lapic_eoi_register = apic.local_apic.eoi_register() as u32;
+            *APIC.lock() = Some(apic);
+        });
+        Ok(())
+    }
+
+    /// Get the IO-APIC which has the global system interrupt `gsi` as input
+    fn io_apic(&mut self, gsi: u32) -> Option<&mut IoApic> {
+        self.io_apics
+            .iter_mut()
+            .find(|io_apic| io_apic.handles(gsi))
+    }
+
+    /// Mask or unmask the IO-APIC input of the legacy IRQ `irq`
+    /// PIT0 stays masked when the local APIC timer is the tick source, as they share a vector
+    unsafe fn set_isa_irq_masked(&mut self, irq: usize, masked: bool) {
+        let masked = masked
+            || (irq == Irq::SystemTimer as usize && self.tick_source == TickSource::LocalApicTimer);
+        let gsi = self.isa_routes[irq].gsi;
+        match self.io_apic(gsi) {
+            Some(io_apic) => io_apic.set_masked(gsi, masked),
+            None => log::warn!("APIC: no IO-APIC for irq {} (gsi {})", irq, gsi),
+        }
+    }
+
+    /// Start the local APIC timer if it is the tick source
+    unsafe fn start_tick(&mut self) {
+        if self.tick_source == TickSource::LocalApicTimer {
+            self.local_apic
+                .start_periodic_timer(KERNEL_PIC_MASTER_IDT_VECTOR, self.timer_initial_count);
+        }
+    }
+
+    /// Get the local APIC of the processor. The other processors use the same one
+    /// to reach their own local APIC
+    pub fn get_local_apic(&self) -> LocalApic {
+        self.local_apic
+    }
+
+    /// Get the source of the scheduler tick
+    pub fn get_tick_source(&self) -> TickSource {
+        self.tick_source
+    }
+
+    /// Enable the legacy IRQ `irq` on the IO-APICs.
+    /// When used without function option. the default symbol in asm file is called
+    pub unsafe fn enable_irq(&mut self, irq: Irq, func_opt: Option<unsafe extern "C" fn()>) {
+        log::debug!("Apic: Enable irq {:?}", irq);
+        if let Some(func) = func_opt {
+            set_irq_handler(irq, func);
+        }
+        self.set_isa_irq_masked(irq as usize, false);
+    }
+
+    /// Enable the interrupt line `line` of a PCI device on the IO-APICs, and returns the
+    /// legacy IRQ whose vector it raises.
+    /// Without the _PRT of the DSDT, a line below 16 is taken on the IO-APIC input of the
+    /// legacy IRQ given by the BIOS, with the polarity and trigger mode of its interrupt
+    /// source override. Without override, behind the PCI interrupt router it is active
+    /// high, and level triggered as it is shared.
+    /// A line above is a global system interrupt given by the firmware, active low and
+    /// level triggered like the PCI interrupts, which raises the vector of a free legacy IRQ
+    pub unsafe fn enable_pci_irq(
+        &mut self,
+        line: u8,
+        func_opt: Option<unsafe extern "C" fn()>,
+    ) -> Option<Irq> {
+        log::debug!("Apic: Enable PCI interrupt line {}", line);
+        let irq = match Irq::try_from(line) {
+            Ok(irq) => {
+                let route = &mut self.isa_routes[irq as usize];
+                if !route.overridden {
+                    route.level_triggered = true;
+                }
+                irq
+            }
+            Err(()) => {
+                let gsi = line as u32;
+                self.io_apic(gsi)?;
+                let irq = self.free_pci_irq(gsi)?;
+                self.isa_routes[irq as usize] = IsaRoute {
+                    gsi,
+                    active_low: true,
+                    level_triggered: true,
+                    overridden: false,
+                };
+                irq
+            }
+        };
+        let route = self.isa_routes[irq as usize];
+
+        let destination = self.local_apic.id();
+        let entry = RedirectionEntry::new(KERNEL_PIC_MASTER_IDT_VECTOR + irq as u8, destination)
+            .set_active_low(route.active_low)
+            .set_level_triggered(route.level_triggered);
+        self.io_apic(route.gsi)?.set_redirection(route.gsi, entry);
+        self.enable_irq(irq, func_opt);
+        Some(irq)
+    }
+
+    /// Get the legacy IRQ whose vector is raised by the PCI global system interrupt `gsi`:
+    /// the one it already raises, else one whose IO-APIC input is masked
+    fn free_pci_irq(&mut self, gsi: u32) -> Option<Irq> {
+        if let Some(irq) = PCI_IRQS
+            .iter()
+            .find(|&&irq| self.isa_routes[irq as usize].gsi == gsi)
+        {
+            return Some(*irq);
+        }
+        PCI_IRQS.iter().cloned().find(|&irq| {
+            let legacy_gsi = self.isa_routes[irq as usize].gsi;
+            self.io_apic(legacy_gsi).map_or(true, |io_apic| {
+                io_apic.get_redirection(legacy_gsi).is_masked()
+            })
+        })
+    }
+
+    /// Disable the legacy IRQ `irq` on the IO-APICs
+    pub unsafe fn disable_irq(&mut self, irq: Irq) {
+        log::debug!("Apic: Disable irq {:?}", irq);
+        self.set_isa_irq_masked(irq as usize, true);
+    }
+
+    /// Send end of interrupt to the local APIC
+    pub fn send_eoi(&mut self) {
+        unsafe {
+            self.local_apic.send_eoi();
+        }
+    }
+
+    /// Mask the IRQs and stop the local APIC timer, for a call to the BIOS through the 8259.
+    /// Returns the masks of the legacy IRQs, which are given back to `resume`
+    pub unsafe fn suspend(&mut self) -> u16 {
+        let mut masks: u16 = 0;
+        for irq in 0..NBR_ISA_IRQS {
+            let gsi = self.isa_routes[irq].gsi;
+            if let Some(io_apic) = self.io_apic(gsi) {
+                masks.set_bit(irq, io_apic.get_redirection(gsi).is_masked());
+                io_apic.set_masked(gsi, true);
+            }
+        }
+        self.local_apic.stop_timer();
+        masks
+    }
+
+    /// Restore the masks of the legacy IRQs and the local APIC timer
+    pub unsafe fn resume(&mut self, masks: u16) {
+        for irq in 0..NBR_ISA_IRQS {
+            let gsi = self.isa_routes[irq].gsi;
+            if let Some(io_apic) = self.io_apic(gsi) {
+                io_apic.set_masked(gsi, masks.get_bit(irq));
+            }
+        }
+        self.start_tick();
+    }
+}
+
+/// Set the interrupt gate of `vector` to `handler`
+pub(crate) unsafe fn set_idt_gate(vector: u8, handler: unsafe extern "C" fn()) {
+    use core::ffi::c_void;
+    use interrupts::idt::GateType::InterruptGate32;
+    use interrupts::idt::*;
+
+    let mut interrupt_table = InterruptTable::current_interrupt_table();
+    let mut gate_entry = *IdtGateEntry::new()
+        .set_storage_segment(false)
+        .set_privilege_level(0)
+        .set_selector(1 << 3)
+        .set_gate_type(InterruptGate32);
+
+    gate_entry.set_handler(handler as *const c_void as u32);
+    interrupt_table[vector as usize] = gate_entry;
+}
@@ -0,0 +1,41 @@
+[BITS 32]
+
+;; This file contains the interrupt service routines of the local APIC
+;; See https://wiki.osdev.org/APIC#Spurious_Interrupt_Vector_Register
+
+segment .text
+
+; The spurious interrupts of the local APIC must not be acknowledged
+global _isr_lapic_spurious
+_isr_lapic_spurious:
+	iret
+
+extern _lapic_eoi_register
+extern tlb_shootdown_interrupt_handler
+
+; Send end of interrupt to the local APIC of the current processor
+%macro LAPIC_EOI 0
+	mov eax, dword [_lapic_eoi_register]
+	mov dword [eax], 0
+%endmacro
+
+; Another processor changed page tables used by this one, see smp/tlb_shootdown.rs
+global _isr_ipi_tlb_shootdown
+_isr_ipi_tlb_shootdown:
+	push ebp
+	mov ebp, esp
+	pushad
+	cld
+	call tlb_shootdown_interrupt_handler
+	LAPIC_EOI
+	popad
+	pop ebp
+	iret
+
+; Another processor queued work for the current one: the interrupt only wakes up its idle task
+global _isr_ipi_wake_up
+_isr_ipi_wake_up:
+	push eax
+	LAPIC_EOI
+	pop eax
+	iret
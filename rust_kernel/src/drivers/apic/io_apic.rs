@@ -0,0 +1,136 @@
+//! This file contains the code related to the IO-APICs: each one routes its inputs,
+//! the global system interrupts from `gsi_base`, to the local APICs.
+//! See https://wiki.osdev.org/IOAPIC (and the Intel 82093AA datasheet)
+
+use bit_field::BitField;
+use core::ptr::{read_volatile, write_volatile};
+
+/// Represents an IO-APIC, through its mapped registers
+#[derive(Debug)]
+pub struct IoApic {
+    /// The virtual address of the registers
+    base: usize,
+    id: u8,
+    gsi_base: u32,
+    nbr_inputs: u32,
+}
+
+impl IoApic {
+    /// The register selecting the indirect register accessed by IOWIN
+    const IOREGSEL: usize = 0x00;
+
+    /// The window on the selected register
+    const IOWIN: usize = 0x10;
+
+    const IOAPICVER: u32 = 0x01;
+
+    /// The first redirection table register, each entry uses two registers
+    const IOREDTBL: u32 = 0x10;
+
+    /// Creates an IoApic with its registers mapped at `base`
+    pub unsafe fn new(base: usize, id: u8, gsi_base: u32) -> Self {
+        let mut io_apic = Self {
+            base,
+            id,
+            gsi_base,
+            nbr_inputs: 0,
+        };
+        // Bits 16 to 23 of the version register: the index of the last redirection entry
+        io_apic.nbr_inputs = io_apic.read(Self::IOAPICVER).get_bits(16..24) + 1;
+        io_apic
+    }
+
+    fn read(&self, register: u32) -> u32 {
+        unsafe {
+            write_volatile((self.base + Self::IOREGSEL) as *mut u32, register);
+            read_volatile((self.base + Self::IOWIN) as *const u32)
+        }
+    }
+
+    unsafe fn write(&mut self, register: u32, value: u32) {
+        write_volatile((self.base + Self::IOREGSEL) as *mut u32, register);
+        write_volatile((self.base + Self::IOWIN) as *mut u32, value);
+    }
+
+    /// Get the ID of the IO-APIC
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Check if the global system interrupt `gsi` is an input of this IO-APIC
+    pub fn handles(&self, gsi: u32) -> bool {
+        gsi >= self.gsi_base && gsi - self.gsi_base < self.nbr_inputs
+    }
+
+    /// Get the redirection entry of the global system interrupt `gsi`
+    pub fn get_redirection(&self, gsi: u32) -> RedirectionEntry {
+        assert!(self.handles(gsi));
+        let register = Self::IOREDTBL + (gsi - self.gsi_base) * 2;
+        RedirectionEntry(self.read(register) as u64 | (self.read(register + 1) as u64) << 32)
+    }
+
+    /// Set the redirection entry of the global system interrupt `gsi`
+    pub unsafe fn set_redirection(&mut self, gsi: u32, entry: RedirectionEntry) {
+        assert!(self.handles(gsi));
+        let register = Self::IOREDTBL + (gsi - self.gsi_base) * 2;
+        // Keep the entry masked while it is incomplete
+        self.write(register, RedirectionEntry::MASKED);
+        self.write(register + 1, (entry.0 >> 32) as u32);
+        self.write(register, entry.0 as u32);
+    }
+
+    /// Mask or unmask the global system interrupt `gsi`
+    pub unsafe fn set_masked(&mut self, gsi: u32, masked: bool) {
+        let entry = self.get_redirection(gsi).set_masked(masked);
+        self.set_redirection(gsi, entry);
+    }
+
+    /// Mask all the inputs
+    pub unsafe fn mask_all(&mut self) {
+        for gsi in self.gsi_base..self.gsi_base + self.nbr_inputs {
+            self.set_masked(gsi, true);
+        }
+    }
+}
+
+/// An entry of the redirection table: how an input of the IO-APIC is delivered
+#[derive(Debug, Copy, Clone)]
+pub struct RedirectionEntry(u64);
+
+impl RedirectionEntry {
+    const MASKED: u32 = 1 << 16;
+
+    /// Creates a masked entry raising `vector` on the local APIC `destination`,
+    /// for an active high and edge triggered input (the ISA ones)
+    pub fn new(vector: u8, destination: u8) -> Self {
+        let mut entry: u64 = 0;
+        // Fixed delivery mode (bits 8-10) and physical destination mode (bit 11) are zeroes
+        entry.set_bits(0..8, vector as u64);
+        entry.set_bit(16, true);
+        entry.set_bits(56..64, destination as u64);
+        Self(entry)
+    }
+
+    /// Set the polarity of the input
+    pub fn set_active_low(mut self, active_low: bool) -> Self {
+        self.0.set_bit(13, active_low);
+        self
+    }
+
+    /// Set the trigger mode of the input
+    pub fn set_level_triggered(mut self, level_triggered: bool) -> Self {
+        self.0.set_bit(15, level_triggered);
+        self
+    }
+
+    /// Set the mask of the input
+    pub fn set_masked(mut self, masked: bool) -> Self {
+        self.0.set_bit(16, masked);
+        self
+    }
+
+    /// Check if the input is masked
+    pub fn is_masked(&self) -> bool {
+        self.0.get_bit(16)
+    }
+}
@@ -0,0 +1,201 @@
+//! This file contains the code related to the local APIC of the processor: it receives
+//! the interrupts of the IO-APICs and has its own timer.
+//! See https://wiki.osdev.org/APIC_timer
+//!
+//! The local APIC also sends the inter-processor interrupts, through its interrupt
+//! command register. See https://wiki.osdev.org/Symmetric_Multiprocessing
+
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::spin_loop_hint;
+
+extern "C" {
+    fn _sleep(next_tic: u32) -> ();
+}
+
+/// Represents the local APIC of the current processor, through its mapped registers.
+/// Each processor sees its own local APIC at the same address
+#[derive(Debug, Copy, Clone)]
+pub struct LocalApic {
+    /// The virtual address of the registers
+    base: usize,
+}
+
+impl LocalApic {
+    const ID: usize = 0x20;
+    const VERSION: usize = 0x30;
+    const TASK_PRIORITY: usize = 0x80;
+    const EOI: usize = 0xb0;
+    const SPURIOUS_INTERRUPT_VECTOR: usize = 0xf0;
+    const INTERRUPT_COMMAND_LOW: usize = 0x300;
+    const INTERRUPT_COMMAND_HIGH: usize = 0x310;
+    const LVT_TIMER: usize = 0x320;
+    const LVT_ERROR: usize = 0x370;
+    const TIMER_INITIAL_COUNT: usize = 0x380;
+    const TIMER_CURRENT_COUNT: usize = 0x390;
+    const TIMER_DIVIDE_CONFIGURATION: usize = 0x3e0;
+
+    /// The APIC software enable bit of the spurious interrupt vector register
+    const SOFTWARE_ENABLE: u32 = 1 << 8;
+
+    /// The mask bit of the local vector table entries
+    const LVT_MASKED: u32 = 1 << 16;
+
+    /// The periodic mode bit of the timer local vector table entry
+    const LVT_TIMER_PERIODIC: u32 = 1 << 17;
+
+    /// The timer counts at the bus frequency divided by 16
+    const TIMER_DIVIDE_BY_16: u32 = 0b0011;
+
+    /// The delivery modes of the interrupt command register
+    const DELIVERY_MODE_INIT: u32 = 0b101 << 8;
+    const DELIVERY_MODE_STARTUP: u32 = 0b110 << 8;
+
+    /// The interrupt command register is busy while this bit is set
+    const DELIVERY_STATUS_PENDING: u32 = 1 << 12;
+
+    /// The level bit of the interrupt command register, set for all but the INIT deassert
+    const LEVEL_ASSERT: u32 = 1 << 14;
+
+    /// The destination shorthand of the interrupt command register: all but the sender
+    const ALL_EXCLUDING_SELF: u32 = 0b11 << 18;
+
+    /// The number of PIT0 ticks used to calibrate the timer
+    const CALIBRATION_TICKS: u32 = 10;
+
+    /// The IA32_APIC_BASE model specific register
+    const IA32_APIC_BASE_MSR: u32 = 0x1b;
+
+    /// The APIC global enable bit of IA32_APIC_BASE
+    const APIC_GLOBAL_ENABLE: u32 = 1 << 11;
+
+    /// Creates a LocalApic with its registers mapped at `base`
+    pub const fn new(base: usize) -> Self {
+        Self { base }
+    }
+
+    fn read(&self, register: usize) -> u32 {
+        unsafe { read_volatile((self.base + register) as *const u32) }
+    }
+
+    unsafe fn write(&mut self, register: usize, value: u32) {
+        write_volatile((self.base + register) as *mut u32, value)
+    }
+
+    /// Get the ID of the local APIC
+    pub fn id(&self) -> u8 {
+        (self.read(Self::ID) >> 24) as u8
+    }
+
+    /// Get the version of the local APIC
+    pub fn version(&self) -> u8 {
+        self.read(Self::VERSION) as u8
+    }
+
+    /// Enable the local APIC, its spurious interrupts raise `spurious_vector`
+    pub unsafe fn enable(&mut self, spurious_vector: u8) {
+        let (low, high) = rdmsr(Self::IA32_APIC_BASE_MSR);
+        wrmsr(
+            Self::IA32_APIC_BASE_MSR,
+            low | Self::APIC_GLOBAL_ENABLE,
+            high,
+        );
+
+        // Accept the interrupts of all the priorities
+        self.write(Self::TASK_PRIORITY, 0);
+        self.write(Self::LVT_ERROR, Self::LVT_MASKED);
+        self.write(Self::LVT_TIMER, Self::LVT_MASKED);
+        self.write(
+            Self::SPURIOUS_INTERRUPT_VECTOR,
+            Self::SOFTWARE_ENABLE | spurious_vector as u32,
+        );
+    }
+
+    /// Send end of interrupt to the local APIC
+    pub unsafe fn send_eoi(&mut self) {
+        self.write(Self::EOI, 0);
+    }
+
+    /// Get the address of the EOI register, which is written by the interrupt service routines
+    pub fn eoi_register(&self) -> *mut u32 {
+        (self.base + Self::EOI) as *mut u32
+    }
+
+    /// Send an interrupt command to the local APIC `destination`, then wait for its delivery
+    unsafe fn send_command(&mut self, destination: u8, command: u32) {
+        self.write(Self::INTERRUPT_COMMAND_HIGH, (destination as u32) << 24);
+        self.write(Self::INTERRUPT_COMMAND_LOW, command);
+        while self.read(Self::INTERRUPT_COMMAND_LOW) & Self::DELIVERY_STATUS_PENDING != 0 {
+            spin_loop_hint();
+        }
+    }
+
+    /// Send an INIT to the processor of the local APIC `apic_id`, which waits for a startup
+    pub unsafe fn send_init(&mut self, apic_id: u8) {
+        self.send_command(apic_id, Self::DELIVERY_MODE_INIT | Self::LEVEL_ASSERT);
+    }
+
+    /// Send a startup to the processor of the local APIC `apic_id`: it starts in real mode
+    /// at the address `page` * 0x1000
+    pub unsafe fn send_startup(&mut self, apic_id: u8, page: u8) {
+        self.send_command(
+            apic_id,
+            Self::DELIVERY_MODE_STARTUP | Self::LEVEL_ASSERT | page as u32,
+        );
+    }
+
+    /// Raise `vector` on the processor of the local APIC `apic_id`
+    pub unsafe fn send_ipi(&mut self, apic_id: u8, vector: u8) {
+        self.send_command(apic_id, Self::LEVEL_ASSERT | vector as u32);
+    }
+
+    /// Raise `vector` on all the processors but the current one
+    pub unsafe fn broadcast_ipi(&mut self, vector: u8) {
+        self.send_command(
+            0,
+            Self::ALL_EXCLUDING_SELF | Self::LEVEL_ASSERT | vector as u32,
+        );
+    }
+
+    /// Count the timer ticks during a tick of PIT0
+    /// PIT0 must be running and the interrupts must be enabled
+    pub unsafe fn calibrate_timer(&mut self) -> u32 {
+        assert!(interrupts::get_interrupts_state());
+
+        self.write(Self::TIMER_DIVIDE_CONFIGURATION, Self::TIMER_DIVIDE_BY_16);
+        self.write(Self::LVT_TIMER, Self::LVT_MASKED);
+
+        // Start counting at the beginning of a PIT0 tick
+        _sleep(1);
+        self.write(Self::TIMER_INITIAL_COUNT, core::u32::MAX);
+        _sleep(Self::CALIBRATION_TICKS);
+        let elapsed = core::u32::MAX - self.read(Self::TIMER_CURRENT_COUNT);
+        self.write(Self::TIMER_INITIAL_COUNT, 0);
+
+        elapsed / Self::CALIBRATION_TICKS
+    }
+
+    /// Raise `vector` every `initial_count` timer ticks
+    pub unsafe fn start_periodic_timer(&mut self, vector: u8, initial_count: u32) {
+        self.write(Self::TIMER_DIVIDE_CONFIGURATION, Self::TIMER_DIVIDE_BY_16);
+        self.write(Self::LVT_TIMER, Self::LVT_TIMER_PERIODIC | vector as u32);
+        self.write(Self::TIMER_INITIAL_COUNT, initial_count);
+    }
+
+    /// Stop the timer
+    pub unsafe fn stop_timer(&mut self) {
+        self.write(Self::LVT_TIMER, Self::LVT_MASKED);
+        self.write(Self::TIMER_INITIAL_COUNT, 0);
+    }
+}
+
+/// Read the model specific register `msr`, returns its low and high parts
+unsafe fn rdmsr(msr: u32) -> (u32, u32) {
+    let (low, high): (u32, u32);
+    asm!("rdmsr" : "={eax}"(low), "={edx}"(high) : "{ecx}"(msr) : : "volatile");
+    (low, high)
+}
+
+/// Write the model specific register `msr`
+unsafe fn wrmsr(msr: u32, low: u32, high: u32) {
+    asm!("wrmsr" : : "{ecx}"(msr), "{eax}"(low), "{edx}"(high) : : "volatile");
+}
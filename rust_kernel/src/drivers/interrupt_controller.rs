@@ -0,0 +1,37 @@
+//! The IRQs are routed by the IO-APICs once they are initialized, else by the 8259 PICs.
+//! The drivers enable and disable their interrupt lines through this module.
+
+use super::apic::APIC;
+use super::pic_8259::PIC_8259;
+use core::convert::TryFrom;
+use irq::Irq;
+
+/// Enable the legacy IRQ `irq`
+/// When used without function option. the default symbol in asm file is called
+pub unsafe fn enable_irq(irq: Irq, func_opt: Option<unsafe extern "C" fn()>) {
+    match APIC.lock().as_mut() {
+        Some(apic) => apic.enable_irq(irq, func_opt),
+        None => PIC_8259.lock().enable_irq(irq, func_opt),
+    }
+}
+
+/// Enable the interrupt line `line` of a PCI device, as given by its configuration space.
+/// Returns the legacy IRQ raised by the line, None when it cannot be routed
+pub unsafe fn enable_pci_irq(line: u8, func_opt: Option<unsafe extern "C" fn()>) -> Option<Irq> {
+    match APIC.lock().as_mut() {
+        Some(apic) => apic.enable_pci_irq(line, func_opt),
+        None => {
+            let irq = Irq::try_from(line).ok()?;
+            PIC_8259.lock().enable_irq(irq, func_opt);
+            Some(irq)
+        }
+    }
+}
+
+/// Disable the interrupt line `irq`
+pub unsafe fn disable_irq(irq: Irq) {
+    match APIC.lock().as_mut() {
+        Some(apic) => apic.disable_irq(irq),
+        None => PIC_8259.lock().disable_irq(irq),
+    }
+}
@@ -59,8 +59,8 @@ impl Pic8259 {
     const DEFAULT_IRQS_SLAVE: [unsafe extern "C" fn(); 8] = [
         _isr_cmos,
         _isr_acpi,
-        reserved_interruption,
-        reserved_interruption,
+        _isr_irq10,
+        _isr_irq11,
         _isr_ps2_mouse,
         _isr_fpu_coproc,
         _isr_primary_hard_disk,
@@ -213,8 +213,7 @@ impl Pic8259 {
     pub unsafe fn enable_irq(&mut self, irq: Irq, func_opt: Option<unsafe extern "C" fn()>) {
         log::info!("Pic8259: Enable irq {:?}", irq);
         if let Some(func) = func_opt {
-            log::info!("Pic8259: Assigning function at {:?}", func);
-            _pic_handlers_array[irq as usize] = func as u32;
+            set_irq_handler(irq, func);
         }
 
         let mut nirq = irq as usize;
@@ -548,3 +547,10 @@ impl PicConfiguration {
 extern "C" {
     static mut _pic_handlers_array: [u32; 16];
 }
+
+/// Set the function called by the interrupt service routine of `irq`
+/// The APICs use the same interrupt service routines
+pub unsafe fn set_irq_handler(irq: Irq, func: unsafe extern "C" fn()) {
+    log::info!("Pic8259: Assigning function at {:?}", func);
+    _pic_handlers_array[irq as usize] = func as u32;
+}
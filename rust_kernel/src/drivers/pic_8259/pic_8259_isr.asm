@@ -16,6 +16,10 @@ _preemptible_state dd LOCKED
 
 _process_end_time dd 0
 
+; The address of the EOI register of the local APIC, null while the 8259 routes the IRQs
+global _lapic_eoi_register
+_lapic_eoi_register dd 0
+
 ;                __ISR_TIMER__
 ;       PIT ACK ------|
 ;                     v
@@ -37,6 +41,48 @@ extern _schedule_next
 
 segment .text
 
+;; The EOI goes to the local APIC when the IO-APICs route the IRQs, else to the 8259
+;; Those macros only use eax
+%macro MASTER 0
+	mov eax, dword [_lapic_eoi_register]
+	test eax, eax
+	jz %%pic
+	mov dword [eax], 0
+	jmp %%end
+%%pic:
+	mov al, 0x20
+	out 0x20, al
+%%end:
+%endmacro
+
+%macro SLAVE 0
+	mov eax, dword [_lapic_eoi_register]
+	test eax, eax
+	jz %%pic
+	mov dword [eax], 0
+	jmp %%end
+%%pic:
+	mov al, 0x20
+	out 0xa0, al
+	out 0x20, al
+%%end:
+%endmacro
+
+; Remove that dummy spurious behavior if we would use lpt1
+; The IO-APIC has no spurious IRQ 7, its interrupts must be acknowledged
+%macro SPURIOUS_IRQ7 0
+	mov eax, dword [_lapic_eoi_register]
+	test eax, eax
+	jz %%end
+	mov dword [eax], 0
+%%end:
+%endmacro
+
+; Remove that dummy spurious behavior if we would use secondary_hard_disk
+%macro SPURIOUS_IRQ15 0
+	MASTER
+%endmacro
+
 %macro GET_PIT_TIME 0
 	lock cmpxchg dword [_pit_time], eax
 %endmacro
@@ -45,13 +91,13 @@ segment .text
 	lock cmpxchg dword [_process_end_time], eax
 %endmacro
 
-; This function is automatically called when Interrupt are enable and PIC irq 0 is enabled
+; This function is automatically called when Interrupt are enable and PIC irq 0 is enabled,
+; or by the local APIC timer when it is the tick source
 global _isr_timer
 _isr_timer:
 	push eax
 
-	mov al, 0x20
-	out 0x20, al
+	MASTER
 
 	; inc _pit_time
 	lock inc dword [_pit_time]
@@ -173,26 +219,6 @@ _isr_%2:
 	iret
 %endmacro
 
-%macro MASTER 0
-	mov al, 0x20
-	out 0x20, al
-%endmacro
-
-%macro SLAVE 0
-	mov al, 0x20
-	out 0xa0, al
-	out 0x20, al
-%endmacro
-
-; Remove that dummy spurious behavior if we would use lpt1
-%macro SPURIOUS_IRQ7 0
-%endmacro
-
-; Remove that dummy spurious behavior if we would use secondary_hard_disk
-%macro SPURIOUS_IRQ15 0
-	MASTER
-%endmacro
-
 ; TIPS: use nasm -E file to view source file on stdout after macro expansion
 CREATE_ISR MASTER, keyboard, "Keyboard", 1
 CREATE_ISR MASTER, cascade, "cascade, never used", 2 ; should never be raised
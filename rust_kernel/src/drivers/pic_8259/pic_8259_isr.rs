@@ -12,6 +12,8 @@ extern "C" {
     pub(super) fn _isr_lpt1();
     pub(super) fn _isr_cmos();
     pub(super) fn _isr_acpi();
+    pub(super) fn _isr_irq10();
+    pub(super) fn _isr_irq11();
     pub(super) fn _isr_ps2_mouse();
     pub(super) fn _isr_fpu_coproc();
     pub(super) fn _isr_primary_hard_disk();
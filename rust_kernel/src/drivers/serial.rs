@@ -0,0 +1,48 @@
+//! Receive path of the COM1 serial port: the IRQ handler stores the
+//! received bytes, the scheduler gives them to the serial tty line discipline
+
+use super::enable_irq;
+use arrayvec::ArrayVec;
+use irq::Irq;
+use lazy_static::lazy_static;
+use sync::Spinlock;
+use terminal::{TERMINAL, UART_16550};
+
+/// Number of received bytes which can wait for the line discipline
+const SERIAL_INPUT_SIZE: usize = 1024;
+
+lazy_static! {
+    /// Bytes received by the IRQ handler and not yet given to the line discipline
+    static ref SERIAL_INPUT: Spinlock<ArrayVec<[u8; SERIAL_INPUT_SIZE]>> =
+        Spinlock::new(ArrayVec::new());
+}
+
+/// Initialize COM1 and enable its receive interrupt
+pub unsafe fn init() {
+    UART_16550.init();
+    lazy_static::initialize(&SERIAL_INPUT);
+    enable_irq(Irq::SerialPortController1, Some(serial_interrupt_handler));
+}
+
+/// Empty the UART FIFO, the bytes are lost when the input buffer is full
+unsafe extern "C" fn serial_interrupt_handler() {
+    let mut input = SERIAL_INPUT.lock();
+    while let Some(byte) = UART_16550.receive() {
+        let _r = input.try_push(byte);
+    }
+}
+
+/// Give the received bytes to the serial line discipline. Called by
+/// the scheduler, like the keyboard messages
+pub unsafe fn dispatch_input() {
+    let mut input = SERIAL_INPUT.lock();
+    if input.is_empty() {
+        return;
+    }
+    if let Some(terminal) = TERMINAL.as_mut() {
+        // The carriage return of the Enter key is translated by ICRNL
+        let consumed = terminal.get_serial_line_discipline().handle_input(&input);
+        // What the full line discipline could not take is kept for later
+        input.drain(..consumed);
+    }
+}
@@ -14,7 +14,8 @@ use ide_ata_controller::AtaError;
 pub use ide_ata_controller::IdeAtaController;
 
 pub mod sata_controller;
-pub use sata_controller::SataController;
+use sata_controller::SataError;
+pub use sata_controller::{SataController, SATA_CONTROLLER};
 
 pub mod bios_int13h;
 pub use bios_int13h::{BiosInt13h, BIOS_INT13H};
@@ -24,6 +25,9 @@ pub use tools::{NbrSectors, Sector};
 
 pub mod ext2;
 
+pub mod block_cache;
+pub use block_cache::{CachedDisk, BLOCK_CACHE};
+
 use crate::multiboot::MultibootInfo;
 pub use ide_ata_controller::IDE_ATA_CONTROLLER;
 
@@ -50,6 +54,20 @@ impl From<AtaError> for DiskError {
     }
 }
 
+impl From<SataError> for DiskError {
+    fn from(sata_error: SataError) -> DiskError {
+        match sata_error {
+            SataError::DeviceNotFound => DiskError::NotSupported,
+            SataError::NotSupported => DiskError::NotSupported,
+            SataError::OutOfBound => DiskError::OutOfBound,
+            SataError::NothingToDo => DiskError::NothingToDo,
+            SataError::IoError => DiskError::IOError,
+            SataError::Timeout => DiskError::IOError,
+            SataError::OutOfMemory => DiskError::InternalError,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum DiskDriverType {
     Sata,
@@ -73,17 +91,21 @@ pub trait BlockIo: Send {
         buf: *const u8,
     ) -> DiskResult<NbrSectors>;
     fn disk_size(&self) -> u64;
+    /// Write the pending modifications to the disk
+    fn flush(&mut self) -> DiskResult<()> {
+        Ok(())
+    }
 }
 
 pub fn init(multiboot_info: &MultibootInfo) {
     // Intialize SATA controller
-    match SataController::init() {
-        Some(sata_controller) => {
-            log::info!("Sata Controller detected: {:#X?}", sata_controller);
-            sata_controller.dump_hba();
-        }
-        None => {
-            log::info!("No Sata controller detected");
+    unsafe {
+        match sata_controller::init() {
+            Ok(()) => log::info!(
+                "Sata Controller detected: {:#X?}",
+                SATA_CONTROLLER.as_ref().unwrap()
+            ),
+            Err(e) => log::info!("No Sata controller available: {:?}", e),
         }
     }
 
@@ -98,3 +120,12 @@ pub fn init(multiboot_info: &MultibootInfo) {
             .expect("bios_int_13 init failed");
     }
 }
+
+/// Get the driver of the disk containing the root filesystem:
+/// The SATA one if a drive is connected to the AHCI controller, the IDE one otherwise
+pub fn root_disk_driver_type() -> DiskDriverType {
+    match unsafe { SATA_CONTROLLER.as_ref() } {
+        Some(sata_controller) if sata_controller.has_selected_drive() => DiskDriverType::Sata,
+        _ => DiskDriverType::Ide,
+    }
+}
@@ -0,0 +1,475 @@
+//! This module contains the block cache: The sectors read from or written to the disks are kept in memory
+//! and shared by all the disk users (the ext2 filesystem and the raw /dev/sdX accesses).
+//! The writes are delayed: The dirty sectors are written back each few seconds by the second callback,
+//! when they are evicted, or on demand by sync() and fsync()
+
+use super::{BlockIo, DiskError, DiskResult, NbrSectors, Sector, SECTOR_SIZE};
+
+use alloc::boxed::Box;
+use alloc::collections::CollectionAllocErr;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use core::slice;
+use core::sync::atomic::{AtomicU32, Ordering};
+use fallible_collections::btree::BTreeMap;
+use fallible_collections::{FallibleBox, FallibleVec};
+use lazy_static::lazy_static;
+use sync::DeadMutex;
+
+lazy_static! {
+    pub static ref BLOCK_CACHE: DeadMutex<BlockCache> = DeadMutex::new(BlockCache::new());
+}
+
+/// Identifier of a disk registered into the block cache
+pub type DeviceId = usize;
+
+/// Maximum number of sectors kept in memory (4 MiB)
+const MAX_CACHED_SECTORS: usize = 8192;
+
+/// Maximum number of contiguous missing sectors read by a single disk command
+const MAX_READ_RUN: usize = 128;
+
+/// Maximum number of contiguous dirty sectors written by a single disk command
+const MAX_WRITE_RUN: usize = 8;
+
+/// Number of seconds between two write-back of the dirty sectors
+const WRITE_BACK_INTERVAL: u32 = 5;
+
+/// A disk known by the cache
+struct Device {
+    disk: Box<dyn BlockIo>,
+    nbr_sectors: NbrSectors,
+}
+
+/// A sector kept in memory
+struct CacheEntry {
+    device: DeviceId,
+    sector: Sector,
+    data: Box<[u8; SECTOR_SIZE]>,
+    /// The sector was modified and not written back yet
+    dirty: bool,
+    /// Less recently used neighbour in the LRU list
+    older: Option<usize>,
+    /// More recently used neighbour in the LRU list
+    newer: Option<usize>,
+}
+
+/// Global structure: The entries are indexed by (device, sector) and linked from the most recently used to the least one
+pub struct BlockCache {
+    devices: Vec<Device>,
+    entries: Vec<Option<CacheEntry>>,
+    free_slots: Vec<usize>,
+    index: BTreeMap<(DeviceId, Sector), usize>,
+    /// Most recently used entry
+    newest: Option<usize>,
+    /// Least recently used entry: The next one to be evicted
+    oldest: Option<usize>,
+    /// Contiguous dirty sectors are gathered here before being written
+    write_buffer: Box<[u8; SECTOR_SIZE * MAX_WRITE_RUN]>,
+}
+
+/// BlockCache Debug boilerplate
+impl Debug for BlockCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "BlockCache: {} devices, {} sectors cached",
+            self.devices.len(),
+            self.index.len()
+        )
+    }
+}
+
+impl BlockCache {
+    fn new() -> Self {
+        Self {
+            devices: Vec::new(),
+            entries: Vec::new(),
+            free_slots: Vec::new(),
+            index: BTreeMap::new(),
+            newest: None,
+            oldest: None,
+            write_buffer: Box::new([0; SECTOR_SIZE * MAX_WRITE_RUN]),
+        }
+    }
+
+    /// Register a new disk of `disk_size` bytes. All its accesses must go through the cache from now
+    pub fn register(
+        &mut self,
+        disk: Box<dyn BlockIo>,
+        disk_size: u64,
+    ) -> Result<DeviceId, CollectionAllocErr> {
+        self.devices.try_push(Device {
+            disk,
+            nbr_sectors: NbrSectors::from(disk_size),
+        })?;
+        Ok(self.devices.len() - 1)
+    }
+
+    /// Return the size in bytes of the disk `device`
+    pub fn disk_size(&self, device: DeviceId) -> u64 {
+        self.devices[device].nbr_sectors.0 as u64 * SECTOR_SIZE as u64
+    }
+
+    /// Read nbr_sectors after start_sector of the disk `device` into buf: only the missing sectors are read from the disk
+    pub fn read(
+        &mut self,
+        device: DeviceId,
+        start_sector: Sector,
+        nbr_sectors: NbrSectors,
+        buf: *mut u8,
+    ) -> DiskResult<NbrSectors> {
+        self.check_bounds(device, start_sector, nbr_sectors)?;
+
+        let mut i = 0;
+        while i < nbr_sectors.0 {
+            let sector = start_sector + NbrSectors(i);
+            let dst = unsafe { slice::from_raw_parts_mut(buf.add(i * SECTOR_SIZE), SECTOR_SIZE) };
+            if let Some(slot) = self.lookup(device, sector) {
+                dst.copy_from_slice(&self.entry(slot).data[..]);
+                self.touch(slot);
+                i += 1;
+                continue;
+            }
+
+            // Read all the following missing sectors with a single command
+            let mut run = 1;
+            while i + run < nbr_sectors.0
+                && run < MAX_READ_RUN
+                && self.lookup(device, sector + NbrSectors(run)).is_none()
+            {
+                run += 1;
+            }
+            let readen =
+                self.devices[device]
+                    .disk
+                    .read(sector, NbrSectors(run), dst.as_mut_ptr())?;
+            if readen == NbrSectors(0) {
+                return Err(DiskError::IOError);
+            }
+            for j in 0..readen.0 {
+                let src =
+                    unsafe { slice::from_raw_parts(buf.add((i + j) * SECTOR_SIZE), SECTOR_SIZE) };
+                // When the memory is low, the sector is just not cached
+                if let Some(slot) = self.allocate(device, sector + NbrSectors(j))? {
+                    self.entry_mut(slot).data.copy_from_slice(src);
+                }
+            }
+            i += readen.0;
+        }
+        Ok(nbr_sectors)
+    }
+
+    /// Write nbr_sectors after start_sector of the disk `device` from buf: The sectors are only marked as dirty
+    pub fn write(
+        &mut self,
+        device: DeviceId,
+        start_sector: Sector,
+        nbr_sectors: NbrSectors,
+        buf: *const u8,
+    ) -> DiskResult<NbrSectors> {
+        self.check_bounds(device, start_sector, nbr_sectors)?;
+
+        for i in 0..nbr_sectors.0 {
+            let sector = start_sector + NbrSectors(i);
+            let src = unsafe { slice::from_raw_parts(buf.add(i * SECTOR_SIZE), SECTOR_SIZE) };
+            let slot = match self.lookup(device, sector) {
+                Some(slot) => Some(slot),
+                None => self.allocate(device, sector)?,
+            };
+            match slot {
+                Some(slot) => {
+                    let entry = self.entry_mut(slot);
+                    entry.data.copy_from_slice(src);
+                    entry.dirty = true;
+                    self.touch(slot);
+                }
+                // When the memory is low, the sector is written through
+                None => {
+                    self.devices[device]
+                        .disk
+                        .write(sector, NbrSectors(1), src.as_ptr())?;
+                }
+            }
+        }
+        Ok(nbr_sectors)
+    }
+
+    /// Write back the dirty sectors of the disk `device`, or of all the disks if None
+    /// All the dirty sectors are tried, the first error is returned
+    pub fn sync(&mut self, device: Option<DeviceId>) -> DiskResult<()> {
+        let Self {
+            devices,
+            entries,
+            index,
+            write_buffer,
+            ..
+        } = self;
+        let mut result = Ok(());
+        let mut run: Option<(DeviceId, Sector)> = None;
+        let mut run_slots = [0; MAX_WRITE_RUN];
+        let mut run_len = 0;
+
+        for (&(entry_device, sector), &slot) in index.iter() {
+            if device.map(|d| d != entry_device).unwrap_or(false) {
+                continue;
+            }
+            let entry = entries[slot].as_ref().expect("corrupted block cache");
+            let extend_run = match run {
+                Some((run_device, run_start)) => {
+                    entry.dirty
+                        && run_device == entry_device
+                        && run_start + NbrSectors(run_len) == sector
+                        && run_len < MAX_WRITE_RUN
+                }
+                None => false,
+            };
+            if !extend_run {
+                if let Some((run_device, run_start)) = run.take() {
+                    let ret = write_run(
+                        &mut *devices[run_device].disk,
+                        entries,
+                        run_start,
+                        &run_slots[..run_len],
+                        &write_buffer[..],
+                    );
+                    result = result.and(ret);
+                }
+                if !entries[slot].as_ref().unwrap().dirty {
+                    continue;
+                }
+                run = Some((entry_device, sector));
+                run_len = 0;
+            }
+            let entry = entries[slot].as_ref().unwrap();
+            write_buffer[run_len * SECTOR_SIZE..(run_len + 1) * SECTOR_SIZE]
+                .copy_from_slice(&entry.data[..]);
+            run_slots[run_len] = slot;
+            run_len += 1;
+        }
+        if let Some((run_device, run_start)) = run {
+            let ret = write_run(
+                &mut *devices[run_device].disk,
+                entries,
+                run_start,
+                &run_slots[..run_len],
+                &write_buffer[..],
+            );
+            result = result.and(ret);
+        }
+        result
+    }
+
+    /// Emit Out Of Bound when the request is outside of the disk
+    fn check_bounds(
+        &self,
+        device: DeviceId,
+        start_sector: Sector,
+        nbr_sectors: NbrSectors,
+    ) -> DiskResult<()> {
+        let capacity = self
+            .devices
+            .get(device)
+            .ok_or(DiskError::NotSupported)?
+            .nbr_sectors;
+        if nbr_sectors == NbrSectors(0) {
+            Err(DiskError::NothingToDo)
+        } else if start_sector.0 > usize::max_value() - nbr_sectors.0 {
+            Err(DiskError::OutOfBound)
+        } else if start_sector.0 + nbr_sectors.0 > capacity.0 {
+            Err(DiskError::OutOfBound)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn lookup(&self, device: DeviceId, sector: Sector) -> Option<usize> {
+        self.index.get(&(device, sector)).cloned()
+    }
+
+    fn entry(&self, slot: usize) -> &CacheEntry {
+        self.entries[slot].as_ref().expect("corrupted block cache")
+    }
+
+    fn entry_mut(&mut self, slot: usize) -> &mut CacheEntry {
+        self.entries[slot].as_mut().expect("corrupted block cache")
+    }
+
+    /// Get a slot for the sector `sector` of `device`: A new one if the cache is not full and the memory is available,
+    /// the least recently used one otherwise. Return None if the sector cannot be cached
+    fn allocate(&mut self, device: DeviceId, sector: Sector) -> DiskResult<Option<usize>> {
+        let data = if self.index.len() < MAX_CACHED_SECTORS {
+            Box::try_new([0; SECTOR_SIZE]).ok()
+        } else {
+            None
+        };
+        let data = match (data, self.oldest) {
+            (Some(data), _) => data,
+            (None, Some(oldest)) => self.evict(oldest)?,
+            (None, None) => return Ok(None),
+        };
+        let slot = match self.free_slots.pop() {
+            Some(slot) => slot,
+            None => {
+                if self.entries.try_push(None).is_err() {
+                    return Ok(None);
+                }
+                self.entries.len() - 1
+            }
+        };
+        if self.index.try_insert((device, sector), slot).is_err() {
+            let _ = self.free_slots.try_push(slot);
+            return Ok(None);
+        }
+        self.entries[slot] = Some(CacheEntry {
+            device,
+            sector,
+            data,
+            dirty: false,
+            older: None,
+            newer: None,
+        });
+        self.link_newest(slot);
+        Ok(Some(slot))
+    }
+
+    /// Remove the entry `slot` from the cache after writing it back if needed, and give its buffer
+    fn evict(&mut self, slot: usize) -> DiskResult<Box<[u8; SECTOR_SIZE]>> {
+        {
+            let entry = self.entries[slot].as_ref().expect("corrupted block cache");
+            if entry.dirty {
+                self.devices[entry.device].disk.write(
+                    entry.sector,
+                    NbrSectors(1),
+                    entry.data.as_ptr(),
+                )?;
+            }
+        }
+        self.unlink(slot);
+        let entry = self.entries[slot].take().expect("corrupted block cache");
+        self.index.remove(&(entry.device, entry.sector));
+        // The slot list never needs more room than the entries list
+        if self.free_slots.try_push(slot).is_err() {
+            log::warn!("block cache: slot {} lost", slot);
+        }
+        Ok(entry.data)
+    }
+
+    /// Mark the entry `slot` as the most recently used
+    fn touch(&mut self, slot: usize) {
+        if self.newest != Some(slot) {
+            self.unlink(slot);
+            self.link_newest(slot);
+        }
+    }
+
+    /// Insert the entry `slot` at the head of the LRU list
+    fn link_newest(&mut self, slot: usize) {
+        let newest = self.newest;
+        {
+            let entry = self.entry_mut(slot);
+            entry.older = newest;
+            entry.newer = None;
+        }
+        match newest {
+            Some(newest) => self.entry_mut(newest).newer = Some(slot),
+            None => self.oldest = Some(slot),
+        }
+        self.newest = Some(slot);
+    }
+
+    /// Remove the entry `slot` from the LRU list
+    fn unlink(&mut self, slot: usize) {
+        let (older, newer) = {
+            let entry = self.entry(slot);
+            (entry.older, entry.newer)
+        };
+        match older {
+            Some(older) => self.entry_mut(older).newer = newer,
+            None => self.oldest = newer,
+        }
+        match newer {
+            Some(newer) => self.entry_mut(newer).older = older,
+            None => self.newest = older,
+        }
+    }
+}
+
+/// Write a run of contiguous dirty sectors gathered into `write_buffer`, then mark them as clean
+fn write_run(
+    disk: &mut dyn BlockIo,
+    entries: &mut Vec<Option<CacheEntry>>,
+    start_sector: Sector,
+    slots: &[usize],
+    write_buffer: &[u8],
+) -> DiskResult<()> {
+    disk.write(start_sector, NbrSectors(slots.len()), write_buffer.as_ptr())?;
+    for slot in slots {
+        entries[*slot]
+            .as_mut()
+            .expect("corrupted block cache")
+            .dirty = false;
+    }
+    Ok(())
+}
+
+/// Called each second by the second callback: write back the dirty sectors each WRITE_BACK_INTERVAL seconds
+pub fn periodic_write_back() {
+    static ELAPSED_SECONDS: AtomicU32 = AtomicU32::new(0);
+
+    if ELAPSED_SECONDS.fetch_add(1, Ordering::Relaxed) + 1 < WRITE_BACK_INTERVAL {
+        return;
+    }
+    // The interrupted code may use the cache: just retry on the next second
+    if let Some(mut block_cache) = BLOCK_CACHE.try_lock() {
+        ELAPSED_SECONDS.store(0, Ordering::Relaxed);
+        if let Err(e) = block_cache.sync(None) {
+            log::error!("block cache write-back failed: {:?}", e);
+        }
+    }
+}
+
+/// A disk whose accesses go through the block cache
+#[derive(Debug, Copy, Clone)]
+pub struct CachedDisk(DeviceId);
+
+impl CachedDisk {
+    /// Register `disk` into the block cache
+    pub fn new(disk: Box<dyn BlockIo>, disk_size: u64) -> Result<Self, CollectionAllocErr> {
+        Ok(Self(BLOCK_CACHE.lock().register(disk, disk_size)?))
+    }
+}
+
+impl BlockIo for CachedDisk {
+    fn read(
+        &mut self,
+        start_sector: Sector,
+        nbr_sectors: NbrSectors,
+        buf: *mut u8,
+    ) -> DiskResult<NbrSectors> {
+        BLOCK_CACHE
+            .lock()
+            .read(self.0, start_sector, nbr_sectors, buf)
+    }
+
+    fn write(
+        &mut self,
+        start_sector: Sector,
+        nbr_sectors: NbrSectors,
+        buf: *const u8,
+    ) -> DiskResult<NbrSectors> {
+        BLOCK_CACHE
+            .lock()
+            .write(self.0, start_sector, nbr_sectors, buf)
+    }
+
+    /// return the size of the disk
+    fn disk_size(&self) -> u64 {
+        BLOCK_CACHE.lock().disk_size(self.0)
+    }
+
+    /// Write back the dirty sectors of this disk
+    fn flush(&mut self) -> DiskResult<()> {
+        BLOCK_CACHE.lock().sync(Some(self.0))
+    }
+}
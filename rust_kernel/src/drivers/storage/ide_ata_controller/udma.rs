@@ -4,7 +4,7 @@ use io::{Io, Pio};
 
 use bitflags::bitflags;
 
-use crate::drivers::PIC_8259;
+use crate::drivers::enable_irq;
 use crate::memory::ffi::get_physical_addr;
 use crate::memory::tools::*;
 
@@ -138,11 +138,11 @@ impl Udma {
         unsafe {
             without_interrupts!({
                 match channel {
-                    Channel::Primary => PIC_8259.lock().enable_irq(
+                    Channel::Primary => enable_irq(
                         irq::Irq::PrimaryATAChannel,
                         Some(super::pci_udma::primary_hard_disk_interrupt_handler),
                     ),
-                    Channel::Secondary => PIC_8259.lock().enable_irq(
+                    Channel::Secondary => enable_irq(
                         irq::Irq::SecondaryATAChannel,
                         Some(super::pci_udma::secondary_hard_disk_interrupt_handler),
                     ),
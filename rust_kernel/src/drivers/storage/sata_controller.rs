@@ -1,126 +1,289 @@
 //! This module handle a SATA driver. See https://wiki.osdev.org/SATA, https://wiki.osdev.org/AHCI
 
-use super::{MassStorageControllerSubClass, PciDeviceClass, PciType0, SerialAtaProgIf, PCI};
+mod drive;
+mod fis;
+mod hba;
 
-use crate::memory::ffi::{map, unmap};
-use core::mem::size_of;
-use raw_data::define_raw_data;
+use drive::Drive;
+use hba::{BiosOsHandoff, GlobalHostControl, HbaMem, PortInterrupt, CAP2_BOH};
+
+use super::{
+    BlockIo, DiskResult, MassStorageControllerSubClass, NbrSectors, PciCommand, PciDeviceClass,
+    PciType0, Sector, SerialAtaProgIf, PCI, SECTOR_SIZE,
+};
 
+use crate::drivers::{enable_pci_irq, PIT0};
+use crate::memory::ffi::map;
+use alloc::collections::CollectionAllocErr;
 use alloc::vec::Vec;
+use bit_field::BitField;
+use core::mem::size_of;
+use core::sync::atomic::{AtomicU32, Ordering};
+use core::time::Duration;
+use fallible_collections::FallibleVec;
+use io::Io;
+use irq::Irq;
 
-#[derive(Debug, Copy, Clone)]
-#[repr(packed)]
-struct HbaMem {
-    // 0x00 - 0x2B, Generic Host Control
-    /*0        |*/ cap: u32, // Host capability
-    /*4        |*/ ghc: u32, // Global host control
-    /*8        |*/ is: u32, // Interrupt status
-    /*c        |*/ pi: u32, // Port implemented
-    /*10       |*/ vs: u32, // Version
-    /*14       |*/ ccc_ctl: u32, // Command completion coalescing control
-    /*18       |*/ ccc_pts: u32, // Command completion coalescing ports
-    /*1c       |*/ en_loc: u32, // Enclosure management location
-    /*20       |*/ en_ctl: u32, // Enclosure management control
-    /*24       |*/ cap2: u32, // Host capabilities extended
-    /*28       |*/ bohc: u32, // BIOS/OS handoff control and status
-
-    // 0x2C - 0x9F, Reserved
-    /*2C       |*/ reserved: Reserved,
-
-    // 0xA0 - 0xFF, Vendor specific registers
-    /*A0       |*/
-    vendor_specific_registers: VendorSpecificRegisters,
-    // 0x100 - 0x10FF, Port control registers ... (relative to pi value)
-}
+pub static mut SATA_CONTROLLER: Option<SataController> = None;
 
-define_raw_data!(Reserved, 0xA0 - 0x2C);
-define_raw_data!(VendorSpecificRegisters, 0x100 - 0xA0);
+/// Initialize the SATA CONTROLLER
+pub unsafe fn init() -> SataResult<()> {
+    let mut sata_controller = SataController::new()?;
 
-#[derive(Debug, Copy, Clone)]
-#[repr(packed)]
-struct HbaPort {
-    /*0        |*/ clb: u32, // command list base address, 1K-byte aligned
-    /*4        |*/ clbu: u32, // command list base address upper 32 bits
-    /*8        |*/ fb: u32, // FIS base address, 256-byte aligned
-    /*c        |*/ fbu: u32, // FIS base address upper 32 bits
-    /*10       |*/ is: u32, // interrupt status
-    /*14       |*/ ie: u32, // interrupt enable
-    /*18       |*/ cmd: u32, // command and status
-    /*1c       |*/ rsv0: u32, // Reserved
-    /*20       |*/ tfd: u32, // task file data
-    /*24       |*/ sig: u32, // signature
-    /*28       |*/ ssts: u32, // SATA status (SCR0:SStatus)
-    /*2c       |*/ sctl: u32, // SATA control (SCR2:SControl)
-    /*30       |*/ serr: u32, // SATA error (SCR1:SError)
-    /*34       |*/ sact: u32, // SATA active (SCR3:SActive)
-    /*38       |*/ ci: u32, // command issue
-    /*3c       |*/ sntf: u32, // SATA notification (SCR4:SNotification)
-    /*40       |*/ fbs: u32, // FIS-based switch control
-
-    // 0x44 ~ 0x6F, Reserved
-    /*10       |*/ reserved: ReservedPort,
-
-    // 0x70 ~ 0x7F, vendor specific
-    /*10       |*/ vendor_specific: VendorSpecificPort,
+    if let Some(port) = sata_controller.drives.first().map(|drive| drive.port) {
+        sata_controller.select_drive(port)?;
+    }
+    SATA_CONTROLLER = Some(sata_controller);
+    Ok(())
 }
 
-define_raw_data!(ReservedPort, 0x70 - 0x44);
-define_raw_data!(VendorSpecificPort, 0x80 - 0x70);
-
-#[derive(Copy, Clone, Debug)]
+/// Global structure
 pub struct SataController {
     pci: PciType0,
     location: u32,
+    hba: &'static mut HbaMem,
+    irq: Option<Irq>,
+    drives: Vec<Drive>,
+    selected_drive: Option<usize>,
+}
+
+/// SataController Debug boilerplate
+impl core::fmt::Debug for SataController {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("SataController")
+            .field("pci", &self.pci)
+            .field("location", &self.location)
+            .field("hba", &(self.hba as *const HbaMem))
+            .field("irq", &self.irq)
+            .field("drives", &self.drives)
+            .field("selected_drive", &self.selected_drive)
+            .finish()
+    }
 }
 
 impl SataController {
-    /// SATA drive
-    const SATA_SIG_ATA: u32 = 0x00000101;
-    /// SATAPI drive
-    const SATA_SIG_ATAPI: u32 = 0xEB140101;
-    /// Enclosure management bridge
-    const SATA_SIG_SEMB: u32 = 0xC33C0101;
-    /// Port multiplier
-    const SATA_SIG_PM0: u32 = 0x96690101;
-
-    pub fn init() -> Option<Self> {
-        PCI.lock()
-            .query_device(PciDeviceClass::MassStorageController(
+    /// Invocation of a new AHCI controller: Take the HBA ownership and initialize all the connected drives
+    pub unsafe fn new() -> SataResult<Self> {
+        let (pci, location) = PCI
+            .lock()
+            .query_device::<PciType0>(PciDeviceClass::MassStorageController(
                 MassStorageControllerSubClass::SerialAta(SerialAtaProgIf::Ahci1),
             ))
-            .map(|(pci, location)| Self { pci, location })
-    }
-
-    pub fn dump_hba(&self) {
-        let virt = unsafe { map(self.pci.bar5 as *mut u8, size_of::<HbaMem>()) };
-        println!("{:#X?}", virt);
-
-        let s = virt as *mut HbaMem;
-        let mut vec = Vec::new();
-        unsafe {
-            println!("{:#X?}", *s);
-            let virt = map(
-                (self.pci.bar5 + 0x100) as *mut u8,
-                size_of::<HbaPort>() * (*s).pi as usize,
-            ) as *const HbaPort;
-            for i in 0..(*s).pi as usize {
-                let l = core::ptr::read_volatile(virt.add(i));
-                if l.sig == Self::SATA_SIG_ATA
-                    || l.sig == Self::SATA_SIG_ATAPI
-                    || l.sig == Self::SATA_SIG_SEMB
-                    || l.sig == Self::SATA_SIG_PM0
-                {
-                    vec.push(virt.add(i));
-                }
+            .ok_or(SataError::DeviceNotFound)?;
+
+        // The HBA registers are memory mapped and the HBA must be the BUS MASTER to do DMA
+        pci.set_command(
+            PciCommand::MEMORY_SPACE | PciCommand::BUS_MASTER,
+            true,
+            location,
+        );
+        pci.set_command(PciCommand::INTERRUPT_DISABLE, false, location);
+
+        // The BAR 5 (ABAR) contains the physical address of the HBA registers
+        let abar = pci.bar5 & !0xf;
+        let hba = map(abar as *mut u8, size_of::<HbaMem>()) as *mut HbaMem;
+        if hba.is_null() {
+            return Err(SataError::OutOfMemory);
+        }
+        let hba = &mut *hba;
+
+        // Switch the HBA into AHCI mode and take it from the BIOS
+        hba.ghc.write(hba.ghc.read() | GlobalHostControl::AE.bits());
+        take_ownership(hba);
+
+        // Register the interrupt handler on the PCI interrupt line
+        HBA_ADDRESS = hba as *mut HbaMem as usize;
+        let irq = without_interrupts!({
+            enable_pci_irq(pci.interrupt_line, Some(sata_interrupt_handler))
+        });
+        if irq.is_none() {
+            log::warn!(
+                "No IRQ for the SATA controller (line {}), completion will be polled",
+                pci.interrupt_line
+            );
+        }
+
+        let mut drives = Vec::new();
+        let implemented_ports = hba.pi.read();
+        for port in (0..32).filter(|port| implemented_ports.get_bit(*port)) {
+            let regs = &mut hba.ports[port];
+            if !Drive::is_present(regs) {
+                continue;
             }
-            for h in vec {
-                println!("{:#X?}", core::ptr::read_volatile(h));
+            match Drive::init(port, regs) {
+                Ok(drive) => {
+                    log::info!("sata drive detected: {:?}", drive);
+                    drives.try_push(drive)?;
+                }
+                Err(e) => log::error!("Cannot initialize SATA drive on port {}: {:?}", port, e),
             }
-            unmap(virt as *mut u8, size_of::<HbaPort>() * (*s).pi as usize);
         }
-        unsafe {
-            unmap(virt, size_of::<HbaMem>());
+
+        // Clear the pending interrupts then allow the HBA to raise them
+        hba.is.write(!0);
+        if irq.is_some() {
+            hba.ghc.write(hba.ghc.read() | GlobalHostControl::IE.bits());
+        }
+
+        Ok(Self {
+            pci,
+            location,
+            hba,
+            irq,
+            drives,
+            selected_drive: None,
+        })
+    }
+
+    /// Select the drive connected to the port `port` for future read and write operations
+    pub fn select_drive(&mut self, port: usize) -> SataResult<()> {
+        self.selected_drive = self.drives.iter().position(|drive| drive.port == port);
+        self.selected_drive
+            .map(|_| ())
+            .ok_or(SataError::DeviceNotFound)
+    }
+
+    /// Check if a drive was selected, so the controller can be used as a BlockIo
+    pub fn has_selected_drive(&self) -> bool {
+        self.selected_drive.is_some()
+    }
+
+    /// Get the selected drive and the registers of its port
+    fn get_selected_drive(&mut self) -> SataResult<(&mut Drive, &mut hba::HbaPort)> {
+        let drive = &mut self.drives[self.selected_drive.ok_or(SataError::DeviceNotFound)?];
+        let regs = &mut self.hba.ports[drive.port];
+        Ok((drive, regs))
+    }
+}
+
+/// Take the HBA ownership from the BIOS if the HBA supports the BIOS/OS handoff
+fn take_ownership(hba: &mut HbaMem) {
+    if hba.cap2.read() & CAP2_BOH == 0 {
+        return;
+    }
+    hba.bohc.write(hba.bohc.read() | BiosOsHandoff::OOS.bits());
+    // The BIOS has 25ms to release the HBA, then 2 seconds if it is busy
+    PIT0.lock().sleep(Duration::from_millis(25));
+    if hba.bohc.read() & BiosOsHandoff::BB.bits() != 0 {
+        PIT0.lock().sleep(Duration::from_secs(2));
+    }
+    if hba.bohc.read() & BiosOsHandoff::BOS.bits() != 0 {
+        log::warn!("The BIOS does not release the SATA controller");
+    }
+}
+
+impl BlockIo for SataController {
+    /// return the size of the disk
+    fn disk_size(&self) -> u64 {
+        self.selected_drive
+            .map(|i| self.drives[i].sector_capacity.0 as u64 * SECTOR_SIZE as u64)
+            .unwrap_or(0)
+    }
+
+    /// Read nbr_sectors after start_sector location and write it into the buf
+    fn read(
+        &mut self,
+        start_sector: Sector,
+        nbr_sectors: NbrSectors,
+        buf: *mut u8,
+    ) -> DiskResult<NbrSectors> {
+        let (drive, regs) = self.get_selected_drive()?;
+        check_bounds(start_sector, nbr_sectors, drive.sector_capacity)?;
+        Ok(drive.read(regs, start_sector, nbr_sectors, buf)?)
+    }
+
+    /// Write nbr_sectors after start_sector location from the buf
+    fn write(
+        &mut self,
+        start_sector: Sector,
+        nbr_sectors: NbrSectors,
+        buf: *const u8,
+    ) -> DiskResult<NbrSectors> {
+        let (drive, regs) = self.get_selected_drive()?;
+        check_bounds(start_sector, nbr_sectors, drive.sector_capacity)?;
+        Ok(drive.write(regs, start_sector, nbr_sectors, buf)?)
+    }
+}
+
+/// Emit Out Of Bound when a bound problem occured
+fn check_bounds(
+    start_sector: Sector,
+    nbr_sectors: NbrSectors,
+    drive_capacity: NbrSectors,
+) -> SataResult<()> {
+    // 0 sector meens nothing for an human interface
+    if nbr_sectors == NbrSectors(0) {
+        Err(SataError::NothingToDo)
+    // Be careful with logical overflow
+    } else if start_sector.0 > usize::max_value() - nbr_sectors.0 {
+        Err(SataError::OutOfBound)
+    // raide disk capacity
+    } else if start_sector.0 + nbr_sectors.0 > drive_capacity.0 {
+        Err(SataError::OutOfBound)
+    } else {
+        Ok(())
+    }
+}
+
+/// SataResult is just made to handle module errors
+pub type SataResult<T> = core::result::Result<T, SataError>;
+
+/// Common errors for this module
+#[derive(Debug, Copy, Clone)]
+pub enum SataError {
+    /// No controller or no drive
+    DeviceNotFound,
+    /// Common error Variant
+    NotSupported,
+    /// Out of bound like always
+    OutOfBound,
+    /// There is nothing to do
+    NothingToDo,
+    /// IO error
+    IoError,
+    /// The HBA does not respond
+    Timeout,
+    /// Cannot allocate the DMA memory
+    OutOfMemory,
+}
+
+impl From<CollectionAllocErr> for SataError {
+    fn from(_e: CollectionAllocErr) -> Self {
+        SataError::OutOfMemory
+    }
+}
+
+/// Virtual address of the HBA registers for the IRQ handler
+static mut HBA_ADDRESS: usize = 0;
+
+/// Ports on which an error was reported by the HBA since their last command
+static PORT_ERRORS: AtomicU32 = AtomicU32::new(0);
+
+/// Check if an error was reported on the port `port`
+fn has_port_error(port: usize) -> bool {
+    PORT_ERRORS.load(Ordering::SeqCst).get_bit(port)
+}
+
+/// Forget the errors of the port `port` before issuing a new command
+fn clear_port_error(port: usize) {
+    PORT_ERRORS.fetch_and(!(1 << port), Ordering::SeqCst);
+}
+
+/// Acknowledge the interrupts of all the ports: The waiting drive sees that its command issue bit was cleared
+unsafe extern "C" fn sata_interrupt_handler() {
+    if HBA_ADDRESS == 0 {
+        return;
+    }
+    let hba = &mut *(HBA_ADDRESS as *mut HbaMem);
+
+    // The interrupt line may be shared, nothing to do if the HBA did not raise it
+    let pending_ports = hba.is.read();
+    for port in (0..32).filter(|port| pending_ports.get_bit(*port)) {
+        let status = hba.ports[port].is.read();
+        hba.ports[port].is.write(status);
+        if PortInterrupt::from_bits_truncate(status).intersects(PortInterrupt::ERRORS) {
+            PORT_ERRORS.fetch_or(1 << port, Ordering::SeqCst);
         }
-        println!("bar 5: {:#X?}", self.pci.bar5);
     }
+    hba.is.write(pending_ports);
 }
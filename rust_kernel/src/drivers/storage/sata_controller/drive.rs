@@ -0,0 +1,371 @@
+//! This module contains the commands sent to a SATA drive through its HBA port. See https://wiki.osdev.org/AHCI
+//! ---------------------------------------------- INITIALISATION -------------------------------------------
+//! - 1: Stop the command engine of the port (ST and FRE cleared, wait for CR and FR to be cleared).
+//! - 2: Set the physical addresses of the command list and of the received FIS area.
+//! - 3: Reset the port with a COMRESET and wait for the device to come back.
+//! - 4: Clear the error and interrupt registers, then enable the port interrupts.
+//! - 5: Start the command engine (FRE then ST set).
+//! - 6: Identify the device to get its capacity.
+//! ----------------------------------------------- READ / WRITE --------------------------------------------
+//! - 1: Wait for the device to be neither busy nor requesting data.
+//! - 2: Fill the PRDT with the DMA buffer pages, the command header and the command FIS (READ/WRITE DMA EXT).
+//! - 3: Set the bit of the command slot in the command issue register.
+//! - 4: When an interrupt arrives, check if the HBA cleared the command issue bit or if a task file error occured.
+use super::fis::{CommandHeader, DmaPage, FisRegH2D, PortMemory, PrdtEntry, NBR_PRDT_ENTRIES};
+use super::hba::{HbaPort, PortCommand, PortInterrupt, TaskFileStatus};
+use super::hba::{SCTL_DET_COMRESET, SCTL_DET_MASK, SSTS_DET_MASK, SSTS_DET_PRESENT};
+use super::hba::{SSTS_IPM_ACTIVE, SSTS_IPM_MASK};
+use super::{clear_port_error, has_port_error, SataError, SataResult};
+use super::{NbrSectors, Sector, SECTOR_SIZE};
+
+use crate::drivers::PIT0;
+use crate::memory::ffi::get_physical_addr;
+use crate::memory::tools::*;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::mem::size_of;
+use core::sync::atomic::{fence, spin_loop_hint, Ordering};
+use core::time::Duration;
+use fallible_collections::{try_vec, FallibleBox};
+use io::Io;
+
+/// A SATA drive connected to a port of the HBA
+pub struct Drive {
+    /// Index of the port in the HBA
+    pub port: usize,
+    /// Command list, received FIS area and command table of the port
+    memory: Box<PortMemory>,
+    /// Data are copied from/to this buffer for each DMA transfert
+    buffer: Vec<DmaPage>,
+    pub sector_capacity: NbrSectors,
+}
+
+/// Drive Debug boilerplate
+impl core::fmt::Debug for Drive {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "SATA drive on port {} -> {:?} at {:#X?}",
+            self.port,
+            self.sector_capacity,
+            self.memory.as_ref() as *const _,
+        )
+    }
+}
+
+/// Global disk commands used by the AHCI driver
+#[derive(Debug, Copy, Clone)]
+#[repr(u8)]
+enum AtaCommand {
+    AtaCmdReadDmaExt = 0x25,
+    AtaCmdWriteDmaExt = 0x35,
+    AtaCmdIdentify = 0xEC,
+}
+
+/// Get the physical address of a kernel object
+fn physical_addr<T>(object: &T) -> SataResult<u32> {
+    get_physical_addr(Virt(object as *const T as usize))
+        .map(|phys| phys.0 as u32)
+        .ok_or(SataError::IoError)
+}
+
+/// Poll `condition` every millisecond until it is true or the timeout (in milliseconds) is reached
+fn wait_for<F: Fn() -> bool>(condition: F, timeout: u64) -> SataResult<()> {
+    for _ in 0..timeout {
+        if condition() {
+            return Ok(());
+        }
+        PIT0.lock().sleep(Duration::from_millis(1));
+    }
+    if condition() {
+        Ok(())
+    } else {
+        Err(SataError::Timeout)
+    }
+}
+
+impl Drive {
+    /// Only the first slot of the command list is used: Only one command is issued at a time
+    const COMMAND_SLOT: usize = 0;
+
+    /// Maximum number of sectors of a single transfert
+    const MAX_SECTORS: NbrSectors = NbrSectors(NBR_PRDT_ENTRIES * PAGE_SIZE / SECTOR_SIZE);
+
+    /// Timeout of the port engine operations in milliseconds
+    const TIMEOUT: u64 = 500;
+
+    /// Number of polls of a command before it is considered lost, some seconds. The
+    /// completion is polled as the interrupts may be disabled
+    const COMMAND_TIMEOUT_POLLS: usize = 1 << 28;
+
+    /// Check if an active SATA drive is connected to the port (ATAPI drives and port multipliers are not supported)
+    pub fn is_present(regs: &HbaPort) -> bool {
+        /// SATA drive signature
+        const SATA_SIG_ATA: u32 = 0x00000101;
+
+        let ssts = regs.ssts.read();
+        ssts & SSTS_DET_MASK == SSTS_DET_PRESENT
+            && ssts & SSTS_IPM_MASK == SSTS_IPM_ACTIVE
+            && regs.sig.read() == SATA_SIG_ATA
+    }
+
+    /// Initialize the port `port` and identify its drive
+    pub fn init(port: usize, regs: &mut HbaPort) -> SataResult<Self> {
+        let memory = Box::try_new(PortMemory::new())?;
+        let buffer = try_vec![DmaPage([0; PAGE_SIZE]); NBR_PRDT_ENTRIES]?;
+
+        let mut drive = Self {
+            port,
+            memory,
+            buffer,
+            sector_capacity: NbrSectors(0),
+        };
+
+        drive.stop(regs)?; /* 1 */
+
+        /* 2 */
+        regs.clb.write(physical_addr(&drive.memory.command_list)?);
+        regs.clbu.write(0);
+        regs.fb.write(physical_addr(&drive.memory.received_fis)?);
+        regs.fbu.write(0);
+
+        // The command table and the PRDT never move: Only the byte counts change between two commands
+        let command_table = physical_addr(&drive.memory.command_table)?;
+        drive.memory.command_list[Self::COMMAND_SLOT].ctba = command_table;
+        drive.memory.command_list[Self::COMMAND_SLOT].ctbau = 0;
+        for (page, entry) in drive
+            .buffer
+            .iter()
+            .zip(drive.memory.command_table.prdt.iter_mut())
+        {
+            entry.dba = physical_addr(page)?;
+            entry.dbau = 0;
+        }
+
+        drive.reset(regs)?; /* 3 */
+
+        /* 4 */
+        regs.serr.write(!0);
+        regs.is.write(!0);
+        regs.ie.write(
+            (PortInterrupt::DHRS
+                | PortInterrupt::PSS
+                | PortInterrupt::DSS
+                | PortInterrupt::SDBS
+                | PortInterrupt::DPS
+                | PortInterrupt::ERRORS)
+                .bits(),
+        );
+
+        drive.start(regs)?; /* 5 */
+
+        drive.sector_capacity = drive.identify(regs)?; /* 6 */
+        Ok(drive)
+    }
+
+    /// Stop the command engine of the port
+    fn stop(&self, regs: &mut HbaPort) -> SataResult<()> {
+        regs.cmd.write(regs.cmd.read() & !PortCommand::ST.bits());
+        wait_for(
+            || !PortCommand::from_bits_truncate(regs.cmd.read()).contains(PortCommand::CR),
+            Self::TIMEOUT,
+        )?;
+
+        regs.cmd.write(regs.cmd.read() & !PortCommand::FRE.bits());
+        wait_for(
+            || !PortCommand::from_bits_truncate(regs.cmd.read()).contains(PortCommand::FR),
+            Self::TIMEOUT,
+        )
+    }
+
+    /// Start the command engine of the port
+    fn start(&self, regs: &mut HbaPort) -> SataResult<()> {
+        wait_for(
+            || !PortCommand::from_bits_truncate(regs.cmd.read()).contains(PortCommand::CR),
+            Self::TIMEOUT,
+        )?;
+        regs.cmd.write(regs.cmd.read() | PortCommand::FRE.bits());
+        regs.cmd.write(regs.cmd.read() | PortCommand::ST.bits());
+        Ok(())
+    }
+
+    /// Reset the port with a COMRESET. The command engine must be stopped
+    fn reset(&self, regs: &mut HbaPort) -> SataResult<()> {
+        let sctl = regs.sctl.read() & !SCTL_DET_MASK;
+        regs.sctl.write(sctl | SCTL_DET_COMRESET);
+        // The COMRESET must be sent for at least 1ms
+        PIT0.lock().sleep(Duration::from_millis(2));
+        regs.sctl.write(sctl);
+
+        wait_for(
+            || regs.ssts.read() & SSTS_DET_MASK == SSTS_DET_PRESENT,
+            Self::TIMEOUT,
+        )?;
+        regs.serr.write(!0);
+        Ok(())
+    }
+
+    /// Get the number of sectors of the drive
+    fn identify(&mut self, regs: &mut HbaPort) -> SataResult<NbrSectors> {
+        self.issue_command(regs, AtaCommand::AtaCmdIdentify, 0, 0, SECTOR_SIZE, false)?;
+
+        let identify = unsafe {
+            core::slice::from_raw_parts(self.buffer.as_ptr() as *const u16, SECTOR_SIZE / 2)
+        };
+        // Bit 10 of word 83 is set if the drive supports LBA48 mode. READ/WRITE DMA EXT commands need it.
+        if identify[83] & (1 << 10) == 0 {
+            log::error!("SATA drive on port {} does not support LBA48", self.port);
+            return Err(SataError::NotSupported);
+        }
+        // 100 through 103 taken as a uint64_t contain the total number of 48 bit addressable sectors on the drive.
+        (identify[100] as u64
+            | (identify[101] as u64) << 16
+            | (identify[102] as u64) << 32
+            | (identify[103] as u64) << 48)
+            .try_into()
+            .map(NbrSectors)
+            .map_err(|_| SataError::NotSupported)
+    }
+
+    /// Read nbr_sectors after start_sector location and write it into the buf
+    pub fn read(
+        &mut self,
+        regs: &mut HbaPort,
+        start_sector: Sector,
+        nbr_sectors: NbrSectors,
+        buf: *mut u8,
+    ) -> SataResult<NbrSectors> {
+        // Set limit as DMA buffer max_size for a single read operation
+        let sectors_to_read = core::cmp::min(Self::MAX_SECTORS, nbr_sectors);
+        let len: usize = sectors_to_read.into();
+
+        self.issue_command(
+            regs,
+            AtaCommand::AtaCmdReadDmaExt,
+            start_sector.0 as u64,
+            sectors_to_read.0 as u16,
+            len,
+            false,
+        )?;
+
+        // Copy the DMA buffer content into the Buf
+        unsafe {
+            (self.buffer.as_ptr() as *const u8).copy_to(buf, len);
+        }
+        Ok(sectors_to_read)
+    }
+
+    /// Write nbr_sectors after start_sector location from the buf
+    pub fn write(
+        &mut self,
+        regs: &mut HbaPort,
+        start_sector: Sector,
+        nbr_sectors: NbrSectors,
+        buf: *const u8,
+    ) -> SataResult<NbrSectors> {
+        // Set limit as DMA buffer max_size for a single write operation
+        let sectors_to_write = core::cmp::min(Self::MAX_SECTORS, nbr_sectors);
+        let len: usize = sectors_to_write.into();
+
+        // Copy the Buf into the DMA buffer content
+        unsafe {
+            buf.copy_to(self.buffer.as_mut_ptr() as *mut u8, len);
+        }
+
+        self.issue_command(
+            regs,
+            AtaCommand::AtaCmdWriteDmaExt,
+            start_sector.0 as u64,
+            sectors_to_write.0 as u16,
+            len,
+            true,
+        )?;
+        Ok(sectors_to_write)
+    }
+
+    /// Send an ATA command transfering `len` bytes of the DMA buffer and wait for its completion
+    fn issue_command(
+        &mut self,
+        regs: &mut HbaPort,
+        command: AtaCommand,
+        lba: u64,
+        count: u16,
+        len: usize,
+        write: bool,
+    ) -> SataResult<()> {
+        /* 1 */
+        wait_for(
+            || {
+                !TaskFileStatus::from_bits_truncate(regs.tfd.read())
+                    .intersects(TaskFileStatus::BSY | TaskFileStatus::DRQ)
+            },
+            Self::TIMEOUT,
+        )?;
+
+        /* 2 */
+        let nbr_entries = (len + PAGE_SIZE - 1) / PAGE_SIZE;
+        for (i, entry) in self.memory.command_table.prdt[..nbr_entries]
+            .iter_mut()
+            .enumerate()
+        {
+            let size = core::cmp::min(PAGE_SIZE, len - i * PAGE_SIZE);
+            // The byte count is 0 based, the last entry raises an interrupt on completion
+            entry.dbc = (size - 1) as u32;
+            if i == nbr_entries - 1 {
+                entry.dbc |= PrdtEntry::INTERRUPT;
+            }
+        }
+        let header = &mut self.memory.command_list[Self::COMMAND_SLOT];
+        header.flags = (size_of::<FisRegH2D>() / 4) as u16;
+        if write {
+            header.flags |= CommandHeader::WRITE;
+        }
+        header.prdtl = nbr_entries as u16;
+        header.prdbc = 0;
+        self.memory.command_table.cfis = FisRegH2D::new(command as u8, lba, count);
+
+        /* 3 */
+        clear_port_error(self.port);
+        regs.is.write(!0);
+        // All the command structures must be written before the HBA fetches them
+        fence(Ordering::SeqCst);
+        regs.ci.write(1 << Self::COMMAND_SLOT);
+
+        /* 4 */
+        for _ in 0..Self::COMMAND_TIMEOUT_POLLS {
+            let status = PortInterrupt::from_bits_truncate(regs.is.read());
+            if has_port_error(self.port) || status.intersects(PortInterrupt::ERRORS) {
+                return Err(self.recover(regs));
+            }
+            if regs.ci.read() & (1 << Self::COMMAND_SLOT) == 0 {
+                fence(Ordering::SeqCst);
+                return Ok(());
+            }
+            spin_loop_hint();
+        }
+        log::error!("SATA command {:?} timed out on port {}", command, self.port);
+        self.recover(regs);
+        Err(SataError::Timeout)
+    }
+
+    /// Log the error of the last command and restart the port, as the HBA stopped processing the command list
+    fn recover(&self, regs: &mut HbaPort) -> SataError {
+        log::error!(
+            "SATA error on port {}: task file {:#X?}, SATA error {:#X?}",
+            self.port,
+            regs.tfd.read(),
+            regs.serr.read()
+        );
+        let res = self.stop(regs).and_then(|_| {
+            regs.serr.write(!0);
+            regs.is.write(!0);
+            self.start(regs)
+        });
+        if let Err(e) = res {
+            log::error!("Cannot restart SATA port {}: {:?}", self.port, e);
+        }
+        SataError::IoError
+    }
+}
@@ -0,0 +1,163 @@
+//! This module contains the in-memory structures shared between the kernel and the HBA: FIS, command list and command table.
+//! See https://wiki.osdev.org/AHCI
+
+use crate::memory::tools::PAGE_SIZE;
+
+/// Kind of Frame Information Structure
+#[allow(dead_code)]
+#[repr(u8)]
+pub enum FisType {
+    /// Register FIS - host to device
+    RegH2D = 0x27,
+    /// Register FIS - device to host
+    RegD2H = 0x34,
+    /// DMA activate FIS - device to host
+    DmaAct = 0x39,
+    /// DMA setup FIS - bidirectional
+    DmaSetup = 0x41,
+    /// Data FIS - bidirectional
+    Data = 0x46,
+    /// BIST activate FIS - bidirectional
+    Bist = 0x58,
+    /// PIO setup FIS - device to host
+    PioSetup = 0x5F,
+    /// Set device bits FIS - device to host
+    DevBits = 0xA1,
+}
+
+/// Register FIS - Host to Device: Used to send an ATA command to the device (size 20)
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+pub struct FisRegH2D {
+    /*0        |*/ pub fis_type: u8,
+    /*1        |*/
+    pub pmport_c: u8, // bits 0-3: port multiplier, bit 7: 1 for command, 0 for control
+    /*2        |*/ pub command: u8,
+    /*3        |*/ pub featurel: u8,
+    /*4        |*/ pub lba0: u8,
+    /*5        |*/ pub lba1: u8,
+    /*6        |*/ pub lba2: u8,
+    /*7        |*/ pub device: u8,
+    /*8        |*/ pub lba3: u8,
+    /*9        |*/ pub lba4: u8,
+    /*a        |*/ pub lba5: u8,
+    /*b        |*/ pub featureh: u8,
+    /*c        |*/ pub countl: u8,
+    /*d        |*/ pub counth: u8,
+    /*e        |*/ pub icc: u8,
+    /*f        |*/ pub control: u8,
+    /*10       |*/ pub rsv: [u8; 4],
+}
+
+impl FisRegH2D {
+    /// Command bit of the pmport_c field
+    pub const COMMAND: u8 = 1 << 7;
+
+    /// LBA mode bit of the device field
+    pub const LBA_MODE: u8 = 1 << 6;
+
+    /// Create a FIS for an ATA command on `count` sectors starting at `lba`
+    pub fn new(command: u8, lba: u64, count: u16) -> Self {
+        Self {
+            fis_type: FisType::RegH2D as u8,
+            pmport_c: Self::COMMAND,
+            command,
+            device: Self::LBA_MODE,
+            lba0: lba as u8,
+            lba1: (lba >> 8) as u8,
+            lba2: (lba >> 16) as u8,
+            lba3: (lba >> 24) as u8,
+            lba4: (lba >> 32) as u8,
+            lba5: (lba >> 40) as u8,
+            countl: count as u8,
+            counth: (count >> 8) as u8,
+            ..Default::default()
+        }
+    }
+}
+
+/// Command header: An entry of the command list (size 32)
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+pub struct CommandHeader {
+    /*0        |*/
+    pub flags: u16, // bits 0-4: command FIS length in dwords, bit 6: write, bit 7: prefetchable
+    /*2        |*/ pub prdtl: u16, // Physical region descriptor table length in entries
+    /*4        |*/
+    pub prdbc: u32, // Physical region descriptor byte count transferred, updated by the HBA
+    /*8        |*/
+    pub ctba: u32, // Command table descriptor base address, 128-byte aligned
+    /*c        |*/
+    pub ctbau: u32, // Command table descriptor base address upper 32 bits
+    /*10       |*/ pub rsv: [u32; 4],
+}
+
+impl CommandHeader {
+    /// Write bit: The direction is host to device
+    pub const WRITE: u16 = 1 << 6;
+}
+
+/// Physical region descriptor: A data chunk of a command (size 16)
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+pub struct PrdtEntry {
+    /*0        |*/ pub dba: u32, // Data base address, word aligned
+    /*4        |*/ pub dbau: u32, // Data base address upper 32 bits
+    /*8        |*/ pub rsv: u32,
+    /*c        |*/
+    pub dbc: u32, // bits 0-21: byte count minus 1 (4M max), bit 31: interrupt on completion
+}
+
+impl PrdtEntry {
+    /// Interrupt on completion bit of the dbc field
+    pub const INTERRUPT: u32 = 1 << 31;
+}
+
+/// Number of command slots of a command list
+pub const NBR_COMMAND_SLOTS: usize = 32;
+
+/// Number of PRD entries of a command table: each entry describes one page of the DMA buffer
+/// With 128 entries, the whole port memory fits into a single page
+pub const NBR_PRDT_ENTRIES: usize = 128;
+
+/// Command table: The command FIS and its PRDT, pointed by a command header
+#[allow(dead_code)]
+#[repr(C)]
+pub struct CommandTable {
+    /*0        |*/ pub cfis: FisRegH2D, // Command FIS
+    /*14       |*/ rsv0: [u8; 64 - 20],
+    /*40       |*/ acmd: [u8; 16], // ATAPI command
+    /*50       |*/ rsv1: [u8; 48],
+    /*80       |*/ pub prdt: [PrdtEntry; NBR_PRDT_ENTRIES],
+}
+
+/// Memory of a port: The HBA has to know the physical address of all its fields
+/// Alignement: The command list is 1K aligned, the received FIS area is 256-byte aligned
+/// and the command table is 128-byte aligned (they are respectively at offsets 0, 0x400 and 0x500)
+#[repr(C, align(4096))]
+pub struct PortMemory {
+    /*0        |*/ pub command_list: [CommandHeader; NBR_COMMAND_SLOTS],
+    /*400      |*/ pub received_fis: [u8; 256],
+    /*500      |*/ pub command_table: CommandTable,
+}
+
+impl PortMemory {
+    pub fn new() -> Self {
+        Self {
+            command_list: [Default::default(); NBR_COMMAND_SLOTS],
+            received_fis: [0; 256],
+            command_table: CommandTable {
+                cfis: Default::default(),
+                rsv0: [0; 64 - 20],
+                acmd: [0; 16],
+                rsv1: [0; 48],
+                prdt: [Default::default(); NBR_PRDT_ENTRIES],
+            },
+        }
+    }
+}
+
+/// A page of the DMA buffer of a port
+#[derive(Copy, Clone)]
+#[repr(C, align(4096))]
+pub struct DmaPage(pub [u8; PAGE_SIZE]);
@@ -0,0 +1,148 @@
+//! This module describes the memory mapped registers of an AHCI HBA (Host Bus Adapter). See https://wiki.osdev.org/AHCI
+
+use bitflags::bitflags;
+use io::Mmio;
+use raw_data::define_raw_data;
+
+/// Generic host control registers, followed by the port control registers
+#[derive(Debug)]
+#[repr(C)]
+pub struct HbaMem {
+    // 0x00 - 0x2B, Generic Host Control
+    /*0        |*/
+    pub cap: Mmio<u32>, // Host capability
+    /*4        |*/ pub ghc: Mmio<u32>, // Global host control
+    /*8        |*/ pub is: Mmio<u32>, // Interrupt status
+    /*c        |*/ pub pi: Mmio<u32>, // Port implemented
+    /*10       |*/ pub vs: Mmio<u32>, // Version
+    /*14       |*/ pub ccc_ctl: Mmio<u32>, // Command completion coalescing control
+    /*18       |*/ pub ccc_pts: Mmio<u32>, // Command completion coalescing ports
+    /*1c       |*/ pub em_loc: Mmio<u32>, // Enclosure management location
+    /*20       |*/ pub em_ctl: Mmio<u32>, // Enclosure management control
+    /*24       |*/ pub cap2: Mmio<u32>, // Host capabilities extended
+    /*28       |*/ pub bohc: Mmio<u32>, // BIOS/OS handoff control and status
+
+    // 0x2C - 0x9F, Reserved
+    /*2C       |*/ reserved: Reserved,
+
+    // 0xA0 - 0xFF, Vendor specific registers
+    /*A0       |*/
+    vendor_specific_registers: VendorSpecificRegisters,
+
+    // 0x100 - 0x10FF, Port control registers (only those set in pi are implemented)
+    /*100      |*/
+    pub ports: [HbaPort; 32],
+}
+
+define_raw_data!(Reserved, 0xA0 - 0x2C);
+define_raw_data!(VendorSpecificRegisters, 0x100 - 0xA0);
+
+/// Port control registers
+#[derive(Debug)]
+#[repr(C)]
+pub struct HbaPort {
+    /*0        |*/ pub clb: Mmio<u32>, // command list base address, 1K-byte aligned
+    /*4        |*/ pub clbu: Mmio<u32>, // command list base address upper 32 bits
+    /*8        |*/ pub fb: Mmio<u32>, // FIS base address, 256-byte aligned
+    /*c        |*/ pub fbu: Mmio<u32>, // FIS base address upper 32 bits
+    /*10       |*/ pub is: Mmio<u32>, // interrupt status
+    /*14       |*/ pub ie: Mmio<u32>, // interrupt enable
+    /*18       |*/ pub cmd: Mmio<u32>, // command and status
+    /*1c       |*/ rsv0: Mmio<u32>, // Reserved
+    /*20       |*/ pub tfd: Mmio<u32>, // task file data
+    /*24       |*/ pub sig: Mmio<u32>, // signature
+    /*28       |*/ pub ssts: Mmio<u32>, // SATA status (SCR0:SStatus)
+    /*2c       |*/ pub sctl: Mmio<u32>, // SATA control (SCR2:SControl)
+    /*30       |*/ pub serr: Mmio<u32>, // SATA error (SCR1:SError)
+    /*34       |*/ pub sact: Mmio<u32>, // SATA active (SCR3:SActive)
+    /*38       |*/ pub ci: Mmio<u32>, // command issue
+    /*3c       |*/ pub sntf: Mmio<u32>, // SATA notification (SCR4:SNotification)
+    /*40       |*/ pub fbs: Mmio<u32>, // FIS-based switch control
+
+    // 0x44 ~ 0x6F, Reserved
+    /*44       |*/ reserved: ReservedPort,
+
+    // 0x70 ~ 0x7F, vendor specific
+    /*70       |*/ vendor_specific: VendorSpecificPort,
+}
+
+define_raw_data!(ReservedPort, 0x70 - 0x44);
+define_raw_data!(VendorSpecificPort, 0x80 - 0x70);
+
+// Global host control
+bitflags! {
+    pub struct GlobalHostControl: u32 {
+        const HR = 1 << 0; // HBA reset: Resets the whole HBA, cleared by the HBA when the reset is complete.
+        const IE = 1 << 1; // Interrupt enable: Allows the HBA to generate interrupts.
+        const AE = 1 << 31; // AHCI enable: The HBA is driven through the AHCI mechanisms (not the legacy ones).
+    }
+}
+
+// BIOS/OS handoff control and status
+bitflags! {
+    pub struct BiosOsHandoff: u32 {
+        const BOS = 1 << 0; // BIOS owned semaphore
+        const OOS = 1 << 1; // OS owned semaphore
+        const BB = 1 << 4; // BIOS busy: The BIOS is cleaning up before giving the HBA to the OS.
+    }
+}
+
+/// Host capabilities extended: BIOS/OS handoff is supported
+pub const CAP2_BOH: u32 = 1 << 0;
+
+// Port command and status
+bitflags! {
+    pub struct PortCommand: u32 {
+        const ST = 1 << 0; // Start: The HBA may process the command list.
+        const SUD = 1 << 1; // Spin-up device
+        const POD = 1 << 2; // Power on device
+        const FRE = 1 << 4; // FIS receive enable: The HBA may post received FIS into the FIS receive area.
+        const FR = 1 << 14; // FIS receive running
+        const CR = 1 << 15; // Command list running
+    }
+}
+
+// Port interrupt status and interrupt enable
+bitflags! {
+    pub struct PortInterrupt: u32 {
+        const DHRS = 1 << 0; // Device to host register FIS interrupt
+        const PSS = 1 << 1; // PIO setup FIS interrupt
+        const DSS = 1 << 2; // DMA setup FIS interrupt
+        const SDBS = 1 << 3; // Set device bits interrupt
+        const UFS = 1 << 4; // Unknown FIS interrupt
+        const DPS = 1 << 5; // Descriptor processed
+        const PCS = 1 << 6; // Port connect change status
+        const DMPS = 1 << 7; // Device mechanical presence status
+        const PRCS = 1 << 22; // PhyRdy change status
+        const IPMS = 1 << 23; // Incorrect port multiplier status
+        const OFS = 1 << 24; // Overflow status
+        const INFS = 1 << 26; // Interface non-fatal error status
+        const IFS = 1 << 27; // Interface fatal error status
+        const HBDS = 1 << 28; // Host bus data error status
+        const HBFS = 1 << 29; // Host bus fatal error status
+        const TFES = 1 << 30; // Task file error status: The status register of the device has ERR set.
+        const CPDS = 1 << 31; // Cold port detect status
+        const ERRORS = Self::IFS.bits | Self::HBDS.bits | Self::HBFS.bits | Self::TFES.bits;
+    }
+}
+
+// Low byte of the port task file data: a copy of the ATA status register
+bitflags! {
+    pub struct TaskFileStatus: u32 {
+        const ERR = 1 << 0; // Indicates an error occurred.
+        const DRQ = 1 << 3; // Set when the drive has data to transfer.
+        const BSY = 1 << 7; // Indicates the drive is busy.
+    }
+}
+
+/// SATA status: device detection field (bits 0-3) and the value for a device present with the phy communication established
+pub const SSTS_DET_MASK: u32 = 0xf;
+pub const SSTS_DET_PRESENT: u32 = 0x3;
+
+/// SATA status: interface power management field (bits 8-11) and the value for an interface in active state
+pub const SSTS_IPM_MASK: u32 = 0xf00;
+pub const SSTS_IPM_ACTIVE: u32 = 0x100;
+
+/// SATA control: device detection initialization field (bits 0-3) and the value to perform a COMRESET
+pub const SCTL_DET_MASK: u32 = 0xf;
+pub const SCTL_DET_COMRESET: u32 = 0x1;
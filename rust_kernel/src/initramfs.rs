@@ -0,0 +1,475 @@
+//! The initramfs is an archive given by the bootloader as the first multiboot module
+//! (`module /turbofish/initramfs.cpio` in grub, `-initrd` with qemu): its files are unpacked in
+//! a tmpfs mounted on the root instead of the ext2 of the disk, then `/init` is executed.
+//! The archive is a cpio in the newc format (`find . | cpio -o -H newc`) or an ustar (`tar -c`)
+
+use crate::memory::allocator::PHYSICAL_ALLOCATOR;
+use crate::memory::ffi::{map, unmap};
+use crate::memory::tools::{Page, Phys};
+use crate::multiboot::{MultibootInfo, MultibootModule};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::str;
+use fallible_collections::FallibleVec;
+use libc_binding::{gid_t, time_t, uid_t, Errno, FileType};
+
+static mut INITRAMFS: Option<MultibootModule> = None;
+
+/// Keep the location of the initramfs, must be called after the memory initialization
+pub fn init(multiboot_info: &MultibootInfo) {
+    let modules = multiboot_info.get_modules();
+    // Only the first module is used
+    for module in modules.iter().skip(1) {
+        release(module);
+    }
+    if let Some(module) = modules.first() {
+        log::info!("Initramfs of {} bytes", module.len());
+        unsafe {
+            INITRAMFS = Some(*module);
+        }
+    }
+}
+
+/// Was the kernel booted with an initramfs
+pub fn is_present() -> bool {
+    unsafe { INITRAMFS.is_some() }
+}
+
+/// Forget the initramfs when it could not be unpacked, the root is then the disk
+pub fn discard() {
+    unsafe {
+        INITRAMFS = None;
+    }
+}
+
+/// The size of the archive
+pub fn size() -> usize {
+    unsafe { INITRAMFS.map(|module| module.len()).unwrap_or(0) }
+}
+
+/// Call `f` on the content of the archive, then give its memory back to the system:
+/// this must be done only once
+pub fn unpack<T>(f: impl FnOnce(&[u8]) -> T) -> Result<T, Errno> {
+    let module = unsafe { INITRAMFS.ok_or(Errno::ENOENT)? };
+    let ptr = unsafe { map(module.mod_start as *mut u8, module.len()) };
+    if ptr.is_null() {
+        release(&module);
+        return Err(Errno::ENOMEM);
+    }
+    let ret = f(unsafe { core::slice::from_raw_parts(ptr, module.len()) });
+    unsafe {
+        unmap(ptr, module.len());
+    }
+    release(&module);
+    Ok(ret)
+}
+
+/// Give the memory of a module to the physical allocator
+fn release(module: &MultibootModule) {
+    let physical_allocator = unsafe { PHYSICAL_ALLOCATOR.as_mut().unwrap() };
+    if let Err(e) = physical_allocator.free_reserve(
+        Page::containing(Phys(module.mod_start as usize)),
+        module.len().into(),
+    ) {
+        log::error!("Cannot release a multiboot module: {:?}", e);
+    }
+}
+
+/// The type of a file of the archive
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EntryKind<'a> {
+    Regular,
+    Directory,
+    Fifo,
+    /// The content is the target of the link
+    Symlink,
+    /// Another path of a file which is already in the archive
+    HardLink(&'a str),
+    /// A device or a socket, which cannot be created from an archive
+    Unsupported,
+}
+
+#[derive(Debug)]
+pub struct ArchiveEntry<'a> {
+    /// The path relative to the root, without the leading '/' or './'
+    pub path: String,
+    pub kind: EntryKind<'a>,
+    /// The permissions, without the file type
+    pub mode: FileType,
+    pub uid: uid_t,
+    pub gid: gid_t,
+    pub mtime: time_t,
+    pub data: &'a [u8],
+}
+
+/// Remove the leading '/' and './' and the trailing '/' of a path
+fn relative_path(path: &str) -> &str {
+    let mut path = path.trim_end_matches('/');
+    loop {
+        if path.starts_with('/') {
+            path = &path[1..];
+        } else if path.starts_with("./") {
+            path = &path[2..];
+        } else if path == "." {
+            return "";
+        } else {
+            return path;
+        }
+    }
+}
+
+/// Join the prefix of a long ustar path and its name into a path relative to the root
+fn join_path(prefix: &str, name: &str) -> Result<String, Errno> {
+    let (prefix, name) = (relative_path(prefix), relative_path(name));
+    let mut path = String::new();
+    path.try_reserve(prefix.len() + 1 + name.len())?;
+    if !prefix.is_empty() {
+        path.push_str(prefix);
+        if !name.is_empty() {
+            path.push('/');
+        }
+    }
+    path.push_str(name);
+    Ok(path)
+}
+
+/// Get the sub slice [offset, offset + len[ of the archive
+fn get(archive: &[u8], offset: usize, len: usize) -> Result<&[u8], Errno> {
+    offset
+        .checked_add(len)
+        .and_then(|end| archive.get(offset..end))
+        .ok_or(Errno::EINVAL)
+}
+
+/// Parse a number written with ascii digits of the radix, ignoring the nul and space padding
+fn parse_number(field: &[u8], radix: u32) -> Result<u32, Errno> {
+    let field = str::from_utf8(field).map_err(|_| Errno::EINVAL)?;
+    let field = field.trim_matches(|c| c == '\0' || c == ' ');
+    if field.is_empty() {
+        return Ok(0);
+    }
+    u32::from_str_radix(field, radix).map_err(|_| Errno::EINVAL)
+}
+
+/// A nul terminated string of a fixed size field
+fn parse_string(field: &[u8]) -> Result<&str, Errno> {
+    let len = field.iter().position(|&c| c == 0).unwrap_or(field.len());
+    str::from_utf8(&field[..len]).map_err(|_| Errno::EINVAL)
+}
+
+fn kind_from_mode(mode: FileType) -> EntryKind<'static> {
+    if mode.is_directory() {
+        EntryKind::Directory
+    } else if mode.is_symlink() {
+        EntryKind::Symlink
+    } else if mode.is_fifo() {
+        EntryKind::Fifo
+    } else if mode.is_regular() {
+        EntryKind::Regular
+    } else {
+        EntryKind::Unsupported
+    }
+}
+
+const CPIO_NEWC_MAGIC: &[u8] = b"070701";
+const CPIO_NEWC_CRC_MAGIC: &[u8] = b"070702";
+const CPIO_NEWC_HEADER_LEN: usize = 110;
+const CPIO_TRAILER: &str = "TRAILER!!!";
+
+const TAR_BLOCK_SIZE: usize = 512;
+const TAR_MAGIC: &[u8] = b"ustar";
+const TAR_MAGIC_OFFSET: usize = 257;
+
+fn align(offset: usize, alignment: usize) -> usize {
+    (offset + alignment - 1) / alignment * alignment
+}
+
+/// Parse the entries of a cpio newc archive
+fn cpio_entries(archive: &[u8]) -> Result<Vec<ArchiveEntry>, Errno> {
+    let mut entries = Vec::new();
+    // (inode, path) of the files with many links, to find the next paths of these files
+    let mut links: Vec<(u32, &str)> = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let header = get(archive, offset, CPIO_NEWC_HEADER_LEN)?;
+        if &header[..6] != CPIO_NEWC_MAGIC && &header[..6] != CPIO_NEWC_CRC_MAGIC {
+            return Err(Errno::EINVAL);
+        }
+        let field = |index: usize| parse_number(&header[6 + index * 8..14 + index * 8], 16);
+        let (ino, mode, uid, gid, nlink, mtime) = (
+            field(0)?,
+            field(1)?,
+            field(2)?,
+            field(3)?,
+            field(4)?,
+            field(5)?,
+        );
+        let (filesize, namesize) = (field(6)? as usize, field(11)? as usize);
+
+        let name = get(archive, offset + CPIO_NEWC_HEADER_LEN, namesize)?;
+        let name = parse_string(name)?;
+        let data_offset = align(offset + CPIO_NEWC_HEADER_LEN + namesize, 4);
+        let data = get(archive, data_offset, filesize)?;
+        offset = align(data_offset + filesize, 4);
+
+        if name == CPIO_TRAILER {
+            return Ok(entries);
+        }
+        let path = relative_path(name);
+        let mode = FileType::from_bits_truncate(mode as u16);
+        let mut kind = kind_from_mode(mode);
+        if kind == EntryKind::Regular && nlink > 1 {
+            // The content of a file is only given with its last path
+            match links.iter().find(|(inode, _)| *inode == ino) {
+                Some((_, target)) => kind = EntryKind::HardLink(*target),
+                None => links.try_push((ino, path))?,
+            }
+        }
+        entries.try_push(ArchiveEntry {
+            path: join_path("", path)?,
+            kind,
+            mode: mode & (FileType::SPECIAL_BITS | FileType::PERMISSIONS_MASK),
+            uid: uid as uid_t,
+            gid: gid as gid_t,
+            mtime: mtime as time_t,
+            data,
+        })?;
+    }
+}
+
+/// Parse the entries of an ustar archive
+fn tar_entries(archive: &[u8]) -> Result<Vec<ArchiveEntry>, Errno> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let header = match get(archive, offset, TAR_BLOCK_SIZE) {
+            Ok(header) => header,
+            // Some archivers do not write the two empty blocks of the end
+            Err(_) if offset == archive.len() => return Ok(entries),
+            Err(e) => return Err(e),
+        };
+        if header.iter().all(|&c| c == 0) {
+            return Ok(entries);
+        }
+        if &header[TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + TAR_MAGIC.len()] != TAR_MAGIC {
+            return Err(Errno::EINVAL);
+        }
+        // A long path is splitted in a prefix and a name
+        let path = join_path(
+            parse_string(&header[345..500])?,
+            parse_string(&header[0..100])?,
+        )?;
+        let mode = parse_number(&header[100..108], 8)?;
+        let uid = parse_number(&header[108..116], 8)?;
+        let gid = parse_number(&header[116..124], 8)?;
+        let size = parse_number(&header[124..136], 8)? as usize;
+        let mtime = parse_number(&header[136..148], 8)?;
+        let linkname = parse_string(&header[157..257])?;
+
+        let data = get(archive, offset + TAR_BLOCK_SIZE, size)?;
+        offset = align(offset + TAR_BLOCK_SIZE + size, TAR_BLOCK_SIZE);
+
+        let (kind, data) = match header[156] {
+            b'0' | b'\0' | b'7' => (EntryKind::Regular, data),
+            b'1' => (EntryKind::HardLink(relative_path(linkname)), data),
+            b'2' => (EntryKind::Symlink, linkname.as_bytes()),
+            b'5' => (EntryKind::Directory, data),
+            b'6' => (EntryKind::Fifo, data),
+            _ => (EntryKind::Unsupported, data),
+        };
+        entries.try_push(ArchiveEntry {
+            path,
+            kind,
+            mode: FileType::from_bits_truncate(mode as u16)
+                & (FileType::SPECIAL_BITS | FileType::PERMISSIONS_MASK),
+            uid: uid as uid_t,
+            gid: gid as gid_t,
+            mtime: mtime as time_t,
+            data,
+        })?;
+    }
+}
+
+/// Get the entries of the archive, in the archive order
+pub fn entries(archive: &[u8]) -> Result<Vec<ArchiveEntry>, Errno> {
+    if archive.starts_with(CPIO_NEWC_MAGIC) || archive.starts_with(CPIO_NEWC_CRC_MAGIC) {
+        cpio_entries(archive)
+    } else if archive.get(TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + TAR_MAGIC.len()) == Some(TAR_MAGIC) {
+        tar_entries(archive)
+    } else {
+        log::error!("initramfs: unknown archive format");
+        Err(Errno::EINVAL)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Append a file to a cpio newc archive
+    fn cpio_push(archive: &mut Vec<u8>, name: &str, ino: u32, mode: u32, nlink: u32, data: &[u8]) {
+        let fields = [
+            ino,
+            mode,
+            0,
+            0,
+            nlink,
+            0,
+            data.len() as u32,
+            0,
+            0,
+            0,
+            0,
+            name.len() as u32 + 1,
+            0,
+        ];
+        archive.extend_from_slice(CPIO_NEWC_MAGIC);
+        for field in fields.iter() {
+            archive.extend_from_slice(format!("{:08X}", field).as_bytes());
+        }
+        archive.extend_from_slice(name.as_bytes());
+        archive.push(0);
+        archive.resize(align(archive.len(), 4), 0);
+        archive.extend_from_slice(data);
+        archive.resize(align(archive.len(), 4), 0);
+    }
+
+    fn cpio_archive(files: &[(&str, u32, &[u8])]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        for (ino, (name, mode, data)) in files.iter().enumerate() {
+            cpio_push(&mut archive, name, ino as u32 + 1, *mode, 1, data);
+        }
+        cpio_push(&mut archive, CPIO_TRAILER, 0, 0, 1, &[]);
+        archive
+    }
+
+    /// Append a file to an ustar archive
+    fn tar_push(archive: &mut Vec<u8>, prefix: &str, name: &str, typeflag: u8, data: &[u8]) {
+        let mut header = [0; TAR_BLOCK_SIZE];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..108].copy_from_slice(b"0000644\0");
+        header[108..116].copy_from_slice(b"0001750\0");
+        header[116..124].copy_from_slice(b"0001750\0");
+        header[124..136].copy_from_slice(format!("{:011o}\0", data.len()).as_bytes());
+        header[136..148].copy_from_slice(b"13520265364\0");
+        header[156] = typeflag;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(data);
+        archive.resize(align(archive.len(), TAR_BLOCK_SIZE), 0);
+    }
+
+    #[test]
+    fn cpio_short_names() {
+        let archive = cpio_archive(&[
+            (".", 0o40755, b""),
+            ("./bin", 0o40755, b""),
+            ("./bin/init", 0o100755, b"\x7fELF"),
+            ("etc/motd", 0o100644, b"hello\n"),
+        ]);
+        let entries = entries(&archive).unwrap();
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["", "bin", "bin/init", "etc/motd"]);
+        assert_eq!(entries[1].kind, EntryKind::Directory);
+        assert_eq!(entries[2].kind, EntryKind::Regular);
+        assert_eq!(entries[2].mode.bits(), 0o755);
+        assert_eq!(entries[2].data, b"\x7fELF");
+        assert_eq!(entries[3].data, b"hello\n");
+    }
+
+    #[test]
+    fn cpio_long_names() {
+        let name = "a/".repeat(100) + "file";
+        let archive = cpio_archive(&[(&name, 0o100644, b"data")]);
+        let entries = entries(&archive).unwrap();
+        assert_eq!(entries[0].path, name);
+        assert_eq!(entries[0].data, b"data");
+    }
+
+    #[test]
+    fn cpio_hard_links() {
+        let mut archive = Vec::new();
+        cpio_push(&mut archive, "a", 7, 0o100644, 2, b"");
+        cpio_push(&mut archive, "b", 7, 0o100644, 2, b"data");
+        cpio_push(&mut archive, CPIO_TRAILER, 0, 0, 1, &[]);
+        let entries = entries(&archive).unwrap();
+        assert_eq!(entries[0].kind, EntryKind::Regular);
+        assert_eq!(entries[1].kind, EntryKind::HardLink("a"));
+    }
+
+    #[test]
+    fn cpio_truncated() {
+        let archive = cpio_archive(&[("file", 0o100644, b"some data")]);
+        // The trailer is missing, then the data, then the header
+        for &len in [archive.len() - 4, 120, 50].iter() {
+            assert_eq!(cpio_entries(&archive[..len]).unwrap_err(), Errno::EINVAL);
+        }
+    }
+
+    #[test]
+    fn cpio_bad_magic() {
+        let mut archive = cpio_archive(&[("file", 0o100644, b"data")]);
+        let second_header = align(CPIO_NEWC_HEADER_LEN + 5, 4) + 4;
+        archive[second_header..second_header + 6].copy_from_slice(b"070707");
+        assert_eq!(cpio_entries(&archive).unwrap_err(), Errno::EINVAL);
+        archive[0] = b'1';
+        assert_eq!(entries(&archive).unwrap_err(), Errno::EINVAL);
+    }
+
+    #[test]
+    fn tar_short_names() {
+        let mut archive = Vec::new();
+        tar_push(&mut archive, "", "./", b'5', b"");
+        tar_push(&mut archive, "", "./etc/", b'5', b"");
+        tar_push(&mut archive, "", "./etc/motd", b'0', b"hello\n");
+        tar_push(&mut archive, "", "./etc/issue", b'2', b"");
+        archive.extend_from_slice(&[0; 2 * TAR_BLOCK_SIZE]);
+        let entries = entries(&archive).unwrap();
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["", "etc", "etc/motd", "etc/issue"]);
+        assert_eq!(entries[1].kind, EntryKind::Directory);
+        assert_eq!(entries[2].kind, EntryKind::Regular);
+        assert_eq!(entries[2].data, b"hello\n");
+        assert_eq!(entries[2].mode.bits(), 0o644);
+        assert_eq!((entries[2].uid, entries[2].gid), (1000, 1000));
+        assert_eq!(entries[3].kind, EntryKind::Symlink);
+    }
+
+    #[test]
+    fn tar_long_prefixed_names() {
+        let prefix = "usr/".repeat(30) + "share";
+        let name = "f".repeat(100);
+        let mut archive = Vec::new();
+        tar_push(&mut archive, &prefix, &name, b'0', &[42; 600]);
+        // Without the two empty blocks of the end
+        let entries = entries(&archive).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, format!("{}/{}", prefix, name));
+        assert_eq!(entries[0].data, &[42; 600][..]);
+    }
+
+    #[test]
+    fn tar_truncated() {
+        let mut archive = Vec::new();
+        tar_push(&mut archive, "", "file", b'0', &[42; 600]);
+        // In the data, then in the header
+        for &len in [TAR_BLOCK_SIZE + 100, 300].iter() {
+            assert_eq!(tar_entries(&archive[..len]).unwrap_err(), Errno::EINVAL);
+        }
+    }
+
+    #[test]
+    fn tar_bad_magic() {
+        let mut archive = Vec::new();
+        tar_push(&mut archive, "", "a", b'0', b"data");
+        tar_push(&mut archive, "", "b", b'0', b"data");
+        archive[2 * TAR_BLOCK_SIZE + TAR_MAGIC_OFFSET] = b'x';
+        assert_eq!(tar_entries(&archive).unwrap_err(), Errno::EINVAL);
+        archive[TAR_MAGIC_OFFSET] = b'x';
+        assert_eq!(entries(&archive).unwrap_err(), Errno::EINVAL);
+    }
+}
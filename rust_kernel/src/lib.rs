@@ -57,17 +57,20 @@ pub mod system;
 pub mod taskmaster;
 #[macro_use]
 pub mod drivers;
+pub mod cmdline;
+pub mod initramfs;
 pub mod math;
 pub mod memory;
 pub mod multiboot;
 pub mod panic;
 pub mod rust_main;
+pub mod smp;
 pub mod tests;
 
 pub mod watch_dog;
 pub use watch_dog::*;
 
-pub use sync::{Spinlock, SpinlockGuard};
+pub use sync::{ReentrantSpinlock, Spinlock, SpinlockGuard};
 pub mod elf_loader;
 
 use crate::memory::RustGlobalAlloc;
@@ -7,7 +7,10 @@ pub mod tools;
 
 pub mod allocator;
 pub use allocator::kernel::ffi;
+#[cfg(feature = "memory-debug")]
+pub use allocator::kernel::{leak_report, print_leak_report};
 pub use allocator::kernel::{set_faillible_context, unset_faillible_context, RustGlobalAlloc};
+pub use allocator::{slab_info, SlabInfo};
 pub use allocator::{VirtualPageAllocator, HIGH_KERNEL_MEMORY, KERNEL_VIRTUAL_PAGE_ALLOCATOR};
 
 pub mod address_space;
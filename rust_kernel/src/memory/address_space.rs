@@ -1,16 +1,20 @@
 use super::allocator::{BuddyAllocator, VirtualPageAllocator};
-use crate::memory::mmu::{Entry, PageDirectory};
+use crate::memory::mmu::{_enable_paging, _read_cr3, invalidate_page, Entry, PageDirectory};
 use crate::memory::tools::*;
 pub use crate::taskmaster::{CString, CStringArray};
+use crate::taskmaster::{SysResult, VFS};
 use alloc::vec::Vec;
 use core::convert::Into;
 use core::mem::size_of;
-use fallible_collections::{try_vec, FallibleVec};
+use fallible_collections::{try_vec, FallibleVec, TryClone};
 use libc_binding::c_char;
 
+mod file_mapping;
+pub use file_mapping::FileMapping;
+
 #[derive(Debug)]
-/// Virtual Allocator Specialized for processus
-pub struct AddressSpace(VirtualPageAllocator);
+/// Virtual Allocator Specialized for processus, with the list of its file mappings
+pub struct AddressSpace(VirtualPageAllocator, Vec<FileMapping>);
 
 impl AddressSpace {
     pub unsafe fn try_new() -> Result<Self> {
@@ -21,12 +25,20 @@ impl AddressSpace {
 
         let pd = PageDirectory::new_for_process()?;
 
-        Ok(Self(VirtualPageAllocator::new(buddy, pd)))
+        Ok(Self(VirtualPageAllocator::new(buddy, pd), Vec::new()))
     }
 
     /// the process forker must be the current cr3
-    pub fn fork(&self) -> Result<Self> {
-        Ok(Self(self.0.fork()?))
+    /// The loaded pages of the shared file mappings are not copied-on-write: the two address spaces share
+    /// the same physical pages and they stay writable on both sides. The pages which are not loaded yet
+    /// keep their FILE_MAPPED entry and are loaded on the first touch in each process
+    pub fn fork(&mut self) -> SysResult<Self> {
+        let file_mappings = self.1.try_clone()?;
+        let mappings = &self.1;
+        let virtual_allocator = self
+            .0
+            .fork(|page| mappings.iter().any(|m| m.shared && m.contains(page)))?;
+        Ok(Self(virtual_allocator, file_mappings))
     }
 
     /// Check if a pointer given by user process is not bullshit
@@ -40,6 +52,7 @@ impl AddressSpace {
                 .checked_add(size_of::<T>() - 1)
                 .ok_or(MemoryError::BadAddr)?,
         );
+        self.load_file_pages(start_ptr.into(), end_ptr.into());
 
         Ok(self
             .0
@@ -67,6 +80,7 @@ impl AddressSpace {
                 .checked_add(length * size_of::<T>() - 1)
                 .ok_or(MemoryError::BadAddr)?,
         );
+        self.load_file_pages(start_ptr.into(), end_ptr.into());
 
         Ok(self
             .0
@@ -81,12 +95,30 @@ impl AddressSpace {
         })
     }
 
+    /// check is a user ptr with len is mapped, its file pages may be not loaded yet
+    pub fn check_user_mapped_with_len<T>(&self, ptr: *const T, length: usize) -> Result<()> {
+        if length == 0 {
+            return Ok(());
+        }
+        let start_ptr = Virt(ptr as usize);
+        let end_ptr = Virt(
+            (ptr as usize)
+                .checked_add(length * size_of::<T>() - 1)
+                .ok_or(MemoryError::BadAddr)?,
+        );
+
+        Ok(self
+            .0
+            .check_page_range(start_ptr.into(), end_ptr.into(), |entry| {
+                entry.contains(Entry::from(AllocFlags::USER_MEMORY))
+                    && entry.intersects(Entry::PRESENT | Entry::FILE_MAPPED)
+            })
+            .map_err(|_| MemoryError::BadAddr)?)
+    }
+
     /// check is a user ptr with len is valid and READ WRITE
     pub fn check_user_mut_ptr_with_len<T>(&self, ptr: *mut T, length: usize) -> Result<()> {
-        self.check_user_ptr_predicate_with_len(ptr, length, |entry| {
-            entry
-                .contains(Entry::from(AllocFlags::USER_MEMORY) | Entry::READ_WRITE | Entry::PRESENT)
-        })
+        self.check_user_ptr_predicate_with_len(ptr, length, is_user_writable)
     }
     /// Creates a slice of T, from `ptr`, of `elem_number` elements.
     /// It checks against the Bullshitship of the ptr, asserting it's a valid userland pointer.
@@ -128,10 +160,7 @@ impl AddressSpace {
     }
     /// check is a user ptr is valid and READ WRITE
     pub fn check_user_ptr<T>(&self, ptr: *const T) -> Result<()> {
-        self.check_user_ptr_predicate(ptr, |entry| {
-            entry
-                .contains(Entry::from(AllocFlags::USER_MEMORY) | Entry::READ_WRITE | Entry::PRESENT)
-        })
+        self.check_user_ptr_predicate(ptr, is_user_writable)
     }
 
     /// create a safe ref from a raw pointer
@@ -144,10 +173,7 @@ impl AddressSpace {
 
     /// create a safe mut ref from a raw mut pointer
     pub fn make_checked_ref_mut<'unbound, T>(&self, ptr: *mut T) -> Result<&'unbound mut T> {
-        self.check_user_ptr_predicate(ptr, |entry| {
-            entry
-                .contains(Entry::from(AllocFlags::USER_MEMORY) | Entry::READ_WRITE | Entry::PRESENT)
-        })?;
+        self.check_user_ptr_predicate(ptr, is_user_writable)?;
         unsafe { Ok(&mut *ptr) }
     }
 
@@ -276,9 +302,272 @@ impl AddressSpace {
             .to_addr()
             .0 as *mut u8)
     }
+
+    /// Unmap a chunk of memory. A chunk is freed at once when possible, page per page otherwise.
+    /// The modified pages of the shared file mappings inside are written back into the files
     pub fn unmap_addr(&mut self, vaddr: Page<Virt>, size: NbrPages) -> Result<()> {
-        self.0.unmap_addr(vaddr, size)
+        let chunk_size = NbrPages(size.0.next_power_of_two());
+        let size = match self.0.ksize(vaddr) {
+            Ok(allocated) if allocated == chunk_size => chunk_size,
+            _ => size,
+        };
+        self.remove_file_mappings(vaddr, vaddr + size)?;
+        self.0
+            .unmap_addr(vaddr, size)
+            .or_else(|_| self.unmap_range(vaddr, size))
     }
+
+    /// Unmap exactly the pages of the range, whatever the chunks which contain them (cf MAP_FIXED)
+    pub fn unmap_range(&mut self, vaddr: Page<Virt>, size: NbrPages) -> Result<()> {
+        if !Self::is_user_range(vaddr, size) {
+            return Err(MemoryError::OutOfBound);
+        }
+        self.remove_file_mappings(vaddr, vaddr + size)?;
+        self.0.unmap_range(vaddr, size)
+    }
+
+    /// Check if the range is inside the part of the address space which belongs to the process
+    pub fn is_user_range(vaddr: Page<Virt>, size: NbrPages) -> bool {
+        vaddr.number >= NbrPages::_4MB.0
+            && vaddr
+                .number
+                .checked_add(size.0)
+                .map(|end| end <= NbrPages::_3GB.0)
+                .unwrap_or(false)
+    }
+
+    /// Count the pages mapped in the address space, cf RLIMIT_AS. The address space must be the current one
+    pub fn nbr_user_pages(&self) -> NbrPages {
+        self.0.nbr_user_pages()
+    }
+
+    /// Extend downward a stack whose lowest page is `bottom` so its lowest page becomes `new_bottom`.
+    /// The address space must be the current one.
+    /// The page under the new bottom is a guard page: it must stay free, so the stack never sticks to another mapping.
+    /// The pages already mapped by a previous attempt are kept
+    pub fn grow_stack(&mut self, bottom: Page<Virt>, new_bottom: Page<Virt>) -> Result<()> {
+        if new_bottom >= bottom || !Self::is_user_range(new_bottom, bottom - new_bottom) {
+            return Err(MemoryError::OutOfBound);
+        }
+        let guard_page = Page::new(new_bottom.number - 1);
+        if let Err(MemoryError::NotSatisfied) =
+            self.0
+                .check_page_range(guard_page, guard_page, |entry| !entry.contains(Entry::USER))
+        {
+            return Err(MemoryError::AlreadyOccupied);
+        }
+        for page in (new_bottom..bottom).iter() {
+            let mapped = self
+                .0
+                .check_page_range(page, page, |entry| entry.contains(Entry::USER))
+                .is_ok();
+            if !mapped {
+                self.0
+                    .alloc_on(page, NbrPages(1), AllocFlags::USER_MEMORY)?;
+                // The previous content of the physical page must not leak to the process
+                unsafe {
+                    (page.to_addr().0 as *mut u8).write_bytes(0, PAGE_SIZE);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Map the file of `file_mapping` on `size` bytes. The mapping is placed exactly at `vaddr` if specified,
+    /// discarding the previous mappings of the area. Its pages are loaded from the file on their first access
+    pub fn map_file(
+        &mut self,
+        vaddr: Option<*mut u8>,
+        size: usize,
+        flags: AllocFlags,
+        mut file_mapping: FileMapping,
+    ) -> Result<*mut u8> {
+        let nbr_pages = NbrPages::from(size);
+        let vaddr = vaddr.map(|vaddr| Page::containing(Virt(vaddr as usize)));
+        if let Some(vaddr) = vaddr {
+            self.unmap_range(vaddr, nbr_pages)?;
+        }
+
+        let entry = Entry::from(flags | AllocFlags::USER_MEMORY) | Entry::FILE_MAPPED;
+        let (start, nbr_pages) = self.0.reserve_unloaded(vaddr, nbr_pages, entry)?;
+
+        file_mapping.start = start;
+        file_mapping.nbr_pages = nbr_pages;
+        if let Err(e) = self.1.try_push(file_mapping) {
+            self.0
+                .unmap_range(start, nbr_pages)
+                .expect("Could not free memory reserved on VirtualPageAllocator");
+            return Err(e.into());
+        }
+        Ok(start.to_addr().0 as *mut u8)
+    }
+
+    /// Remove the file mappings of the range (start..end), the overlapping mappings are cut.
+    /// The address space must be the current one
+    fn remove_file_mappings(&mut self, start: Page<Virt>, end: Page<Virt>) -> Result<()> {
+        let mut i = 0;
+        while i < self.1.len() {
+            let mapping = &self.1[i];
+            if !mapping.intersects(start, end) {
+                i += 1;
+                continue;
+            }
+            if let Err(e) = unsafe { mapping.sync_range(start, end) } {
+                log::warn!("Cannot write back a shared mapping: {:?}", e);
+            }
+            let before = if mapping.start < start {
+                Some(mapping.sub_mapping(mapping.start, start))
+            } else {
+                None
+            };
+            let after = if mapping.end() > end {
+                Some(mapping.sub_mapping(end, mapping.end()))
+            } else {
+                None
+            };
+            match (before, after) {
+                (Some(before), Some(after)) => {
+                    self.1.try_push(after)?;
+                    self.1[i] = before;
+                    i += 1;
+                }
+                (Some(remaining), None) | (None, Some(remaining)) => {
+                    self.1[i] = remaining;
+                    i += 1;
+                }
+                (None, None) => {
+                    self.1.remove(i);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write back the modified pages of the shared file mappings of the range (start..end) into their files.
+    /// The address space must be the current one
+    pub fn sync_file_mappings(&self, start: Page<Virt>, end: Page<Virt>) -> SysResult<()> {
+        for mapping in self.1.iter().filter(|m| m.intersects(start, end)) {
+            unsafe {
+                mapping.sync_range(start, end)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Load the pages of the range (start..=end) which belong to a file mapping and are not loaded yet,
+    /// so the kernel can access them. Nothing is done when the address space is not the current one.
+    /// The pages are read through the VFS, so the user pointers must be checked before locking it:
+    /// when the VFS is already locked, the pages stay not present and the check fails
+    fn load_file_pages(&self, start: Page<Virt>, end: Page<Virt>) {
+        if self.1.is_empty() || !self.0.is_current() {
+            return;
+        }
+        let mut vfs = None;
+        for page in (start..=end).iter() {
+            if let Some(mapping) = self.1.iter().find(|m| m.contains(page)) {
+                if vfs.is_none() {
+                    vfs = VFS.try_lock();
+                }
+                if let Some(vfs) = vfs.as_mut() {
+                    // On failure, the page stays not present and the check fails
+                    let _r = unsafe { mapping.load_page(vfs, page) };
+                }
+            }
+        }
+    }
+
+    /// Write `value` at `ptr` like a debugger (cf PTRACE_POKETEXT): a read-only page, like
+    /// the code, is written anyway and gets a private copy if it is shared. The address space
+    /// must be the current one
+    pub unsafe fn force_write<T: Copy>(&mut self, ptr: *mut T, value: T) -> Result<()> {
+        self.check_user_ptr_predicate(ptr, |entry| {
+            entry.contains(Entry::from(AllocFlags::USER_MEMORY) | Entry::PRESENT)
+        })?;
+        let first = Page::containing(Virt(ptr as usize));
+        let last = Page::containing(Virt(ptr as usize + size_of::<T>() - 1));
+        let pages = if first == last {
+            &[first][..]
+        } else {
+            &[first, last][..]
+        };
+        let page_directory = PageDirectory::current();
+        let mut read_only = [false; 2];
+
+        // The read-only pages are made copy-on-write for the time of the write
+        for (i, &page) in pages.iter().enumerate() {
+            let entry = page_directory
+                .get_entry_mut(page)
+                .ok_or(MemoryError::BadAddr)?;
+            if !entry.intersects(Entry::READ_WRITE | Entry::COW) {
+                read_only[i] = true;
+                entry.insert(Entry::COW);
+            }
+            if entry.contains(Entry::COW) {
+                page_directory.cow_handle_page_fault(page.to_addr().0 as u32)?;
+            }
+        }
+        ptr.write_unaligned(value);
+        for (_, &page) in pages.iter().enumerate().filter(|(i, _)| read_only[*i]) {
+            if let Some(entry) = page_directory.get_entry_mut(page) {
+                entry.remove(Entry::READ_WRITE);
+                invalidate_page(page);
+            }
+        }
+        Ok(())
+    }
+
+    /// Write back the modified pages of the shared file mappings. The address space must not be the current one
+    fn sync_file_mappings(&self) {
+        if self.1.is_empty() {
+            return;
+        }
+        unsafe {
+            let old_cr3 = _read_cr3();
+            self.0.context_switch();
+            for mapping in self.1.iter() {
+                if let Err(e) = mapping.sync_range(mapping.start, mapping.end()) {
+                    log::warn!("Cannot write back a shared mapping: {:?}", e);
+                }
+            }
+            _enable_paging(old_cr3);
+        }
+    }
+
+    /// Write back the shared file mappings of a dead address space, and give its pages,
+    /// which can be freed on another processor
+    pub fn into_virtual_allocator(mut self) -> VirtualPageAllocator {
+        self.sync_file_mappings();
+        drop(core::mem::replace(&mut self.1, Vec::new()));
+        let address_space = core::mem::ManuallyDrop::new(self);
+        // The file mappings are empty and nothing else is dropped
+        unsafe { core::ptr::read(&address_space.0) }
+    }
+
+    /// Handle a page fault on a file page which is not loaded yet. The address space must be the current one
+    /// The fault fails if the VFS is already locked, as it cannot be waited for from the ISR
+    pub fn file_mapping_handle_page_fault(&self, cr2: u32) -> SysResult<()> {
+        let page = Page::containing(Virt(cr2 as usize));
+        let mapping = self
+            .1
+            .iter()
+            .find(|m| m.contains(page))
+            .ok_or(libc_binding::Errno::EFAULT)?;
+        let mut vfs = VFS.try_lock().ok_or(libc_binding::Errno::EFAULT)?;
+        unsafe { mapping.load_page(&mut vfs, page) }
+    }
+}
+
+/// The modified pages of the shared file mappings are written back into the files when the address space dies
+impl Drop for AddressSpace {
+    fn drop(&mut self) {
+        self.sync_file_mappings();
+    }
+}
+
+/// A copy-on-write page is writable for the process: the first write on it, even from the kernel, gives it a private copy
+fn is_user_writable(entry: Entry) -> bool {
+    entry.contains(Entry::from(AllocFlags::USER_MEMORY) | Entry::PRESENT)
+        && entry.intersects(Entry::READ_WRITE | Entry::COW)
 }
 
 /// Get the len of a C style *const c_char. Operate in a limited area
@@ -0,0 +1,187 @@
+//! This module contains the mappings of files into a process address space
+
+use crate::memory::allocator::PHYSICAL_ALLOCATOR;
+use crate::memory::mmu::{invalidate_page, Entry, PageDirectory};
+use crate::memory::tools::*;
+use crate::taskmaster::vfs::{InodeId, VirtualFileSystem};
+use crate::taskmaster::{FileOperation, SysResult, VFS};
+use alloc::collections::CollectionAllocErr;
+use alloc::sync::Arc;
+use fallible_collections::TryClone;
+use libc_binding::Errno;
+use sync::DeadMutex;
+
+/// A file region mapped into an address space.
+/// Its pages are loaded from the file on their first access (cf Entry::FILE_MAPPED)
+#[derive(Debug)]
+pub struct FileMapping {
+    /// First page of the mapping
+    pub start: Page<Virt>,
+    /// Number of pages of the mapping
+    pub nbr_pages: NbrPages,
+    /// The open file keeps the inode alive while it is mapped
+    file_operation: Arc<DeadMutex<dyn FileOperation>>,
+    inode_id: InodeId,
+    /// Offset in the file of the first page
+    offset: u64,
+    /// The modifications are carried through to the file
+    pub shared: bool,
+}
+
+/// TryClone boilerplate. The open file is shared by the two mappings
+impl TryClone for FileMapping {
+    fn try_clone(&self) -> Result<Self, CollectionAllocErr> {
+        Ok(Self {
+            start: self.start,
+            nbr_pages: self.nbr_pages,
+            file_operation: self.file_operation.clone(),
+            inode_id: self.inode_id,
+            offset: self.offset,
+            shared: self.shared,
+        })
+    }
+}
+
+impl FileMapping {
+    /// Create a mapping of the file at `offset`. Its location is set when it is placed into an address space
+    pub fn new(
+        file_operation: Arc<DeadMutex<dyn FileOperation>>,
+        inode_id: InodeId,
+        offset: u64,
+        shared: bool,
+    ) -> Self {
+        Self {
+            start: Page::new(0),
+            nbr_pages: NbrPages(0),
+            file_operation,
+            inode_id,
+            offset,
+            shared,
+        }
+    }
+
+    /// Get the page following the last page of the mapping
+    #[inline(always)]
+    pub fn end(&self) -> Page<Virt> {
+        self.start + self.nbr_pages
+    }
+
+    /// Check if `page` is part of the mapping
+    #[inline(always)]
+    pub fn contains(&self, page: Page<Virt>) -> bool {
+        page >= self.start && page < self.end()
+    }
+
+    /// Check if the mapping intersects the range (start..end)
+    #[inline(always)]
+    pub fn intersects(&self, start: Page<Virt>, end: Page<Virt>) -> bool {
+        start < self.end() && end > self.start
+    }
+
+    /// Get the mapping of the pages (start..end) of self.
+    pub fn sub_mapping(&self, start: Page<Virt>, end: Page<Virt>) -> Self {
+        Self {
+            start,
+            nbr_pages: end - start,
+            file_operation: self.file_operation.clone(),
+            inode_id: self.inode_id,
+            offset: self.file_offset(start),
+            shared: self.shared,
+        }
+    }
+
+    /// Get the file offset of a page of the mapping
+    #[inline(always)]
+    fn file_offset(&self, page: Page<Virt>) -> u64 {
+        self.offset + ((page - self.start).to_bytes() as u64)
+    }
+
+    /// Load a page of the mapping from the file if it is not loaded yet.
+    /// The bytes after the end of the file are filled with zeros.
+    /// The caller gives the locked VFS, the address space of the mapping must be the current one
+    pub unsafe fn load_page(&self, vfs: &mut VirtualFileSystem, page: Page<Virt>) -> SysResult<()> {
+        let entry = PageDirectory::current()
+            .get_entry_mut(page)
+            .ok_or(Errno::EFAULT)?;
+        if entry.contains(Entry::PRESENT) {
+            return Ok(());
+        }
+        if !entry.contains(Entry::FILE_MAPPED) {
+            return Err(Errno::EFAULT);
+        }
+        let physical_allocator = PHYSICAL_ALLOCATOR.as_mut().unwrap();
+        let paddr = physical_allocator.alloc(NbrPages(1), AllocFlags::USER_MEMORY)?;
+
+        // The page may be read-only for the process, so give the write right to the kernel while it fills it
+        let final_entry = *entry | Entry::PRESENT;
+        *entry = Entry::PRESENT | Entry::READ_WRITE;
+        entry.set_entry_page(paddr);
+        invalidate_page(page);
+
+        let buf = &mut *(page.to_addr().0 as *mut [u8; PAGE_SIZE]);
+        let mut offset = self.file_offset(page);
+        let mut len = 0;
+        while len < PAGE_SIZE {
+            let res = vfs
+                .get_inode(self.inode_id)
+                .and_then(|inode| inode.read(&mut offset, &mut buf[len..]));
+            match res {
+                Ok(0) => break,
+                Ok(count) => len += count as usize,
+                Err(e) => {
+                    *entry = final_entry - Entry::PRESENT;
+                    invalidate_page(page);
+                    physical_allocator
+                        .free(paddr)
+                        .expect("Could not free memory on PhysicalAllocator");
+                    return Err(e);
+                }
+            }
+        }
+        for byte in &mut buf[len..] {
+            *byte = 0;
+        }
+
+        *entry = final_entry;
+        entry.set_entry_page(paddr);
+        invalidate_page(page);
+        Ok(())
+    }
+
+    /// Write back a page of a shared mapping into the file if it was modified.
+    /// A mapping never extends its file, only the part of the page inside the file is written.
+    /// The address space of the mapping must be the current one
+    pub unsafe fn sync_page(&self, page: Page<Virt>) -> SysResult<()> {
+        if !self.shared {
+            return Ok(());
+        }
+        let entry = match PageDirectory::current().get_entry_mut(page) {
+            Some(entry) if entry.contains(Entry::PRESENT | Entry::DIRTY) => entry,
+            _ => return Ok(()),
+        };
+        let mut vfs = VFS.lock();
+        let inode = vfs.get_inode(self.inode_id)?;
+        let mut offset = self.file_offset(page);
+
+        if offset < inode.size {
+            let len = core::cmp::min(PAGE_SIZE as u64, inode.size - offset) as usize;
+            let buf = core::slice::from_raw_parts(page.to_addr().0 as *const u8, len);
+            inode.write(&mut offset, buf)?;
+        }
+        entry.remove(Entry::DIRTY);
+        invalidate_page(page);
+        Ok(())
+    }
+
+    /// Write back all the modified pages of the range (start..end) of a shared mapping.
+    /// The address space of the mapping must be the current one
+    pub unsafe fn sync_range(&self, start: Page<Virt>, end: Page<Virt>) -> SysResult<()> {
+        let start = core::cmp::max(start, self.start);
+        let end = core::cmp::min(end, self.end());
+
+        for page in (start..end).iter() {
+            self.sync_page(page)?;
+        }
+        Ok(())
+    }
+}
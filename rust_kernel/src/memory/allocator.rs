@@ -1,6 +1,6 @@
 pub mod kernel;
 pub use kernel::init_kernel_virtual_allocator;
-pub use kernel::{HIGH_KERNEL_MEMORY, KERNEL_VIRTUAL_PAGE_ALLOCATOR};
+pub use kernel::{slab_info, HIGH_KERNEL_MEMORY, KERNEL_VIRTUAL_PAGE_ALLOCATOR, SLAB_ALLOCATOR};
 
 mod physical;
 pub use physical::{init_physical_allocator, PHYSICAL_ALLOCATOR};
@@ -10,3 +10,6 @@ pub use r#virtual::VirtualPageAllocator;
 
 mod buddy;
 pub use buddy::BuddyAllocator;
+
+mod slab;
+pub use slab::{SlabAllocator, SlabInfo, SlabPageProvider, NBR_CACHES, SLAB_NBR_PAGES};
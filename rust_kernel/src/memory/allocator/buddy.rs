@@ -82,6 +82,48 @@ impl<T: Address> BuddyAllocator<T> {
         }
     }
 
+    /// Free a single page, even if it belongs to a bigger allocated buddy.
+    /// The allocated buddies containing the page are splitted on the way down,
+    /// so the other pages of the allocation stay occupied.
+    pub fn free_page(&mut self, addr: Page<T>) -> Result<()> {
+        if addr < self.addr || (addr - self.addr) >= self.size {
+            return Err(MemoryError::OutOfBound);
+        }
+
+        let target = (addr - self.addr).0;
+        let mut current_index = 0;
+        let mut current_page = 0;
+        let mut order = self.max_order;
+
+        while order > Order(0) {
+            let (occupied, splitted) = {
+                let buddy = self.get_buddy(current_index);
+                (buddy.occupied(), buddy.splitted())
+            };
+            let left_index = Self::left_child_index(current_index);
+            let right_index = Self::right_child_index(current_index);
+
+            if occupied && !splitted {
+                // The page is inside this allocation: cut it in two occupied halves
+                self.get_buddy(current_index).set_splitted(true);
+                self.get_buddy(left_index).set_occupied(true);
+                self.get_buddy(right_index).set_occupied(true);
+            } else if !splitted {
+                return Err(MemoryError::NotAllocated);
+            }
+
+            let half = (order - Order(1)).nbr_pages().0;
+            if target >= current_page + half {
+                current_page += half;
+                current_index = right_index;
+            } else {
+                current_index = left_index;
+            }
+            order = order - Order(1);
+        }
+        self.free(addr, Order(0))
+    }
+
     /// Reserves a buddy of order `order` starting at address `addr`.
     /// address reserved can be free the same way as an address returned by alloc
     /// # Panic
@@ -692,6 +734,36 @@ mod test {
             .expect("failed to free");
     }
     #[test]
+    fn test_free_page() {
+        const NB_BLOCK: usize = 16;
+        let map_location = 0x00010000 as *const u8;
+
+        let mut buddy_allocator: BuddyAllocator<Virt> =
+            BuddyAllocator::new(Virt(map_location as usize).into(), NbrPages(NB_BLOCK)).unwrap();
+        let buddy_before = buddy_allocator.clone();
+
+        let addr = buddy_allocator.alloc(NbrPages(4).into()).unwrap();
+        assert_eq!(addr, Virt(map_location as usize).into());
+
+        // Free the pages of the allocation one by one, in a random order
+        for i in [2, 0, 3].iter() {
+            buddy_allocator.free_page(addr + NbrPages(*i)).unwrap();
+            assert_eq!(
+                buddy_allocator.free_page(addr + NbrPages(*i)),
+                Err(MemoryError::CannotFree)
+            );
+        }
+        // The last page of the allocation is still here
+        assert_eq!(buddy_allocator.ksize(addr + NbrPages(1)), Ok(Order(0)));
+        buddy_allocator.free_page(addr + NbrPages(1)).unwrap();
+        assert_eq!(buddy_before, buddy_allocator);
+
+        assert_eq!(
+            buddy_allocator.free_page(addr),
+            Err(MemoryError::NotAllocated)
+        );
+    }
+    #[test]
     fn sodo_buddy_fill() {
         use crate::math::random::rand;
 
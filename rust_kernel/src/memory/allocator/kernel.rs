@@ -1,8 +1,11 @@
-use super::{BuddyAllocator, VirtualPageAllocator};
+use super::{BuddyAllocator, SlabAllocator, SlabInfo, SlabPageProvider, VirtualPageAllocator};
+use super::{NBR_CACHES, SLAB_NBR_PAGES};
 use crate::memory::mmu::{Entry, PageDirectory, _enable_paging, BIOS_PAGE_TABLE, PAGE_TABLES};
 use crate::memory::tools::*;
+use crate::ReentrantSpinlock;
 use alloc::boxed::Box;
 use core::alloc::{GlobalAlloc, Layout};
+use lazy_static::lazy_static;
 
 mod bootstrap;
 use bootstrap::*;
@@ -10,6 +13,13 @@ use bootstrap::*;
 mod rust_global_alloc;
 pub use rust_global_alloc::{set_faillible_context, unset_faillible_context, RustGlobalAlloc};
 
+#[cfg(feature = "memory-debug")]
+pub mod debug;
+#[cfg(feature = "memory-debug")]
+pub use debug::{debug_kcalloc, debug_kfree, debug_kmalloc, debug_krealloc};
+#[cfg(feature = "memory-debug")]
+pub use debug::{leak_report, print_leak_report};
+
 pub mod ffi;
 pub use ffi::*;
 
@@ -17,6 +27,13 @@ pub use ffi::*;
 pub static mut KERNEL_ALLOCATOR: KernelAllocator =
     KernelAllocator::Bootstrap(BootstrapKernelAllocator::new());
 
+lazy_static! {
+    /// The kernel virtual page allocator and kmalloc are shared by all the processors. The
+    /// physical allocator and the slab caches have their own locks. It is reentrant, as some
+    /// allocation functions call the others
+    pub static ref KERNEL_ALLOCATOR_LOCK: ReentrantSpinlock<()> = ReentrantSpinlock::new(());
+}
+
 /// The High kernel memory is reserved for PageDirectory && linear virtual addressing
 pub static mut HIGH_KERNEL_MEMORY: Option<VirtualPageAllocator> = None;
 const HIGH_KERNEL_MEMORY_START_ADDR: Virt = Virt(0xE0000000);
@@ -29,6 +46,40 @@ const PD_ADDRESS: Virt = Virt(0xFFC00000);
 pub static mut KERNEL_VIRTUAL_PAGE_ALLOCATOR: Option<VirtualPageAllocator> = None;
 const KERNEL_VIRTUAL_PAGE_ALLOCATOR_LEN: NbrPages = NbrPages::_512MB;
 
+/// The Slab Allocator is dedicated to the small kernel objects
+pub static mut SLAB_ALLOCATOR: Option<SlabAllocator> = None;
+
+/// The slabs are taken from the Kernel Virtual Page Allocator: as a buddy
+/// allocation, a slab is aligned on its size
+pub struct KernelSlabPages;
+
+impl SlabPageProvider for KernelSlabPages {
+    fn alloc_slab(&mut self) -> Result<Virt> {
+        unsafe {
+            KERNEL_VIRTUAL_PAGE_ALLOCATOR
+                .as_mut()
+                .unwrap()
+                .alloc(SLAB_NBR_PAGES, AllocFlags::KERNEL_MEMORY)
+                .map(|page| page.to_addr())
+        }
+    }
+
+    fn free_slab(&mut self, addr: Virt) {
+        unsafe {
+            KERNEL_VIRTUAL_PAGE_ALLOCATOR
+                .as_mut()
+                .unwrap()
+                .free(Page::containing(addr))
+                .expect("Cannot free a slab")
+        }
+    }
+}
+
+/// Get the statistics of the caches of the Slab Allocator
+pub fn slab_info() -> Option<[SlabInfo; NBR_CACHES]> {
+    unsafe { SLAB_ALLOCATOR.as_ref().map(|slab| slab.info()) }
+}
+
 pub enum KernelAllocator {
     Bootstrap(BootstrapKernelAllocator),
     Kernel,
@@ -85,9 +136,9 @@ pub unsafe fn init_kernel_virtual_allocator() {
     HIGH_KERNEL_MEMORY = Some(high_virt);
 
     let pd = HIGH_KERNEL_MEMORY
-        .as_ref()
+        .as_mut()
         .unwrap()
-        .fork_pd()
+        .fork_pd(|_| false)
         .expect("Cannot fork Page directory");
 
     let mut buddy = BuddyAllocator::new(virt_start, KERNEL_VIRTUAL_PAGE_ALLOCATOR_LEN)
@@ -100,6 +151,7 @@ pub unsafe fn init_kernel_virtual_allocator() {
     // Assign the kernel Virtual Page Allocator
     let virt = VirtualPageAllocator::new(buddy, pd);
     KERNEL_VIRTUAL_PAGE_ALLOCATOR = Some(virt);
+    SLAB_ALLOCATOR = Some(SlabAllocator::new());
 
     // Switch to the end allocator
     KERNEL_ALLOCATOR = KernelAllocator::Kernel;
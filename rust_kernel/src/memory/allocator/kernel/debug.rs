@@ -0,0 +1,387 @@
+//! This file contains the memory debugging mode of the kernel allocators (cf the
+//! feature memory-debug). Each allocation is surrounded by redzones and carries a
+//! header with its call stack:
+//!
+//! | padding | AllocHeader | redzone | object | redzone |
+//!
+//! The redzones are checked on free. A freed object is poisoned and kept in a
+//! quarantine before being given back to its allocator: a double free is caught
+//! while the object is in quarantine, and a write after free when it leaves it.
+//! The live allocations are linked together for the leak report.
+//! The allocations of the C interface (kmalloc of the kernel modules, vmalloc) are
+//! checked the same way.
+use super::rust_global_alloc::{raw_alloc, raw_dealloc};
+use super::KERNEL_ALLOCATOR_LOCK;
+use crate::panic::get_symbol;
+use alloc::string::String;
+use core::alloc::Layout;
+use core::fmt::{self, Write};
+use core::mem::{align_of, size_of};
+use core::ptr;
+use core::slice;
+
+/// The size of each redzone
+const REDZONE_SIZE: usize = 16;
+/// The content of the redzones
+const REDZONE_BYTE: u8 = 0xbb;
+/// The content of a new object
+const POISON_INUSE: u8 = 0x5a;
+/// The content of a freed object
+const POISON_FREE: u8 = 0x6b;
+
+const ALLOC_MAGIC: u32 = 0xa110_ca7e;
+const FREE_MAGIC: u32 = 0xf4ee_f4ee;
+
+/// The alignment of kmalloc
+const KMALLOC_ALIGN: usize = 16;
+
+/// The errors found on free
+pub const DOUBLE_FREE: &str = "double free";
+pub const INVALID_POINTER: &str = "free of an invalid pointer";
+pub const WRONG_SIZE: &str = "free with a wrong size";
+pub const UNDERFLOW: &str = "write before the start of the object";
+pub const OVERFLOW: &str = "write after the end of the object";
+
+/// The number of return addresses recorded for an allocation or a free
+const BACKTRACE_DEPTH: usize = 8;
+/// The number of freed objects kept in quarantine
+const QUARANTINE_SIZE: usize = 256;
+/// The number of allocation sites shown by the leak report
+const MAX_LEAK_SITES: usize = 32;
+
+type Backtrace = [u32; BACKTRACE_DEPTH];
+
+#[repr(C)]
+struct AllocHeader {
+    magic: u32,
+    size: usize,
+    align: usize,
+    alloc_site: Backtrace,
+    free_site: Backtrace,
+    prev: *mut AllocHeader,
+    next: *mut AllocHeader,
+}
+
+/// A freed allocation in quarantine
+#[derive(Copy, Clone)]
+struct Quarantined {
+    object: *mut u8,
+    layout: Layout,
+}
+
+/// The list of the live allocations
+static mut LIVE_ALLOCATIONS: *mut AllocHeader = ptr::null_mut();
+static mut QUARANTINE: [Option<Quarantined>; QUARANTINE_SIZE] = [None; QUARANTINE_SIZE];
+static mut QUARANTINE_INDEX: usize = 0;
+
+/// Get the return addresses of the callers of the caller
+#[inline(never)]
+unsafe fn backtrace() -> Backtrace {
+    let mut trace = [0; BACKTRACE_DEPTH];
+    let mut ebp: *const u32;
+    asm!("mov eax, ebp" : "={eax}"(ebp) : : : "intel");
+    // Skip the frame of the caller, which is in this file
+    ebp = *ebp as *const u32;
+    for eip in trace.iter_mut() {
+        if ebp.is_null() || *ebp.add(1) == 0 {
+            break;
+        }
+        *eip = *ebp.add(1);
+        ebp = *ebp as *const u32;
+    }
+    trace
+}
+
+/// Write the symbols of `trace`
+fn write_backtrace<W: Write>(w: &mut W, trace: &Backtrace) -> fmt::Result {
+    for &eip in trace.iter().take_while(|&&eip| eip != 0) {
+        let (name, offset) = get_symbol(eip);
+        writeln!(w, "    {:X?} : {:?}, eip={:X?}", offset, name, eip)?;
+    }
+    Ok(())
+}
+
+/// A writer on the error output
+struct ErrorOutput;
+
+impl Write for ErrorOutput {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        eprint!("{}", s);
+        Ok(())
+    }
+}
+
+/// Print the allocation site and the free site of `header`, then panic with `reason`
+unsafe fn memory_error(object: *mut u8, header: *const AllocHeader, reason: &str) -> ! {
+    eprintln!(
+        "memory-debug: {} ({:?}, {} bytes)",
+        reason,
+        object,
+        (*header).size
+    );
+    eprintln!("allocated from:");
+    let _ = write_backtrace(&mut ErrorOutput, &(*header).alloc_site);
+    if (*header).magic == FREE_MAGIC {
+        eprintln!("freed from:");
+        let _ = write_backtrace(&mut ErrorOutput, &(*header).free_site);
+    }
+    panic!("memory-debug: {} ({:?})", reason, object);
+}
+
+/// Get the offset of the object from the start of an allocation of `layout`
+fn object_offset(layout: Layout) -> usize {
+    let align = layout.align().max(align_of::<AllocHeader>());
+    (size_of::<AllocHeader>() + REDZONE_SIZE + align - 1) & !(align - 1)
+}
+
+/// Get the layout of the whole allocation of an object of `layout`
+fn debug_layout(layout: Layout) -> Layout {
+    let align = layout.align().max(align_of::<AllocHeader>());
+    unsafe {
+        Layout::from_size_align_unchecked(
+            object_offset(layout) + layout.size() + REDZONE_SIZE,
+            align,
+        )
+    }
+}
+
+/// Get the header of the allocation of `object`
+unsafe fn get_header(object: *mut u8) -> *mut AllocHeader {
+    object.sub(REDZONE_SIZE + size_of::<AllocHeader>()) as *mut AllocHeader
+}
+
+/// Get the redzones around `object`
+unsafe fn redzones(object: *mut u8, size: usize) -> (&'static mut [u8], &'static mut [u8]) {
+    (
+        slice::from_raw_parts_mut(object.sub(REDZONE_SIZE), REDZONE_SIZE),
+        slice::from_raw_parts_mut(object.add(size), REDZONE_SIZE),
+    )
+}
+
+/// Allocate an object of `layout` in the kernel allocators with its header and its redzones
+pub unsafe fn debug_alloc(layout: Layout) -> *mut u8 {
+    let base = raw_alloc(debug_layout(layout));
+    if base.is_null() {
+        return base;
+    }
+    let object = base.add(object_offset(layout));
+    let header = get_header(object);
+    header.write(AllocHeader {
+        magic: ALLOC_MAGIC,
+        size: layout.size(),
+        align: layout.align(),
+        alloc_site: backtrace(),
+        free_site: [0; BACKTRACE_DEPTH],
+        prev: ptr::null_mut(),
+        next: LIVE_ALLOCATIONS,
+    });
+    if !LIVE_ALLOCATIONS.is_null() {
+        (*LIVE_ALLOCATIONS).prev = header;
+    }
+    LIVE_ALLOCATIONS = header;
+
+    let (before, after) = redzones(object, layout.size());
+    before.iter_mut().for_each(|byte| *byte = REDZONE_BYTE);
+    after.iter_mut().for_each(|byte| *byte = REDZONE_BYTE);
+    object.write_bytes(POISON_INUSE, layout.size());
+    object
+}
+
+/// Check that the object `object` of `layout` can be freed: it is still allocated and its redzones are intact
+pub unsafe fn check_object(object: *mut u8, layout: Layout) -> Result<(), &'static str> {
+    let header = get_header(object);
+    match (*header).magic {
+        ALLOC_MAGIC => {}
+        FREE_MAGIC => return Err(DOUBLE_FREE),
+        _ => return Err(INVALID_POINTER),
+    }
+    if (*header).size != layout.size() || (*header).align != layout.align() {
+        return Err(WRONG_SIZE);
+    }
+    let (before, after) = redzones(object, layout.size());
+    if before.iter().any(|&byte| byte != REDZONE_BYTE) {
+        return Err(UNDERFLOW);
+    }
+    if after.iter().any(|&byte| byte != REDZONE_BYTE) {
+        return Err(OVERFLOW);
+    }
+    Ok(())
+}
+
+/// Check and poison the object `object` of `layout`, then put it in quarantine
+pub unsafe fn debug_dealloc(object: *mut u8, layout: Layout) {
+    let header = get_header(object);
+    match check_object(object, layout) {
+        Ok(()) => {}
+        Err(INVALID_POINTER) => panic!("memory-debug: {} {:?}", INVALID_POINTER, object),
+        Err(reason) => memory_error(object, header, reason),
+    }
+
+    if (*header).prev.is_null() {
+        LIVE_ALLOCATIONS = (*header).next;
+    } else {
+        (*(*header).prev).next = (*header).next;
+    }
+    if !(*header).next.is_null() {
+        (*(*header).next).prev = (*header).prev;
+    }
+    (*header).magic = FREE_MAGIC;
+    (*header).free_site = backtrace();
+    object.write_bytes(POISON_FREE, layout.size());
+
+    let evicted = QUARANTINE[QUARANTINE_INDEX].replace(Quarantined { object, layout });
+    QUARANTINE_INDEX = (QUARANTINE_INDEX + 1) % QUARANTINE_SIZE;
+    if let Some(Quarantined { object, layout }) = evicted {
+        let content = slice::from_raw_parts(object, layout.size());
+        if content.iter().any(|&byte| byte != POISON_FREE) {
+            memory_error(object, get_header(object), "write after free");
+        }
+        raw_dealloc(object.sub(object_offset(layout)), debug_layout(layout));
+    }
+}
+
+/// Get the layout of an object allocated by debug_alloc, for the interfaces which free without it
+pub unsafe fn object_layout(object: *mut u8) -> Layout {
+    let header = get_header(object);
+    match (*header).magic {
+        ALLOC_MAGIC => {}
+        FREE_MAGIC => memory_error(object, header, DOUBLE_FREE),
+        _ => panic!("memory-debug: {} {:?}", INVALID_POINTER, object),
+    }
+    Layout::from_size_align_unchecked((*header).size, (*header).align)
+}
+
+/// The kmalloc given to the kernel modules
+pub unsafe extern "C" fn debug_kmalloc(size: usize) -> *mut u8 {
+    let _allocator = KERNEL_ALLOCATOR_LOCK.lock();
+    debug_alloc(Layout::from_size_align_unchecked(size, KMALLOC_ALIGN))
+}
+
+/// The kcalloc given to the kernel modules
+pub unsafe extern "C" fn debug_kcalloc(count: usize, size: usize) -> *mut u8 {
+    let size = match count.checked_mul(size) {
+        Some(size) => size,
+        None => return ptr::null_mut(),
+    };
+    let object = debug_kmalloc(size);
+    if !object.is_null() {
+        object.write_bytes(0, size);
+    }
+    object
+}
+
+/// The kfree given to the kernel modules
+pub unsafe extern "C" fn debug_kfree(object: *mut u8) {
+    if !object.is_null() {
+        let _allocator = KERNEL_ALLOCATOR_LOCK.lock();
+        debug_dealloc(object, object_layout(object));
+    }
+}
+
+/// The krealloc given to the kernel modules, the object always moves
+pub unsafe extern "C" fn debug_krealloc(object: *mut u8, new_size: usize) -> *mut u8 {
+    if object.is_null() {
+        return debug_kmalloc(new_size);
+    }
+    let _allocator = KERNEL_ALLOCATOR_LOCK.lock();
+    let layout = object_layout(object);
+    let new_object = debug_alloc(Layout::from_size_align_unchecked(new_size, KMALLOC_ALIGN));
+    if !new_object.is_null() {
+        new_object.copy_from(object, core::cmp::min(layout.size(), new_size));
+        debug_dealloc(object, layout);
+    }
+    new_object
+}
+
+/// An allocation site in the leak report
+#[derive(Copy, Clone)]
+struct LeakSite {
+    alloc_site: Backtrace,
+    count: usize,
+    bytes: usize,
+}
+
+/// Write the live allocations of the kernel, grouped by allocation site
+pub fn write_leak_report<W: Write>(w: &mut W) -> fmt::Result {
+    let mut sites = [LeakSite {
+        alloc_site: [0; BACKTRACE_DEPTH],
+        count: 0,
+        bytes: 0,
+    }; MAX_LEAK_SITES];
+    let mut nbr_sites = 0;
+    let mut other = (0, 0);
+    let mut total = (0, 0);
+
+    // The sites are gathered before writing anything, as writing may allocate
+    unsafe {
+        let mut header = LIVE_ALLOCATIONS;
+        while !header.is_null() {
+            let size = (*header).size;
+            total = (total.0 + 1, total.1 + size);
+            match sites[..nbr_sites]
+                .iter_mut()
+                .find(|site| site.alloc_site == (*header).alloc_site)
+            {
+                Some(site) => {
+                    site.count += 1;
+                    site.bytes += size;
+                }
+                None if nbr_sites < MAX_LEAK_SITES => {
+                    sites[nbr_sites] = LeakSite {
+                        alloc_site: (*header).alloc_site,
+                        count: 1,
+                        bytes: size,
+                    };
+                    nbr_sites += 1;
+                }
+                None => other = (other.0 + 1, other.1 + size),
+            }
+            header = (*header).next;
+        }
+    }
+    let sites = &mut sites[..nbr_sites];
+    sites.sort_unstable_by(|a, b| b.bytes.cmp(&a.bytes));
+
+    writeln!(w, "{} live allocations, {} bytes", total.0, total.1)?;
+    for site in sites.iter() {
+        writeln!(
+            w,
+            "{} bytes in {} allocations from:",
+            site.bytes, site.count
+        )?;
+        write_backtrace(w, &site.alloc_site)?;
+    }
+    if other.0 != 0 {
+        writeln!(
+            w,
+            "{} bytes in {} allocations from other sites",
+            other.1, other.0
+        )?;
+    }
+    Ok(())
+}
+
+/// Print the leak report on the error output
+pub fn print_leak_report() {
+    eprintln!("--- memory-debug: leak report ---");
+    let _ = write_leak_report(&mut ErrorOutput);
+    eprintln!("---------------------------------");
+}
+
+/// A writer in a String which fails instead of aborting when out of memory
+struct FallibleString(String);
+
+impl Write for FallibleString {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.try_reserve(s.len()).map_err(|_| fmt::Error)?;
+        self.0.push_str(s);
+        Ok(())
+    }
+}
+
+/// Get the leak report
+pub fn leak_report() -> Option<String> {
+    let mut report = FallibleString(String::new());
+    write_leak_report(&mut report).ok()?;
+    Some(report.0)
+}
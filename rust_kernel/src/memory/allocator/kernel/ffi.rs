@@ -2,6 +2,7 @@ use super::*;
 
 #[no_mangle]
 pub unsafe extern "C" fn kreserve(virt: *mut u8, phys: *mut u8, size: usize) -> *mut u8 {
+    let _allocator = KERNEL_ALLOCATOR_LOCK.lock();
     match &mut KERNEL_ALLOCATOR {
         KernelAllocator::Bootstrap(_) => {
             panic!("Attempting to kreserve while in bootstrap allocator")
@@ -26,7 +27,14 @@ pub unsafe extern "C" fn kreserve(virt: *mut u8, phys: *mut u8, size: usize) ->
 /// FFI safe function: Allocate Kernel virtual Memory
 #[no_mangle]
 pub unsafe extern "C" fn vmalloc(size: usize) -> *mut u8 {
+    let _allocator = KERNEL_ALLOCATOR_LOCK.lock();
     match &mut KERNEL_ALLOCATOR {
+        // The pages are checked like the other allocations in the memory debugging mode
+        #[cfg(feature = "memory-debug")]
+        KernelAllocator::Kernel => {
+            debug::debug_alloc(Layout::from_size_align_unchecked(size, PAGE_SIZE))
+        }
+        #[cfg(not(feature = "memory-debug"))]
         KernelAllocator::Kernel => {
             KERNEL_VIRTUAL_PAGE_ALLOCATOR
                 .as_mut()
@@ -43,7 +51,11 @@ pub unsafe extern "C" fn vmalloc(size: usize) -> *mut u8 {
 /// FFI safe function: De-allocate Kernel virtual Memory
 #[no_mangle]
 pub unsafe extern "C" fn vfree(addr: *mut u8) {
+    let _allocator = KERNEL_ALLOCATOR_LOCK.lock();
     match &mut KERNEL_ALLOCATOR {
+        #[cfg(feature = "memory-debug")]
+        KernelAllocator::Kernel => debug::debug_dealloc(addr, debug::object_layout(addr)),
+        #[cfg(not(feature = "memory-debug"))]
         KernelAllocator::Kernel => KERNEL_VIRTUAL_PAGE_ALLOCATOR
             .as_mut()
             .unwrap()
@@ -56,7 +68,11 @@ pub unsafe extern "C" fn vfree(addr: *mut u8) {
 /// FFI safe function: Get the internal size of a vmalloc allocation
 #[no_mangle]
 pub unsafe extern "C" fn vsize(addr: *mut u8) -> usize {
+    let _allocator = KERNEL_ALLOCATOR_LOCK.lock();
     match &mut KERNEL_ALLOCATOR {
+        #[cfg(feature = "memory-debug")]
+        KernelAllocator::Kernel => debug::object_layout(addr).size(),
+        #[cfg(not(feature = "memory-debug"))]
         KernelAllocator::Kernel => KERNEL_VIRTUAL_PAGE_ALLOCATOR
             .as_mut()
             .unwrap()
@@ -70,6 +86,7 @@ pub unsafe extern "C" fn vsize(addr: *mut u8) -> usize {
 /// FFI safe function: Map a physical addr
 #[no_mangle]
 pub unsafe extern "C" fn map(phy_addr: *mut u8, size: usize) -> *mut u8 {
+    let _allocator = KERNEL_ALLOCATOR_LOCK.lock();
     match &mut KERNEL_ALLOCATOR {
         KernelAllocator::Kernel => {
             let addr = Phys(phy_addr as usize);
@@ -90,6 +107,7 @@ pub unsafe extern "C" fn map(phy_addr: *mut u8, size: usize) -> *mut u8 {
 /// FFI safe function: Unmap a physical addr corresponding to a physical addr
 #[no_mangle]
 pub unsafe extern "C" fn unmap(virt_addr: *mut u8, size: usize) -> i32 {
+    let _allocator = KERNEL_ALLOCATOR_LOCK.lock();
     match &mut KERNEL_ALLOCATOR {
         KernelAllocator::Kernel => {
             let addr = Virt(virt_addr as usize);
@@ -31,6 +31,73 @@ extern "C" {
     fn krealloc(addr: *mut u8, new_size: usize) -> *mut u8;
 }
 
+/// Allocate `layout` in the kernel allocators: the small objects are allocated by
+/// the slab allocator, the bigger ones by kmalloc, and those with a bigger alignment
+/// than kmalloc can give take whole pages
+pub(super) unsafe fn raw_alloc(layout: Layout) -> *mut u8 {
+    if SlabAllocator::handles(layout) {
+        SLAB_ALLOCATOR
+            .as_mut()
+            .unwrap()
+            .alloc(layout, &mut KernelSlabPages)
+            .unwrap_or(0x0 as *mut u8)
+    } else if layout.align() <= 16 {
+        kmalloc(layout.size())
+    } else {
+        KERNEL_VIRTUAL_PAGE_ALLOCATOR
+            .as_mut()
+            .unwrap()
+            .alloc(layout.size().into(), AllocFlags::KERNEL_MEMORY)
+            .unwrap_or(Page::containing(Virt(0x0)))
+            .to_addr()
+            .0 as *mut u8
+    }
+}
+
+/// Free `ptr` allocated by raw_alloc with the same `layout`
+pub(super) unsafe fn raw_dealloc(ptr: *mut u8, layout: Layout) {
+    if SlabAllocator::handles(layout) {
+        SLAB_ALLOCATOR
+            .as_mut()
+            .unwrap()
+            .free(ptr, &mut KernelSlabPages);
+    } else if layout.align() <= 16 {
+        kfree(ptr);
+    } else {
+        KERNEL_VIRTUAL_PAGE_ALLOCATOR
+            .as_mut()
+            .unwrap()
+            .free(Page::containing(Virt(ptr as usize)))
+            .expect("Cannot dealloc page");
+    }
+}
+
+#[cfg(not(feature = "memory-debug"))]
+use self::{raw_alloc as kernel_alloc, raw_dealloc as kernel_dealloc};
+
+/// With the memory debugging mode, the objects are checked on free
+#[cfg(feature = "memory-debug")]
+use super::debug::{debug_alloc as kernel_alloc, debug_dealloc as kernel_dealloc};
+
+/// The objects cannot be reallocated in place in the memory debugging mode
+const MEMORY_DEBUG: bool = cfg!(feature = "memory-debug");
+
+/// Check if an allocation of `layout` is made by kmalloc
+fn is_kmalloc_layout(layout: Layout) -> bool {
+    !SlabAllocator::handles(layout) && layout.align() <= 16
+}
+
+/// Allocate with new_size -> copy from old_ptr -> free old_ptr
+unsafe fn kernel_realloc(ptr: *mut u8, layout: Layout, new_layout: Layout) -> *mut u8 {
+    let new_ptr = kernel_alloc(new_layout);
+    if !new_ptr.is_null() {
+        let min = core::cmp::min(layout.size(), new_layout.size());
+        new_ptr.copy_from(ptr, min);
+        kernel_dealloc(ptr, layout);
+    }
+    new_ptr
+}
+
 unsafe impl GlobalAlloc for RustGlobalAlloc {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
         // This is for allocation of Empty struct rust, we can't
@@ -43,20 +110,9 @@ unsafe impl GlobalAlloc for RustGlobalAlloc {
         if HOOK_FAILLIBLE_CHECKER == true {
             return 0x0 as *mut u8;
         }
+        let _allocator = KERNEL_ALLOCATOR_LOCK.lock();
         match &mut KERNEL_ALLOCATOR {
-            KernelAllocator::Kernel => {
-                if layout.align() <= 16 {
-                    kmalloc(layout.size())
-                } else {
-                    KERNEL_VIRTUAL_PAGE_ALLOCATOR
-                        .as_mut()
-                        .unwrap()
-                        .alloc(layout.size().into(), AllocFlags::KERNEL_MEMORY)
-                        .unwrap_or(Page::containing(Virt(0x0)))
-                        .to_addr()
-                        .0 as *mut u8
-                }
-            }
+            KernelAllocator::Kernel => kernel_alloc(layout),
             KernelAllocator::Bootstrap(b) => {
                 b.alloc_bootstrap(layout).unwrap_or(Virt(0x0)).0 as *mut u8
             }
@@ -67,18 +123,9 @@ unsafe impl GlobalAlloc for RustGlobalAlloc {
         if ptr == DEVIL_POINTER {
             return;
         }
+        let _allocator = KERNEL_ALLOCATOR_LOCK.lock();
         match &mut KERNEL_ALLOCATOR {
-            KernelAllocator::Kernel => {
-                if layout.align() <= 16 {
-                    kfree(ptr);
-                } else {
-                    KERNEL_VIRTUAL_PAGE_ALLOCATOR
-                        .as_mut()
-                        .unwrap()
-                        .free(Page::containing(Virt(ptr as usize)))
-                        .expect("Cannot dealloc page");
-                }
-            }
+            KernelAllocator::Kernel => kernel_dealloc(ptr, layout),
             KernelAllocator::Bootstrap(_) => {
                 panic!("Attempting to free while in bootstrap allocator")
             }
@@ -86,26 +133,18 @@ unsafe impl GlobalAlloc for RustGlobalAlloc {
     }
 
     unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
-        assert!(layout.align() <= 16);
         // This is for allocation of Empty struct rust, we can't
         // return 0x0 because it is considered as an allocErr by rust,
         // and we want to avoid alloc so we return DEVIL_POINTER
         if layout.size() == 0 && layout.align() == 1 {
             return DEVIL_POINTER;
         }
+        let _allocator = KERNEL_ALLOCATOR_LOCK.lock();
         match &mut KERNEL_ALLOCATOR {
-            KernelAllocator::Kernel => {
-                if layout.align() <= 16 {
-                    kcalloc(1, layout.size())
-                } else {
-                    let ptr = self.alloc(layout);
-                    if !ptr.is_null() {
-                        ptr.write_bytes(0, layout.size());
-                    }
-                    ptr
-                }
+            KernelAllocator::Kernel if is_kmalloc_layout(layout) && !MEMORY_DEBUG => {
+                kcalloc(1, layout.size())
             }
-            KernelAllocator::Bootstrap(_) => {
+            _ => {
                 let ptr = self.alloc(layout);
                 if !ptr.is_null() {
                     ptr.write_bytes(0, layout.size());
@@ -126,31 +165,21 @@ unsafe impl GlobalAlloc for RustGlobalAlloc {
         if HOOK_FAILLIBLE_CHECKER == true {
             return 0x0 as *mut u8;
         }
+        let _allocator = KERNEL_ALLOCATOR_LOCK.lock();
         match &mut KERNEL_ALLOCATOR {
             KernelAllocator::Kernel => {
-                if layout.align() <= 16 {
+                let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+                if MEMORY_DEBUG {
+                    kernel_realloc(ptr, layout, new_layout)
+                } else if SlabAllocator::handles(layout)
+                    && SlabAllocator::object_size(layout) == SlabAllocator::object_size(new_layout)
+                {
+                    // The object is big enough
+                    ptr
+                } else if is_kmalloc_layout(layout) && is_kmalloc_layout(new_layout) {
                     krealloc(ptr, new_size)
                 } else {
-                    // The main KERNEL_VIRTUAL_PAGE_ALLOCATOR cannot realloc:
-                    // Allocate with new_size -> copy from old_ptr -> free old_ptr
-                    let new_ptr = KERNEL_VIRTUAL_PAGE_ALLOCATOR
-                        .as_mut()
-                        .unwrap()
-                        .alloc(new_size.into(), AllocFlags::KERNEL_MEMORY)
-                        .unwrap_or(Page::containing(Virt(0x0)))
-                        .to_addr()
-                        .0 as *mut u8;
-
-                    if !new_ptr.is_null() {
-                        let min = core::cmp::min(layout.size(), new_size);
-                        new_ptr.copy_from(ptr, min);
-                        KERNEL_VIRTUAL_PAGE_ALLOCATOR
-                            .as_mut()
-                            .unwrap()
-                            .free(Page::containing(Virt(ptr as usize)))
-                            .expect("Cannot dealloc page");
-                    }
-                    new_ptr
+                    kernel_realloc(ptr, layout, new_layout)
                 }
             }
             KernelAllocator::Bootstrap(_) => {
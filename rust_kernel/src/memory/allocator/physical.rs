@@ -1,15 +1,27 @@
 use super::BuddyAllocator;
 use crate::memory::tools::*;
+use crate::multiboot::MultibootModule;
+use crate::Spinlock;
+use fallible_collections::btree::BTreeMap;
 
 #[derive(Debug)]
 pub struct PhysicalPageAllocator {
     allocator: BuddyAllocator<Phys>,
+    /// Number of address spaces mapping a page, only for the pages shared by a copy-on-write fork.
+    /// A page which is not in this map is owned by a single address space.
+    /// The map grows on the kernel heap, so its lock is always taken before the kernel allocator
+    /// lock and `lock`: the allocators never use it
+    shared_pages: Spinlock<BTreeMap<Page<Phys>, usize>>,
+    /// The physical pages are shared by all the processors
+    lock: Spinlock<()>,
 }
 
 impl PhysicalPageAllocator {
     pub fn new(phys_start: Page<Phys>, size: NbrPages) -> Self {
         Self {
             allocator: BuddyAllocator::new(phys_start, size).expect("new physical buddy failed"),
+            shared_pages: Spinlock::new(BTreeMap::new()),
+            lock: Spinlock::new(()),
         }
     }
 
@@ -17,6 +29,7 @@ impl PhysicalPageAllocator {
         // Commented this as this is currently handled by the upper abstraction.
         // This is to be modified eventually.
         // if flags.contains(AllocFlags::KERNEL_MEMORY) {
+        let _lock = self.lock.lock();
         let order = size.into();
         let res = self.allocator.alloc(order)?;
         // eprintln!("{:x?}", res.to_addr());
@@ -27,24 +40,71 @@ impl PhysicalPageAllocator {
     }
 
     pub fn reserve(&mut self, addr: Page<Phys>, size: NbrPages) -> Result<()> {
+        let _lock = self.lock.lock();
         self.allocator.reserve_exact(addr, size)
     }
 
+    /// Give back to the allocator an area which was reserved
+    pub fn free_reserve(&mut self, addr: Page<Phys>, size: NbrPages) -> Result<()> {
+        let _lock = self.lock.lock();
+        self.allocator.free_reserve(addr, size)
+    }
+
     pub fn free(&mut self, paddr: Page<Phys>) -> Result<NbrPages> {
-        let nbr_pages = self.ksize(paddr)?;
+        let _lock = self.lock.lock();
+        let nbr_pages = self.allocator.ksize(paddr)?.nbr_pages();
         let order = nbr_pages.into();
         self.allocator.free(paddr, order)?;
         Ok(nbr_pages)
     }
 
     pub fn ksize(&mut self, paddr: Page<Phys>) -> Result<NbrPages> {
+        let _lock = self.lock.lock();
         Ok(self.allocator.ksize(paddr)?.nbr_pages())
     }
+
+    /// Take one more reference on a page which is going to be mapped by another address space
+    pub fn share_page(&mut self, paddr: Page<Phys>) -> Result<()> {
+        let mut shared_pages = self.shared_pages.lock();
+        match shared_pages.get_mut(&paddr) {
+            Some(count) => *count += 1,
+            None => {
+                shared_pages.try_insert(paddr, 2)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Is the page mapped by more than one address space
+    pub fn is_shared(&self, paddr: Page<Phys>) -> bool {
+        self.shared_pages.lock().get(&paddr).is_some()
+    }
+
+    /// Drop a reference on a single page. The page is freed when nobody else maps it,
+    /// even if it was allocated as a part of a bigger block.
+    pub fn release_page(&mut self, paddr: Page<Phys>) -> Result<()> {
+        let mut shared_pages = self.shared_pages.lock();
+        match shared_pages.get_mut(&paddr) {
+            Some(count) if *count > 2 => *count -= 1,
+            Some(_) => {
+                shared_pages.remove(&paddr);
+            }
+            None => {
+                let _lock = self.lock.lock();
+                self.allocator.free_page(paddr)?
+            }
+        }
+        Ok(())
+    }
 }
 
 pub static mut PHYSICAL_ALLOCATOR: Option<PhysicalPageAllocator> = None;
 
-pub unsafe fn init_physical_allocator(system_memory_amount: NbrPages, device_map: &[DeviceMap]) {
+pub unsafe fn init_physical_allocator(
+    system_memory_amount: NbrPages,
+    device_map: &[DeviceMap],
+    modules: &[MultibootModule],
+) {
     //eprintln!("kernel physical end: {:x?}", symbol_addr!(kernel_physical_end));
     //eprintln!("kernel physical end alligned: {:x?}", Phys(symbol_addr!(kernel_physical_end)).align_next(PAGE_SIZE));
 
@@ -69,5 +129,14 @@ pub unsafe fn init_physical_allocator(system_memory_amount: NbrPages, device_map
             //println!("some error were occured on pallocator ! {:?}", e);
         }
     }
+    // The modules loaded by the bootloader are kept until their content is used
+    for module in modules.iter() {
+        if let Err(e) = pallocator.reserve(
+            Page::containing(Phys(module.mod_start as usize)),
+            module.len().into(),
+        ) {
+            log::error!("Cannot reserve a multiboot module: {:?}", e);
+        }
+    }
     PHYSICAL_ALLOCATOR = Some(pallocator);
 }
@@ -0,0 +1,483 @@
+//! This file contains the slab allocator of the kernel: the small objects are
+//! allocated from caches of objects of the same size, each cache owning slabs
+//! of pages given by the buddy allocator.
+//!
+//! A slab is SLAB_SIZE bytes, aligned on its size: the header of a slab is at its
+//! start and is found from any of its objects by masking their address. An object
+//! is aligned on its size, which is a power of two.
+use crate::memory::tools::*;
+use crate::Spinlock;
+use core::alloc::Layout;
+use core::mem::size_of;
+use core::ptr;
+
+/// The number of pages of a slab
+pub const SLAB_NBR_PAGES: NbrPages = NbrPages::_16K;
+/// The size of a slab in bytes
+pub const SLAB_SIZE: usize = SLAB_NBR_PAGES.0 * PAGE_SIZE;
+
+/// The smallest object size, big enough to hold the link of a free object
+const MIN_OBJECT_SIZE: usize = 8;
+/// The biggest object size, bigger objects are not allocated by the slab allocator
+pub const MAX_OBJECT_SIZE: usize = 2048;
+
+/// The number of size classes of the slab allocator
+pub const NBR_CACHES: usize = 9;
+
+/// The number of free slabs kept by a cache, the other ones are given back to the buddy allocator
+const MAX_FREE_SLABS: usize = 1;
+
+/// A magic number put in the header of each slab, to check the freed pointers
+const SLAB_MAGIC: u32 = 0x51ab_51ab;
+
+/// The provider of the pages of the slabs
+pub trait SlabPageProvider {
+    /// Allocate SLAB_SIZE bytes, aligned on SLAB_SIZE
+    fn alloc_slab(&mut self) -> Result<Virt>;
+    /// Free a slab allocated by alloc_slab
+    fn free_slab(&mut self, addr: Virt);
+}
+
+/// A free object, linked to the next free object of its slab
+struct FreeObject {
+    next: *mut FreeObject,
+}
+
+/// The header at the start of a slab
+#[repr(C)]
+struct Slab {
+    magic: u32,
+    object_size: usize,
+    prev: *mut Slab,
+    next: *mut Slab,
+    free_list: *mut FreeObject,
+    in_use: usize,
+}
+
+impl Slab {
+    /// Get the slab containing the object `ptr`
+    fn containing(ptr: *mut u8) -> *mut Slab {
+        (ptr as usize & !(SLAB_SIZE - 1)) as *mut Slab
+    }
+
+    /// Check if `object` is on the free list of the slab
+    unsafe fn is_free(&self, object: *mut FreeObject) -> bool {
+        let mut free_object = self.free_list;
+        while !free_object.is_null() {
+            if free_object == object {
+                return true;
+            }
+            free_object = (*free_object).next;
+        }
+        false
+    }
+}
+
+/// A doubly linked list of slabs
+#[derive(Debug)]
+struct SlabList {
+    head: *mut Slab,
+    len: usize,
+}
+
+impl SlabList {
+    const fn new() -> Self {
+        Self {
+            head: ptr::null_mut(),
+            len: 0,
+        }
+    }
+
+    unsafe fn push(&mut self, slab: *mut Slab) {
+        (*slab).prev = ptr::null_mut();
+        (*slab).next = self.head;
+        if !self.head.is_null() {
+            (*self.head).prev = slab;
+        }
+        self.head = slab;
+        self.len += 1;
+    }
+
+    unsafe fn remove(&mut self, slab: *mut Slab) {
+        if (*slab).prev.is_null() {
+            self.head = (*slab).next;
+        } else {
+            (*(*slab).prev).next = (*slab).next;
+        }
+        if !(*slab).next.is_null() {
+            (*(*slab).next).prev = (*slab).prev;
+        }
+        self.len -= 1;
+    }
+
+    unsafe fn pop(&mut self) -> Option<*mut Slab> {
+        let slab = self.head;
+        if slab.is_null() {
+            None
+        } else {
+            self.remove(slab);
+            Some(slab)
+        }
+    }
+}
+
+/// The statistics of a cache, as shown by /proc/slabinfo
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SlabInfo {
+    pub name: &'static str,
+    pub active_objects: usize,
+    pub nbr_objects: usize,
+    pub object_size: usize,
+    pub objects_per_slab: usize,
+    pub pages_per_slab: usize,
+    pub active_slabs: usize,
+    pub nbr_slabs: usize,
+}
+
+/// A cache of objects of the same size
+#[derive(Debug)]
+pub struct ObjectCache {
+    name: &'static str,
+    object_size: usize,
+    /// The offset of the first object in a slab, after the header
+    first_object: usize,
+    objects_per_slab: usize,
+    partial: SlabList,
+    full: SlabList,
+    free: SlabList,
+    active_objects: usize,
+    /// Each cache is shared by all the processors
+    lock: Spinlock<()>,
+}
+
+impl ObjectCache {
+    /// Create a cache of objects of `object_size` bytes, which must be a power of two
+    pub fn new(name: &'static str, object_size: usize) -> Self {
+        assert!(object_size.is_power_of_two());
+        assert!(object_size >= MIN_OBJECT_SIZE && object_size <= MAX_OBJECT_SIZE);
+        let first_object = Virt(size_of::<Slab>()).align_next(object_size).0;
+        Self {
+            name,
+            object_size,
+            first_object,
+            objects_per_slab: (SLAB_SIZE - first_object) / object_size,
+            partial: SlabList::new(),
+            full: SlabList::new(),
+            free: SlabList::new(),
+            active_objects: 0,
+            lock: Spinlock::new(()),
+        }
+    }
+
+    /// Get a new slab from `provider`, and link all its objects
+    unsafe fn new_slab<P: SlabPageProvider>(&self, provider: &mut P) -> Result<*mut Slab> {
+        let addr = provider.alloc_slab()?;
+        debug_assert!(addr.is_aligned_on(SLAB_SIZE));
+        let slab = addr.0 as *mut Slab;
+        let mut free_list = ptr::null_mut();
+        for i in (0..self.objects_per_slab).rev() {
+            let object = (addr.0 + self.first_object + i * self.object_size) as *mut FreeObject;
+            (*object).next = free_list;
+            free_list = object;
+        }
+        slab.write(Slab {
+            magic: SLAB_MAGIC,
+            object_size: self.object_size,
+            prev: ptr::null_mut(),
+            next: ptr::null_mut(),
+            free_list,
+            in_use: 0,
+        });
+        Ok(slab)
+    }
+
+    /// Allocate an object
+    pub unsafe fn alloc<P: SlabPageProvider>(&mut self, provider: &mut P) -> Result<*mut u8> {
+        let _lock = self.lock.lock();
+        if self.partial.head.is_null() {
+            let slab = match self.free.pop() {
+                Some(slab) => slab,
+                None => self.new_slab(provider)?,
+            };
+            self.partial.push(slab);
+        }
+        let slab = self.partial.head;
+        let object = (*slab).free_list;
+        (*slab).free_list = (*object).next;
+        (*slab).in_use += 1;
+        if (*slab).in_use == self.objects_per_slab {
+            self.partial.remove(slab);
+            self.full.push(slab);
+        }
+        self.active_objects += 1;
+        Ok(object as *mut u8)
+    }
+
+    /// Free the object `ptr` allocated by this cache
+    pub unsafe fn free<P: SlabPageProvider>(&mut self, ptr: *mut u8, provider: &mut P) {
+        let _lock = self.lock.lock();
+        let slab = Slab::containing(ptr);
+        let offset = ptr as usize - slab as usize;
+        if (*slab).magic != SLAB_MAGIC
+            || (*slab).object_size != self.object_size
+            || offset < self.first_object
+            || (offset - self.first_object) % self.object_size != 0
+        {
+            panic!("{:?} was not allocated by the cache {}", ptr, self.name);
+        }
+        let object = ptr as *mut FreeObject;
+        // The whole free list is only walked with the debug assertions
+        if (*slab).in_use == 0 || cfg!(debug_assertions) && (*slab).is_free(object) {
+            panic!("double free of {:?} in the cache {}", ptr, self.name);
+        }
+        (*object).next = (*slab).free_list;
+        (*slab).free_list = object;
+        if (*slab).in_use == self.objects_per_slab {
+            self.full.remove(slab);
+            self.partial.push(slab);
+        }
+        (*slab).in_use -= 1;
+        self.active_objects -= 1;
+        if (*slab).in_use == 0 {
+            self.partial.remove(slab);
+            if self.free.len < MAX_FREE_SLABS {
+                self.free.push(slab);
+            } else {
+                provider.free_slab(Virt(slab as usize));
+            }
+        }
+    }
+
+    /// Get the statistics of the cache
+    pub fn info(&self) -> SlabInfo {
+        let _lock = self.lock.lock();
+        let active_slabs = self.partial.len + self.full.len;
+        let nbr_slabs = active_slabs + self.free.len;
+        SlabInfo {
+            name: self.name,
+            active_objects: self.active_objects,
+            nbr_objects: nbr_slabs * self.objects_per_slab,
+            object_size: self.object_size,
+            objects_per_slab: self.objects_per_slab,
+            pages_per_slab: SLAB_NBR_PAGES.0,
+            active_slabs,
+            nbr_slabs,
+        }
+    }
+}
+
+/// The slab allocator: a cache for each power of two size, from
+/// MIN_OBJECT_SIZE to MAX_OBJECT_SIZE
+#[derive(Debug)]
+pub struct SlabAllocator {
+    caches: [ObjectCache; NBR_CACHES],
+}
+
+impl SlabAllocator {
+    pub fn new() -> Self {
+        Self {
+            caches: [
+                ObjectCache::new("kmalloc-8", 8),
+                ObjectCache::new("kmalloc-16", 16),
+                ObjectCache::new("kmalloc-32", 32),
+                ObjectCache::new("kmalloc-64", 64),
+                ObjectCache::new("kmalloc-128", 128),
+                ObjectCache::new("kmalloc-256", 256),
+                ObjectCache::new("kmalloc-512", 512),
+                ObjectCache::new("kmalloc-1024", 1024),
+                ObjectCache::new("kmalloc-2048", 2048),
+            ],
+        }
+    }
+
+    /// Get the index of the cache of the objects of size `object_size`
+    fn cache_index(object_size: usize) -> usize {
+        (object_size.trailing_zeros() - MIN_OBJECT_SIZE.trailing_zeros()) as usize
+    }
+
+    /// Get the size of the object allocated for `layout`. As an object is
+    /// aligned on its size, a bigger alignment takes a bigger object
+    pub fn object_size(layout: Layout) -> usize {
+        layout
+            .size()
+            .max(layout.align())
+            .max(MIN_OBJECT_SIZE)
+            .next_power_of_two()
+    }
+
+    /// Check if an allocation of `layout` is made by the slab allocator
+    pub fn handles(layout: Layout) -> bool {
+        Self::object_size(layout) <= MAX_OBJECT_SIZE
+    }
+
+    /// Allocate an object for `layout`, which must be handled by the slab allocator
+    pub unsafe fn alloc<P: SlabPageProvider>(
+        &mut self,
+        layout: Layout,
+        provider: &mut P,
+    ) -> Result<*mut u8> {
+        debug_assert!(Self::handles(layout));
+        self.caches[Self::cache_index(Self::object_size(layout))].alloc(provider)
+    }
+
+    /// Free the object `ptr`, the slab and the size of the object are found from the pointer
+    pub unsafe fn free<P: SlabPageProvider>(&mut self, ptr: *mut u8, provider: &mut P) {
+        let object_size = Self::ksize(ptr);
+        self.caches[Self::cache_index(object_size)].free(ptr, provider)
+    }
+
+    /// Get the usable size of the object `ptr`
+    pub unsafe fn ksize(ptr: *mut u8) -> usize {
+        let slab = Slab::containing(ptr);
+        if (*slab).magic != SLAB_MAGIC {
+            panic!("{:?} was not allocated by the slab allocator", ptr);
+        }
+        (*slab).object_size
+    }
+
+    /// Get the statistics of all the caches
+    pub fn info(&self) -> [SlabInfo; NBR_CACHES] {
+        let mut info = [self.caches[0].info(); NBR_CACHES];
+        for (info, cache) in info.iter_mut().zip(self.caches.iter()) {
+            *info = cache.info();
+        }
+        info
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::alloc::{alloc, dealloc};
+    use std::collections::HashSet;
+    use std::vec::Vec;
+
+    /// A provider of slabs from the host allocator, which counts them
+    #[derive(Default)]
+    struct HostPages {
+        slabs: HashSet<usize>,
+    }
+
+    impl SlabPageProvider for HostPages {
+        fn alloc_slab(&mut self) -> Result<Virt> {
+            let ptr = unsafe { alloc(Layout::from_size_align(SLAB_SIZE, SLAB_SIZE).unwrap()) };
+            if ptr.is_null() {
+                return Err(MemoryError::OutOfMem);
+            }
+            self.slabs.insert(ptr as usize);
+            Ok(Virt(ptr as usize))
+        }
+
+        fn free_slab(&mut self, addr: Virt) {
+            assert!(self.slabs.remove(&addr.0), "freeing an unknown slab");
+            unsafe {
+                dealloc(
+                    addr.0 as *mut u8,
+                    Layout::from_size_align(SLAB_SIZE, SLAB_SIZE).unwrap(),
+                )
+            };
+        }
+    }
+
+    fn layout(size: usize, align: usize) -> Layout {
+        Layout::from_size_align(size, align).unwrap()
+    }
+
+    #[test]
+    fn slab_size_classes() {
+        let mut provider = HostPages::default();
+        let mut slab = SlabAllocator::new();
+        for &(size, align, object_size) in [
+            (1, 1, 8),
+            (8, 8, 8),
+            (9, 1, 16),
+            (24, 8, 32),
+            (100, 4, 128),
+            (2048, 16, 2048),
+            (4, 512, 512),
+            (300, 1024, 1024),
+        ]
+        .iter()
+        {
+            let ptr = unsafe { slab.alloc(layout(size, align), &mut provider).unwrap() };
+            assert_eq!(ptr as usize % align, 0);
+            assert_eq!(ptr as usize % object_size, 0);
+            assert_eq!(unsafe { SlabAllocator::ksize(ptr) }, object_size);
+            assert_eq!(SlabAllocator::object_size(layout(size, align)), object_size);
+            unsafe { slab.free(ptr, &mut provider) };
+        }
+        assert!(!SlabAllocator::handles(layout(2049, 8)));
+        assert!(!SlabAllocator::handles(layout(64, 4096)));
+    }
+
+    #[test]
+    fn slab_objects_do_not_overlap() {
+        let mut provider = HostPages::default();
+        let mut slab = SlabAllocator::new();
+        let mut objects = Vec::new();
+        for i in 0..2000 {
+            let size = 1 << (i % 9 + 3);
+            let ptr = unsafe { slab.alloc(layout(size, 1), &mut provider).unwrap() };
+            unsafe { ptr.write_bytes(i as u8, size) };
+            objects.push((ptr, size, i as u8));
+        }
+        for &(ptr, size, value) in objects.iter() {
+            let object = unsafe { core::slice::from_raw_parts(ptr, size) };
+            assert!(object.iter().all(|&byte| byte == value));
+        }
+        for &(ptr, _, _) in objects.iter() {
+            unsafe { slab.free(ptr, &mut provider) };
+        }
+    }
+
+    #[test]
+    fn slab_statistics() {
+        let mut provider = HostPages::default();
+        let mut slab = SlabAllocator::new();
+        let objects_per_slab = slab.info()[3].objects_per_slab;
+        let objects: Vec<*mut u8> = (0..objects_per_slab + 1)
+            .map(|_| unsafe { slab.alloc(layout(64, 8), &mut provider).unwrap() })
+            .collect();
+
+        let info = slab.info()[3];
+        assert_eq!(info.name, "kmalloc-64");
+        assert_eq!(info.active_objects, objects_per_slab + 1);
+        assert_eq!(info.nbr_objects, objects_per_slab * 2);
+        assert_eq!(info.active_slabs, 2);
+        assert_eq!(info.nbr_slabs, 2);
+        assert_eq!(provider.slabs.len(), 2);
+
+        for &ptr in objects.iter() {
+            unsafe { slab.free(ptr, &mut provider) };
+        }
+        // One free slab is kept by the cache
+        let info = slab.info()[3];
+        assert_eq!(info.active_objects, 0);
+        assert_eq!(info.active_slabs, 0);
+        assert_eq!(info.nbr_slabs, MAX_FREE_SLABS);
+        assert_eq!(provider.slabs.len(), MAX_FREE_SLABS);
+    }
+
+    #[test]
+    #[should_panic]
+    fn slab_free_invalid_pointer() {
+        let mut provider = HostPages::default();
+        let mut slab = SlabAllocator::new();
+        let ptr = unsafe { slab.alloc(layout(64, 8), &mut provider).unwrap() };
+        unsafe { slab.free(ptr.add(8), &mut provider) };
+    }
+
+    #[test]
+    #[should_panic(expected = "double free")]
+    fn slab_double_free() {
+        let mut provider = HostPages::default();
+        let mut slab = SlabAllocator::new();
+        let ptr = unsafe { slab.alloc(layout(64, 8), &mut provider).unwrap() };
+        // Another object keeps the slab in use
+        let _other = unsafe { slab.alloc(layout(64, 8), &mut provider).unwrap() };
+        unsafe {
+            slab.free(ptr, &mut provider);
+            slab.free(ptr, &mut provider);
+        }
+    }
+}
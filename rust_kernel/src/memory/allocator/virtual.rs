@@ -21,11 +21,7 @@ impl Debug for VirtualPageAllocator {
 /// Drop protector
 impl Drop for VirtualPageAllocator {
     fn drop(&mut self) {
-        if unsafe {
-            self.get_physical_addr(Virt(self.mmu.as_ref() as *const _ as usize))
-                .expect("Woot ?")
-                == _read_cr3()
-        } {
+        if self.is_current() {
             panic!("Page Directory Auto-Sodomization: Would you really trash your current CR3 ?");
         }
     }
@@ -36,16 +32,31 @@ impl VirtualPageAllocator {
         Self { virt, mmu }
     }
 
+    /// Is the handled PageDirectory the current cr3
+    pub fn is_current(&self) -> bool {
+        unsafe {
+            self.get_physical_addr(Virt(self.mmu.as_ref() as *const _ as usize))
+                .expect("Woot ?")
+                == _read_cr3()
+        }
+    }
+
     /// Just for the handled PageDirectory
-    pub unsafe fn fork_pd(&self) -> Result<Box<PageDirectory>> {
-        self.mmu.fork()
+    pub unsafe fn fork_pd<F>(&mut self, is_shared: F) -> Result<Box<PageDirectory>>
+    where
+        F: Fn(Page<Virt>) -> bool,
+    {
+        self.mmu.fork(is_shared)
     }
 
-    /// Fork the VirtualPageAllocator
-    pub fn fork(&self) -> Result<Self> {
+    /// Fork the VirtualPageAllocator, the pages for which `is_shared` is true are not copied-on-write
+    pub fn fork<F>(&mut self, is_shared: F) -> Result<Self>
+    where
+        F: Fn(Page<Virt>) -> bool,
+    {
         let buddy = self.virt.try_clone().map_err(|_| MemoryError::OutOfMem)?;
 
-        let pd = unsafe { self.fork_pd()? };
+        let pd = unsafe { self.fork_pd(is_shared)? };
 
         Ok(VirtualPageAllocator::new(buddy, pd))
     }
@@ -120,6 +131,12 @@ impl VirtualPageAllocator {
         Ok(())
     }
 
+    /// Count the user pages. The handled PageDirectory must be the current cr3
+    pub fn nbr_user_pages(&self) -> NbrPages {
+        debug_assert!(self.is_current());
+        self.mmu.nbr_user_pages()
+    }
+
     /// get the physical mapping of virtual address `v`
     pub unsafe fn get_physical_addr(&self, v: Virt) -> Option<Phys> {
         let offset = v.offset();
@@ -188,29 +205,49 @@ impl VirtualPageAllocator {
     pub fn unmap_addr(&mut self, vaddr: Page<Virt>, size: NbrPages) -> Result<()> {
         let order = size.into();
 
-        let page_paddr = unsafe {
-            self.mmu
-                .physical_page(vaddr)
-                .ok_or(MemoryError::NotPhysicallyMapped)?
-        };
+        self.mmu
+            .get_entry(vaddr)
+            .ok_or(MemoryError::NotPhysicallyMapped)?;
         // release the chunk on kernel virtual buddy
         self.virt.free(vaddr, order)?;
 
-        // Free the chunk on physical allocator
-        let physical_allocator = unsafe { PHYSICAL_ALLOCATOR.as_mut().unwrap() };
-        let r = physical_allocator.free(page_paddr.into());
-        if let Err(e) = r {
-            log::error!(
-                "A physical page was never allocated at {:#X?} {:#X?} nbr_pages: {:?} ! {:?}",
-                Phys::from(page_paddr),
-                Virt::from(vaddr),
-                size,
-                e
-            );
+        // Release the chunk on physical allocator page per page, a page may still be shared with another address space
+        for virtp in (vaddr..vaddr + order.nbr_pages()).iter() {
+            self.unmap_virtual_page(virtp);
         }
+        Ok(())
+    }
 
-        // unmap this vitual chunk
-        unsafe { self.mmu.unmap_range_page(vaddr, order.into()) }
+    /// UnMap a range of virtual pages, whatever the size of the chunks which contain them.
+    /// The pages which are not allocated are ignored
+    pub fn unmap_range(&mut self, vaddr: Page<Virt>, size: NbrPages) -> Result<()> {
+        for virtp in (vaddr..vaddr + size).iter() {
+            if self.virt.free_page(virtp).is_ok() {
+                self.unmap_virtual_page(virtp);
+            }
+        }
+        Ok(())
+    }
+
+    /// Release the physical page mapped on `virtp` if any and clear its entry, even if it was not present
+    fn unmap_virtual_page(&mut self, virtp: Page<Virt>) {
+        let physical_allocator = unsafe { PHYSICAL_ALLOCATOR.as_mut().unwrap() };
+        let entry = match self.mmu.get_entry_mut(virtp) {
+            Some(entry) => entry,
+            None => return,
+        };
+        if entry.contains(Entry::PRESENT) {
+            if let Err(e) = physical_allocator.release_page(entry.entry_page()) {
+                log::error!(
+                    "A physical page was never allocated at {:#X?} {:#X?} ! {:?}",
+                    entry.entry_addr(),
+                    Virt::from(virtp),
+                    e
+                );
+            }
+        }
+        *entry = Default::default();
+        invalidate_page(virtp);
     }
 
     pub fn alloc_on(
@@ -295,6 +332,36 @@ impl VirtualPageAllocator {
         Ok(vaddr.into())
     }
 
+    /// Reserve virtual pages without physical memory behind them: their entries are just set to `entry`.
+    /// The pages are placed exactly at `vaddr` if specified. Returns the number of pages reserved
+    pub fn reserve_unloaded(
+        &mut self,
+        vaddr: Option<Page<Virt>>,
+        size: NbrPages,
+        entry: Entry,
+    ) -> Result<(Page<Virt>, NbrPages)> {
+        let (vaddr, size) = match vaddr {
+            Some(vaddr) => {
+                self.virt.reserve_exact(vaddr, size)?;
+                (vaddr, size)
+            }
+            None => {
+                let order = size.into();
+                (self.virt.alloc(order)?, order.nbr_pages())
+            }
+        };
+
+        for virtp in (vaddr..vaddr + size).iter() {
+            let res = unsafe { self.mmu.map_page(virtp, Page::new(0), entry) };
+            if let Err(e) = res {
+                self.unmap_range(vaddr, size)
+                    .expect("Could not free memory reserved on VirtualPageAllocator");
+                return Err(e);
+            }
+        }
+        Ok((vaddr, size))
+    }
+
     pub fn valloc(&mut self, size: NbrPages, flags: AllocFlags) -> Result<Page<Virt>> {
         let order = size.into();
         let vaddr = self.virt.alloc(order)?;
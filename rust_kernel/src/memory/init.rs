@@ -1,11 +1,13 @@
 use crate::memory::allocator::{init_kernel_virtual_allocator, init_physical_allocator};
 use crate::memory::tools::*;
+use crate::multiboot::MultibootModule;
 
 pub unsafe fn init_memory_system(
     system_memory_amount: NbrPages,
     device_map_ptr: &[DeviceMap],
+    modules: &[MultibootModule],
 ) -> Result<()> {
-    init_physical_allocator(system_memory_amount, device_map_ptr);
+    init_physical_allocator(system_memory_amount, device_map_ptr, modules);
     init_kernel_virtual_allocator();
 
     Ok(())
@@ -13,23 +13,35 @@ pub static mut BIOS_PAGE_TABLE: [PageTable; 1] = [PageTable::new(); 1];
 pub static mut PAGE_TABLES: [PageTable; 255] = [PageTable::new(); 255];
 
 extern "C" {
-    pub fn _enable_paging(addr: Phys);
+    #[link_name = "_enable_paging"]
+    fn _load_page_directory(addr: Phys);
     pub fn _read_cr3() -> Phys;
     fn _enable_pse();
     fn _invlpg(addr: Virt);
     fn _invlpg_range(addr: Virt, nbr_pages: NbrPages);
 }
 
+/// Load the page directory `addr` and enable paging. The processors record the page
+/// directory they use before loading it, to receive the TLB shootdowns of its pages
+pub unsafe fn _enable_paging(addr: Phys) {
+    crate::smp::set_active_page_directory(addr);
+    _load_page_directory(addr);
+}
+
+/// Invalidate `page` in the TLB of all the processors
 #[inline(always)]
 pub fn invalidate_page(page: Page<Virt>) {
     unsafe {
         _invlpg(page.into());
     }
+    crate::smp::tlb_shootdown(page, NbrPages(1));
 }
 
+/// Invalidate `nbr_pages` from `page` in the TLB of all the processors
 #[inline(always)]
 pub fn invalidate_page_range(page: Page<Virt>, nbr_pages: NbrPages) {
     unsafe {
         _invlpg_range(page.into(), nbr_pages);
     }
+    crate::smp::tlb_shootdown(page, nbr_pages);
 }
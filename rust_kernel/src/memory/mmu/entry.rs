@@ -51,6 +51,16 @@ bitflags! {
         /// if set, prevents the TLB from updating the address in its cache if CR3 is reset. Note, that the page global enable bit in CR4 must be set to enable this feature.
         const GLOBAL = 1 << 8;
         const VALLOC = 1 << 9;
+
+        /// WARN: Page Table specific, available bit
+        /// The physical page is shared with another address space since a fork.
+        /// The page is logically writable but mapped read-only, the first write on it gives a private copy to the writer.
+        const COW = 1 << 10;
+
+        /// WARN: Page Table specific, available bit
+        /// The page belongs to a file mapping of a process.
+        /// When not present, its content is loaded from the file on the first access.
+        const FILE_MAPPED = 1 << 11;
     }
 }
 
@@ -2,7 +2,7 @@
 //! See https://wiki.osdev.org/Paging for relevant documentation.
 use super::_read_cr3;
 use super::page_table::PageTable;
-use super::{Entry, _enable_paging, BIOS_PAGE_TABLE, PAGE_TABLES};
+use super::{invalidate_page, Entry, _enable_paging, BIOS_PAGE_TABLE, PAGE_TABLES};
 use crate::memory::allocator::{HIGH_KERNEL_MEMORY, PHYSICAL_ALLOCATOR};
 use crate::memory::tools::*;
 use alloc::boxed::Box;
@@ -62,54 +62,113 @@ impl PageDirectory {
         _enable_paging(phys_pd);
     }
 
-    // Very very dummy fork ( no copy on write, a lot of context switch and a page per page approach )
-    pub unsafe fn fork(&self) -> Result<Box<Self>> {
+    /// Copy-on-write fork: the child shares every present user page with the parent.
+    /// The writable pages become read-only and COW on both sides, the first write on one of them gives a private copy to the writer.
+    /// The pages for which `is_shared` is true (cf shared file mappings) stay writable and are only shared by refcount,
+    /// the parent keeps their DIRTY bit so their modifications are written back once.
+    /// The forker must be the current cr3
+    pub unsafe fn fork<F>(&mut self, is_shared: F) -> Result<Box<Self>>
+    where
+        F: Fn(Page<Virt>) -> bool,
+    {
         #[allow(unused_assignments)]
-        let mut mem_tmp = [0; PAGE_SIZE];
+        let mut page_table_tmp = PageTable::new();
         let mut child = Self::new_for_process()?;
+        let physical_allocator = PHYSICAL_ALLOCATOR.as_mut().unwrap();
 
         // parcour the user page directory
         for i in 1..768 {
             let page = Page::new(i * 1024);
             if self[i].contains(Entry::PRESENT) {
+                // Allocate the child page table before taking any reference on the shared pages
+                child.as_ref().context_switch();
+                let res = child.get_page_table_trick_alloc(page).map(|_| ());
+                self.context_switch();
+                res?;
+
                 let page_table = self.get_page_table_trick(page).expect("can't happen");
 
-                // parcour the user page table
+                // parcour the user page table and take a reference on each present page for the child
                 for j in 0..1024 {
-                    let entry = page_table[j];
-                    if entry.contains(Entry::PRESENT) {
-                        // get the memory
-                        let virt = page + NbrPages(j);
-                        let mem = virt.to_addr().0 as *mut [u8; PAGE_SIZE];
-                        mem_tmp = *mem;
-
-                        child.as_ref().context_switch();
-                        let phys = PHYSICAL_ALLOCATOR
-                            .as_mut()
-                            .unwrap()
-                            .alloc(PAGE_SIZE.into(), AllocFlags::USER_MEMORY)?;
-                        // TODO: Respect the origin layout
-                        child.map_page(
-                            virt,
-                            phys,
-                            Entry::PRESENT | Entry::READ_WRITE | Entry::USER,
-                        )?;
-                        *(virt.to_addr().0 as *mut [u8; PAGE_SIZE]) = mem_tmp;
-                        self.context_switch();
+                    if page_table[j].contains(Entry::PRESENT) {
+                        if let Err(e) = physical_allocator.share_page(page_table[j].entry_page()) {
+                            for k in (0..j).filter(|&k| page_table[k].contains(Entry::PRESENT)) {
+                                physical_allocator
+                                    .release_page(page_table[k].entry_page())
+                                    .expect("A shared page cannot be freed here");
+                            }
+                            return Err(e);
+                        }
+                        if page_table[j].contains(Entry::READ_WRITE)
+                            && !is_shared(page + NbrPages(j))
+                        {
+                            page_table[j].remove(Entry::READ_WRITE);
+                            page_table[j].insert(Entry::COW);
+                        }
                     }
                 }
+                page_table_tmp = *page_table;
+                for j in (0..1024).filter(|&j| is_shared(page + NbrPages(j))) {
+                    page_table_tmp[j].remove(Entry::DIRTY);
+                }
+
+                child.as_ref().context_switch();
+                *child.get_page_table_trick(page).expect("can't happen") = page_table_tmp;
+                if self[i].contains(Entry::USER) {
+                    child[i] |= Entry::USER;
+                }
+                // Switching back to the parent cr3 also flushes its downgraded entries from the TLB
+                self.context_switch();
             }
         }
         Ok(child)
     }
 
+    /// Get the page directory of the current cr3, seen through the self map trick
+    #[inline(always)]
+    pub unsafe fn current<'a>() -> &'a mut Self {
+        &mut *(0xFFFFF000 as *mut Self)
+    }
+
+    /// Resolve a write fault on a copy-on-write user page. Must be called on the current page directory.
+    /// The writer gets a private copy of the shared page, or just its write right back if nobody else maps it anymore
+    pub unsafe fn cow_handle_page_fault(&mut self, cr2: u32) -> Result<()> {
+        let virtp = Page::containing(Virt(cr2 as usize));
+        // Only the user space may be shared between page directories
+        if virtp.pd_index() == 0 || virtp.pd_index() >= 768 {
+            return Err(MemoryError::PageFault);
+        }
+        let physical_allocator = PHYSICAL_ALLOCATOR.as_mut().unwrap();
+        let entry = self.get_entry_mut(virtp).ok_or(MemoryError::PageFault)?;
+
+        if !entry.contains(Entry::PRESENT | Entry::COW) {
+            return Err(MemoryError::PageFault);
+        }
+        let shared_page = entry.entry_page();
+        if physical_allocator.is_shared(shared_page) {
+            let private_page = physical_allocator.alloc(NbrPages(1), AllocFlags::USER_MEMORY)?;
+            // The shared page is still mapped read-only at the faulting address, copy it through the stack
+            let mem_tmp = *(virtp.to_addr().0 as *const [u8; PAGE_SIZE]);
+            entry.set_entry_page(private_page);
+            entry.remove(Entry::COW);
+            entry.insert(Entry::READ_WRITE);
+            invalidate_page(virtp);
+            *(virtp.to_addr().0 as *mut [u8; PAGE_SIZE]) = mem_tmp;
+            physical_allocator.release_page(shared_page)?;
+        } else {
+            entry.remove(Entry::COW);
+            entry.insert(Entry::READ_WRITE);
+            invalidate_page(virtp);
+        }
+        Ok(())
+    }
+
     /// Free the user ressources of a process by following its Page Directory (Cannot work with valloc)
+    /// Each page drops its reference on its physical page, which is only freed when no other process shares it
     unsafe fn free_user_ressources(&mut self) {
         let old_cr3 = _read_cr3();
         self.context_switch();
-
-        let mut remaining_pages: NbrPages = NbrPages(0);
-        let mut temporary_addr: Phys = Phys(0);
+        let physical_allocator = PHYSICAL_ALLOCATOR.as_mut().unwrap();
 
         for i in 1..768 {
             let page = Page::new(i * 1024);
@@ -117,46 +176,63 @@ impl PageDirectory {
                 let page_table = self.get_page_table_trick(page).expect("can't happen");
                 for j in 0..1024 {
                     if page_table[j].contains(Entry::PRESENT) {
-                        if page_table[j].entry_addr() != temporary_addr
-                            || remaining_pages == NbrPages(0)
-                        {
-                            // This point may signify the end of the previous block and the begin of the next
-                            temporary_addr = page_table[j].entry_addr();
-                            // A physical block of size max NbrPages(remaining_pages) is detected, liberate it
-                            remaining_pages = PHYSICAL_ALLOCATOR
-                                .as_mut()
-                                .unwrap()
-                                .free(page_table[j].entry_addr().into())
-                                .unwrap();
-                        }
-                        remaining_pages -= NbrPages(1);
-                        temporary_addr += PAGE_SIZE;
-                    } else {
-                        // This point may signify the end of the previous block
-                        remaining_pages = NbrPages(0);
+                        physical_allocator
+                            .release_page(page_table[j].entry_page())
+                            .unwrap();
                     }
                 }
-                PHYSICAL_ALLOCATOR
-                    .as_mut()
-                    .unwrap()
-                    .free(self[i].entry_page())
-                    .unwrap();
+                physical_allocator.free(self[i].entry_page()).unwrap();
             }
         }
         _enable_paging(old_cr3);
     }
 
+    /// Count the user pages, loaded or not. Uses the self map trick, so it must be the current page directory
+    pub fn nbr_user_pages(&self) -> NbrPages {
+        let mut nbr_pages = 0;
+        for i in (1..768).filter(|&i| self[i].contains(Entry::PRESENT)) {
+            let page_table = self
+                .get_page_table_trick(Page::new(i * 1024))
+                .expect("can't happen");
+            nbr_pages += (0..1024)
+                .filter(|&j| page_table[j].contains(Entry::USER))
+                .count();
+        }
+        NbrPages(nbr_pages)
+    }
+
     /// Modify the alloc flags for a specific and existing page
     #[inline(always)]
     pub fn modify_page_entry(&mut self, page: Page<Virt>, entry: Entry) {
         let pt_index = page.pt_index();
         let page_table = self.get_page_table_trick(page).expect("can't happen");
+
+        // A file page which is not loaded yet just takes its new flags
+        if !page_table[pt_index].contains(Entry::PRESENT)
+            && page_table[pt_index].contains(Entry::FILE_MAPPED)
+        {
+            page_table[pt_index] = entry | Entry::FILE_MAPPED;
+            return;
+        }
         assert!(page_table[pt_index].contains(Entry::PRESENT));
 
         // Be careful, reseting the flags of a page_table[pt_index] remove automaticely its physical entry addr (seems to be a dev error)
         let entry_addr = page_table[pt_index].entry_addr();
         page_table[pt_index] = entry | Entry::PRESENT;
         page_table[pt_index].set_entry_addr(entry_addr);
+
+        // A page still shared since a fork cannot become writable, it stays copy-on-write
+        if page_table[pt_index].contains(Entry::READ_WRITE)
+            && unsafe {
+                PHYSICAL_ALLOCATOR
+                    .as_ref()
+                    .unwrap()
+                    .is_shared(entry_addr.into())
+            }
+        {
+            page_table[pt_index].remove(Entry::READ_WRITE);
+            page_table[pt_index].insert(Entry::COW);
+        }
     }
 
     /// This is a trick that ensures that the page tables are mapped into virtual memory at address 0xFFC00000 .
@@ -15,6 +15,8 @@ global _invlpg_range
 
 ;; It loads the argument as the page directory pointer in cr3,
 ;; then actives paging.
+;; The Write Protect bit is also set, so the kernel faults when it writes on a read-only page,
+;; that is mandatory for the copy-on-write pages.
 ;; Takes a pointer to the page directory as argument
 _enable_paging:
 	push ebp
@@ -24,7 +26,7 @@ _enable_paging:
 	mov cr3, eax
 
 	mov eax, cr0
-	or eax, 0x80000001
+	or eax, 0x80010001
 	mov cr0, eax
 	leave
 	ret
@@ -1,4 +1,26 @@
-use crate::memory::tools::NbrPages;
+use crate::memory::tools::{virtual_offset, NbrPages};
+
+/// The mods_count and mods_addr fields of the multiboot info are valid when this flag is set
+const MULTIBOOT_MODS_FLAG: u32 = 1 << 3;
+
+/// A file loaded in memory by the bootloader along with the kernel (ex: an initramfs)
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct MultibootModule {
+    /// physical address of the first byte of the module
+    pub mod_start: u32,
+    /// physical address following the last byte of the module
+    pub mod_end: u32,
+    /// physical address of the command line of the module
+    pub string: u32,
+    reserved: u32,
+}
+
+impl MultibootModule {
+    pub fn len(&self) -> usize {
+        (self.mod_end - self.mod_start) as usize
+    }
+}
 
 #[derive(Debug, Copy, Clone)]
 #[repr(packed)]
@@ -63,4 +85,18 @@ impl MultibootInfo {
     pub fn get_system_starting_addr(&self) -> usize {
         (self.mem_lower as usize + 1024) * 1024
     }
+
+    /// Get the modules loaded by the bootloader. start.asm copied their list
+    /// in the kernel image, which is mapped at virtual_offset
+    pub fn get_modules(&self) -> &'static [MultibootModule] {
+        if self.flags & MULTIBOOT_MODS_FLAG == 0 {
+            return &[];
+        }
+        unsafe {
+            core::slice::from_raw_parts(
+                (self.mods_addr as usize + symbol_addr!(virtual_offset)) as *const MultibootModule,
+                self.mods_count as usize,
+            )
+        }
+    }
 }
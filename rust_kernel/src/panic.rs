@@ -49,6 +49,12 @@ struct Symbol {
     name: c_str,
 }
 
+/// Get the kernel symbol containing `eip`: its name and the offset of eip in it
+pub fn get_symbol(eip: u32) -> (c_str, u32) {
+    let symbol = unsafe { _get_symbol(eip) };
+    (symbol.name, symbol.offset)
+}
+
 /// Get eip from ebp
 // return tupple of (eip, ebp)
 fn get_eip(ebp: *const u32) -> (u32, *const u32) {
@@ -150,6 +156,8 @@ pub fn panic_sa_mere(info: &PanicInfo) {
         asm!("mov eax, ebp" : "={eax}"(ebp) : : : "intel");
         trace_back((*ebp.add(1), *ebp as *const u32));
     };
+    #[cfg(feature = "memory-debug")]
+    crate::memory::print_leak_report();
 }
 
 #[cfg(not(test))]
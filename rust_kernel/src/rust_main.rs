@@ -1,5 +1,7 @@
 use crate::memory::tools::DeviceMap;
 use crate::multiboot::MultibootInfo;
+#[cfg(not(feature = "test"))]
+use alloc::{format, vec};
 
 #[cfg(not(feature = "test"))]
 #[no_mangle]
@@ -8,22 +10,36 @@ pub extern "C" fn kmain(
     device_map_ptr: *const DeviceMap,
 ) -> ! {
     init_kernel(multiboot_info, device_map_ptr);
+
+    #[cfg(feature = "with-login")]
+    let mut envp = vec![];
+    #[cfg(not(feature = "with-login"))]
+    let mut envp = vec!["HOME=/root", "SHELL=/bin/sh"];
+
+    // The session manager also opens a session on the console chosen by the command line
+    let console = crate::cmdline::get_option("console").map(|tty| format!("CONSOLE=/dev/{}", tty));
+    if let Some(console) = console.as_ref() {
+        envp.push(console.as_str());
+    }
+
+    crate::taskmaster::init();
+
+    // The init of an initramfs is /init, unless the command line gives another one.
+    // The initramfs is not present anymore if it could not be unpacked
+    let init = if crate::initramfs::is_present() {
+        crate::cmdline::get_option("rdinit").unwrap_or("/init")
+    } else {
+        "/bin/init"
+    };
+
     #[cfg(feature = "with-login")]
-    crate::taskmaster::start(
-        "/bin/init",
-        &["/bin/init", "/bin/session_manager", "/bin/login"],
-        &[],
-    );
+    crate::taskmaster::start(init, &[init, "/bin/session_manager", "/bin/login"], &envp);
     #[cfg(not(feature = "with-login"))]
-    crate::taskmaster::start(
-        "/bin/init",
-        &["/bin/init", "/bin/session_manager", "-"],
-        &["HOME=/root", "SHELL=/bin/sh"],
-    );
+    crate::taskmaster::start(init, &[init, "/bin/session_manager", "-"], &envp);
 }
 
 use crate::drivers::pit_8253::OperatingMode;
-use crate::drivers::{Acpi, ACPI, PCI, PIC_8259, PIT0};
+use crate::drivers::{Acpi, Apic, TickSource, ACPI, APIC, PCI, PIC_8259, PIT0};
 use crate::memory::init_memory_system;
 use crate::memory::tools::device_map::get_device_map_slice;
 use crate::system::init_idt;
@@ -56,6 +72,9 @@ pub fn init_kernel(multiboot_info: *const MultibootInfo, device_map_ptr: *const
      */
     init_terminal();
 
+    crate::cmdline::init(&multiboot_info);
+    crate::initramfs::init(&multiboot_info);
+
     /*
      * Initialize Pic8259 and base PIT0 drivers
      */
@@ -68,6 +87,9 @@ pub fn init_kernel(multiboot_info: *const MultibootInfo, device_map_ptr: *const
 
         PIC_8259.lock().enable_irq(irq::Irq::SystemTimer, None);
 
+        // Enable the receive path of the serial tty
+        crate::drivers::serial::init();
+
         watch_dog();
         interrupts::enable();
     }
@@ -83,6 +105,39 @@ pub fn init_kernel(multiboot_info: *const MultibootInfo, device_map_ptr: *const
         Err(e) => log::error!("Cannot initialize ACPI: {:?}", e),
     };
 
+    /*
+     * Route the IRQs through the APICs, the 8259 stays when there is no MADT
+     */
+    let madt = ACPI.lock().and_then(|acpi| acpi.get_madt());
+    match madt {
+        Some(_) if crate::cmdline::get_option("noapic").is_some() => {
+            log::info!("APIC disabled by the command line")
+        }
+        Some(madt) => {
+            let tick_source = match crate::cmdline::get_option("clocksource") {
+                Some("pit") => TickSource::Pit,
+                _ => TickSource::LocalApicTimer,
+            };
+            match unsafe { Apic::init(&madt, tick_source) } {
+                Ok(()) => log::info!("APIC initialized, scheduler tick: {:?}", tick_source),
+                Err(e) => log::error!("Cannot initialize APIC: {:?}", e),
+            }
+        }
+        None => log::info!("No MADT, the IRQs are routed by the 8259"),
+    }
+
+    /*
+     * Start the application processors, they are reached through the local APIC
+     */
+    let local_apic = APIC.lock().as_ref().map(|apic| apic.get_local_apic());
+    match (madt, local_apic) {
+        (Some(_), Some(_)) if crate::cmdline::get_option("nosmp").is_some() => {
+            log::info!("SMP disabled by the command line")
+        }
+        (Some(madt), Some(local_apic)) => unsafe { crate::smp::init(&madt, local_apic) },
+        _ => log::info!("SMP: only the bootstrap processor runs"),
+    }
+
     /*
      * Initialize PCI driver
      */
@@ -0,0 +1,373 @@
+//! This file contains the bring-up of the application processors described by the MADT.
+//! See [SMP](https://wiki.osdev.org/Symmetric_Multiprocessing).
+//!
+//! Each application processor is started by INIT and startup interrupts on the trampoline
+//! of ap_trampoline.asm, then takes its own GDT, TSS and kernel stack, shares the IDT of the
+//! bootstrap processor and runs its idle task: it executes the work queued on its run queue
+//! by `call_on`, and halts until the next inter-processor interrupt.
+//!
+//! The scheduler queues the work which does not need a thread on the run queues of the
+//! application processors, as the freeing of the memory of the dead processes. The user
+//! threads stay scheduled on the bootstrap processor, which is also the only one to receive
+//! the IRQs and the scheduler tick: the preemption state and the time slice of the scheduler
+//! are still global.
+
+mod tlb_shootdown;
+pub use tlb_shootdown::tlb_shootdown;
+
+use crate::drivers::acpi::{Madt, MADT_MAX_PROCESSORS};
+use crate::drivers::apic::{set_idt_gate, LocalApic, SPURIOUS_VECTOR};
+use crate::memory::mmu::_read_cr3;
+use crate::memory::tools::Phys;
+use crate::taskmaster::TssBlock;
+use crate::Spinlock;
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::mem::size_of;
+use core::sync::atomic::{fence, AtomicU32, Ordering};
+use fallible_collections::{try_vec, FallibleBox};
+use lazy_static::lazy_static;
+
+/// The maximum number of processors, the bootstrap processor is the processor 0
+pub const MAX_CPUS: usize = MADT_MAX_PROCESSORS;
+
+/// Raised on the other processors when a processor changes page tables they use
+pub const TLB_SHOOTDOWN_VECTOR: u8 = 0xf0;
+
+/// Raised on a processor to wake up its idle task when work is queued on its run queue
+pub const WAKE_UP_VECTOR: u8 = 0xf1;
+
+/// The trampoline is copied at the start of this page, under 1MB (cf ap_trampoline.asm)
+const TRAMPOLINE_PAGE: u8 = 0x6;
+const TRAMPOLINE_ADDRESS: usize = (TRAMPOLINE_PAGE as usize) << 12;
+
+/// The GDT of the bootstrap processor is built by the bootstrap code at this address
+const BSP_GDT_ADDRESS: usize = 0x800;
+
+/// The GDT entries used by the kernel: the segments, the TSS and the TLS
+const GDT_ENTRIES: usize = 9;
+
+/// The GDT entry of the TSS
+const TSS_ENTRY: usize = 7;
+
+/// Present, ring 3, 32 bits available TSS, as the one of the bootstrap code
+const TSS_ACCESS_BYTE: u64 = 0xe9;
+
+/// The segment selectors of the kernel
+const KERNEL_DATA_SELECTOR: u16 = 0x10;
+const KERNEL_STACK_SELECTOR: u16 = 0x18;
+const TSS_SELECTOR: u16 = (TSS_ENTRY << 3) as u16;
+
+/// The size of the kernel stack of an application processor
+const AP_STACK_SIZE: usize = 64 * 1024;
+
+/// The ticks waited after an INIT, after a startup, and for the processor to come online
+const INIT_DELAY_TICKS: u32 = 1;
+const STARTUP_DELAY_TICKS: u32 = 1;
+const ONLINE_TIMEOUT_TICKS: u32 = 100;
+
+extern "C" {
+    fn _sleep(next_tic: u32) -> ();
+
+    fn _isr_ipi_tlb_shootdown();
+    fn _isr_ipi_wake_up();
+
+    fn _ap_trampoline();
+    fn _ap_trampoline_end();
+    static mut _ap_trampoline_cr3: u32;
+    static mut _ap_trampoline_cr4: u32;
+    static mut _ap_trampoline_stack: u32;
+    static mut _ap_trampoline_entry: u32;
+    static mut _ap_trampoline_cpu: u32;
+}
+
+/// The operand of lgdt and lidt
+#[derive(Copy, Clone)]
+#[repr(C, packed)]
+struct DescriptorRegister {
+    limit: u16,
+    base: u32,
+}
+
+/// The GDTs of the application processors, the bootstrap processor keeps its own one
+static mut GDTS: [[u64; GDT_ENTRIES]; MAX_CPUS] = [[0; GDT_ENTRIES]; MAX_CPUS];
+
+/// The local APIC IDs of the processors
+static mut APIC_IDS: [u8; MAX_CPUS] = [0; MAX_CPUS];
+
+/// The IDT of the bootstrap processor, shared by all the processors
+static mut IDT_REGISTER: DescriptorRegister = DescriptorRegister { limit: 0, base: 0 };
+
+/// The local APIC of the current processor: it has the same address on all of them
+static mut LOCAL_APIC: Option<LocalApic> = None;
+
+/// The physical address of the page directory loaded by each processor
+static mut ACTIVE_PAGE_DIRECTORIES: [usize; MAX_CPUS] = [0; MAX_CPUS];
+
+/// The bitmap of the processors which are online
+static ONLINE_CPUS: AtomicU32 = AtomicU32::new(1);
+
+/// A work item executed by the idle task of an application processor
+type Work = Box<dyn FnOnce() + Send>;
+
+lazy_static! {
+    /// The run queues of the processors
+    static ref RUN_QUEUES: Vec<Spinlock<VecDeque<Work>>> =
+        (0..MAX_CPUS).map(|_| Spinlock::new(VecDeque::new())).collect();
+}
+
+/// Basics SMP errors
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SmpError {
+    OutOfMemory,
+    Timeout,
+    CpuOffline,
+}
+
+/// Start the application processors described by `madt`, `local_apic` is the local APIC
+/// of the bootstrap processor. The interrupts must be enabled, as it sleeps between the
+/// interrupts sent to the processors
+pub unsafe fn init(madt: &Madt, local_apic: LocalApic) {
+    LOCAL_APIC = Some(local_apic);
+    APIC_IDS[0] = local_apic.id();
+    sync::set_cpu_hooks(cpu_id, relax);
+
+    set_idt_gate(TLB_SHOOTDOWN_VECTOR, _isr_ipi_tlb_shootdown);
+    set_idt_gate(WAKE_UP_VECTOR, _isr_ipi_wake_up);
+    lazy_static::initialize(&RUN_QUEUES);
+    asm!("sidt ($0)" : : "r"(&mut IDT_REGISTER) : "memory" : "volatile");
+
+    let trampoline_len = _ap_trampoline_end as usize - _ap_trampoline as usize;
+    (TRAMPOLINE_ADDRESS as *mut u8).copy_from(_ap_trampoline as *const u8, trampoline_len);
+    let cr4: u32;
+    asm!("mov %cr4, $0" : "=r"(cr4) : : : "volatile");
+    set_trampoline_parameter(&_ap_trampoline_cr3, _read_cr3().0 as u32);
+    set_trampoline_parameter(&_ap_trampoline_cr4, cr4);
+    set_trampoline_parameter(&_ap_trampoline_entry, ap_main as u32);
+
+    let mut cpu = 1;
+    for processor in madt
+        .processors()
+        .iter()
+        .filter(|processor| processor.apic_id != APIC_IDS[0])
+    {
+        if cpu == MAX_CPUS {
+            log::warn!("SMP: only {} processors are supported", MAX_CPUS);
+            break;
+        }
+        match start_cpu(cpu, processor.apic_id) {
+            Ok(()) => {
+                log::info!("SMP: processor {} (APIC {}) online", cpu, processor.apic_id);
+                cpu += 1;
+            }
+            Err(e) => log::error!(
+                "SMP: cannot start the processor of APIC {}: {:?}",
+                processor.apic_id,
+                e
+            ),
+        }
+    }
+    log::info!("SMP: {} processor(s) online", nbr_cpus_online());
+}
+
+/// Write a parameter of the trampoline copy
+unsafe fn set_trampoline_parameter(parameter: &u32, value: u32) {
+    let offset = parameter as *const u32 as usize - _ap_trampoline as usize;
+    ((TRAMPOLINE_ADDRESS + offset) as *mut u32).write_volatile(value);
+}
+
+/// Start the processor of the local APIC `apic_id` as the processor `cpu`
+unsafe fn start_cpu(cpu: usize, apic_id: u8) -> Result<(), SmpError> {
+    let stack = try_vec![0u8; AP_STACK_SIZE]
+        .map_err(|_| SmpError::OutOfMemory)?
+        .into_boxed_slice();
+    let stack_top = (stack.as_ptr() as usize + AP_STACK_SIZE) & !0xf;
+
+    // The TSS is used when an interrupt comes from ring 3
+    let tss = Box::try_new(TssBlock::new(stack_top as u32, KERNEL_STACK_SELECTOR))
+        .map_err(|_| SmpError::OutOfMemory)?;
+    // The processor keeps them until the shutdown
+    Box::leak(stack);
+    let tss = Box::leak(tss);
+    let gdt = &mut GDTS[cpu];
+    gdt.copy_from_slice(core::slice::from_raw_parts(
+        BSP_GDT_ADDRESS as *const u64,
+        GDT_ENTRIES,
+    ));
+    gdt[TSS_ENTRY] = tss_descriptor(tss as *const TssBlock as u32, size_of::<TssBlock>() as u32);
+    APIC_IDS[cpu] = apic_id;
+
+    set_trampoline_parameter(&_ap_trampoline_stack, stack_top as u32);
+    set_trampoline_parameter(&_ap_trampoline_cpu, cpu as u32);
+
+    let mut local_apic = LOCAL_APIC.expect("no local APIC");
+    local_apic.send_init(apic_id);
+    _sleep(INIT_DELAY_TICKS);
+    // A second startup is sent if the first one is lost
+    for _ in 0..2 {
+        local_apic.send_startup(apic_id, TRAMPOLINE_PAGE);
+        if wait_online(cpu, STARTUP_DELAY_TICKS) {
+            return Ok(());
+        }
+    }
+    if wait_online(cpu, ONLINE_TIMEOUT_TICKS) {
+        Ok(())
+    } else {
+        Err(SmpError::Timeout)
+    }
+}
+
+/// Wait `ticks` for the processor `cpu` to come online
+unsafe fn wait_online(cpu: usize, ticks: u32) -> bool {
+    for _ in 0..ticks {
+        if is_online(cpu) {
+            return true;
+        }
+        _sleep(1);
+    }
+    is_online(cpu)
+}
+
+/// Create the GDT entry of a TSS
+fn tss_descriptor(base: u32, limit: u32) -> u64 {
+    let (base, limit) = (base as u64, limit as u64);
+    let low = (base & 0xffff) << 16 | (limit & 0xffff);
+    let high =
+        (base & 0xff00_0000) | (base >> 16) & 0xff | (limit & 0xf_0000) | TSS_ACCESS_BYTE << 8;
+    high << 32 | low
+}
+
+/// The entry point of the application processors, called by the trampoline
+extern "C" fn ap_main(cpu: usize) -> ! {
+    unsafe {
+        // The locks need the GDT of the processor to know which one takes them
+        load_descriptor_tables(cpu);
+        set_active_page_directory(_read_cr3());
+        LOCAL_APIC.expect("no local APIC").enable(SPURIOUS_VECTOR);
+    }
+    ONLINE_CPUS.fetch_or(1 << cpu, Ordering::SeqCst);
+    idle(cpu)
+}
+
+/// Load the GDT and the TSS of the processor `cpu`, and the shared IDT
+unsafe fn load_descriptor_tables(cpu: usize) {
+    let gdt_register = DescriptorRegister {
+        limit: (GDT_ENTRIES * size_of::<u64>() - 1) as u16,
+        base: GDTS[cpu].as_ptr() as u32,
+    };
+    asm!("lgdt ($0)" : : "r"(&gdt_register) : "memory" : "volatile");
+    asm!("mov $0, %ds
+          mov $0, %es
+          mov $0, %fs
+          mov $0, %gs
+          mov $1, %ss
+          ljmp $$0x8, $$1f
+          1:"
+         : : "r"(KERNEL_DATA_SELECTOR), "r"(KERNEL_STACK_SELECTOR) : "memory" : "volatile");
+    asm!("ltr $0" : : "r"(TSS_SELECTOR) : : "volatile");
+    asm!("lidt ($0)" : : "r"(&IDT_REGISTER) : "memory" : "volatile");
+}
+
+/// The idle task of an application processor: it runs the work queued on its run queue,
+/// and halts until the next interrupt when the queue is empty
+fn idle(cpu: usize) -> ! {
+    loop {
+        unsafe {
+            interrupts::disable();
+        }
+        let work = RUN_QUEUES[cpu].lock().pop_front();
+        match work {
+            Some(work) => {
+                unsafe {
+                    interrupts::enable();
+                }
+                work();
+            }
+            // sti takes effect after hlt: a wake up cannot be lost between them
+            None => unsafe { asm!("sti; hlt" : : : : "volatile") },
+        }
+    }
+}
+
+/// Queue `work` on the run queue of the application processor `cpu`. On failure, the work
+/// is dropped without being run
+pub fn call_on<F>(cpu: usize, work: F) -> Result<(), SmpError>
+where
+    F: FnOnce() + Send + 'static,
+{
+    // The bootstrap processor runs the scheduler instead of an idle task
+    if cpu == 0 || !is_online(cpu) {
+        return Err(SmpError::CpuOffline);
+    }
+    let work: Work = Box::try_new(work).map_err(|_| SmpError::OutOfMemory)?;
+    {
+        let mut run_queue = RUN_QUEUES[cpu].lock();
+        run_queue.try_reserve(1).map_err(|_| SmpError::OutOfMemory)?;
+        run_queue.push_back(work);
+    }
+    if cpu != cpu_id() {
+        without_interrupts!({
+            unsafe {
+                LOCAL_APIC
+                    .expect("no local APIC")
+                    .send_ipi(APIC_IDS[cpu], WAKE_UP_VECTOR);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Get the first application processor online after the processor `cpu`, in a round-robin
+pub fn next_application_processor(cpu: usize) -> Option<usize> {
+    (1..MAX_CPUS)
+        .map(|offset| (cpu + offset - 1) % (MAX_CPUS - 1) + 1)
+        .find(|&ap| is_online(ap))
+}
+
+/// Get the index of the current processor, from the address of its GDT
+pub fn cpu_id() -> usize {
+    let mut gdt_register = DescriptorRegister { limit: 0, base: 0 };
+    unsafe {
+        asm!("sgdt ($0)" : : "r"(&mut gdt_register) : "memory" : "volatile");
+        match gdt_register.base as usize {
+            BSP_GDT_ADDRESS => 0,
+            base => (base - GDTS.as_ptr() as usize) / size_of::<[u64; GDT_ENTRIES]>(),
+        }
+    }
+}
+
+/// Check if the processor `cpu` is online
+pub fn is_online(cpu: usize) -> bool {
+    cpu < MAX_CPUS && ONLINE_CPUS.load(Ordering::SeqCst) & 1 << cpu != 0
+}
+
+/// Get the bitmap of the processors which are online
+fn online_cpus() -> u32 {
+    ONLINE_CPUS.load(Ordering::SeqCst)
+}
+
+/// Get the number of processors which are online
+pub fn nbr_cpus_online() -> usize {
+    online_cpus().count_ones() as usize
+}
+
+/// Record the page directory `phys_pd` as the one of the current processor. It must be
+/// called before loading it: a shootdown sent once it is loaded reaches the processor
+pub fn set_active_page_directory(phys_pd: Phys) {
+    unsafe {
+        (&mut ACTIVE_PAGE_DIRECTORIES[cpu_id()] as *mut usize).write_volatile(phys_pd.0);
+    }
+    fence(Ordering::SeqCst);
+}
+
+/// Get the physical address of the page directory loaded by the processor `cpu`
+fn active_page_directory(cpu: usize) -> usize {
+    unsafe { (&ACTIVE_PAGE_DIRECTORIES[cpu] as *const usize).read_volatile() }
+}
+
+/// Called by the spin loops of the locks: a processor which waits for a lock with the
+/// interrupts disabled must still answer the TLB shootdowns
+fn relax() {
+    tlb_shootdown::handle_pending();
+}
@@ -0,0 +1,106 @@
+[BITS 32]
+
+;; This file contains the code started by the application processors, see smp.rs
+;; See https://wiki.osdev.org/Symmetric_Multiprocessing#AP_startup
+
+	; An application processor starts in real mode at the address given by the startup
+	; interrupt, which must be the start of a page under 1MB. This code is copied there
+	; by the bootstrap processor, with the parameters of the application processor, then:
+	;     Load a temporary flat GDT and enter protected mode.
+	;     Load the control registers of the bootstrap processor, which enables paging.
+	;     Take the kernel stack of the application processor.
+	;     Call the entry function with the index of the processor.
+	; The entry function loads the GDT and the IDT of the processor and never returns.
+
+; The address of the copy must match the startup page given by smp.rs
+%define BASE_LOCATION       0x6000
+%define REBASE(x)           (BASE_LOCATION + x - _ap_trampoline)
+
+%define CODE_SELECTOR       0x08
+%define DATA_SELECTOR       0x10
+
+; Protection enable, write protect and paging bits of CR0
+%define CR0_PROTECTED_PAGING 0x80010001
+
+segment .text
+
+global _ap_trampoline
+global _ap_trampoline_end
+
+; The parameters filled by the bootstrap processor before each startup
+global _ap_trampoline_cr3
+global _ap_trampoline_cr4
+global _ap_trampoline_stack
+global _ap_trampoline_entry
+global _ap_trampoline_cpu
+
+	; -------------------------------------------------
+	; *** This part is copied in BASE_LOCATION area ***
+	; -------------------------------------------------
+[BITS 16]
+_ap_trampoline:
+	cli
+	cld
+
+	xor ax, ax
+	mov ds, ax
+
+	lgdt [REBASE(gdt_flat_ptr)]
+
+	mov eax, cr0
+	or eax, 1
+	mov cr0, eax
+
+	jmp dword CODE_SELECTOR:REBASE(.protected_mode)
+
+[BITS 32]
+.protected_mode:
+	mov ax, DATA_SELECTOR
+	mov ds, ax
+	mov es, ax
+	mov fs, ax
+	mov gs, ax
+	mov ss, ax
+
+	; Take the paging configuration of the bootstrap processor
+	mov eax, [REBASE(_ap_trampoline_cr4)]
+	mov cr4, eax
+	mov eax, [REBASE(_ap_trampoline_cr3)]
+	mov cr3, eax
+	mov eax, cr0
+	or eax, CR0_PROTECTED_PAGING
+	mov cr0, eax
+
+	mov esp, [REBASE(_ap_trampoline_stack)]
+	xor ebp, ebp
+
+	; fn ap_main(cpu: usize) -> !
+	push dword [REBASE(_ap_trampoline_cpu)]
+	mov eax, [REBASE(_ap_trampoline_entry)]
+	call eax
+
+.halt:
+	cli
+	hlt
+	jmp .halt
+
+align 8
+gdt_flat:
+	dq 0
+	; Code segment: base 0, limit 4GB, ring 0, 32 bits
+	dq 0x00cf9a000000ffff
+	; Data segment: base 0, limit 4GB, ring 0, 32 bits
+	dq 0x00cf92000000ffff
+gdt_flat_end:
+
+gdt_flat_ptr:
+	dw gdt_flat_end - gdt_flat - 1
+	dd REBASE(gdt_flat)
+
+align 4
+_ap_trampoline_cr3:   dd 0
+_ap_trampoline_cr4:   dd 0
+_ap_trampoline_stack: dd 0
+_ap_trampoline_entry: dd 0
+_ap_trampoline_cpu:   dd 0
+_ap_trampoline_end:
@@ -0,0 +1,97 @@
+//! The processors cache the page tables in their TLB: when a processor changes a mapping,
+//! it invalidates its own entry, then asks the other processors to invalidate theirs with
+//! an inter-processor interrupt, and waits for their acknowledgement.
+//! See https://wiki.osdev.org/TLB
+
+use super::{active_page_directory, cpu_id, online_cpus};
+use super::{APIC_IDS, LOCAL_APIC, MAX_CPUS, TLB_SHOOTDOWN_VECTOR};
+use crate::memory::mmu::_read_cr3;
+use crate::memory::tools::{NbrPages, Page, Virt};
+use crate::Spinlock;
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use lazy_static::lazy_static;
+
+extern "C" {
+    fn _invlpg_range(addr: Virt, nbr_pages: NbrPages);
+}
+
+/// The first page of the current shootdown
+static SHOOTDOWN_PAGE: AtomicUsize = AtomicUsize::new(0);
+
+/// The number of pages of the current shootdown
+static SHOOTDOWN_NBR_PAGES: AtomicUsize = AtomicUsize::new(0);
+
+/// The bitmap of the processors which did not invalidate the current shootdown yet
+static PENDING_CPUS: AtomicU32 = AtomicU32::new(0);
+
+lazy_static! {
+    /// Only one shootdown is sent at a time
+    static ref SHOOTDOWN: Spinlock<()> = Spinlock::new(());
+}
+
+/// Invalidate `nbr_pages` from `page` of the current page directory on the other processors,
+/// once the current one has invalidated them. The kernel space is shared by all the page
+/// directories, so its changes are sent to all the processors, the changes of the user space
+/// only to the processors which use the same page directory
+pub fn tlb_shootdown(page: Page<Virt>, nbr_pages: NbrPages) {
+    let cpu = cpu_id();
+    let others = online_cpus() & !(1 << cpu);
+    if others == 0 {
+        return;
+    }
+    let end = page.to_addr().0.saturating_add(nbr_pages.to_bytes());
+    let targets = if end > NbrPages::_3GB.to_bytes() {
+        others
+    } else {
+        let page_directory = unsafe { _read_cr3().0 };
+        (0..MAX_CPUS)
+            .filter(|&target| {
+                others & 1 << target != 0 && active_page_directory(target) == page_directory
+            })
+            .fold(0, |targets, target| targets | 1 << target)
+    };
+    if targets == 0 {
+        return;
+    }
+    without_interrupts!({
+        let _shootdown = SHOOTDOWN.lock();
+        SHOOTDOWN_PAGE.store(page.to_addr().0, Ordering::SeqCst);
+        SHOOTDOWN_NBR_PAGES.store(nbr_pages.0, Ordering::SeqCst);
+        PENDING_CPUS.store(targets, Ordering::SeqCst);
+        unsafe {
+            let mut local_apic = LOCAL_APIC.expect("no local APIC");
+            if targets == others {
+                local_apic.broadcast_ipi(TLB_SHOOTDOWN_VECTOR);
+            } else {
+                for target in (0..MAX_CPUS).filter(|&target| targets & 1 << target != 0) {
+                    local_apic.send_ipi(APIC_IDS[target], TLB_SHOOTDOWN_VECTOR);
+                }
+            }
+        }
+        while PENDING_CPUS.load(Ordering::SeqCst) != 0 {
+            core::sync::atomic::spin_loop_hint();
+        }
+    });
+}
+
+/// Invalidate the current shootdown if it is pending on the current processor. This is
+/// called by the interrupt and by the spin loops of the locks, so a processor waiting for
+/// a lock held by the sender does not block it
+pub fn handle_pending() {
+    let cpu_bit = 1 << cpu_id();
+    if PENDING_CPUS.load(Ordering::SeqCst) & cpu_bit != 0 {
+        unsafe {
+            _invlpg_range(
+                Virt(SHOOTDOWN_PAGE.load(Ordering::SeqCst)),
+                NbrPages(SHOOTDOWN_NBR_PAGES.load(Ordering::SeqCst)),
+            );
+        }
+        PENDING_CPUS.fetch_and(!cpu_bit, Ordering::SeqCst);
+    }
+}
+
+/// Called by the TLB shootdown interrupt service routine
+#[no_mangle]
+extern "C" fn tlb_shootdown_interrupt_handler() {
+    handle_pending();
+}
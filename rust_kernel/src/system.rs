@@ -33,10 +33,13 @@ extern "C" {
 
 #[no_mangle]
 pub unsafe fn real_mode_op(reg: *mut BaseRegisters, bios_int: u16) -> u16 {
-    use crate::drivers::{pic_8259, Pic8259, PIC_8259};
+    use crate::drivers::{pic_8259, Pic8259, APIC, PIC_8259};
 
     without_interrupts!({
         let ret;
+        // The BIOS is called through the 8259, the IO-APICs must be quiet
+        let mut apic = APIC.lock();
+        let apic_masks = apic.as_mut().map(|apic| apic.suspend());
         // check if PIC is initialized
         let mut pic_8259 = PIC_8259.lock();
         match pic_8259.is_initialized() {
@@ -54,6 +57,9 @@ pub unsafe fn real_mode_op(reg: *mut BaseRegisters, bios_int: u16) -> u16 {
                 pic_8259.set_masks(imrs);
             }
         }
+        if let (Some(apic), Some(masks)) = (apic.as_mut(), apic_masks) {
+            apic.resume(masks);
+        }
         ret
     })
 }
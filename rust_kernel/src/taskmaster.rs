@@ -51,8 +51,20 @@ impl<T> IpcResult<T> {
     }
 }
 
+impl IpcResult<PollEvents> {
+    /// Result of a poll request: the thread should wait on `file_op_uid` when no event is ready
+    pub fn poll(revents: PollEvents, file_op_uid: usize) -> Self {
+        if revents.is_empty() {
+            IpcResult::Wait(revents, file_op_uid)
+        } else {
+            IpcResult::Done(revents)
+        }
+    }
+}
+
 pub use process::{
-    get_file_content, KernelProcess, Process, ProcessArguments, ProcessOrigin, UserProcess,
+    get_file_content, KernelProcess, Process, ProcessArguments, ProcessOrigin, TssBlock,
+    UserProcess,
 };
 pub use safe_ffi::{CString, CStringArray};
 
@@ -61,7 +73,7 @@ use scheduler::SCHEDULER;
 #[allow(unused)]
 use tests::*;
 
-use libc_binding::Errno;
+use libc_binding::{Errno, PollEvents};
 
 /// SysResult is just made to handle module errors. Return optional return and errno
 pub type SysResult<T> = core::result::Result<T, Errno>;
@@ -87,9 +99,8 @@ pub enum TaskMode {
     Multi(f32),
 }
 
-// Create an ASM dummy process based on a simple function
-/// Main function of taskMaster Initialisation
-pub fn start(filename: &str, argv: &[&str], envp: &[&str]) -> ! {
+/// Initialize taskMaster: its interrupts, its syscalls and the VFS
+pub fn init() {
     // Reassign all cpu exceptions for taskmaster
     unsafe {
         cpu_isr::reassign_cpu_exceptions();
@@ -108,7 +119,11 @@ pub fn start(filename: &str, argv: &[&str], envp: &[&str]) -> ! {
 
     // Initialize VFS
     lazy_static::initialize(&VFS);
+}
 
+// Create an ASM dummy process based on a simple function
+/// Main function of taskMaster, must be called after init()
+pub fn start(filename: &str, argv: &[&str], envp: &[&str]) -> ! {
     // Register the first process
     let path = filename
         .try_into()
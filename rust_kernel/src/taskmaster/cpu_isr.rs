@@ -2,14 +2,16 @@
 
 use super::global_time::{TimeSession, GLOBAL_TIME};
 use super::process::CpuState;
-use super::scheduler::{Scheduler, SCHEDULER};
-use super::syscall::sys_kill;
-use libc_binding::Signum;
+use super::scheduler::{trace_pending_signals, Scheduler, SCHEDULER};
+use super::signal_interface::siginfo;
+use libc_binding::{siginfo_t, Signum};
+use libc_binding::{SEGV_ACCERR, SEGV_MAPERR, SI_KERNEL, TRAP_BRKPT, TRAP_TRACE};
 
 use core::ffi::c_void;
 use elf_loader::SymbolTable;
 use interrupts::idt::{GateType, IdtGateEntry, InterruptTable};
 
+use crate::memory::mmu::PageDirectory;
 use crate::memory::{AddressSpace, KERNEL_VIRTUAL_PAGE_ALLOCATOR};
 use crate::panic::{get_page_fault_origin, qemu_check, trace_back};
 
@@ -147,6 +149,12 @@ const CPU_EXCEPTIONS: [(unsafe extern "C" fn() -> !, &str, GateType); 32] = [
     (reserved_exception, "reserved", GateType::InterruptGate32),
 ];
 
+/// The exception raised after each instruction when the trap flag is set (cf PTRACE_SINGLESTEP)
+const DEBUG_VECTOR: u32 = 1;
+
+/// The exception raised by the `int3` instruction, the breakpoints of the debuggers
+const BREAKPOINT_VECTOR: u32 = 3;
+
 /// Set the CPU exceptions vectors on the first 32 entries.
 /// # Panics
 /// Panics if the interruptions are not disabled when this is called, that is, if interrupts::get_interrupts_state() == true.
@@ -160,9 +168,16 @@ pub unsafe fn reassign_cpu_exceptions() {
         .set_gate_type(GateType::InterruptGate32);
 
     for (index, &(exception, _, gate_type)) in CPU_EXCEPTIONS.iter().enumerate() {
+        // The breakpoint instruction `int3` is usable by the debuggers in ring 3
+        let privilege_level = if index == BREAKPOINT_VECTOR as usize {
+            3
+        } else {
+            0
+        };
         gate_entry
             .set_handler(exception as *const c_void as u32)
-            .set_gate_type(gate_type);
+            .set_gate_type(gate_type)
+            .set_privilege_level(privilege_level);
 
         interrupt_table[index] = gate_entry;
     }
@@ -224,6 +239,9 @@ unsafe extern "C" fn cpu_isr_interrupt_handler(cpu_state: *mut CpuState) -> u32
             // Kernel valloc case
             if let Ok(()) = virtual_page_allocator.valloc_handle_page_fault(_read_cr2()) {
                 return cpu_state as u32;
+            // The kernel wrote on a copy-on-write page of the current process
+            } else if let Ok(()) = PageDirectory::current().cow_handle_page_fault(_read_cr2()) {
+                return cpu_state as u32;
             } else {
                 let page_fault_cause = get_page_fault_origin((*cpu_state).err_code_reserved);
                 eprintln!("{}     address: {:#X?}", page_fault_cause, _read_cr2());
@@ -243,17 +261,44 @@ unsafe extern "C" fn cpu_isr_interrupt_handler(cpu_state: *mut CpuState) -> u32
             .as_mut()
             .unwrap()
             .update_global_time(TimeSession::User);
-        // Temporaly display a debug
-        let page_fault_cause = get_page_fault_origin((*cpu_state).err_code_reserved);
-        log::warn!("{}     address: {:#X?}", page_fault_cause, _read_cr2());
-        log::warn!("{:X?}", *cpu_state);
-        log::warn!(
-            "Stack informations 'ss: 0x{:X?} esp: 0x{:X?}'",
-            (*cpu_state).ss,
-            (*cpu_state).esp
-        );
-
+        // Copy-on-write case: the process wrote on a page shared since its last fork
+        // File mapping case: the process accessed a page of a mapped file which is not loaded yet
+        // Stack case: the process accessed the guard page under its main stack
+        if (*cpu_state).cpu_isr_reserved == 14
+            && (PageDirectory::current()
+                .cow_handle_page_fault(_read_cr2())
+                .is_ok()
+                || SCHEDULER
+                    .lock()
+                    .current_thread()
+                    .unwrap_process()
+                    .get_virtual_allocator()
+                    .file_mapping_handle_page_fault(_read_cr2())
+                    .is_ok()
+                || SCHEDULER
+                    .lock()
+                    .current_thread_stack_handle_page_fault(_read_cr2(), (*cpu_state).esp)
+                    .is_ok())
         {
+            GLOBAL_TIME
+                .as_mut()
+                .unwrap()
+                .update_global_time(TimeSession::System);
+            return cpu_state as u32;
+        }
+        let cpu_isr = (*cpu_state).cpu_isr_reserved;
+        // The traps of the debuggers are not faults
+        if cpu_isr != DEBUG_VECTOR && cpu_isr != BREAKPOINT_VECTOR {
+            // Temporaly display a debug
+            let page_fault_cause = get_page_fault_origin((*cpu_state).err_code_reserved);
+            log::warn!("{}     address: {:#X?}", page_fault_cause, _read_cr2());
+            log::warn!("{:X?}", *cpu_state);
+            log::warn!(
+                "Stack informations 'ss: 0x{:X?} esp: 0x{:X?}'",
+                (*cpu_state).ss,
+                (*cpu_state).esp
+            );
+
             let scheduler = SCHEDULER.lock();
 
             let thread = scheduler.current_thread();
@@ -276,19 +321,53 @@ unsafe extern "C" fn cpu_isr_interrupt_handler(cpu_state: *mut CpuState) -> u32
             }
         }
 
-        // Send a kill signum to the current process: kernel-sodo mode
-        let current_thread_pid = SCHEDULER.lock().current_task_id().0;
-        let _res = match (*cpu_state).cpu_isr_reserved {
-            0 => sys_kill(current_thread_pid as i32, Signum::SIGFPE as u32),
-            14 => sys_kill(current_thread_pid as i32, Signum::SIGSEGV as u32),
+        // Send a kill signum to the faulting thread: kernel-sodo mode
+        let info = match cpu_isr {
+            0 => siginfo_t {
+                si_addr: (*cpu_state).eip as *mut _,
+                ..siginfo(Signum::SIGFPE, SI_KERNEL as i32)
+            },
+            DEBUG_VECTOR => {
+                // One instruction was executed, the tracer resumes again the
+                // tracee with PTRACE_SINGLESTEP to execute the next one
+                (*cpu_state).eflags.set_trap_flag(false);
+                siginfo_t {
+                    si_addr: (*cpu_state).eip as *mut _,
+                    ..siginfo(Signum::SIGTRAP, TRAP_TRACE as i32)
+                }
+            }
+            BREAKPOINT_VECTOR => siginfo_t {
+                si_addr: (*cpu_state).eip as *mut _,
+                ..siginfo(Signum::SIGTRAP, TRAP_BRKPT as i32)
+            },
+            14 => {
+                // The bit 0 of the error code is set on a protection violation
+                let si_code = if (*cpu_state).err_code_reserved & 1 != 0 {
+                    SEGV_ACCERR
+                } else {
+                    SEGV_MAPERR
+                };
+                siginfo_t {
+                    si_addr: _read_cr2() as *mut _,
+                    ..siginfo(Signum::SIGSEGV, si_code as i32)
+                }
+            }
             _ => {
                 log::warn!(
                     "{}",
                     CPU_EXCEPTIONS[(*cpu_state).cpu_isr_reserved as usize].1
                 );
-                sys_kill(current_thread_pid as i32, Signum::SIGKILL as u32)
+                siginfo(Signum::SIGKILL, SI_KERNEL as i32)
             }
         };
+        let _res = SCHEDULER
+            .lock()
+            .current_thread_mut()
+            .signal
+            .generate_signal_info(info);
+
+        // A tracee stops there to report its signal to its tracer
+        trace_pending_signals(cpu_state, Scheduler::NOT_IN_BLOCKED_SYSCALL);
 
         // On ring3 process -> Mark process on signal execution state, modify CPU state, prepare a signal frame.
         // Ce sera sans doute un signal fatal. L'exit routine va etre certainement declenchee.
@@ -5,7 +5,7 @@ use super::SysResult;
 use super::scheduler::Scheduler;
 use super::vfs;
 use super::vfs::Path;
-use super::vfs::{InodeId, VFS};
+use super::vfs::{FileSystemId, InodeId, VFS};
 use super::Credentials;
 use super::IpcResult;
 
@@ -16,7 +16,7 @@ use alloc::sync::Arc;
 use fallible_collections::FallibleArc;
 use libc_binding::{
     gid_t, off_t, stat, statfs, termios, uid_t, Errno, FileType, IoctlCmd, OpenFlags, Pid,
-    ShutDownOption, Whence,
+    PollEvents, ShutDownOption, Whence,
 };
 use sync::dead_mutex::DeadMutex;
 
@@ -35,6 +35,10 @@ pub trait FileOperation: core::fmt::Debug + Send {
 
     fn set_file_offset(&mut self, _offset: u64) {}
 
+    /// Keep the mount through which the file was opened, its flags
+    /// apply to the operations on the open file
+    fn set_mount(&mut self, _mount: Option<FileSystemId>) {}
+
     fn lseek(&mut self, _offset: off_t, _whence: Whence) -> SysResult<off_t> {
         Err(Errno::EINVAL)
     }
@@ -44,11 +48,37 @@ pub trait FileOperation: core::fmt::Debug + Send {
         Err(Errno::ENOSYS)
     }
 
+    /// The delay in tenths of second after which a blocked read gives up waiting for its
+    /// file_op_uid (cf VTIME of the terminals). It is asked after read() returned IpcResult::Wait
+    fn read_timer(&self) -> Option<u32> {
+        None
+    }
+
+    /// Read again when the delay given by read_timer() elapsed without any wake up
+    fn read_timer_expired(&mut self, buf: &mut [u8]) -> SysResult<u32> {
+        match self.read(buf)? {
+            IpcResult::Done(res) | IpcResult::Wait(res, _) => Ok(res),
+        }
+    }
+
     /// Write something into the File Descriptor: Important ! When in blocked syscall, the slice must be verified before write op
     fn write(&mut self, _buf: &[u8]) -> SysResult<IpcResult<u32>> {
         Err(Errno::ENOSYS)
     }
 
+    /// Report which of the `events` are ready for poll() and select(): IpcResult::Wait gives the file_op_uid
+    /// to wait on when none of them is ready. Regular files shall always poll TRUE for reading and writing
+    fn poll(&mut self, events: PollEvents) -> SysResult<IpcResult<PollEvents>> {
+        Ok(IpcResult::Done(
+            events & (PollEvents::READ | PollEvents::WRITE),
+        ))
+    }
+
+    /// Write the modified data of the file to the disk: fsync() is not possible on a pipe, socket or character device
+    fn fsync(&mut self) -> SysResult<u32> {
+        Err(Errno::EINVAL)
+    }
+
     fn fstat(&mut self, stat: &mut stat) -> SysResult<u32> {
         let inode_id = self.get_inode_id()?;
         VFS.lock()
@@ -82,6 +112,16 @@ pub trait FileOperation: core::fmt::Debug + Send {
         Err(Errno::ENOTTY)
     }
 
+    /// Get the session of which the terminal is the controlling terminal, 0 if none
+    fn tcgetsid(&self) -> SysResult<Pid> {
+        Err(Errno::ENOTTY)
+    }
+
+    /// Make the terminal the controlling terminal of the session `sid`, or release it if `sid` is 0
+    fn tcsetsid(&mut self, _sid: Pid) -> SysResult<u32> {
+        Err(Errno::ENOTTY)
+    }
+
     fn isatty(&mut self) -> SysResult<u32> {
         Err(Errno::ENOTTY)
     }
@@ -197,6 +237,10 @@ pub trait Driver: core::fmt::Debug + Send {
     fn shutdown(&mut self, _option: ShutDownOption) -> SysResult<()> {
         Err(Errno::ENOTSOCK)
     }
+
+    fn poll(&mut self, _events: PollEvents, _whom: Whom) -> SysResult<IpcResult<PollEvents>> {
+        Err(Errno::ENOSYS)
+    }
 }
 
 #[derive(Debug)]
@@ -13,7 +13,7 @@ use alloc::sync::Arc;
 use sync::DeadMutex;
 
 use fallible_collections::arc::FallibleArc;
-use libc_binding::{Errno, OpenFlags};
+use libc_binding::{Errno, OpenFlags, PollEvents};
 
 use core::cmp;
 
@@ -119,6 +119,9 @@ impl FileOperation for FifoFileOperation {
     fn write(&mut self, buf: &[u8]) -> SysResult<IpcResult<u32>> {
         self.data.lock().write(buf)
     }
+    fn poll(&mut self, events: PollEvents) -> SysResult<IpcResult<PollEvents>> {
+        self.data.lock().poll(events)
+    }
 }
 
 /// Main Trait implementation
@@ -230,6 +233,24 @@ impl FileOperation for FifoFileOperationData {
             Ok(IpcResult::Wait(min as _, self.file_op_uid))
         }
     }
+    fn poll(&mut self, events: PollEvents) -> SysResult<IpcResult<PollEvents>> {
+        let mut revents = PollEvents::empty();
+        if self.current_index > 0 {
+            revents |= events & PollEvents::READ;
+        }
+        if self.current_index < Buf::BUF_SIZE {
+            revents |= events & PollEvents::WRITE;
+        }
+        // Writers are gone, a read returns immediatly
+        if self.output_ref == 0 {
+            revents |= PollEvents::POLLHUP;
+        }
+        // Readers are gone, a write fails with EPIPE
+        if self.input_ref == 0 {
+            revents |= PollEvents::POLLERR;
+        }
+        Ok(IpcResult::poll(revents, self.file_op_uid))
+    }
 }
 
 /// Some boilerplate to check if all is okay
@@ -8,7 +8,7 @@ use super::IpcResult;
 
 use super::get_file_op_uid;
 
-use libc_binding::{stat, Errno, OpenFlags};
+use libc_binding::{stat, Errno, OpenFlags, PollEvents};
 
 use core::cmp;
 
@@ -122,6 +122,25 @@ impl FileOperation for Pipe {
         }
     }
 
+    fn poll(&mut self, events: PollEvents) -> SysResult<IpcResult<PollEvents>> {
+        let mut revents = PollEvents::empty();
+        if self.current_index > 0 {
+            revents |= events & PollEvents::READ;
+        }
+        if self.current_index < Buf::BUF_SIZE {
+            revents |= events & PollEvents::WRITE;
+        }
+        // Writers are gone, a read returns immediatly
+        if self.output_ref == 0 {
+            revents |= PollEvents::POLLHUP;
+        }
+        // Readers are gone, a write fails with EPIPE
+        if self.input_ref == 0 {
+            revents |= PollEvents::POLLERR;
+        }
+        Ok(IpcResult::poll(revents, self.file_op_uid))
+    }
+
     fn fstat(&mut self, _stat: &mut stat) -> SysResult<u32> {
         // TODO: This is for ls | cat -e to works, because cat do a fstat(0)
         Ok(0)
@@ -14,7 +14,7 @@ use super::Path;
 use super::VFS;
 
 use alloc::sync::Arc;
-use libc_binding::{Errno, OpenFlags, ShutDownOption};
+use libc_binding::{Errno, OpenFlags, PollEvents, ShutDownOption};
 use sync::dead_mutex::DeadMutex;
 
 mod sockdgram;
@@ -120,4 +120,12 @@ impl Driver for SocketDriver {
             }
         }
     }
+
+    fn poll(&mut self, events: PollEvents, whom: Whom) -> SysResult<IpcResult<PollEvents>> {
+        use SocketDriver::*;
+        match self {
+            Connected(driver) => driver.poll(events, whom),
+            Dgram(driver) => driver.poll(events),
+        }
+    }
 }
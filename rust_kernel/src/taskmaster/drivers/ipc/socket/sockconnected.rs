@@ -16,7 +16,7 @@ use alloc::collections::VecDeque;
 use alloc::vec::Vec;
 use core::cmp;
 use fallible_collections::{FallibleBox, FallibleVec, TryClone};
-use libc_binding::{Errno, FileType, OpenFlags, ShutDownOption};
+use libc_binding::{Errno, FileType, OpenFlags, PollEvents, ShutDownOption};
 use messaging::MessageTo;
 
 #[derive(Debug)]
@@ -325,6 +325,46 @@ impl ConnectedSocketDriver {
         self.shutdown = Some(option);
         Ok(())
     }
+
+    pub(super) fn poll(
+        &mut self,
+        events: PollEvents,
+        whom: Whom,
+    ) -> SysResult<IpcResult<PollEvents>> {
+        let mut revents = PollEvents::empty();
+        // A listening socket is readable when a connection is pending
+        if let Some(listen_queue) = &self.listen_queue {
+            if !listen_queue.is_empty() {
+                revents |= events & PollEvents::READ;
+            }
+        }
+        let (incoming, outgoing) = match whom {
+            Client => (&self.messaging_to_client, &self.messaging_to_server),
+            Server => (&self.messaging_to_server, &self.messaging_to_client),
+        };
+        let readable = match incoming {
+            Streamed(StreamedMessaging { index, .. }) => *index > 0,
+            Packeted(PacketedMessaging { messages }) => !messages.is_empty(),
+        };
+        let writable = match outgoing {
+            Streamed(StreamedMessaging { index, .. }) => *index < Buf::BUF_SIZE,
+            Packeted(_) => true,
+        };
+        if readable {
+            revents |= events & PollEvents::READ;
+        }
+        if writable {
+            revents |= events & PollEvents::WRITE;
+        }
+        // A shutdown socket fails immediatly with ECONNRESET
+        match self.shutdown {
+            Some(ShutDownOption::ShutRd) => revents |= events & PollEvents::READ,
+            Some(ShutDownOption::ShutWr) => revents |= events & PollEvents::WRITE,
+            Some(ShutDownOption::ShutRdwr) => revents |= PollEvents::POLLHUP,
+            None => {}
+        }
+        Ok(IpcResult::poll(revents, self.file_op_uid))
+    }
 }
 
 /// This structure represents a FileOperation of type Socket
@@ -486,6 +526,12 @@ impl FileOperation for ConnectedSocket {
         driver.shutdown(option)
     }
 
+    fn poll(&mut self, events: PollEvents) -> SysResult<IpcResult<PollEvents>> {
+        let mut vfs = VFS.lock();
+        let driver = vfs.get_driver(self.inode_id)?;
+        driver.poll(events, self.whom)
+    }
+
     fn get_inode_id(&self) -> SysResult<InodeId> {
         Ok(self.inode_id)
     }
@@ -16,7 +16,7 @@ use alloc::collections::VecDeque;
 use alloc::vec::Vec;
 use core::cmp;
 use fallible_collections::{FallibleBox, FallibleVec, TryClone};
-use libc_binding::{Errno, FileType, OpenFlags};
+use libc_binding::{Errno, FileType, OpenFlags, PollEvents};
 use messaging::MessageTo;
 
 #[derive(Debug)]
@@ -84,6 +84,14 @@ impl SocketDgramDriver {
             None => IpcResult::Wait((0, None), self.file_op_uid),
         })
     }
+
+    pub(super) fn poll(&mut self, events: PollEvents) -> SysResult<IpcResult<PollEvents>> {
+        let mut revents = events & PollEvents::WRITE;
+        if !self.messages.is_empty() {
+            revents |= events & PollEvents::READ;
+        }
+        Ok(IpcResult::poll(revents, self.file_op_uid))
+    }
 }
 
 /// This structure represents a FileOperation of type Socket
@@ -205,6 +213,12 @@ impl FileOperation for SocketDgram {
         driver.recv_from(buf, flags, Whom::Client)
     }
 
+    fn poll(&mut self, events: PollEvents) -> SysResult<IpcResult<PollEvents>> {
+        let mut vfs = VFS.lock();
+        let driver = vfs.get_driver(self.inode_id)?;
+        driver.poll(events, Whom::Client)
+    }
+
     fn get_inode_id(&self) -> SysResult<InodeId> {
         Ok(self.inode_id)
     }
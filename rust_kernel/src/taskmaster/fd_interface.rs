@@ -1,6 +1,6 @@
 use super::drivers::FileOperation;
 use super::syscall::socket;
-use super::thread_group::Credentials;
+use super::thread_group::{Credentials, ResourceLimits};
 use super::vfs::Path;
 use super::IpcResult;
 /// The User File Descriptor are sorted into a Binary Tree
@@ -10,7 +10,7 @@ use super::VFS;
 
 use core::convert::TryFrom;
 
-use libc_binding::{Errno, FileType, OpenFlags};
+use libc_binding::{Errno, FileType, OpenFlags, PollEvents};
 
 use super::drivers::ipc::{ConnectedSocket, Pipe, SocketDgram};
 use alloc::sync::Arc;
@@ -28,20 +28,27 @@ pub type Fd = u32;
 #[derive(Debug, TryClone)]
 pub struct FileDescriptorInterface {
     user_fd_list: BTreeMap<Fd, FileDescriptor>,
+    /// One more than the greatest allowed File Descriptor number (cf RLIMIT_NOFILE)
+    max_fd: Fd,
 }
 
 /// Main implementation
 impl FileDescriptorInterface {
-    const MAX_FD: Fd = 128;
-
     /// Global constructor
     pub fn new() -> Self {
         Self {
             // New BTreeMap does not allocate memory
             user_fd_list: BTreeMap::new(),
+            max_fd: ResourceLimits::DEFAULT_NOFILE,
         }
     }
 
+    /// Set the limit on the File Descriptor numbers. The File Descriptors already
+    /// opened above it are kept
+    pub fn set_max_fd(&mut self, max_fd: Fd) {
+        self.max_fd = max_fd;
+    }
+
     /// Clear all the owned content into the File Descriptor Interface
     pub fn delete(&mut self) {
         self.user_fd_list.clear();
@@ -52,6 +59,15 @@ impl FileDescriptorInterface {
         Ok(elem.file_operation.lock())
     }
 
+    /// Get the open file of a File Descriptor and its open flags. Used to keep the file alive after a close (cf mmap)
+    pub fn get_open_file(
+        &self,
+        fd: Fd,
+    ) -> SysResult<(OpenFlags, Arc<DeadMutex<dyn FileOperation>>)> {
+        let elem = self.user_fd_list.get(&fd).ok_or::<Errno>(Errno::EBADF)?;
+        Ok((elem.flags, elem.file_operation.clone()))
+    }
+
     /// Open a file and give a file descriptor
     pub fn open(
         &mut self,
@@ -103,6 +119,23 @@ impl FileDescriptorInterface {
         res
     }
 
+    /// Get the delay in tenths of second of a blocked read, cf FileOperation::read_timer()
+    pub fn read_timer(&self, fd: Fd) -> SysResult<Option<u32>> {
+        let elem = self.user_fd_list.get(&fd).ok_or::<Errno>(Errno::EBADF)?;
+
+        Ok(elem.file_operation.lock().read_timer())
+    }
+
+    /// Read when the delay of a blocked read elapsed, cf FileOperation::read_timer_expired()
+    pub fn read_timer_expired(&mut self, fd: Fd, buf: &mut [u8]) -> SysResult<u32> {
+        let elem = self.user_fd_list.get(&fd).ok_or::<Errno>(Errno::EBADF)?;
+
+        if !elem.flags.is_open_for_read() {
+            return Err(Errno::EBADF);
+        }
+        elem.file_operation.lock().read_timer_expired(buf)
+    }
+
     /// Write something into the File Descriptor: Can block
     /// Important ! When in blocked syscall, the slice must be verified before write op and
     /// we have fo find a solution to avoid the DeadLock when multiple access to fd occured
@@ -115,6 +148,21 @@ impl FileDescriptorInterface {
         elem.file_operation.lock().write(buf)
     }
 
+    /// Get the ready events of a File Descriptor for poll() and select()
+    /// The reading and writing events are only reported if the File Descriptor is open for them
+    pub fn poll(&self, fd: Fd, events: PollEvents) -> SysResult<IpcResult<PollEvents>> {
+        let elem = self.user_fd_list.get(&fd).ok_or::<Errno>(Errno::EBADF)?;
+
+        let mut events = events;
+        if !elem.flags.is_open_for_read() {
+            events.remove(PollEvents::READ);
+        }
+        if !elem.flags.is_open_for_write() {
+            events.remove(PollEvents::WRITE);
+        }
+        elem.file_operation.lock().poll(events)
+    }
+
     /// Made two File Descriptors connected with a Pipe
     pub fn new_pipe(&mut self) -> SysResult<(Fd, Fd)> {
         let pipe = Arc::try_new(DeadMutex::new(Pipe::new()))?;
@@ -148,7 +196,7 @@ impl FileDescriptorInterface {
 
     /// Duplicate one file descriptor with possible override
     pub fn dup2(&mut self, oldfd: Fd, newfd: Fd) -> SysResult<Fd> {
-        if newfd > Self::MAX_FD {
+        if newfd >= self.max_fd {
             return Err(Errno::EBADF);
         }
 
@@ -188,7 +236,7 @@ impl FileDescriptorInterface {
                 lower_fd += 1;
             }
         }
-        if lower_fd > Self::MAX_FD {
+        if lower_fd >= self.max_fd {
             None
         } else {
             Some(lower_fd)
@@ -1,9 +1,11 @@
 //! This crate provides a toolkit to measure time
 
 use core::ops::AddAssign;
+use core::sync::atomic::Ordering;
 use core::time::Duration;
 use libc_binding::{rusage, timeval};
 
+use super::kmodules::CURRENT_UNIX_TIME;
 use crate::drivers::PIT0;
 
 /// Get the new read time stamp counter
@@ -22,7 +24,10 @@ fn get_cpu_time() -> u64 {
 /// Main Global Time structure
 pub struct GlobalTime {
     cpu_frequency: u64,
+    boot_cpu_time: u64,
     last_mesured_time: Option<u64>,
+    /// The last second given by the RTC and the monotonic time when it was seen
+    last_unix_time: Option<(u32, Duration)>,
     global_user_time: Duration,
     global_system_time: Duration,
     global_idle_time: Duration,
@@ -52,6 +57,11 @@ impl ProcessDuration {
     pub fn system_time(&self) -> Duration {
         self.system_time
     }
+
+    /// The CPU time of the process, user and system
+    pub fn cpu_time(&self) -> Duration {
+        self.user_time + self.system_time
+    }
 }
 
 /// Default boilerplate for ProcessDuration
@@ -104,7 +114,9 @@ impl GlobalTime {
         log::info!("CPU FREQUENCY DETECTED: {} mhz", cpu_frequency / 1000000);
         Self {
             cpu_frequency,
+            boot_cpu_time: old_cpu_time,
             last_mesured_time: None,
+            last_unix_time: None,
             global_user_time: Duration::default(),
             global_system_time: Duration::default(),
             global_idle_time: Duration::default(),
@@ -146,6 +158,39 @@ impl GlobalTime {
         res
     }
 
+    /// Get the Time Summary of the current process without reseting it
+    pub fn peek_process_time(&self) -> ProcessDuration {
+        ProcessDuration {
+            user_time: self.process_user_time,
+            system_time: self.process_system_time,
+        }
+    }
+
+    /// Get the time elapsed since the boot, measured with the TSC
+    pub fn monotonic_time(&self) -> Duration {
+        let cpu_time = get_cpu_time() - self.boot_cpu_time;
+        Duration::new(
+            cpu_time / self.cpu_frequency,
+            ((cpu_time % self.cpu_frequency) * 1000000000 / self.cpu_frequency) as u32,
+        )
+    }
+
+    /// Get the time since the Epoch. The RTC gives the seconds and the
+    /// TSC measures the time elapsed since the last second change
+    pub fn real_time(&mut self) -> Duration {
+        let unix_time = unsafe { CURRENT_UNIX_TIME.load(Ordering::Acquire) };
+        let now = self.monotonic_time();
+        let second_start = match self.last_unix_time {
+            Some((last_unix_time, second_start)) if last_unix_time == unix_time => second_start,
+            _ => {
+                self.last_unix_time = Some((unix_time, now));
+                now
+            }
+        };
+        Duration::from_secs(unix_time as u64)
+            + (now - second_start).min(Duration::from_nanos(999999999))
+    }
+
     /// Set the new time and get the duration between the last call
     fn get_time(&mut self) -> Option<Duration> {
         self.last_mesured_time.map(|old_cpu_time| {
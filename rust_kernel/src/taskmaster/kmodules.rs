@@ -24,7 +24,6 @@ use core::convert::{TryFrom, TryInto};
 use core::slice;
 use core::sync::atomic::AtomicU32;
 
-use crate::drivers::PIC_8259;
 use crate::elf_loader::load_elf;
 use crate::memory::mmu::Entry;
 use crate::memory::tools::{AllocFlags, NbrPages, Page, Virt};
@@ -275,14 +274,14 @@ pub static mut CURRENT_UNIX_TIME: AtomicU32 = AtomicU32::new(0);
 /// Set IDT ENTRY fn: Usable by modules
 fn enable_irq(idt_gate: Irq, func: unsafe extern "C" fn()) {
     unsafe {
-        PIC_8259.lock().enable_irq(idt_gate, Some(func));
+        crate::drivers::enable_irq(idt_gate, Some(func));
     }
 }
 
 /// Unset IDT ENTRY fn: Usable by modules
 fn disable_irq(idt_gate: Irq) {
     unsafe {
-        PIC_8259.lock().disable_irq(idt_gate);
+        crate::drivers::disable_irq(idt_gate);
     }
 }
 
@@ -322,6 +321,7 @@ pub fn add_syslog_entry(entry: &str) -> Result<(), Errno> {
 }
 
 /// Common allocator methods for modules
+#[cfg(not(feature = "memory-debug"))]
 extern "C" {
     fn kmalloc(len: usize) -> *mut u8;
     fn kcalloc(count: usize, size: usize) -> *mut u8;
@@ -329,6 +329,13 @@ extern "C" {
     fn krealloc(addr: *mut u8, new_size: usize) -> *mut u8;
 }
 
+/// The allocations of the modules are checked like the ones of the kernel in the memory debugging mode
+#[cfg(feature = "memory-debug")]
+use crate::memory::allocator::kernel::{
+    debug_kcalloc as kcalloc, debug_kfree as kfree, debug_kmalloc as kmalloc,
+    debug_krealloc as krealloc,
+};
+
 struct AllocTable(Vec<AllocEntry>);
 
 struct AllocEntry {
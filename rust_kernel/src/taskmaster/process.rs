@@ -1,7 +1,10 @@
 //! This file contains the process description
 
 mod tss;
+pub use tss::TssBlock;
 use tss::TSS;
+mod tls;
+pub use tls::TlsSegment;
 
 use super::safe_ffi::CStringArray;
 use super::syscall::clone::CloneFlags;
@@ -22,7 +25,7 @@ use libc_binding::{c_char, Errno};
 
 use crate::elf_loader::load_elf;
 use crate::memory::mmu::{_enable_paging, _read_cr3};
-use crate::memory::tools::{AllocFlags, NbrPages, Page, Phys, Virt};
+use crate::memory::tools::{AllocFlags, NbrPages, Page, Phys, Virt, PAGE_SIZE};
 use crate::memory::KERNEL_VIRTUAL_PAGE_ALLOCATOR;
 use crate::memory::{mmu::Entry, AddressSpace};
 
@@ -40,6 +43,11 @@ impl ProcessArguments {
     pub fn new(argv: CStringArray, envp: CStringArray) -> Self {
         Self { argv, envp }
     }
+
+    /// Get the size taken by the arguments on the stack of the new process
+    pub fn get_serialized_len(&self, align: usize) -> Option<usize> {
+        Some(self.argv.get_serialized_len(align)? + self.envp.get_serialized_len(align)?)
+    }
 }
 
 const FX_REGION_LEN: usize = 512;
@@ -138,12 +146,13 @@ pub struct UserProcess {
     pub symbol_table: Option<Arc<SymbolTable>>,
     /// User Stack location
     pub user_stack_range: (u32, u32),
+    /// Thread local storage segment (cf set_thread_area())
+    pub tls: TlsSegment,
 }
 
 /// This structure represents an entire kernel process
 pub struct KernelProcess {
     /// kernel stack
-    #[allow(dead_code)]
     kernel_stack: Vec<u8>,
     /// Current process ESP on kernel stack
     pub kernel_esp: u32,
@@ -191,6 +200,8 @@ impl UserProcess {
     const RING3_DPL: u32 = 0b11;
 
     const RING3_RAW_PROCESS_MAX_SIZE: NbrPages = NbrPages::_64K;
+    /// The main stack is at the top of the user space, it grows downward from its initial size
+    const RING3_PROCESS_STACK_TOP: usize = 0xC000_0000;
     const RING3_PROCESS_STACK_SIZE: NbrPages = NbrPages::_64K;
     const RING3_PROCESS_KERNEL_STACK_SIZE: NbrPages = NbrPages::_128K;
 
@@ -235,11 +246,106 @@ impl UserProcess {
             },
             symbol_table: self.symbol_table.as_ref().map(|elem| elem.clone()),
             user_stack_range: self.user_stack_range,
+            tls: self.tls,
         })?)
     }
     pub fn get_virtual_allocator(&self) -> DeadMutexGuard<AddressSpace> {
         self.virtual_allocator.lock()
     }
+
+    /// Extend the main stack downward so it contains `addr`. The stack size cannot exceed `limit`
+    pub fn grow_stack(&mut self, addr: u32, limit: Option<usize>) -> SysResult<()> {
+        let (bottom, top) = self.user_stack_range;
+        if addr >= bottom {
+            return Err(Errno::EFAULT);
+        }
+        if limit.map_or(false, |limit| (top - addr) as usize > limit) {
+            return Err(Errno::ENOMEM);
+        }
+        let new_bottom = Page::containing(Virt(addr as usize));
+        self.get_virtual_allocator()
+            .grow_stack(Page::containing(Virt(bottom as usize)), new_bottom)?;
+        self.user_stack_range.0 = new_bottom.to_addr().0 as u32;
+        Ok(())
+    }
+
+    /// Get the number of pages mapped in the address space of the process (cf RLIMIT_AS)
+    pub fn get_address_space_size(&self) -> NbrPages {
+        unsafe {
+            let mut virtual_allocator = self.virtual_allocator.lock();
+            let _context_switch_guard = ContextSwitchGuard::new(&mut virtual_allocator);
+            virtual_allocator.nbr_user_pages()
+        }
+    }
+
+    /// Write a value in the address space of the process, which may not be the current one.
+    /// The pointer must have been checked against this address space
+    pub unsafe fn write_in_address_space<T>(&self, ptr: *mut T, value: T) {
+        let _context_switch_guard = ContextSwitchGuard::new(&mut self.virtual_allocator.lock());
+        ptr.write(value);
+    }
+
+    /// Read a word in the address space of the process, which may not be the current one
+    pub fn peek_word(&self, ptr: *const u32) -> SysResult<u32> {
+        let mut address_space = self.virtual_allocator.lock();
+        unsafe {
+            let _context_switch_guard = ContextSwitchGuard::new(&mut address_space);
+            Ok(*address_space.make_checked_ref(ptr)?)
+        }
+    }
+
+    /// Write a word in the address space of the process, which may not be the current one,
+    /// even on a read-only page (cf PTRACE_POKETEXT)
+    pub fn poke_word(&self, ptr: *mut u32, value: u32) -> SysResult<()> {
+        let mut address_space = self.virtual_allocator.lock();
+        unsafe {
+            let _context_switch_guard = ContextSwitchGuard::new(&mut address_space);
+            Ok(address_space.force_write(ptr, value)?)
+        }
+    }
+
+    /// The registers of the process in ring 3, saved at the top of its kernel stack
+    /// when it entered the kernel or was preempted
+    pub fn user_cpu_state(&self) -> *mut CpuState {
+        unsafe {
+            (self
+                .kernel_stack
+                .as_ptr()
+                .add(Self::RING3_PROCESS_KERNEL_STACK_SIZE.into()) as *mut CpuState)
+                .sub(1)
+        }
+    }
+}
+
+/// The first page of the kernel stack may have been marked as read-only to catch the overflows.
+/// Give it back its write right before the memory returns to the kernel heap
+impl Drop for UserProcess {
+    fn drop(&mut self) {
+        unsafe {
+            KERNEL_VIRTUAL_PAGE_ALLOCATOR
+                .as_mut()
+                .unwrap()
+                .change_flags_page_entry(
+                    Virt(self.kernel_stack.as_ptr() as usize).into(),
+                    AllocFlags::KERNEL_MEMORY,
+                );
+        }
+    }
+}
+
+/// Same as the UserProcess: restore the write right on the first page of the kernel stack
+impl Drop for KernelProcess {
+    fn drop(&mut self) {
+        unsafe {
+            KERNEL_VIRTUAL_PAGE_ALLOCATOR
+                .as_mut()
+                .unwrap()
+                .change_flags_page_entry(
+                    Virt(self.kernel_stack.as_ptr() as usize).into(),
+                    AllocFlags::KERNEL_MEMORY,
+                );
+        }
+    }
 }
 
 /// Main implementation of KernalProcess
@@ -360,18 +466,17 @@ impl Process for UserProcess {
                 - core::mem::size_of::<CpuState>(),
         ) as u32;
 
-        // Allocate one page for stack segment of the process
-        let stack_addr =
-            virtual_allocator.alloc(Self::RING3_PROCESS_STACK_SIZE, AllocFlags::USER_MEMORY)?;
-
-        // Mark the first entry of the user stack as read-only, this prevent user stack overflow
-        virtual_allocator.change_flags_page_entry(
-            Virt(stack_addr as usize).into(),
-            AllocFlags::READ_ONLY | AllocFlags::USER_MEMORY,
-        );
+        // Allocate the initial stack of the process. The page under it stays free: an access
+        // on this guard page extends the stack (cf grow_stack), a stack overflow cannot corrupt another mapping
+        let stack_top = Self::RING3_PROCESS_STACK_TOP as *mut u8;
+        let mut stack_addr = virtual_allocator.alloc_on(
+            stack_top.sub(Self::RING3_PROCESS_STACK_SIZE.into()),
+            Self::RING3_PROCESS_STACK_SIZE.into(),
+            AllocFlags::USER_MEMORY,
+        )?;
 
         // stack go downwards set esp to the end of the allocation
-        let mut esp = stack_addr.add(Self::RING3_PROCESS_STACK_SIZE.into()) as u32;
+        let mut esp = stack_top as u32;
 
         let (mut eax, mut ebx, mut ecx) = (0, 0, 0);
 
@@ -379,6 +484,15 @@ impl Process for UserProcess {
         if let Some(arguments) = arguments {
             let align = 4;
 
+            // Keep at least one free page for the main function under the arguments
+            let args_len = arguments.get_serialized_len(align).expect("WTF");
+            let needed_bottom = Page::containing(Virt(esp as usize - args_len - PAGE_SIZE));
+            let bottom = Page::containing(Virt(stack_addr as usize));
+            if needed_bottom < bottom {
+                virtual_allocator.grow_stack(bottom, needed_bottom)?;
+                stack_addr = needed_bottom.to_addr().0 as *mut u8;
+            }
+
             // Set the argc argument: EAX
             eax = arguments.argv.len() as u32;
 
@@ -430,10 +544,8 @@ impl Process for UserProcess {
             kernel_esp,
             virtual_allocator: Arc::try_new(DeadMutex::new(virtual_allocator))?,
             symbol_table,
-            user_stack_range: (
-                stack_addr as u32,
-                stack_addr.add(Self::RING3_PROCESS_STACK_SIZE.into()) as u32,
-            ),
+            user_stack_range: (stack_addr as u32, stack_top as u32),
+            tls: Default::default(),
         })?)
     }
 
@@ -451,6 +563,8 @@ impl Process for UserProcess {
         self.virtual_allocator.lock().context_switch();
         // Re-init the TSS block for the new process
         self.init_tss();
+        // Give the GDT entry of the TLS to the new thread
+        self.tls.load();
     }
 
     unsafe fn start(&self) -> ! {
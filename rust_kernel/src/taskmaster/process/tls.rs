@@ -0,0 +1,53 @@
+//! Process TLS segment manager
+
+use super::SysResult;
+use libc_binding::{user_desc, Errno};
+
+/// Definition of a TLS segment descriptor. The default one is not present
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct TlsSegment(u64);
+
+/// Main TLS segment implementation
+impl TlsSegment {
+    /// The GDT is built by the bootstrap code at this address
+    const GDT_MEMORY_ADDRESS: u32 = 0x800;
+
+    /// The GDT entry following the TSS one is reserved for the TLS of the running thread
+    pub const ENTRY_NUMBER: u32 = 8;
+
+    /// Present, code or data segment, ring 3
+    const ACCESS_BYTE: u64 = 0b1111_0000;
+
+    /// Create a new TLS segment from a user description (cf set_thread_area())
+    pub fn new(desc: &user_desc) -> SysResult<Self> {
+        if desc.entry_number != !0 && desc.entry_number != Self::ENTRY_NUMBER {
+            return Err(Errno::EINVAL);
+        }
+        if desc.seg_not_present() == 1 {
+            return Ok(Self::default());
+        }
+        // Conforming code segments are not allowed
+        if desc.contents() == 3 {
+            return Err(Errno::EINVAL);
+        }
+        let (base, limit) = (desc.base_addr as u64, desc.limit as u64);
+        let low = (base & 0xffff) << 16 | (limit & 0xffff);
+        let high = (base & 0xff00_0000)
+            | (base >> 16) & 0xff
+            | (limit & 0xf_0000)
+            | ((desc.read_exec_only() ^ 1) as u64) << 9
+            | (desc.contents() as u64) << 10
+            | Self::ACCESS_BYTE << 8
+            | (desc.useable() as u64) << 20
+            | (desc.seg_32bit() as u64) << 22
+            | (desc.limit_in_pages() as u64) << 23;
+        Ok(Self(high << 32 | low))
+    }
+
+    /// Write the descriptor into the GDT entry. The segment registers selecting it
+    /// take the new descriptor when they are reloaded, that is on the return to ring 3
+    pub unsafe fn load(self) {
+        let entry = (Self::GDT_MEMORY_ADDRESS as *mut u64).add(Self::ENTRY_NUMBER as usize);
+        entry.write_volatile(self.0);
+    }
+}
@@ -7,7 +7,7 @@ use sync::Spinlock;
 #[derive(Default, Debug)]
 #[cfg_attr(rustfmt, rustfmt_skip)]
 #[repr(C)]
-struct TssBlock {
+pub struct TssBlock {
     /*0x00*/ link: u16, _reserved1: u16,
     /*0x04*/ esp0: u32,                   // Need to be set for software switch
     /*0x08*/ ss0: u16, _reserved2: u16,   // Need to be set for software switch
@@ -36,6 +36,18 @@ struct TssBlock {
     /*0x64*/ debug_flag: u16, io_map: u16,
 }
 
+impl TssBlock {
+    /// Create a TSS block which switches to the stack `esp` of the segment `ss` on an
+    /// interrupt from ring 3. Each application processor has its own one
+    pub fn new(esp: u32, ss: u16) -> Self {
+        Self {
+            esp0: esp,
+            ss0: ss,
+            ..Default::default()
+        }
+    }
+}
+
 /// Necessary for LazyStatic
 unsafe impl core::marker::Send for Tss {}
 
@@ -2,10 +2,11 @@
 use super::global_time::{TimeSession, GLOBAL_TIME};
 use super::kmodules::KernelModules;
 use super::process::{get_ring, CpuState, KernelProcess, Process, ProcessOrigin, UserProcess};
-use super::signal_interface::JobAction;
+use super::signal_interface::{siginfo, JobAction};
 use super::sync::SmartMutex;
-use super::syscall::clone::CloneFlags;
+use super::syscall::clone::{CloneArgs, CloneFlags};
 use super::thread::{AutoPreemptReturnValue, ProcessState, Thread, WaitingState};
+use super::thread_group::Tracee;
 pub use super::thread_group::{
     Credentials, RunningThreadGroup, Status, ThreadGroup, ThreadGroupState,
 };
@@ -15,9 +16,16 @@ mod dustman;
 use dustman::{dustman_handler, DUSTMAN_TRIGGER};
 mod second_callback;
 use second_callback::{second_callback_handler, SECOND_CALLBACK_TRIGGER};
+mod priority;
+pub use priority::{Priority, SchedPolicy};
+mod job_control;
+pub use job_control::TerminalAccess;
+mod ptrace;
+pub use ptrace::{trace_pending_signals, trace_syscall_enter, trace_syscall_exit, SKIPPED_SYSCALL};
 
 use alloc::boxed::Box;
 use alloc::collections::CollectionAllocErr;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use ansi_escape_code::Colored;
 use core::ffi::c_void;
@@ -27,11 +35,13 @@ use fallible_collections::FallibleVec;
 use i386::PrivilegeLevel;
 use interrupts::idt::{GateType::InterruptGate32, IdtGateEntry, InterruptTable};
 use libc_binding::Errno;
-use libc_binding::Signum;
+use libc_binding::{siginfo_t, Signum};
 use messaging::{MessageTo, ProcessGroupMessage, ProcessMessage};
 use terminal::TERMINAL;
 
 use crate::drivers::PIT0;
+use crate::memory::AddressSpace;
+use crate::smp;
 
 /// These extern functions are coded in low level assembly. They are 'arch specific i686'
 extern "C" {
@@ -96,6 +106,8 @@ pub struct Scheduler {
     current_task_index: usize,
     /// time interval in PIT tics between two schedules + microseconds between two PIT tics
     time_interval: Option<(u32, u32)>,
+    /// The current thread called sched_yield(): it goes after the threads of the same priority
+    yielded: bool,
     /// The scheduler must have an idle kernel proces if all the user process are waiting
     kernel_idle_process: Box<KernelProcess>,
     /// DustMan: The process Ripper
@@ -107,7 +119,18 @@ pub struct Scheduler {
     /// Current mode of the scheduler
     mode: Mode,
     /// Indicate if scheduler is on exit routine
-    pub on_exit_routine: Option<(Pid, Status)>,
+    pub on_exit_routine: Option<ExitRoutine>,
+    /// The application processor which got the last work of the scheduler
+    last_cpu: usize,
+}
+
+/// What the DustMan has to trash
+#[derive(Debug, Copy, Clone)]
+pub enum ExitRoutine {
+    /// A whole thread group, which becomes a zombie
+    ThreadGroup(Pid, Status),
+    /// A single thread of a thread group which is still alive
+    Thread(Pid, Tid),
 }
 
 /// The pit handler (cpu_state represents a pointer to esp)
@@ -181,6 +204,7 @@ impl Scheduler {
             current_task_index: 0,
             current_task_id: (1, 0),
             time_interval: None,
+            yielded: false,
             kernel_idle_process: unsafe {
                 KernelProcess::new(
                     ProcessOrigin::Raw(_idle_process_code as *const u8, _idle_process_len),
@@ -199,16 +223,22 @@ impl Scheduler {
             last_second_callback_pit_time: unsafe { _get_pit_time() },
             mode: Mode::Normal,
             on_exit_routine: None,
+            last_cpu: 0,
         }
     }
 
     /// load the next process, returning the new_kernel_esp
     unsafe fn load_next_process(&mut self, next_process: usize) -> u32 {
-        _update_process_end_time(self.time_interval.unwrap().0);
-
         self.dispatch_messages();
+        self.check_real_timers();
         // Switch between processes
         let action = self.advance_next_process(next_process);
+        // The time slice of the elected thread depends on its priority
+        let period = self.time_interval.unwrap().0;
+        _update_process_end_time(match self.mode {
+            Mode::Normal => self.current_thread().priority.time_slice(period),
+            _ => period,
+        });
         // Set all the context of the illigible process
         self.load_new_context(action)
     }
@@ -232,6 +262,21 @@ impl Scheduler {
                 _ => panic!("message not covered"),
             }
         }
+        unsafe {
+            crate::drivers::serial::dispatch_input();
+        }
+    }
+
+    /// Send SIGALRM to the processes whose ITIMER_REAL timer expired
+    fn check_real_timers(&mut self) {
+        let now = unsafe { GLOBAL_TIME.as_ref().unwrap().monotonic_time() };
+        for thread_group in self.iter_thread_groups_mut() {
+            if thread_group.itimers.check_real_timer(now) {
+                if let Some(thread) = thread_group.get_first_thread() {
+                    let _r = thread.signal.generate_signal(Signum::SIGALRM);
+                }
+            }
+        }
     }
 
     /// Add a process into the scheduler (transfert ownership)
@@ -259,8 +304,16 @@ impl Scheduler {
         use Mode::*;
         match self.mode {
             Normal => {
-                self.current_thread_group_mut().process_duration +=
-                    unsafe { GLOBAL_TIME.as_mut().unwrap().get_process_time() };
+                let duration = unsafe { GLOBAL_TIME.as_mut().unwrap().get_process_time() };
+                let thread_group = self.current_thread_group_mut();
+                thread_group.process_duration += duration;
+                // The ITIMER_VIRTUAL and ITIMER_PROF timers consume the CPU time of the process
+                let signals = thread_group.itimers.consume_cpu_time(duration);
+                if let Some(thread) = thread_group.get_first_thread() {
+                    for signum in signals.iter().filter_map(|signum| *signum) {
+                        let _r = thread.signal.generate_signal(signum);
+                    }
+                }
                 self.current_thread_mut().unwrap_process_mut().kernel_esp = kernel_esp;
             }
             Idle => {
@@ -279,64 +332,152 @@ impl Scheduler {
     /// Advance until a next elligible process was found, modify
     /// self.current_task_index and self.current_task_id
     fn advance_next_process(&mut self, offset: usize) -> JobAction {
-        let next_process_index = (self.current_task_index + offset) % self.running_process.len();
+        let len = self.running_process.len();
+        let current_index = self.current_task_index;
+        let next_process_index = (current_index + offset) % len;
+        let yielded = core::mem::replace(&mut self.yielded, false);
         // dbg!(&self.running_process);
 
-        for idx in 0..self.running_process.len() {
-            self.current_task_index = (next_process_index + idx) % self.running_process.len();
-            self.current_task_id = self.running_process[self.current_task_index];
-
-            // Check if pending signal: Signal Can interrupt all except zombie
-            // some signals may be marked as IGNORED, Remove signal and dont DO anything in this case
-            // else create a signal var with option<SignalStatus>
-            let action = self.current_thread_get_job_action();
-
-            // Job control: STOP lock thread, CONTINUE (witch erase STOP) or TERMINATE unlock it
-            if action.intersects(JobAction::STOP) && !action.intersects(JobAction::TERMINATE) {
-                continue;
+        // The real time threads are elected first, by priority. Between
+        // the threads of the same priority, a FIFO thread keeps the CPU
+        // until it yields, the other ones go in round robin order
+        let real_time_rank = |scheduler: &Self, index: usize| {
+            let thread = scheduler.get_thread(scheduler.running_process[index])?;
+            if !thread.priority.is_real_time() {
+                return None;
             }
-            match &self.current_thread().process_state {
-                ProcessState::Running(_) => return action,
-                ProcessState::Waiting(_, waiting_state) => {
-                    if action.intersects(JobAction::TERMINATE) {
-                        // Immediately resume blocking syscall if TERMINATE action.
-                        // Ce n'est pas tout a fait logique mais on va faire la meme
-                        // chose que pour un signal tout con. Le fait de mettre Errno
-                        // a EINTR sert juste a retourner une erreur pour debloquer
-                        // auto-preempt(). Le deliver_pending_signal machin au fond de syscall
-                        // fera le reste du boulot pour achever le process.
-                        // Il y a toujours un truc qui me chaffouine profondement la-dedans...
-                        self.current_thread_mut().set_running();
-                        self.current_thread_mut()
-                            .set_return_value_autopreempt(Err(Errno::EINTR));
-                        return action;
-                    } else if action.intersects(JobAction::INTERRUPT) {
-                        // Check if signal var contains something, set return value as
-                        // Err(Errno::EINTR), set process as running then return
-                        self.current_thread_mut().set_running();
-                        self.current_thread_mut()
-                            .set_return_value_autopreempt(Err(Errno::EINTR));
+            let order = if index == current_index
+                && thread.priority.policy == SchedPolicy::Fifo
+                && !yielded
+            {
+                0
+            } else {
+                (index + len - next_process_index) % len + 1
+            };
+            Some((thread.priority.rt_priority, len - order))
+        };
+        let mut last_rank = None;
+        loop {
+            let best = (0..len)
+                .filter_map(|index| real_time_rank(self, index).map(|rank| (rank, index)))
+                .filter(|(rank, _)| last_rank.map(|last| *rank < last).unwrap_or(true))
+                .max_by_key(|(rank, _)| *rank);
+            match best {
+                Some((rank, index)) => {
+                    if let Some(action) = self.check_elligible(index) {
                         return action;
                     }
-                    match waiting_state {
-                        WaitingState::Sleeping(time) => {
-                            let now = unsafe { _get_pit_time() };
-                            if now >= *time {
-                                self.current_thread_mut().set_running();
-                                self.current_thread_mut()
-                                    .set_return_value_autopreempt(Ok(AutoPreemptReturnValue::None));
-                                return action;
-                            }
-                        }
-                        _ => {}
-                    }
+                    last_rank = Some(rank);
                 }
-            };
+                None => break,
+            }
+        }
+
+        // Then the time sharing threads in round robin order
+        for idx in 0..len {
+            let index = (next_process_index + idx) % len;
+            if real_time_rank(self, index).is_some() {
+                continue;
+            }
+            if let Some(action) = self.check_elligible(index) {
+                return action;
+            }
         }
         self.mode = Mode::Idle;
         JobAction::default()
     }
 
+    /// Make the thread at `index` in running_process the current one and
+    /// check if it can run, waking it up if its blocking syscall is over
+    fn check_elligible(&mut self, index: usize) -> Option<JobAction> {
+        self.current_task_index = index;
+        self.current_task_id = self.running_process[index];
+
+        // A traced process does not run while it is stopped for its tracer
+        if self.current_thread_is_trace_stopped() {
+            return None;
+        }
+
+        // Check if pending signal: Signal Can interrupt all except zombie
+        // some signals may be marked as IGNORED, Remove signal and dont DO anything in this case
+        // else create a signal var with option<SignalStatus>
+        let action = self.current_thread_get_job_action();
+
+        // Job control: STOP lock thread, CONTINUE (witch erase STOP) or TERMINATE unlock it
+        if action.intersects(JobAction::STOP) && !action.intersects(JobAction::TERMINATE) {
+            return None;
+        }
+        match &self.current_thread().process_state {
+            ProcessState::Running(_) => return Some(action),
+            ProcessState::Waiting(_, waiting_state) => {
+                if action.intersects(JobAction::TERMINATE) {
+                    // Immediately resume blocking syscall if TERMINATE action.
+                    // Ce n'est pas tout a fait logique mais on va faire la meme
+                    // chose que pour un signal tout con. Le fait de mettre Errno
+                    // a EINTR sert juste a retourner une erreur pour debloquer
+                    // auto-preempt(). Le deliver_pending_signal machin au fond de syscall
+                    // fera le reste du boulot pour achever le process.
+                    // Il y a toujours un truc qui me chaffouine profondement la-dedans...
+                    self.current_thread_mut().set_running();
+                    self.current_thread_mut()
+                        .set_return_value_autopreempt(Err(Errno::EINTR));
+                    return Some(action);
+                } else if action.intersects(JobAction::INTERRUPT) {
+                    // Check if signal var contains something, set return value as
+                    // Err(Errno::EINTR), set process as running then return
+                    self.current_thread_mut().set_running();
+                    self.current_thread_mut()
+                        .set_return_value_autopreempt(Err(Errno::EINTR));
+                    return Some(action);
+                }
+                match waiting_state {
+                    WaitingState::Sleeping(time)
+                    | WaitingState::Poll {
+                        timeout: Some(time),
+                        ..
+                    }
+                    | WaitingState::Read {
+                        timeout: Some(time),
+                        ..
+                    } => {
+                        let now = unsafe { _get_pit_time() };
+                        if now >= *time {
+                            self.current_thread_mut().set_running();
+                            self.current_thread_mut()
+                                .set_return_value_autopreempt(Ok(AutoPreemptReturnValue::None));
+                            return Some(action);
+                        }
+                    }
+                    &WaitingState::Futex {
+                        timeout: Some(time),
+                        ..
+                    } => {
+                        let now = unsafe { _get_pit_time() };
+                        if now >= time {
+                            self.current_thread_mut().set_running();
+                            self.current_thread_mut()
+                                .set_return_value_autopreempt(Err(Errno::ETIMEDOUT));
+                            return Some(action);
+                        }
+                    }
+                    &WaitingState::SigWait { set, timeout } => {
+                        let now = unsafe { _get_pit_time() };
+                        if self.current_thread().signal.has_pending_signal(set)
+                            || timeout.map_or(false, |time| now >= time)
+                        {
+                            self.current_thread_mut().set_running();
+                            self.current_thread_mut()
+                                .set_return_value_autopreempt(Ok(AutoPreemptReturnValue::None));
+                            return Some(action);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        };
+        None
+    }
+
     /// Prepare the context for the new illigible process
     fn load_new_context(&mut self, action: JobAction) -> u32 {
         use Mode::*;
@@ -410,11 +551,14 @@ impl Scheduler {
             .expect("remove_thread_goup, thread group doen't exist");
     }
 
+    /// Clone the current thread. Return the TID of the new thread for
+    /// CLONE_THREAD, the PID of the new thread group otherwise
     pub fn current_thread_clone(
         &mut self,
         kernel_esp: u32,
         child_stack: *const c_void,
         flags: CloneFlags,
+        args: CloneArgs,
     ) -> SysResult<Pid> {
         if self.time_interval == None {
             panic!("It'a illogical to fork a process when we are in monotask mode");
@@ -422,10 +566,18 @@ impl Scheduler {
         self.running_process.try_reserve(1)?;
         let (father_pid, father_tid) = self.current_task_id;
 
-        let child_pid = if flags.contains(CloneFlags::THREAD) {
+        let tracer = self
+            .current_thread_group()
+            .tracee
+            .as_ref()
+            .map(|tracee| tracee.tracer);
+
+        let (child_id, child_task_id) = if flags.contains(CloneFlags::THREAD) {
             let current_thread = self.current_thread_mut();
 
-            let child = current_thread.sys_clone(kernel_esp, child_stack, flags)?;
+            let mut child = current_thread.sys_clone(kernel_esp, child_stack, flags)?;
+            // The threads of a traced process are traced too
+            child.signal.set_traced(tracer.is_some());
             let thread_group = self.current_thread_group_mut();
             let tid = thread_group.get_available_tid();
             thread_group
@@ -433,12 +585,24 @@ impl Scheduler {
                 .expect("wtf")
                 .try_insert(tid, child)?;
             self.running_process.push((father_pid, tid));
-            father_pid
+            (tid as Pid, (father_pid, tid))
         } else {
+            // The real user ID cannot own more than RLIMIT_NPROC processes, unless it is root
+            let current = self.current_thread_group();
+            if let Some(limit) = current.soft_limit(Resource::NProc) {
+                let uid = current.credentials.uid;
+                let nbr_process = self
+                    .iter_thread_groups_with_pid()
+                    .filter(|(_, tg)| !tg.is_zombie() && tg.credentials.uid == uid)
+                    .count();
+                if nbr_process >= limit && !current.credentials.is_root() {
+                    return Err(Errno::EAGAIN);
+                }
+            }
             let child_pid = self.get_available_pid();
             let thread_group = self.current_thread_group_mut();
 
-            let new_thread_group = thread_group.sys_clone(
+            let mut new_thread_group = thread_group.sys_clone(
                 father_pid,
                 father_tid,
                 child_pid,
@@ -446,13 +610,42 @@ impl Scheduler {
                 child_stack,
                 flags,
             )?;
+            // With CLONE_PTRACE, the tracer of the process also traces the child,
+            // which starts with a SIGSTOP
+            if let Some(tracer) = tracer.filter(|_| {
+                flags.contains(CloneFlags::PTRACE) && !flags.contains(CloneFlags::UNTRACED)
+            }) {
+                new_thread_group.tracee = Some(Tracee::new(tracer));
+                let child = new_thread_group
+                    .get_first_thread()
+                    .expect("no child thread");
+                child.signal.set_traced(true);
+                child.signal.generate_signal(Signum::SIGSTOP)?;
+            }
 
             self.all_process.try_insert(child_pid, new_thread_group)?;
             self.running_process.push((child_pid, 0));
-            child_pid
+            (child_pid, (child_pid, 0))
         };
 
-        Ok(child_pid)
+        let child = self.get_thread_mut(child_task_id).expect("no child wtf");
+        if let Some(tls) = args.tls {
+            child.unwrap_process_mut().tls = tls;
+        }
+        if let Some(child_tid) = args.child_tid {
+            if flags.contains(CloneFlags::CHILD_SETTID) {
+                // The child may not share the address space of its father
+                unsafe {
+                    child
+                        .unwrap_process()
+                        .write_in_address_space(child_tid, child_id);
+                }
+            }
+            if flags.contains(CloneFlags::CHILD_CLEARTID) {
+                child.clear_child_tid = Some(child_tid);
+            }
+        }
+        Ok(child_id)
     }
 
     const REAPER_PID: Pid = 1;
@@ -472,6 +665,17 @@ impl Scheduler {
             _ => {}
         }
 
+        let (pid, _) = self.current_task_id;
+        let (pgid, sid, parent_pid) = {
+            let thread_group = self.current_thread_group();
+            (thread_group.pgid, thread_group.sid, thread_group.parent)
+        };
+
+        // The exit of a session leader hangs up its controlling terminal
+        if pid == sid {
+            self.hang_up_controlling_terminal(sid);
+        }
+
         while let Some(child_pid) = self.current_thread_group_running_mut().child.pop() {
             let thread_group = self
                 .get_thread_group_mut(child_pid)
@@ -480,6 +684,7 @@ impl Scheduler {
                 // If the child is on RunningState. The REAPER must adopt him
                 ThreadGroupState::Running(_) => {
                     thread_group.parent = Self::REAPER_PID;
+                    let (child_pgid, child_sid) = (thread_group.pgid, thread_group.sid);
 
                     let _r = self
                         .get_thread_group_mut(Self::REAPER_PID)
@@ -491,6 +696,10 @@ impl Scheduler {
                             log::error!("no memory to push on the reaper pid: Trashing zombie");
                             self.remove_thread_group(child_pid);
                         });
+                    // The process group of the child may become orphaned
+                    if child_pgid != pgid && child_sid == sid {
+                        self.kill_orphaned_process_group(child_pgid, pid);
+                    }
                 }
                 // Else, definitively destroy it
                 ThreadGroupState::Zombie(_) => {
@@ -498,16 +707,125 @@ impl Scheduler {
                 }
             }
         }
+        // So may the process group of the exiting process
+        if self
+            .get_thread_group(parent_pid)
+            .map_or(false, |parent| parent.pgid != pgid && parent.sid == sid)
+        {
+            self.kill_orphaned_process_group(pgid, pid);
+        }
 
-        let (pid, _) = self.current_task_id;
+        // The tracees of the exiting process are detached and resumed
+        while let Some(tracee_pid) = self
+            .iter_thread_groups_with_pid()
+            .find(|(_, tg)| tg.tracee.as_ref().map_or(false, |t| t.tracer == pid))
+            .map(|(&tracee_pid, _)| tracee_pid)
+        {
+            let _r = self.detach_tracee(tracee_pid, 0);
+        }
+        if let Some(tracee) = &mut self.current_thread_group_mut().tracee {
+            tracee.stop = None;
+        }
 
         self.remove_thread_group_running(pid);
 
         DUSTMAN_TRIGGER.store(true, Ordering::Relaxed);
         unpreemptible();
 
-        self.on_exit_routine = Some((pid, status));
-        self.on_exit_routine
+        self.on_exit_routine = Some(ExitRoutine::ThreadGroup(pid, status));
+        Some((pid, status))
+    }
+
+    /// Start the exit() routine of the current thread: the whole
+    /// thread group exits if it is its last thread or its main thread
+    pub fn current_thread_exit(&mut self, status: Status) {
+        let (pid, tid) = self.current_task_id;
+        let last_thread = self
+            .current_thread_group()
+            .get_all_thread()
+            .map_or(true, |all_thread| all_thread.len() == 1);
+        if tid == 0 || last_thread {
+            self.current_thread_group_exit(status);
+            return;
+        }
+        log::info!(
+            "{} exit called for thread with ID: {:?} STATUS: {:?}",
+            self.read_date(),
+            self.current_task_id,
+            status,
+        );
+
+        // Clear the TID of the thread and wake the one which may be joining it
+        if let Some(clear_child_tid) = self.current_thread_mut().clear_child_tid.take() {
+            let checked_tid = self
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator()
+                .make_checked_ref_mut(clear_child_tid)
+                .ok();
+            if let Some(child_tid) = checked_tid {
+                *child_tid = 0;
+                self.futex_wake(clear_child_tid as u32, 1, None);
+            }
+        }
+
+        self.running_process
+            .retain(|&task_id| task_id != (pid, tid));
+
+        DUSTMAN_TRIGGER.store(true, Ordering::Relaxed);
+        unpreemptible();
+
+        self.on_exit_routine = Some(ExitRoutine::Thread(pid, tid));
+    }
+
+    /// Finalize the exit() routine of a thread: Remove it from its thread group
+    fn thread_exit_resume(&mut self, pid: Pid, tid: Tid) {
+        // The whole thread group may have exited since
+        if let Some(all_thread) = self
+            .get_thread_group_mut(pid)
+            .and_then(|thread_group| thread_group.get_all_thread_mut())
+        {
+            all_thread.remove(&tid);
+        }
+    }
+
+    /// Wake at most `count` threads waiting on the futex `uaddr` of
+    /// the current address space then, for `requeue` as Some((uaddr2,
+    /// max)), move at most `max` of the remaining waiters on the
+    /// futex `uaddr2`. Return the number of woken and requeued threads
+    pub fn futex_wake(&mut self, uaddr: u32, count: u32, requeue: Option<(u32, u32)>) -> u32 {
+        let address_space = self
+            .current_thread()
+            .unwrap_process()
+            .virtual_allocator
+            .clone();
+        let (mut woken, mut requeued) = (0, 0);
+        for thread in self.iter_thread_mut() {
+            if !Arc::ptr_eq(&thread.unwrap_process().virtual_allocator, &address_space) {
+                continue;
+            }
+            let timeout = match thread.get_waiting_state() {
+                Some(&WaitingState::Futex {
+                    uaddr: waited_uaddr,
+                    timeout,
+                }) if waited_uaddr == uaddr => timeout,
+                _ => continue,
+            };
+            if woken < count {
+                thread.set_return_value_autopreempt(Ok(AutoPreemptReturnValue::None));
+                thread.set_running();
+                woken += 1;
+            } else if let Some((uaddr2, max)) = requeue {
+                if requeued < max {
+                    thread.set_waiting(WaitingState::Futex {
+                        uaddr: uaddr2,
+                        timeout,
+                    });
+                    requeued += 1;
+                }
+            }
+        }
+        woken + requeued
     }
 
     /// Finalize the exit() routine: Remove ressources of the exited process and send his exit status
@@ -519,11 +837,18 @@ impl Scheduler {
 
         // Send a sig child signal to the father
         let parent_pid = dead_process.parent;
+        let (si_code, si_status) = status.sigchld_info();
+        let info = siginfo_t {
+            si_pid: process_to_free_pid,
+            si_uid: dead_process.credentials.uid,
+            si_status,
+            ..siginfo(Signum::SIGCHLD, si_code)
+        };
         let parent = self
             .get_thread_mut((parent_pid, 0))
             .expect("WTF: Parent not alive");
 
-        let res = parent.signal.generate_signal(Signum::SIGCHLD);
+        let res = parent.signal.generate_signal_info(info);
         if let Err(e) = res {
             log::error!("generate sigchidld failed {:?}", e);
         }
@@ -541,8 +866,24 @@ impl Scheduler {
             .file_descriptor_interface
             .delete();
 
+        let address_space = dead_process
+            .iter_thread_mut()
+            .next()
+            .map(|thread| thread.unwrap_process().virtual_allocator.clone());
+
         dead_process.set_zombie(status);
 
+        // The threads are dropped, the address space is freed if no other process shares it
+        if let Some(address_space) = address_space.and_then(|a| Arc::try_unwrap(a).ok()) {
+            self.free_address_space(address_space.into_inner());
+        }
+
+        let dead_process = self
+            .get_thread_group_mut(process_to_free_pid)
+            .expect("WTF: No Dead Process");
+
+        let tracer = dead_process.tracee.as_ref().map(|tracee| tracee.tracer);
+
         // Send a death testament message to the parent
         self.send_message(MessageTo::Process {
             pid: parent_pid,
@@ -552,6 +893,37 @@ impl Scheduler {
                 status: status.into(),
             },
         });
+        // And to the tracer, which must also detach the zombie
+        if let Some(tracer) = tracer.filter(|&tracer| tracer != parent_pid) {
+            self.send_message(MessageTo::Process {
+                pid: tracer,
+                content: ProcessMessage::ProcessUpdated {
+                    pid: process_to_free_pid,
+                    pgid: dead_process_pgid,
+                    status: status.into(),
+                },
+            });
+        }
+    }
+
+    /// Free the pages of a dead address space on an application processor. The shared file
+    /// mappings are written back here, the disks are driven by the bootstrap processor
+    fn free_address_space(&mut self, address_space: AddressSpace) {
+        let virtual_allocator = address_space.into_virtual_allocator();
+        match smp::next_application_processor(self.last_cpu) {
+            Some(cpu) => {
+                self.last_cpu = cpu;
+                // On failure, the pages are freed here when the work is dropped
+                if let Err(e) = smp::call_on(cpu, move || drop(virtual_allocator)) {
+                    log::warn!(
+                        "Cannot free an address space on the processor {}: {:?}",
+                        cpu,
+                        e
+                    );
+                }
+            }
+            None => drop(virtual_allocator),
+        }
     }
 
     /// Call the DustMan to trash a process
@@ -603,6 +975,12 @@ impl Scheduler {
             PrivilegeLevel::Ring3,
             "Cannot apply signal from ring0 process"
         );
+        // The signal frames are built under the current ESP, the main stack may have to grow
+        let process_esp = unsafe { (*cpu_state).esp };
+        if process_esp >= self.current_thread().unwrap_process().user_stack_range.0 {
+            let _r = self
+                .current_thread_grow_stack(process_esp.saturating_sub(Self::SIGNAL_STACK_RESERVE));
+        }
         // Get the user_stack location to ensure that are large available space
         let user_stack_range = self.current_thread().unwrap_process().user_stack_range;
         let signum: Option<Signum> = self.current_thread_mut().signal.exec_signal_handler(
@@ -617,23 +995,68 @@ impl Scheduler {
         }
     }
 
+    /// The space needed under the ESP of a thread to deliver it signals
+    const SIGNAL_STACK_RESERVE: u32 = 4 * PAGE_SIZE as u32;
+
+    /// A page fault may be an access under the ESP of a thread on the guard page of
+    /// its main stack. Like on linux, an access up to 64K (the `enter` instruction) and 32
+    /// registers (the `pusha` instruction) under the ESP extends the stack
+    const STACK_GUARD_GAP: u32 = 65536 + 32 * 4;
+
+    /// Extend the main stack of the current thread so it contains `addr`, within the limits
+    /// on the stack size (RLIMIT_STACK) and on the address space size (RLIMIT_AS)
+    pub fn current_thread_grow_stack(&mut self, addr: u32) -> SysResult<()> {
+        let thread_group = self.current_thread_group();
+        let stack_limit = thread_group.soft_limit(Resource::Stack);
+        let as_limit = thread_group.soft_limit(Resource::AddressSpace);
+
+        let process = self.current_thread_mut().unwrap_process_mut();
+        let bottom = process.user_stack_range.0;
+        if addr >= bottom {
+            return Ok(());
+        }
+        if let Some(as_limit) = as_limit {
+            let new_pages = NbrPages::from((bottom - addr) as usize);
+            let size = process.get_virtual_allocator().nbr_user_pages() + new_pages;
+            if size.to_bytes() > as_limit {
+                return Err(Errno::ENOMEM);
+            }
+        }
+        process.grow_stack(addr, stack_limit)
+    }
+
+    /// Handle a page fault of the current thread on the guard page of its main stack.
+    /// A thread running on another stack (cf pthread_create) never extends the main stack
+    pub fn current_thread_stack_handle_page_fault(&mut self, cr2: u32, esp: u32) -> SysResult<()> {
+        let bottom = self.current_thread().unwrap_process().user_stack_range.0;
+        if cr2 >= bottom
+            || cr2.saturating_add(Self::STACK_GUARD_GAP) < esp
+            || esp.saturating_add(Self::STACK_GUARD_GAP) < bottom
+        {
+            return Err(Errno::EFAULT);
+        }
+        self.current_thread_grow_stack(cr2)
+    }
+
     /// Update the Job process state regarding to the get_job_action() return value
     pub fn current_thread_get_job_action(&mut self) -> JobAction {
         let pid = self.current_task_id.0;
         let current = self.current_thread();
         let action = current.signal.get_job_action();
+        let stop_signal = current.signal.get_stop_signal();
         let current_thread_group = self.current_thread_group_mut();
         let pgid = current_thread_group.pgid;
         let parent_pid = current_thread_group.parent;
         if action != JobAction::TERMINATE {
             if action == JobAction::STOP {
-                if current_thread_group.job.try_set_stoped() {
+                let signum = stop_signal.unwrap_or(Signum::SIGSTOP);
+                if current_thread_group.job.try_set_stoped(signum) {
                     self.send_message(MessageTo::Process {
                         pid: parent_pid,
                         content: ProcessMessage::ProcessUpdated {
                             pid: pid,
                             pgid: pgid,
-                            status: Status::Stopped.into(),
+                            status: Status::Stopped(signum as u32).into(),
                         },
                     });
                 }
@@ -652,6 +1075,11 @@ impl Scheduler {
         }
         action
     }
+    /// The current thread gives up the CPU for the threads of the same priority
+    pub fn current_thread_yield(&mut self) {
+        self.yielded = true;
+    }
+
     /// Get current process pid
     pub fn current_task_id(&self) -> (Pid, Tid) {
         self.current_task_id
@@ -738,19 +1166,37 @@ impl Scheduler {
             .flat_map(|thread_group| thread_group.iter_thread_mut())
     }
 
+    /// Wake all the threads which are polling the file operation `uid_file_op`
+    fn wake_pollers(&mut self, uid_file_op: usize) {
+        for thread in self.iter_thread_mut() {
+            let polled = match thread.get_waiting_state() {
+                Some(WaitingState::Poll { file_op_uids, .. }) => {
+                    file_op_uids.contains(&uid_file_op)
+                }
+                _ => false,
+            };
+            if polled {
+                thread.set_return_value_autopreempt(Ok(AutoPreemptReturnValue::None));
+                thread.set_running();
+            }
+        }
+    }
+
     pub fn send_message(&mut self, message: MessageTo) {
         use super::syscall::WaitOption;
         // log::info!("{:?}", message);
         match message {
             MessageTo::Reader { uid_file_op } => {
                 self.iter_thread_mut()
-                    .find(|thread| {
-                        thread.get_waiting_state() == Some(&WaitingState::Read(uid_file_op))
+                    .find(|thread| match thread.get_waiting_state() {
+                        Some(WaitingState::Read { file_op_uid, .. }) => *file_op_uid == uid_file_op,
+                        _ => false,
                     })
                     .map(|thread| {
                         thread.set_return_value_autopreempt(Ok(AutoPreemptReturnValue::None));
                         thread.set_running();
                     });
+                self.wake_pollers(uid_file_op);
             }
             MessageTo::Accepter { uid_file_op } => {
                 self.iter_thread_mut()
@@ -761,6 +1207,7 @@ impl Scheduler {
                         thread.set_return_value_autopreempt(Ok(AutoPreemptReturnValue::None));
                         thread.set_running();
                     });
+                self.wake_pollers(uid_file_op);
             }
             MessageTo::Connecter { uid_file_op } => {
                 self.iter_thread_mut()
@@ -771,6 +1218,7 @@ impl Scheduler {
                         thread.set_return_value_autopreempt(Ok(AutoPreemptReturnValue::None));
                         thread.set_running();
                     });
+                self.wake_pollers(uid_file_op);
             }
             MessageTo::Writer { uid_file_op } => {
                 self.iter_thread_mut()
@@ -781,6 +1229,7 @@ impl Scheduler {
                         thread.set_return_value_autopreempt(Ok(AutoPreemptReturnValue::None));
                         thread.set_running();
                     });
+                self.wake_pollers(uid_file_op);
             }
             MessageTo::Opener { uid_file_op } => {
                 self.iter_thread_mut()
@@ -791,6 +1240,7 @@ impl Scheduler {
                         thread.set_return_value_autopreempt(Ok(AutoPreemptReturnValue::None));
                         thread.set_running();
                     });
+                self.wake_pollers(uid_file_op);
             }
             MessageTo::Process { pid, content } => match content {
                 ProcessMessage::ProcessUpdated {
@@ -803,6 +1253,8 @@ impl Scheduler {
                     // with the right options
                     let mut finded = false;
                     let s: Status = status.into();
+                    // A tracer is told about the stops of its tracee without WUNTRACED
+                    let traced_by_waiter = self.is_traced_by(dead_process_pid, pid);
                     if let Some(thread) = self
                         .get_thread_group_mut(pid)
                         .iter_mut()
@@ -815,7 +1267,9 @@ impl Scheduler {
                                 options,
                             }) = thread.get_waiting_state()
                             {
-                                ((options.contains(WaitOption::WUNTRACED) && s == Status::Stopped)
+                                ((s.is_stopped()
+                                    && (options.contains(WaitOption::WUNTRACED)
+                                        || traced_by_waiter))
                                     || (options.contains(WaitOption::WCONTINUED)
                                         && s == Status::Continued)
                                     || s.is_exited()
@@ -838,16 +1292,25 @@ impl Scheduler {
                         }));
                     }
 
-                    if finded && (s == Status::Stopped || s == Status::Continued) {
+                    if finded && (s.is_stopped() || s == Status::Continued) {
                         // consume the state, because at the return of
                         // auto_preempt after scheduling, the state
                         // can change and it maybe too late to consume
                         // the state
-                        self.get_thread_group_mut(dead_process_pid)
-                            .expect("no dead pid")
-                            .job
-                            .consume_last_event()
-                            .expect("no status after autopreempt");
+                        let thread_group = self
+                            .get_thread_group_mut(dead_process_pid)
+                            .expect("no dead pid");
+                        match &mut thread_group.tracee {
+                            Some(tracee) if traced_by_waiter && tracee.has_unreported_stop() => {
+                                tracee.report_stop();
+                            }
+                            _ => {
+                                thread_group
+                                    .job
+                                    .consume_last_event()
+                                    .expect("no status after autopreempt");
+                            }
+                        }
                     }
                 }
                 _ => panic!("message not covered"),
@@ -872,13 +1335,6 @@ impl Scheduler {
     }
 }
 
-#[no_mangle]
-#[allow(dead_code)]
-pub unsafe fn get_current_pgid() -> Pid {
-    SCHEDULER.force_unlock();
-    SCHEDULER.lock().current_thread_group().pgid
-}
-
 #[no_mangle]
 pub unsafe extern "C" fn send_message(message: MessageTo) {
     SCHEDULER.force_unlock();
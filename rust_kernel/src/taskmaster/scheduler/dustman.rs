@@ -1,5 +1,5 @@
 //! Here is the DustMan worker. It trashes processes
-use super::{_preemptible, SCHEDULER};
+use super::{_preemptible, ExitRoutine, SCHEDULER};
 
 use core::sync::atomic::{AtomicBool, Ordering};
 
@@ -11,10 +11,10 @@ extern "C" {
 
 fn trash_process() {
     let mut scheduler = SCHEDULER.lock();
-    if let Some((pid, status)) = scheduler.on_exit_routine {
-        scheduler.exit_resume(pid, status);
-    } else {
-        log::info!("Dustman, ready to serve !");
+    match scheduler.on_exit_routine {
+        Some(ExitRoutine::ThreadGroup(pid, status)) => scheduler.exit_resume(pid, status),
+        Some(ExitRoutine::Thread(pid, tid)) => scheduler.thread_exit_resume(pid, tid),
+        None => log::info!("Dustman, ready to serve !"),
     }
 }
 
@@ -0,0 +1,141 @@
+//! This file contains the job control of the sessions: the orphaned
+//! process groups and the accesses of the background process groups
+//! to their controlling terminal
+
+use super::{Pid, Scheduler, SysResult};
+use crate::taskmaster::fd_interface::Fd;
+use crate::taskmaster::signal_interface::JobAction;
+
+use libc_binding::{termios, Errno, Signum, TOSTOP};
+use messaging::{MessageTo, ProcessGroupMessage};
+
+/// The accesses of a process to its controlling terminal which are checked by the job control
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TerminalAccess {
+    /// A read: a background process group is sent SIGTTIN
+    Read,
+    /// A write: a background process group is sent SIGTTOU if TOSTOP is set
+    Write,
+    /// A change of the terminal attributes: a background process group is sent SIGTTOU
+    Control,
+}
+
+impl Scheduler {
+    /// Send the signal `signum` to all the members of the process group `pgid`
+    fn signal_process_group(&mut self, pgid: Pid, signum: Signum) {
+        self.send_message(MessageTo::ProcessGroup {
+            pgid,
+            content: ProcessGroupMessage::Signal(signum),
+        });
+    }
+
+    /// A process group is orphaned when the parent of each of its members is either
+    /// itself a member of the group or is not a member of the group's session.
+    /// The process `exiting_pid` is not counted, neither as a member nor as a parent
+    pub fn is_orphaned_process_group(&self, pgid: Pid, exiting_pid: Option<Pid>) -> bool {
+        self.iter_thread_groups_with_pid()
+            .filter(|&(&pid, tg)| tg.pgid == pgid && !tg.is_zombie() && Some(pid) != exiting_pid)
+            .all(|(_, tg)| {
+                Some(tg.parent) == exiting_pid
+                    || self
+                        .get_thread_group(tg.parent)
+                        .map_or(true, |parent| parent.pgid == pgid || parent.sid != tg.sid)
+            })
+    }
+
+    /// The exit of the process `exiting_pid` may orphan the process group `pgid`:
+    /// if it has stopped members, nobody could continue them anymore. So every
+    /// member is sent SIGHUP then SIGCONT
+    pub(super) fn kill_orphaned_process_group(&mut self, pgid: Pid, exiting_pid: Pid) {
+        let has_stopped_member = self
+            .iter_thread_groups()
+            .any(|tg| tg.pgid == pgid && !tg.is_zombie() && tg.job.is_stopped());
+        if has_stopped_member && self.is_orphaned_process_group(pgid, Some(exiting_pid)) {
+            self.signal_process_group(pgid, Signum::SIGHUP);
+            self.signal_process_group(pgid, Signum::SIGCONT);
+        }
+    }
+
+    /// The exit of the leader of the session `sid` disassociates the session from its
+    /// controlling terminal: the foreground process group is sent SIGHUP then SIGCONT
+    pub(super) fn hang_up_controlling_terminal(&mut self, sid: Pid) {
+        let terminal = match self.current_thread_group_mut().controlling_terminal.take() {
+            Some(terminal) => terminal,
+            None => return,
+        };
+        let foreground = {
+            let mut terminal = terminal.lock();
+            // The terminal may have been released since
+            if terminal.tcgetsid() != Ok(sid) {
+                return;
+            }
+            let foreground = terminal.tcgetpgrp().unwrap_or(0);
+            let _r = terminal.tcsetsid(0);
+            let _r = terminal.tcsetpgrp(0);
+            foreground
+        };
+        if foreground != 0 {
+            self.signal_process_group(foreground, Signum::SIGHUP);
+            self.signal_process_group(foreground, Signum::SIGCONT);
+        }
+        for thread_group in self.iter_thread_groups_mut().filter(|tg| tg.sid == sid) {
+            thread_group.controlling_terminal = None;
+        }
+    }
+
+    /// Apply the job control on an `access` of the current process to the file `fd`,
+    /// when it is its controlling terminal: a process of a background process group
+    /// is sent SIGTTIN or SIGTTOU with its group.
+    ///
+    /// Returns Ok(true) when the access is allowed, Ok(false) when the process was
+    /// stopped by the signal: the access must be retried after an auto_preempt().
+    /// Returns EINTR when the signal is caught, EIO when the process group is orphaned
+    /// or when the process ignores or blocks SIGTTIN
+    pub fn current_thread_terminal_access(
+        &mut self,
+        fd: Fd,
+        access: TerminalAccess,
+    ) -> SysResult<bool> {
+        let thread_group = self.current_thread_group();
+        let (pgid, sid) = (thread_group.pgid, thread_group.sid);
+        let (_, file_operation) = thread_group
+            .unwrap_running()
+            .file_descriptor_interface
+            .get_open_file(fd)?;
+        let signum = {
+            let terminal = file_operation.lock();
+            if terminal.tcgetsid() != Ok(sid) || terminal.tcgetpgrp()? == pgid {
+                return Ok(true);
+            }
+            match access {
+                TerminalAccess::Read => Signum::SIGTTIN,
+                TerminalAccess::Write => {
+                    let mut termios: termios = unsafe { core::mem::zeroed() };
+                    terminal.tcgetattr(&mut termios)?;
+                    if termios.c_lflag & TOSTOP == 0 {
+                        return Ok(true);
+                    }
+                    Signum::SIGTTOU
+                }
+                TerminalAccess::Control => Signum::SIGTTOU,
+            }
+        };
+        if self.current_thread().signal.is_blocked_or_ignored(signum) {
+            return match access {
+                TerminalAccess::Read => Err(Errno::EIO),
+                TerminalAccess::Write | TerminalAccess::Control => Ok(true),
+            };
+        }
+        if self.is_orphaned_process_group(pgid, None) {
+            return Err(Errno::EIO);
+        }
+        self.signal_process_group(pgid, signum);
+
+        let action = self.current_thread_get_job_action();
+        if action.intersects(JobAction::STOP) && !action.intersects(JobAction::TERMINATE) {
+            Ok(false)
+        } else {
+            Err(Errno::EINTR)
+        }
+    }
+}
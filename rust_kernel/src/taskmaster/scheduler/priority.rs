@@ -0,0 +1,115 @@
+//! This file contains the scheduling policies and the nice values of the threads
+
+use core::convert::TryFrom;
+use libc_binding::{Errno, SCHED_FIFO, SCHED_OTHER, SCHED_RR};
+
+/// The scheduling policy of a thread, cf sched_setscheduler()
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SchedPolicy {
+    /// The time sharing threads, their time slice depends on their nice value
+    Other,
+    /// A real time thread runs until it blocks, yields or a higher priority thread is ready
+    Fifo,
+    /// Like Fifo, but the threads of the same priority share the CPU by time slices
+    RoundRobin,
+}
+
+impl TryFrom<i32> for SchedPolicy {
+    type Error = Errno;
+
+    fn try_from(policy: i32) -> Result<Self, Self::Error> {
+        match policy as u32 {
+            SCHED_OTHER => Ok(SchedPolicy::Other),
+            SCHED_FIFO => Ok(SchedPolicy::Fifo),
+            SCHED_RR => Ok(SchedPolicy::RoundRobin),
+            _ => Err(Errno::EINVAL),
+        }
+    }
+}
+
+impl From<SchedPolicy> for u32 {
+    fn from(policy: SchedPolicy) -> Self {
+        match policy {
+            SchedPolicy::Other => SCHED_OTHER,
+            SchedPolicy::Fifo => SCHED_FIFO,
+            SchedPolicy::RoundRobin => SCHED_RR,
+        }
+    }
+}
+
+/// The scheduling parameters of a thread
+#[derive(Debug, Copy, Clone)]
+pub struct Priority {
+    pub policy: SchedPolicy,
+    /// Only used by SchedPolicy::Other, from NICE_MIN (favorable) to NICE_MAX
+    pub nice: i32,
+    /// Only used by the real time policies, from RT_PRIORITY_MIN to RT_PRIORITY_MAX (favorable)
+    pub rt_priority: u32,
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Self {
+            policy: SchedPolicy::Other,
+            nice: 0,
+            rt_priority: 0,
+        }
+    }
+}
+
+/// Weight of the time slices of the nice values, from -20 to 19. Each nice
+/// level gives about 10% of CPU time more or less than the next one (from linux)
+const NICE_TO_WEIGHT: [u32; 40] = [
+    /* -20 */ 88761, 71755, 56483, 46273, 36291, /* -15 */ 29154, 23254, 18705, 14949,
+    11916, /* -10 */ 9548, 7620, 6100, 4904, 3906, /* -5 */ 3121, 2501, 1991, 1586, 1277,
+    /* 0 */ 1024, 820, 655, 526, 423, /* 5 */ 335, 272, 215, 172, 137, /* 10 */ 110,
+    87, 70, 56, 45, /* 15 */ 36, 29, 23, 18, 15,
+];
+
+/// The weight of the nice value 0
+const NICE_0_WEIGHT: u32 = 1024;
+
+impl Priority {
+    pub const NICE_MIN: i32 = -20;
+    pub const NICE_MAX: i32 = 19;
+    pub const RT_PRIORITY_MIN: u32 = 1;
+    pub const RT_PRIORITY_MAX: u32 = 99;
+    /// The time slice of a nice -20 thread is limited to MAX_SLICE_FACTOR * the default one
+    const MAX_SLICE_FACTOR: u32 = 20;
+    /// Never give less than 2 tics to a thread, cf scheduler::start()
+    const MIN_SLICE: u32 = 2;
+
+    /// Clamp a nice value to the valid range
+    pub fn clamp_nice(nice: i32) -> i32 {
+        nice.max(Self::NICE_MIN).min(Self::NICE_MAX)
+    }
+
+    /// The real time threads are always elected before the time sharing ones
+    pub fn is_real_time(&self) -> bool {
+        self.policy != SchedPolicy::Other
+    }
+
+    /// Get the time slice in PIT tics of the thread, `period` is the
+    /// time slice of a nice 0 thread
+    pub fn time_slice(&self, period: u32) -> u32 {
+        match self.policy {
+            SchedPolicy::Other => {
+                let weight =
+                    NICE_TO_WEIGHT[(Self::clamp_nice(self.nice) - Self::NICE_MIN) as usize];
+                (period * weight / NICE_0_WEIGHT)
+                    .max(Self::MIN_SLICE)
+                    .min(period * Self::MAX_SLICE_FACTOR)
+            }
+            SchedPolicy::Fifo | SchedPolicy::RoundRobin => period,
+        }
+    }
+
+    /// The priority field of /proc/[pid]/stat: 0 to 39 for the nice values,
+    /// -2 to -100 for the real time priorities
+    pub fn proc_priority(&self) -> i32 {
+        match self.policy {
+            SchedPolicy::Other => self.nice + 20,
+            SchedPolicy::Fifo | SchedPolicy::RoundRobin => -1 - self.rt_priority as i32,
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! This file contains the stops of the traced processes: a tracee
+//! stops at its signals, at its syscalls (cf PTRACE_SYSCALL) and after
+//! each instruction (cf PTRACE_SINGLESTEP) until its tracer resumes it
+
+use super::{auto_preempt, Pid, Scheduler, SysResult, SCHEDULER};
+use crate::taskmaster::process::{get_ring, CpuState};
+use crate::taskmaster::signal_interface::{siginfo, JobAction};
+use crate::taskmaster::thread::AutoPreemptReturnValue;
+use crate::taskmaster::thread_group::{Resume, TraceEvent, TraceStop};
+
+use core::convert::TryFrom;
+use i386::PrivilegeLevel;
+use libc_binding::{Errno, Signum, SI_USER};
+use messaging::{MessageTo, ProcessMessage};
+
+/// The syscall number set by the tracer to skip a syscall
+pub const SKIPPED_SYSCALL: u32 = u32::max_value();
+
+impl Scheduler {
+    /// Check if the process `pid` is traced by the process `tracer`
+    pub fn is_traced_by(&self, pid: Pid, tracer: Pid) -> bool {
+        self.get_thread_group(pid)
+            .and_then(|thread_group| thread_group.tracee.as_ref())
+            .map_or(false, |tracee| tracee.tracer == tracer)
+    }
+
+    /// Stop the traced process of the current thread on `event` and wake its tracer
+    fn current_thread_set_trace_stop(&mut self, event: TraceEvent, in_kernel: bool) {
+        let (pid, tid) = self.current_task_id;
+        let thread_group = self.current_thread_group_mut();
+        let pgid = thread_group.pgid;
+        let tracee = match &mut thread_group.tracee {
+            Some(tracee) => tracee,
+            None => return,
+        };
+        tracee.stop = Some(TraceStop {
+            tid,
+            event,
+            reported: false,
+            in_kernel,
+        });
+        let tracer = tracee.tracer;
+        let status = tracee.stop_status().expect("no trace stop");
+        self.send_message(MessageTo::Process {
+            pid: tracer,
+            content: ProcessMessage::ProcessUpdated {
+                pid,
+                pgid,
+                status: status.into(),
+            },
+        });
+    }
+
+    /// Check if the current thread cannot be elected because its process is in a
+    /// trace stop. A thread which runs in ring 3 stops there to report its signals
+    pub(super) fn current_thread_is_trace_stopped(&mut self) -> bool {
+        let pid = self.current_task_id.0;
+        let stop = match &self.current_thread_group().tracee {
+            Some(tracee) => tracee.stop,
+            None => return false,
+        };
+        let killed = self
+            .current_thread()
+            .signal
+            .get_job_action()
+            .intersects(JobAction::TERMINATE);
+        match stop {
+            Some(_) if !killed => true,
+            // A SIGKILL always ends the stop
+            Some(stop) => {
+                if let Some(tracee) = &mut self.current_thread_group_mut().tracee {
+                    tracee.stop = None;
+                }
+                if stop.in_kernel {
+                    if let Some(thread) = self.get_thread_mut((pid, stop.tid)) {
+                        thread.set_return_value_autopreempt(Ok(AutoPreemptReturnValue::None));
+                    }
+                }
+                false
+            }
+            None if killed => false,
+            None => {
+                let thread = self.current_thread_mut();
+                if !thread.is_running()
+                    || unsafe { get_ring(thread.unwrap_process().kernel_esp) }
+                        != PrivilegeLevel::Ring3
+                {
+                    return false;
+                }
+                match thread.signal.take_intercepted_signal() {
+                    Some(info) => {
+                        self.current_thread_set_trace_stop(TraceEvent::Signal(info), false);
+                        true
+                    }
+                    None => false,
+                }
+            }
+        }
+    }
+
+    /// Resume the stopped tracee `pid` as asked by `resume`, delivering it
+    /// the signal `signum` if it is not 0
+    pub fn resume_tracee(&mut self, pid: Pid, resume: Resume, signum: u32) -> SysResult<()> {
+        let signum = match signum {
+            0 => None,
+            signum => Some(Signum::try_from(signum).map_err(|_| Errno::EIO)?),
+        };
+        let tracee = self
+            .get_thread_group_mut(pid)
+            .and_then(|thread_group| thread_group.tracee.as_mut())
+            .ok_or(Errno::ESRCH)?;
+        let stop = tracee.stop.ok_or(Errno::ESRCH)?;
+        tracee.stop = None;
+        tracee.resume = resume;
+
+        let thread = self.get_thread_mut((pid, stop.tid)).ok_or(Errno::ESRCH)?;
+        if let Some(signum) = signum {
+            // The signal of a signal stop keeps its informations
+            let info = match stop.event {
+                TraceEvent::Signal(info) if info.si_signo == signum as i32 => info,
+                _ => siginfo(signum, SI_USER as i32),
+            };
+            thread.signal.inject_signal(info)?;
+        }
+        let cpu_state = thread.unwrap_process().user_cpu_state();
+        unsafe {
+            (*cpu_state)
+                .eflags
+                .set_trap_flag(resume == Resume::SingleStep);
+        }
+        if stop.in_kernel {
+            thread.set_return_value_autopreempt(Ok(AutoPreemptReturnValue::None));
+        }
+        Ok(())
+    }
+
+    /// Stop tracing the process `pid`, resuming it with the signal `signum` if it is
+    /// stopped. The process is detached even if the signal cannot be delivered
+    pub fn detach_tracee(&mut self, pid: Pid, signum: u32) -> SysResult<()> {
+        let stopped = self
+            .get_thread_group(pid)
+            .and_then(|thread_group| thread_group.tracee.as_ref())
+            .map_or(false, |tracee| tracee.stop.is_some());
+        let result = if stopped {
+            self.resume_tracee(pid, Resume::Continue, signum)
+        } else {
+            Ok(())
+        };
+        let thread_group = self.get_thread_group_mut(pid).ok_or(Errno::ESRCH)?;
+        thread_group.tracee = None;
+        for thread in thread_group.iter_thread_mut() {
+            thread.signal.set_traced(false);
+        }
+        result
+    }
+}
+
+/// Stop the current thread from a syscall or an exception handler until its tracer resumes it
+fn current_thread_trace_stop(event: TraceEvent) {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+        scheduler.current_thread_set_trace_stop(event, true);
+        drop(scheduler);
+
+        let _ret = auto_preempt();
+    })
+}
+
+/// Stop the current thread at the entry of its syscall if its tracer asked for it
+/// (cf PTRACE_SYSCALL). The tracer may change the syscall number with the register
+/// orig_eax: return the syscall to execute, or None if it must be skipped
+pub fn trace_syscall_enter(cpu_state: *mut CpuState) -> Option<u32> {
+    let syscall_stop = unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+        let sysnum = unsafe { (*cpu_state).registers.eax };
+        match &mut scheduler.current_thread_group_mut().tracee {
+            Some(tracee) => {
+                tracee.orig_eax = sysnum;
+                tracee.resume == Resume::Syscall
+            }
+            None => return Some(sysnum),
+        }
+    });
+    if syscall_stop {
+        current_thread_trace_stop(TraceEvent::SyscallEnter);
+    }
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+        if scheduler
+            .current_thread()
+            .signal
+            .get_job_action()
+            .intersects(JobAction::TERMINATE)
+        {
+            return None;
+        }
+        let sysnum = match &scheduler.current_thread_group().tracee {
+            Some(tracee) => tracee.orig_eax,
+            None => unsafe { (*cpu_state).registers.eax },
+        };
+        if sysnum == SKIPPED_SYSCALL {
+            None
+        } else {
+            Some(sysnum)
+        }
+    })
+}
+
+/// Stop the current thread at the exit of its syscall if its tracer asked for it,
+/// the tracer may change the return value
+pub fn trace_syscall_exit() {
+    let syscall_stop = unpreemptible_context!({
+        SCHEDULER
+            .lock()
+            .current_thread_group()
+            .tracee
+            .as_ref()
+            .map_or(false, |tracee| tracee.resume == Resume::Syscall)
+    });
+    if syscall_stop {
+        current_thread_trace_stop(TraceEvent::SyscallExit);
+    }
+}
+
+/// Report the pending signals of the current thread to its tracer before their
+/// delivery, with a stop for each one. Return if the thread is still in a blocked
+/// syscall: when the tracer suppressed the signals which interrupted it, the
+/// syscall is restarted
+pub fn trace_pending_signals(cpu_state: *mut CpuState, in_blocked_syscall: bool) -> bool {
+    loop {
+        let info = unpreemptible_context!({
+            SCHEDULER
+                .lock()
+                .current_thread_mut()
+                .signal
+                .take_intercepted_signal()
+        });
+        match info {
+            Some(info) => current_thread_trace_stop(TraceEvent::Signal(info)),
+            None => break,
+        }
+    }
+    if !in_blocked_syscall {
+        return false;
+    }
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+        let traced = scheduler.current_thread_group().tracee.is_some();
+        let action = scheduler.current_thread().signal.get_job_action();
+        if traced && !action.intersects(JobAction::INTERRUPT | JobAction::TERMINATE) {
+            // Back 2 instruction to reput eip on `int 80h`, eax still contains the syscall number
+            unsafe { (*cpu_state).eip -= 2 };
+            false
+        } else {
+            true
+        }
+    })
+}
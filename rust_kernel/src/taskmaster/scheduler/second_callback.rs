@@ -1,5 +1,6 @@
 //! Here is the Second Callback worker. It call process registered to each seconds events
 use super::{_preemptible, SCHEDULER};
+use crate::drivers::storage::block_cache;
 
 use core::sync::atomic::{AtomicBool, Ordering};
 
@@ -16,6 +17,9 @@ fn second_callback() {
     for f in scheduler.kernel_modules.second_cycle.iter() {
         (f)()
     }
+    drop(scheduler);
+    // Write back the dirty sectors of the block cache
+    block_cache::periodic_write_back();
 }
 
 /// This function must be called in a unpremptible_context with the SECOND_CALLBACK_TRIGGER set as true
@@ -6,20 +6,29 @@ use super::SysResult;
 use alloc::collections::vec_deque::VecDeque;
 use bit_field::BitField;
 use bitflags::bitflags;
+use core::convert::TryFrom;
+use core::fmt;
 use core::mem;
 use core::ops::{BitAnd, BitOr, BitOrAssign, Index, IndexMut, Not};
+use core::ptr;
 use libc_binding::Errno;
 use libc_binding::Signum;
+use libc_binding::{siginfo_t, stack_t, ucontext_t};
+use libc_binding::{MINSIGSTKSZ, SS_DISABLE, SS_ONSTACK};
 use libc_binding::{
     SA_NOCLDSTOP, SA_NOCLDWAIT, SA_NODEFER, SA_ONSTACK, SA_RESETHAND, SA_RESTART, SA_RESTORER,
     SA_SIGINFO,
 };
+use libc_binding::{SIGQUEUE_MAX, SI_KERNEL};
 use libc_binding::{SIG_BLOCK, SIG_SETMASK, SIG_UNBLOCK};
 
 use crate::memory::tools::PAGE_SIZE;
 
 #[allow(non_camel_case_types)]
-pub type sigset_t = u32;
+pub type sigset_t = u64;
+
+/// Number of signals, the real-time ones included
+const NSIG: usize = Signum::SIGRTMAX as usize + 1;
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -35,6 +44,10 @@ pub enum DefaultAction {
 impl core::convert::From<Signum> for DefaultAction {
     fn from(signum: Signum) -> Self {
         use Signum::*;
+        // The default action of the real-time signals is to terminate the process.
+        if signum.is_realtime() {
+            return DefaultAction::Terminate;
+        }
         match signum {
             // These signals are deadly by default. The behavior of a process is undefined after it ignores a
             // SIGFPE, SIGILL, SIGSEGV, or SIGBUS signal that was not generated by kill(), sigqueue(), or raise().
@@ -54,13 +67,15 @@ impl core::convert::From<Signum> for DefaultAction {
 
             // SIGSTOP cannot be handled or ignore. For others, they can STOP process execution, or execute handler ot be ignored.
             SIGSTOP | SIGTSTP | SIGTTIN | SIGTTOU => DefaultAction::Stop,
+
+            _ => DefaultAction::Terminate,
         }
     }
 }
 
 #[derive(Copy, Clone, Debug, Default)]
 #[repr(transparent)]
-pub struct SaMask(u32);
+pub struct SaMask(u64);
 
 impl SaMask {
     /// Check is sa_mask contains a specified signum
@@ -85,16 +100,19 @@ impl SaMask {
 
 impl From<Signum> for SaMask {
     fn from(s: Signum) -> Self {
-        Self(1 << s as u32)
+        Self(1 << s as u64)
     }
 }
 
-impl From<u32> for SaMask {
-    fn from(n: u32) -> Self {
+impl From<u64> for SaMask {
+    fn from(n: u64) -> Self {
         // make sure we don't create a mask with syskill or Sigstop as
         // syskill and sigstop cannot be blocked
         SaMask(
-            n & !(Signum::SIGKILL as u32) & !(Signum::SIGSTOP as u32) & !(Signum::SIGCONT as u32),
+            n & !(SaMask::from(Signum::SIGKILL)
+                | SaMask::from(Signum::SIGSTOP)
+                | SaMask::from(Signum::SIGCONT))
+            .0,
         )
     }
 }
@@ -174,8 +192,14 @@ impl StructSigaction {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
-struct SignalActions(pub [StructSigaction; 32]);
+#[derive(Copy, Clone)]
+struct SignalActions(pub [StructSigaction; NSIG]);
+
+impl fmt::Debug for SignalActions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.0.iter()).finish()
+    }
+}
 
 impl IndexMut<Signum> for SignalActions {
     fn index_mut(&mut self, index: Signum) -> &mut StructSigaction {
@@ -190,15 +214,52 @@ impl Index<Signum> for SignalActions {
     }
 }
 
+/// Create the siginfo_t of a signal, the caller may fill the
+/// signal-specific fields
+pub fn siginfo(signum: Signum, si_code: i32) -> siginfo_t {
+    siginfo_t {
+        si_signo: signum as i32,
+        si_code,
+        ..Default::default()
+    }
+}
+
+/// A generated signal, waiting to be delivered or accepted
+#[derive(Copy, Clone, Debug)]
+struct PendingSignal {
+    signum: Signum,
+    info: siginfo_t,
+    /// The signal was already seen by the tracer of the thread (cf ptrace())
+    reported: bool,
+}
+
+/// An alternate signal stack registered with sigaltstack()
+#[derive(Copy, Clone, Debug)]
+struct AltStack {
+    sp: u32,
+    size: u32,
+}
+
+impl AltStack {
+    /// Check if a stack pointer is inside the alternate stack
+    fn contains(&self, esp: u32) -> bool {
+        esp > self.sp && esp <= self.sp + self.size
+    }
+}
+
 /// Main Signal Inteface structure
 #[derive(Debug)]
 pub struct SignalInterface {
     /// This array contains all the actions which must be applied for each signums
     signal_actions: SignalActions,
     /// This is the queue of all pending signals
-    signal_queue: VecDeque<Signum>,
+    signal_queue: VecDeque<PendingSignal>,
     /// The SaMask specifies a mask of signals which should be blocked
     current_sa_mask: SaMask,
+    /// The alternate stack used by the SA_ONSTACK handlers
+    alt_stack: Option<AltStack>,
+    /// The thread is traced: its signals are reported to the tracer before their delivery
+    traced: bool,
 }
 
 bitflags! {
@@ -218,9 +279,11 @@ impl SignalInterface {
     /// Create a new signal Inteface
     pub fn new() -> Self {
         Self {
-            signal_actions: SignalActions([Default::default(); 32]),
+            signal_actions: SignalActions([Default::default(); NSIG]),
             signal_queue: VecDeque::new(),
             current_sa_mask: Default::default(),
+            alt_stack: None,
+            traced: false,
         }
     }
 
@@ -247,6 +310,7 @@ impl SignalInterface {
             // SA_ONSTACK flag shall be cleared for all signals.
             sigaction.sa_flags.remove(SaFlags::SA_ONSTACK);
         }
+        self.alt_stack = None;
 
         // The initial thread of the new process shall inherit at
         // least the following attributes from the calling thread:
@@ -266,6 +330,9 @@ impl SignalInterface {
             // parent process if the thread was created as the result of a call to
             // fork()
             current_sa_mask: self.current_sa_mask,
+            alt_stack: self.alt_stack,
+            // The tracing of the child is set up by the scheduler (cf CLONE_PTRACE)
+            traced: false,
         }
     }
 
@@ -275,10 +342,16 @@ impl SignalInterface {
         let mut action: JobAction = JobAction::default();
         let mut sa_mask = self.current_sa_mask;
 
-        for &signum in self.signal_queue.iter() {
+        for pending in self.signal_queue.iter() {
+            let signum = pending.signum;
             if sa_mask.is_masked(signum) {
                 continue;
             }
+            // The thread must stop to report the signal to its tracer
+            if self.is_intercepted(pending) {
+                action |= JobAction::INTERRUPT;
+                continue;
+            }
             let sigaction = self.signal_actions[signum];
             action |= match sigaction.sa_handler {
                 SIG_IGN => JobAction::default(),
@@ -299,6 +372,19 @@ impl SignalInterface {
         action
     }
 
+    /// Get the pending signal which stops the job, cf get_job_action()
+    pub fn get_stop_signal(&self) -> Option<Signum> {
+        self.signal_queue
+            .iter()
+            .find(|pending| {
+                !self.current_sa_mask.is_masked(pending.signum)
+                    && !self.is_intercepted(pending)
+                    && self.signal_actions[pending.signum].sa_handler == SIG_DFL
+                    && DefaultAction::from(pending.signum) == DefaultAction::Stop
+            })
+            .map(|pending| pending.signum)
+    }
+
     /// Create handler contexts and pop the signal queue. Return Some(signum) in case of Deadly signal
     pub fn exec_signal_handler(
         &mut self,
@@ -309,8 +395,9 @@ impl SignalInterface {
         let mut i = 0;
         let mut frame_build = 0;
 
-        while let Some(&signum) = self.signal_queue.get(i) {
-            if self.current_sa_mask.is_masked(signum) {
+        while let Some(&pending) = self.signal_queue.get(i) {
+            let PendingSignal { signum, info, .. } = pending;
+            if self.current_sa_mask.is_masked(signum) || self.is_intercepted(&pending) {
                 i += 1;
             } else {
                 let sigaction = self.signal_actions[signum];
@@ -322,27 +409,11 @@ impl SignalInterface {
                     },
                     _ => {
                         let process_esp = unsafe { (*cpu_state).esp };
-                        if process_esp >= user_stack_range.1 {
-                            log::error!("ESP range of the current process is bullshit !");
-                            log::error!(
-                                "proc esp: {:#X?} > stack end: {:#X?}",
-                                process_esp,
-                                user_stack_range.1
-                            );
-                            return Some(Signum::SIGKILL);
-                        }
-                        // It There are not enough space in user stack (neg | x < PAGE_SIZE)
-                        else if process_esp < user_stack_range.0
-                            || process_esp - user_stack_range.0 < PAGE_SIZE as u32
-                        {
-                            log::warn!("ESP range underflow detected !");
-                            log::warn!(
-                                "proc esp: {:#X?}, stack start: {:#X?}",
-                                process_esp,
-                                user_stack_range.0
-                            );
-                            return Some(Signum::SIGKILL);
-                        }
+                        let frame_esp =
+                            match self.frame_location(process_esp, &sigaction, user_stack_range) {
+                                Some(esp) => esp,
+                                None => return Some(Signum::SIGKILL),
+                            };
                         if frame_build == 0 && in_blocked_syscall {
                             if sigaction.sa_flags.intersects(SaFlags::SA_RESTART) {
                                 // Back 2 instruction to reput eip on `int 80h` and restart the syscall
@@ -355,11 +426,18 @@ impl SignalInterface {
                             }
                         }
                         frame_build += 1;
+                        let ucontext = ucontext_t {
+                            uc_link: ptr::null_mut(),
+                            uc_sigmask: self.current_sa_mask.0,
+                            uc_stack: self.get_alt_stack(process_esp),
+                            uc_mcontext: 0,
+                        };
                         unsafe {
                             context_builder::push(
                                 cpu_state,
-                                self.current_sa_mask,
-                                signum,
+                                frame_esp,
+                                ucontext,
+                                info,
                                 sigaction.sa_handler as u32,
                             );
                         }
@@ -372,6 +450,51 @@ impl SignalInterface {
         None
     }
 
+    /// Get the stack pointer from which the signal frame must be
+    /// built, None if there is not enough space on the stack
+    fn frame_location(
+        &self,
+        process_esp: u32,
+        sigaction: &StructSigaction,
+        user_stack_range: (u32, u32),
+    ) -> Option<u32> {
+        if let Some(alt_stack) = self.alt_stack {
+            // A nested signal is built just below the current frame of the alternate stack
+            if alt_stack.contains(process_esp) {
+                if process_esp - alt_stack.sp < context_builder::frame_size() {
+                    log::warn!("Alternate signal stack overflow detected !");
+                    return None;
+                }
+                return Some(process_esp);
+            } else if sigaction.sa_flags.contains(SaFlags::SA_ONSTACK) {
+                return Some(alt_stack.sp + alt_stack.size);
+            }
+        }
+        if process_esp >= user_stack_range.1 {
+            log::error!("ESP range of the current process is bullshit !");
+            log::error!(
+                "proc esp: {:#X?} > stack end: {:#X?}",
+                process_esp,
+                user_stack_range.1
+            );
+            None
+        }
+        // It There are not enough space in user stack (neg | x < PAGE_SIZE)
+        else if process_esp < user_stack_range.0
+            || process_esp - user_stack_range.0 < PAGE_SIZE as u32
+        {
+            log::warn!("ESP range underflow detected !");
+            log::warn!(
+                "proc esp: {:#X?}, stack start: {:#X?}",
+                process_esp,
+                user_stack_range.0
+            );
+            None
+        } else {
+            Some(process_esp)
+        }
+    }
+
     /// Acknowledge end of signal execution, pop the first internal signal and a restore context form the signal frame.
     pub fn terminate_pending_signal(&mut self, process_context_ptr: u32) {
         unsafe {
@@ -405,10 +528,32 @@ impl SignalInterface {
         }
     }
 
-    /// Register a new signal
+    /// Register a new signal sent by the kernel
     pub fn generate_signal(&mut self, signum: Signum) -> SysResult<u32> {
-        // If the same signal already exists in signal queue, ignore it
-        if self.signal_queue.iter().any(|&s| s == signum) {
+        self.generate_signal_info(siginfo(signum, SI_KERNEL as i32))
+    }
+
+    /// Register a new signal with the informations given to its handler
+    pub fn generate_signal_info(&mut self, info: siginfo_t) -> SysResult<u32> {
+        let signum = Signum::try_from(info.si_signo as u32).map_err(|_| Errno::EINVAL)?;
+
+        if signum.is_realtime() {
+            // The real-time signals are queued, up to SIGQUEUE_MAX of them
+            if self
+                .signal_queue
+                .iter()
+                .filter(|pending| pending.signum.is_realtime())
+                .count()
+                >= SIGQUEUE_MAX as usize
+            {
+                return Err(Errno::EAGAIN);
+            }
+        } else if self
+            .signal_queue
+            .iter()
+            .any(|pending| pending.signum == signum)
+        {
+            // If the same signal already exists in signal queue, ignore it
             return Ok(0);
         }
 
@@ -419,19 +564,131 @@ impl SignalInterface {
         // or any of the threads within that process shall be
         // discarded.
         if default_action == DefaultAction::Stop {
-            self.signal_queue.retain(|&s| s != Signum::SIGCONT);
+            self.signal_queue
+                .retain(|pending| pending.signum != Signum::SIGCONT);
         }
 
         // Conversely, when SIGCONT is generated for a process or
         // thread, all pending stop signals for that process or any of the
         // threads within that process shall be discarded
         if default_action == DefaultAction::Continue {
-            self.signal_queue
-                .retain(|&s| Into::<DefaultAction>::into(s) != DefaultAction::Stop);
+            self.signal_queue.retain(|pending| {
+                Into::<DefaultAction>::into(pending.signum) != DefaultAction::Stop
+            });
         }
 
         self.signal_queue.try_reserve(1)?;
-        self.signal_queue.push_back(signum);
+        self.signal_queue.push_back(PendingSignal {
+            signum,
+            info,
+            reported: false,
+        });
+        Ok(0)
+    }
+
+    /// Set or unset the thread as traced
+    pub fn set_traced(&mut self, traced: bool) {
+        self.traced = traced;
+    }
+
+    /// Check if a pending signal must be reported to the tracer before its
+    /// delivery. SIGKILL is never reported and the blocked signals stay pending
+    fn is_intercepted(&self, pending: &PendingSignal) -> bool {
+        self.traced
+            && !pending.reported
+            && pending.signum != Signum::SIGKILL
+            && !self.current_sa_mask.is_masked(pending.signum)
+    }
+
+    /// Remove from the queue the oldest signal which must be reported to the tracer
+    pub fn take_intercepted_signal(&mut self) -> Option<siginfo_t> {
+        let index = self
+            .signal_queue
+            .iter()
+            .position(|pending| self.is_intercepted(pending))?;
+        self.signal_queue.remove(index).map(|pending| pending.info)
+    }
+
+    /// Queue a signal given by the tracer on the resumption of the thread, it is
+    /// delivered first and is not reported again
+    pub fn inject_signal(&mut self, info: siginfo_t) -> SysResult<()> {
+        let signum = Signum::try_from(info.si_signo as u32).map_err(|_| Errno::EINVAL)?;
+        self.signal_queue.try_reserve(1)?;
+        self.signal_queue.push_front(PendingSignal {
+            signum,
+            info,
+            reported: true,
+        });
+        Ok(())
+    }
+
+    /// Check if the signal `signum` is blocked by the thread or ignored by the process
+    pub fn is_blocked_or_ignored(&self, signum: Signum) -> bool {
+        self.current_sa_mask.is_masked(signum) || self.signal_actions[signum].sa_handler == SIG_IGN
+    }
+
+    /// Check if one of the signals of `set` is pending
+    pub fn has_pending_signal(&self, set: sigset_t) -> bool {
+        let set = SaMask(set);
+        self.signal_queue
+            .iter()
+            .any(|pending| set.contains(pending.signum))
+    }
+
+    /// Remove from the queue the pending signal of `set` with the
+    /// lowest number, the oldest one if it was queued several times
+    pub fn accept_signal(&mut self, set: sigset_t) -> Option<siginfo_t> {
+        let set = SaMask(set);
+        let (index, _) = self
+            .signal_queue
+            .iter()
+            .enumerate()
+            .filter(|(_, pending)| set.contains(pending.signum))
+            .min_by_key(|(index, pending)| (pending.signum as u32, *index))?;
+        self.signal_queue.remove(index).map(|pending| pending.info)
+    }
+
+    /// Get the state of the alternate signal stack, relative to the
+    /// stack pointer of the thread
+    pub fn get_alt_stack(&self, esp: u32) -> stack_t {
+        match self.alt_stack {
+            Some(alt_stack) => stack_t {
+                ss_sp: alt_stack.sp as *mut _,
+                ss_size: alt_stack.size as _,
+                ss_flags: if alt_stack.contains(esp) {
+                    SS_ONSTACK as i32
+                } else {
+                    0
+                },
+            },
+            None => stack_t {
+                ss_sp: ptr::null_mut(),
+                ss_size: 0,
+                ss_flags: SS_DISABLE as i32,
+            },
+        }
+    }
+
+    /// Register or disable the alternate signal stack. The memory of
+    /// the stack must have been checked by the caller
+    pub fn set_alt_stack(&mut self, esp: u32, ss: &stack_t) -> SysResult<u32> {
+        // The alternate stack cannot be modified while the thread is executing on it
+        if self.get_alt_stack(esp).ss_flags == SS_ONSTACK as i32 {
+            return Err(Errno::EPERM);
+        }
+        self.alt_stack = match ss.ss_flags as u32 {
+            SS_DISABLE => None,
+            0 => {
+                if (ss.ss_size as u32) < MINSIGSTKSZ {
+                    return Err(Errno::ENOMEM);
+                }
+                Some(AltStack {
+                    sp: ss.ss_sp as u32,
+                    size: ss.ss_size as u32,
+                })
+            }
+            _ => return Err(Errno::EINVAL),
+        };
         Ok(0)
     }
 
@@ -466,26 +723,40 @@ impl SignalInterface {
 
 /// This module allow to create contexts for handlers and to get back from them
 mod context_builder {
-    use super::{CpuState, SaMask, Signum};
+    use super::{siginfo_t, ucontext_t, CpuState, SaMask};
 
     use core::mem::size_of;
 
+    /// Size of a signal frame on the user stack
+    pub fn frame_size() -> u32 {
+        (size_of::<CpuState>()
+            + size_of::<ucontext_t>()
+            + size_of::<siginfo_t>()
+            + align_on(unsafe { _trampoline_len } as usize, 4)
+            + 4 * size_of::<u32>()) as u32
+    }
+
     /// Create a new context witch will execute a signal handler
+    /// The handler is called as `handler(signum, &siginfo, &ucontext)`
     pub unsafe fn push(
         cpu_state: *mut CpuState,
-        sa_mask: SaMask,
-        signum: Signum,
+        mut user_esp: u32,
+        ucontext: ucontext_t,
+        siginfo: siginfo_t,
         handler_address: u32,
     ) {
-        let mut user_esp = (*cpu_state).esp;
-
         /* PUSH DATA SECTION */
 
         // push the current cpu_state on the user stack
         push_esp(&mut user_esp, *cpu_state);
 
-        // push the sa_mask
-        push_esp(&mut user_esp, sa_mask);
+        // push the ucontext, which contains the sa_mask to restore
+        push_esp(&mut user_esp, ucontext);
+        let ucontext_ptr = user_esp;
+
+        // push the siginfo
+        push_esp(&mut user_esp, siginfo);
+        let siginfo_ptr = user_esp;
 
         // push the trampoline code on the user stack
         push_buff_esp(
@@ -494,9 +765,11 @@ mod context_builder {
             _trampoline_len as usize,
         );
 
-        // push the address of start of trampoline code stack on the user stack
+        // push the handler arguments then the address of start of trampoline code stack on the user stack
         let eip_trampoline = user_esp;
-        push_esp(&mut user_esp, signum as u32);
+        push_esp(&mut user_esp, ucontext_ptr);
+        push_esp(&mut user_esp, siginfo_ptr);
+        push_esp(&mut user_esp, siginfo.si_signo as u32);
         push_esp(&mut user_esp, eip_trampoline);
 
         (*cpu_state).eip = handler_address;
@@ -505,15 +778,19 @@ mod context_builder {
 
     /// Destroy a context and set execution pointer on the previous context. Return the stored SA_MASK
     pub unsafe fn pop(cpu_state: *mut CpuState) -> SaMask {
+        // skip the handler arguments: eq to `add esp, 12`
+        (*cpu_state).esp += 3 * size_of::<u32>() as u32;
+
         // skip the trampoline code
         (*cpu_state).esp += align_on(_trampoline_len as usize, 4) as u32;
 
-        // skip Signum: eq to `add esp, 4`
-        (*cpu_state).esp += 4;
+        // skip the siginfo
+        (*cpu_state).esp += size_of::<siginfo_t>() as u32;
 
         /* POP DATA SECTION */
 
-        let sa_mask = pop_esp(&mut (*cpu_state).esp);
+        // the handler may have modified the mask of the ucontext
+        let ucontext: ucontext_t = pop_esp(&mut (*cpu_state).esp);
 
         // secure restore stored registers (GDT selectors are exclude)
         let old_cpu_state: CpuState = pop_esp(&mut (*cpu_state).esp);
@@ -523,7 +800,7 @@ mod context_builder {
         (*cpu_state).eflags = old_cpu_state.eflags;
 
         // return stored sa_mask
-        sa_mask
+        SaMask::from(ucontext.uc_sigmask)
     }
 
     /// helper to push on the stack
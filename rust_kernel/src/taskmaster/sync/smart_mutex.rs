@@ -54,8 +54,8 @@ unsafe fn trace_back(mut ebp: *const u32) {
 impl RawSmartMutex {
     const INIT: RawSmartMutex = RawSmartMutex(AtomicU32::new(0));
 
-    /// Try to lock the mutex
-    fn try_lock(&self) -> bool {
+    /// Try to lock the mutex, the backtrace of the previous locker is dumped on failure if `verbose`
+    fn try_lock(&self, verbose: bool) -> bool {
         let mut current_ebp: u32;
 
         unsafe {
@@ -67,12 +67,14 @@ impl RawSmartMutex {
         };
         let ebp = self.0.compare_and_swap(0, current_ebp, Ordering::Relaxed) as *const u32;
         if ebp != 0 as *const u32 {
-            // Here a DeadSmartMutex, we trace back the process which had put his EBP in the mutex
-            eprintln!("--- Previous locker backtrace ----");
-            unsafe {
-                trace_back(ebp);
+            if verbose {
+                // Here a DeadSmartMutex, we trace back the process which had put his EBP in the mutex
+                eprintln!("--- Previous locker backtrace ----");
+                unsafe {
+                    trace_back(ebp);
+                }
+                eprintln!("----------------------------------");
             }
-            eprintln!("----------------------------------");
             false
         } else {
             true
@@ -121,11 +123,21 @@ impl<'a, T: Debug> SmartMutex<T> {
         }
     }
     pub fn lock(&'a self) -> SmartMutexGuard<'a, T> {
-        if !self.raw_lock.try_lock() {
+        if !self.raw_lock.try_lock(true) {
             panic!("Dead lock {:?}", self.data);
         }
         SmartMutexGuard(unsafe { &mut *(self as *const Self as *mut Self) })
     }
+    /// Lock the mutex only if it is free
+    pub fn try_lock(&'a self) -> Option<SmartMutexGuard<'a, T>> {
+        if self.raw_lock.try_lock(false) {
+            Some(SmartMutexGuard(unsafe {
+                &mut *(self as *const Self as *mut Self)
+            }))
+        } else {
+            None
+        }
+    }
     pub fn force_unlock(&'a self) {
         self.raw_lock.unlock();
     }
@@ -7,7 +7,10 @@ use super::process;
 use super::process::CpuState;
 use super::safe_ffi;
 use super::scheduler;
-use super::scheduler::{Pid, PreemptionGuard, SCHEDULER};
+use super::scheduler::{
+    trace_pending_signals, trace_syscall_enter, trace_syscall_exit, Pid, PreemptionGuard,
+    SCHEDULER, SKIPPED_SYSCALL,
+};
 use super::signal_interface;
 use super::signal_interface::{sigset_t, StructSigaction};
 use super::thread;
@@ -16,28 +19,34 @@ use super::vfs;
 use super::IpcResult;
 use super::{IntoRawResult, SysResult};
 use libc_binding::{
-    ACCESS, CHDIR, CHMOD, CHOWN, CLONE, CLOSE, DUP, DUP2, EXECVE, EXIT, EXIT_QEMU, FCHMOD, FCHOWN,
-    FCNTL, FORK, FSTAT, FSTATFS, GETCWD, GETEGID, GETEUID, GETGID, GETGROUPS, GETHOSTNAME, GETPGID,
-    GETPGRP, GETPID, GETPPID, GETTIMEOFDAY, GETUID, GET_KERNEL_PROPERTIES, INSMOD, IOCTL, ISATTY,
-    IS_STR_VALID, KILL, LINK, LSEEK, LSMOD, LSTAT, MKDIR, MKNOD, MMAP, MOUNT, MPROTECT, MUNMAP,
-    NANOSLEEP, OPEN, OPENDIR, PAUSE, PIPE, READ, READLINK, REBOOT, RENAME, RMDIR, RMMOD, SETEGID,
-    SETEUID, SETGID, SETGROUPS, SETHOSTNAME, SETPGID, SETUID, SHUTDOWN, SIGACTION, SIGNAL,
-    SIGPROCMASK, SIGRETURN, SIGSUSPEND, SOCKETCALL, STACK_OVERFLOW, STAT, STATFS, SYMLINK,
-    TCGETATTR, TCGETPGRP, TCSETATTR, TCSETPGRP, TEST, TIMES, UMASK, UMOUNT, UNLINK, UTIME, WAIT4,
+    ACCESS, CHDIR, CHMOD, CHOWN, CLOCK_GETRES, CLOCK_GETTIME, CLOCK_NANOSLEEP, CLONE, CLOSE, DUP,
+    DUP2, EXECVE, EXIT, EXIT_GROUP, EXIT_QEMU, FCHMOD, FCHOWN, FCNTL, FORK, FSTAT, FSTATFS, FSYNC,
+    FUTEX, GETCWD, GETEGID, GETEUID, GETGID, GETGROUPS, GETHOSTNAME, GETITIMER, GETPGID, GETPGRP,
+    GETPID, GETPPID, GETPRIORITY, GETRLIMIT, GETSID, GETTIMEOFDAY, GETUID, GET_KERNEL_PROPERTIES,
+    INSMOD, IOCTL, ISATTY, IS_STR_VALID, KILL, LINK, LSEEK, LSMOD, LSTAT, MKDIR, MKNOD, MMAP,
+    MMAP2, MOUNT, MPROTECT, MSYNC, MUNMAP, NANOSLEEP, NICE, OPEN, OPENDIR, PAUSE, PIPE, PIVOT_ROOT,
+    POLL, PPOLL, PRLIMIT, PSELECT, PTRACE, READ, READLINK, REBOOT, RENAME, RMDIR, RMMOD,
+    SCHED_GETPARAM, SCHED_GETSCHEDULER, SCHED_SETSCHEDULER, SCHED_YIELD, SELECT, SETEGID, SETEUID,
+    SETGID, SETGROUPS, SETHOSTNAME, SETITIMER, SETPGID, SETPRIORITY, SETRLIMIT, SETSID, SETUID,
+    SET_THREAD_AREA, SHUTDOWN, SIGACTION, SIGALTSTACK, SIGNAL, SIGPROCMASK, SIGQUEUE, SIGRETURN,
+    SIGSUSPEND, SIGTIMEDWAIT, SOCKETCALL, STACK_OVERFLOW, STAT, STATFS, SYMLINK, SYNC, TCGETATTR,
+    TCGETPGRP, TCGETSID, TCSETATTR, TCSETPGRP, TEST, TIMES, UMASK, UMOUNT, UNLINK, UTIME, WAIT4,
     WAITPID, WRITE,
 };
 
+use crate::memory::tools::{NbrPages, Virt};
 use core::ffi::c_void;
 use i386::BaseRegisters;
 use interrupts::idt::{GateType, IdtGateEntry, InterruptTable};
 use libc_binding::Errno;
 use libc_binding::{
-    c_char, dev_t, gid_t, kernel, mode_t, off_t, rusage, termios, timeval, timezone, tms, uid_t,
-    utimbuf, DIR,
+    c_char, dev_t, fd_set, gid_t, itimerval, kernel, mode_t, off_t, pollfd, rlimit, rusage,
+    sched_param, siginfo_t, sigval, stack_t, termios, timespec, timeval, timezone, tms, uid_t,
+    user_desc, utimbuf, DIR,
 };
 
 mod mmap;
-use mmap::{sys_mmap, MmapArgStruct};
+use mmap::{sys_mmap, sys_mmap2, MmapArgStruct, MmapFlags};
 
 mod nanosleep;
 use nanosleep::{sys_nanosleep, TimeSpec};
@@ -70,6 +79,9 @@ use tcsetpgrp::sys_tcsetpgrp;
 mod tcgetpgrp;
 use tcgetpgrp::sys_tcgetpgrp;
 
+mod tcgetsid;
+use tcgetsid::sys_tcgetsid;
+
 mod getpid;
 use getpid::sys_getpid;
 
@@ -77,7 +89,7 @@ mod getppid;
 use getppid::sys_getppid;
 
 mod exit;
-use exit::sys_exit;
+use exit::{sys_exit, sys_exit_group};
 
 mod setgroups;
 use setgroups::sys_setgroups;
@@ -106,6 +118,21 @@ use sigaction::sys_sigaction;
 mod sigreturn;
 use sigreturn::sys_sigreturn;
 
+mod sigaltstack;
+use sigaltstack::sys_sigaltstack;
+
+mod sigqueue;
+use sigqueue::sys_sigqueue;
+
+mod sigtimedwait;
+use sigtimedwait::sys_sigtimedwait;
+
+mod futex;
+use futex::sys_futex;
+
+mod set_thread_area;
+use set_thread_area::sys_set_thread_area;
+
 mod pause;
 use pause::sys_pause;
 
@@ -118,6 +145,18 @@ use mprotect::{sys_mprotect, MmapProt};
 mod munmap;
 use munmap::sys_munmap;
 
+mod msync;
+use msync::sys_msync;
+
+mod fsync;
+use fsync::{sys_fsync, sys_sync};
+
+mod poll;
+use poll::{sys_poll, sys_ppoll};
+
+mod select;
+use select::{sys_pselect, sys_select};
+
 mod umask;
 use umask::sys_umask;
 
@@ -145,6 +184,12 @@ use getpgid::sys_getpgid;
 mod setpgid;
 use setpgid::sys_setpgid;
 
+mod setsid;
+use setsid::sys_setsid;
+
+mod getsid;
+use getsid::sys_getsid;
+
 mod getuid;
 use getuid::sys_getuid;
 
@@ -255,6 +300,8 @@ mod umount;
 use umount::sys_umount;
 mod mount;
 use mount::sys_mount;
+mod pivot_root;
+use pivot_root::sys_pivot_root;
 
 mod times;
 use times::sys_times;
@@ -265,6 +312,24 @@ use gethostname::{sys_gethostname, HOSTNAME};
 mod sethostname;
 use sethostname::sys_sethostname;
 
+mod nice;
+use nice::sys_nice;
+
+mod priority;
+use priority::{sys_getpriority, sys_setpriority};
+
+mod rlimit;
+use rlimit::{sys_getrlimit, sys_prlimit, sys_setrlimit};
+
+mod sched;
+use sched::{sys_sched_getparam, sys_sched_getscheduler, sys_sched_setscheduler, sys_sched_yield};
+
+mod itimer;
+use itimer::{sys_getitimer, sys_setitimer};
+
+mod clock;
+use clock::{sys_clock_getres, sys_clock_gettime, sys_clock_nanosleep};
+
 /*
  * Module kernel management
  */
@@ -281,6 +346,9 @@ use lsmod::sys_lsmod;
 mod get_kernel_properties;
 use get_kernel_properties::sys_get_kernel_properties;
 
+mod ptrace;
+use ptrace::sys_ptrace;
+
 mod trace_syscall;
 
 extern "C" {
@@ -302,9 +370,10 @@ pub unsafe extern "C" fn syscall_interrupt_handler(cpu_state: *mut CpuState) ->
             .unwrap()
             .update_global_time(TimeSession::User);
     });
+    // A tracer may change the syscall and its arguments at its entry
+    let sysnum = trace_syscall_enter(cpu_state).unwrap_or(SKIPPED_SYSCALL);
     #[allow(unused_variables)]
     let BaseRegisters {
-        eax,
         ebx,
         ecx,
         edx,
@@ -313,12 +382,14 @@ pub unsafe extern "C" fn syscall_interrupt_handler(cpu_state: *mut CpuState) ->
         ebp,
         ..
     } = (*cpu_state).registers;
+    let eax = sysnum;
 
     if eax != READ && eax != WRITE {
         // trace_syscall::trace_syscall(cpu_state);
     }
     let result = match eax {
-        EXIT => sys_exit(ebx as i32),       // This syscall doesn't return !
+        EXIT => sys_exit(ebx as i32), // This syscall doesn't return !
+        EXIT_GROUP => sys_exit_group(ebx as i32), // This syscall doesn't return !
         FORK => sys_fork(cpu_state as u32), // CpuState represents kernel_esp
         READ => sys_read(ebx as i32, ecx as *mut u8, edx as usize),
         WRITE => sys_write(ebx as i32, ecx as *const u8, edx as usize),
@@ -353,11 +424,18 @@ pub unsafe extern "C" fn syscall_interrupt_handler(cpu_state: *mut CpuState) ->
         ),
         SETUID => sys_setuid(ebx as uid_t),
         GETUID => sys_getuid(),
+        PTRACE => sys_ptrace(
+            ebx as u32,
+            ecx as Pid,
+            edx as *mut c_void,
+            esi as *mut c_void,
+        ),
         PAUSE => sys_pause(),
         FSTAT => sys_fstat(ebx as Fd, ecx as *mut libc_binding::stat),
         ACCESS => sys_access(ebx as *const c_char, ecx as u32),
         UTIME => sys_utime(ebx as *const c_char, ecx as *const utimbuf),
         KILL => sys_kill(ebx as i32, ecx as u32),
+        SYNC => sys_sync(),
         RENAME => sys_rename(ebx as *const c_char, ecx as *const c_char),
         MKDIR => sys_mkdir(ebx as *const c_char, ecx as mode_t),
         RMDIR => sys_rmdir(ebx as *const c_char),
@@ -370,9 +448,11 @@ pub unsafe extern "C" fn syscall_interrupt_handler(cpu_state: *mut CpuState) ->
         FCNTL => sys_fcntl(ebx as Fd, ecx as u32, edx as Fd),
         GETEGID => sys_getegid(),
         UMOUNT => sys_umount(ebx as *const c_char),
+        PIVOT_ROOT => sys_pivot_root(ebx as *const c_char, ecx as *const c_char),
         IOCTL => sys_ioctl(ebx as Fd, ecx as u32, edx as u32),
         SIGNAL => sys_signal(ebx as u32, ecx as usize),
         SETPGID => sys_setpgid(ebx as Pid, ecx as Pid),
+        SETSID => sys_setsid(),
         DUP2 => sys_dup2(ebx as u32, ecx as u32),
         GETPPID => sys_getppid(),
         GETPGRP => sys_getpgrp(),
@@ -393,23 +473,63 @@ pub unsafe extern "C" fn syscall_interrupt_handler(cpu_state: *mut CpuState) ->
         UMASK => sys_umask(ebx as mode_t),
         SOCKETCALL => sys_socketcall(ebx as u32, ecx as SocketArgsPtr),
         WAIT4 => sys_wait4(ebx as i32, ecx as *mut i32, edx as u32, esi as *mut rusage),
-        CLONE => sys_clone(cpu_state as u32, ebx as *const c_void, ecx as u32),
+        FSYNC => sys_fsync(ebx as Fd),
+        CLONE => sys_clone(
+            cpu_state as u32,
+            ebx as *const c_void,
+            ecx as u32,
+            edx as *mut Pid,
+            esi as *const user_desc,
+            edi as *mut Pid,
+        ),
         MPROTECT => sys_mprotect(
             ebx as *mut u8,
             ecx as usize,
             MmapProt::from_bits_truncate(edx),
         ),
+        SELECT => sys_select(
+            ebx as i32,
+            ecx as *mut fd_set,
+            edx as *mut fd_set,
+            esi as *mut fd_set,
+            edi as *mut timeval,
+        ),
+        MSYNC => sys_msync(ebx as *mut u8, ecx as usize, edx as u32),
+        MMAP2 => sys_mmap2(
+            Virt(ebx as usize),
+            ecx as usize,
+            MmapProt::from_bits_truncate(edx),
+            MmapFlags::from_bits_truncate(esi),
+            edi as i32,
+            NbrPages(ebp as usize),
+        ),
         SIGPROCMASK => sys_sigprocmask(ebx as u32, ecx as *const sigset_t, edx as *mut sigset_t),
         GETPGID => sys_getpgid(ebx as Pid),
+        GETSID => sys_getsid(ebx as Pid),
         STATFS => sys_statfs(ebx as *const c_char, ecx as *mut libc_binding::statfs),
         FSTATFS => sys_fstatfs(ebx as Fd, ecx as *mut libc_binding::statfs),
         NANOSLEEP => sys_nanosleep(ebx as *const TimeSpec, ecx as *mut TimeSpec),
+        POLL => sys_poll(ebx as *mut pollfd, ecx as u32, edx as i32),
         CHOWN => sys_chown(ebx as *const c_char, ecx as uid_t, edx as gid_t),
         FCHOWN => sys_fchown(ebx as Fd, ecx as uid_t, edx as gid_t),
         GETCWD => sys_getcwd(ebx as *mut c_char, ecx as usize),
         GETTIMEOFDAY => sys_gettimeofday(ebx as *mut timeval, ecx as *mut timezone),
         SIGRETURN => sys_sigreturn(cpu_state),
         SHUTDOWN => sys_shutdown(),
+        PSELECT => sys_pselect(
+            ebx as i32,
+            ecx as *mut fd_set,
+            edx as *mut fd_set,
+            esi as *mut fd_set,
+            edi as *const timespec,
+            ebp as *const sigset_t,
+        ),
+        PPOLL => sys_ppoll(
+            ebx as *mut pollfd,
+            ecx as u32,
+            edx as *const timespec,
+            esi as *const sigset_t,
+        ),
         TEST => sys_test(),
         STACK_OVERFLOW => sys_stack_overflow(0, 0, 0, 0, 0, 0),
         EXIT_QEMU => crate::tests::helpers::exit_qemu(ebx as u32),
@@ -417,6 +537,7 @@ pub unsafe extern "C" fn syscall_interrupt_handler(cpu_state: *mut CpuState) ->
         TCSETATTR => sys_tcsetattr(ebx as Fd, ecx as u32, edx as *const termios),
         TCSETPGRP => sys_tcsetpgrp(ebx as Fd, ecx as Pid),
         TCGETPGRP => sys_tcgetpgrp(ebx as Fd),
+        TCGETSID => sys_tcgetsid(ebx as Fd),
         SETEGID => sys_setegid(ebx as gid_t),
         SETEUID => sys_seteuid(ebx as uid_t),
         ISATTY => sys_isatty(ebx as u32),
@@ -424,6 +545,55 @@ pub unsafe extern "C" fn syscall_interrupt_handler(cpu_state: *mut CpuState) ->
         IS_STR_VALID => sys_is_str_valid(ebx as *const c_char),
         GETHOSTNAME => sys_gethostname(ebx as *mut c_char, ecx as usize),
         SETHOSTNAME => sys_sethostname(ebx as *const c_char, ecx as usize),
+        NICE => sys_nice(ebx as i32),
+        GETPRIORITY => sys_getpriority(ebx as u32, ecx as u32),
+        SETPRIORITY => sys_setpriority(ebx as u32, ecx as u32, edx as i32),
+        GETRLIMIT => sys_getrlimit(ebx as u32, ecx as *mut rlimit),
+        SETRLIMIT => sys_setrlimit(ebx as u32, ecx as *const rlimit),
+        PRLIMIT => sys_prlimit(
+            ebx as Pid,
+            ecx as u32,
+            edx as *const rlimit,
+            esi as *mut rlimit,
+        ),
+        SCHED_SETSCHEDULER => {
+            sys_sched_setscheduler(ebx as Pid, ecx as i32, edx as *const sched_param)
+        }
+        SCHED_GETSCHEDULER => sys_sched_getscheduler(ebx as Pid),
+        SCHED_GETPARAM => sys_sched_getparam(ebx as Pid, ecx as *mut sched_param),
+        SCHED_YIELD => sys_sched_yield(),
+        GETITIMER => sys_getitimer(ebx as u32, ecx as *mut itimerval),
+        SETITIMER => sys_setitimer(ebx as u32, ecx as *const itimerval, edx as *mut itimerval),
+        CLOCK_GETTIME => sys_clock_gettime(ebx as u32, ecx as *mut timespec),
+        CLOCK_GETRES => sys_clock_getres(ebx as u32, ecx as *mut timespec),
+        CLOCK_NANOSLEEP => sys_clock_nanosleep(
+            ebx as u32,
+            ecx as u32,
+            edx as *const timespec,
+            esi as *mut timespec,
+        ),
+        SIGALTSTACK => sys_sigaltstack(cpu_state, ebx as *const stack_t, ecx as *mut stack_t),
+        SIGQUEUE => sys_sigqueue(
+            ebx as Pid,
+            ecx as u32,
+            sigval {
+                sival_int: edx as i32,
+            },
+        ),
+        SIGTIMEDWAIT => sys_sigtimedwait(
+            ebx as *const sigset_t,
+            ecx as *mut siginfo_t,
+            edx as *const timespec,
+        ),
+        FUTEX => sys_futex(
+            ebx as *const i32,
+            ecx as u32,
+            edx as i32,
+            esi as *const timespec,
+            edi as *const i32,
+            ebp as i32,
+        ),
+        SET_THREAD_AREA => sys_set_thread_area(ebx as *mut user_desc),
 
         // Kernel module management
         INSMOD => sys_insmod(ebx as *const c_char),
@@ -433,8 +603,7 @@ pub unsafe extern "C" fn syscall_interrupt_handler(cpu_state: *mut CpuState) ->
         // Get informations from kernel
         GET_KERNEL_PROPERTIES => sys_get_kernel_properties(ebx as *mut kernel),
 
-        // set thread area: WTF
-        0xf3 => Err(Errno::EPERM),
+        SKIPPED_SYSCALL => Err(Errno::ENOSYS),
         sysnum => {
             log::warn!("Wrong syscall was called: {}", sysnum);
             Err(Errno::ENOSYS)
@@ -452,10 +621,13 @@ pub unsafe extern "C" fn syscall_interrupt_handler(cpu_state: *mut CpuState) ->
         // Return value will be on EAX. Errno always represents the low 7 bits
         (*cpu_state).registers.eax = result.into_raw_result();
     }
+    trace_syscall_exit();
     exit_from_syscall(cpu_state, is_in_blocked_syscall)
 }
 
 fn exit_from_syscall(cpu_state: *mut CpuState, is_in_blocked_syscall: bool) -> u32 {
+    // The tracer sees the signals before their delivery
+    let is_in_blocked_syscall = trace_pending_signals(cpu_state, is_in_blocked_syscall);
     let mut preemption_guard = PreemptionGuard::new();
     let mut scheduler = SCHEDULER.lock();
     // An exit() routine may be engaged by the exit() syscall - An exit() routine is already on execution
@@ -1,10 +1,10 @@
 use super::scheduler::SCHEDULER;
 use super::statfn;
-use super::vfs::Path;
+use super::vfs::{Path, VFS};
 use super::SysResult;
 use core::convert::TryFrom;
 
-use libc_binding::{c_char, stat, Amode, Errno, FileType};
+use libc_binding::{c_char, stat, Amode, Errno, FileType, MountFlags};
 
 pub fn sys_access(path: *const c_char, amode: u32) -> SysResult<u32> {
     unpreemptible_context!({
@@ -22,11 +22,21 @@ pub fn sys_access(path: *const c_char, amode: u32) -> SysResult<u32> {
         let creds = &tg.credentials;
 
         let path = Path::try_from(safe_path)?;
+        let mount_flags = VFS.lock().path_mount_flags(&tg.cwd, creds, &path)?;
         let mut buf: stat = Default::default();
         statfn(&scheduler, path, &mut buf)?;
         let amode = Amode::from_bits(amode).ok_or(Errno::EINVAL)?;
         let filetype =
             FileType::from_bits(buf.st_mode as u16).expect("filetypes bits should be valid");
+        if amode.contains(Amode::W_OK) && mount_flags.contains(MountFlags::MS_RDONLY) {
+            return Err(Errno::EROFS);
+        }
+        if amode.contains(Amode::X_OK)
+            && !filetype.is_directory()
+            && mount_flags.contains(MountFlags::MS_NOEXEC)
+        {
+            return Err(Errno::EACCES);
+        }
         let has_access = if creds.uid == 0 {
             // If a process has appropriate privileges:
             //
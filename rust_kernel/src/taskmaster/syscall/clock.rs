@@ -0,0 +1,209 @@
+//! clock_gettime, clock_getres and clock_nanosleep syscalls
+use super::nanosleep::sleep;
+use super::scheduler::SCHEDULER;
+use super::SysResult;
+use super::GLOBAL_TIME;
+
+use core::convert::TryFrom;
+use core::time::Duration;
+use libc_binding::{
+    timespec, Errno, CLOCK_MONOTONIC, CLOCK_PROCESS_CPUTIME_ID, CLOCK_REALTIME, TIMER_ABSTIME,
+};
+
+/// The clocks of the system
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Clock {
+    /// The time since the Epoch, given by the RTC and refined with the TSC
+    RealTime,
+    /// The time since the boot, measured with the TSC
+    Monotonic,
+    /// The CPU time consumed by the current process
+    ProcessCpuTime,
+}
+
+impl TryFrom<u32> for Clock {
+    type Error = Errno;
+
+    fn try_from(clock_id: u32) -> Result<Self, Self::Error> {
+        match clock_id {
+            CLOCK_REALTIME => Ok(Clock::RealTime),
+            CLOCK_MONOTONIC => Ok(Clock::Monotonic),
+            CLOCK_PROCESS_CPUTIME_ID => Ok(Clock::ProcessCpuTime),
+            _ => Err(Errno::EINVAL),
+        }
+    }
+}
+
+impl Clock {
+    /// Get the current value of the clock
+    fn now(self) -> Duration {
+        let global_time = unsafe { GLOBAL_TIME.as_mut().unwrap() };
+        match self {
+            Clock::RealTime => global_time.real_time(),
+            Clock::Monotonic => global_time.monotonic_time(),
+            Clock::ProcessCpuTime => {
+                // Add the time of the current schedule, not accounted yet
+                SCHEDULER
+                    .lock()
+                    .current_thread_group()
+                    .process_duration
+                    .cpu_time()
+                    + global_time.peek_process_time().cpu_time()
+            }
+        }
+    }
+
+    fn resolution(self) -> Duration {
+        match self {
+            Clock::RealTime | Clock::Monotonic => Duration::from_nanos(1),
+            // The CPU times are accounted in microseconds
+            Clock::ProcessCpuTime => Duration::from_micros(1),
+        }
+    }
+}
+
+fn duration_to_timespec(duration: Duration) -> timespec {
+    timespec {
+        tv_sec: duration.as_secs() as _,
+        tv_nsec: duration.subsec_nanos() as _,
+    }
+}
+
+pub fn timespec_to_duration(timespec: &timespec) -> SysResult<Duration> {
+    if timespec.tv_sec < 0 || timespec.tv_nsec < 0 || timespec.tv_nsec >= 1000000000 {
+        return Err(Errno::EINVAL);
+    }
+    Ok(Duration::new(
+        timespec.tv_sec as u64,
+        timespec.tv_nsec as u32,
+    ))
+}
+
+/// The clock_gettime() function shall return the current value tp for
+/// the specified clock, clock_id.
+///
+/// [EINVAL]
+///     The clock_id argument does not specify a known clock.
+pub fn sys_clock_gettime(clock_id: u32, tp: *mut timespec) -> SysResult<u32> {
+    unpreemptible_context!({
+        let tp = {
+            let scheduler = SCHEDULER.lock();
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            // Check if pointer exists in user virtual address space
+            v.make_checked_ref_mut(tp)?
+        };
+        *tp = duration_to_timespec(Clock::try_from(clock_id)?.now());
+        Ok(0)
+    })
+}
+
+/// The clock_getres() function shall return the resolution of any
+/// clock. If the argument res is not NULL, the resolution of the
+/// specified clock shall be stored in the location pointed to by res.
+///
+/// [EINVAL]
+///     The clock_id argument does not specify a known clock.
+pub fn sys_clock_getres(clock_id: u32, res: *mut timespec) -> SysResult<u32> {
+    unpreemptible_context!({
+        let res = {
+            let scheduler = SCHEDULER.lock();
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            // Check if pointer exists in user virtual address space
+            if res.is_null() {
+                None
+            } else {
+                Some(v.make_checked_ref_mut(res)?)
+            }
+        };
+        let clock = Clock::try_from(clock_id)?;
+        if let Some(res) = res {
+            *res = duration_to_timespec(clock.resolution());
+        }
+        Ok(0)
+    })
+}
+
+/// If the flag TIMER_ABSTIME is not set in the flags argument, the
+/// clock_nanosleep() function shall cause the current thread to be
+/// suspended from execution until either the time interval specified
+/// by the rqtp argument has elapsed, or a signal is delivered to the
+/// calling thread and its action is to invoke a signal-catching
+/// function, or the process is terminated. The clock used to measure
+/// the time shall be the clock specified by clock_id.
+///
+/// If the flag TIMER_ABSTIME is set in the flags argument, the
+/// clock_nanosleep() function shall cause the current thread to be
+/// suspended from execution until either the time value of the clock
+/// specified by clock_id reaches the absolute time specified by the
+/// rqtp argument, or a signal is delivered to the calling thread and
+/// its action is to invoke a signal-catching function, or the process
+/// is terminated. If, at the time of the call, the time value
+/// specified by rqtp is less than or equal to the time value of the
+/// specified clock, then clock_nanosleep() shall return immediately.
+///
+/// If the clock_nanosleep() function is interrupted by a signal and
+/// TIMER_ABSTIME is not set, the remaining time is stored in rmtp if
+/// it is not NULL.
+///
+/// [EINVAL]
+///     The rqtp argument specified a nanosecond value less than zero
+///     or greater than or equal to 1000 million, the clock_id argument
+///     does not specify a known clock, or specifies the CPU-time clock
+///     of the calling thread, on which sleeping is not supported.
+pub fn sys_clock_nanosleep(
+    clock_id: u32,
+    flags: u32,
+    rqtp: *const timespec,
+    rmtp: *mut timespec,
+) -> SysResult<u32> {
+    unpreemptible_context!({
+        let (rqtp, rmtp) = {
+            let scheduler = SCHEDULER.lock();
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            // Check if pointers exist in user virtual address space
+            let rmtp = if rmtp.is_null() {
+                None
+            } else {
+                Some(v.make_checked_ref_mut(rmtp)?)
+            };
+            (v.make_checked_ref(rqtp)?, rmtp)
+        };
+        let clock = Clock::try_from(clock_id)?;
+        if clock == Clock::ProcessCpuTime {
+            return Err(Errno::EINVAL);
+        }
+        let request = timespec_to_duration(rqtp)?;
+        let absolute = flags & TIMER_ABSTIME != 0;
+        let duration = if absolute {
+            let now = clock.now();
+            if request <= now {
+                return Ok(0);
+            }
+            request - now
+        } else {
+            request
+        };
+
+        match sleep(duration) {
+            Err(remaining_time) => {
+                if let (Some(rmtp), false) = (rmtp, absolute) {
+                    *rmtp = duration_to_timespec(remaining_time);
+                }
+                Err(Errno::EINTR)
+            }
+            Ok(()) => Ok(0),
+        }
+    })
+}
@@ -1,7 +1,9 @@
-use super::scheduler::SCHEDULER;
+use super::process::TlsSegment;
+use super::scheduler::{Pid, SCHEDULER};
 use super::SysResult;
 use bitflags::bitflags;
 use core::ffi::c_void;
+use libc_binding::user_desc;
 
 bitflags! {
     /// the clone flags
@@ -40,13 +42,59 @@ bitflags! {
 // flags=CLONE_VM|CLONE_FS|CLONE_FILES|CLONE_SIGHAND|CLONE_THREAD|CLONE_SYSVSEM|CLONE_SETTLS|CLONE_PARENT_SETTID|CLONE_CHILD_CLEARTID,
 // parent_tidptr=0x7ff03ba959d0, tls=0x7ff03ba95700,
 // child_tidptr=0x7ff03ba959d0) = 21807
+/// The arguments of clone() for the thread libraries, checked against the flags
+#[derive(Debug, Copy, Clone)]
+pub struct CloneArgs {
+    /// Where the child TID is set in the child memory (CHILD_SETTID)
+    /// and cleared when the child exits (CHILD_CLEARTID)
+    pub child_tid: Option<*mut Pid>,
+    /// TLS segment of the child (SETTLS)
+    pub tls: Option<TlsSegment>,
+}
+
 /// the clone syscall
-pub fn sys_clone(kernel_esp: u32, child_stack: *const c_void, clone_flags: u32) -> SysResult<u32> {
+pub fn sys_clone(
+    kernel_esp: u32,
+    child_stack: *const c_void,
+    clone_flags: u32,
+    parent_tid: *mut Pid,
+    tls: *const user_desc,
+    child_tid: *mut Pid,
+) -> SysResult<u32> {
     let flags = CloneFlags::from_bits_truncate(clone_flags);
 
     unpreemptible_context!({
-        Ok(SCHEDULER
-            .lock()
-            .current_thread_clone(kernel_esp, child_stack, flags)? as u32)
+        let mut scheduler = SCHEDULER.lock();
+
+        let (parent_tid, args) = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            let parent_tid = if flags.contains(CloneFlags::PARENT_SETTID) {
+                Some(v.make_checked_ref_mut(parent_tid)?)
+            } else {
+                None
+            };
+            let child_tid =
+                if flags.intersects(CloneFlags::CHILD_SETTID | CloneFlags::CHILD_CLEARTID) {
+                    Some(v.make_checked_ref_mut(child_tid)? as *mut Pid)
+                } else {
+                    None
+                };
+            let tls = if flags.contains(CloneFlags::SETTLS) {
+                Some(TlsSegment::new(v.make_checked_ref(tls)?)?)
+            } else {
+                None
+            };
+            (parent_tid, CloneArgs { child_tid, tls })
+        };
+
+        let child_id = scheduler.current_thread_clone(kernel_esp, child_stack, flags, args)?;
+        if let Some(parent_tid) = parent_tid {
+            *parent_tid = child_id;
+        }
+        Ok(child_id as u32)
     })
 }
@@ -8,12 +8,13 @@ use super::process::{
 use super::safe_ffi::CStringArray;
 use super::scheduler::SCHEDULER;
 use super::thread::ProcessState;
+use super::thread_group::Resource;
 use fallible_collections::TryClone;
 use libc_binding::c_char;
 
 use core::convert::TryFrom;
 
-use libc_binding::{Amode, Errno, FileType};
+use libc_binding::{Amode, Errno, FileType, MountFlags, Signum};
 
 use super::vfs::{Path, VFS};
 
@@ -123,6 +124,7 @@ pub fn sys_execve(
         let tg = scheduler.current_thread_group_mut();
         let creds = &tg.credentials;
         let cwd = &tg.cwd;
+        let traced = tg.tracee.is_some();
         // This seems unefficient since pathname resolution will be executed a lot of times:
         // here and in get_file_content.
 
@@ -135,7 +137,19 @@ pub fn sys_execve(
                 return Err(Errno::EACCESS);
             }
 
-            filetype = vfs.file_type(cwd, creds, &pathname)?;
+            filetype = {
+                let mut filetype = vfs.file_type(cwd, creds, &pathname)?;
+                // The set-user-ID and set-group-ID bits are ignored on a nosuid mount, and
+                // for a traced process, as its tracer could take the new identity
+                if traced
+                    || vfs
+                        .path_mount_flags(cwd, creds, &pathname)?
+                        .contains(MountFlags::MS_NOSUID)
+                {
+                    filetype.remove(FileType::SET_USER_ID | FileType::SET_GROUP_ID);
+                }
+                filetype
+            };
             // (owner, group) = vfs.get_file_owner(cwd, creds, &pathname)?; this does not compile...
             let (tmp_owner, tmp_group) = vfs.get_file_owner(cwd, creds, &pathname)?;
             owner = tmp_owner;
@@ -145,16 +159,29 @@ pub fn sys_execve(
 
         let tg = scheduler.current_thread_group_mut();
 
-        tg.environ = Some(envp_content.try_clone()?);
-        tg.argv = Some(argv_content.try_clone()?);
+        let environ = envp_content.try_clone()?;
+        let argv = argv_content.try_clone()?;
+        let arguments = ProcessArguments::new(argv_content, envp_content);
+        // Like on linux, the arguments cannot take more than a quarter of the stack
+        if let Some(limit) = tg.soft_limit(Resource::Stack) {
+            if arguments.get_serialized_len(4).ok_or(Errno::E2BIG)? > limit / 4 {
+                return Err(Errno::E2BIG);
+            }
+        }
+
+        tg.environ = Some(environ);
+        tg.argv = Some(argv);
         tg.filename = Some(pathname);
 
-        let mut new_process = unsafe {
-            UserProcess::new(
-                ProcessOrigin::Elf(content.as_ref()),
-                Some(ProcessArguments::new(argv_content, envp_content)),
-            )?
-        };
+        let mut new_process =
+            unsafe { UserProcess::new(ProcessOrigin::Elf(content.as_ref()), Some(arguments))? };
+
+        // The new process image cannot exceed RLIMIT_AS
+        if let Some(limit) = tg.soft_limit(Resource::AddressSpace) {
+            if new_process.get_address_space_size().to_bytes() > limit {
+                return Err(Errno::ENOMEM);
+            }
+        }
 
         // Save the euid/egid as POSIX specifies.
         tg.credentials.suid = tg.credentials.euid;
@@ -211,6 +238,14 @@ pub fn sys_execve(
             .signal
             .reset_for_new_process_image();
 
+        // A traced process stops with a SIGTRAP at the start of the new program
+        if scheduler.current_thread_group().tracee.is_some() {
+            let _r = scheduler
+                .current_thread_mut()
+                .signal
+                .generate_signal(Signum::SIGTRAP);
+        }
+
         // Set the argc argument: EAX
         argv_content_len as u32
     });
@@ -129,7 +129,7 @@ use libc_binding::EXITED_STATUS_BITS;
 ///  to trace event type identifiers of any process built for these
 ///  trace streams may be deallocated. [Option End]
 
-pub unsafe fn sys_exit(status: i32) -> SysResult<u32> {
+pub unsafe fn sys_exit_group(status: i32) -> SysResult<u32> {
     // Avoid preempting when we are on the exit routine
     unpreemptible();
     // Status & EXITED_STATUS_BITS (0xff) to avoid negative bullshit status
@@ -138,3 +138,19 @@ pub unsafe fn sys_exit(status: i32) -> SysResult<u32> {
         .current_thread_group_exit(Status::Exited(status & EXITED_STATUS_BITS as i32));
     Ok(0)
 }
+
+/// Terminate the calling thread. If it was created with
+/// CLONE_CHILD_CLEARTID, the TID stored at the given address is set
+/// to 0 and a FUTEX_WAKE is done on it, so a thread joining it is
+/// woken.
+///
+/// When the calling thread is the last one of its process, or its
+/// main thread, the whole process terminates as with exit_group().
+pub unsafe fn sys_exit(status: i32) -> SysResult<u32> {
+    // Avoid preempting when we are on the exit routine
+    unpreemptible();
+    SCHEDULER
+        .lock()
+        .current_thread_exit(Status::Exited(status & EXITED_STATUS_BITS as i32));
+    Ok(0)
+}
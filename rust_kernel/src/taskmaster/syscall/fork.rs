@@ -1,6 +1,8 @@
 use super::clone::sys_clone;
+use super::scheduler::Pid;
 use super::SysResult;
 use core::ffi::c_void;
+use libc_binding::user_desc;
 
 /// The fork() function shall create a new process. The new process
 /// (child process) shall be an exact copy of the calling process
@@ -140,5 +142,8 @@ pub fn sys_fork(kernel_esp: u32) -> SysResult<u32> {
         kernel_esp,
         0 as *const c_void,
         0, /*CLONE_CHILD_CLEARTID|CLONE_CHILD_SETTID|SIGCHLD*/
+        0 as *mut Pid,
+        0 as *const user_desc,
+        0 as *mut Pid,
     )
 }
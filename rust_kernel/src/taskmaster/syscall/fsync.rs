@@ -0,0 +1,54 @@
+//! sys_sync and sys_fsync implementations
+
+use super::SysResult;
+
+use super::scheduler::SCHEDULER;
+use super::Fd;
+
+use crate::drivers::storage::BLOCK_CACHE;
+
+/// The sync() function shall cause all information in memory that
+/// updates file systems to be scheduled for writing out to all file
+/// systems.
+///
+/// The writing, although scheduled, is not necessarily complete upon
+/// return from sync(). On turbofish, the dirty sectors of the block
+/// cache are written back before sync() returns.
+pub fn sys_sync() -> SysResult<u32> {
+    unpreemptible_context!({
+        if let Err(e) = BLOCK_CACHE.lock().sync(None) {
+            log::error!("sync failed: {:?}", e);
+        }
+    });
+    Ok(0)
+}
+
+/// The fsync() function shall request that all data for the open file
+/// descriptor named by fildes is to be transferred to the storage
+/// device associated with the file described by fildes.
+///
+/// The fsync() function shall not return until the system has
+/// completed that action or until an error is detected.
+///
+/// The fsync() function shall fail if:
+///
+/// [EBADF]
+///     The fildes argument is not a valid descriptor.
+/// [EINVAL]
+///     The fildes argument does not refer to a file on which this
+///     operation is possible.
+/// [EIO]
+///     An I/O error occurred while reading from or writing to the
+///     file system.
+pub fn sys_fsync(fd: Fd) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        let fd_interface = &scheduler
+            .current_thread_group_running()
+            .file_descriptor_interface;
+
+        let file_operation = &mut fd_interface.get_file_operation(fd)?;
+        file_operation.fsync()
+    })
+}
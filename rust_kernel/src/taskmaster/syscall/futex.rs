@@ -0,0 +1,131 @@
+//! futex syscall
+use super::clock::timespec_to_duration;
+use super::nanosleep::pit_deadline;
+use super::scheduler::{auto_preempt, SCHEDULER};
+use super::thread::WaitingState;
+use super::SysResult;
+
+use libc_binding::{
+    timespec, Errno, FUTEX_CMP_REQUEUE, FUTEX_PRIVATE_FLAG, FUTEX_REQUEUE, FUTEX_WAIT, FUTEX_WAKE,
+};
+
+/// The futex() system call provides a method for waiting until a
+/// certain condition becomes true. It is typically used as a
+/// blocking construct in the context of shared-memory
+/// synchronization: the threads spin in user space and only call the
+/// kernel to sleep or to wake the sleepers.
+///
+/// FUTEX_WAIT
+///     Tests that the value at the futex word pointed to by uaddr
+///     still contains the expected value val, and if so, then sleeps
+///     waiting for a FUTEX_WAKE operation on the futex word. If the
+///     timeout is not NULL, it specifies a relative timeout for the
+///     wait.
+/// FUTEX_WAKE
+///     Wakes at most val of the waiters that are waiting on the futex
+///     word at the address uaddr.
+/// FUTEX_REQUEUE
+///     Wakes at most val of the waiters and requeues at most val2 of
+///     the remaining ones on the futex at the address uaddr2. val2 is
+///     given in place of the timeout argument.
+/// FUTEX_CMP_REQUEUE
+///     Same as FUTEX_REQUEUE, once checked that the value at uaddr is
+///     still val3.
+///
+/// FUTEX_WAIT returns 0 if the caller was woken up, the other
+/// operations return the number of woken and requeued waiters.
+///
+/// [EAGAIN]
+///     (FUTEX_WAIT) The value pointed to by uaddr was not equal to
+///     the expected value val at the time of the call.
+/// [EAGAIN]
+///     (FUTEX_CMP_REQUEUE) The value pointed to by uaddr is not
+///     equal to the expected value val3.
+/// [EFAULT]
+///     A required pointer argument did not point to a valid
+///     user-space address.
+/// [EINTR]
+///     A FUTEX_WAIT operation was interrupted by a signal.
+/// [EINVAL]
+///     The supplied timeout argument was invalid, or uaddr is not
+///     aligned on a four-byte boundary.
+/// [ENOSYS]
+///     Invalid operation specified in futex_op.
+/// [ETIMEDOUT]
+///     The operation in futex_op employed the timeout specified in
+///     timeout, and the timeout expired before the operation
+///     completed.
+pub fn sys_futex(
+    uaddr: *const i32,
+    futex_op: u32,
+    val: i32,
+    timeout: *const timespec,
+    uaddr2: *const i32,
+    val3: i32,
+) -> SysResult<u32> {
+    unpreemptible_context!({
+        if uaddr as usize % 4 != 0 {
+            return Err(Errno::EINVAL);
+        }
+        let mut scheduler = SCHEDULER.lock();
+        let v = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator();
+        let futex_word = v.make_checked_ref(uaddr)?;
+
+        // All the futexes are private to an address space
+        let futex_op = futex_op & !FUTEX_PRIVATE_FLAG;
+        match futex_op {
+            FUTEX_WAIT => {
+                let deadline = if timeout.is_null() {
+                    None
+                } else {
+                    Some(pit_deadline(timespec_to_duration(
+                        v.make_checked_ref(timeout)?,
+                    )?))
+                };
+                drop(v);
+
+                // We are unpreemptible: nobody can change the futex word until we sleep
+                if *futex_word != val {
+                    return Err(Errno::EAGAIN);
+                }
+                scheduler
+                    .current_thread_mut()
+                    .set_waiting(WaitingState::Futex {
+                        uaddr: uaddr as u32,
+                        timeout: deadline,
+                    });
+                drop(scheduler);
+
+                // The waker, a signal or the timeout can end the wait
+                auto_preempt()?;
+                Ok(0)
+            }
+            FUTEX_WAKE => {
+                drop(v);
+                Ok(scheduler.futex_wake(uaddr as u32, val as u32, None))
+            }
+            FUTEX_REQUEUE | FUTEX_CMP_REQUEUE => {
+                if uaddr2 as usize % 4 != 0 {
+                    return Err(Errno::EINVAL);
+                }
+                v.make_checked_ref(uaddr2)?;
+                drop(v);
+
+                if futex_op == FUTEX_CMP_REQUEUE && *futex_word != val3 {
+                    return Err(Errno::EAGAIN);
+                }
+                // The timeout argument is the maximum number of requeued waiters
+                let max_requeue = timeout as u32;
+                Ok(scheduler.futex_wake(
+                    uaddr as u32,
+                    val as u32,
+                    Some((uaddr2 as u32, max_requeue)),
+                ))
+            }
+            _ => Err(Errno::ENOSYS),
+        }
+    })
+}
@@ -1,17 +1,14 @@
-use super::kmodules::CURRENT_UNIX_TIME;
 use super::scheduler::SCHEDULER;
+use super::thread_group::duration_to_timeval;
 use super::SysResult;
-use libc_binding::{time_t, timeval, timezone};
+use super::GLOBAL_TIME;
+use libc_binding::{timeval, timezone};
 
 use core::ptr;
-use core::sync::atomic::Ordering;
 
 fn gettimeofday(timeval: Option<&mut timeval>, _timezone: Option<&mut timezone>) -> SysResult<u32> {
-    let current_unix_time = unsafe { CURRENT_UNIX_TIME.load(Ordering::Acquire) };
-
     if let Some(timeval) = timeval {
-        timeval.tv_sec = current_unix_time as time_t;
-        timeval.tv_usec = 0; // No fucks given.
+        *timeval = duration_to_timeval(unsafe { GLOBAL_TIME.as_mut().unwrap().real_time() });
     }
     Ok(0)
 }
@@ -0,0 +1,87 @@
+//! getitimer and setitimer syscalls
+use super::scheduler::SCHEDULER;
+use super::thread_group::ITimer;
+use super::SysResult;
+use super::GLOBAL_TIME;
+
+use core::convert::TryFrom;
+use libc_binding::itimerval;
+
+/// The getitimer() function shall store the current value of the
+/// timer specified by which into the structure pointed to by value.
+///
+/// [EINVAL]
+///     The which argument is not recognized.
+pub fn sys_getitimer(which: u32, value: *mut itimerval) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let value = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            // Check if pointer exists in user virtual address space
+            v.make_checked_ref_mut(value)?
+        };
+        let which = ITimer::try_from(which)?;
+        let now = unsafe { GLOBAL_TIME.as_ref().unwrap().monotonic_time() };
+        *value = scheduler.current_thread_group_mut().itimers.get(which, now);
+        Ok(0)
+    })
+}
+
+/// The setitimer() function shall set the timer specified by which to
+/// the value specified in the structure pointed to by value, and if
+/// ovalue is not a null pointer, store the previous value of the
+/// timer in the structure pointed to by ovalue.
+///
+/// A timer value is defined by the itimerval structure. If it_value is
+/// non-zero, it shall indicate the time to the next timer
+/// expiration. If it_interval is non-zero, it shall specify a value to
+/// be used in reloading it_value when the timer expires. Setting
+/// it_value to 0 shall disable a timer, regardless of the value of
+/// it_interval. Setting it_interval to 0 shall disable a timer after
+/// its next expiration (assuming it_value is non-zero).
+///
+/// The timers are checked by the scheduler, so their resolution is
+/// the scheduling period.
+///
+/// [EINVAL]
+///     The value argument is not in canonical form, or the which
+///     argument is not recognized.
+pub fn sys_setitimer(
+    which: u32,
+    value: *const itimerval,
+    ovalue: *mut itimerval,
+) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let (value, ovalue) = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            // Check if pointers exist in user virtual address space
+            let ovalue = if ovalue.is_null() {
+                None
+            } else {
+                Some(v.make_checked_ref_mut(ovalue)?)
+            };
+            (v.make_checked_ref(value)?, ovalue)
+        };
+        let which = ITimer::try_from(which)?;
+        let now = unsafe { GLOBAL_TIME.as_ref().unwrap().monotonic_time() };
+        let old_value = scheduler
+            .current_thread_group_mut()
+            .itimers
+            .set(which, value, now)?;
+        if let Some(ovalue) = ovalue {
+            *ovalue = old_value;
+        }
+        Ok(0)
+    })
+}
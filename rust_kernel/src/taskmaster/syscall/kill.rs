@@ -1,12 +1,51 @@
 use super::SysResult;
 
 use super::scheduler::{auto_preempt, Pid, SCHEDULER};
-use super::signal_interface::JobAction;
+use super::signal_interface::{siginfo, JobAction};
 use super::thread_group::{Credentials, ThreadGroup};
 
 use core::convert::TryInto;
 use libc_binding::Errno;
-use libc_binding::{uid_t, Signum};
+use libc_binding::{siginfo_t, uid_t, Signum, SI_USER};
+
+/// Send a signal to the thread groups of `iter` which the sender has
+/// the permission to signal
+pub fn generate_signal<'a, T: Iterator<Item = &'a mut ThreadGroup>>(
+    iter: T,
+    info: siginfo_t,
+    self_uid: uid_t,
+    self_euid: uid_t,
+) -> SysResult<u32> {
+    let mut present = false;
+    let mut has_perm = false;
+    for tg in iter {
+        if let Some(_thread) = tg.get_first_thread() {
+            present = true;
+        }
+        // For a process to have permission to send a signal to a process
+        // designated by pid, unless the sending process has appropriate
+        // privileges, the real or effective user ID of the sending process
+        // shall match the real or saved set-user-ID of the receiving
+        // process.
+        if tg.credentials.uid == self_uid
+            || tg.credentials.uid == self_euid
+            || tg.credentials.euid == self_uid
+            || tg.credentials.euid == self_euid
+        {
+            if let Some(thread) = tg.get_first_thread() {
+                thread.signal.generate_signal_info(info)?;
+                has_perm = true;
+            }
+        }
+    }
+    if !present {
+        return Err(Errno::ESRCH);
+    }
+    if !has_perm {
+        return Err(Errno::EPERM);
+    }
+    Ok(0)
+}
 
 /// The kill() function shall send a signal to a process or a group of
 /// processes specified by pid. The signal to be sent is specified by
@@ -59,44 +98,8 @@ use libc_binding::{uid_t, Signum};
 /// send sig to any of the processes specified by pid. If kill()
 /// fails, no signal shall be sent.
 pub unsafe fn sys_kill(mut pid: i32, signum: u32) -> SysResult<u32> {
-    fn generate_signal<'a, T: Iterator<Item = &'a mut ThreadGroup>>(
-        iter: T,
-        signum: Signum,
-        self_uid: uid_t,
-        self_euid: uid_t,
-    ) -> SysResult<u32> {
-        let mut present = false;
-        let mut has_perm = false;
-        for tg in iter {
-            if let Some(_thread) = tg.get_first_thread() {
-                present = true;
-            }
-            // For a process to have permission to send a signal to a process
-            // designated by pid, unless the sending process has appropriate
-            // privileges, the real or effective user ID of the sending process
-            // shall match the real or saved set-user-ID of the receiving
-            // process.
-            if tg.credentials.uid == self_uid
-                || tg.credentials.uid == self_euid
-                || tg.credentials.euid == self_uid
-                || tg.credentials.euid == self_euid
-            {
-                if let Some(thread) = tg.get_first_thread() {
-                    thread.signal.generate_signal(signum)?;
-                    has_perm = true;
-                }
-            }
-        }
-        if !present {
-            return Err(Errno::ESRCH);
-        }
-        if !has_perm {
-            return Err(Errno::EPERM);
-        }
-        Ok(0)
-    }
     unpreemptible_context!({
-        let signum = signum.try_into().map_err(|_| Errno::EINVAL)?;
+        let signum: Signum = signum.try_into().map_err(|_| Errno::EINVAL)?;
         let mut scheduler = SCHEDULER.lock();
 
         let Credentials {
@@ -105,6 +108,12 @@ pub unsafe fn sys_kill(mut pid: i32, signum: u32) -> SysResult<u32> {
             ..
         } = scheduler.current_thread_group().credentials;
 
+        let info = siginfo_t {
+            si_pid: scheduler.current_task_id().0,
+            si_uid: self_uid,
+            ..siginfo(signum, SI_USER as i32)
+        };
+
         if pid == 0 || pid < -1 {
             if pid == 0 {
                 pid = -scheduler.current_thread_group().pgid;
@@ -119,21 +128,21 @@ pub unsafe fn sys_kill(mut pid: i32, signum: u32) -> SysResult<u32> {
                             None
                         }
                     }),
-                signum,
+                info,
                 self_uid,
                 self_euid,
             )
         } else if pid == -1 {
             generate_signal(
                 scheduler.iter_thread_groups_mut(),
-                signum,
+                info,
                 self_uid,
                 self_euid,
             )
         } else {
             generate_signal(
                 scheduler.get_thread_group_mut(pid).into_iter(),
-                signum,
+                info,
                 self_uid,
                 self_euid,
             )
@@ -2,38 +2,45 @@
 
 use super::SysResult;
 
-use super::scheduler::SCHEDULER;
+use super::scheduler::{Scheduler, SCHEDULER};
+use super::thread_group::Resource;
+use super::vfs::VFS;
 use super::MmapProt;
 
 use bitflags::bitflags;
+use libc_binding::{off_t, Errno};
 
-use crate::memory::tools::{AllocFlags, NbrPages, Virt};
+use crate::memory::address_space::FileMapping;
+use crate::memory::tools::{Address, AllocFlags, NbrPages, Virt, PAGE_SIZE};
+use crate::memory::AddressSpace;
 
 /// This structure is the argument structure of the mmap syscall
 #[derive(Debug, Copy, Clone)]
+#[repr(C)]
 pub struct MmapArgStruct {
     virt_addr: Virt, // Virt has the same sizeof of an address (newtype based on usize)
     length: usize,
     prot: MmapProt,
     flags: MmapFlags,
     fd: i32,
-    offset: usize,
+    offset: off_t,
 }
 
 /// Map files or devices into memory
 pub fn sys_mmap(mmap_arg: *const MmapArgStruct) -> SysResult<u32> {
     unpreemptible_context!({
-        let mut scheduler = SCHEDULER.lock();
+        let scheduler = SCHEDULER.lock();
 
-        let mut v = scheduler
-            .current_thread_mut()
-            .unwrap_process_mut()
-            .get_virtual_allocator();
+        let mmap_arg = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
 
-        // Check if pointer exists in user virtual address space
-        let mmap_arg = v.make_checked_ref(mmap_arg)?;
+            // Check if pointer exists in user virtual address space
+            *v.make_checked_ref(mmap_arg)?
+        };
 
-        #[allow(unused_variables)]
         let MmapArgStruct {
             virt_addr,
             length,
@@ -41,31 +48,160 @@ pub fn sys_mmap(mmap_arg: *const MmapArgStruct) -> SysResult<u32> {
             flags,
             fd,
             offset,
-        } = *mmap_arg;
+        } = mmap_arg;
+
+        if offset < 0 {
+            return Err(Errno::EINVAL);
+        }
+        mmap(
+            &scheduler,
+            virt_addr,
+            length,
+            prot,
+            flags,
+            fd,
+            offset as u64,
+        )
+    })
+}
+
+/// Map files or devices into memory. The offset is given in pages.
+pub fn sys_mmap2(
+    addr: Virt,
+    length: usize,
+    prot: MmapProt,
+    flags: MmapFlags,
+    fd: i32,
+    pgoffset: NbrPages,
+) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
 
-        let alloc_flags = AllocFlags::from(prot);
-        let addr = v.alloc(length, alloc_flags)?;
+        mmap(
+            &scheduler,
+            addr,
+            length,
+            prot,
+            flags,
+            fd,
+            pgoffset.to_bytes() as u64,
+        )
+    })
+}
+
+/// Common part of sys_mmap and sys_mmap2.
+/// Anonymous mappings are allocated immediately, the pages of a file mapping are loaded from the file on their first access.
+/// A MAP_FIXED mapping discards the previous mappings of its area
+fn mmap(
+    scheduler: &Scheduler,
+    virt_addr: Virt,
+    length: usize,
+    prot: MmapProt,
+    flags: MmapFlags,
+    fd: i32,
+    offset: u64,
+) -> SysResult<u32> {
+    if length == 0 {
+        return Err(Errno::EINVAL);
+    }
+    let fixed_addr = if flags.contains(MmapFlags::MAP_FIXED) {
+        if !virt_addr.is_aligned_on(PAGE_SIZE)
+            || !AddressSpace::is_user_range(virt_addr.into(), NbrPages::from(length))
+        {
+            return Err(Errno::EINVAL);
+        }
+        Some(virt_addr.0 as *mut u8)
+    } else {
+        None
+    };
+    let alloc_flags = AllocFlags::from(prot);
+
+    // The address space cannot exceed RLIMIT_AS
+    if let Some(limit) = scheduler
+        .current_thread_group()
+        .soft_limit(Resource::AddressSpace)
+    {
+        let size = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator()
+            .nbr_user_pages()
+            + NbrPages::from(length);
+        if size.to_bytes() > limit {
+            return Err(Errno::ENOMEM);
+        }
+    }
+
+    if flags.contains(MmapFlags::MAP_ANONYMOUS) {
+        // The anonymous pages are copied-on-write at fork, they cannot be shared
+        if flags.contains(MmapFlags::MAP_SHARED) {
+            return Err(Errno::EINVAL);
+        }
+        let mut v = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator();
+
+        let addr = match fixed_addr {
+            Some(addr) => {
+                v.unmap_range(virt_addr.into(), NbrPages::from(length))?;
+                v.alloc_on(addr, length, alloc_flags)?
+            }
+            None => v.alloc(length, alloc_flags)?,
+        };
         if !alloc_flags.contains(AllocFlags::READ_ONLY) {
             unsafe {
                 addr.write_bytes(0, length);
             }
         }
         // log::info!("Claiming mmap of {:?} sending {:#X?}", NbrPages::from(length), addr);
-        Ok(addr as u32)
-    })
-}
+        return Ok(addr as u32);
+    }
 
-/// Map files or devices into memory
-#[allow(dead_code)]
-pub unsafe fn sys_mmap2(
-    _addr: Virt,
-    _length: usize,
-    _prot: MmapProt,
-    _flags: MmapFlags,
-    _fd: i32,
-    _pgoffset: NbrPages,
-) -> SysResult<i32> {
-    unimplemented!();
+    let shared = match (
+        flags.contains(MmapFlags::MAP_SHARED),
+        flags.contains(MmapFlags::MAP_PRIVATE),
+    ) {
+        (true, false) => true,
+        (false, true) => false,
+        _ => return Err(Errno::EINVAL),
+    };
+    if offset % PAGE_SIZE as u64 != 0 {
+        return Err(Errno::EINVAL);
+    }
+
+    let (open_flags, file_operation) = scheduler
+        .current_thread_group_running()
+        .file_descriptor_interface
+        .get_open_file(fd as _)?;
+
+    // Only the regular files can be mapped
+    let inode_id = file_operation
+        .lock()
+        .get_inode_id()
+        .map_err(|_| Errno::ENODEV)?;
+    if !VFS.lock().get_inode(inode_id)?.is_regular() {
+        return Err(Errno::ENODEV);
+    }
+
+    if !open_flags.is_open_for_read()
+        || (shared && prot.contains(MmapProt::WRITE) && !open_flags.is_open_for_write())
+    {
+        return Err(Errno::EACCES);
+    }
+
+    let mut v = scheduler
+        .current_thread()
+        .unwrap_process()
+        .get_virtual_allocator();
+
+    let addr = v.map_file(
+        fixed_addr,
+        length,
+        alloc_flags,
+        FileMapping::new(file_operation, inode_id, offset, shared),
+    )?;
+    Ok(addr as u32)
 }
 
 bitflags! {
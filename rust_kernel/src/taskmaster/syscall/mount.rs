@@ -1,37 +1,79 @@
 use super::scheduler::SCHEDULER;
-use super::vfs::{Path, VFS};
+use super::vfs::{FileSystemType, Path, VFS};
 use super::SysResult;
 use core::convert::TryFrom;
 use core::ffi::c_void;
 
-use libc_binding::c_char;
+use libc_binding::{c_char, Errno, MountFlags};
 
-/// Simply mount source on directory target for the moment
+/// Mount the source on the directory target. `filesystemtype` is
+/// the name of a filesystem of the registry, "ext2" when it is NULL.
+/// For a tmpfs, the source is only a name and `data` may contain the
+/// comma separated options size=N[k|m|g], nr_inodes=N and mode=OCTAL.
+/// For a vfat, the owner and the permissions of the files are given by
+/// the options uid=N, gid=N, umask=OCTAL, fmask=OCTAL, dmask=OCTAL and quiet.
+///
+/// With MS_REMOUNT, the flags of the mount on target are changed.
+/// With MS_BIND, the directory source is made visible on target.
+/// With MS_MOVE, the mount on source is moved on target.
+/// The filesystem type and data are then ignored
 pub fn sys_mount(
     source: *const c_char,
     target: *const c_char,
-    _filesystemtype: *const c_char,
-    _mountflags: u32,
-    _data: *const c_void,
+    filesystemtype: *const c_char,
+    mountflags: u32,
+    data: *const c_void,
 ) -> SysResult<u32> {
     unpreemptible_context!({
         let scheduler = SCHEDULER.lock();
-        let (safe_source, safe_target) = {
+        let (safe_source, safe_target, safe_fs_type, safe_data) = {
             let v = scheduler
                 .current_thread()
                 .unwrap_process()
                 .get_virtual_allocator();
 
-            (v.make_checked_str(source)?, v.make_checked_str(target)?)
+            let fs_type = if filesystemtype.is_null() {
+                None
+            } else {
+                Some(v.make_checked_str(filesystemtype)?)
+            };
+            let data = if data.is_null() {
+                None
+            } else {
+                Some(v.make_checked_str(data as *const c_char)?)
+            };
+            // The source of a remount is ignored
+            let source = if source.is_null() {
+                None
+            } else {
+                Some(v.make_checked_str(source)?)
+            };
+            (source, v.make_checked_str(target)?, fs_type, data)
         };
 
         let tg = scheduler.current_thread_group();
         let creds = &tg.credentials;
+        if !creds.is_root() {
+            return Err(Errno::EPERM);
+        }
         let cwd = &tg.cwd;
-        let source = Path::try_from(safe_source)?;
+        let source =
+            || -> SysResult<Path> { Ok(Path::try_from(safe_source.ok_or(Errno::EFAULT)?)?) };
         let target = Path::try_from(safe_target)?;
+        let flags = MountFlags::from_bits(mountflags).ok_or(Errno::EINVAL)?;
 
-        VFS.lock().mount(cwd, creds, source, target)?;
+        if flags.contains(MountFlags::MS_REMOUNT) {
+            VFS.lock().remount(cwd, creds, &target, flags)?;
+        } else if flags.contains(MountFlags::MS_MOVE) {
+            VFS.lock().move_mount(cwd, creds, &source()?, &target)?;
+        } else if flags.contains(MountFlags::MS_BIND) {
+            VFS.lock()
+                .bind_mount(cwd, creds, &source()?, &target, flags)?;
+        } else {
+            let fs_type = FileSystemType::try_from(safe_fs_type.unwrap_or("ext2"))?;
+            VFS.lock()
+                .mount(cwd, creds, source()?, target, fs_type, flags, safe_data)?;
+        }
         Ok(0)
     })
 }
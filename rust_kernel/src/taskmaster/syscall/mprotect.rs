@@ -80,7 +80,8 @@ pub unsafe fn sys_mprotect(addr: *mut u8, length: usize, prot: MmapProt) -> SysR
             .current_thread_mut()
             .unwrap_process_mut()
             .get_virtual_allocator();
-        match v.check_user_ptr_with_len(addr, length) {
+        // The pages of a file mapping which are not loaded yet just take the new flags
+        match v.check_user_mapped_with_len(addr, length) {
             Err(e) => log::warn!(
                 "a mprotect was bullshit, error: {:?}, {:?}, size {}",
                 e,
@@ -0,0 +1,77 @@
+use super::SysResult;
+use super::SCHEDULER;
+
+use bitflags::bitflags;
+use libc_binding::Errno;
+use libc_binding::{MS_ASYNC, MS_INVALIDATE, MS_SYNC};
+
+use crate::memory::tools::{Address, NbrPages, Page, Virt, PAGE_SIZE};
+
+bitflags! {
+    pub struct MsyncFlags: u32 {
+        /// Perform asynchronous writes.
+        const ASYNC = MS_ASYNC;
+        /// Invalidate mappings.
+        const INVALIDATE = MS_INVALIDATE;
+        /// Perform synchronous writes.
+        const SYNC = MS_SYNC;
+    }
+}
+
+/// The msync() function shall write all modified data to permanent
+/// storage locations, if any, in those whole pages containing any
+/// part of the address space of the process starting at address addr
+/// and continuing for len bytes. If no such storage exists, msync()
+/// need not have any effect. If requested, the msync() function shall
+/// then invalidate cached copies of data.
+///
+/// For mappings to files, the msync() function shall ensure that all
+/// write operations are completed as defined for synchronized I/O
+/// data integrity completion.
+///
+/// When MS_ASYNC is specified, msync() shall return immediately once
+/// all the write operations are initiated or queued for servicing;
+/// when MS_SYNC is specified, msync() shall not return until all
+/// write operations are completed as defined for synchronized I/O
+/// data integrity completion. Either MS_ASYNC or MS_SYNC shall be
+/// specified, but not both.
+///
+/// The writes of turbofish are always synchronous, MS_ASYNC behaves
+/// like MS_SYNC. The cached copies of a private mapping are never
+/// invalidated.
+///
+/// The msync() function shall fail if:
+///
+/// [EINVAL]
+///     The value of flags is invalid.
+/// [EINVAL]
+///     The value of addr is not a multiple of the page size as
+///     returned by sysconf().
+/// [ENOMEM]
+///     The addresses in the range starting at addr and continuing for
+///     len bytes are outside the range allowed for the address space
+///     of a process or specify one or more pages that are not mapped.
+pub fn sys_msync(addr: *mut u8, length: usize, flags: u32) -> SysResult<u32> {
+    let flags = MsyncFlags::from_bits(flags).ok_or(Errno::EINVAL)?;
+    if flags.contains(MsyncFlags::ASYNC | MsyncFlags::SYNC) {
+        return Err(Errno::EINVAL);
+    }
+    let vaddr = Virt(addr as usize);
+    if !vaddr.is_aligned_on(PAGE_SIZE) {
+        return Err(Errno::EINVAL);
+    }
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+        let v = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator();
+
+        v.check_user_mapped_with_len(addr, length)
+            .map_err(|_| Errno::ENOMEM)?;
+
+        let start = Page::containing(vaddr);
+        v.sync_file_mappings(start, start + NbrPages::from(length))?;
+    });
+    Ok(0)
+}
@@ -50,6 +50,9 @@ use libc_binding::Errno;
 pub unsafe fn sys_munmap(addr: *mut u8, length: usize) -> SysResult<u32> {
     unpreemptible_context!({
         let vaddr = Virt(addr as usize);
+        if length == 0 {
+            return Err(Errno::EINVAL);
+        }
         if !vaddr.is_aligned_on(PAGE_SIZE) {
             log::warn!("a munmap addr was unaligned: {:?}", vaddr);
             return Err(Errno::EINVAL);
@@ -61,7 +64,8 @@ pub unsafe fn sys_munmap(addr: *mut u8, length: usize) -> SysResult<u32> {
             .unwrap_process_mut()
             .get_virtual_allocator();
         // log::info!("Claiming munmap of {:?} at {:#X?}", NbrPages::from(length), addr);
-        match v.check_user_ptr_with_len(addr, length) {
+        // The pages of a file mapping may be not loaded yet
+        match v.check_user_mapped_with_len(addr, length) {
             Err(e) => log::warn!(
                 "a munmap was bullshit, error: {:?}, {:?}, size {}",
                 e,
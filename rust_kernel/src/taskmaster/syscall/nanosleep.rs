@@ -9,7 +9,7 @@ use super::thread::WaitingState;
 use libc_binding::Errno;
 
 use crate::drivers::PIT0;
-use crate::math::convert::Convert;
+use core::time::Duration;
 
 #[derive(Debug, Copy, Clone)]
 #[repr(C)]
@@ -51,35 +51,59 @@ extern "C" {
 /// If nanosleep() fails, it shall return a value of -1 and set errno
 /// to indicate the error.
 fn nanosleep(req: *const TimeSpec, rem: *mut TimeSpec) -> SysResult<u32> {
-    let mut scheduler = SCHEDULER.lock();
-
-    let v = scheduler
-        .current_thread_mut()
-        .unwrap_process_mut()
-        .get_virtual_allocator();
-
-    let req = v.make_checked_ref(req)?;
-    let safe_rem = if rem.is_null() {
-        None
-    } else {
-        Some(v.make_checked_ref_mut(rem)?)
+    let (req, safe_rem) = {
+        let scheduler = SCHEDULER.lock();
+
+        let v = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator();
+
+        let req = v.make_checked_ref(req)?;
+        let safe_rem = if rem.is_null() {
+            None
+        } else {
+            Some(v.make_checked_ref_mut(rem)?)
+        };
+        (req, safe_rem)
     };
 
-    // drop the mutex
-    drop(v);
-
     let nsec = req.tv_nsec;
     if nsec < 0 || nsec >= 1000000000 {
         return Err(Errno::EINVAL);
     }
 
+    match sleep(Duration::new(req.tv_sec as u64, nsec as u32)) {
+        Err(remaining_time) => {
+            if let Some(rem) = safe_rem {
+                rem.tv_sec = remaining_time.as_secs() as u32;
+                rem.tv_nsec = (remaining_time.subsec_millis() * 1000000) as i32;
+            }
+            Err(Errno::EINTR)
+        }
+        Ok(()) => Ok(0),
+    }
+}
+
+/// Get the pit time at which `duration` will be elapsed, with a
+/// precision of 1/1000 second
+pub fn pit_deadline(duration: Duration) -> u32 {
     // Set precision as 1/1000
-    let request_time = req.tv_sec as f32 + (req.tv_nsec / 1000000) as f32 / 1000.;
+    let request_time = duration.as_secs() as f32 + duration.subsec_millis() as f32 / 1000.;
     let pit_period = 1. / PIT0.lock().get_frequency().expect("PIT0 not initialized");
-    let next_wake = (request_time / pit_period) as u32 + unsafe { _get_pit_time() };
+    (request_time / pit_period) as u32 + unsafe { _get_pit_time() }
+}
+
+/// Suspend the current thread during `duration`, with a precision of
+/// 1/1000 second. Return the remaining time if a signal interrupted
+/// the sleep
+pub fn sleep(duration: Duration) -> Result<(), Duration> {
+    let pit_period = 1. / PIT0.lock().get_frequency().expect("PIT0 not initialized");
+    let next_wake = pit_deadline(duration);
 
     // Set as Sleeping
-    scheduler
+    SCHEDULER
+        .lock()
         .current_thread_mut()
         .set_waiting(WaitingState::Sleeping(next_wake));
 
@@ -89,15 +113,12 @@ fn nanosleep(req: *const TimeSpec, rem: *mut TimeSpec) -> SysResult<u32> {
             let now = unsafe { _get_pit_time() };
             if now < next_wake {
                 let remaining_time = (next_wake - now) as f32 * pit_period;
-
-                if let Some(rem) = safe_rem {
-                    rem.tv_sec = remaining_time.trunc() as u32;
-                    rem.tv_nsec = ((remaining_time * 1000.).trunc() as u32 % 1000 * 1000000) as i32;
-                }
+                Err(Duration::from_millis((remaining_time * 1000.) as u64))
+            } else {
+                Err(Duration::default())
             }
-            Err(Errno::EINTR)
         }
-        _ => Ok(0),
+        _ => Ok(()),
     }
 }
 
@@ -0,0 +1,40 @@
+//! nice syscall
+use super::scheduler::{Priority, SCHEDULER};
+use super::SysResult;
+
+use libc_binding::Errno;
+
+/// The nice() function shall add the value of incr to the nice value
+/// of the calling process. A process' nice value is a non-negative
+/// number for which a more positive value shall result in less
+/// favorable scheduling.
+///
+/// A nice value outside the range [NICE_MIN, NICE_MAX] is clamped.
+///
+/// Only a process with appropriate privileges can lower the nice
+/// value.
+///
+/// The libc gets the new nice value with getpriority().
+/// [EPERM]
+///     The incr argument is negative and the calling process does not
+///     have appropriate privileges.
+pub fn sys_nice(incr: i32) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        if incr < 0 && !scheduler.current_thread_group().credentials.is_root() {
+            return Err(Errno::EPERM);
+        }
+        let nice = Priority::clamp_nice(
+            scheduler
+                .current_thread()
+                .priority
+                .nice
+                .saturating_add(incr),
+        );
+        for thread in scheduler.current_thread_group_mut().iter_thread_mut() {
+            thread.priority.nice = nice;
+        }
+        Ok(0)
+    })
+}
@@ -2,7 +2,6 @@
 use super::scheduler::auto_preempt;
 use super::scheduler::SCHEDULER;
 use super::thread::WaitingState;
-use super::vfs::VFS;
 use super::IpcResult;
 use super::SysResult;
 use libc_binding::{c_char, mode_t, Errno, FileType, OpenFlags};
@@ -57,27 +56,28 @@ pub fn sys_open(filename: *const c_char, flags: u32, mut mode: mode_t) -> SysRes
             }
         };
 
+        // A session leader without controlling terminal acquires the
+        // first terminal it opens which is not the controlling terminal
+        // of another session, unless O_NOCTTY is set
+        let pid = scheduler.current_task_id().0;
         let tg = scheduler.current_thread_group_mut();
-        let fd_interface = &tg
-            .thread_group_state
-            .unwrap_running_mut()
-            .file_descriptor_interface;
-
-        let mut file_operation = fd_interface
-            .get_file_operation(fd)
-            .expect("FileOperation should be there for fd.");
-
-        if file_operation.isatty() == Ok(1) && !flags.contains(OpenFlags::O_NOCTTY) {
-            let inode_id = file_operation
-                .get_inode_id()
-                .expect("Should be possible to get the inode id of a tty");
-            let tty_minor = VFS
-                .lock()
-                .get_inode(inode_id)
-                .expect("Inode should be there")
-                .minor;
-
-            tg.controlling_terminal = Some(tty_minor);
+        if tg.sid == pid
+            && tg.controlling_terminal.is_none()
+            && !flags.contains(OpenFlags::O_NOCTTY)
+        {
+            let (_, file_operation) = tg
+                .unwrap_running()
+                .file_descriptor_interface
+                .get_open_file(fd)?;
+            let acquired = {
+                let mut terminal = file_operation.lock();
+                terminal.tcgetsid() == Ok(0)
+                    && terminal.tcsetsid(pid).is_ok()
+                    && terminal.tcsetpgrp(tg.pgid).is_ok()
+            };
+            if acquired {
+                tg.controlling_terminal = Some(file_operation);
+            }
         }
         Ok(fd)
     })
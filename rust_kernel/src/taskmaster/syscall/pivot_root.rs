@@ -0,0 +1,34 @@
+use super::scheduler::SCHEDULER;
+use super::vfs::{Path, VFS};
+use super::SysResult;
+use core::convert::TryFrom;
+
+use libc_binding::{c_char, Errno};
+
+/// Make the mount on new_root the root mount, the previous root
+/// mount is moved on put_old, which must be under new_root
+pub fn sys_pivot_root(new_root: *const c_char, put_old: *const c_char) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+        let (safe_new_root, safe_put_old) = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            (v.make_checked_str(new_root)?, v.make_checked_str(put_old)?)
+        };
+
+        let tg = scheduler.current_thread_group();
+        let creds = &tg.credentials;
+        if !creds.is_root() {
+            return Err(Errno::EPERM);
+        }
+        let cwd = &tg.cwd;
+        let new_root = Path::try_from(safe_new_root)?;
+        let put_old = Path::try_from(safe_put_old)?;
+
+        VFS.lock().pivot_root(cwd, creds, &new_root, &put_old)?;
+        Ok(0)
+    })
+}
@@ -0,0 +1,254 @@
+//! sys_poll(), sys_ppoll() and the waiting mechanism shared with select()
+
+use super::SysResult;
+
+use super::scheduler::auto_preempt;
+use super::scheduler::SCHEDULER;
+use super::signal_interface::sigset_t;
+use super::thread::WaitingState;
+use super::Fd;
+use super::IpcResult;
+
+use alloc::vec::Vec;
+use fallible_collections::FallibleVec;
+use libc_binding::{pollfd, timespec, Errno, PollEvents, FD_SETSIZE, SIG_SETMASK};
+
+use crate::drivers::PIT0;
+
+extern "C" {
+    fn _get_pit_time() -> u32;
+}
+
+/// A File Descriptor watched by poll() or select(). A negative fd is ignored
+#[derive(Debug, Copy, Clone)]
+pub struct PollRequest {
+    pub fd: i32,
+    pub events: PollEvents,
+    pub revents: PollEvents,
+}
+
+/// Get the pit time at which a timeout of `sec` seconds and `nsec` nanoseconds expires
+pub fn get_timeout(sec: u32, nsec: u32) -> u32 {
+    let pit_frequency = PIT0.lock().get_frequency().expect("PIT0 not initialized");
+    // Set precision as 1/1000
+    let request_time = sec as f32 + (nsec / 1000000) as f32 / 1000.;
+    let ticks = (request_time * pit_frequency) as u32;
+    unsafe { _get_pit_time() }.saturating_add(ticks)
+}
+
+/// Get the pit time at which the timeout described by `timeout` expires. A null pointer means no timeout
+pub fn get_timespec_timeout(timeout: *const timespec) -> SysResult<Option<u32>> {
+    if timeout.is_null() {
+        return Ok(None);
+    }
+    let timeout = unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+        let v = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator();
+
+        *v.make_checked_ref(timeout)?
+    });
+    if timeout.tv_sec < 0 || timeout.tv_nsec < 0 || timeout.tv_nsec >= 1000000000 {
+        return Err(Errno::EINVAL);
+    }
+    Ok(Some(get_timeout(
+        timeout.tv_sec as u32,
+        timeout.tv_nsec as u32,
+    )))
+}
+
+/// Replace the signal mask of the current thread by the set pointed by `sigmask` and return the old mask
+/// Nothing is done if `sigmask` is a null pointer
+pub fn set_signal_mask(sigmask: *const sigset_t) -> SysResult<Option<sigset_t>> {
+    if sigmask.is_null() {
+        return Ok(None);
+    }
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+        let mask = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            *v.make_checked_ref(sigmask)?
+        };
+        let mut oldmask: sigset_t = 0;
+        scheduler.current_thread_mut().signal.change_signal_mask(
+            SIG_SETMASK,
+            Some(&mask),
+            Some(&mut oldmask),
+        )?;
+        Ok(Some(oldmask))
+    })
+}
+
+/// Restore the signal mask saved by set_signal_mask()
+pub fn restore_signal_mask(oldmask: Option<sigset_t>) -> SysResult<u32> {
+    if let Some(oldmask) = oldmask {
+        unpreemptible_context!({
+            SCHEDULER
+                .lock()
+                .current_thread_mut()
+                .signal
+                .change_signal_mask(SIG_SETMASK, Some(&oldmask), None)?;
+        });
+    }
+    Ok(0)
+}
+
+/// Wait until some events are reported on one of the `requests`, until the pit time `timeout` is
+/// reached or until a signal is caught. Returns the number of requests which have reported events
+pub fn wait_events(requests: &mut [PollRequest], timeout: Option<u32>) -> SysResult<u32> {
+    loop {
+        unpreemptible_context!({
+            let mut scheduler = SCHEDULER.lock();
+            let mut file_op_uids = Vec::new();
+            let mut nbr_ready = 0;
+
+            let fd_interface = &scheduler
+                .current_thread_group_running()
+                .file_descriptor_interface;
+
+            for request in requests.iter_mut() {
+                request.revents = PollEvents::empty();
+                if request.fd < 0 {
+                    continue;
+                }
+                request.revents = match fd_interface.poll(request.fd as Fd, request.events) {
+                    Ok(IpcResult::Done(revents)) => revents,
+                    Ok(IpcResult::Wait(revents, file_op_uid)) => {
+                        file_op_uids.try_push(file_op_uid)?;
+                        revents
+                    }
+                    Err(Errno::EBADF) => PollEvents::POLLNVAL,
+                    Err(e) => return Err(e),
+                };
+                if !request.revents.is_empty() {
+                    nbr_ready += 1;
+                }
+            }
+
+            let expired = match timeout {
+                Some(timeout) => unsafe { _get_pit_time() } >= timeout,
+                None => false,
+            };
+            if nbr_ready > 0 || expired {
+                return Ok(nbr_ready);
+            }
+            scheduler
+                .current_thread_mut()
+                .set_waiting(WaitingState::Poll {
+                    file_op_uids,
+                    timeout,
+                });
+            let _ret = auto_preempt()?;
+        })
+    }
+}
+
+/// Poll the `nfds` structures pointed by `fds` until the pit time `timeout`
+fn poll(fds: *mut pollfd, nfds: u32, timeout: Option<u32>) -> SysResult<u32> {
+    if nfds > FD_SETSIZE {
+        return Err(Errno::EINVAL);
+    }
+    let mut requests: Vec<PollRequest> = Vec::new();
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+        let v = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator();
+
+        for fd in v.make_checked_slice(fds, nfds as usize)? {
+            requests.try_push(PollRequest {
+                fd: fd.fd,
+                events: PollEvents::from_bits_truncate(fd.events as u16),
+                revents: PollEvents::empty(),
+            })?;
+        }
+    });
+
+    let nbr_ready = wait_events(&mut requests, timeout)?;
+
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+        let v = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator();
+
+        // The user memory must be checked again after the wait
+        let fds = v.make_checked_mut_slice(fds, nfds as usize)?;
+        for (fd, request) in fds.iter_mut().zip(requests.iter()) {
+            fd.revents = request.revents.bits() as i16;
+        }
+    });
+    Ok(nbr_ready)
+}
+
+/// The poll() function provides applications with a mechanism for
+/// multiplexing input/output over a set of file descriptors. For each
+/// member of the array pointed to by fds, poll() shall examine the
+/// given file descriptor for the event(s) specified in events. The
+/// number of pollfd structures in the fds array is specified by
+/// nfds. The poll() function shall identify those file descriptors on
+/// which an application can read or write data, or on which certain
+/// events have occurred.
+///
+/// If the value of fd is less than 0, events shall be ignored, and
+/// revents shall be set to 0 in that entry on return from poll().
+///
+/// In each pollfd structure, poll() shall clear the revents member,
+/// except that where the application requested a report on a
+/// condition by setting one of the bits of events listed above,
+/// poll() shall set the corresponding bit in revents if the requested
+/// condition is true. In addition, poll() shall set the POLLHUP,
+/// POLLERR, and POLLNVAL flag in revents if the condition is true,
+/// even if the application did not set the corresponding bit in
+/// events.
+///
+/// If none of the defined events have occurred on any selected file
+/// descriptor, poll() shall wait at least timeout milliseconds for an
+/// event to occur on any of the selected file descriptors. If the
+/// value of timeout is 0, poll() shall return immediately. If the
+/// value of timeout is -1, poll() shall block until a requested event
+/// occurs or until the call is interrupted.
+///
+/// Regular files shall always poll TRUE for reading and writing.
+///
+/// The poll() function shall fail if:
+///
+/// [EINTR]
+///     A signal was caught during poll().
+/// [EINVAL]
+///     The nfds argument is greater than {OPEN_MAX}.
+pub fn sys_poll(fds: *mut pollfd, nfds: u32, timeout: i32) -> SysResult<u32> {
+    let timeout = if timeout < 0 {
+        None
+    } else {
+        let timeout = timeout as u32;
+        Some(get_timeout(timeout / 1000, timeout % 1000 * 1000000))
+    };
+    poll(fds, nfds, timeout)
+}
+
+/// ppoll() is equivalent to poll() except that the timeout is given
+/// as a struct timespec, a null pointer meaning an infinite timeout,
+/// and that, if sigmask is not a null pointer, the signal mask of the
+/// calling thread is replaced by the set pointed to by sigmask during
+/// the wait.
+pub fn sys_ppoll(
+    fds: *mut pollfd,
+    nfds: u32,
+    timeout: *const timespec,
+    sigmask: *const sigset_t,
+) -> SysResult<u32> {
+    let timeout = get_timespec_timeout(timeout)?;
+    let oldmask = set_signal_mask(sigmask)?;
+    let ret = poll(fds, nfds, timeout);
+    restore_signal_mask(oldmask)?;
+    ret
+}
@@ -0,0 +1,129 @@
+//! getpriority and setpriority syscalls
+use super::scheduler::{Pid, Priority, Scheduler, SCHEDULER};
+use super::thread_group::{Credentials, ThreadGroup};
+use super::SysResult;
+
+use alloc::vec::Vec;
+use fallible_collections::TryCollect;
+
+use libc_binding::{uid_t, Errno, PRIO_PGRP, PRIO_PROCESS, PRIO_USER};
+
+/// Unless the caller is privileged, its effective user ID must match
+/// the real or effective user ID of the target process
+pub fn can_change_priority(current: &Credentials, target: &Credentials) -> bool {
+    current.is_root() || current.euid == target.uid || current.euid == target.euid
+}
+
+/// Get the scheduling parameters of a thread group, those of its first thread
+pub fn thread_group_priority(thread_group: &ThreadGroup) -> Option<Priority> {
+    thread_group
+        .get_all_thread()?
+        .values()
+        .next()
+        .map(|thread| thread.priority)
+}
+
+/// The processes designated by the `which` and `who` arguments
+enum Target {
+    Process(Pid),
+    ProcessGroup(Pid),
+    User(uid_t),
+}
+
+impl Target {
+    fn matches(&self, pid: Pid, thread_group: &ThreadGroup) -> bool {
+        match *self {
+            Target::Process(target) => pid == target,
+            Target::ProcessGroup(pgid) => thread_group.pgid == pgid,
+            Target::User(uid) => thread_group.credentials.uid == uid,
+        }
+    }
+}
+
+/// Get the pids of the living processes designated by `which` and `who`
+fn get_targets(scheduler: &Scheduler, which: u32, who: u32) -> SysResult<Vec<Pid>> {
+    let current = scheduler.current_thread_group();
+    let target = match (which, who) {
+        (PRIO_PROCESS, 0) => Target::Process(scheduler.current_task_id().0),
+        (PRIO_PROCESS, pid) => Target::Process(pid as Pid),
+        (PRIO_PGRP, 0) => Target::ProcessGroup(current.pgid),
+        (PRIO_PGRP, pgid) => Target::ProcessGroup(pgid as Pid),
+        (PRIO_USER, 0) => Target::User(current.credentials.uid),
+        (PRIO_USER, uid) => Target::User(uid as uid_t),
+        _ => return Err(Errno::EINVAL),
+    };
+    let targets: Vec<Pid> = scheduler
+        .iter_thread_groups_with_pid()
+        .filter(|(pid, tg)| tg.get_all_thread().is_some() && target.matches(**pid, tg))
+        .map(|(pid, _)| *pid)
+        .try_collect()?;
+    if targets.is_empty() {
+        return Err(Errno::ESRCH);
+    }
+    Ok(targets)
+}
+
+/// The getpriority() function shall obtain the nice value of a
+/// process, process group, or user. The which argument specifies
+/// PRIO_PROCESS, PRIO_PGRP or PRIO_USER, and who is interpreted
+/// relative to which (a zero value of who shall specify the current
+/// process, process group, or user).
+///
+/// If more than one process is specified, getpriority() shall return
+/// value the lowest nice value of all the specified processes.
+///
+/// Like on linux, the syscall returns 20 - nice, so that the returned
+/// value is never negative. The libc makes the conversion.
+pub fn sys_getpriority(which: u32, who: u32) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        let nice = get_targets(&scheduler, which, who)?
+            .iter()
+            .filter_map(|pid| scheduler.get_thread_group(*pid))
+            .filter_map(thread_group_priority)
+            .map(|priority| priority.nice)
+            .min()
+            .ok_or(Errno::ESRCH)?;
+        Ok((20 - nice) as u32)
+    })
+}
+
+/// The setpriority() function shall set the nice value of a process,
+/// process group, or user to value + NZERO. Values outside the range
+/// [NICE_MIN, NICE_MAX] are clamped.
+///
+/// [EPERM]
+///     A process was located, but neither the real nor effective user
+///     ID of the executing process match the effective user ID of the
+///     process whose nice value is being changed.
+/// [EACCES]
+///     A request was made to change the nice value to a lower numeric
+///     value and the current process does not have appropriate
+///     privileges.
+pub fn sys_setpriority(which: u32, who: u32, value: i32) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let nice = Priority::clamp_nice(value);
+        let targets = get_targets(&scheduler, which, who)?;
+        let credentials = &scheduler.current_thread_group().credentials;
+        for pid in targets.iter() {
+            let tg = scheduler.get_thread_group(*pid).ok_or(Errno::ESRCH)?;
+            if !can_change_priority(credentials, &tg.credentials) {
+                return Err(Errno::EPERM);
+            }
+            let current_nice = thread_group_priority(tg).ok_or(Errno::ESRCH)?.nice;
+            if nice < current_nice && !credentials.is_root() {
+                return Err(Errno::EACCES);
+            }
+        }
+        for pid in targets.iter() {
+            let tg = scheduler.get_thread_group_mut(*pid).ok_or(Errno::ESRCH)?;
+            for thread in tg.iter_thread_mut() {
+                thread.priority.nice = nice;
+            }
+        }
+        Ok(0)
+    })
+}
@@ -0,0 +1,275 @@
+//! ptrace syscall
+use super::process::{CpuState, UserProcess};
+use super::scheduler::{Pid, Scheduler, SCHEDULER};
+use super::thread_group::{Credentials, Resume, TraceEvent, TraceStop, Tracee};
+use super::SysResult;
+
+use core::ffi::c_void;
+use core::mem::size_of;
+use i386::Eflags;
+use libc_binding::{siginfo_t, user_regs_struct, Errno, Signum};
+use libc_binding::{
+    PTRACE_ATTACH, PTRACE_CONT, PTRACE_DETACH, PTRACE_GETREGS, PTRACE_GETSIGINFO, PTRACE_KILL,
+    PTRACE_O_TRACESYSGOOD, PTRACE_PEEKDATA, PTRACE_PEEKTEXT, PTRACE_PEEKUSER, PTRACE_POKEDATA,
+    PTRACE_POKETEXT, PTRACE_POKEUSER, PTRACE_SETOPTIONS, PTRACE_SETREGS, PTRACE_SINGLESTEP,
+    PTRACE_SYSCALL, PTRACE_TRACEME,
+};
+
+/// The flags of EFLAGS which a tracer may change: CF, PF, AF, ZF, SF, TF, DF and OF
+const USER_EFLAGS_MASK: u32 = 0xdd5;
+
+/// A process may trace the processes of its real user and group IDs which did
+/// not change their identity (a set-user-ID or set-group-ID program for example)
+fn can_trace(current: &Credentials, target: &Credentials) -> bool {
+    current.is_root()
+        || (target.uid == current.uid
+            && target.euid == current.uid
+            && target.suid == current.uid
+            && target.gid == current.gid
+            && target.egid == current.gid
+            && target.sgid == current.gid)
+}
+
+/// Get the registers of a stopped tracee
+fn get_regs(cpu_state: &CpuState, orig_eax: u32) -> user_regs_struct {
+    let registers = &cpu_state.registers;
+    user_regs_struct {
+        ebx: registers.ebx as i32,
+        ecx: registers.ecx as i32,
+        edx: registers.edx as i32,
+        esi: registers.esi as i32,
+        edi: registers.edi as i32,
+        ebp: registers.ebp as i32,
+        eax: registers.eax as i32,
+        xds: cpu_state.ds as i32,
+        xes: cpu_state.es as i32,
+        xfs: cpu_state.fs as i32,
+        xgs: cpu_state.gs as i32,
+        orig_eax: orig_eax as i32,
+        eip: cpu_state.eip as i32,
+        xcs: cpu_state.cs as i32,
+        eflags: cpu_state.eflags.inner() as i32,
+        esp: cpu_state.esp as i32,
+        xss: cpu_state.ss as i32,
+    }
+}
+
+/// Set the registers of a stopped tracee: the segment registers and the
+/// system flags cannot be changed. Return the new orig_eax
+fn set_regs(cpu_state: &mut CpuState, regs: &user_regs_struct) -> u32 {
+    let registers = &mut cpu_state.registers;
+    registers.ebx = regs.ebx as u32;
+    registers.ecx = regs.ecx as u32;
+    registers.edx = regs.edx as u32;
+    registers.esi = regs.esi as u32;
+    registers.edi = regs.edi as u32;
+    registers.ebp = regs.ebp as u32;
+    registers.eax = regs.eax as u32;
+    cpu_state.eip = regs.eip as u32;
+    cpu_state.esp = regs.esp as u32;
+    cpu_state.eflags = Eflags::new(
+        cpu_state.eflags.inner() & !USER_EFLAGS_MASK | regs.eflags as u32 & USER_EFLAGS_MASK,
+    );
+    regs.orig_eax as u32
+}
+
+/// Get the index of the register at the offset `offset` of a user_regs_struct
+fn user_regs_index(offset: usize) -> SysResult<usize> {
+    if offset % size_of::<u32>() != 0 || offset >= size_of::<user_regs_struct>() {
+        return Err(Errno::EIO);
+    }
+    Ok(offset / size_of::<u32>())
+}
+
+/// Copy `value` at `ptr` in the address space of the caller
+fn copy_to_user<T>(scheduler: &Scheduler, ptr: *mut T, value: T) -> SysResult<u32> {
+    let v = scheduler
+        .current_thread()
+        .unwrap_process()
+        .get_virtual_allocator();
+    *v.make_checked_ref_mut(ptr)? = value;
+    Ok(0)
+}
+
+/// Start to trace the process `pid`, which is sent a SIGSTOP
+fn attach(scheduler: &mut Scheduler, pid: Pid) -> SysResult<u32> {
+    let current_pid = scheduler.current_task_id().0;
+    if pid == current_pid {
+        return Err(Errno::EPERM);
+    }
+    let target = scheduler
+        .get_thread_group(pid)
+        .filter(|thread_group| !thread_group.is_zombie())
+        .ok_or(Errno::ESRCH)?;
+    let credentials = &scheduler.current_thread_group().credentials;
+    if target.tracee.is_some() || !can_trace(credentials, &target.credentials) {
+        return Err(Errno::EPERM);
+    }
+    let thread_group = scheduler.get_thread_group_mut(pid).expect("WTF");
+    thread_group.tracee = Some(Tracee::new(current_pid));
+    for thread in thread_group.iter_thread_mut() {
+        thread.signal.set_traced(true);
+    }
+    if let Some(thread) = thread_group.get_first_thread() {
+        thread.signal.generate_signal(Signum::SIGSTOP)?;
+    }
+    Ok(0)
+}
+
+/// Get the process of the stopped tracee `pid`, and its stop
+fn stopped_tracee(scheduler: &Scheduler, pid: Pid) -> SysResult<(&UserProcess, TraceStop, u32)> {
+    let tracee = scheduler
+        .get_thread_group(pid)
+        .and_then(|thread_group| thread_group.tracee.as_ref())
+        .ok_or(Errno::ESRCH)?;
+    let stop = tracee.stop.ok_or(Errno::ESRCH)?;
+    let thread = scheduler.get_thread((pid, stop.tid)).ok_or(Errno::ESRCH)?;
+    Ok((thread.unwrap_process(), stop, tracee.orig_eax))
+}
+
+/// The ptrace() function allows a process, the tracer, to observe and
+/// control the execution of another process, the tracee, and to read
+/// and write its memory and its registers.
+///
+/// A process becomes a tracee when it calls PTRACE_TRACEME, its tracer
+/// is then its parent, or when a process calls PTRACE_ATTACH on it. It
+/// stops each time a signal is delivered to it, except SIGKILL. The
+/// tracer is told about the stop by waitpid(), even without WUNTRACED,
+/// and resumes the tracee with PTRACE_CONT, PTRACE_SYSCALL or
+/// PTRACE_SINGLESTEP, delivering it the signal given in data, or no
+/// signal if data is 0. A tracee resumed with PTRACE_SYSCALL stops
+/// again at the entry or at the exit of its next syscall, with a
+/// SIGTRAP. One resumed with PTRACE_SINGLESTEP gets a SIGTRAP after
+/// one instruction. A traced process receives a SIGTRAP after a
+/// successful execve().
+///
+/// Except PTRACE_TRACEME, PTRACE_ATTACH and PTRACE_KILL, the requests
+/// can only be made on a stopped tracee of the caller.
+///
+/// [EIO]
+///     request is invalid, an access to the memory of the tracee
+///     failed, an offset in the user area is invalid or an invalid
+///     signal was given to resume the tracee.
+/// [EFAULT]
+///     data points outside the accessible address space.
+/// [EPERM]
+///     The process cannot be traced: it is the caller, it is already
+///     traced, or the caller does not have the permission to trace it.
+/// [ESRCH]
+///     The process does not exist, is not traced by the caller or is
+///     not stopped.
+pub fn sys_ptrace(request: u32, pid: Pid, addr: *mut c_void, data: *mut c_void) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+        let current_pid = scheduler.current_task_id().0;
+
+        match request {
+            PTRACE_TRACEME => {
+                let thread_group = scheduler.current_thread_group_mut();
+                if thread_group.tracee.is_some() {
+                    return Err(Errno::EPERM);
+                }
+                thread_group.tracee = Some(Tracee::new(thread_group.parent));
+                for thread in thread_group.iter_thread_mut() {
+                    thread.signal.set_traced(true);
+                }
+                return Ok(0);
+            }
+            PTRACE_ATTACH => return attach(&mut scheduler, pid),
+            _ => {}
+        }
+        if !scheduler.is_traced_by(pid, current_pid) {
+            return Err(Errno::ESRCH);
+        }
+        if request == PTRACE_KILL {
+            scheduler
+                .get_thread_group_mut(pid)
+                .and_then(|thread_group| thread_group.get_first_thread())
+                .ok_or(Errno::ESRCH)?
+                .signal
+                .generate_signal(Signum::SIGKILL)?;
+            return Ok(0);
+        }
+
+        let (process, stop, orig_eax) = stopped_tracee(&scheduler, pid)?;
+        let cpu_state = process.user_cpu_state();
+        match request {
+            PTRACE_PEEKTEXT | PTRACE_PEEKDATA => {
+                let word = process
+                    .peek_word(addr as *const u32)
+                    .map_err(|_| Errno::EIO)?;
+                copy_to_user(&scheduler, data as *mut u32, word)
+            }
+            PTRACE_PEEKUSER => {
+                let index = user_regs_index(addr as usize)?;
+                let regs = get_regs(unsafe { &*cpu_state }, orig_eax);
+                let word = unsafe { *(&regs as *const user_regs_struct as *const u32).add(index) };
+                copy_to_user(&scheduler, data as *mut u32, word)
+            }
+            PTRACE_POKETEXT | PTRACE_POKEDATA => {
+                process
+                    .poke_word(addr as *mut u32, data as u32)
+                    .map_err(|_| Errno::EIO)?;
+                Ok(0)
+            }
+            PTRACE_POKEUSER | PTRACE_SETREGS => {
+                let regs = if request == PTRACE_POKEUSER {
+                    let index = user_regs_index(addr as usize)?;
+                    let mut regs = get_regs(unsafe { &*cpu_state }, orig_eax);
+                    unsafe {
+                        *(&mut regs as *mut user_regs_struct as *mut u32).add(index) = data as u32;
+                    }
+                    regs
+                } else {
+                    *scheduler
+                        .current_thread()
+                        .unwrap_process()
+                        .get_virtual_allocator()
+                        .make_checked_ref(data as *const user_regs_struct)?
+                };
+                let orig_eax = set_regs(unsafe { &mut *cpu_state }, &regs);
+                if let Some(tracee) = scheduler
+                    .get_thread_group_mut(pid)
+                    .and_then(|thread_group| thread_group.tracee.as_mut())
+                {
+                    tracee.orig_eax = orig_eax;
+                }
+                Ok(0)
+            }
+            PTRACE_GETREGS => {
+                let regs = get_regs(unsafe { &*cpu_state }, orig_eax);
+                copy_to_user(&scheduler, data as *mut user_regs_struct, regs)
+            }
+            PTRACE_GETSIGINFO => match stop.event {
+                TraceEvent::Signal(info) => copy_to_user(&scheduler, data as *mut siginfo_t, info),
+                _ => Err(Errno::EINVAL),
+            },
+            PTRACE_SETOPTIONS => {
+                if data as u32 & !PTRACE_O_TRACESYSGOOD != 0 {
+                    return Err(Errno::EINVAL);
+                }
+                if let Some(tracee) = scheduler
+                    .get_thread_group_mut(pid)
+                    .and_then(|thread_group| thread_group.tracee.as_mut())
+                {
+                    tracee.sysgood = data as u32 & PTRACE_O_TRACESYSGOOD != 0;
+                }
+                Ok(0)
+            }
+            PTRACE_CONT | PTRACE_SYSCALL | PTRACE_SINGLESTEP => {
+                let resume = match request {
+                    PTRACE_CONT => Resume::Continue,
+                    PTRACE_SYSCALL => Resume::Syscall,
+                    _ => Resume::SingleStep,
+                };
+                scheduler.resume_tracee(pid, resume, data as u32)?;
+                Ok(0)
+            }
+            PTRACE_DETACH => {
+                scheduler.detach_tracee(pid, data as u32)?;
+                Ok(0)
+            }
+            _ => Err(Errno::EIO),
+        }
+    })
+}
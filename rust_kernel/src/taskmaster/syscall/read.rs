@@ -2,14 +2,20 @@
 
 use super::SysResult;
 
+use super::poll::get_timeout;
 use super::scheduler::auto_preempt;
-use super::scheduler::SCHEDULER;
+use super::scheduler::{TerminalAccess, SCHEDULER};
 use super::thread::WaitingState;
 use super::IpcResult;
 
+extern "C" {
+    fn _get_pit_time() -> u32;
+}
+
 /// Read something from a file descriptor
 pub fn sys_read(fd: i32, mut buf: *mut u8, mut count: usize) -> SysResult<u32> {
     let mut readen_bytes = 0;
+    let mut timeout: Option<u32> = None;
     loop {
         unpreemptible_context!({
             let mut scheduler = SCHEDULER.lock();
@@ -24,18 +30,34 @@ pub fn sys_read(fd: i32, mut buf: *mut u8, mut count: usize) -> SysResult<u32> {
                 v.make_checked_mut_slice(buf, count)?
             };
 
+            // Job control: a background process cannot read its controlling terminal
+            if !scheduler.current_thread_terminal_access(fd as _, TerminalAccess::Read)? {
+                let _ret = auto_preempt();
+                continue;
+            }
+
             let fd_interface = &mut scheduler
                 .current_thread_group_running_mut()
                 .file_descriptor_interface;
 
+            // The thread was woken up by the timer of the previous read
+            if timeout.map(|time| unsafe { _get_pit_time() } >= time) == Some(true) {
+                return Ok(readen_bytes + fd_interface.read_timer_expired(fd as _, output)?);
+            }
             match fd_interface.read(fd as _, output)? {
                 IpcResult::Wait(res, file_op_uid) => {
                     readen_bytes += res;
                     buf = unsafe { buf.add(res as _) };
                     count -= res as usize;
+                    timeout = fd_interface.read_timer(fd as _)?.map(|deciseconds| {
+                        get_timeout(deciseconds / 10, deciseconds % 10 * 100000000)
+                    });
                     scheduler
                         .current_thread_mut()
-                        .set_waiting(WaitingState::Read(file_op_uid));
+                        .set_waiting(WaitingState::Read {
+                            file_op_uid,
+                            timeout,
+                        });
                     let _ret = auto_preempt()?;
                 }
                 IpcResult::Done(res) => return Ok(readen_bytes + res),
@@ -4,11 +4,15 @@ use super::{SysResult, SCHEDULER};
 
 use libc_binding::Errno;
 
+use crate::drivers::storage::BLOCK_CACHE;
 use crate::drivers::ACPI;
 
 /// Reboot thw computer
 pub fn sys_reboot() -> SysResult<u32> {
     unpreemptible_context!({
+        if let Err(e) = BLOCK_CACHE.lock().sync(None) {
+            log::error!("Cannot write back the block cache before reboot: {:?}", e);
+        }
         match *ACPI.lock() {
             Some(mut acpi) => match acpi.reboot_computer() {
                 Ok(_) => {}
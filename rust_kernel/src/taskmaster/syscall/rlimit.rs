@@ -0,0 +1,135 @@
+//! getrlimit, setrlimit and prlimit syscalls
+use super::priority::can_change_priority;
+use super::scheduler::{Pid, SCHEDULER};
+use super::thread_group::Resource;
+use super::SysResult;
+
+use core::convert::TryFrom;
+use libc_binding::{rlimit, Errno};
+
+/// The getrlimit() function shall get the limits on the consumption
+/// of a variety of resources by the calling process. Each call to
+/// getrlimit() or setrlimit() identifies a specific resource to be
+/// operated upon as well as a resource limit. A resource limit is
+/// represented by an rlimit structure. The rlim_cur member specifies
+/// the current or soft limit and the rlim_max member specifies the
+/// maximum or hard limit.
+///
+/// [EFAULT]
+///     rlp points outside the accessible address space.
+/// [EINVAL]
+///     An invalid resource was specified.
+pub fn sys_getrlimit(resource: u32, rlp: *mut rlimit) -> SysResult<u32> {
+    sys_prlimit(0, resource, core::ptr::null(), rlp)
+}
+
+/// The setrlimit() function shall set the limits on the consumption
+/// of a variety of resources by the calling process. The soft limit
+/// may be changed to any value that is less than or equal to the
+/// hard limit. Any process may decrease its hard limit to a value
+/// greater than or equal to its soft limit. Only a process with
+/// appropriate privileges can raise a hard limit.
+///
+/// The enforced limits are:
+///
+/// RLIMIT_NOFILE
+///     One more than the greatest file descriptor number that can be
+///     opened by the process: open(), dup(), pipe() fail with EMFILE.
+/// RLIMIT_STACK
+///     The maximum size of the main thread stack, it is no more
+///     extended beyond it: the process receives a SIGSEGV.
+/// RLIMIT_AS
+///     The maximum size of the address space: mmap(), execve() and
+///     the stack extension fail with ENOMEM.
+/// RLIMIT_NPROC
+///     The maximum number of processes of the real user ID of the
+///     process: fork() fails with EAGAIN. The root user is not limited.
+///
+/// RLIMIT_CORE is 0 by default, like the limits on CPU time, data
+/// segment and file size it is not enforced.
+///
+/// [EFAULT]
+///     rlp points outside the accessible address space.
+/// [EINVAL]
+///     An invalid resource was specified; or in a setrlimit() call,
+///     the new rlim_cur exceeds the new rlim_max.
+/// [EPERM]
+///     The limit specified to setrlimit() would have raised the
+///     maximum limit value, and the calling process does not have
+///     appropriate privileges.
+pub fn sys_setrlimit(resource: u32, rlp: *const rlimit) -> SysResult<u32> {
+    if rlp.is_null() {
+        return Err(Errno::EFAULT);
+    }
+    sys_prlimit(0, resource, rlp, core::ptr::null_mut())
+}
+
+/// The prlimit() system call combines and extends the functionality
+/// of setrlimit() and getrlimit(). It can be used to both set and get
+/// the resource limits of the process pid, the calling process if pid
+/// is 0. If the new_limit argument is a not NULL, then the rlimit
+/// structure to which it points is used to set new values for the
+/// soft and hard limits for resource. If the old_limit argument is a
+/// not NULL, then a successful call to prlimit() places the previous
+/// soft and hard limits for resource in the rlimit structure pointed
+/// to by old_limit.
+///
+/// [EPERM]
+///     The caller is not privileged, and its effective user ID does
+///     not match the real or effective user ID of the target process.
+/// [ESRCH]
+///     Could not find a process with the ID specified in pid.
+pub fn sys_prlimit(
+    pid: Pid,
+    resource: u32,
+    new_limit: *const rlimit,
+    old_limit: *mut rlimit,
+) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let resource = Resource::try_from(resource)?;
+        let (new_limit, old_limit) = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+            let new_limit = if new_limit.is_null() {
+                None
+            } else {
+                Some(*v.make_checked_ref(new_limit)?)
+            };
+            let old_limit = if old_limit.is_null() {
+                None
+            } else {
+                Some(v.make_checked_ref_mut(old_limit)?)
+            };
+            (new_limit, old_limit)
+        };
+
+        let pid = if pid == 0 {
+            scheduler.current_task_id().0
+        } else {
+            pid
+        };
+        let credentials = &scheduler.current_thread_group().credentials;
+        let privileged = credentials.is_root();
+        let target = scheduler
+            .get_thread_group(pid)
+            .filter(|tg| !tg.is_zombie())
+            .ok_or(Errno::ESRCH)?;
+        if !can_change_priority(credentials, &target.credentials) {
+            return Err(Errno::EPERM);
+        }
+
+        let target = scheduler.get_thread_group_mut(pid).expect("WTF");
+        let previous = target.get_rlimit(resource);
+        if let Some(new_limit) = new_limit {
+            target.set_rlimit(resource, new_limit, privileged)?;
+        }
+        if let Some(old_limit) = old_limit {
+            *old_limit = previous;
+        }
+        Ok(0)
+    })
+}
@@ -0,0 +1,145 @@
+//! sched_setscheduler, sched_getscheduler, sched_getparam and sched_yield syscalls
+use super::priority::{can_change_priority, thread_group_priority};
+use super::scheduler::{auto_preempt, Pid, Priority, SchedPolicy, SCHEDULER};
+use super::SysResult;
+
+use core::convert::TryFrom;
+use libc_binding::{sched_param, Errno};
+
+/// The sched_setscheduler() function shall set the scheduling policy
+/// and scheduling parameters of the process specified by pid to
+/// policy and the parameters specified in the sched_param structure
+/// pointed to by param, respectively. If pid is zero, the scheduling
+/// policy and scheduling parameters shall be set for the calling
+/// process.
+///
+/// The sched_priority of SCHED_FIFO and SCHED_RR must be in the range
+/// [1, 99], the one of SCHED_OTHER must be 0.
+///
+/// [EINVAL]
+///     The value of the policy parameter is invalid, or one or more of
+///     the parameters contained in param is outside the valid range
+///     for the specified scheduling policy.
+/// [EPERM]
+///     The requesting process does not have permission to set either
+///     or both of the scheduling parameters or the scheduling policy
+///     of the specified process.
+/// [ESRCH]
+///     No process can be found corresponding to that specified by pid.
+pub fn sys_sched_setscheduler(pid: Pid, policy: i32, param: *const sched_param) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let param = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            // Check if pointer exists in user virtual address space
+            *v.make_checked_ref(param)?
+        };
+        let policy = SchedPolicy::try_from(policy)?;
+        let rt_priority = param.sched_priority;
+        let valid = match policy {
+            SchedPolicy::Other => rt_priority == 0,
+            SchedPolicy::Fifo | SchedPolicy::RoundRobin => {
+                rt_priority >= Priority::RT_PRIORITY_MIN as i32
+                    && rt_priority <= Priority::RT_PRIORITY_MAX as i32
+            }
+        };
+        if !valid {
+            return Err(Errno::EINVAL);
+        }
+        let pid = if pid == 0 {
+            scheduler.current_task_id().0
+        } else {
+            pid
+        };
+        let credentials = &scheduler.current_thread_group().credentials;
+        let is_root = credentials.is_root();
+        let tg = scheduler.get_thread_group(pid).ok_or(Errno::ESRCH)?;
+        if tg.get_all_thread().is_none() {
+            return Err(Errno::ESRCH);
+        }
+        if !can_change_priority(credentials, &tg.credentials) {
+            return Err(Errno::EPERM);
+        }
+        // Only a privileged process can get a real time policy
+        if policy != SchedPolicy::Other && !is_root {
+            return Err(Errno::EPERM);
+        }
+        for thread in scheduler
+            .get_thread_group_mut(pid)
+            .ok_or(Errno::ESRCH)?
+            .iter_thread_mut()
+        {
+            thread.priority.policy = policy;
+            thread.priority.rt_priority = rt_priority as u32;
+        }
+        Ok(0)
+    })
+}
+
+/// The sched_getscheduler() function shall return the scheduling
+/// policy of the process specified by pid. If the value of pid is
+/// zero, the scheduling policy shall be returned for the calling
+/// process.
+pub fn sys_sched_getscheduler(pid: Pid) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        let pid = if pid == 0 {
+            scheduler.current_task_id().0
+        } else {
+            pid
+        };
+        let priority = scheduler
+            .get_thread_group(pid)
+            .and_then(thread_group_priority)
+            .ok_or(Errno::ESRCH)?;
+        Ok(u32::from(priority.policy))
+    })
+}
+
+/// The sched_getparam() function shall return the scheduling
+/// parameters of a process specified by pid in the sched_param
+/// structure pointed to by param. If pid is zero, the scheduling
+/// parameters for the calling process shall be returned.
+pub fn sys_sched_getparam(pid: Pid, param: *mut sched_param) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        let param = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            // Check if pointer exists in user virtual address space
+            v.make_checked_ref_mut(param)?
+        };
+        let pid = if pid == 0 {
+            scheduler.current_task_id().0
+        } else {
+            pid
+        };
+        let priority = scheduler
+            .get_thread_group(pid)
+            .and_then(thread_group_priority)
+            .ok_or(Errno::ESRCH)?;
+        param.sched_priority = priority.rt_priority as i32;
+        Ok(0)
+    })
+}
+
+/// The sched_yield() function shall force the running thread to
+/// relinquish the processor until it again becomes the head of its
+/// thread list.
+pub fn sys_sched_yield() -> SysResult<u32> {
+    unpreemptible_context!({
+        SCHEDULER.lock().current_thread_yield();
+        let _ignored_result = auto_preempt();
+        Ok(0)
+    })
+}
@@ -0,0 +1,211 @@
+//! sys_select() and sys_pselect(), built on the poll() waiting mechanism
+
+use super::SysResult;
+
+use super::poll::{
+    get_timeout, get_timespec_timeout, restore_signal_mask, set_signal_mask, wait_events,
+    PollRequest,
+};
+use super::scheduler::SCHEDULER;
+use super::signal_interface::sigset_t;
+
+use alloc::vec::Vec;
+use fallible_collections::FallibleVec;
+use libc_binding::{fd_set, timespec, timeval, Errno, PollEvents, FD_SETSIZE};
+
+/// Number of file descriptors in a word of a fd_set
+const NFDBITS: usize = 32;
+
+/// Check if `fd` is a member of the set
+fn fd_isset(set: &Option<fd_set>, fd: usize) -> bool {
+    match set {
+        Some(set) => set.fds_bits[fd / NFDBITS] & (1 << (fd % NFDBITS)) != 0,
+        None => false,
+    }
+}
+
+/// Add `fd` to the set
+fn fd_set(set: &mut Option<fd_set>, fd: usize) {
+    if let Some(set) = set {
+        set.fds_bits[fd / NFDBITS] |= 1 << (fd % NFDBITS);
+    }
+}
+
+/// Copy the user fd_set pointed by `set`, a null pointer is an absent set
+fn read_fd_set(set: *const fd_set) -> SysResult<Option<fd_set>> {
+    if set.is_null() {
+        return Ok(None);
+    }
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+        let v = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator();
+
+        Ok(Some(*v.make_checked_ref(set)?))
+    })
+}
+
+/// Copy `content` into the user fd_set pointed by `set`
+fn write_fd_set(set: *mut fd_set, content: &Option<fd_set>) -> SysResult<()> {
+    if let Some(content) = content {
+        unpreemptible_context!({
+            let scheduler = SCHEDULER.lock();
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            *v.make_checked_ref_mut(set)? = *content;
+        });
+    }
+    Ok(())
+}
+
+/// Examine the `nfds` first file descriptors of the three sets until the pit time `timeout`
+fn select(
+    nfds: i32,
+    readfds: *mut fd_set,
+    writefds: *mut fd_set,
+    exceptfds: *mut fd_set,
+    timeout: Option<u32>,
+) -> SysResult<u32> {
+    if nfds < 0 || nfds as u32 > FD_SETSIZE {
+        return Err(Errno::EINVAL);
+    }
+    let sets = [
+        read_fd_set(readfds)?,
+        read_fd_set(writefds)?,
+        read_fd_set(exceptfds)?,
+    ];
+    let wanted_events = [PollEvents::READ, PollEvents::WRITE, PollEvents::POLLPRI];
+
+    let mut requests: Vec<PollRequest> = Vec::new();
+    for fd in 0..nfds as usize {
+        let events = sets
+            .iter()
+            .zip(wanted_events.iter())
+            .filter(|(set, _)| fd_isset(set, fd))
+            .fold(PollEvents::empty(), |events, (_, wanted)| events | *wanted);
+        if !events.is_empty() {
+            requests.try_push(PollRequest {
+                fd: fd as i32,
+                events,
+                revents: PollEvents::empty(),
+            })?;
+        }
+    }
+
+    wait_events(&mut requests, timeout)?;
+
+    // The sets are modified to indicate which file descriptors are ready
+    let ready_events = [
+        PollEvents::READ | PollEvents::POLLHUP | PollEvents::POLLERR,
+        PollEvents::WRITE | PollEvents::POLLHUP | PollEvents::POLLERR,
+        PollEvents::POLLPRI,
+    ];
+    let mut ready_sets = sets.clone();
+    for set in ready_sets.iter_mut().flatten() {
+        set.fds_bits = Default::default();
+    }
+    let mut nbr_bits = 0;
+    for request in requests.iter() {
+        if request.revents.contains(PollEvents::POLLNVAL) {
+            return Err(Errno::EBADF);
+        }
+        for i in 0..sets.len() {
+            let fd = request.fd as usize;
+            if fd_isset(&sets[i], fd) && request.revents.intersects(ready_events[i]) {
+                fd_set(&mut ready_sets[i], fd);
+                nbr_bits += 1;
+            }
+        }
+    }
+
+    write_fd_set(readfds, &ready_sets[0])?;
+    write_fd_set(writefds, &ready_sets[1])?;
+    write_fd_set(exceptfds, &ready_sets[2])?;
+    Ok(nbr_bits)
+}
+
+/// The select() function shall examine the file descriptor sets
+/// whose addresses are passed in the readfds, writefds, and errorfds
+/// parameters to see whether some of their descriptors are ready for
+/// reading, are ready for writing, or have an exceptional condition
+/// pending, respectively.
+///
+/// The select() function shall support regular files, terminal and
+/// pseudo-terminal devices, FIFOs, pipes, and sockets.
+///
+/// If the timeout parameter is not a null pointer, it specifies a
+/// maximum interval to wait for the selection to complete. If the
+/// timeout parameter is a null pointer, then the call to select()
+/// shall block indefinitely until at least one descriptor meets the
+/// specified criteria.
+///
+/// Upon successful completion, select() shall modify the objects
+/// pointed to by the readfds, writefds, and errorfds arguments to
+/// indicate which file descriptors are ready, and shall return the
+/// total number of bits set in all the output sets.
+///
+/// Under the following conditions, select() shall fail and set errno
+/// to:
+///
+/// [EBADF]
+///     One or more of the file descriptor sets specified a file
+///     descriptor that is not a valid open file descriptor.
+/// [EINTR]
+///     The function was interrupted while blocked waiting for any of
+///     the selected descriptors to become ready and before the
+///     timeout interval expired.
+/// [EINVAL]
+///     An invalid timeout interval was specified or the nfds argument
+///     is less than 0 or greater than FD_SETSIZE.
+pub fn sys_select(
+    nfds: i32,
+    readfds: *mut fd_set,
+    writefds: *mut fd_set,
+    exceptfds: *mut fd_set,
+    timeout: *mut timeval,
+) -> SysResult<u32> {
+    let timeout = if timeout.is_null() {
+        None
+    } else {
+        let timeout = unpreemptible_context!({
+            let scheduler = SCHEDULER.lock();
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            *v.make_checked_ref(timeout)?
+        });
+        if timeout.tv_sec < 0 || timeout.tv_usec >= 1000000 {
+            return Err(Errno::EINVAL);
+        }
+        Some(get_timeout(timeout.tv_sec as u32, timeout.tv_usec * 1000))
+    };
+    select(nfds, readfds, writefds, exceptfds, timeout)
+}
+
+/// The pselect() function shall be equivalent to the select()
+/// function, except that the timeout is given as a struct timespec
+/// and that, if the sigmask argument is not a null pointer, the
+/// signal mask of the calling thread is replaced by the set of signals
+/// pointed to by sigmask before examining the descriptors, and
+/// restored before returning.
+pub fn sys_pselect(
+    nfds: i32,
+    readfds: *mut fd_set,
+    writefds: *mut fd_set,
+    exceptfds: *mut fd_set,
+    timeout: *const timespec,
+    sigmask: *const sigset_t,
+) -> SysResult<u32> {
+    let timeout = get_timespec_timeout(timeout)?;
+    let oldmask = set_signal_mask(sigmask)?;
+    let ret = select(nfds, readfds, writefds, exceptfds, timeout);
+    restore_signal_mask(oldmask)?;
+    ret
+}
@@ -0,0 +1,48 @@
+//! set_thread_area syscall
+use super::process::TlsSegment;
+use super::scheduler::SCHEDULER;
+use super::SysResult;
+
+use libc_binding::user_desc;
+
+/// set_thread_area() sets an entry in the current thread's Thread
+/// Local Storage (TLS) array. The TLS array entry set by
+/// set_thread_area() corresponds to the value of u_info->entry_number
+/// passed in by the user. If this value is in bounds,
+/// set_thread_area() writes the TLS descriptor pointed to by u_info
+/// into the thread's TLS array.
+///
+/// When set_thread_area() is passed an entry_number of -1, it searches
+/// for a free TLS entry. If set_thread_area() finds a free TLS entry,
+/// the value of u_info->entry_number is set upon return to show which
+/// entry was changed.
+///
+/// There is only one TLS entry, the segment selector to load in gs
+/// or fs is ((entry_number << 3) | 3). A descriptor with
+/// seg_not_present set clears the entry.
+///
+/// [EFAULT]
+///     u_info is an invalid pointer.
+/// [EINVAL]
+///     u_info->entry_number is out of bounds or the descriptor is
+///     a conforming code segment.
+pub fn sys_set_thread_area(u_info: *mut user_desc) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+        let u_info = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator()
+            .make_checked_ref_mut(u_info)?;
+
+        let tls = TlsSegment::new(u_info)?;
+        u_info.entry_number = TlsSegment::ENTRY_NUMBER;
+
+        scheduler.current_thread_mut().unwrap_process_mut().tls = tls;
+        // The descriptor is taken by the segment registers on the return to ring 3
+        unsafe {
+            tls.load();
+        }
+        Ok(0)
+    })
+}
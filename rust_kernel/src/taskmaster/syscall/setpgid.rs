@@ -14,16 +14,47 @@ use libc_binding::Errno;
 /// As a special case, if pid is 0, the process ID of the calling
 /// process shall be used. Also, if pgid is 0, the process ID of the
 /// indicated process shall be used.
+/// [EINVAL]
+///     The value of the pgid argument is less than 0.
+/// [EPERM]
+///     The process indicated by the pid argument is a session leader,
+///     or is a child of the calling process in another session, or
+///     pgid does not match the process ID of the process indicated by
+///     pid and there is no process with a process group ID that
+///     matches pgid in the same session as the calling process.
+/// [ESRCH]
+///     The value of the pid argument does not match the process ID of
+///     the calling process or of a child process of the calling
+///     process.
 pub fn sys_setpgid(mut pid: Pid, mut pgid: Pid) -> SysResult<u32> {
     unpreemptible_context!({
         let mut scheduler = SCHEDULER.lock();
 
+        if pgid < 0 {
+            return Err(Errno::EINVAL);
+        }
+        let current_pid = scheduler.current_task_id().0;
+        let sid = scheduler.current_thread_group().sid;
         if pid == 0 {
-            pid = scheduler.current_task_id().0
+            pid = current_pid
         }
         if pgid == 0 {
             pgid = pid
         }
+        let target = scheduler
+            .get_thread_group(pid)
+            .filter(|tg| !tg.is_zombie() && (pid == current_pid || tg.parent == current_pid))
+            .ok_or(Errno::ESRCH)?;
+        if target.sid == pid || target.sid != sid {
+            return Err(Errno::EPERM);
+        }
+        if pgid != pid
+            && !scheduler
+                .iter_thread_groups()
+                .any(|tg| tg.pgid == pgid && tg.sid == sid)
+        {
+            return Err(Errno::EPERM);
+        }
         scheduler
             .get_thread_group_mut(pid)
             .ok_or(Errno::ESRCH)?
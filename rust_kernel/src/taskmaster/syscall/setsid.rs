@@ -0,0 +1,35 @@
+//! setsid syscall
+use super::scheduler::SCHEDULER;
+use super::SysResult;
+use libc_binding::Errno;
+
+/// The setsid() function shall create a new session, if the calling
+/// process is not a process group leader. Upon return the calling
+/// process shall be the session leader of this new session, shall be
+/// the process group leader of a new process group, and shall have no
+/// controlling terminal. The process group ID of the calling process
+/// shall be set equal to the process ID of the calling process. The
+/// calling process shall be the only process in the new process group
+/// and the only process in the new session.
+///
+/// Upon successful completion, setsid() shall return the value of the
+/// new process group ID of the calling process.
+/// [EPERM]
+///     The calling process is already a process group leader, or the
+///     process group ID of a process other than the calling process
+///     matches the process ID of the calling process.
+pub fn sys_setsid() -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let pid = scheduler.current_task_id().0;
+        if scheduler.iter_thread_groups().any(|tg| tg.pgid == pid) {
+            return Err(Errno::EPERM);
+        }
+        let thread_group = scheduler.current_thread_group_mut();
+        thread_group.sid = pid;
+        thread_group.pgid = pid;
+        thread_group.controlling_terminal = None;
+        Ok(pid as u32)
+    })
+}
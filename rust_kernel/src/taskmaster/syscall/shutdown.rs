@@ -2,12 +2,16 @@ use super::SysResult;
 
 use libc_binding::Errno;
 
+use crate::drivers::storage::BLOCK_CACHE;
 use crate::drivers::ACPI;
 use crate::system::i8086_payload_apm_shutdown;
 
 /// Shutdown the computer
 pub fn sys_shutdown() -> SysResult<u32> {
     unpreemptible_context!({
+        if let Err(e) = BLOCK_CACHE.lock().sync(None) {
+            log::error!("Cannot write back the block cache before shutdown: {:?}", e);
+        }
         match *ACPI.lock() {
             Some(mut acpi) => match unsafe { acpi.shutdown() } {
                 Ok(_) => {}
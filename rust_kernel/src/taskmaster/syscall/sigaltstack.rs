@@ -0,0 +1,78 @@
+//! sigaltstack syscall
+use super::process::CpuState;
+use super::scheduler::SCHEDULER;
+use super::SysResult;
+
+use libc_binding::{stack_t, SS_DISABLE};
+
+/// The sigaltstack() function allows a process to define and examine
+/// the state of an alternate stack for signal handlers for the
+/// current thread. Signals that have been explicitly declared to
+/// execute on the alternate stack shall be delivered on the alternate
+/// stack.
+///
+/// If ss is not a null pointer, it points to a stack_t structure that
+/// specifies the alternate signal stack that shall take effect upon
+/// return from sigaltstack(). The ss_flags member specifies the new
+/// stack state. If it is set to SS_DISABLE, the stack is disabled
+/// and ss_sp and ss_size are ignored. Otherwise, the stack shall be
+/// enabled, and the ss_sp and ss_size members specify the new address
+/// and size of the stack.
+///
+/// If oss is not a null pointer, on successful completion it shall
+/// point to a stack_t structure that specifies the alternate signal
+/// stack that was in effect prior to the call to sigaltstack(). The
+/// ss_flags member shall contain SS_ONSTACK if the thread is currently
+/// executing on it, or SS_DISABLE if it is disabled.
+///
+/// [EINVAL]
+///     The ss argument is not a null pointer, and the ss_flags member
+///     pointed to by ss contains flags other than SS_DISABLE.
+/// [ENOMEM]
+///     The size of the alternate stack area is less than MINSIGSTKSZ.
+/// [EPERM]
+///     An attempt was made to modify an active stack.
+pub unsafe fn sys_sigaltstack(
+    cpu_state: *mut CpuState,
+    ss: *const stack_t,
+    old_ss: *mut stack_t,
+) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+        let checked_ss;
+        let checked_old_ss;
+        {
+            let v = scheduler
+                .current_thread_mut()
+                .unwrap_process_mut()
+                .get_virtual_allocator();
+
+            // Check if pointer exists in user virtual address space
+            checked_ss = if ss.is_null() {
+                None
+            } else {
+                let ss = v.make_checked_ref(ss)?;
+                // The whole alternate stack must be writable by the process
+                if ss.ss_flags as u32 != SS_DISABLE {
+                    v.make_checked_mut_slice(ss.ss_sp as *mut u8, ss.ss_size as usize)?;
+                }
+                Some(ss)
+            };
+            checked_old_ss = if old_ss.is_null() {
+                None
+            } else {
+                Some(v.make_checked_ref_mut(old_ss)?)
+            };
+        }
+        let esp = (*cpu_state).esp;
+        let signal = &mut scheduler.current_thread_mut().signal;
+        let old = signal.get_alt_stack(esp);
+        if let Some(ss) = checked_ss {
+            signal.set_alt_stack(esp, ss)?;
+        }
+        if let Some(old_ss) = checked_old_ss {
+            *old_ss = old;
+        }
+        Ok(0)
+    })
+}
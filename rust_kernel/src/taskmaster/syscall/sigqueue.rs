@@ -0,0 +1,57 @@
+//! sigqueue syscall
+use super::kill::generate_signal;
+use super::scheduler::{Pid, SCHEDULER};
+use super::signal_interface::siginfo;
+use super::thread_group::Credentials;
+use super::SysResult;
+
+use core::convert::TryInto;
+use libc_binding::{siginfo_t, sigval, Errno, Signum, SI_QUEUE};
+
+/// The sigqueue() function shall cause the signal specified by signo
+/// to be sent with the value specified by value to the process
+/// specified by pid. If signo is zero (the null signal), error
+/// checking is performed but no signal is actually sent. The null
+/// signal can be used to check the validity of pid.
+///
+/// The conditions required for a process to have permission to queue
+/// a signal to another process are the same as for the kill()
+/// function.
+///
+/// The sigqueue() function shall return immediately. If SA_SIGINFO is
+/// set for signo and if the resources were available to queue the
+/// signal, the signal shall be queued and sent to the receiving
+/// process. If SA_SIGINFO is not set for signo, then signo shall be
+/// sent at least once to the receiving process; it is unspecified
+/// whether value shall be sent to the receiving process as a result
+/// of this call.
+///
+/// [EAGAIN]
+///     No resources are available to queue the signal. The process
+///     has already queued {SIGQUEUE_MAX} signals that are still
+///     pending at the receiver(s).
+pub fn sys_sigqueue(pid: Pid, signum: u32, value: sigval) -> SysResult<u32> {
+    unpreemptible_context!({
+        let signum: Signum = signum.try_into().map_err(|_| Errno::EINVAL)?;
+        let mut scheduler = SCHEDULER.lock();
+
+        let Credentials {
+            uid: self_uid,
+            euid: self_euid,
+            ..
+        } = scheduler.current_thread_group().credentials;
+
+        let info = siginfo_t {
+            si_pid: scheduler.current_task_id().0,
+            si_uid: self_uid,
+            si_value: value,
+            ..siginfo(signum, SI_QUEUE)
+        };
+        generate_signal(
+            scheduler.get_thread_group_mut(pid).into_iter(),
+            info,
+            self_uid,
+            self_euid,
+        )
+    })
+}
@@ -0,0 +1,99 @@
+//! sigtimedwait syscall
+use super::clock::timespec_to_duration;
+use super::nanosleep::pit_deadline;
+use super::scheduler::{auto_preempt, SCHEDULER};
+use super::signal_interface::sigset_t;
+use super::thread::WaitingState;
+use super::SysResult;
+
+use libc_binding::{siginfo_t, timespec, Errno};
+
+extern "C" {
+    fn _get_pit_time() -> u32;
+}
+
+/// The sigtimedwait() function shall select the pending signal from
+/// the set specified by set. Should any of multiple pending signals
+/// in the range SIGRTMIN to SIGRTMAX be selected, it shall be the
+/// lowest numbered one. If no signal in set is pending at the time of
+/// the call, the thread shall be suspended until one or more signals
+/// in set become pending or until it is interrupted by an unblocked,
+/// caught signal.
+///
+/// The timeout specifies the maximum time the thread is suspended. If
+/// timeout is a null pointer, the thread waits indefinitely, like
+/// with sigwaitinfo().
+///
+/// If the info argument is non-NULL, the selected signal number shall
+/// be stored in the si_signo member, and the cause of the signal
+/// shall be stored in the si_code member. If any value is queued to
+/// the selected signal, the first such queued value shall be dequeued
+/// and, if the info argument is non-NULL, the value shall be stored in
+/// the si_value member of info.
+///
+/// Upon successful completion, the selected signal number is
+/// returned.
+///
+/// [EAGAIN]
+///     No signal specified by set was generated within the specified
+///     timeout period.
+/// [EINTR]
+///     The wait was interrupted by an unblocked, caught signal.
+/// [EINVAL]
+///     The timeout argument specified a tv_nsec value less than zero
+///     or greater than or equal to 1000 million.
+pub fn sys_sigtimedwait(
+    set: *const sigset_t,
+    info: *mut siginfo_t,
+    timeout: *const timespec,
+) -> SysResult<u32> {
+    unpreemptible_context!({
+        let (set, info, timeout) = {
+            let scheduler = SCHEDULER.lock();
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            // Check if pointers exist in user virtual address space
+            let info = if info.is_null() {
+                None
+            } else {
+                Some(v.make_checked_ref_mut(info)?)
+            };
+            let timeout = if timeout.is_null() {
+                None
+            } else {
+                Some(v.make_checked_ref(timeout)?)
+            };
+            (*v.make_checked_ref(set)?, info, timeout)
+        };
+        let deadline = match timeout {
+            Some(timeout) => Some(pit_deadline(timespec_to_duration(timeout)?)),
+            None => None,
+        };
+        loop {
+            let mut scheduler = SCHEDULER.lock();
+            let thread = scheduler.current_thread_mut();
+            if let Some(siginfo) = thread.signal.accept_signal(set) {
+                if let Some(info) = info {
+                    *info = siginfo;
+                }
+                return Ok(siginfo.si_signo as u32);
+            }
+            if let Some(deadline) = deadline {
+                if unsafe { _get_pit_time() } >= deadline {
+                    return Err(Errno::EAGAIN);
+                }
+            }
+            thread.set_waiting(WaitingState::SigWait {
+                set,
+                timeout: deadline,
+            });
+            drop(scheduler);
+
+            // An unblocked caught signal interrupts the wait
+            auto_preempt()?;
+        }
+    })
+}
@@ -756,7 +756,10 @@ fn recv_from(
                 IpcResult::Wait(_res, file_op_uid) => {
                     scheduler
                         .current_thread_mut()
-                        .set_waiting(WaitingState::Read(file_op_uid));
+                        .set_waiting(WaitingState::Read {
+                            file_op_uid,
+                            timeout: None,
+                        });
                     let _ret = auto_preempt()?;
                 }
                 IpcResult::Done((readen_bytes, sender_path)) => {
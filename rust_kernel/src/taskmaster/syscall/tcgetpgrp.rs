@@ -2,6 +2,7 @@
 use super::scheduler::SCHEDULER;
 use super::Fd;
 use super::SysResult;
+use libc_binding::Errno;
 
 /// The tcgetpgrp() function shall return the value of the process
 /// group ID of the foreground process group associated with the
@@ -36,11 +37,15 @@ use super::SysResult;
 pub fn sys_tcgetpgrp(fildes: Fd) -> SysResult<u32> {
     unpreemptible_context!({
         let scheduler = SCHEDULER.lock();
+        let sid = scheduler.current_thread_group().sid;
         let fd_interface = &scheduler
             .current_thread_group_running()
             .file_descriptor_interface;
 
         let file_operation = &fd_interface.get_file_operation(fildes)?;
+        if file_operation.tcgetsid()? != sid {
+            return Err(Errno::ENOTTY);
+        }
         Ok(file_operation.tcgetpgrp()? as u32)
     })
 }
@@ -0,0 +1,29 @@
+//! tcgetsid syscall
+use super::scheduler::SCHEDULER;
+use super::Fd;
+use super::SysResult;
+use libc_binding::Errno;
+
+/// The tcgetsid() function shall obtain the process group ID of the
+/// session for which the terminal specified by fildes is the
+/// controlling terminal.
+/// [EBADF]
+///     The fildes argument is not a valid file descriptor.
+/// [ENOTTY]
+///     The calling process does not have a controlling terminal, or
+///     the file is not the controlling terminal.
+pub fn sys_tcgetsid(fildes: Fd) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+        let sid = scheduler.current_thread_group().sid;
+        let fd_interface = &scheduler
+            .current_thread_group_running()
+            .file_descriptor_interface;
+
+        let file_operation = &fd_interface.get_file_operation(fildes)?;
+        if file_operation.tcgetsid()? != sid {
+            return Err(Errno::ENOTTY);
+        }
+        Ok(sid as u32)
+    })
+}
@@ -1,5 +1,5 @@
 //! tcsetattr syscall
-use super::scheduler::SCHEDULER;
+use super::scheduler::{auto_preempt, TerminalAccess, SCHEDULER};
 use super::Fd;
 use super::SysResult;
 use libc_binding::termios;
@@ -83,22 +83,29 @@ pub fn sys_tcsetattr(
     optional_actions: u32,
     termios_p: *const termios,
 ) -> SysResult<u32> {
-    unpreemptible_context!({
-        let scheduler = SCHEDULER.lock();
-        let termios_p = {
-            let v = scheduler
-                .current_thread()
-                .unwrap_process()
-                .get_virtual_allocator();
+    loop {
+        unpreemptible_context!({
+            let mut scheduler = SCHEDULER.lock();
+            let termios_p = {
+                let v = scheduler
+                    .current_thread()
+                    .unwrap_process()
+                    .get_virtual_allocator();
 
-            // Check if pointer exists in user virtual address space
-            v.make_checked_ref(termios_p)?
-        };
-        let fd_interface = &scheduler
-            .current_thread_group_running()
-            .file_descriptor_interface;
+                // Check if pointer exists in user virtual address space
+                v.make_checked_ref(termios_p)?
+            };
+            // Job control: the process must be in the foreground process group
+            if !scheduler.current_thread_terminal_access(fildes, TerminalAccess::Control)? {
+                let _ret = auto_preempt();
+                continue;
+            }
+            let fd_interface = &scheduler
+                .current_thread_group_running()
+                .file_descriptor_interface;
 
-        let file_operation = &mut fd_interface.get_file_operation(fildes)?;
-        file_operation.tcsetattr(optional_actions, termios_p)
-    })
+            let file_operation = &mut fd_interface.get_file_operation(fildes)?;
+            return file_operation.tcsetattr(optional_actions, termios_p);
+        })
+    }
 }
@@ -1,8 +1,9 @@
 //! tcsetpgrp syscall
-use super::scheduler::SCHEDULER;
+use super::scheduler::{auto_preempt, TerminalAccess, SCHEDULER};
 use super::Fd;
 use super::Pid;
 use super::SysResult;
+use libc_binding::Errno;
 
 /// If the process has a controlling terminal, tcsetpgrp() shall set
 /// the foreground process group ID associated with the terminal to
@@ -13,7 +14,6 @@ use super::SysResult;
 /// value of pgid_id matches a process group ID of a process in the
 /// same session as the calling process.
 ///
-//TODO:
 /// Attempts to use tcsetpgrp() from a process which is a member of a
 /// background process group on a fildes associated with its
 /// controlling terminal shall cause the process group to be sent a
@@ -22,17 +22,53 @@ use super::SysResult;
 /// allowed to perform the operation, and no signal is sent.
 /// [EBADF]
 ///     The fildes argument is not a valid file descriptor.
+/// [EINVAL]
+///     The value of the pgid_id argument is less than 0.
+/// [EIO]
+///     The process group of the calling process is orphaned, the
+///     calling thread is not blocking SIGTTOU, and the process is not
+///     ignoring SIGTTOU.
 /// [ENOTTY]
 ///     The calling process does not have a controlling terminal, or
 ///     the file is not the controlling terminal.
+/// [EPERM]
+///     The value of pgid_id does not match the process group ID of a
+///     process in the same session as the calling process.
 pub fn sys_tcsetpgrp(fildes: Fd, pgid_id: Pid) -> SysResult<u32> {
-    unpreemptible_context!({
-        let scheduler = SCHEDULER.lock();
-        let fd_interface = &scheduler
-            .current_thread_group_running()
-            .file_descriptor_interface;
+    if pgid_id < 0 {
+        return Err(Errno::EINVAL);
+    }
+    loop {
+        unpreemptible_context!({
+            let mut scheduler = SCHEDULER.lock();
+            let sid = scheduler.current_thread_group().sid;
 
-        let file_operation = &mut fd_interface.get_file_operation(fildes)?;
-        file_operation.tcsetpgrp(pgid_id)
-    })
+            if scheduler
+                .current_thread_group_running()
+                .file_descriptor_interface
+                .get_file_operation(fildes)?
+                .tcgetsid()?
+                != sid
+            {
+                return Err(Errno::ENOTTY);
+            }
+            if !scheduler
+                .iter_thread_groups()
+                .any(|tg| tg.pgid == pgid_id && tg.sid == sid)
+            {
+                return Err(Errno::EPERM);
+            }
+            // Job control: the process must be in the foreground process group
+            if !scheduler.current_thread_terminal_access(fildes, TerminalAccess::Control)? {
+                let _ret = auto_preempt();
+                continue;
+            }
+            let fd_interface = &scheduler
+                .current_thread_group_running()
+                .file_descriptor_interface;
+
+            let file_operation = &mut fd_interface.get_file_operation(fildes)?;
+            return file_operation.tcsetpgrp(pgid_id);
+        })
+    }
 }
@@ -1,4 +1,4 @@
-use super::mmap::MmapArgStruct;
+use super::mmap::{MmapArgStruct, MmapFlags};
 use super::nanosleep::TimeSpec;
 use super::process::CpuState;
 use super::signal_interface::{sigset_t, StructSigaction};
@@ -10,18 +10,24 @@ use crate::memory::tools::address::Virt;
 use core::ffi::c_void;
 use i386::BaseRegisters;
 use libc_binding::{
-    c_char, dev_t, gid_t, kernel, mode_t, off_t, rusage, stat, termios, timeval, timezone, tms,
-    uid_t, utimbuf, OpenFlags, Pid, DIR,
+    c_char, dev_t, fd_set, gid_t, itimerval, kernel, mode_t, off_t, pollfd, rlimit, rusage,
+    sched_param, siginfo_t, stack_t, stat, termios, timespec, timeval, timezone, tms, uid_t,
+    user_desc, utimbuf, OpenFlags, Pid, DIR,
 };
 use libc_binding::{
-    ACCESS, CHDIR, CHMOD, CHOWN, CLONE, CLOSE, DUP, DUP2, EXECVE, EXIT, EXIT_QEMU, FCHMOD, FCHOWN,
-    FCNTL, FORK, FSTAT, GETCWD, GETEGID, GETEUID, GETGID, GETGROUPS, GETHOSTNAME, GETPGID, GETPGRP,
-    GETPID, GETPPID, GETTIMEOFDAY, GETUID, GET_KERNEL_PROPERTIES, INSMOD, IOCTL, ISATTY, KILL,
-    LINK, LSEEK, LSMOD, LSTAT, MKDIR, MKNOD, MMAP, MOUNT, MPROTECT, MUNMAP, NANOSLEEP, OPEN,
-    OPENDIR, PAUSE, PIPE, READ, READLINK, REBOOT, RENAME, RMDIR, RMMOD, SETEGID, SETEUID, SETGID,
-    SETGROUPS, SETHOSTNAME, SETPGID, SETUID, SHUTDOWN, SIGACTION, SIGNAL, SIGPROCMASK, SIGRETURN,
-    SIGSUSPEND, SOCKETCALL, STACK_OVERFLOW, STAT, SYMLINK, TCGETATTR, TCGETPGRP, TCSETATTR,
-    TCSETPGRP, TEST, TIMES, UMASK, UMOUNT, UNLINK, UTIME, WAIT4, WAITPID, WRITE,
+    ACCESS, CHDIR, CHMOD, CHOWN, CLOCK_GETRES, CLOCK_GETTIME, CLOCK_NANOSLEEP, CLONE, CLOSE, DUP,
+    DUP2, EXECVE, EXIT, EXIT_GROUP, EXIT_QEMU, FCHMOD, FCHOWN, FCNTL, FORK, FSTAT, FSYNC, FUTEX,
+    GETCWD, GETEGID, GETEUID, GETGID, GETGROUPS, GETHOSTNAME, GETITIMER, GETPGID, GETPGRP, GETPID,
+    GETPPID, GETPRIORITY, GETRLIMIT, GETSID, GETTIMEOFDAY, GETUID, GET_KERNEL_PROPERTIES, INSMOD,
+    IOCTL, ISATTY, KILL, LINK, LSEEK, LSMOD, LSTAT, MKDIR, MKNOD, MMAP, MMAP2, MOUNT, MPROTECT,
+    MSYNC, MUNMAP, NANOSLEEP, NICE, OPEN, OPENDIR, PAUSE, PIPE, PIVOT_ROOT, POLL, PPOLL, PRLIMIT,
+    PSELECT, PTRACE, READ, READLINK, REBOOT, RENAME, RMDIR, RMMOD, SCHED_GETPARAM,
+    SCHED_GETSCHEDULER, SCHED_SETSCHEDULER, SCHED_YIELD, SELECT, SETEGID, SETEUID, SETGID,
+    SETGROUPS, SETHOSTNAME, SETITIMER, SETPGID, SETPRIORITY, SETRLIMIT, SETSID, SETUID,
+    SET_THREAD_AREA, SHUTDOWN, SIGACTION, SIGALTSTACK, SIGNAL, SIGPROCMASK, SIGQUEUE, SIGRETURN,
+    SIGSUSPEND, SIGTIMEDWAIT, SOCKETCALL, STACK_OVERFLOW, STAT, SYMLINK, SYNC, TCGETATTR,
+    TCGETPGRP, TCGETSID, TCSETATTR, TCSETPGRP, TEST, TIMES, UMASK, UMOUNT, UNLINK, UTIME, WAIT4,
+    WAITPID, WRITE,
 };
 
 #[allow(dead_code)]
@@ -39,6 +45,7 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
     unpreemptible_context!({
         match eax {
             EXIT => log::info!("exit({:#?})", ebx as i32),
+            EXIT_GROUP => log::info!("exit_group({:#?})", ebx as i32),
             FORK => log::info!("fork()"),
             READ => log::info!(
                 "read({:#?}, {:#?}, {:#?})",
@@ -109,6 +116,13 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
             ),
             SETUID => log::info!("setuid({:#?})", ebx as uid_t),
             GETUID => log::info!("getuid()"),
+            PTRACE => log::info!(
+                "ptrace({:#?}, {:#?}, {:#?}, {:#?})",
+                ebx as u32,
+                ecx as Pid,
+                edx as *mut c_void,
+                esi as *mut c_void
+            ),
             PAUSE => log::info!("pause()"),
             FSTAT => log::info!("fstat(fd: {:?}, buf: {:#X?})", ebx as Fd, ecx as *mut stat),
             ACCESS => log::info!("access({:#?}, {:#?})", ebx as *const c_char, ecx as i32),
@@ -124,6 +138,7 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
                 ebx as *const c_char,
                 ecx as *const c_char,
             ),
+            SYNC => log::info!("sync()"),
             MKDIR => log::info!("mkdir({:#?}, {:#?})", ebx as *const c_char, ecx as mode_t),
             RMDIR => log::info!("rmdir({:#?})", ebx as *const c_char),
             PIPE => log::info!("pipe({:#?})", ebx as *const i32),
@@ -140,6 +155,11 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
             ),
             GETEGID => log::info!("getegid()"),
             UMOUNT => log::info!("umount({:#?})", ebx as *const c_char),
+            PIVOT_ROOT => log::info!(
+                "pivot_root({:#?}, {:#?})",
+                ebx as *const c_char,
+                ecx as *const c_char,
+            ),
             IOCTL => log::info!(
                 "ioctl({:#?}, {:#?}, {:#?})",
                 ebx as Fd,
@@ -148,6 +168,7 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
             ),
             SIGNAL => log::info!("signal({:#?}, {:#?})", ebx as u32, ecx as usize),
             SETPGID => log::info!("setpgid({:#?}, {:#?})", ebx as Pid, ecx as Pid),
+            SETSID => log::info!("setsid()"),
             GETPPID => log::info!("getppid()"),
             DUP2 => log::info!("dup2({:#?}, {:#?})", ebx as u32, ecx as u32),
             GETPGRP => log::info!("getpgrp()"),
@@ -189,11 +210,15 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
                 edx as i32,
                 esi as *mut rusage,
             ),
+            FSYNC => log::info!("fsync({:#?})", ebx as i32),
             CLONE => log::info!(
-                "clone({:#?}, {:#?}, {:#?})",
+                "clone({:#?}, {:#?}, {:#?}, {:#?}, {:#?}, {:#?})",
                 cpu_state as u32,
                 ebx as *const c_void,
-                ecx as u32
+                ecx as u32,
+                edx as *mut Pid,
+                esi as *const user_desc,
+                edi as *mut Pid
             ),
             MPROTECT => log::info!(
                 "mprotect({:#?}, {:#?}, {:#?})",
@@ -201,6 +226,29 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
                 ecx as usize,
                 MmapProt::from_bits_truncate(edx),
             ),
+            SELECT => log::info!(
+                "select({:#?}, {:#?}, {:#?}, {:#?}, {:#?})",
+                ebx as i32,
+                ecx as *mut fd_set,
+                edx as *mut fd_set,
+                esi as *mut fd_set,
+                edi as *mut timeval
+            ),
+            MSYNC => log::info!(
+                "msync({:#?}, {:#?}, {:#?})",
+                Virt(ebx as usize),
+                ecx as usize,
+                edx as u32
+            ),
+            MMAP2 => log::info!(
+                "mmap2({:#?}, {:#?}, {:#?}, {:#?}, {:#?}, {:#?})",
+                Virt(ebx as usize),
+                ecx as usize,
+                MmapProt::from_bits_truncate(edx),
+                MmapFlags::from_bits_truncate(esi),
+                edi as i32,
+                ebp as usize
+            ),
             SIGPROCMASK => log::info!(
                 "sigprocmask({:#?}, {:#?}, {:#?})",
                 ebx as i32,
@@ -208,11 +256,18 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
                 edx as *mut sigset_t
             ),
             GETPGID => log::info!("getpgid({:#?})", ebx as Pid),
+            GETSID => log::info!("getsid({:#?})", ebx as Pid),
             NANOSLEEP => log::info!(
                 "nanosleep({:#?}, {:#?})",
                 ebx as *const TimeSpec,
                 ecx as *mut TimeSpec
             ),
+            POLL => log::info!(
+                "poll({:#?}, {:#?}, {:#?})",
+                ebx as *mut pollfd,
+                ecx as u32,
+                edx as i32
+            ),
             CHOWN => log::info!(
                 "chown({:#?}, {:#?}, {:#?})",
                 ebx as *const c_char,
@@ -230,6 +285,22 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
             GETCWD => log::info!("getcwd({:#?}, {:#?})", ebx as *const c_char, ecx as usize),
             SIGRETURN => log::info!("sigreturn({:#?})", cpu_state),
             SHUTDOWN => log::info!("shutdown()"),
+            PSELECT => log::info!(
+                "pselect({:#?}, {:#?}, {:#?}, {:#?}, {:#?}, {:#?})",
+                ebx as i32,
+                ecx as *mut fd_set,
+                edx as *mut fd_set,
+                esi as *mut fd_set,
+                edi as *const timespec,
+                ebp as *const sigset_t
+            ),
+            PPOLL => log::info!(
+                "ppoll({:#?}, {:#?}, {:#?}, {:#?})",
+                ebx as *mut pollfd,
+                ecx as u32,
+                edx as *const timespec,
+                esi as *const sigset_t
+            ),
             TEST => log::info!("test()"),
             STACK_OVERFLOW => log::info!("stack_overflow()"),
             EXIT_QEMU => log::info!("exit_qemu({:#?})", ebx as u32),
@@ -242,6 +313,7 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
             ),
             TCSETPGRP => log::info!("tcsetpgrp({:#?}, {:#?})", ebx as i32, ecx as Pid),
             TCGETPGRP => log::info!("tcgetpgrp({:#?})", ebx as i32),
+            TCGETSID => log::info!("tcgetsid({:#?})", ebx as i32),
             SETEGID => log::info!("setegid({:#?})", ebx as gid_t),
             SETEUID => log::info!("seteuid({:#?})", ebx as uid_t),
             ISATTY => log::info!("isatty({:#?})", ebx as u32),
@@ -249,6 +321,87 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
             INSMOD => log::info!("insmod({:#?})", ebx as *const c_char),
             RMMOD => log::info!("rmmod({:#?})", ebx as *const c_char),
             LSMOD => log::info!("lsmod"),
+            NICE => log::info!("nice({:#?})", ebx as i32),
+            GETPRIORITY => log::info!("getpriority({:#?}, {:#?})", ebx as u32, ecx as u32),
+            SETPRIORITY => log::info!(
+                "setpriority({:#?}, {:#?}, {:#?})",
+                ebx as u32,
+                ecx as u32,
+                edx as i32
+            ),
+            GETRLIMIT => log::info!("getrlimit({:#?}, {:#?})", ebx as u32, ecx as *mut rlimit),
+            SETRLIMIT => log::info!("setrlimit({:#?}, {:#?})", ebx as u32, ecx as *const rlimit),
+            PRLIMIT => log::info!(
+                "prlimit({:#?}, {:#?}, {:#?}, {:#?})",
+                ebx as Pid,
+                ecx as u32,
+                edx as *const rlimit,
+                esi as *mut rlimit
+            ),
+            SCHED_SETSCHEDULER => log::info!(
+                "sched_setscheduler({:#?}, {:#?}, {:#?})",
+                ebx as Pid,
+                ecx as i32,
+                edx as *const sched_param
+            ),
+            SCHED_GETSCHEDULER => log::info!("sched_getscheduler({:#?})", ebx as Pid),
+            SCHED_GETPARAM => log::info!(
+                "sched_getparam({:#?}, {:#?})",
+                ebx as Pid,
+                ecx as *mut sched_param
+            ),
+            SCHED_YIELD => log::info!("sched_yield()"),
+            GETITIMER => log::info!("getitimer({:#?}, {:#?})", ebx as u32, ecx as *mut itimerval),
+            SETITIMER => log::info!(
+                "setitimer({:#?}, {:#?}, {:#?})",
+                ebx as u32,
+                ecx as *const itimerval,
+                edx as *mut itimerval
+            ),
+            CLOCK_GETTIME => log::info!(
+                "clock_gettime({:#?}, {:#?})",
+                ebx as u32,
+                ecx as *mut timespec
+            ),
+            CLOCK_GETRES => log::info!(
+                "clock_getres({:#?}, {:#?})",
+                ebx as u32,
+                ecx as *mut timespec
+            ),
+            CLOCK_NANOSLEEP => log::info!(
+                "clock_nanosleep({:#?}, {:#?}, {:#?}, {:#?})",
+                ebx as u32,
+                ecx as u32,
+                edx as *const timespec,
+                esi as *mut timespec
+            ),
+            SIGALTSTACK => log::info!(
+                "sigaltstack({:#?}, {:#?})",
+                ebx as *const stack_t,
+                ecx as *mut stack_t
+            ),
+            SIGQUEUE => log::info!(
+                "sigqueue({:#?}, {:#?}, {:#?})",
+                ebx as Pid,
+                ecx as u32,
+                edx as i32
+            ),
+            SIGTIMEDWAIT => log::info!(
+                "sigtimedwait({:#?}, {:#?}, {:#?})",
+                ebx as *const sigset_t,
+                ecx as *mut siginfo_t,
+                edx as *const timespec
+            ),
+            FUTEX => log::info!(
+                "futex({:#?}, {:#?}, {:#?}, {:#?}, {:#?}, {:#?})",
+                ebx as *const i32,
+                ecx as u32,
+                edx as i32,
+                esi as *const timespec,
+                edi as *const i32,
+                ebp as i32
+            ),
+            SET_THREAD_AREA => log::info!("set_thread_area({:#?})", ebx as *mut user_desc),
             GETHOSTNAME => log::info!(
                 "gethostname({:#?}, {:#?})",
                 ebx as *mut c_char,
@@ -273,6 +426,7 @@ pub fn trace_syscall_result(cpu_state: *mut CpuState, result: SysResult<u32>) {
 
     let sysname = match eax {
         EXIT => "exit",
+        EXIT_GROUP => "exit_group",
         FORK => "fork",
         READ => "read",
         WRITE => "write",
@@ -291,12 +445,14 @@ pub fn trace_syscall_result(cpu_state: *mut CpuState, result: SysResult<u32>) {
         MOUNT => "mount",
         SETUID => "setuid",
         GETUID => "getuid",
+        PTRACE => "ptrace",
         PAUSE => "pause",
         FSTAT => "fstat",
         UTIME => "utime",
         ACCESS => "access",
         KILL => "kill",
         RENAME => "rename",
+        SYNC => "sync",
         MKDIR => "mkdir",
         RMDIR => "rmdir",
         PIPE => "pipe",
@@ -306,9 +462,11 @@ pub fn trace_syscall_result(cpu_state: *mut CpuState, result: SysResult<u32>) {
         FCNTL => "fcntl",
         GETEGID => "getegid",
         UMOUNT => "umount",
+        PIVOT_ROOT => "pivot_root",
         IOCTL => "ioctl",
         SIGNAL => "signal",
         SETPGID => "setpgid",
+        SETSID => "setsid",
         GETPPID => "getppid",
         GETPGRP => "getpgrp",
         SIGACTION => "sigaction",
@@ -324,16 +482,24 @@ pub fn trace_syscall_result(cpu_state: *mut CpuState, result: SysResult<u32>) {
         GETTIMEOFDAY => "gettimeofday",
         SOCKETCALL => "socketcall",
         WAIT4 => "wait4",
+        FSYNC => "fsync",
         CLONE => "clone",
         MPROTECT => "mprotect",
+        SELECT => "select",
+        MSYNC => "msync",
+        MMAP2 => "mmap2",
         SIGPROCMASK => "sigprocmask",
         GETPGID => "getpgid",
+        GETSID => "getsid",
         CHOWN => "chown",
         FCHOWN => "fchown",
         NANOSLEEP => "nanosleep",
+        POLL => "poll",
         GETCWD => "getcwd",
         SIGRETURN => "sigreturn",
         SHUTDOWN => "shutdown",
+        PSELECT => "pselect",
+        PPOLL => "ppoll",
         TEST => "test",
         STACK_OVERFLOW => "stack_overflow",
         EXIT_QEMU => "exit_qemu",
@@ -341,6 +507,7 @@ pub fn trace_syscall_result(cpu_state: *mut CpuState, result: SysResult<u32>) {
         TCSETATTR => "tcsetattr",
         TCSETPGRP => "tcsetpgrp",
         TCGETPGRP => "tcgetpgrp",
+        TCGETSID => "tcgetsid",
         SETEGID => "setegid",
         SETEUID => "seteuid",
         ISATTY => "isatty",
@@ -348,6 +515,26 @@ pub fn trace_syscall_result(cpu_state: *mut CpuState, result: SysResult<u32>) {
         INSMOD => "insmod",
         RMMOD => "rmmod",
         LSMOD => "lsmod",
+        NICE => "nice",
+        GETPRIORITY => "getpriority",
+        SETPRIORITY => "setpriority",
+        GETRLIMIT => "getrlimit",
+        SETRLIMIT => "setrlimit",
+        PRLIMIT => "prlimit",
+        SCHED_SETSCHEDULER => "sched_setscheduler",
+        SCHED_GETSCHEDULER => "sched_getscheduler",
+        SCHED_GETPARAM => "sched_getparam",
+        SCHED_YIELD => "sched_yield",
+        GETITIMER => "getitimer",
+        SETITIMER => "setitimer",
+        CLOCK_GETTIME => "clock_gettime",
+        CLOCK_GETRES => "clock_getres",
+        CLOCK_NANOSLEEP => "clock_nanosleep",
+        SIGALTSTACK => "sigaltstack",
+        SIGQUEUE => "sigqueue",
+        SIGTIMEDWAIT => "sigtimedwait",
+        FUTEX => "futex",
+        SET_THREAD_AREA => "set_thread_area",
         GETHOSTNAME => "gethostname",
         SETHOSTNAME => "sethostname",
         GET_KERNEL_PROPERTIES => "get_kernel_properties",
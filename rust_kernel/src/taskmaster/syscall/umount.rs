@@ -3,7 +3,7 @@ use super::vfs::{Path, VFS};
 use super::SysResult;
 use core::convert::TryFrom;
 
-use libc_binding::c_char;
+use libc_binding::{c_char, Errno};
 
 pub fn sys_umount(path: *const c_char) -> SysResult<u32> {
     unpreemptible_context!({
@@ -19,6 +19,9 @@ pub fn sys_umount(path: *const c_char) -> SysResult<u32> {
 
         let tg = scheduler.current_thread_group();
         let creds = &tg.credentials;
+        if !creds.is_root() {
+            return Err(Errno::EPERM);
+        }
         let cwd = &tg.cwd;
         let path = Path::try_from(safe_path)?;
 
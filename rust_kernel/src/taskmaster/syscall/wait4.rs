@@ -233,28 +233,27 @@ fn wait4(pid: i32, wstatus: *mut i32, options: u32, rusage: *mut rusage) -> SysR
     // Return EINVAL for any unknown option
     let options = WaitOption::from_bits(options).ok_or(Errno::EINVAL)?;
 
-    let thread_group = scheduler.current_thread_group();
+    let current_pid = scheduler.current_task_id().0;
+    let pgid = scheduler.current_thread_group().pgid;
 
     // The pid argument specifies a set of child processes for which
     // status is requested. The waitpid() function shall only return the
     // status of a child process from this set:
     // child_pid is a Option. Some(child_pid) if some child is dead, None
-    // otherwise
+    // otherwise. The tracees of the calling process are waited like its children
     let child_pid = match pid {
         // If pid is equal to (pid_t)-1, status is requested for any
         // child process. In this respect, waitpid() is then
         // equivalent to wait().
         -1 => {
             // Check if at leat one child exists
-            if thread_group.unwrap_running().child.len() == 0 {
+            if scheduler.waitable_processes().count() == 0 {
                 return Err(Errno::ECHILD);
             }
             // Check is the at least one child is a already a zombie -> Return immediatly child PID
-            thread_group
-                .unwrap_running()
-                .child
-                .iter()
-                .find(|&current_pid| scheduler.has_status_available(*current_pid, options))
+            scheduler
+                .waitable_processes()
+                .find(|&current_pid| scheduler.has_status_available(current_pid, options))
         }
         // If pid is less than (pid_t)-1, status is requested for any
         // child process whose process group ID is equal to the
@@ -264,41 +263,38 @@ fn wait4(pid: i32, wstatus: *mut i32, options: u32, rusage: *mut rusage) -> SysR
             // whose process group ID is equal to that of the calling
             // process.
             if pid == 0 {
-                pid = thread_group.pgid;
+                pid = pgid;
             }
-            let candidate_number = thread_group
-                .unwrap_running()
-                .child
-                .iter()
-                .map(|&current_pid| {
-                    scheduler
-                        .get_thread_group(current_pid)
-                        .expect("Pid must be here")
-                })
-                .filter(|tg| tg.pgid == -pid)
+            let in_process_group = |current_pid: &Pid| {
+                scheduler
+                    .get_thread_group(*current_pid)
+                    .expect("Pid must be here")
+                    .pgid
+                    == -pid
+            };
+            let candidate_number = scheduler
+                .waitable_processes()
+                .filter(in_process_group)
                 .count();
 
             if candidate_number == 0 {
                 return Err(Errno::ECHILD);
             }
 
-            thread_group
-                .unwrap_running()
-                .child
-                .iter()
-                .find(|&current_pid| scheduler.has_status_available(*current_pid, options))
+            scheduler
+                .waitable_processes()
+                .filter(in_process_group)
+                .find(|&current_pid| scheduler.has_status_available(current_pid, options))
         }
         // If pid is greater than 0, it specifies the process ID of a
         // single child process for which status is requested.
         pid if pid > 0 => {
             // Check if specified child exists
-            if let Some(elem) = thread_group
-                .unwrap_running()
-                .child
-                .iter()
-                .find(|&&current_pid| current_pid == pid)
+            if let Some(elem) = scheduler
+                .waitable_processes()
+                .find(|&current_pid| current_pid == pid)
             {
-                if scheduler.has_status_available(*elem, options) {
+                if scheduler.has_status_available(elem, options) {
                     Some(elem)
                 } else {
                     None
@@ -311,7 +307,7 @@ fn wait4(pid: i32, wstatus: *mut i32, options: u32, rusage: *mut rusage) -> SysR
     };
 
     match child_pid {
-        Some(&dead_pid) => {
+        Some(dead_pid) => {
             let tg = scheduler
                 .get_thread_group_mut(dead_pid)
                 .expect("Pid must be here");
@@ -319,17 +315,20 @@ fn wait4(pid: i32, wstatus: *mut i32, options: u32, rusage: *mut rusage) -> SysR
             let status = match tg.get_death_status() {
                 Some(status) => {
                     if let Some(rusage) = rusage {
-                        *rusage = scheduler
-                            .get_thread_group(dead_pid)
-                            .expect("Woot ?")
-                            .process_duration
-                            .into();
+                        *rusage = tg.process_duration.into();
                     }
-                    scheduler.current_thread_group_mut().remove_child(dead_pid);
-                    scheduler.remove_thread_group(dead_pid);
+                    scheduler.reap_waited_process(dead_pid);
                     status
                 }
-                None => Status::from(tg.job.consume_last_event().expect("no status")),
+                None => match tg
+                    .tracee
+                    .as_mut()
+                    .filter(|tracee| tracee.tracer == current_pid)
+                    .and_then(|tracee| tracee.report_stop())
+                {
+                    Some(status) => status,
+                    None => Status::from(tg.job.consume_last_event().expect("no status")),
+                },
             };
             if let Some(wstatus) = wstatus {
                 *wstatus = status.into()
@@ -341,7 +340,6 @@ fn wait4(pid: i32, wstatus: *mut i32, options: u32, rusage: *mut rusage) -> SysR
         }
         None => {
             // Set process as Waiting for ChildDeath. set the PID option inside
-            let pgid = thread_group.pgid;
             scheduler
                 .current_thread_mut()
                 .set_waiting(WaitingState::Waitpid { pid, pgid, options });
@@ -365,9 +363,7 @@ fn wait4(pid: i32, wstatus: *mut i32, options: u32, rusage: *mut rusage) -> SysR
                             .into();
                     }
                     if status.is_terminated() {
-                        let thread_group = scheduler.current_thread_group_mut();
-                        thread_group.remove_child(dead_process_pid);
-                        scheduler.remove_thread_group(dead_process_pid);
+                        scheduler.reap_waited_process(dead_process_pid);
                     }
                     // Set wstatus pointer is not null by reading y
                     if let Some(wstatus) = wstatus {
@@ -399,12 +395,52 @@ bitflags! {
 }
 
 impl Scheduler {
+    /// The processes waited by the current process: its children and its tracees
+    fn waitable_processes<'a>(&'a self) -> impl Iterator<Item = Pid> + 'a {
+        let current_pid = self.current_task_id().0;
+        let tracees = self
+            .iter_thread_groups_with_pid()
+            .filter(move |(_, tg)| tg.parent != current_pid)
+            .filter(move |&(&pid, _)| self.is_traced_by(pid, current_pid))
+            .map(|(&pid, _)| pid);
+        self.current_thread_group()
+            .unwrap_running()
+            .child
+            .iter()
+            .cloned()
+            .chain(tracees)
+    }
+
     fn has_status_available(&self, pid: Pid, options: WaitOption) -> bool {
         let thread_group = self.get_thread_group(pid).expect("Pid must be here");
+        let is_child = thread_group.parent == self.current_task_id().0;
+        let has_trace_stop = self.is_traced_by(pid, self.current_task_id().0)
+            && thread_group
+                .tracee
+                .as_ref()
+                .map_or(false, |tracee| tracee.has_unreported_stop());
+        let stopped_event = match thread_group.job.get_last_event() {
+            Some(JobState::Stopped(_)) => true,
+            _ => false,
+        };
         thread_group.is_zombie()
-            || (options.contains(WaitOption::WUNTRACED)
-                && thread_group.job.get_last_event() == Some(JobState::Stopped))
-            || (options.contains(WaitOption::WUNTRACED)
+            || has_trace_stop
+            || (is_child && options.contains(WaitOption::WUNTRACED) && stopped_event)
+            || (is_child
+                && options.contains(WaitOption::WUNTRACED)
                 && thread_group.job.get_last_event() == Some(JobState::Continued))
     }
+
+    /// Remove a waited zombie: a tracee which is not a child of the
+    /// current process is only detached, its parent reaps it
+    fn reap_waited_process(&mut self, pid: Pid) {
+        let current_pid = self.current_task_id().0;
+        let thread_group = self.get_thread_group_mut(pid).expect("Pid must be here");
+        if thread_group.parent == current_pid {
+            self.current_thread_group_mut().remove_child(pid);
+            self.remove_thread_group(pid);
+        } else {
+            thread_group.tracee = None;
+        }
+    }
 }
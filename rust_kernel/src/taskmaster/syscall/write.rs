@@ -3,7 +3,7 @@
 use super::SysResult;
 
 use super::scheduler::auto_preempt;
-use super::scheduler::SCHEDULER;
+use super::scheduler::{TerminalAccess, SCHEDULER};
 use super::thread::WaitingState;
 use super::IpcResult;
 
@@ -24,6 +24,12 @@ pub fn sys_write(fd: i32, mut buf: *const u8, mut count: usize) -> SysResult<u32
                 v.make_checked_slice(buf, count)?
             };
 
+            // Job control: a background process may not write on its controlling terminal (cf TOSTOP)
+            if !scheduler.current_thread_terminal_access(fd as _, TerminalAccess::Write)? {
+                let _ret = auto_preempt();
+                continue;
+            }
+
             let fd_interface = &mut scheduler
                 .current_thread_group_running_mut()
                 .file_descriptor_interface;
@@ -1,8 +1,8 @@
 //! This file contains definition of a task
 
 use super::process::{CpuState, UserProcess};
-use super::scheduler::Pid;
-use super::signal_interface::SignalInterface;
+use super::scheduler::{Pid, Priority};
+use super::signal_interface::{sigset_t, SignalInterface};
 use super::syscall::clone::CloneFlags;
 use super::syscall::WaitOption;
 use super::thread_group::Status;
@@ -13,6 +13,7 @@ use fallible_collections::FallibleBox;
 
 use alloc::boxed::Box;
 use alloc::collections::CollectionAllocErr;
+use alloc::vec::Vec;
 
 use core::mem;
 
@@ -40,6 +41,10 @@ pub struct Thread {
     pub signal: SignalInterface,
     /// Return value for auto_preempt
     autopreempt_return_value: Box<SysResult<AutoPreemptReturnValue>>,
+    /// Scheduling policy and nice value, inherited by the children
+    pub priority: Priority,
+    /// Cleared and woken as a futex when the thread exits (cf CLONE_CHILD_CLEARTID)
+    pub clear_child_tid: Option<*mut Pid>,
 }
 
 impl Thread {
@@ -48,6 +53,8 @@ impl Thread {
             process_state,
             signal: SignalInterface::new(),
             autopreempt_return_value: Box::try_new(Ok(Default::default()))?,
+            priority: Default::default(),
+            clear_child_tid: None,
         })
     }
 
@@ -73,6 +80,8 @@ impl Thread {
                 _ => panic!("Non running process should not clone"),
             },
             autopreempt_return_value: Box::try_new(Ok(Default::default()))?,
+            priority: self.priority,
+            clear_child_tid: None,
         })
     }
 
@@ -154,8 +163,11 @@ pub enum WaitingState {
         pgid: Pid,
         options: WaitOption,
     },
-    /// In Waiting to read
-    Read(usize),
+    /// In Waiting to read, the timeout is a pit time like Sleeping (cf VTIME of the terminals)
+    Read {
+        file_op_uid: usize,
+        timeout: Option<u32>,
+    },
     /// In Waiting to write
     Write(usize),
     /// In Waiting to open
@@ -164,6 +176,18 @@ pub enum WaitingState {
     Connect(usize),
     /// In waiting for a socket connection
     Accept(usize),
+    /// In waiting for an event on one of the file operations (cf poll() and select()),
+    /// the timeout is a pit time like Sleeping
+    Poll {
+        file_op_uids: Vec<usize>,
+        timeout: Option<u32>,
+    },
+    /// In waiting for one of the signals of the set to be pending (cf sigtimedwait()),
+    /// the timeout is a pit time like Sleeping
+    SigWait { set: sigset_t, timeout: Option<u32> },
+    /// In waiting for a FUTEX_WAKE on the user address uaddr (cf futex()),
+    /// the timeout is a pit time like Sleeping
+    Futex { uaddr: u32, timeout: Option<u32> },
 }
 
 #[derive(Debug)]
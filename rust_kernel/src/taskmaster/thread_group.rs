@@ -1,3 +1,4 @@
+use super::drivers::FileOperation;
 use super::fd_interface::FileDescriptorInterface;
 use super::global_time::ProcessDuration;
 use super::scheduler::{Pid, Tid};
@@ -11,12 +12,23 @@ use super::vfs::Path;
 use super::safe_ffi::CStringArray;
 
 use alloc::collections::CollectionAllocErr;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::ffi::c_void;
 use fallible_collections::{btree::BTreeMap, TryClone};
-use libc_binding::{dev_t, gid_t, mode_t, uid_t, Signum};
+use libc_binding::{gid_t, mode_t, rlimit, uid_t, Signum};
+use sync::dead_mutex::DeadMutex;
 use try_clone_derive::TryClone;
 
+mod itimer;
+pub use itimer::{duration_to_timeval, timeval_to_duration, ITimer, IntervalTimers};
+
+mod rlimit;
+pub use rlimit::{Resource, ResourceLimits};
+
+mod ptrace;
+pub use ptrace::{Resume, TraceEvent, TraceStop, Tracee};
+
 #[derive(Debug)]
 pub enum ThreadGroupState {
     /// The process is running and has a thread list
@@ -86,6 +98,8 @@ pub struct ThreadGroup {
     pub thread_group_state: ThreadGroupState,
     /// the process group id
     pub pgid: Pid,
+    /// the session id: the pid of the session leader
+    pub sid: Pid,
     /// Parent
     pub parent: Pid,
     /// the next availabel tid for a new thread
@@ -94,11 +108,16 @@ pub struct ThreadGroup {
     pub job: Job,
     /// Time the process has spend is user space and in kernel space
     pub process_duration: ProcessDuration,
+    /// The ITIMER_REAL, ITIMER_VIRTUAL and ITIMER_PROF timers of the process
+    pub itimers: IntervalTimers,
     /// The umask of the process: The actived bits in it are disabled in all file creating operations.
     pub umask: mode_t,
+    /// The limits on the resources of the process, inherited on fork
+    rlimits: ResourceLimits,
 
-    /// The minor of the controlling terminal of the process.
-    pub controlling_terminal: Option<dev_t>,
+    /// The controlling terminal of the session of the process, acquired by its session
+    /// leader on the first open() of a terminal (cf setsid()). It is shared by the session
+    pub controlling_terminal: Option<Arc<DeadMutex<dyn FileOperation>>>,
 
     /// Filled by execve, used by /proc/[pid]/environ in the procfs.
     pub environ: Option<CStringArray>,
@@ -108,6 +127,9 @@ pub struct ThreadGroup {
 
     /// Filled by execve, used by /proc/[pid]/exe in the procfs.
     pub filename: Option<Path>,
+
+    /// The tracing state of the process when it is traced with ptrace()
+    pub tracee: Option<Tracee>,
 }
 
 #[derive(Debug, TryClone)]
@@ -226,13 +248,18 @@ impl ThreadGroup {
             }),
             next_tid: 1,
             pgid,
+            // A process created by the kernel is the leader of its own session
+            sid: pgid,
             job: Job::new(),
             process_duration: ProcessDuration::default(),
+            itimers: IntervalTimers::default(),
             umask: 0,
-            controlling_terminal: None, // hum...
+            rlimits: ResourceLimits::default(),
+            controlling_terminal: None,
             environ: None,
             argv: None,
             filename: None,
+            tracee: None,
         })
     }
 
@@ -283,20 +310,51 @@ impl ThreadGroup {
                     .try_clone()?,
             }),
             pgid: self.pgid,
+            sid: self.sid,
             next_tid: 1,
             job: Job::new(),
             process_duration: ProcessDuration::default(),
+            itimers: IntervalTimers::default(),
             umask: 0,
-            controlling_terminal: self.controlling_terminal,
+            rlimits: self.rlimits,
+            controlling_terminal: self.controlling_terminal.clone(),
             environ: None,
             argv: None,
             filename: None,
+            tracee: None,
         };
 
         self.unwrap_running_mut().child.push(child_pid);
         Ok(child)
     }
 
+    pub fn get_rlimit(&self, resource: Resource) -> rlimit {
+        self.rlimits.get(resource)
+    }
+
+    /// Get the soft limit of a resource, None if it is unlimited
+    pub fn soft_limit(&self, resource: Resource) -> Option<usize> {
+        self.rlimits.soft_limit(resource)
+    }
+
+    /// Set the limits of a resource. Only a privileged process can raise a hard limit
+    pub fn set_rlimit(
+        &mut self,
+        resource: Resource,
+        new: rlimit,
+        privileged: bool,
+    ) -> SysResult<()> {
+        self.rlimits.set(resource, new, privileged)?;
+        if resource == Resource::NoFile {
+            if let ThreadGroupState::Running(running) = &mut self.thread_group_state {
+                running
+                    .file_descriptor_interface
+                    .set_max_fd(new.rlim_cur.min(ResourceLimits::MAX_NOFILE));
+            }
+        }
+        Ok(())
+    }
+
     /// remove pid `pid` from the child list, Panic if not present
     pub fn remove_child(&mut self, pid: Pid) {
         self.unwrap_running_mut()
@@ -347,7 +405,9 @@ impl ThreadGroup {
 pub enum Status {
     Exited(i32),
     Signaled(Signum),
-    Stopped,
+    /// The stop signal, ORed with 0x80 for the syscall stops of a
+    /// tracee with PTRACE_O_TRACESYSGOOD
+    Stopped(u32),
     Continued,
 }
 
@@ -370,17 +430,34 @@ impl Status {
             _ => false,
         }
     }
+    pub fn is_stopped(&self) -> bool {
+        match self {
+            Self::Stopped(_) => true,
+            _ => false,
+        }
+    }
+    /// Get the si_code and the si_status of the SIGCHLD signal
+    /// relative to this status
+    pub fn sigchld_info(&self) -> (i32, i32) {
+        match *self {
+            Self::Exited(value) => (CLD_EXITED as i32, value),
+            Self::Signaled(signum) => (CLD_KILLED as i32, signum as i32),
+            Self::Stopped(signum) => (CLD_STOPPED as i32, (signum & 0x7f) as i32),
+            Self::Continued => (CLD_CONTINUED as i32, Signum::SIGCONT as i32),
+        }
+    }
 }
 
 impl From<JobState> for Status {
     fn from(job_state: JobState) -> Self {
         match job_state {
             JobState::Continued => Self::Continued,
-            JobState::Stopped => Self::Stopped,
+            JobState::Stopped(signum) => Self::Stopped(signum as u32),
         }
     }
 }
 
+use libc_binding::{CLD_CONTINUED, CLD_EXITED, CLD_KILLED, CLD_STOPPED};
 use libc_binding::{
     CONTINUED_STATUS_BIT, EXITED_STATUS_BITS, SIGNALED_STATUS_BITS, SIGNALED_STATUS_SHIFT,
     STOPPED_STATUS_BIT,
@@ -393,7 +470,7 @@ impl From<Status> for i32 {
         match status {
             Exited(v) => v,
             Signaled(signum) => (signum as i32) << SIGNALED_STATUS_SHIFT as i32,
-            Stopped => STOPPED_STATUS_BIT as _,
+            Stopped(signum) => (STOPPED_STATUS_BIT | signum) as _,
             Continued => CONTINUED_STATUS_BIT as _,
         }
     }
@@ -409,8 +486,10 @@ impl From<i32> for Status {
             && status & !SIGNALED_STATUS_BITS as i32 == 0
         {
             Signaled(unsafe { core::mem::transmute(status >> SIGNALED_STATUS_SHIFT) })
-        } else if status & !STOPPED_STATUS_BIT as i32 == 0 {
-            Stopped
+        } else if status & STOPPED_STATUS_BIT as i32 != 0
+            && status & !(STOPPED_STATUS_BIT | EXITED_STATUS_BITS) as i32 == 0
+        {
+            Stopped((status & EXITED_STATUS_BITS as i32) as u32)
         } else if status & !CONTINUED_STATUS_BIT as i32 == 0 {
             Continued
         } else {
@@ -422,7 +501,8 @@ impl From<i32> for Status {
 /// State of a process in the point of view of JobAction
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum JobState {
-    Stopped,
+    /// Stopped by the contained signal
+    Stopped(Signum),
     Continued,
 }
 
@@ -445,7 +525,7 @@ impl Job {
     }
     /// Try to set as continue, return TRUE is state is changing
     pub fn try_set_continued(&mut self) -> bool {
-        if self.state == JobState::Stopped {
+        if self.is_stopped() {
             self.state = JobState::Continued;
             self.last_event = Some(JobState::Continued);
             true
@@ -453,11 +533,11 @@ impl Job {
             false
         }
     }
-    /// Try to set as stoped, return TRUE is state is changing
-    pub fn try_set_stoped(&mut self) -> bool {
+    /// Try to set as stoped by `signum`, return TRUE is state is changing
+    pub fn try_set_stoped(&mut self, signum: Signum) -> bool {
         if self.state == JobState::Continued {
-            self.state = JobState::Stopped;
-            self.last_event = Some(JobState::Stopped);
+            self.state = JobState::Stopped(signum);
+            self.last_event = Some(JobState::Stopped(signum));
             true
         } else {
             false
@@ -472,6 +552,13 @@ impl Job {
     pub fn get_last_event(&self) -> Option<JobState> {
         self.last_event
     }
+
+    pub fn is_stopped(&self) -> bool {
+        match self.state {
+            JobState::Stopped(_) => true,
+            JobState::Continued => false,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,181 @@
+//! This file contains the interval timers of a process, cf setitimer()
+
+use super::{ProcessDuration, SysResult};
+
+use core::convert::TryFrom;
+use core::time::Duration;
+use libc_binding::{itimerval, timeval, Errno, Signum, ITIMER_PROF, ITIMER_REAL, ITIMER_VIRTUAL};
+
+/// The three interval timers of a process
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ITimer {
+    /// Decrements in real time and delivers SIGALRM
+    Real,
+    /// Decrements in process virtual time and delivers SIGVTALRM
+    Virtual,
+    /// Decrements in process virtual and system time and delivers SIGPROF
+    Prof,
+}
+
+impl TryFrom<u32> for ITimer {
+    type Error = Errno;
+
+    fn try_from(which: u32) -> Result<Self, Self::Error> {
+        match which {
+            ITIMER_REAL => Ok(ITimer::Real),
+            ITIMER_VIRTUAL => Ok(ITimer::Virtual),
+            ITIMER_PROF => Ok(ITimer::Prof),
+            _ => Err(Errno::EINVAL),
+        }
+    }
+}
+
+impl ITimer {
+    /// The signal generated when the timer expires
+    pub fn signal(self) -> Signum {
+        match self {
+            ITimer::Real => Signum::SIGALRM,
+            ITimer::Virtual => Signum::SIGVTALRM,
+            ITimer::Prof => Signum::SIGPROF,
+        }
+    }
+}
+
+/// Convert a timeval into a Duration, the microseconds must be valid
+pub fn timeval_to_duration(timeval: &timeval) -> SysResult<Duration> {
+    if timeval.tv_sec < 0 || timeval.tv_usec >= 1000000 {
+        return Err(Errno::EINVAL);
+    }
+    Ok(Duration::new(timeval.tv_sec as u64, timeval.tv_usec * 1000))
+}
+
+/// Convert a Duration into a timeval
+pub fn duration_to_timeval(duration: Duration) -> timeval {
+    timeval {
+        tv_sec: duration.as_secs() as _,
+        tv_usec: duration.subsec_micros(),
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+struct Timer {
+    /// The monotonic time of expiration for ITimer::Real, the
+    /// remaining CPU time for the other ones. None if disarmed
+    value: Option<Duration>,
+    /// The value reloaded on expiration, zero for a one-shot timer
+    interval: Duration,
+}
+
+impl Timer {
+    /// Consume `elapsed` of CPU time, return true if the timer expired
+    fn consume(&mut self, elapsed: Duration) -> bool {
+        match self.value {
+            Some(value) if value > elapsed => {
+                self.value = Some(value - elapsed);
+                false
+            }
+            Some(_) => {
+                self.value = if self.interval == Duration::default() {
+                    None
+                } else {
+                    Some(self.interval)
+                };
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// The interval timers are not inherited by fork(), but they are
+/// preserved across execve()
+#[derive(Debug, Default, Copy, Clone)]
+pub struct IntervalTimers {
+    real: Timer,
+    virtual_time: Timer,
+    prof: Timer,
+}
+
+impl IntervalTimers {
+    fn timer_mut(&mut self, which: ITimer) -> &mut Timer {
+        match which {
+            ITimer::Real => &mut self.real,
+            ITimer::Virtual => &mut self.virtual_time,
+            ITimer::Prof => &mut self.prof,
+        }
+    }
+
+    /// Get the value of the timer `which`, `now` is the monotonic time
+    pub fn get(&mut self, which: ITimer, now: Duration) -> itimerval {
+        let timer = self.timer_mut(which);
+        let value = match (which, timer.value) {
+            (_, None) => Duration::default(),
+            // An expired real timer may not have been checked yet
+            (ITimer::Real, Some(expiration)) if expiration > now => expiration - now,
+            (ITimer::Real, Some(_)) => Duration::from_micros(1),
+            (_, Some(remaining)) => remaining,
+        };
+        itimerval {
+            it_interval: duration_to_timeval(timer.interval),
+            it_value: duration_to_timeval(value),
+        }
+    }
+
+    /// Arm or disarm the timer `which`, returning its old value. A
+    /// zero it_value disarms the timer
+    pub fn set(
+        &mut self,
+        which: ITimer,
+        new_value: &itimerval,
+        now: Duration,
+    ) -> SysResult<itimerval> {
+        let value = timeval_to_duration(&new_value.it_value)?;
+        let interval = timeval_to_duration(&new_value.it_interval)?;
+        let old_value = self.get(which, now);
+
+        let timer = self.timer_mut(which);
+        timer.interval = interval;
+        timer.value = match (which, value == Duration::default()) {
+            (_, true) => None,
+            (ITimer::Real, false) => Some(now + value),
+            (_, false) => Some(value),
+        };
+        Ok(old_value)
+    }
+
+    /// Check if the real timer expired at the monotonic time `now`
+    /// and reload it
+    pub fn check_real_timer(&mut self, now: Duration) -> bool {
+        match self.real.value {
+            Some(expiration) if expiration <= now => {
+                self.real.value = if self.real.interval == Duration::default() {
+                    None
+                } else {
+                    // Do not accumulate the delay of the scheduler
+                    Some(now.max(expiration) + self.real.interval)
+                };
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Consume the CPU time used by the process, return the signals
+    /// of the expired timers
+    pub fn consume_cpu_time(&mut self, duration: ProcessDuration) -> [Option<Signum>; 2] {
+        let virtual_expired = self.virtual_time.consume(duration.user_time());
+        let prof_expired = self.prof.consume(duration.cpu_time());
+        [
+            if virtual_expired {
+                Some(ITimer::Virtual.signal())
+            } else {
+                None
+            },
+            if prof_expired {
+                Some(ITimer::Prof.signal())
+            } else {
+                None
+            },
+        ]
+    }
+}
@@ -0,0 +1,93 @@
+//! This file contains the tracing state of a process, cf ptrace()
+
+use super::{Pid, Status, Tid};
+
+use libc_binding::{siginfo_t, Signum};
+
+/// How the tracer resumed its tracee
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Resume {
+    /// PTRACE_CONT: the tracee runs until its next signal
+    Continue,
+    /// PTRACE_SYSCALL: the tracee also stops at the entry and at the exit of its syscalls
+    Syscall,
+    /// PTRACE_SINGLESTEP: the tracee gets a SIGTRAP after one instruction
+    SingleStep,
+}
+
+/// Why a tracee is stopped
+#[derive(Debug, Copy, Clone)]
+pub enum TraceEvent {
+    /// A signal is about to be delivered: the tracer may suppress it or replace it
+    Signal(siginfo_t),
+    /// Before the execution of a syscall, the tracer may change its number and its arguments
+    SyscallEnter,
+    /// After the execution of a syscall, the tracer may change its return value
+    SyscallExit,
+}
+
+/// A stop of a thread of the tracee
+#[derive(Debug, Copy, Clone)]
+pub struct TraceStop {
+    /// The stopped thread, the other ones are not elected until the tracer resumes it
+    pub tid: Tid,
+    pub event: TraceEvent,
+    /// The stop was reported to the tracer by waitpid()
+    pub reported: bool,
+    /// The thread stopped itself from a syscall or an exception handler, it waits in
+    /// auto_preempt(). Else it was stopped by the scheduler while it ran in ring 3
+    pub in_kernel: bool,
+}
+
+/// The tracing state of a process
+#[derive(Debug)]
+pub struct Tracee {
+    /// The tracer, which may not be the parent of the process
+    pub tracer: Pid,
+    /// PTRACE_O_TRACESYSGOOD: the syscall stops are reported with SIGTRAP | 0x80
+    pub sysgood: bool,
+    pub resume: Resume,
+    pub stop: Option<TraceStop>,
+    /// The number of the syscall in progress, set at its entry (cf PTRACE_PEEKUSER)
+    pub orig_eax: u32,
+}
+
+impl Tracee {
+    pub fn new(tracer: Pid) -> Self {
+        Self {
+            tracer,
+            sysgood: false,
+            resume: Resume::Continue,
+            stop: None,
+            orig_eax: 0,
+        }
+    }
+
+    /// The status of the current stop, as reported by waitpid()
+    pub fn stop_status(&self) -> Option<Status> {
+        let stop = self.stop?;
+        Some(Status::Stopped(match stop.event {
+            TraceEvent::Signal(info) => info.si_signo as u32,
+            TraceEvent::SyscallEnter | TraceEvent::SyscallExit => {
+                Signum::SIGTRAP as u32 | if self.sysgood { 0x80 } else { 0 }
+            }
+        }))
+    }
+
+    /// Check if the current stop was not reported yet to the tracer
+    pub fn has_unreported_stop(&self) -> bool {
+        self.stop.map_or(false, |stop| !stop.reported)
+    }
+
+    /// Mark the current stop as reported by waitpid(), returning its status
+    pub fn report_stop(&mut self) -> Option<Status> {
+        if !self.has_unreported_stop() {
+            return None;
+        }
+        let status = self.stop_status();
+        if let Some(stop) = &mut self.stop {
+            stop.reported = true;
+        }
+        status
+    }
+}
@@ -0,0 +1,105 @@
+//! This file contains the resource limits of a process, cf setrlimit()
+
+use super::SysResult;
+
+use core::convert::TryFrom;
+use libc_binding::{
+    rlim_t, rlimit, Errno, RLIMIT_AS, RLIMIT_CORE, RLIMIT_CPU, RLIMIT_DATA, RLIMIT_FSIZE,
+    RLIMIT_NOFILE, RLIMIT_NPROC, RLIMIT_STACK, RLIM_INFINITY, RLIM_NLIMITS,
+};
+
+/// The resources which can be limited, in the order of the RLIMIT_* constants
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Resource {
+    /// Maximum size of a core file, there is no core dump for now
+    Core,
+    /// CPU time limit, not enforced
+    Cpu,
+    /// Data segment size limit, not enforced
+    Data,
+    /// File size limit, not enforced
+    FileSize,
+    /// One more than the greatest file descriptor number
+    NoFile,
+    /// Maximum size of the main thread stack
+    Stack,
+    /// Maximum size of the address space
+    AddressSpace,
+    /// Maximum number of processes of the real user ID
+    NProc,
+}
+
+impl TryFrom<u32> for Resource {
+    type Error = Errno;
+
+    fn try_from(resource: u32) -> Result<Self, Self::Error> {
+        match resource {
+            RLIMIT_CORE => Ok(Resource::Core),
+            RLIMIT_CPU => Ok(Resource::Cpu),
+            RLIMIT_DATA => Ok(Resource::Data),
+            RLIMIT_FSIZE => Ok(Resource::FileSize),
+            RLIMIT_NOFILE => Ok(Resource::NoFile),
+            RLIMIT_STACK => Ok(Resource::Stack),
+            RLIMIT_AS => Ok(Resource::AddressSpace),
+            RLIMIT_NPROC => Ok(Resource::NProc),
+            _ => Err(Errno::EINVAL),
+        }
+    }
+}
+
+/// The soft and hard limits of all the resources of a process
+#[derive(Debug, Copy, Clone)]
+pub struct ResourceLimits([rlimit; RLIM_NLIMITS as usize]);
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        let unlimited = rlimit {
+            rlim_cur: RLIM_INFINITY,
+            rlim_max: RLIM_INFINITY,
+        };
+        let mut limits = [unlimited; RLIM_NLIMITS as usize];
+        limits[RLIMIT_CORE as usize].rlim_cur = 0;
+        limits[RLIMIT_NOFILE as usize] = rlimit {
+            rlim_cur: Self::DEFAULT_NOFILE,
+            rlim_max: Self::MAX_NOFILE,
+        };
+        limits[RLIMIT_STACK as usize].rlim_cur = Self::DEFAULT_STACK;
+        Self(limits)
+    }
+}
+
+impl ResourceLimits {
+    /// Default number of file descriptors
+    pub const DEFAULT_NOFILE: rlim_t = 128;
+    /// The limit on the number of file descriptors cannot be raised above this value
+    pub const MAX_NOFILE: rlim_t = 1024;
+    /// Default size of the stack: 8MB
+    pub const DEFAULT_STACK: rlim_t = 8 << 20;
+
+    pub fn get(&self, resource: Resource) -> rlimit {
+        self.0[resource as usize]
+    }
+
+    /// Set the limits of a resource. Only a privileged process can raise a hard limit
+    pub fn set(&mut self, resource: Resource, new: rlimit, privileged: bool) -> SysResult<()> {
+        if new.rlim_cur > new.rlim_max {
+            return Err(Errno::EINVAL);
+        }
+        if new.rlim_max > self.get(resource).rlim_max && !privileged {
+            return Err(Errno::EPERM);
+        }
+        if resource == Resource::NoFile && new.rlim_max > Self::MAX_NOFILE {
+            return Err(Errno::EPERM);
+        }
+        self.0[resource as usize] = new;
+        Ok(())
+    }
+
+    /// Get the soft limit of a resource, None if it is unlimited
+    pub fn soft_limit(&self, resource: Resource) -> Option<usize> {
+        match self.get(resource).rlim_cur {
+            RLIM_INFINITY => None,
+            limit => Some(limit as usize),
+        }
+    }
+}
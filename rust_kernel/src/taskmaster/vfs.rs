@@ -9,7 +9,7 @@ use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::convert::TryInto;
 use core::sync::atomic::Ordering;
-use fallible_collections::{btree::BTreeMap, FallibleArc, FallibleBox, TryCollect};
+use fallible_collections::{btree::BTreeMap, FallibleArc, FallibleBox, FallibleVec, TryCollect};
 use lazy_static::lazy_static;
 use sync::DeadMutex;
 
@@ -42,13 +42,14 @@ use libc_binding::dirent;
 use libc_binding::statfs;
 use libc_binding::Errno::*;
 use libc_binding::FileType;
-use libc_binding::{gid_t, stat, time_t, uid_t, utimbuf, Amode, Errno};
+use libc_binding::{gid_t, stat, time_t, uid_t, utimbuf, Amode, Errno, MountFlags};
 
 pub mod init;
 pub use init::{init, VFS};
 
 mod filesystem;
-use filesystem::{DeadFileSystem, FileSystem, FileSystemId, FileSystemSource, FileSystemType};
+use filesystem::{DeadFileSystem, FileSystem, FileSystemDriver, FileSystemSource};
+pub use filesystem::{FileSystemId, FileSystemType};
 
 pub struct VirtualFileSystem {
     mounted_filesystems: BTreeMap<FileSystemId, MountedFileSystem>,
@@ -63,6 +64,10 @@ pub struct MountedFileSystem {
     fs_type: FileSystemType,
     target: Path,
     fs: Arc<DeadMutex<dyn FileSystem>>,
+    /// the flags of the mount, among MS_RDONLY, MS_NOSUID, MS_NODEV and MS_NOEXEC
+    flags: MountFlags,
+    /// the directory on which the filesystem is mounted
+    mount_dir: DirectoryEntryId,
 }
 
 use core::fmt::{self, Debug};
@@ -237,13 +242,7 @@ impl VirtualFileSystem {
         creds: &Credentials,
         pathname: &Path,
     ) -> SysResult<DirectoryEntryId> {
-        let root = if pathname.is_absolute() {
-            self.dcache.root_id
-        } else {
-            debug_assert!(cwd.is_absolute());
-            self._pathname_resolution(self.dcache.root_id, creds, cwd, 0, true)?
-        };
-        self._pathname_resolution(root, creds, pathname, 0, false)
+        Ok(self.resolve_with_mount(cwd, creds, pathname, false)?.0)
     }
 
     /// resolve the path `pathname` from root `root`, return the
@@ -254,25 +253,136 @@ impl VirtualFileSystem {
         creds: &Credentials,
         pathname: &Path,
     ) -> SysResult<DirectoryEntryId> {
-        let root = if pathname.is_absolute() {
-            self.dcache.root_id
+        Ok(self.resolve_with_mount(cwd, creds, pathname, true)?.0)
+    }
+
+    /// resolve the path `pathname`, return the directory_entry_id
+    /// associate with the file and the mount through which it was
+    /// reached
+    fn resolve_with_mount(
+        &mut self,
+        cwd: &Path,
+        creds: &Credentials,
+        pathname: &Path,
+        follow_last_symlink: bool,
+    ) -> SysResult<(DirectoryEntryId, Option<FileSystemId>)> {
+        let root_mount = self.mount_on(self.dcache.root_id);
+        let (root, mount) = if pathname.is_absolute() {
+            (self.dcache.root_id, root_mount)
         } else {
             debug_assert!(cwd.is_absolute());
-            self._pathname_resolution(self.dcache.root_id, creds, cwd, 0, true)?
+            self._pathname_resolution(self.dcache.root_id, root_mount, creds, cwd, 0, true)?
         };
-        self._pathname_resolution(root, creds, pathname, 0, true)
+        self._pathname_resolution(root, mount, creds, pathname, 0, follow_last_symlink)
+    }
+
+    /// resolve the path `pathname`, return the directory_entry_id
+    /// associate with the file and the flags of its mount
+    fn resolve_with_mount_flags(
+        &mut self,
+        cwd: &Path,
+        creds: &Credentials,
+        pathname: &Path,
+        follow_last_symlink: bool,
+    ) -> SysResult<(DirectoryEntryId, MountFlags)> {
+        let (entry_id, mount) =
+            self.resolve_with_mount(cwd, creds, pathname, follow_last_symlink)?;
+        Ok((entry_id, self.mount_flags(mount)))
+    }
+
+    /// Returns the flags of the mount on which the file pointed by `path` is
+    pub fn path_mount_flags(
+        &mut self,
+        cwd: &Path,
+        creds: &Credentials,
+        path: &Path,
+    ) -> SysResult<MountFlags> {
+        Ok(self.resolve_with_mount_flags(cwd, creds, path, true)?.1)
+    }
+
+    /// Returns the flags of the mount `mount`
+    fn mount_flags(&self, mount: Option<FileSystemId>) -> MountFlags {
+        mount
+            .and_then(|fs_id| self.mounted_filesystems.get(&fs_id))
+            .map_or(MountFlags::empty(), |mounted| mounted.flags)
+    }
+
+    /// Returns EROFS when the mount flags `flags` are read-only
+    fn check_writable(flags: MountFlags) -> SysResult<()> {
+        if flags.contains(MountFlags::MS_RDONLY) {
+            Err(EROFS)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the directory entry of the root of the mount `fs_id`
+    fn mount_root(&self, fs_id: FileSystemId) -> Option<DirectoryEntryId> {
+        let mount_dir = self.mounted_filesystems.get(&fs_id)?.mount_dir;
+        self.dcache
+            .get_entry(&mount_dir)
+            .ok()?
+            .get_mountpoint_entry()
+    }
+
+    /// Returns the mount whose mount dir is `mount_dir_id`
+    fn mount_on(&self, mount_dir_id: DirectoryEntryId) -> Option<FileSystemId> {
+        self.mounted_filesystems
+            .iter()
+            .find(|(_, mounted)| mounted.mount_dir == mount_dir_id)
+            .map(|(fs_id, _)| *fs_id)
+    }
+
+    /// Returns whether the directory entry `id` is the root of a mount
+    fn is_mount_root(&self, id: DirectoryEntryId) -> bool {
+        self.mounted_filesystems
+            .keys()
+            .any(|fs_id| self.mount_root(*fs_id) == Some(id))
+    }
+
+    /// Returns the mount of the directory entry `id`, found by
+    /// following up its parents. A bind mount is never returned, as a
+    /// directory entry is shared by all the mounts which show it
+    fn dentry_mount(&self, mut id: DirectoryEntryId) -> Option<FileSystemId> {
+        loop {
+            let parent_id = self.dcache.get_entry(&id).ok()?.parent_id;
+            if parent_id == id {
+                return None;
+            }
+            let parent = self.dcache.get_entry(&parent_id).ok()?;
+            if parent.get_mountpoint_entry() == Some(id) {
+                return self.mount_on(parent_id);
+            }
+            id = parent_id;
+        }
+    }
+
+    /// Returns whether the directory entry `id` is `ancestor` or is below it
+    fn is_below(&self, mut id: DirectoryEntryId, ancestor: DirectoryEntryId) -> bool {
+        loop {
+            if id == ancestor {
+                return true;
+            }
+            match self.dcache.get_entry(&id) {
+                Ok(entry) if entry.parent_id != id => id = entry.parent_id,
+                _ => return false,
+            }
+        }
     }
 
     /// this method follow the mount point
     /// if current_entry is mounted, it set current_entry and
     /// current_dir_id to the direntry and direntry_id of the mount
-    /// point
+    /// point, and mount to the crossed mount
     fn handle_mount_point<'a>(
         &'a self,
         current_entry: &mut &'a DirectoryEntry,
         current_dir_id: &mut DirectoryEntryId,
+        mount: &mut Option<FileSystemId>,
     ) {
-        if let Ok(true) = current_entry.is_mounted() {
+        // filesystems can be mounted on the root of another mount
+        while let Ok(true) = current_entry.is_mounted() {
+            *mount = self.mount_on(*current_dir_id);
             *current_dir_id = current_entry
                 .get_mountpoint_entry()
                 .expect("mount point entry should be there");
@@ -287,17 +397,19 @@ impl VirtualFileSystem {
     fn _pathname_resolution(
         &mut self,
         mut root: DirectoryEntryId,
+        mut mount: Option<FileSystemId>,
         creds: &Credentials,
         pathname: &Path,
         recursion_level: usize,
         follow_last_symlink: bool,
-    ) -> SysResult<DirectoryEntryId> {
+    ) -> SysResult<(DirectoryEntryId, Option<FileSystemId>)> {
         if recursion_level > SYMLOOP_MAX {
             return Err(Errno::ELOOP);
         }
 
         if pathname.is_absolute() {
             root = self.dcache.root_id;
+            mount = self.mount_on(root);
         }
 
         if !self.dcache.contains_entry(&root) {
@@ -309,19 +421,32 @@ impl VirtualFileSystem {
         let mut was_symlink = false;
         let mut current_entry = self.dcache.get_entry(&current_dir_id)?;
 
-        self.handle_mount_point(&mut current_entry, &mut current_dir_id);
+        self.handle_mount_point(&mut current_entry, &mut current_dir_id, &mut mount);
         // quick fix, this handle / mount point
         for component in components.by_ref() {
-            self.handle_mount_point(&mut current_entry, &mut current_dir_id);
+            self.handle_mount_point(&mut current_entry, &mut current_dir_id, &mut mount);
 
             let current_dir = current_entry.get_directory()?;
 
             if component == &"." {
                 continue;
             } else if component == &".." {
+                // the parent of the root of a mount is the parent of its mount dir
+                while let Some(fs_id) = mount {
+                    if self.mount_root(fs_id) != Some(current_dir_id) {
+                        break;
+                    }
+                    current_dir_id = self
+                        .mounted_filesystems
+                        .get(&fs_id)
+                        .expect("mount should be there")
+                        .mount_dir;
+                    current_entry = self.dcache.get_entry(&current_dir_id)?;
+                    mount = self.dentry_mount(current_dir_id);
+                }
                 current_dir_id = current_entry.parent_id;
                 current_entry = self.dcache.get_entry(&current_dir_id)?;
-                self.handle_mount_point(&mut current_entry, &mut current_dir_id);
+                self.handle_mount_point(&mut current_entry, &mut current_dir_id, &mut mount);
                 continue;
             }
 
@@ -392,11 +517,11 @@ impl VirtualFileSystem {
             // symlink, as on a symlink current_dir_id must point
             // to the directory, not the symlink
             current_dir_id = *next_entry_id;
-            self.handle_mount_point(&mut current_entry, &mut current_dir_id);
+            self.handle_mount_point(&mut current_entry, &mut current_dir_id, &mut mount);
         }
         if was_symlink {
             if components.len() == 0 && !follow_last_symlink {
-                return Ok(current_entry.id);
+                return Ok((current_entry.id, mount));
             }
             let mut new_path = current_entry
                 .get_symbolic_content()
@@ -406,13 +531,14 @@ impl VirtualFileSystem {
 
             self._pathname_resolution(
                 current_dir_id,
+                mount,
                 creds,
                 &new_path,
                 recursion_level + 1,
                 follow_last_symlink,
             )
         } else {
-            Ok(self.dcache.get_entry(&current_dir_id).unwrap().id)
+            Ok((self.dcache.get_entry(&current_dir_id).unwrap().id, mount))
         }
     }
 
@@ -591,13 +717,13 @@ impl VirtualFileSystem {
     //     Ok(())
     // }
     /// Mount the filesystem `filesystem` with filesystem id `fs_id`
-    /// on mount dir `mount_dir_id`
+    /// on its mount dir
     pub fn mount_filesystem(
         &mut self,
         filesystem: MountedFileSystem,
         fs_id: FileSystemId,
-        mount_dir_id: DirectoryEntryId,
     ) -> SysResult<()> {
+        let mount_dir_id = filesystem.mount_dir;
         let mount_dir = self.dcache.get_entry_mut(&mount_dir_id)?;
         if !mount_dir.is_directory() {
             return Err(ENOTDIR);
@@ -631,50 +757,288 @@ impl VirtualFileSystem {
         Ok(())
     }
 
-    /// mount the source `source` on the target `target`
+    /// mount the source `source`, a filesystem of type `fs_type`, on
+    /// the target `target` with the mount flags `flags` and the
+    /// filesystem specific `options`
     pub fn mount(
         &mut self,
         cwd: &Path,
         creds: &Credentials,
         source: Path,
         target: Path,
+        fs_type: FileSystemType,
+        flags: MountFlags,
+        options: Option<&str>,
     ) -> SysResult<()> {
-        use ext2::Ext2Filesystem;
         use filesystem::devfs::DiskWrapper;
-        use filesystem::Ext2fs;
 
-        let flags = libc_binding::OpenFlags::O_RDWR;
-        let mode = FileType::from_bits(0o777).expect("file permission creation failed");
-        let source_path = self.resolve_path(cwd, creds, &source)?;
-        let file_operation = self
-            .open(cwd, creds, source, flags, mode)
-            .expect("open sda1 failed")
-            .expect("disk driver open failed");
+        let driver = FileSystemDriver::get(fs_type);
+        // The procfs and the devfs are mounted by the kernel at boot
+        let constructor = driver.new.ok_or(ENODEV)?;
 
-        let ext2_disk = DiskWrapper(file_operation);
-        VFS.force_unlock();
+        let mount_dir = self.pathname_resolution(cwd, creds, &target)?;
+        let target = self.resolve_path(cwd, creds, &target)?;
 
-        let ext2 = Ext2Filesystem::new(Box::try_new(ext2_disk)?).map_err(|_| Errno::EINVAL)?;
-        let fs_id: FileSystemId = self.gen();
+        let (source, disk) = if driver.nodev {
+            // The source of a nodev filesystem is only a name
+            (FileSystemSource::Nodev(fs_type), None)
+        } else {
+            let source_id = self.pathname_resolution(cwd, creds, &source)?;
+            if !self
+                .get_inode_from_direntry_id(source_id)?
+                .access_mode
+                .is_character_device()
+            {
+                return Err(ENOTBLK);
+            }
+            let source_path = self.dentry_path(source_id)?;
+            let mode = FileType::from_bits(0o777).expect("file permission creation failed");
+            let file_operation = match self.open(cwd, creds, source, OpenFlags::O_RDWR, mode)? {
+                IpcResult::Done(file_operation) => file_operation,
+                IpcResult::Wait(..) => return Err(EIO),
+            };
+            (
+                FileSystemSource::File { source_path },
+                Some(DiskWrapper(file_operation)),
+            )
+        };
 
-        // we handle only ext2 fs right now
-        // HARDFIX: `mount kernel.elf .` should be solved in a better way.
-        let filesystem = Ext2fs::new(ext2, fs_id);
-        let mount_dir_id = self.pathname_resolution(cwd, creds, &target)?;
-        let target = self.resolve_path(cwd, creds, &target)?;
+        let fs_id: FileSystemId = self.gen();
+        VFS.force_unlock();
+        let fs = constructor(fs_id, disk, options)?;
         self.mount_filesystem(
             MountedFileSystem {
-                source: FileSystemSource::File { source_path },
-                // we only handle ext2
-                fs_type: FileSystemType::Ext2,
+                source,
+                fs_type,
                 target,
-                fs: Arc::try_new(DeadMutex::new(filesystem))?,
+                fs,
+                flags: flags & MountFlags::PER_MOUNT,
+                mount_dir,
             },
             fs_id,
-            mount_dir_id,
         )
     }
 
+    /// Change the mount flags of the mount whose root is `target`
+    pub fn remount(
+        &mut self,
+        cwd: &Path,
+        creds: &Credentials,
+        target: &Path,
+        flags: MountFlags,
+    ) -> SysResult<()> {
+        let (root_id, mount) = self.resolve_with_mount(cwd, creds, target, true)?;
+        let fs_id = mount
+            .filter(|fs_id| self.mount_root(*fs_id) == Some(root_id))
+            .ok_or(EINVAL)?;
+
+        self.mounted_filesystems
+            .get_mut(&fs_id)
+            .expect("mount should be there")
+            .flags = flags & MountFlags::PER_MOUNT;
+        Ok(())
+    }
+
+    /// Make the directory `source` visible on the directory `target`,
+    /// with its own mount flags `flags`
+    pub fn bind_mount(
+        &mut self,
+        cwd: &Path,
+        creds: &Credentials,
+        source: &Path,
+        target: &Path,
+        flags: MountFlags,
+    ) -> SysResult<()> {
+        let (source_id, source_mount) = self.resolve_with_mount(cwd, creds, source, true)?;
+        if !self.dcache.get_entry(&source_id)?.is_directory() {
+            return Err(ENOTDIR);
+        }
+        let (source, fs_type, fs) = {
+            let source_mount = source_mount
+                .and_then(|fs_id| self.mounted_filesystems.get(&fs_id))
+                .ok_or(EINVAL)?;
+            (
+                source_mount.source.try_clone()?,
+                source_mount.fs_type,
+                source_mount.fs.clone(),
+            )
+        };
+        // The directory entries of a dynamic filesystem are rebuilt
+        // at each lookup, they cannot be shared by a mount
+        if fs.lock().is_dynamic() {
+            return Err(EINVAL);
+        }
+
+        let mount_dir_id = self.pathname_resolution(cwd, creds, target)?;
+        if mount_dir_id == source_id {
+            return Err(EINVAL);
+        }
+        let target = self.dentry_path(mount_dir_id)?;
+        let mount_dir = self.dcache.get_entry(&mount_dir_id)?;
+        if !mount_dir.is_directory() {
+            return Err(ENOTDIR);
+        }
+        if mount_dir.is_mounted()? {
+            return Err(EBUSY);
+        }
+
+        let fs_id: FileSystemId = self.gen();
+        self.mounted_filesystems.try_insert(
+            fs_id,
+            MountedFileSystem {
+                source,
+                fs_type,
+                target,
+                fs,
+                flags: flags & MountFlags::PER_MOUNT,
+                mount_dir: mount_dir_id,
+            },
+        )?;
+        // The mount dir shows the source directory, which stays a
+        // child of its own parent
+        self.dcache
+            .get_entry_mut(&mount_dir_id)
+            .expect("mount dir should be there")
+            .set_mounted(source_id)
+            .expect("mount dir should be a directory");
+        Ok(())
+    }
+
+    /// Returns the mount whose root is the file pointed by `path`
+    fn resolve_mount(
+        &mut self,
+        cwd: &Path,
+        creds: &Credentials,
+        path: &Path,
+    ) -> SysResult<(FileSystemId, DirectoryEntryId)> {
+        let (root_id, mount) = self.resolve_with_mount(cwd, creds, path, true)?;
+        let fs_id = mount
+            .filter(|fs_id| self.mount_root(*fs_id) == Some(root_id))
+            .ok_or(EINVAL)?;
+        Ok((fs_id, root_id))
+    }
+
+    /// Returns the directory entry of `path` if a mount can be made on it
+    fn resolve_free_mount_dir(
+        &mut self,
+        cwd: &Path,
+        creds: &Credentials,
+        path: &Path,
+    ) -> SysResult<DirectoryEntryId> {
+        let mount_dir_id = self.pathname_resolution(cwd, creds, path)?;
+        let mount_dir = self.dcache.get_entry(&mount_dir_id)?;
+        if !mount_dir.is_directory() {
+            return Err(ENOTDIR);
+        }
+        if mount_dir.is_mounted()? {
+            return Err(EBUSY);
+        }
+        Ok(mount_dir_id)
+    }
+
+    /// Move the mount `fs_id`, whose root is `root_id`, on the free
+    /// directory `mount_dir_id`
+    fn attach_mount(
+        &mut self,
+        fs_id: FileSystemId,
+        root_id: DirectoryEntryId,
+        mount_dir_id: DirectoryEntryId,
+    ) -> SysResult<()> {
+        let old_mount_dir_id = self
+            .mounted_filesystems
+            .get(&fs_id)
+            .ok_or(EINVAL)?
+            .mount_dir;
+        let old_mount_dir = self.dcache.get_entry_mut(&old_mount_dir_id)?;
+        if old_mount_dir.get_mountpoint_entry() == Some(root_id) {
+            old_mount_dir.unset_mounted()?;
+        }
+        // The root of a bind mount stays a child of its own parent
+        if self.dcache.get_entry(&root_id)?.parent_id == old_mount_dir_id {
+            self.dcache.reparent(root_id, mount_dir_id)?;
+        }
+        self.dcache
+            .get_entry_mut(&mount_dir_id)?
+            .set_mounted(root_id)?;
+        self.mounted_filesystems
+            .get_mut(&fs_id)
+            .expect("mount should be there")
+            .mount_dir = mount_dir_id;
+        Ok(())
+    }
+
+    /// Compute again the targets of the mounts, after some of them moved
+    fn update_mount_targets(&mut self) -> SysResult<()> {
+        let mut targets = Vec::new();
+        for (fs_id, mounted) in self.mounted_filesystems.iter() {
+            targets.try_push((*fs_id, self.dentry_path(mounted.mount_dir)?))?;
+        }
+        for (fs_id, target) in targets {
+            if let Some(mounted) = self.mounted_filesystems.get_mut(&fs_id) {
+                mounted.target = target;
+            }
+        }
+        Ok(())
+    }
+
+    /// Move the mount whose root is `source` on the directory `target`
+    pub fn move_mount(
+        &mut self,
+        cwd: &Path,
+        creds: &Credentials,
+        source: &Path,
+        target: &Path,
+    ) -> SysResult<()> {
+        let (fs_id, root_id) = self.resolve_mount(cwd, creds, source)?;
+        let mount_dir_id = self.resolve_free_mount_dir(cwd, creds, target)?;
+        // The root mount cannot be moved, and a mount cannot be
+        // moved under itself
+        if self.mounted_filesystems[&fs_id].mount_dir == self.dcache.root_id
+            || self.is_below(mount_dir_id, root_id)
+        {
+            return Err(EINVAL);
+        }
+        self.attach_mount(fs_id, root_id, mount_dir_id)?;
+        self.update_mount_targets()
+    }
+
+    /// Make the mount whose root is `new_root` the root mount, the
+    /// current root mount is moved on `put_old`, which must be under
+    /// `new_root`
+    pub fn pivot_root(
+        &mut self,
+        cwd: &Path,
+        creds: &Credentials,
+        new_root: &Path,
+        put_old: &Path,
+    ) -> SysResult<()> {
+        let root_dir_id = self.dcache.root_id;
+        let old_fs_id = self.mount_on(root_dir_id).ok_or(EINVAL)?;
+        let old_root_id = self.mount_root(old_fs_id).ok_or(EINVAL)?;
+
+        let (new_fs_id, new_root_id) = self.resolve_mount(cwd, creds, new_root)?;
+        if new_fs_id == old_fs_id {
+            return Err(EBUSY);
+        }
+        // The root of a bind mount is not a child of its mount dir,
+        // the root mount must be one
+        let new_mount_dir_id = self.mounted_filesystems[&new_fs_id].mount_dir;
+        if self.dcache.get_entry(&new_root_id)?.parent_id != new_mount_dir_id {
+            return Err(EINVAL);
+        }
+        let put_old_id = self.resolve_free_mount_dir(cwd, creds, put_old)?;
+        if !self.is_below(put_old_id, new_root_id) {
+            return Err(EINVAL);
+        }
+
+        // The new root leaves the tree of the old root before the old
+        // root goes under the new one
+        self.dcache.get_entry_mut(&root_dir_id)?.unset_mounted()?;
+        self.attach_mount(new_fs_id, new_root_id, root_dir_id)?;
+        self.attach_mount(old_fs_id, old_root_id, put_old_id)?;
+        self.update_mount_targets()
+    }
+
     fn recursive_trash(&mut self, root_dentry_id: DirectoryEntryId) {
         let direntry = self.dcache.d_entries.remove(&root_dentry_id);
         if let Some(direntry) = direntry {
@@ -688,27 +1052,42 @@ impl VirtualFileSystem {
     }
 
     pub fn umount(&mut self, cwd: &Path, creds: &Credentials, path: Path) -> SysResult<()> {
-        let mut mount_dir_id = self.pathname_resolution(cwd, creds, &path)?;
-        let mount_dir = self.dcache.get_entry_mut(&mount_dir_id)?;
-        // get the parent to have the mount point as pathname
-        // resolution follow mount points
-        mount_dir_id = mount_dir.parent_id;
-        let mount_dir = self.dcache.get_entry_mut(&mount_dir_id)?;
+        // pathname resolution follow mount points, so path is the
+        // root of the mount
+        let (root_dentry_id, mount) = self.resolve_with_mount(cwd, creds, &path, true)?;
+        let fs_id = mount
+            .filter(|fs_id| self.mount_root(*fs_id) == Some(root_dentry_id))
+            .ok_or(EINVAL)?;
 
-        if !mount_dir.is_mounted()? {
-            return Err(EINVAL);
+        let mount_dir_id = self
+            .mounted_filesystems
+            .get(&fs_id)
+            .expect("mount should be there")
+            .mount_dir;
+        let is_bind_mount = self.dcache.get_entry(&root_dentry_id)?.parent_id != mount_dir_id;
+
+        // A filesystem is busy when another mount is made on it, or
+        // shows a part of it. The directory entries of a bind mount
+        // stay owned by the source mount
+        if !is_bind_mount
+            && self.mounted_filesystems.iter().any(|(id, mounted)| {
+                *id != fs_id
+                    && (self.is_below(mounted.mount_dir, root_dentry_id)
+                        || self
+                            .mount_root(*id)
+                            .map_or(false, |root| self.is_below(root, root_dentry_id)))
+            })
+        {
+            return Err(EBUSY);
         }
-
-        let root_dentry_id = mount_dir.get_mountpoint_entry().ok_or(EINVAL)?;
+        let mount_dir = self.dcache.get_entry_mut(&mount_dir_id)?;
 
         // this set the mount_dir as unmouted
         mount_dir.unset_mounted()?;
-        mount_dir.remove_entry(root_dentry_id)?;
-
-        let mounted_dir = self.dcache.get_entry_mut(&root_dentry_id)?;
-        let fs_id = mounted_dir.inode_id.filesystem_id.ok_or(EINVAL)?;
-
-        self.recursive_trash(root_dentry_id);
+        if !is_bind_mount {
+            mount_dir.remove_entry(root_dentry_id)?;
+            self.recursive_trash(root_dentry_id);
+        }
 
         self.mounted_filesystems.remove(&fs_id);
 
@@ -792,7 +1171,8 @@ impl VirtualFileSystem {
     }
 
     pub fn unlink(&mut self, cwd: &Path, creds: &Credentials, path: Path) -> SysResult<()> {
-        let entry_id = self.pathname_resolution_no_follow_last_symlink(cwd, creds, &path)?;
+        let (entry_id, mount_flags) = self.resolve_with_mount_flags(cwd, creds, &path, false)?;
+        Self::check_writable(mount_flags)?;
         let parent_id;
 
         {
@@ -937,11 +1317,18 @@ impl VirtualFileSystem {
         path: &Path,
         amode: Amode,
     ) -> bool {
-        let direntry_id = match self.pathname_resolution(cwd, creds, path) {
+        let (direntry_id, mount_flags) = match self.resolve_with_mount_flags(cwd, creds, path, true)
+        {
             Err(_) => return false,
-            Ok(id) => id,
+            Ok(resolved) => resolved,
         };
 
+        if (amode.contains(Amode::WRITE) && mount_flags.contains(MountFlags::MS_RDONLY))
+            || (amode.contains(Amode::EXECUTE) && mount_flags.contains(MountFlags::MS_NOEXEC))
+        {
+            return false;
+        }
+
         let inode = self.get_inode_from_direntry_id(direntry_id).unwrap();
 
         // Ensure that non-regular files are not executables regardless of the file permissions.
@@ -969,23 +1356,36 @@ impl VirtualFileSystem {
         mode: FileType,
     ) -> SysResult<IpcResult<Arc<DeadMutex<dyn FileOperation>>>> {
         let entry_id;
-        match self.pathname_resolution(cwd, creds, &path) {
-            Ok(_id) if flags.contains(OpenFlags::O_CREAT | OpenFlags::O_EXCL) => {
+        let mount;
+        match self.resolve_with_mount(cwd, creds, &path, true) {
+            Ok(_) if flags.contains(OpenFlags::O_CREAT | OpenFlags::O_EXCL) => {
                 return Err(Errno::EEXIST)
             }
-            Ok(id) => {
+            Ok((id, id_mount)) => {
+                let mount_flags = self.mount_flags(id_mount);
                 let amode = Amode::from(flags);
+                if amode.contains(Amode::WRITE) {
+                    Self::check_writable(mount_flags)?;
+                }
                 let inode = self.get_inode_from_direntry_id(id)?;
 
+                if mount_flags.contains(MountFlags::MS_NODEV)
+                    && inode.access_mode.is_character_device()
+                {
+                    return Err(Errno::EACCES);
+                }
                 if !creds.is_access_granted(inode.access_mode, amode, (inode.uid, inode.gid)) {
                     return Err(Errno::EACCES);
                 }
 
                 entry_id = id;
+                mount = id_mount;
             }
             Err(e) if !flags.contains(OpenFlags::O_CREAT) => return Err(e.into()),
             _ => {
-                let parent_id = self.pathname_resolution(cwd, creds, &path.parent()?)?;
+                let (parent_id, parent_mount) =
+                    self.resolve_with_mount(cwd, creds, &path.parent()?, true)?;
+                Self::check_writable(self.mount_flags(parent_mount))?;
                 let parent_entry = self.dcache.get_entry(&parent_id)?;
                 let parent_inode = self.get_inode_from_direntry_id(parent_id)?;
 
@@ -1012,6 +1412,7 @@ impl VirtualFileSystem {
                 )?;
                 let fs_entry = (direntry, inode_data, Some(driver));
                 entry_id = self.add_entry_from_filesystem(fs_cloned, Some(parent_id), fs_entry)?;
+                mount = parent_mount;
             }
         }
 
@@ -1021,10 +1422,15 @@ impl VirtualFileSystem {
             return Err(Errno::ENOTDIR);
         }
 
-        self.inodes
+        let mut res = self
+            .inodes
             .get_mut(&entry_inode_id)
             .ok_or(ENOENT)?
-            .open(flags)
+            .open(flags)?;
+        if let IpcResult::Done(file_op) = &mut res {
+            file_op.lock().set_mount(mount);
+        }
+        Ok(res)
     }
 
     // pub fn creat(
@@ -1071,17 +1477,19 @@ impl VirtualFileSystem {
         let mask = FileType::SPECIAL_BITS | FileType::PERMISSIONS_MASK;
         mode &= mask;
 
-        let entry_id = self.pathname_resolution(cwd, creds, &path)?;
+        let (entry_id, mount) = self.resolve_with_mount(cwd, creds, &path, true)?;
         let entry = self.dcache.get_entry(&entry_id)?;
 
         let inode_id = entry.inode_id;
-        self.fchmod(creds, inode_id, mode)
+        self.fchmod(creds, inode_id, mount, mode)
     }
 
+    /// Change the mode of the inode `inode_id`, reached through the mount `mount`
     pub fn fchmod(
         &mut self,
         creds: &Credentials,
         inode_id: InodeId,
+        mount: Option<FileSystemId>,
         mut mode: FileType,
     ) -> SysResult<()> {
         let mask = FileType::SPECIAL_BITS | FileType::PERMISSIONS_MASK;
@@ -1095,6 +1503,7 @@ impl VirtualFileSystem {
         if !creds.is_root() && creds.euid != inode.uid {
             return Err(Errno::EPERM);
         }
+        Self::check_writable(self.mount_flags(mount))?;
 
         self.get_filesystem(inode_id)
             .expect("No corresponding filesystem")
@@ -1122,17 +1531,19 @@ impl VirtualFileSystem {
         owner: uid_t,
         group: gid_t,
     ) -> SysResult<()> {
-        let entry_id = self.pathname_resolution(cwd, creds, &path)?;
+        let (entry_id, mount) = self.resolve_with_mount(cwd, creds, &path, true)?;
         let entry = self.dcache.get_entry(&entry_id)?;
 
         let inode_id = entry.inode_id;
-        self.fchown(creds, inode_id, owner, group)
+        self.fchown(creds, inode_id, mount, owner, group)
     }
 
+    /// Change the owner of the inode `inode_id`, reached through the mount `mount`
     pub fn fchown(
         &mut self,
         creds: &Credentials,
         inode_id: InodeId,
+        mount: Option<FileSystemId>,
         owner: uid_t,
         group: gid_t,
     ) -> SysResult<()> {
@@ -1144,6 +1555,7 @@ impl VirtualFileSystem {
         if !creds.is_root() && creds.euid != inode.uid {
             return Err(Errno::EPERM);
         }
+        Self::check_writable(self.mount_flags(mount))?;
 
         let fs = self.get_filesystem(inode_id).expect("no filesystem");
         fs.lock()
@@ -1172,7 +1584,8 @@ impl VirtualFileSystem {
         times: Option<&utimbuf>,
     ) -> SysResult<()> {
         // Handle permissions here too.
-        let entry_id = self.pathname_resolution(cwd, creds, &path)?;
+        let (entry_id, mount_flags) = self.resolve_with_mount_flags(cwd, creds, &path, true)?;
+        Self::check_writable(mount_flags)?;
         let inode_id = self.dcache.get_entry(&entry_id)?.inode_id;
         let fs = self.get_filesystem(inode_id).expect("No filesystem");
 
@@ -1203,11 +1616,12 @@ impl VirtualFileSystem {
             return Err(EEXIST);
         }
         let filename = path.pop().ok_or(EINVAL)?;
-        let entry_id = self.pathname_resolution(cwd, creds, &path)?;
+        let (entry_id, mount_flags) = self.resolve_with_mount_flags(cwd, creds, &path, true)?;
         let entry = self.dcache.get_entry(&entry_id)?;
         if !entry.is_directory() {
             return Err(ENOTDIR);
         }
+        Self::check_writable(mount_flags)?;
 
         let parent_inode = self.get_inode_from_direntry_id(entry_id)?;
         if !creds.is_access_granted(
@@ -1269,11 +1683,12 @@ impl VirtualFileSystem {
             return Err(EEXIST);
         }
         let filename = path.pop().ok_or(EINVAL)?;
-        let entry_id = self.pathname_resolution(cwd, creds, &path)?;
+        let (entry_id, mount_flags) = self.resolve_with_mount_flags(cwd, creds, &path, true)?;
         let entry = self.dcache.get_entry(&entry_id)?;
         if !entry.is_directory() {
             return Err(ENOTDIR);
         }
+        Self::check_writable(mount_flags)?;
         let inode_id = entry.inode_id;
 
         let inode = self
@@ -1324,12 +1739,16 @@ impl VirtualFileSystem {
             return Err(EINVAL);
         }
 
-        let entry_id = self.pathname_resolution(cwd, creds, &path)?;
+        let (entry_id, mount_flags) = self.resolve_with_mount_flags(cwd, creds, &path, true)?;
+        if self.is_mount_root(entry_id) {
+            return Err(EBUSY);
+        }
         let entry = self.dcache.get_entry(&entry_id)?;
 
         if !entry.is_directory() {
             return Err(ENOTDIR);
         }
+        Self::check_writable(mount_flags)?;
         if !entry.is_directory_empty()? {
             return Err(ENOTEMPTY);
         }
@@ -1390,7 +1809,9 @@ impl VirtualFileSystem {
             return Err(EEXIST);
         }
 
-        let parent_new_id = self.pathname_resolution(cwd, creds, &newpath.parent()?)?;
+        let (parent_new_id, mount_flags) =
+            self.resolve_with_mount_flags(cwd, creds, &newpath.parent()?, true)?;
+        Self::check_writable(mount_flags)?;
         let parent_inode_id = self.dcache.get_entry_mut(&parent_new_id)?.inode_id;
         let parent_inode_number = parent_inode_id.inode_number;
 
@@ -1485,11 +1906,13 @@ impl VirtualFileSystem {
             return Err(EEXIST);
         }
         let filename = linkname.pop().expect("no filename");
-        let direntry_id = self.pathname_resolution(cwd, creds, &linkname)?;
+        let (direntry_id, mount_flags) =
+            self.resolve_with_mount_flags(cwd, creds, &linkname, true)?;
         let direntry = self.dcache.get_entry(&direntry_id)?;
         if !direntry.is_directory() {
             return Err(ENOENT);
         }
+        Self::check_writable(mount_flags)?;
 
         let parent_inode_id = direntry.inode_id;
 
@@ -1555,7 +1978,13 @@ impl VirtualFileSystem {
             return Err(Errno::EINVAL);
         }
 
-        let oldentry_id = self.pathname_resolution_no_follow_last_symlink(cwd, creds, &oldpath)?;
+        let (oldentry_id, old_mount_flags) =
+            self.resolve_with_mount_flags(cwd, creds, &oldpath, false)?;
+        Self::check_writable(old_mount_flags)?;
+        Self::check_writable(self.path_mount_flags(cwd, creds, &newpath.parent()?)?)?;
+        if self.is_mount_root(oldentry_id) {
+            return Err(EBUSY);
+        }
         // The old pathname shall not name an ancestor directory of
         // the new pathname.
         let resolved_old_path = self.resolve_path(cwd, creds, &oldpath)?;
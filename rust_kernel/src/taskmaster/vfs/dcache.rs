@@ -169,6 +169,19 @@ impl Dcache {
         self.add_entry(Some(new_parent), entry)
     }
 
+    /// Make the entry `id` a child of `new_parent`, keeping its id
+    pub fn reparent(
+        &mut self,
+        id: DirectoryEntryId,
+        new_parent: DirectoryEntryId,
+    ) -> SysResult<()> {
+        let parent_id = self.get_entry(&id)?.parent_id;
+        self.get_entry_mut(&new_parent)?.add_entry(id)?;
+        self.get_entry_mut(&parent_id)?.remove_entry(id)?;
+        self.get_entry_mut(&id)?.set_parent_id(new_parent);
+        Ok(())
+    }
+
     fn get_available_id(&self) -> DirectoryEntryId {
         let mut current_id = self.root_id; // check this
         loop {
@@ -7,9 +7,15 @@ use super::{DirectoryEntry, DirectoryEntryId, SysResult};
 use super::{DirectoryEntryBuilder, Filename, InodeId, Path};
 use super::{Driver, FileOperation, Inode, InodeData, IpcResult, PATH_MAX, VFS};
 use alloc::boxed::Box;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::convert::TryFrom;
 use core::fmt::{self, Debug, Display};
+use ext2::Ext2Filesystem;
+use fallible_collections::{FallibleArc, FallibleBox};
+use fat::FatFilesystem;
 use libc_binding::{gid_t, statfs, uid_t, utimbuf, Errno, FileType};
+use sync::DeadMutex;
 use try_clone_derive::TryClone;
 
 pub mod dead;
@@ -20,10 +26,17 @@ pub use ext2fs::Ext2fs;
 
 pub mod devfs;
 pub use devfs::Devfs;
+use devfs::DiskWrapper;
 
 pub mod procfs;
 pub use procfs::ProcFs;
 
+pub mod tmpfs;
+pub use tmpfs::Tmpfs;
+
+pub mod fatfs;
+pub use fatfs::Fatfs;
+
 pub trait FileSystem: Send + Debug {
     /// Returns whether the filesystem is dynamic, that is,
     /// if files can disappear from beneath the VFS,
@@ -47,11 +60,11 @@ pub trait FileSystem: Send + Debug {
         Err(Errno::ENOSYS)
     }
 
-    fn chmod(&self, _inode_nbr: u32, _mode: FileType) -> SysResult<()> {
+    fn chmod(&mut self, _inode_nbr: u32, _mode: FileType) -> SysResult<()> {
         Err(Errno::ENOSYS)
     }
 
-    fn chown(&self, _inode_nbr: u32, _owner: uid_t, _group: gid_t) -> SysResult<()> {
+    fn chown(&mut self, _inode_nbr: u32, _owner: uid_t, _group: gid_t) -> SysResult<()> {
         Err(Errno::ENOSYS)
     }
 
@@ -155,44 +168,152 @@ pub trait FileSystem: Send + Debug {
     // fn rmdir: Option<fn(&mut Superblock)>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, TryClone)]
 /// the filesystem source,
 pub enum FileSystemSource {
     /// is it mounted from  /dev/sda for exemple
     File { source_path: Path },
-    /// or a procfs ?
-    Procfs,
-    /// or a devfs ?
-    Devfs,
+    /// or a filesystem without device, like a procfs or a tmpfs ?
+    Nodev(FileSystemType),
 }
 
 impl Display for FileSystemSource {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::File { source_path } => write!(f, "{}", source_path),
-            Self::Procfs => write!(f, "proc"),
-            Self::Devfs => write!(f, "dev"),
+            Self::Nodev(fs_type) => write!(f, "{}", fs_type),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, TryClone)]
 pub enum FileSystemType {
     Ext2,
     Procfs,
     Devfs,
+    Tmpfs,
+    Vfat,
 }
 
-impl Display for FileSystemType {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl FileSystemType {
+    /// the name of the filesystem type, as given to mount(2)
+    pub fn name(&self) -> &'static str {
         match self {
-            Self::Ext2 => write!(f, "ext2"),
-            Self::Procfs => write!(f, "proc"),
-            Self::Devfs => write!(f, "dev"),
+            Self::Ext2 => "ext2",
+            Self::Procfs => "proc",
+            Self::Devfs => "dev",
+            Self::Tmpfs => "tmpfs",
+            Self::Vfat => "vfat",
         }
     }
 }
 
+/// Looks up the filesystem type `name` in the registry
+impl TryFrom<&str> for FileSystemType {
+    type Error = Errno;
+    fn try_from(name: &str) -> SysResult<Self> {
+        FILESYSTEM_DRIVERS
+            .iter()
+            .map(|driver| driver.fs_type)
+            .find(|fs_type| fs_type.name() == name)
+            .ok_or(Errno::ENODEV)
+    }
+}
+
+impl Display for FileSystemType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Creates a filesystem of id `fs_id` from the disk of its source,
+/// which is None for the nodev filesystems, with the filesystem
+/// specific `options` given to mount(2)
+pub type FileSystemConstructor = fn(
+    FileSystemId,
+    Option<DiskWrapper>,
+    Option<&str>,
+) -> SysResult<Arc<DeadMutex<dyn FileSystem>>>;
+
+/// A filesystem driver of the registry
+pub struct FileSystemDriver {
+    pub fs_type: FileSystemType,
+    /// the filesystem is not backed by a device
+    pub nodev: bool,
+    /// None when the filesystem is only mounted by the kernel at boot
+    pub new: Option<FileSystemConstructor>,
+}
+
+impl FileSystemDriver {
+    /// Returns the driver of the filesystem type `fs_type`
+    pub fn get(fs_type: FileSystemType) -> &'static Self {
+        FILESYSTEM_DRIVERS
+            .iter()
+            .find(|driver| driver.fs_type == fs_type)
+            .expect("every filesystem type should have a driver")
+    }
+}
+
+fn new_ext2(
+    fs_id: FileSystemId,
+    disk: Option<DiskWrapper>,
+    _options: Option<&str>,
+) -> SysResult<Arc<DeadMutex<dyn FileSystem>>> {
+    let disk = disk.ok_or(Errno::ENOTBLK)?;
+    let ext2 = Ext2Filesystem::new(Box::try_new(disk)?).map_err(|_| Errno::EINVAL)?;
+    Ok(Arc::try_new(DeadMutex::new(Ext2fs::new(ext2, fs_id)))?)
+}
+
+fn new_tmpfs(
+    fs_id: FileSystemId,
+    _disk: Option<DiskWrapper>,
+    options: Option<&str>,
+) -> SysResult<Arc<DeadMutex<dyn FileSystem>>> {
+    Ok(Arc::try_new(DeadMutex::new(Tmpfs::new(fs_id, options)?))?)
+}
+
+fn new_vfat(
+    fs_id: FileSystemId,
+    disk: Option<DiskWrapper>,
+    options: Option<&str>,
+) -> SysResult<Arc<DeadMutex<dyn FileSystem>>> {
+    let disk = disk.ok_or(Errno::ENOTBLK)?;
+    let fat = FatFilesystem::new(Box::try_new(disk)?).map_err(|_| Errno::EINVAL)?;
+    Ok(Arc::try_new(DeadMutex::new(Fatfs::new(
+        fat, fs_id, options,
+    )?))?)
+}
+
+/// The registry of the filesystem drivers, the filesystem type given
+/// to mount(2) selects one of them
+pub static FILESYSTEM_DRIVERS: [FileSystemDriver; 5] = [
+    FileSystemDriver {
+        fs_type: FileSystemType::Ext2,
+        nodev: false,
+        new: Some(new_ext2),
+    },
+    FileSystemDriver {
+        fs_type: FileSystemType::Procfs,
+        nodev: true,
+        new: None,
+    },
+    FileSystemDriver {
+        fs_type: FileSystemType::Devfs,
+        nodev: true,
+        new: None,
+    },
+    FileSystemDriver {
+        fs_type: FileSystemType::Tmpfs,
+        nodev: true,
+        new: Some(new_tmpfs),
+    },
+    FileSystemDriver {
+        fs_type: FileSystemType::Vfat,
+        nodev: false,
+        new: Some(new_vfat),
+    },
+];
+
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Default, Eq, PartialEq, TryClone)]
 pub struct FileSystemId(pub usize);
 
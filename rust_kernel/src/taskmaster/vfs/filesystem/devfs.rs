@@ -32,17 +32,28 @@ pub use random::{DevRandom, RandomDevice};
 pub mod fb;
 pub use fb::{DevFb, FbDevice};
 
+pub mod pty;
+pub use pty::{PtmxDevice, PtsDevice};
+
+pub mod serial;
+pub use serial::SerialDevice;
+
 pub mod sda;
-pub use sda::{BiosInt13hInstance, DiskDriver, DiskFileOperation, DiskWrapper, IdeAtaInstance};
+pub use sda::{
+    BiosInt13hInstance, DiskDriver, DiskFileOperation, DiskWrapper, IdeAtaInstance, SataInstance,
+};
+
+/// The files of the devfs are indexed by their parent directory inode number and their filename
+type DevfsKey = (InodeNumber, Filename);
 
 #[derive(Debug)]
 pub struct Devfs {
     fs_id: FileSystemId,
-    files: BTreeMap<Filename, (InodeData, Option<Box<dyn Driver>>)>,
+    files: BTreeMap<DevfsKey, (InodeData, Option<Box<dyn Driver>>)>,
     tty_minors: BTreeSet<dev_t>,
 }
 
-const ROOT_ID: InodeNumber = 2;
+pub const ROOT_ID: InodeNumber = 2;
 const TTY_MAJOR: dev_t = 4;
 
 impl KeyGenerator<InodeNumber> for Devfs {
@@ -140,7 +151,7 @@ impl Devfs {
             nbr_disk_sectors: 0,
         };
         self.files
-            .try_insert(filename, (inode_data, Some(driver)))?;
+            .try_insert((ROOT_ID, filename), (inode_data, Some(driver)))?;
         self.tty_minors.try_insert(minor)?;
         Ok(inode_id)
     }
@@ -162,12 +173,25 @@ impl Devfs {
         filetype: FileType,
         driver: Box<dyn Driver>,
         inode_id: InodeId,
+    ) -> SysResult<()> {
+        self.add_device(ROOT_ID, filename, filetype, driver, inode_id, (42, 42))
+    }
+
+    /// Add the device `filename` with the given `major` and `minor` numbers in the directory `parent`
+    pub fn add_device(
+        &mut self,
+        parent: InodeNumber,
+        filename: Filename,
+        filetype: FileType,
+        driver: Box<dyn Driver>,
+        inode_id: InodeId,
+        (major, minor): (dev_t, dev_t),
     ) -> SysResult<()> {
         let timestamp = unsafe { CURRENT_UNIX_TIME.load(Ordering::Relaxed) };
         let inode_data = InodeData {
             id: inode_id,
-            major: 42,
-            minor: 42,
+            major,
+            minor,
             link_number: 1,
             access_mode: filetype,
 
@@ -182,9 +206,42 @@ impl Devfs {
             nbr_disk_sectors: 0,
         };
         self.files
-            .try_insert(filename, (inode_data, Some(driver)))?;
+            .try_insert((parent, filename), (inode_data, Some(driver)))?;
         Ok(())
     }
+
+    /// Add the directory `filename` in the directory `parent`, returns its inode number
+    pub fn add_directory(
+        &mut self,
+        parent: InodeNumber,
+        filename: Filename,
+        permissions: FileType,
+    ) -> SysResult<InodeNumber> {
+        let inode_id = self.gen_inode_id();
+        let timestamp = unsafe { CURRENT_UNIX_TIME.load(Ordering::Relaxed) };
+        let inode_data = InodeData {
+            id: inode_id,
+            major: 0,
+            minor: 0,
+            link_number: 2,
+            access_mode: FileType::DIRECTORY | permissions,
+
+            uid: 0,
+            gid: 0,
+
+            atime: timestamp as time_t,
+            mtime: timestamp as time_t,
+            ctime: timestamp as time_t,
+
+            size: PAGE_SIZE as u64,
+            nbr_disk_sectors: 0,
+        };
+        self.files.try_insert(
+            (parent, filename),
+            (inode_data, Some(Box::try_new(DefaultDriver)?)),
+        )?;
+        Ok(inode_id.inode_number)
+    }
 }
 
 impl FileSystem for Devfs {
@@ -226,18 +283,23 @@ impl FileSystem for Devfs {
 
     fn lookup_directory(
         &mut self,
-        _inode_nbr: u32,
+        inode_nbr: u32,
     ) -> SysResult<Vec<(DirectoryEntry, InodeData, Box<dyn Driver>)>> {
-        // just returning all files in dev,
+        // just returning all files in the directory,
         Ok(self
             .files
             .iter_mut()
-            .filter_map(|(filename, (inode_data, driver))| {
+            .filter(|((parent, _), _)| *parent == inode_nbr)
+            .filter_map(|((_, filename), (inode_data, driver))| {
                 let inode_id = inode_data.id;
                 let direntry = {
                     let mut builder = DirectoryEntryBuilder::new();
                     builder.set_filename(*filename).set_inode_id(inode_id);
-                    builder.set_chardevice();
+                    if inode_data.is_directory() {
+                        builder.set_directory();
+                    } else {
+                        builder.set_chardevice();
+                    }
                     builder.build()
                 };
 
@@ -35,9 +35,8 @@ impl FileOperation for DevFb {
     fn write(&mut self, buf: &[u8]) -> SysResult<IpcResult<u32>> {
         dbg!("write");
         dbg!(buf.len());
-        let mut screen = SCREEN_MONAD.lock();
-
-        let r = screen.query_graphic_infos();
+        // The screen is not locked while logging, the logger draws on it
+        let r = SCREEN_MONAD.lock().query_graphic_infos();
         let data_to_write = match r {
             Ok((height, width, bpp)) => cmp::min(width * height * bpp / 8, buf.len()),
             Err(e) => {
@@ -45,7 +44,8 @@ impl FileOperation for DevFb {
                 return Err(Errno::EINVAL);
             }
         };
-        screen
+        SCREEN_MONAD
+            .lock()
             .draw_graphic_buffer(|ptr, _width, _height, _bpp| {
                 let s = unsafe { slice::from_raw_parts_mut(ptr, data_to_write) };
                 s.copy_from_slice(buf);
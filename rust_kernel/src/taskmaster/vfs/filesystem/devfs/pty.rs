@@ -0,0 +1,492 @@
+//! This file contains all the stuff about pseudo-terminals: the master
+//! side is obtained by opening /dev/ptmx, the slave side is /dev/pts/N
+
+use super::InodeId;
+use super::SysResult;
+use super::{Driver, FileOperation, IpcResult};
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use arrayvec::ArrayVec;
+use core::cmp::min;
+use fallible_collections::FallibleArc;
+use libc_binding::{dev_t, termios, winsize, Errno, IoctlCmd, OpenFlags, Pid, PollEvents, Signum};
+use messaging::{MessageTo, ProcessGroupMessage};
+use sync::dead_mutex::DeadMutex;
+use terminal::{LineDiscipline, ReadResult, TtyOutput};
+
+use crate::taskmaster::drivers::get_file_op_uid;
+use crate::taskmaster::scheduler::Scheduler;
+
+/// Number of pseudo-terminals available in /dev/pts
+pub const NB_PTYS: usize = 8;
+
+/// Major number of /dev/ptmx
+pub const PTMX_MAJOR: dev_t = 5;
+/// Minor number of /dev/ptmx
+pub const PTMX_MINOR: dev_t = 2;
+/// Major number of the slaves /dev/pts/N, the minor is N
+pub const PTS_MAJOR: dev_t = 136;
+
+/// Size of the buffer between the slave output and the master
+const PTY_BUF_SIZE: usize = 4096;
+
+/// The output of the line discipline of a pseudo-terminal: it is read by the master side
+#[derive(Debug)]
+pub struct PtyOutput {
+    buf: ArrayVec<[u8; PTY_BUF_SIZE]>,
+    /// ipc uid of the master side, woken up when some output is available
+    master_uid: usize,
+}
+
+impl PtyOutput {
+    /// Read the output of the slave side, returns the number of bytes read
+    fn read(&mut self, buf: &mut [u8]) -> usize {
+        let len = min(buf.len(), self.buf.len());
+        for (dest, src) in buf.iter_mut().zip(self.buf.drain(..len)) {
+            *dest = src;
+        }
+        len
+    }
+
+    fn is_full(&self) -> bool {
+        self.buf.is_full()
+    }
+}
+
+impl TtyOutput for PtyOutput {
+    fn write_bytes(&mut self, buf: &[u8]) -> usize {
+        let len = min(buf.len(), self.buf.capacity() - self.buf.len());
+        self.buf.extend(buf[..len].iter().cloned());
+        if len > 0 {
+            unsafe {
+                messaging::send_message(MessageTo::Reader {
+                    uid_file_op: self.master_uid,
+                });
+            }
+        }
+        len
+    }
+
+    fn erase_char(&mut self) {
+        self.write_bytes(b"\x08 \x08");
+    }
+
+    fn erase_line(&mut self, nb_chars: usize) {
+        for _ in 0..nb_chars {
+            self.erase_char();
+        }
+    }
+}
+
+/// A pair of master and slave pseudo-terminal devices
+#[derive(Debug)]
+pub struct Pty {
+    /// The N of /dev/pts/N
+    index: usize,
+    line_discipline: LineDiscipline<PtyOutput>,
+    window_size: winsize,
+    /// Number of file descriptors opened on the master side
+    master_ref: usize,
+    /// Number of file descriptors opened on the slave side
+    slave_ref: usize,
+    /// The slave side cannot be opened before unlockpt()
+    locked: bool,
+    /// The last slave file descriptor was closed: a read on the master side fails with EIO
+    slave_hung_up: bool,
+    master_uid: usize,
+    slave_uid: usize,
+}
+
+/// Main implementation of Pty
+impl Pty {
+    pub fn new(index: usize) -> Self {
+        let master_uid = get_file_op_uid();
+        let slave_uid = get_file_op_uid();
+        Self {
+            index,
+            line_discipline: Self::new_line_discipline(master_uid, slave_uid),
+            window_size: Self::default_window_size(),
+            master_ref: 0,
+            slave_ref: 0,
+            locked: true,
+            slave_hung_up: false,
+            master_uid,
+            slave_uid,
+        }
+    }
+
+    fn new_line_discipline(master_uid: usize, slave_uid: usize) -> LineDiscipline<PtyOutput> {
+        let mut line_discipline = LineDiscipline::new(PtyOutput {
+            buf: ArrayVec::new(),
+            master_uid,
+        });
+        line_discipline.open(slave_uid);
+        line_discipline
+    }
+
+    fn default_window_size() -> winsize {
+        let mut window_size: winsize = unsafe { core::mem::zeroed() };
+        window_size.ws_row = 24;
+        window_size.ws_col = 80;
+        window_size
+    }
+
+    /// A pty is free when no file descriptor is opened on it
+    fn is_free(&self) -> bool {
+        self.master_ref == 0 && self.slave_ref == 0
+    }
+
+    /// Reset the pty before giving it to a new master
+    fn reset(&mut self) {
+        self.line_discipline = Self::new_line_discipline(self.master_uid, self.slave_uid);
+        self.window_size = Self::default_window_size();
+        self.locked = true;
+        self.slave_hung_up = false;
+    }
+
+    /// Send the signal `signum` to the foreground process group of the slave
+    fn signal_foreground(&mut self, signum: Signum) {
+        let pgid = self.line_discipline.tcgetpgrp();
+        if pgid != 0 {
+            unsafe {
+                messaging::send_message(MessageTo::ProcessGroup {
+                    pgid,
+                    content: ProcessGroupMessage::Signal(signum),
+                });
+            }
+        }
+    }
+
+    /// Read the input of the slave side through the line discipline
+    fn read_slave(&mut self, buf: &mut [u8], timer_expired: bool) -> SysResult<IpcResult<u32>> {
+        match self.line_discipline.read(buf, timer_expired) {
+            ReadResult::NonBlocking(read_count) => {
+                // Some room was made for the master writers
+                unsafe {
+                    messaging::send_message(MessageTo::Writer {
+                        uid_file_op: self.master_uid,
+                    });
+                }
+                Ok(IpcResult::Done(read_count as _))
+            }
+            // The master is gone, returns an end of file
+            ReadResult::Blocking if self.master_ref == 0 => Ok(IpcResult::Done(0)),
+            ReadResult::Blocking => Ok(IpcResult::Wait(0, self.slave_uid)),
+        }
+    }
+}
+
+/// The side of a pseudo-terminal
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum PtySide {
+    Master,
+    Slave,
+}
+
+/// This structure represents a FileOperation on a side of a pseudo-terminal
+#[derive(Debug)]
+pub struct PtyFileOperation {
+    pty: Arc<DeadMutex<Pty>>,
+    side: PtySide,
+    inode_id: InodeId,
+}
+
+/// Main implementation of PtyFileOperation
+impl PtyFileOperation {
+    fn new(pty: Arc<DeadMutex<Pty>>, side: PtySide, inode_id: InodeId) -> Self {
+        Self {
+            pty,
+            side,
+            inode_id,
+        }
+    }
+}
+
+/// Main Trait implementation of PtyFileOperation
+impl FileOperation for PtyFileOperation {
+    fn register(&mut self, _flags: OpenFlags) {
+        let mut pty = self.pty.lock();
+        match self.side {
+            PtySide::Master => pty.master_ref += 1,
+            PtySide::Slave => {
+                pty.slave_ref += 1;
+                pty.slave_hung_up = false;
+            }
+        }
+    }
+
+    fn unregister(&mut self, _flags: OpenFlags) {
+        let mut pty = self.pty.lock();
+        match self.side {
+            PtySide::Master => {
+                pty.master_ref -= 1;
+                // Hang up the slave side when the last master is gone
+                if pty.master_ref == 0 {
+                    pty.signal_foreground(Signum::SIGHUP);
+                    unsafe {
+                        messaging::send_message(MessageTo::Reader {
+                            uid_file_op: pty.slave_uid,
+                        });
+                        messaging::send_message(MessageTo::Writer {
+                            uid_file_op: pty.slave_uid,
+                        });
+                    }
+                }
+            }
+            PtySide::Slave => {
+                pty.slave_ref -= 1;
+                // Announce to the master that the last slave is gone
+                if pty.slave_ref == 0 {
+                    pty.slave_hung_up = true;
+                    unsafe {
+                        messaging::send_message(MessageTo::Reader {
+                            uid_file_op: pty.master_uid,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    fn get_inode_id(&self) -> SysResult<InodeId> {
+        Ok(self.inode_id)
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> SysResult<IpcResult<u32>> {
+        let mut pty = self.pty.lock();
+        match self.side {
+            PtySide::Master => {
+                let read_count = pty.line_discipline.tty.read(buf);
+                if read_count > 0 {
+                    // Some room was made for the slave writers
+                    unsafe {
+                        messaging::send_message(MessageTo::Writer {
+                            uid_file_op: pty.slave_uid,
+                        });
+                    }
+                    Ok(IpcResult::Done(read_count as _))
+                } else if pty.slave_hung_up {
+                    Err(Errno::EIO)
+                } else {
+                    Ok(IpcResult::Wait(0, pty.master_uid))
+                }
+            }
+            PtySide::Slave => pty.read_slave(buf, false),
+        }
+    }
+
+    fn read_timer(&self) -> Option<u32> {
+        match self.side {
+            PtySide::Master => None,
+            PtySide::Slave => self.pty.lock().line_discipline.read_timer(),
+        }
+    }
+
+    fn read_timer_expired(&mut self, buf: &mut [u8]) -> SysResult<u32> {
+        let read_result = match self.side {
+            PtySide::Master => self.read(buf)?,
+            PtySide::Slave => self.pty.lock().read_slave(buf, true)?,
+        };
+        match read_result {
+            IpcResult::Done(res) | IpcResult::Wait(res, _) => Ok(res),
+        }
+    }
+
+    fn write(&mut self, buf: &[u8]) -> SysResult<IpcResult<u32>> {
+        let mut pty = self.pty.lock();
+        let (written, file_op_uid) = match self.side {
+            // What is written on the master side is the input of the line discipline
+            PtySide::Master => (pty.line_discipline.handle_input(buf), pty.master_uid),
+            PtySide::Slave => {
+                if pty.master_ref == 0 {
+                    return Err(Errno::EIO);
+                }
+                (pty.line_discipline.write(buf), pty.slave_uid)
+            }
+        };
+        if written == buf.len() {
+            Ok(IpcResult::Done(written as _))
+        } else {
+            Ok(IpcResult::Wait(written as _, file_op_uid))
+        }
+    }
+
+    fn poll(&mut self, events: PollEvents) -> SysResult<IpcResult<PollEvents>> {
+        let pty = self.pty.lock();
+        let mut revents = PollEvents::empty();
+        let file_op_uid = match self.side {
+            PtySide::Master => {
+                if pty.line_discipline.tty.buf.len() > 0 {
+                    revents |= events & PollEvents::READ;
+                }
+                if pty.line_discipline.can_receive() {
+                    revents |= events & PollEvents::WRITE;
+                }
+                if pty.slave_hung_up {
+                    revents |= PollEvents::POLLHUP;
+                }
+                pty.master_uid
+            }
+            PtySide::Slave => {
+                if pty.line_discipline.is_readable() {
+                    revents |= events & PollEvents::READ;
+                }
+                if !pty.line_discipline.tty.is_full() {
+                    revents |= events & PollEvents::WRITE;
+                }
+                if pty.master_ref == 0 {
+                    revents |= PollEvents::POLLHUP;
+                }
+                pty.slave_uid
+            }
+        };
+        Ok(IpcResult::poll(revents, file_op_uid))
+    }
+
+    fn tcgetattr(&self, termios_p: &mut termios) -> SysResult<u32> {
+        self.pty.lock().line_discipline.tcgetattr(termios_p);
+        Ok(0)
+    }
+
+    fn tcsetattr(&mut self, optional_actions: u32, termios_p: &termios) -> SysResult<u32> {
+        self.pty
+            .lock()
+            .line_discipline
+            .tcsetattr(optional_actions, termios_p);
+        Ok(0)
+    }
+
+    fn tcgetpgrp(&self) -> SysResult<Pid> {
+        Ok(self.pty.lock().line_discipline.tcgetpgrp())
+    }
+
+    fn tcsetpgrp(&mut self, pgid_id: Pid) -> SysResult<u32> {
+        self.pty.lock().line_discipline.tcsetpgrp(pgid_id);
+        Ok(0)
+    }
+
+    fn tcgetsid(&self) -> SysResult<Pid> {
+        Ok(self.pty.lock().line_discipline.tcgetsid())
+    }
+
+    /// Only the slave side can be a controlling terminal
+    fn tcsetsid(&mut self, sid: Pid) -> SysResult<u32> {
+        match self.side {
+            PtySide::Master => Err(Errno::ENOTTY),
+            PtySide::Slave => {
+                self.pty.lock().line_discipline.tcsetsid(sid);
+                Ok(0)
+            }
+        }
+    }
+
+    fn isatty(&mut self) -> SysResult<u32> {
+        Ok(1)
+    }
+
+    fn ioctl(&mut self, scheduler: &Scheduler, cmd: IoctlCmd, arg: u32) -> SysResult<u32> {
+        let v = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator();
+        let mut pty = self.pty.lock();
+
+        match cmd {
+            IoctlCmd::TIOCGWINSZ => {
+                *v.make_checked_ref_mut(arg as *mut winsize)? = pty.window_size;
+                Ok(0)
+            }
+            IoctlCmd::TIOCSWINSZ => {
+                let window_size = *v.make_checked_ref(arg as *const winsize)?;
+                let resized = window_size.ws_row != pty.window_size.ws_row
+                    || window_size.ws_col != pty.window_size.ws_col;
+                pty.window_size = window_size;
+                if resized {
+                    pty.signal_foreground(Signum::SIGWINCH);
+                }
+                Ok(0)
+            }
+            IoctlCmd::TIOCGPTN if self.side == PtySide::Master => {
+                *v.make_checked_ref_mut(arg as *mut i32)? = pty.index as i32;
+                Ok(0)
+            }
+            IoctlCmd::TIOCSPTLCK if self.side == PtySide::Master => {
+                pty.locked = *v.make_checked_ref(arg as *const i32)? != 0;
+                Ok(0)
+            }
+            IoctlCmd::TIOCGPTN | IoctlCmd::TIOCSPTLCK => Err(Errno::ENOTTY),
+            _ => Err(Errno::EINVAL),
+        }
+    }
+}
+
+/// Structure of the pseudo-terminal multiplexer /dev/ptmx
+#[derive(Debug)]
+pub struct PtmxDevice {
+    ptys: Vec<Arc<DeadMutex<Pty>>>,
+    inode_id: InodeId,
+}
+
+/// Main implementation of PtmxDevice
+impl PtmxDevice {
+    pub fn new(ptys: Vec<Arc<DeadMutex<Pty>>>, inode_id: InodeId) -> Self {
+        Self { ptys, inode_id }
+    }
+}
+
+/// Driver trait implementation of PtmxDevice: each open gives the master side of a free pty
+impl Driver for PtmxDevice {
+    fn open(
+        &mut self,
+        _flags: OpenFlags,
+    ) -> SysResult<IpcResult<Arc<DeadMutex<dyn FileOperation>>>> {
+        let pty = self
+            .ptys
+            .iter()
+            .find(|pty| pty.lock().is_free())
+            .ok_or(Errno::EAGAIN)?;
+        pty.lock().reset();
+        Ok(IpcResult::Done(Arc::try_new(DeadMutex::new(
+            PtyFileOperation::new(pty.clone(), PtySide::Master, self.inode_id),
+        ))?))
+    }
+}
+
+/// Structure of a pseudo-terminal slave /dev/pts/N
+#[derive(Debug)]
+pub struct PtsDevice {
+    /// A slave got just one FileOperation structure which share with all
+    operation: Arc<DeadMutex<PtyFileOperation>>,
+}
+
+/// Main implementation of PtsDevice
+impl PtsDevice {
+    pub fn try_new(pty: Arc<DeadMutex<Pty>>, inode_id: InodeId) -> SysResult<Self> {
+        Ok(Self {
+            operation: Arc::try_new(DeadMutex::new(PtyFileOperation::new(
+                pty,
+                PtySide::Slave,
+                inode_id,
+            )))?,
+        })
+    }
+}
+
+/// Driver trait implementation of PtsDevice
+impl Driver for PtsDevice {
+    fn open(
+        &mut self,
+        _flags: OpenFlags,
+    ) -> SysResult<IpcResult<Arc<DeadMutex<dyn FileOperation>>>> {
+        {
+            let operation = self.operation.lock();
+            let pty = operation.pty.lock();
+            // The slave cannot be opened without master or before unlockpt()
+            if pty.master_ref == 0 || pty.locked {
+                return Err(Errno::EIO);
+            }
+        }
+        Ok(IpcResult::Done(self.operation.clone()))
+    }
+}
@@ -5,8 +5,8 @@ use super::InodeId;
 use super::IpcResult;
 use super::SysResult;
 use crate::drivers::storage::{
-    BlockIo, DiskResult, NbrSectors, Sector, BIOS_INT13H, IDE_ATA_CONTROLLER, SECTOR_MASK,
-    SECTOR_SHIFT, SECTOR_SIZE,
+    BlockIo, DiskResult, NbrSectors, Sector, BIOS_INT13H, IDE_ATA_CONTROLLER, SATA_CONTROLLER,
+    SECTOR_MASK, SECTOR_SHIFT, SECTOR_SIZE,
 };
 use alloc::sync::Arc;
 use core::cmp::min;
@@ -132,6 +132,44 @@ impl BlockIo for IdeAtaInstance {
     }
 }
 
+#[derive(Debug, Copy, Clone)]
+pub struct SataInstance;
+
+impl BlockIo for SataInstance {
+    fn read(
+        &mut self,
+        start_sector: Sector,
+        nbr_sectors: NbrSectors,
+        buf: *mut u8,
+    ) -> DiskResult<NbrSectors> {
+        unsafe {
+            SATA_CONTROLLER
+                .as_mut()
+                .unwrap()
+                .read(start_sector, nbr_sectors, buf)
+        }
+    }
+
+    fn write(
+        &mut self,
+        start_sector: Sector,
+        nbr_sectors: NbrSectors,
+        buf: *const u8,
+    ) -> DiskResult<NbrSectors> {
+        unsafe {
+            SATA_CONTROLLER
+                .as_mut()
+                .unwrap()
+                .write(start_sector, nbr_sectors, buf)
+        }
+    }
+
+    /// return the size of the disk
+    fn disk_size(&self) -> u64 {
+        unsafe { SATA_CONTROLLER.as_ref().unwrap().disk_size() }
+    }
+}
+
 /// transform a disk which read sector by sector into a disk which
 /// implement file operation
 pub struct DiskFileOperation<D: BlockIo> {
@@ -257,6 +295,11 @@ impl<D: BlockIo + Send> FileOperation for DiskFileOperation<D> {
         Ok(IpcResult::Done(len as u32))
     }
 
+    fn fsync(&mut self) -> SysResult<u32> {
+        self.disk.flush().map_err(|_| Errno::EIO)?;
+        Ok(0)
+    }
+
     fn lseek(&mut self, offset: off_t, whence: Whence) -> SysResult<off_t> {
         if offset == core::i64::MIN {
             // volontary trash i64 min value to avoid -offset ==
@@ -314,6 +357,7 @@ use sync::DeadMutex;
 impl DiskIo for DiskWrapper {
     /// flush
     fn flush(&mut self) -> IoResult<()> {
+        self.0.lock().fsync()?;
         Ok(())
     }
     /// write at offset
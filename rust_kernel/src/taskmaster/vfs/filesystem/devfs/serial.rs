@@ -0,0 +1,155 @@
+//! This file contains the serial tty /dev/ttyS0 over COM1
+
+use super::InodeId;
+use super::SysResult;
+use super::{Driver, FileOperation, IpcResult};
+
+use alloc::sync::Arc;
+use fallible_collections::FallibleArc;
+use libc_binding::{dev_t, termios, winsize, Errno, IoctlCmd, OpenFlags, Pid, PollEvents};
+use sync::dead_mutex::DeadMutex;
+use terminal::{LineDiscipline, ReadResult, SerialOutput, TERMINAL};
+
+use crate::taskmaster::drivers::get_file_op_uid;
+use crate::taskmaster::scheduler::Scheduler;
+
+/// Major number of the serial ttys
+pub const SERIAL_MAJOR: dev_t = 4;
+/// Minor number of /dev/ttyS0
+pub const SERIAL_MINOR: dev_t = 64;
+
+/// Get the line discipline of the serial tty
+fn line_discipline() -> &'static mut LineDiscipline<SerialOutput> {
+    unsafe { TERMINAL.as_mut().unwrap().get_serial_line_discipline() }
+}
+
+/// This structure represents a FileOperation of type SerialFileOperation
+#[derive(Debug)]
+pub struct SerialFileOperation {
+    file_op_uid: usize,
+    inode_id: InodeId,
+    /// The size of the remote terminal cannot be queried, it is set by TIOCSWINSZ
+    window_size: winsize,
+}
+
+/// Main implementation of SerialFileOperation
+impl SerialFileOperation {
+    pub fn new(inode_id: InodeId) -> Self {
+        let mut window_size: winsize = unsafe { core::mem::zeroed() };
+        window_size.ws_row = 24;
+        window_size.ws_col = 80;
+        Self {
+            file_op_uid: get_file_op_uid(),
+            inode_id,
+            window_size,
+        }
+    }
+}
+
+/// Main Trait implementation of SerialFileOperation
+impl FileOperation for SerialFileOperation {
+    fn register(&mut self, _flags: OpenFlags) {}
+    fn unregister(&mut self, _flags: OpenFlags) {}
+
+    fn get_inode_id(&self) -> SysResult<InodeId> {
+        Ok(self.inode_id)
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> SysResult<IpcResult<u32>> {
+        match line_discipline().read(buf, false) {
+            ReadResult::NonBlocking(read_count) => Ok(IpcResult::Done(read_count as _)),
+            ReadResult::Blocking => Ok(IpcResult::Wait(0, self.file_op_uid)),
+        }
+    }
+    fn read_timer(&self) -> Option<u32> {
+        line_discipline().read_timer()
+    }
+    fn read_timer_expired(&mut self, buf: &mut [u8]) -> SysResult<u32> {
+        match line_discipline().read(buf, true) {
+            ReadResult::NonBlocking(read_count) => Ok(read_count as _),
+            ReadResult::Blocking => Ok(0),
+        }
+    }
+    fn poll(&mut self, events: PollEvents) -> SysResult<IpcResult<PollEvents>> {
+        // A write on the serial tty never blocks
+        let mut revents = events & PollEvents::WRITE;
+        if line_discipline().is_readable() {
+            revents |= events & PollEvents::READ;
+        }
+        Ok(IpcResult::poll(revents, self.file_op_uid))
+    }
+    fn write(&mut self, buf: &[u8]) -> SysResult<IpcResult<u32>> {
+        Ok(IpcResult::Done(line_discipline().write(buf) as _))
+    }
+    fn tcgetattr(&self, termios_p: &mut termios) -> SysResult<u32> {
+        line_discipline().tcgetattr(termios_p);
+        Ok(0)
+    }
+    fn tcsetattr(&mut self, optional_actions: u32, termios_p: &termios) -> SysResult<u32> {
+        line_discipline().tcsetattr(optional_actions, termios_p);
+        Ok(0)
+    }
+    fn tcgetpgrp(&self) -> SysResult<Pid> {
+        Ok(line_discipline().tcgetpgrp())
+    }
+    fn tcsetpgrp(&mut self, pgid_id: Pid) -> SysResult<u32> {
+        line_discipline().tcsetpgrp(pgid_id);
+        Ok(0)
+    }
+    fn tcgetsid(&self) -> SysResult<Pid> {
+        Ok(line_discipline().tcgetsid())
+    }
+    fn tcsetsid(&mut self, sid: Pid) -> SysResult<u32> {
+        line_discipline().tcsetsid(sid);
+        Ok(0)
+    }
+    fn isatty(&mut self) -> SysResult<u32> {
+        return Ok(1);
+    }
+
+    fn ioctl(&mut self, scheduler: &Scheduler, cmd: IoctlCmd, arg: u32) -> SysResult<u32> {
+        let v = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator();
+        match cmd {
+            IoctlCmd::TIOCGWINSZ => {
+                *v.make_checked_ref_mut(arg as *mut winsize)? = self.window_size;
+                Ok(0)
+            }
+            IoctlCmd::TIOCSWINSZ => {
+                self.window_size = *v.make_checked_ref(arg as *const winsize)?;
+                Ok(0)
+            }
+            _ => Err(Errno::EINVAL),
+        }
+    }
+}
+
+/// Stucture of SerialDevice
+#[derive(Debug)]
+pub struct SerialDevice {
+    /// The serial tty got just one FileOperation structure which share with all
+    operation: Arc<DeadMutex<SerialFileOperation>>,
+}
+
+/// Main implementation of SerialDevice
+impl SerialDevice {
+    pub fn try_new(inode_id: InodeId) -> SysResult<Self> {
+        Ok(Self {
+            operation: Arc::try_new(DeadMutex::new(SerialFileOperation::new(inode_id)))?,
+        })
+    }
+}
+
+/// Driver trait implementation of SerialDevice
+impl Driver for SerialDevice {
+    fn open(
+        &mut self,
+        _flags: OpenFlags,
+    ) -> SysResult<IpcResult<Arc<DeadMutex<dyn FileOperation>>>> {
+        let file_op_uid = self.operation.lock().file_op_uid;
+        line_discipline().open(file_op_uid);
+        Ok(IpcResult::Done(self.operation.clone()))
+    }
+}
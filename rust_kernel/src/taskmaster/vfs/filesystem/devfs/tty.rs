@@ -6,7 +6,7 @@ use super::{Driver, FileOperation, IpcResult};
 
 use alloc::sync::Arc;
 use fallible_collections::FallibleArc;
-use libc_binding::{local_buffer, termios, winsize, Errno, IoctlCmd, OpenFlags, Pid};
+use libc_binding::{local_buffer, termios, winsize, Errno, IoctlCmd, OpenFlags, Pid, PollEvents};
 use sync::dead_mutex::DeadMutex;
 use terminal::{ReadResult, TERMINAL};
 
@@ -35,34 +35,6 @@ impl TtyFileOperation {
     }
 }
 
-/// Taken directly from the rust std Utf8Error doc.
-fn from_utf8_lossy<F>(mut input: &[u8], mut push: F)
-where
-    F: FnMut(&str),
-{
-    loop {
-        match ::core::str::from_utf8(input) {
-            Ok(valid) => {
-                push(valid);
-                break;
-            }
-            Err(error) => {
-                let (valid, after_valid) = input.split_at(error.valid_up_to());
-                let to_push =
-                    ::core::str::from_utf8(valid).expect("Valid str from res should be valid...");
-                push(to_push);
-                // push("\u{FFFD}");
-
-                if let Some(invalid_sequence_length) = error.error_len() {
-                    input = &after_valid[invalid_sequence_length..]
-                } else {
-                    break;
-                }
-            }
-        }
-    }
-}
-
 /// Main Trait implementation of TtyFileOperation
 impl FileOperation for TtyFileOperation {
     fn register(&mut self, _flags: OpenFlags) {}
@@ -77,7 +49,7 @@ impl FileOperation for TtyFileOperation {
             TERMINAL
                 .as_mut()
                 .unwrap()
-                .read(buf, self.controlling_terminal)
+                .read(buf, self.controlling_terminal, false)
         };
 
         match read_result {
@@ -86,12 +58,51 @@ impl FileOperation for TtyFileOperation {
             ReadResult::Blocking => Ok(IpcResult::Wait(0, self.file_op_uid)),
         }
     }
+    fn read_timer(&self) -> Option<u32> {
+        unsafe {
+            TERMINAL
+                .as_mut()
+                .unwrap()
+                .get_line_discipline(self.controlling_terminal)
+                .read_timer()
+        }
+    }
+    fn read_timer_expired(&mut self, buf: &mut [u8]) -> SysResult<u32> {
+        let read_result = unsafe {
+            TERMINAL
+                .as_mut()
+                .unwrap()
+                .read(buf, self.controlling_terminal, true)
+        };
+        match read_result {
+            ReadResult::NonBlocking(read_count) => Ok(read_count as _),
+            ReadResult::Blocking => Ok(0),
+        }
+    }
+    fn poll(&mut self, events: PollEvents) -> SysResult<IpcResult<PollEvents>> {
+        let readable = unsafe {
+            TERMINAL
+                .as_mut()
+                .unwrap()
+                .get_line_discipline(self.controlling_terminal)
+                .is_readable()
+        };
+        // A write on the tty never blocks
+        let mut revents = events & PollEvents::WRITE;
+        if readable {
+            revents |= events & PollEvents::READ;
+        }
+        Ok(IpcResult::poll(revents, self.file_op_uid))
+    }
     fn write(&mut self, buf: &[u8]) -> SysResult<IpcResult<u32>> {
-        // This skips invalid utf8 sequences.
-        from_utf8_lossy(buf, |to_print| {
-            print_tty!(self.controlling_terminal, "{}", to_print)
-        });
-        Ok(IpcResult::Done(buf.len() as _))
+        let written = unsafe {
+            TERMINAL
+                .as_mut()
+                .unwrap()
+                .get_line_discipline(self.controlling_terminal)
+                .write(buf)
+        };
+        Ok(IpcResult::Done(written as _))
     }
     fn tcgetattr(&self, termios_p: &mut termios) -> SysResult<u32> {
         unsafe {
@@ -132,6 +143,25 @@ impl FileOperation for TtyFileOperation {
         }
         Ok(0)
     }
+    fn tcgetsid(&self) -> SysResult<Pid> {
+        unsafe {
+            Ok(TERMINAL
+                .as_mut()
+                .unwrap()
+                .get_line_discipline(self.controlling_terminal)
+                .tcgetsid())
+        }
+    }
+    fn tcsetsid(&mut self, sid: Pid) -> SysResult<u32> {
+        unsafe {
+            TERMINAL
+                .as_mut()
+                .unwrap()
+                .get_line_discipline(self.controlling_terminal)
+                .tcsetsid(sid);
+        }
+        Ok(0)
+    }
     fn isatty(&mut self) -> SysResult<u32> {
         return Ok(1);
     }
@@ -161,11 +161,11 @@ impl FileSystem for Ext2fs {
         Ok(self.ext2.lock().truncate(inode_nbr, new_size)?)
     }
 
-    fn chmod(&self, inode_nbr: u32, mode: FileType) -> SysResult<()> {
+    fn chmod(&mut self, inode_nbr: u32, mode: FileType) -> SysResult<()> {
         Ok(self.ext2.lock().chmod(inode_nbr, mode)?)
     }
 
-    fn chown(&self, inode_nbr: u32, owner: uid_t, group: gid_t) -> SysResult<()> {
+    fn chown(&mut self, inode_nbr: u32, owner: uid_t, group: gid_t) -> SysResult<()> {
         Ok(self.ext2.lock().chown(inode_nbr, owner, group)?)
     }
 
@@ -1,5 +1,6 @@
 use super::{Credentials, Driver, FileOperation, IpcResult, SysResult};
-use super::{InodeId, VFS};
+use super::{FileSystemId, InodeId, VFS};
+use crate::drivers::storage::BLOCK_CACHE;
 use alloc::sync::Arc;
 use libc_binding::{gid_t, off_t, statfs, uid_t, Errno, FileType, OpenFlags, Whence};
 use sync::DeadMutex;
@@ -32,6 +33,8 @@ impl Driver for Ext2DriverFile {
 pub struct Ext2FileOperation {
     inode_id: InodeId,
     offset: u64,
+    /// the mount through which the file was opened
+    mount: Option<FileSystemId>,
 }
 
 impl Ext2FileOperation {
@@ -39,6 +42,7 @@ impl Ext2FileOperation {
         Self {
             inode_id,
             offset: 0,
+            mount: None,
         }
     }
 }
@@ -48,6 +52,10 @@ impl FileOperation for Ext2FileOperation {
         self.offset = offset;
     }
 
+    fn set_mount(&mut self, mount: Option<FileSystemId>) {
+        self.mount = mount;
+    }
+
     fn get_inode_id(&self) -> SysResult<InodeId> {
         Ok(self.inode_id)
     }
@@ -66,6 +74,12 @@ impl FileOperation for Ext2FileOperation {
         Ok(0)
     }
 
+    /// The data and the metadata of the file may be anywhere on the disk: write back all the block cache
+    fn fsync(&mut self) -> SysResult<u32> {
+        BLOCK_CACHE.lock().sync(None).map_err(|_| Errno::EIO)?;
+        Ok(0)
+    }
+
     fn write(&mut self, buf: &[u8]) -> SysResult<IpcResult<u32>> {
         let res = VFS
             .lock()
@@ -109,12 +123,13 @@ impl FileOperation for Ext2FileOperation {
     }
 
     fn fchmod(&mut self, creds: &Credentials, mode: FileType) -> SysResult<u32> {
-        VFS.lock().fchmod(creds, self.inode_id, mode)?;
+        VFS.lock().fchmod(creds, self.inode_id, self.mount, mode)?;
         Ok(0)
     }
 
     fn fchown(&mut self, creds: &Credentials, owner: uid_t, group: gid_t) -> SysResult<u32> {
-        VFS.lock().fchown(creds, self.inode_id, owner, group)?;
+        VFS.lock()
+            .fchown(creds, self.inode_id, self.mount, owner, group)?;
         Ok(0)
     }
 }
@@ -0,0 +1,345 @@
+//! The FAT wrapper: FAT has no owners and no permissions, they are
+//! given by the mount options, like on Linux
+
+use super::Credentials;
+use super::DefaultDriver;
+use super::FileOperation;
+use super::FileSystem;
+use super::IpcResult;
+use super::VFS;
+use super::{DirectoryEntry, Driver, FileSystemId, InodeData};
+use super::{DirectoryEntryBuilder, Filename, InodeId, SysResult};
+use crate::taskmaster::kmodules::CURRENT_UNIX_TIME;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::sync::atomic::Ordering;
+use fallible_collections::{FallibleBox, FallibleVec};
+use fat::{FatFilesystem, FileInfo, ROOT_INODE};
+use libc_binding::{gid_t, statfs, time_t, uid_t, utimbuf, Errno, FileType, MSDOS_SUPER_MAGIC};
+
+pub mod file;
+pub use file::{FatDriverFile, FatFileOperation};
+
+/// The permissions which are not given by default
+const DEFAULT_UMASK: u16 = 0o022;
+
+/// The number of sectors of 512 bytes in which the size of a file is counted
+const SECTOR_SIZE: u64 = 512;
+
+fn current_time() -> u32 {
+    unsafe { CURRENT_UNIX_TIME.load(Ordering::Relaxed) }
+}
+
+/// Parse the value of a numeric mount option, in `radix`
+fn parse_option<T: TryFrom<u32>>(value: &str, radix: u32) -> SysResult<T> {
+    u32::from_str_radix(value, radix)
+        .ok()
+        .and_then(|n| T::try_from(n).ok())
+        .ok_or(Errno::EINVAL)
+}
+
+#[derive(Debug)]
+pub struct Fatfs {
+    fat: FatFilesystem,
+    fs_id: FileSystemId,
+    /// The owner of all the files
+    uid: uid_t,
+    gid: gid_t,
+    /// The permissions which are removed from the files
+    fmask: u16,
+    /// The permissions which are removed from the directories
+    dmask: u16,
+    /// Do not fail on the changes of owner or permissions which cannot be stored
+    quiet: bool,
+}
+
+impl Fatfs {
+    /// Wrap a FAT filesystem. The comma separated mount `options` are:
+    /// uid=N, gid=N: the owner of the files, root by default
+    /// umask=OCTAL: the permissions which are not given, 022 by default
+    /// fmask=OCTAL, dmask=OCTAL: the umask of the files or of the directories only
+    /// quiet: chmod and chown do not fail when they cannot be honoured
+    pub fn new(fat: FatFilesystem, fs_id: FileSystemId, options: Option<&str>) -> SysResult<Self> {
+        let mut fatfs = Self {
+            fat,
+            fs_id,
+            uid: 0,
+            gid: 0,
+            fmask: DEFAULT_UMASK,
+            dmask: DEFAULT_UMASK,
+            quiet: false,
+        };
+        for option in options.unwrap_or("").split(',').filter(|o| !o.is_empty()) {
+            let mut it = option.splitn(2, '=');
+            match (it.next(), it.next()) {
+                (Some("uid"), Some(value)) => fatfs.uid = parse_option(value, 10)?,
+                (Some("gid"), Some(value)) => fatfs.gid = parse_option(value, 10)?,
+                (Some("umask"), Some(value)) => {
+                    fatfs.fmask = parse_option(value, 8)?;
+                    fatfs.dmask = fatfs.fmask;
+                }
+                (Some("fmask"), Some(value)) => fatfs.fmask = parse_option(value, 8)?,
+                (Some("dmask"), Some(value)) => fatfs.dmask = parse_option(value, 8)?,
+                (Some("quiet"), None) => fatfs.quiet = true,
+                _ => return Err(Errno::EINVAL),
+            }
+        }
+        Ok(fatfs)
+    }
+
+    /// The mode of a file: its type, the permissions allowed by the mount
+    /// options, without the write ones when the file is read-only
+    fn mode(&self, info: &FileInfo) -> FileType {
+        let (file_type, mask) = if info.is_directory() {
+            (FileType::DIRECTORY, self.dmask)
+        } else {
+            (FileType::REGULAR_FILE, self.fmask)
+        };
+        let mut permissions = FileType::from_bits_truncate(0o777 & !mask);
+        if info.attributes.contains(fat::Attributes::READ_ONLY) {
+            permissions.remove(
+                FileType::USER_WRITE_PERMISSION
+                    | FileType::GROUP_WRITE_PERMISSION
+                    | FileType::OTHER_WRITE_PERMISSION,
+            );
+        }
+        file_type | permissions
+    }
+
+    fn inode_data(&self, info: &FileInfo) -> InodeData {
+        let cluster_size = self.fat.cluster_size() as u64;
+        InodeData {
+            id: InodeId::new(info.inode_nbr, Some(self.fs_id)),
+            major: 0,
+            minor: 0,
+            link_number: if info.is_directory() { 2 } else { 1 },
+            access_mode: self.mode(info),
+            uid: self.uid,
+            gid: self.gid,
+            atime: info.access_time as time_t,
+            mtime: info.modification_time as time_t,
+            // FAT has no change time
+            ctime: info.modification_time as time_t,
+            size: info.size,
+            nbr_disk_sectors: ((info.size + cluster_size - 1) / cluster_size * cluster_size
+                / SECTOR_SIZE) as _,
+        }
+    }
+
+    /// Build the directory entry, the inode data and the driver of the file `filename`
+    fn make_entry(
+        &self,
+        filename: Filename,
+        info: &FileInfo,
+    ) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        let inode_data = self.inode_data(info);
+        let inode_id = inode_data.id;
+
+        let direntry = {
+            let mut builder = DirectoryEntryBuilder::new();
+            builder.set_filename(filename).set_inode_id(inode_id);
+            if info.is_directory() {
+                builder.set_directory();
+            } else {
+                builder.set_regular();
+            }
+            builder.build()
+        };
+        let driver: Box<dyn Driver> = if info.is_directory() {
+            Box::try_new(DefaultDriver)?
+        } else {
+            Box::try_new(FatDriverFile::new(inode_id))?
+        };
+        Ok((direntry, inode_data, driver))
+    }
+}
+
+impl FileSystem for Fatfs {
+    fn root(&self) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        let info = self.fat.get_info(ROOT_INODE)?;
+        self.make_entry(Filename::try_from("fatRoot").unwrap(), &info)
+    }
+
+    fn lookup_directory(
+        &mut self,
+        inode_nbr: u32,
+    ) -> SysResult<Vec<(DirectoryEntry, InodeData, Box<dyn Driver>)>> {
+        let mut entries = Vec::new();
+        for (name, info) in self.fat.lookup_directory(inode_nbr)? {
+            // A long name of 255 UTF-16 characters may not fit in NAME_MAX bytes
+            if let Ok(filename) = Filename::try_from(name.as_str()) {
+                entries.try_push(self.make_entry(filename, &info)?)?;
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Only the write permissions can be stored, in the read-only attribute
+    fn chmod(&mut self, inode_nbr: u32, mode: FileType) -> SysResult<()> {
+        let write = FileType::USER_WRITE_PERMISSION
+            | FileType::GROUP_WRITE_PERMISSION
+            | FileType::OTHER_WRITE_PERMISSION;
+        let info = self.fat.get_info(inode_nbr)?;
+        let current = self.mode(&info) & (FileType::SPECIAL_BITS | FileType::PERMISSIONS_MASK);
+
+        if (mode & !write) != (current & !write) && !self.quiet {
+            return Err(Errno::EPERM);
+        }
+        // The root directory has no attributes
+        if inode_nbr == ROOT_INODE {
+            return if (mode & write) == (current & write) || self.quiet {
+                Ok(())
+            } else {
+                Err(Errno::EPERM)
+            };
+        }
+        self.fat
+            .set_read_only(inode_nbr, (mode & write).is_empty())?;
+        Ok(())
+    }
+
+    fn chown(&mut self, _inode_nbr: u32, owner: uid_t, group: gid_t) -> SysResult<()> {
+        let same_owner = owner == uid_t::max_value() || owner == self.uid;
+        let same_group = group == gid_t::max_value() || group == self.gid;
+        if (same_owner && same_group) || self.quiet {
+            Ok(())
+        } else {
+            Err(Errno::EPERM)
+        }
+    }
+
+    fn unlink(
+        &mut self,
+        dir_inode_nbr: u32,
+        name: &str,
+        free_inode_data: bool,
+        _inode_nbr: u32,
+    ) -> SysResult<()> {
+        self.fat
+            .unlink(dir_inode_nbr, name, free_inode_data, current_time())
+    }
+
+    fn remove_inode(&mut self, inode_nbr: u32) -> SysResult<()> {
+        self.fat.remove_inode(inode_nbr)
+    }
+
+    fn truncate(&mut self, inode_nbr: u32, new_size: u64) -> SysResult<()> {
+        self.fat.truncate(inode_nbr, new_size, current_time())
+    }
+
+    /// FAT only stores regular files and directories
+    fn create(
+        &mut self,
+        filename: &str,
+        parent_inode_nbr: u32,
+        mode: FileType,
+        _owner: (uid_t, gid_t),
+    ) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        if mode.is_typed() && !mode.is_regular() {
+            return Err(Errno::EPERM);
+        }
+        let info = self
+            .fat
+            .create(filename, parent_inode_nbr, current_time())?;
+        self.make_entry(Filename::try_from(filename)?, &info)
+    }
+
+    fn write(
+        &mut self,
+        inode_number: u32,
+        offset: &mut u64,
+        buf: &[u8],
+    ) -> SysResult<(u32, InodeData)> {
+        let (count, info) = self.fat.write(inode_number, offset, buf, current_time())?;
+        Ok((count as u32, self.inode_data(&info)))
+    }
+
+    fn read(&mut self, inode_number: u32, offset: &mut u64, buf: &mut [u8]) -> SysResult<u32> {
+        Ok(self.fat.read(inode_number, offset, buf)? as u32)
+    }
+
+    fn create_dir(
+        &mut self,
+        parent_inode_nbr: u32,
+        filename: &str,
+        _mode: FileType,
+        _owner: (uid_t, gid_t),
+    ) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        let info = self
+            .fat
+            .create_dir(parent_inode_nbr, filename, current_time())?;
+        self.make_entry(Filename::try_from(filename)?, &info)
+    }
+
+    fn rmdir(&mut self, parent_inode_nbr: u32, filename: &str) -> SysResult<()> {
+        self.fat.rmdir(parent_inode_nbr, filename, current_time())
+    }
+
+    /// FAT has neither symbolic links nor hard links
+    fn symlink(
+        &mut self,
+        _parent_inode_nbr: u32,
+        _target: &str,
+        _filename: &str,
+    ) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        Err(Errno::EPERM)
+    }
+
+    fn link(
+        &mut self,
+        _parent_inode_nbr: u32,
+        _target_inode_nbr: u32,
+        _filename: &str,
+    ) -> SysResult<DirectoryEntry> {
+        Err(Errno::EPERM)
+    }
+
+    fn rename(
+        &mut self,
+        parent_inode_nbr: u32,
+        filename: &str,
+        new_parent_inode_nbr: u32,
+        new_filename: &str,
+    ) -> SysResult<()> {
+        self.fat.rename(
+            parent_inode_nbr,
+            filename,
+            new_parent_inode_nbr,
+            new_filename,
+            current_time(),
+        )
+    }
+
+    fn statfs(&self, buf: &mut statfs) -> SysResult<()> {
+        let cluster_size = self.fat.cluster_size();
+
+        Ok(*buf = statfs {
+            f_type: MSDOS_SUPER_MAGIC,
+            f_bsize: cluster_size as _,
+            f_blocks: self.fat.nbr_clusters() as _,
+            f_bfree: self.fat.free_clusters() as _,
+            f_bavail: self.fat.free_clusters() as _,
+            // FAT has no inode table
+            f_files: 0,
+            f_ffree: 0,
+            f_fsid: self.fs_id.0 as u32,
+            f_namelen: fat::MAX_NAME_LEN as _,
+            f_frsize: cluster_size as _,
+            f_flags: 0,
+        })
+    }
+
+    /// The root directory has no directory entry to store its times
+    fn utime(&mut self, inode_number: u32, times: Option<&utimbuf>) -> SysResult<()> {
+        if inode_number == ROOT_INODE {
+            return Ok(());
+        }
+        let (access_time, modification_time) = match times {
+            Some(times) => (times.actime as u32, times.modtime as u32),
+            None => (current_time(), current_time()),
+        };
+        self.fat.utime(inode_number, access_time, modification_time)
+    }
+}
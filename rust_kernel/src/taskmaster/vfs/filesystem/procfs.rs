@@ -69,6 +69,12 @@ pub use vmstat::VmstatDriver;
 mod mounts;
 pub use mounts::MountsDriver;
 
+mod slabinfo;
+pub use slabinfo::SlabinfoDriver;
+
+#[cfg(feature = "memory-debug")]
+mod kmemleak;
+
 mod comm;
 pub use comm::CommDriver;
 
@@ -224,6 +230,7 @@ impl ProcFs {
         let meminfo_filename = Filename::from_str_unwrap("meminfo");
         let vmstat_filename = Filename::from_str_unwrap("vmstat");
         let mounts_filename = Filename::from_str_unwrap("mounts");
+        let slabinfo_filename = Filename::from_str_unwrap("slabinfo");
         let owning = (0, 0);
 
         self.register_file(
@@ -297,6 +304,26 @@ impl ProcFs {
             owning,
         )?;
 
+        self.register_file(
+            root_dir_id,
+            slabinfo_filename,
+            Box::try_new(|inode_id| -> Result<Box<dyn Driver>, CollectionAllocErr> {
+                Ok(Box::try_new(slabinfo::SlabinfoDriver::new(inode_id))? as Box<dyn Driver>)
+            })?,
+            owning,
+        )?;
+
+        // The live allocations of the kernel, with the memory debugging mode
+        #[cfg(feature = "memory-debug")]
+        self.register_file(
+            root_dir_id,
+            Filename::from_str_unwrap("kmemleak"),
+            Box::try_new(|inode_id| -> Result<Box<dyn Driver>, CollectionAllocErr> {
+                Ok(Box::try_new(kmemleak::KmemleakDriver::new(inode_id))? as Box<dyn Driver>)
+            })?,
+            owning,
+        )?;
+
         // Inserting divers basic procfs files.
         Ok(())
     }
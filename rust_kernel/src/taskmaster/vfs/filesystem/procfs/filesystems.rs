@@ -1,11 +1,14 @@
+use super::super::FILESYSTEM_DRIVERS;
 use super::{Driver, FileOperation, InodeId, IpcResult, ProcFsOperations, SysResult, VFS};
 
+use alloc::borrow::Cow;
+use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 
-use alloc::borrow::Cow;
-use fallible_collections::FallibleArc;
+use fallible_collections::{FallibleArc, TryCollect};
 
-use libc_binding::OpenFlags;
+use libc_binding::{Errno, OpenFlags};
 use sync::DeadMutex;
 
 type Mutex<T> = DeadMutex<T>;
@@ -41,9 +44,6 @@ impl Driver for FilesystemsDriver {
     }
 }
 
-// Hardcoded because no time for this bullshit.
-const FILESYSTEMS: &str = "nodev procfs\n      ext2\n";
-
 impl FileOperation for FilesystemsOperations {
     fn get_inode_id(&self) -> SysResult<InodeId> {
         Ok(self.inode_id)
@@ -60,7 +60,25 @@ impl FileOperation for FilesystemsOperations {
 
 impl ProcFsOperations for FilesystemsOperations {
     fn get_seq_string(&self) -> SysResult<Cow<str>> {
-        Ok(Cow::from(FILESYSTEMS))
+        let filesystems_bytes: Vec<u8> = FILESYSTEM_DRIVERS
+            .iter()
+            .filter_map(|driver| {
+                let nodev = if driver.nodev { "nodev" } else { "" };
+                Some(
+                    tryformat!(32, "{}\t{}\n", nodev, driver.fs_type)
+                        .ok()?
+                        .into_bytes(),
+                )
+            })
+            .flatten()
+            .try_collect()?;
+
+        Ok(Cow::from(String::from_utf8(filesystems_bytes).map_err(
+            |_| {
+                log::error!("invalid utf8 in filesystems operation");
+                Errno::EINVAL
+            },
+        )?))
     }
     fn get_offset(&mut self) -> &mut usize {
         &mut self.offset
@@ -0,0 +1,73 @@
+use super::{Driver, FileOperation, InodeId, IpcResult, ProcFsOperations, SysResult, VFS};
+
+use crate::memory::leak_report;
+
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
+
+use fallible_collections::FallibleArc;
+
+use libc_binding::{Errno, OpenFlags};
+use sync::DeadMutex;
+
+type Mutex<T> = DeadMutex<T>;
+
+use libc_binding::{off_t, Whence};
+
+#[derive(Debug, Clone)]
+pub struct KmemleakDriver {
+    inode_id: InodeId,
+}
+
+impl KmemleakDriver {
+    pub fn new(inode_id: InodeId) -> Self {
+        Self { inode_id }
+    }
+}
+
+unsafe impl Send for KmemleakDriver {}
+
+#[derive(Debug, Default)]
+pub struct KmemleakOperations {
+    inode_id: InodeId,
+    offset: usize,
+}
+
+impl Driver for KmemleakDriver {
+    fn open(&mut self, _flags: OpenFlags) -> SysResult<IpcResult<Arc<Mutex<dyn FileOperation>>>> {
+        let res = Arc::try_new(Mutex::new(KmemleakOperations {
+            inode_id: self.inode_id,
+            offset: 0,
+        }))?;
+        Ok(IpcResult::Done(res))
+    }
+}
+
+impl FileOperation for KmemleakOperations {
+    fn get_inode_id(&self) -> SysResult<InodeId> {
+        Ok(self.inode_id)
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> SysResult<IpcResult<u32>> {
+        self.seq_read(buf)
+    }
+
+    fn lseek(&mut self, offset: off_t, whence: Whence) -> SysResult<off_t> {
+        self.proc_lseek(offset, whence)
+    }
+}
+
+impl ProcFsOperations for KmemleakOperations {
+    fn get_seq_string(&self) -> SysResult<Cow<str>> {
+        Ok(Cow::from(leak_report().ok_or(Errno::ENOMEM)?))
+    }
+    fn get_offset(&mut self) -> &mut usize {
+        &mut self.offset
+    }
+}
+
+impl Drop for KmemleakOperations {
+    fn drop(&mut self) {
+        VFS.lock().close_file_operation(self.inode_id);
+    }
+}
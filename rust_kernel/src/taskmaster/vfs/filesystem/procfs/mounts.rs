@@ -9,7 +9,7 @@ use alloc::vec::Vec;
 
 use fallible_collections::{FallibleArc, TryCollect};
 
-use libc_binding::{Errno, OpenFlags};
+use libc_binding::{Errno, MountFlags, OpenFlags};
 use sync::DeadMutex;
 
 type Mutex<T> = DeadMutex<T>;
@@ -71,12 +71,28 @@ impl ProcFsOperations for MountsOperations {
                      ref source,
                      ref target,
                      ref fs_type,
+                     flags,
                      ..
                  }| {
+                    let option = |flag, name| if flags.contains(flag) { name } else { "" };
                     Some(
-                        tryformat!(128, "{} {} {} rw 0 0\n", source, target, fs_type)
-                            .ok()?
-                            .into_bytes(),
+                        tryformat!(
+                            128,
+                            "{} {} {} {}{}{}{} 0 0\n",
+                            source,
+                            target,
+                            fs_type,
+                            if flags.contains(MountFlags::MS_RDONLY) {
+                                "ro"
+                            } else {
+                                "rw"
+                            },
+                            option(MountFlags::MS_NOSUID, ",nosuid"),
+                            option(MountFlags::MS_NODEV, ",nodev"),
+                            option(MountFlags::MS_NOEXEC, ",noexec")
+                        )
+                        .ok()?
+                        .into_bytes(),
                     )
                 },
             )
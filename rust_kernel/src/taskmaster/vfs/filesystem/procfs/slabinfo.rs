@@ -0,0 +1,117 @@
+use super::{Driver, FileOperation, InodeId, IpcResult, ProcFsOperations, SysResult, VFS};
+
+use crate::memory::slab_info;
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use fallible_collections::{FallibleArc, TryCollect};
+
+use libc_binding::{Errno, OpenFlags};
+use sync::DeadMutex;
+
+type Mutex<T> = DeadMutex<T>;
+
+use libc_binding::{off_t, Whence};
+
+#[derive(Debug, Clone)]
+pub struct SlabinfoDriver {
+    inode_id: InodeId,
+}
+
+impl SlabinfoDriver {
+    pub fn new(inode_id: InodeId) -> Self {
+        Self { inode_id }
+    }
+}
+
+unsafe impl Send for SlabinfoDriver {}
+
+#[derive(Debug, Default)]
+pub struct SlabinfoOperations {
+    inode_id: InodeId,
+    offset: usize,
+}
+
+impl Driver for SlabinfoDriver {
+    fn open(&mut self, _flags: OpenFlags) -> SysResult<IpcResult<Arc<Mutex<dyn FileOperation>>>> {
+        let res = Arc::try_new(Mutex::new(SlabinfoOperations {
+            inode_id: self.inode_id,
+            offset: 0,
+        }))?;
+        Ok(IpcResult::Done(res))
+    }
+}
+
+impl FileOperation for SlabinfoOperations {
+    fn get_inode_id(&self) -> SysResult<InodeId> {
+        Ok(self.inode_id)
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> SysResult<IpcResult<u32>> {
+        self.seq_read(buf)
+    }
+
+    fn lseek(&mut self, offset: off_t, whence: Whence) -> SysResult<off_t> {
+        self.proc_lseek(offset, whence)
+    }
+}
+
+const SLABINFO_HEADER: &str = "slabinfo - version: 2.1
+# name            <active_objs> <num_objs> <objsize> <objperslab> <pagesperslab> : tunables <limit> <batchcount> <sharedfactor> : slabdata <active_slabs> <num_slabs> <sharedavail>
+";
+
+impl ProcFsOperations for SlabinfoOperations {
+    fn get_seq_string(&self) -> SysResult<Cow<str>> {
+        // The statistics are copied first, as formatting them allocates in the caches
+        let caches = slab_info().ok_or(Errno::EINVAL)?;
+        let slabinfo_bytes: Vec<u8> = SLABINFO_HEADER
+            .bytes()
+            .chain(
+                caches
+                    .iter()
+                    .filter_map(|info| {
+                        Some(
+                            tryformat!(
+                                128,
+                                "{:<17} {:>6} {:>6} {:>6} {:>4} {:>4} : tunables {:>4} {:>4} {:>4} : slabdata {:>6} {:>6} {:>6}\n",
+                                info.name,
+                                info.active_objects,
+                                info.nbr_objects,
+                                info.object_size,
+                                info.objects_per_slab,
+                                info.pages_per_slab,
+                                0,
+                                0,
+                                0,
+                                info.active_slabs,
+                                info.nbr_slabs,
+                                0
+                            )
+                            .ok()?
+                            .into_bytes(),
+                        )
+                    })
+                    .flatten(),
+            )
+            .try_collect()?;
+
+        Ok(Cow::from(String::from_utf8(slabinfo_bytes).map_err(
+            |_| {
+                log::error!("invalid utf8 in slabinfo operation");
+                Errno::EINVAL
+            },
+        )?))
+    }
+    fn get_offset(&mut self) -> &mut usize {
+        &mut self.offset
+    }
+}
+
+impl Drop for SlabinfoOperations {
+    fn drop(&mut self) {
+        VFS.lock().close_file_operation(self.inode_id);
+    }
+}
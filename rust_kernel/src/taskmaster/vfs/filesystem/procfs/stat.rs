@@ -88,7 +88,29 @@ impl ProcFsOperations for StatOperations {
         let utime = thread_group.process_duration.user_time().as_secs(); // convert to clock tick count.
         let stime = thread_group.process_duration.system_time().as_secs();
 
-        let ctty = thread_group.controlling_terminal.unwrap_or(0 as dev_t);
+        // The device number of the controlling terminal and its foreground process group
+        let (tty_nr, tpgid) = match &thread_group.controlling_terminal {
+            Some(terminal) => {
+                let terminal = terminal.lock();
+                let tty_nr = terminal
+                    .get_inode_id()
+                    .and_then(|inode_id| {
+                        let mut vfs = VFS.lock();
+                        let inode = vfs.get_inode(inode_id)?;
+                        Ok(inode.major << 8 | inode.minor)
+                    })
+                    .unwrap_or(0 as dev_t);
+                (tty_nr, terminal.tcgetpgrp().unwrap_or(-1))
+            }
+            None => (0, -1),
+        };
+
+        // A zombie has no more threads, it keeps the default priority
+        let priority = thread_group
+            .get_all_thread()
+            .and_then(|threads| threads.values().next())
+            .map(|thread| thread.priority)
+            .unwrap_or_default();
 
         let stat_string = tryformat!(4096, "{} ({}) {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}\n", self.pid,
                                   // comm
@@ -100,11 +122,11 @@ impl ProcFsOperations for StatOperations {
                                   // pgrp
                                   thread_group.pgid,
                                   // session
-                                  1,
+                                  thread_group.sid,
                                   // tty_nr
-                                  4 << 8 | ctty, // TODO: make the major not hardcoded.
+                                  tty_nr,
                                   // tpgid
-                                  1,
+                                  tpgid,
                                   // flags
                                   1,
                                   // minflt
@@ -124,9 +146,9 @@ impl ProcFsOperations for StatOperations {
                                   // cstime
                                   42,
                                   // priority
-                                  1,
+                                  priority.proc_priority(),
                                   // nice
-                                  1,
+                                  priority.nice,
                                   // num_threads
                                   1,
                                   // itrealvalue
@@ -168,9 +190,9 @@ impl ProcFsOperations for StatOperations {
                                   // processor
                                   1,
                                   // rt_priority
-                                  1,
+                                  priority.rt_priority,
                                   // policy
-                                  1,
+                                  u32::from(priority.policy),
                                   // delayacct_blkio_ticks
                                   1,
                                   // guest_time
@@ -0,0 +1,646 @@
+//! The tmpfs is a filesystem whose files live in the kernel memory:
+//! their content is lost at unmount
+
+use super::super::inode::InodeNumber;
+use super::super::tools::KeyGenerator;
+use super::Credentials;
+use super::DefaultDriver;
+use super::FileOperation;
+use super::FileSystem;
+use super::IpcResult;
+use super::VFS;
+use super::{DirectoryEntry, Driver, FileSystemId, InodeData, Path};
+use super::{DirectoryEntryBuilder, Filename, InodeId, SysResult};
+use crate::memory::tools::PAGE_SIZE;
+use crate::taskmaster::kmodules::CURRENT_UNIX_TIME;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp::{max, min};
+use core::convert::TryFrom;
+use core::fmt::{self, Debug};
+use core::sync::atomic::Ordering;
+use fallible_collections::{btree::BTreeMap, try_vec, FallibleBox, FallibleVec, TryClone};
+use libc_binding::{
+    gid_t, nlink_t, statfs, time_t, uid_t, utimbuf, Errno, FileType, NAME_MAX, TMPFS_MAGIC,
+};
+
+pub mod file;
+pub use file::{TmpfsDriverFile, TmpfsFileOperation};
+
+pub const ROOT_ID: InodeNumber = 2;
+
+/// The size limit of the content of the files, when no size option is given
+const DEFAULT_SIZE: usize = 32 * 1024 * 1024;
+
+/// The size of a sector, in which the number of blocks of a file is counted
+const SECTOR_SIZE: usize = 512;
+
+/// A page of the content of a regular file
+struct DataPage(Vec<u8>);
+
+impl Debug for DataPage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DataPage")
+    }
+}
+
+#[derive(Debug)]
+struct TmpfsInode {
+    inode_data: InodeData,
+    /// The content of a regular file, page by page. The pages of the holes are not allocated
+    pages: Vec<Option<DataPage>>,
+    /// The target of a symbolic link
+    symlink: Option<Path>,
+}
+
+/// The files of the tmpfs are indexed by their parent directory inode number and their filename
+type TmpfsKey = (InodeNumber, Filename);
+
+#[derive(Debug)]
+pub struct Tmpfs {
+    fs_id: FileSystemId,
+    inodes: BTreeMap<InodeNumber, TmpfsInode>,
+    entries: BTreeMap<TmpfsKey, InodeNumber>,
+    /// The maximum number of data pages, all the files together
+    max_pages: usize,
+    /// The maximum number of inodes
+    max_inodes: usize,
+    /// The number of allocated data pages
+    nbr_pages: usize,
+}
+
+impl KeyGenerator<InodeNumber> for Tmpfs {
+    fn gen_filter(&self, id: InodeNumber) -> bool {
+        id > ROOT_ID && !self.inodes.contains_key(&id)
+    }
+}
+
+/// Returns the number of pages holding `size` bytes
+fn size_in_pages(size: usize) -> usize {
+    size / PAGE_SIZE + (size % PAGE_SIZE != 0) as usize
+}
+
+fn current_time() -> time_t {
+    unsafe { CURRENT_UNIX_TIME.load(Ordering::Relaxed) as time_t }
+}
+
+/// Parse a size option: a number of bytes, with an optional k, m or g suffix
+fn parse_size(value: &str) -> SysResult<usize> {
+    let (number, unit) = match value.bytes().last().ok_or(Errno::EINVAL)? {
+        b'k' | b'K' => (&value[..value.len() - 1], 1024),
+        b'm' | b'M' => (&value[..value.len() - 1], 1024 * 1024),
+        b'g' | b'G' => (&value[..value.len() - 1], 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+    number
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .ok_or(Errno::EINVAL)
+}
+
+impl Tmpfs {
+    /// Create an empty tmpfs. The comma separated mount `options` are:
+    /// size=N[k|m|g]: the size limit of the content of the files
+    /// nr_inodes=N: the maximum number of inodes
+    /// mode=OCTAL: the permissions of the root directory, 1777 by default
+    pub fn new(fs_id: FileSystemId, options: Option<&str>) -> SysResult<Self> {
+        let mut max_pages = DEFAULT_SIZE / PAGE_SIZE;
+        let mut max_inodes = None;
+        let mut mode = FileType::from_bits(0o1777).expect("bad tmpfs root mode");
+
+        for option in options.unwrap_or("").split(',').filter(|o| !o.is_empty()) {
+            let mut it = option.splitn(2, '=');
+            match (it.next(), it.next()) {
+                (Some("size"), Some(value)) => {
+                    max_pages = (parse_size(value)? + PAGE_SIZE - 1) / PAGE_SIZE
+                }
+                (Some("nr_inodes"), Some(value)) => {
+                    max_inodes = Some(value.parse::<usize>().map_err(|_| Errno::EINVAL)?)
+                }
+                (Some("mode"), Some(value)) => {
+                    mode = u16::from_str_radix(value, 8)
+                        .ok()
+                        .and_then(FileType::from_bits)
+                        .filter(|mode| !mode.is_typed())
+                        .ok_or(Errno::EINVAL)?
+                }
+                _ => return Err(Errno::EINVAL),
+            }
+        }
+
+        let mut tmpfs = Self {
+            fs_id,
+            inodes: BTreeMap::new(),
+            entries: BTreeMap::new(),
+            max_pages,
+            // As many inodes as data pages by default, like on Linux
+            max_inodes: max_inodes.unwrap_or(max_pages),
+            nbr_pages: 0,
+        };
+        tmpfs.add_inode(ROOT_ID, FileType::DIRECTORY | mode, (0, 0), 2)?;
+        Ok(tmpfs)
+    }
+
+    fn add_inode(
+        &mut self,
+        inode_nbr: InodeNumber,
+        access_mode: FileType,
+        (owner, group): (uid_t, gid_t),
+        link_number: nlink_t,
+    ) -> SysResult<()> {
+        if self.inodes.len() >= self.max_inodes {
+            return Err(Errno::ENOSPC);
+        }
+        let mut inode_data = InodeData {
+            id: InodeId::new(inode_nbr, Some(self.fs_id)),
+            major: 0,
+            minor: 0,
+            link_number,
+            access_mode,
+
+            uid: owner,
+            gid: group,
+
+            atime: 0,
+            mtime: 0,
+            ctime: 0,
+
+            size: if access_mode.is_directory() {
+                PAGE_SIZE as u64
+            } else {
+                0
+            },
+            nbr_disk_sectors: 0,
+        };
+        inode_data.set_alltime(current_time());
+        self.inodes.try_insert(
+            inode_nbr,
+            TmpfsInode {
+                inode_data,
+                pages: Vec::new(),
+                symlink: None,
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Create a new inode and link it in the directory `parent` under `filename`
+    fn create_inode(
+        &mut self,
+        parent: InodeNumber,
+        filename: &str,
+        access_mode: FileType,
+        owner: (uid_t, gid_t),
+        link_number: nlink_t,
+    ) -> SysResult<(Filename, InodeNumber)> {
+        let filename = Filename::try_from(filename)?;
+        if self.entries.contains_key(&(parent, filename)) {
+            return Err(Errno::EEXIST);
+        }
+        let inode_nbr = self.gen();
+        self.add_inode(inode_nbr, access_mode, owner, link_number)?;
+        if let Err(e) = self.entries.try_insert((parent, filename), inode_nbr) {
+            self.inodes.remove(&inode_nbr);
+            return Err(e.into());
+        }
+        self.touch_directory(parent);
+        Ok((filename, inode_nbr))
+    }
+
+    /// Get the inode number of the file `filename` of the directory `parent`
+    pub fn lookup(&self, parent: InodeNumber, filename: &str) -> SysResult<InodeNumber> {
+        self.entries
+            .get(&(parent, Filename::try_from(filename)?))
+            .cloned()
+            .ok_or(Errno::ENOENT)
+    }
+
+    fn get_inode(&self, inode_nbr: InodeNumber) -> SysResult<&TmpfsInode> {
+        self.inodes.get(&inode_nbr).ok_or(Errno::ENOENT)
+    }
+
+    fn get_inode_mut(&mut self, inode_nbr: InodeNumber) -> SysResult<&mut TmpfsInode> {
+        self.inodes.get_mut(&inode_nbr).ok_or(Errno::ENOENT)
+    }
+
+    /// Returns the number of pages of a file of `size` bytes, EFBIG
+    /// when it is bigger than the filesystem
+    fn nbr_pages_of(&self, size: u64) -> SysResult<usize> {
+        let size = usize::try_from(size).map_err(|_| Errno::EFBIG)?;
+        let nbr_pages = size_in_pages(size);
+        if nbr_pages > self.max_pages {
+            return Err(Errno::EFBIG);
+        }
+        Ok(nbr_pages)
+    }
+
+    /// The content of the directory `inode_nbr` changed
+    fn touch_directory(&mut self, inode_nbr: InodeNumber) {
+        if let Some(directory) = self.inodes.get_mut(&inode_nbr) {
+            directory.inode_data.mtime = current_time();
+            directory.inode_data.ctime = directory.inode_data.mtime;
+        }
+    }
+
+    /// Add `diff` to the link number of the inode `inode_nbr`
+    fn add_links(&mut self, inode_nbr: InodeNumber, diff: i32) {
+        if let Some(inode) = self.inodes.get_mut(&inode_nbr) {
+            inode.inode_data.link_number = (inode.inode_data.link_number as i32 + diff) as nlink_t;
+            inode.inode_data.ctime = current_time();
+        }
+    }
+
+    /// Free the inode `inode_nbr` with its data pages
+    fn free_inode(&mut self, inode_nbr: InodeNumber) -> SysResult<()> {
+        let inode = self.inodes.remove(&inode_nbr).ok_or(Errno::ENOENT)?;
+        self.nbr_pages -= inode.pages.iter().filter(|page| page.is_some()).count();
+        Ok(())
+    }
+
+    /// Build the directory entry, the inode data and the driver of the file `filename`
+    fn make_entry(
+        &self,
+        filename: Filename,
+        inode_nbr: InodeNumber,
+    ) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        let inode = self.get_inode(inode_nbr)?;
+        let inode_data = inode.inode_data;
+        let inode_id = inode_data.id;
+
+        let direntry = {
+            let mut builder = DirectoryEntryBuilder::new();
+            builder.set_filename(filename).set_inode_id(inode_id);
+            if inode_data.is_directory() {
+                builder.set_directory();
+            } else if inode_data.is_fifo() {
+                builder.set_fifo();
+            } else if inode_data.is_socket() {
+                builder.set_socket();
+            } else if let Some(target) = &inode.symlink {
+                builder.set_symlink(target.try_clone()?);
+            } else {
+                builder.set_regular();
+            }
+            builder.build()
+        };
+        let driver: Box<dyn Driver> = if inode_data.is_regular() {
+            Box::try_new(TmpfsDriverFile::new(inode_id))?
+        } else {
+            Box::try_new(DefaultDriver)?
+        };
+        Ok((direntry, inode_data, driver))
+    }
+}
+
+impl TmpfsInode {
+    fn update_nbr_disk_sectors(&mut self) {
+        let nbr_pages = self.pages.iter().filter(|page| page.is_some()).count();
+        self.inode_data.nbr_disk_sectors = (nbr_pages * PAGE_SIZE / SECTOR_SIZE) as _;
+    }
+}
+
+impl FileSystem for Tmpfs {
+    fn root(&self) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        self.make_entry(Filename::try_from("tmpfsroot").unwrap(), ROOT_ID)
+    }
+
+    fn lookup_directory(
+        &mut self,
+        inode_nbr: u32,
+    ) -> SysResult<Vec<(DirectoryEntry, InodeData, Box<dyn Driver>)>> {
+        let mut entries = Vec::new();
+        for (&(_, filename), &child) in self
+            .entries
+            .iter()
+            .filter(|((parent, _), _)| *parent == inode_nbr)
+        {
+            entries.try_push(self.make_entry(filename, child)?)?;
+        }
+        Ok(entries)
+    }
+
+    fn chmod(&mut self, inode_nbr: u32, mode: FileType) -> SysResult<()> {
+        let mask = FileType::SPECIAL_BITS | FileType::PERMISSIONS_MASK;
+        let inode_data = &mut self.get_inode_mut(inode_nbr)?.inode_data;
+
+        inode_data.access_mode.remove(mask);
+        inode_data.access_mode.insert(mode & mask);
+        inode_data.ctime = current_time();
+        Ok(())
+    }
+
+    fn chown(&mut self, inode_nbr: u32, owner: uid_t, group: gid_t) -> SysResult<()> {
+        let inode_data = &mut self.get_inode_mut(inode_nbr)?.inode_data;
+
+        if owner != uid_t::max_value() {
+            inode_data.uid = owner;
+        }
+        if group != gid_t::max_value() {
+            inode_data.gid = group;
+        }
+        inode_data.ctime = current_time();
+        Ok(())
+    }
+
+    fn unlink(
+        &mut self,
+        dir_inode_nbr: u32,
+        name: &str,
+        free_inode_data: bool,
+        inode_nbr: u32,
+    ) -> SysResult<()> {
+        self.entries
+            .remove(&(dir_inode_nbr, Filename::try_from(name)?))
+            .ok_or(Errno::ENOENT)?;
+        self.touch_directory(dir_inode_nbr);
+        self.add_links(inode_nbr, -1);
+        // Else the inode is freed by remove_inode() when its last file operation is closed
+        if free_inode_data {
+            self.free_inode(inode_nbr)?;
+        }
+        Ok(())
+    }
+
+    fn remove_inode(&mut self, inode_nbr: u32) -> SysResult<()> {
+        self.free_inode(inode_nbr)
+    }
+
+    fn truncate(&mut self, inode_nbr: u32, new_size: u64) -> SysResult<()> {
+        let nbr_pages = self.nbr_pages_of(new_size)?;
+        let inode = self.get_inode_mut(inode_nbr)?;
+        if inode.inode_data.is_directory() {
+            return Err(Errno::EISDIR);
+        }
+        let new_size = new_size as usize;
+        let mut freed = 0;
+
+        if nbr_pages < inode.pages.len() {
+            freed = inode.pages[nbr_pages..]
+                .iter()
+                .filter(|page| page.is_some())
+                .count();
+            inode.pages.truncate(nbr_pages);
+        }
+        // The end of the last page must read as zeros if the file grows again
+        if new_size % PAGE_SIZE != 0 {
+            if let Some(Some(DataPage(page))) = inode.pages.get_mut(nbr_pages - 1) {
+                for byte in &mut page[new_size % PAGE_SIZE..] {
+                    *byte = 0;
+                }
+            }
+        }
+        inode.inode_data.size = new_size as u64;
+        inode.inode_data.mtime = current_time();
+        inode.inode_data.ctime = inode.inode_data.mtime;
+        inode.update_nbr_disk_sectors();
+        self.nbr_pages -= freed;
+        Ok(())
+    }
+
+    fn create(
+        &mut self,
+        filename: &str,
+        parent_inode_nbr: u32,
+        mode: FileType,
+        owner: (uid_t, gid_t),
+    ) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        let (filename, inode_nbr) =
+            self.create_inode(parent_inode_nbr, filename, mode, owner, 1)?;
+        self.make_entry(filename, inode_nbr)
+    }
+
+    fn write(
+        &mut self,
+        inode_number: u32,
+        offset: &mut u64,
+        buf: &[u8],
+    ) -> SysResult<(u32, InodeData)> {
+        let free_pages = self.max_pages - self.nbr_pages;
+        let max_size = self.max_pages.saturating_mul(PAGE_SIZE);
+        let inode = self.get_inode_mut(inode_number)?;
+        if !inode.inode_data.is_regular() {
+            return Err(Errno::EINVAL);
+        }
+        let start = usize::try_from(*offset).map_err(|_| Errno::EFBIG)?;
+        let end = start.checked_add(buf.len()).ok_or(Errno::EFBIG)?;
+        // The file cannot be bigger than the filesystem
+        let end = min(end, max_size);
+        if start >= end && !buf.is_empty() {
+            return Err(Errno::ENOSPC);
+        }
+        let nbr_pages = size_in_pages(end);
+
+        if inode.pages.len() < nbr_pages {
+            inode.pages.try_reserve(nbr_pages - inode.pages.len())?;
+            while inode.pages.len() < nbr_pages {
+                inode.pages.push(None);
+            }
+        }
+
+        let mut written = 0;
+        let mut allocated = 0;
+        while start + written < end {
+            let page_offset = (start + written) % PAGE_SIZE;
+            let len = min(PAGE_SIZE - page_offset, end - start - written);
+            let page = &mut inode.pages[(start + written) / PAGE_SIZE];
+            if page.is_none() {
+                if allocated == free_pages {
+                    break;
+                }
+                *page = match try_vec![0; PAGE_SIZE] {
+                    Ok(data) => Some(DataPage(data)),
+                    Err(_) => break,
+                };
+                allocated += 1;
+            }
+            if let Some(DataPage(data)) = page {
+                data[page_offset..page_offset + len].copy_from_slice(&buf[written..written + len]);
+            }
+            written += len;
+        }
+        // Do not keep the page slots which could not be filled
+        let end = max(start + written, inode.inode_data.size as usize);
+        inode.pages.truncate(size_in_pages(end));
+
+        if written == 0 && !buf.is_empty() {
+            return Err(Errno::ENOSPC);
+        }
+        *offset += written as u64;
+        inode.inode_data.size = max(inode.inode_data.size, *offset);
+        inode.inode_data.mtime = current_time();
+        inode.inode_data.ctime = inode.inode_data.mtime;
+        inode.update_nbr_disk_sectors();
+        let inode_data = inode.inode_data;
+        self.nbr_pages += allocated;
+        Ok((written as u32, inode_data))
+    }
+
+    fn read(&mut self, inode_number: u32, offset: &mut u64, buf: &mut [u8]) -> SysResult<u32> {
+        let inode = self.get_inode_mut(inode_number)?;
+        if *offset >= inode.inode_data.size {
+            return Ok(0);
+        }
+        let start = *offset as usize;
+        let end = min(inode.inode_data.size as usize, start + buf.len());
+
+        let mut read = 0;
+        while start + read < end {
+            let page_offset = (start + read) % PAGE_SIZE;
+            let len = min(PAGE_SIZE - page_offset, end - start - read);
+            let dest = &mut buf[read..read + len];
+            match inode.pages.get((start + read) / PAGE_SIZE) {
+                Some(Some(DataPage(data))) => {
+                    dest.copy_from_slice(&data[page_offset..page_offset + len])
+                }
+                // A hole reads as zeros
+                _ => {
+                    for byte in dest {
+                        *byte = 0;
+                    }
+                }
+            }
+            read += len;
+        }
+        *offset += read as u64;
+        inode.inode_data.atime = current_time();
+        Ok(read as u32)
+    }
+
+    fn create_dir(
+        &mut self,
+        parent_inode_nbr: u32,
+        filename: &str,
+        mode: FileType,
+        owner: (uid_t, gid_t),
+    ) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        let (filename, inode_nbr) = self.create_inode(
+            parent_inode_nbr,
+            filename,
+            FileType::DIRECTORY | mode,
+            owner,
+            2,
+        )?;
+        // The '..' entry of the new directory
+        self.add_links(parent_inode_nbr, 1);
+        self.make_entry(filename, inode_nbr)
+    }
+
+    fn rmdir(&mut self, parent_inode_nbr: u32, filename: &str) -> SysResult<()> {
+        let key = (parent_inode_nbr, Filename::try_from(filename)?);
+        let inode_nbr = *self.entries.get(&key).ok_or(Errno::ENOENT)?;
+
+        if !self.get_inode(inode_nbr)?.inode_data.is_directory() {
+            return Err(Errno::ENOTDIR);
+        }
+        if self.entries.keys().any(|(parent, _)| *parent == inode_nbr) {
+            return Err(Errno::ENOTEMPTY);
+        }
+        self.entries.remove(&key);
+        self.free_inode(inode_nbr)?;
+        self.add_links(parent_inode_nbr, -1);
+        self.touch_directory(parent_inode_nbr);
+        Ok(())
+    }
+
+    fn symlink(
+        &mut self,
+        parent_inode_nbr: u32,
+        target: &str,
+        filename: &str,
+    ) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        let target_path = Path::try_from(target)?;
+        let (filename, inode_nbr) = self.create_inode(
+            parent_inode_nbr,
+            filename,
+            FileType::SYMBOLIC_LINK | FileType::from_bits(0o777).unwrap(),
+            (0, 0),
+            1,
+        )?;
+        let inode = self.get_inode_mut(inode_nbr)?;
+        inode.inode_data.size = target.len() as u64;
+        inode.symlink = Some(target_path);
+        self.make_entry(filename, inode_nbr)
+    }
+
+    fn link(
+        &mut self,
+        parent_inode_nbr: u32,
+        target_inode_nbr: u32,
+        filename: &str,
+    ) -> SysResult<DirectoryEntry> {
+        let filename = Filename::try_from(filename)?;
+        if self.entries.contains_key(&(parent_inode_nbr, filename)) {
+            return Err(Errno::EEXIST);
+        }
+        self.get_inode(target_inode_nbr)?;
+        self.entries
+            .try_insert((parent_inode_nbr, filename), target_inode_nbr)?;
+        self.add_links(target_inode_nbr, 1);
+        self.touch_directory(parent_inode_nbr);
+        Ok(self.make_entry(filename, target_inode_nbr)?.0)
+    }
+
+    fn rename(
+        &mut self,
+        parent_inode_nbr: u32,
+        filename: &str,
+        new_parent_inode_nbr: u32,
+        new_filename: &str,
+    ) -> SysResult<()> {
+        let key = (parent_inode_nbr, Filename::try_from(filename)?);
+        let new_key = (new_parent_inode_nbr, Filename::try_from(new_filename)?);
+        let inode_nbr = *self.entries.get(&key).ok_or(Errno::ENOENT)?;
+
+        // The VFS has already removed the file which was at the new path
+        if self.entries.contains_key(&new_key) {
+            return Err(Errno::EEXIST);
+        }
+        self.entries.try_insert(new_key, inode_nbr)?;
+        self.entries.remove(&key);
+
+        if self.get_inode(inode_nbr)?.inode_data.is_directory()
+            && parent_inode_nbr != new_parent_inode_nbr
+        {
+            self.add_links(parent_inode_nbr, -1);
+            self.add_links(new_parent_inode_nbr, 1);
+        }
+        self.touch_directory(parent_inode_nbr);
+        self.touch_directory(new_parent_inode_nbr);
+        Ok(())
+    }
+
+    fn statfs(&self, buf: &mut statfs) -> SysResult<()> {
+        Ok(*buf = statfs {
+            f_type: TMPFS_MAGIC,
+            f_bsize: PAGE_SIZE as _,
+            f_blocks: self.max_pages as _,
+            f_bfree: (self.max_pages - self.nbr_pages) as _,
+            f_bavail: (self.max_pages - self.nbr_pages) as _,
+            f_files: self.max_inodes as _,
+            f_ffree: (self.max_inodes - self.inodes.len()) as _,
+            f_fsid: self.fs_id.0 as u32,
+            f_namelen: NAME_MAX - 1,
+            f_frsize: PAGE_SIZE as _,
+            f_flags: 0,
+        })
+    }
+
+    fn utime(&mut self, inode_number: u32, times: Option<&utimbuf>) -> SysResult<()> {
+        let inode_data = &mut self.get_inode_mut(inode_number)?.inode_data;
+
+        match times {
+            Some(times) => {
+                inode_data.atime = times.actime;
+                inode_data.mtime = times.modtime;
+            }
+            None => {
+                inode_data.atime = current_time();
+                inode_data.mtime = inode_data.atime;
+            }
+        }
+        inode_data.ctime = current_time();
+        Ok(())
+    }
+}
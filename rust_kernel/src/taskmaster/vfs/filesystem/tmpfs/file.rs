@@ -0,0 +1,126 @@
+use super::{Credentials, Driver, FileOperation, IpcResult, SysResult};
+use super::{FileSystemId, InodeId, VFS};
+use alloc::sync::Arc;
+use libc_binding::{gid_t, off_t, statfs, uid_t, Errno, FileType, OpenFlags, Whence};
+use sync::DeadMutex;
+
+/// a driver of a tmpfs regular file
+#[derive(Debug)]
+pub struct TmpfsDriverFile {
+    inode_id: InodeId,
+}
+
+impl TmpfsDriverFile {
+    pub fn new(inode_id: InodeId) -> Self {
+        Self { inode_id }
+    }
+}
+
+impl Driver for TmpfsDriverFile {
+    fn open(
+        &mut self,
+        _flags: OpenFlags,
+    ) -> SysResult<IpcResult<Arc<DeadMutex<dyn FileOperation>>>> {
+        Ok(IpcResult::Done(Arc::new(DeadMutex::new(
+            TmpfsFileOperation::new(self.inode_id),
+        ))))
+    }
+}
+
+/// a file operation of a tmpfs regular file
+#[derive(Debug)]
+pub struct TmpfsFileOperation {
+    inode_id: InodeId,
+    offset: u64,
+    /// the mount through which the file was opened
+    mount: Option<FileSystemId>,
+}
+
+impl TmpfsFileOperation {
+    fn new(inode_id: InodeId) -> Self {
+        Self {
+            inode_id,
+            offset: 0,
+            mount: None,
+        }
+    }
+}
+
+impl FileOperation for TmpfsFileOperation {
+    fn set_file_offset(&mut self, offset: u64) {
+        self.offset = offset;
+    }
+
+    fn set_mount(&mut self, mount: Option<FileSystemId>) {
+        self.mount = mount;
+    }
+
+    fn get_inode_id(&self) -> SysResult<InodeId> {
+        Ok(self.inode_id)
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> SysResult<IpcResult<u32>> {
+        let res = VFS
+            .lock()
+            .get_inode(self.inode_id)
+            .expect("no such inode")
+            .read(&mut self.offset, buf)? as u32;
+        Ok(IpcResult::Done(res))
+    }
+
+    fn fstatfs(&mut self, buf: &mut statfs) -> SysResult<u32> {
+        VFS.lock().fstatfs(self.inode_id, buf)?;
+        Ok(0)
+    }
+
+    /// The file lives in memory: there is nothing to write back
+    fn fsync(&mut self) -> SysResult<u32> {
+        Ok(0)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> SysResult<IpcResult<u32>> {
+        let res = VFS
+            .lock()
+            .get_inode(self.inode_id)
+            .expect("no such inode")
+            .write(&mut self.offset, buf)? as u32;
+        Ok(IpcResult::Done(res))
+    }
+
+    fn lseek(&mut self, offset: off_t, whence: Whence) -> SysResult<off_t> {
+        if offset == core::i64::MIN {
+            // volontary trash i64 min value to avoid -offset ==
+            // offset
+            return Err(Errno::EINVAL);
+        }
+        let base = match whence {
+            Whence::SeekCur => self.offset,
+            Whence::SeekSet => 0,
+            Whence::SeekEnd => VFS.lock().get_inode(self.inode_id)?.size,
+        };
+        let new_offset = if offset < 0 {
+            base.checked_sub((-offset) as u64).ok_or(Errno::EINVAL)?
+        } else {
+            base.checked_add(offset as u64).ok_or(Errno::EINVAL)?
+        };
+        self.offset = new_offset;
+        Ok(self.offset as off_t)
+    }
+
+    fn fchmod(&mut self, creds: &Credentials, mode: FileType) -> SysResult<u32> {
+        VFS.lock().fchmod(creds, self.inode_id, self.mount, mode)?;
+        Ok(0)
+    }
+
+    fn fchown(&mut self, creds: &Credentials, owner: uid_t, group: gid_t) -> SysResult<u32> {
+        VFS.lock()
+            .fchown(creds, self.inode_id, self.mount, owner, group)?;
+        Ok(0)
+    }
+}
+
+impl Drop for TmpfsFileOperation {
+    fn drop(&mut self) {
+        VFS.lock().close_file_operation(self.inode_id);
+    }
+}
@@ -1,8 +1,8 @@
 use super::filesystem::devfs::{
-    BiosInt13hInstance, DiskDriver, DiskWrapper, FbDevice, IdeAtaInstance, NullDevice,
-    RandomDevice, ZeroDevice,
+    pty, serial, BiosInt13hInstance, DiskDriver, DiskWrapper, FbDevice, IdeAtaInstance, NullDevice,
+    PtmxDevice, PtsDevice, RandomDevice, SataInstance, SerialDevice, ZeroDevice, ROOT_ID,
 };
-use super::filesystem::{Devfs, Ext2fs, FileSystemSource, FileSystemType};
+use super::filesystem::{Devfs, Ext2fs, FileSystemSource, FileSystemType, Tmpfs};
 use super::SmartMutex;
 use crate::taskmaster::drivers::Driver;
 use alloc::format;
@@ -14,11 +14,15 @@ use libc_binding::{dev_t, OpenFlags};
 use sync::DeadMutex;
 
 use super::filesystem::procfs::ProcFs;
+use super::filesystem::tmpfs::ROOT_ID as TMPFS_ROOT_ID;
 use super::*;
-use crate::drivers::storage::{BlockIo, DiskDriverType, NbrSectors, Sector};
+use crate::drivers::storage::{
+    root_disk_driver_type, BlockIo, CachedDisk, DiskDriverType, NbrSectors, Sector, SECTOR_SIZE,
+};
+use crate::initramfs::{self, ArchiveEntry, EntryKind};
 use alloc::boxed::Box;
 use ext2::Ext2Filesystem;
-use mbr::Mbr;
+use mbr::{MbrError, MbrResult, PartitionEntry, PartitionTable, SectorRead};
 
 lazy_static! {
     pub static ref VFS: SmartMutex<Vfs> = SmartMutex::new(init());
@@ -31,10 +35,35 @@ pub fn init() -> Vfs {
     let fs_id = FileSystemId(2);
     let mut devfs = Devfs::new(fs_id);
 
-    init_ext2(&mut vfs, &mut devfs, DiskDriverType::Ide);
+    let initramfs = if initramfs::is_present() {
+        match init_initramfs(&mut vfs) {
+            Ok(()) => true,
+            Err(e) => {
+                log::error!("Cannot unpack the initramfs, the root is the disk: {:?}", e);
+                initramfs::discard();
+                false
+            }
+        }
+    } else {
+        false
+    };
+    if initramfs {
+        // The filesystems of the disk can be mounted later, before a pivot_root
+        match new_disk_drivers(root_disk_driver_type()) {
+            Ok((sda_driver, partition_drivers)) => {
+                init_sda(&mut devfs, sda_driver, partition_drivers)
+            }
+            Err(e) => log::warn!("No disk available: {:?}", e),
+        }
+    } else {
+        init_ext2(&mut vfs, &mut devfs, root_disk_driver_type());
+    }
     init_procfs(&mut vfs).expect("Failed to init /proc (procfs)");
+    init_tmpfs(&mut vfs).expect("Failed to init /tmp (tmpfs)");
     // then init tty on /dev/tty
     init_tty(&mut devfs);
+    init_pty(&mut devfs);
+    init_serial(&mut devfs);
     mount_devfs(&mut vfs, devfs, fs_id);
     vfs
 }
@@ -86,18 +115,33 @@ fn mount_devfs(vfs: &mut Vfs, mut devfs: Devfs, fs_id: FileSystemId) {
         )
         .expect("failed to add new driver sda to devfs");
 
-    let dev_id = vfs
-        .pathname_resolution(&Path::root(), &root_creds, &Path::try_from("/dev").unwrap())
-        .unwrap();
+    let dev_path = Path::try_from("/dev").expect("/dev path creation failed");
+    let dev_id = match vfs.pathname_resolution(&Path::root(), &root_creds, &dev_path) {
+        Err(Errno::ENOENT) => {
+            let dev_dir_perms =
+                FileType::from_bits(0o755).expect("file permission creation failed");
+            vfs.mkdir(
+                &Path::root(),
+                &root_creds,
+                dev_path.try_clone().unwrap(),
+                dev_dir_perms,
+            )
+            .expect("/dev creation failed");
+            vfs.pathname_resolution(&Path::root(), &root_creds, &dev_path)
+                .unwrap()
+        }
+        ret => ret.unwrap(),
+    };
     vfs.mount_filesystem(
         MountedFileSystem {
-            source: FileSystemSource::Devfs,
+            source: FileSystemSource::Nodev(FileSystemType::Devfs),
             fs_type: FileSystemType::Devfs,
             target: Path::try_from("/dev").expect("/dev path creation failed"),
             fs: Arc::try_new(DeadMutex::new(devfs)).expect("arc new devfs failed"),
+            flags: MountFlags::MS_NOSUID,
+            mount_dir: dev_id,
         },
         fs_id,
-        dev_id,
     )
     .expect("mounting /dev failed");
 }
@@ -108,9 +152,19 @@ fn init_ext2(vfs: &mut Vfs, devfs: &mut Devfs, driver_type: DiskDriverType) {
     let (sda_driver, mut partition_drivers) =
         new_disk_drivers(driver_type).expect("initialisation of disk drivers failed");
 
-    let file_operation = partition_drivers[0]
+    // The root filesystem is on the first Linux partition, or on the first partition if none is tagged as such
+    let (root_partition, root_driver) = {
+        let index = partition_drivers
+            .iter()
+            .position(|(part, _)| part.is_linux_filesystem())
+            .unwrap_or(0);
+        partition_drivers
+            .get_mut(index)
+            .expect("no partition found for the root filesystem")
+    };
+    let file_operation = root_driver
         .open(OpenFlags::O_RDWR)
-        .expect("open sda1 failed")
+        .expect("open root partition failed")
         .expect("disk driver open failed");
 
     let ext2_disk = DiskWrapper(file_operation);
@@ -120,26 +174,163 @@ fn init_ext2(vfs: &mut Vfs, devfs: &mut Devfs, driver_type: DiskDriverType) {
     vfs.mount_filesystem(
         MountedFileSystem {
             source: FileSystemSource::File {
-                source_path: Path::try_from("/dev/sda1").expect("enomem to create path /dev/sda1"),
+                source_path: Path::try_from(format!("/dev/sda{}", root_partition.number).as_ref())
+                    .expect("enomem to create path /dev/sdaN"),
             },
             fs_type: FileSystemType::Ext2,
             target: Path::try_from("/").expect("enomem to create path /"),
             fs: Arc::try_new(DeadMutex::new(ext2fs)).expect("arc new ext2fs failed"),
+            flags: MountFlags::empty(),
+            mount_dir: DirectoryEntryId::new(2),
         },
         fs_id,
-        DirectoryEntryId::new(2),
     )
     .expect("mount filesystem failed");
 
     init_sda(devfs, sda_driver, partition_drivers);
 }
 
-/// mount /dev/sda1 on the vfs, WARNING: must be call after ext2 is
+/// The free space of the tmpfs of the initramfs, in addition to the size of the archive
+const INITRAMFS_FREE_SPACE: usize = 32 * 1024 * 1024;
+
+/// unpack the initramfs in a tmpfs mounted on root, instead of the ext2 of the disk
+fn init_initramfs(vfs: &mut Vfs) -> SysResult<()> {
+    let fs_id = FileSystemId(0);
+    let options = tryformat!(
+        64,
+        "mode=755,size={}",
+        initramfs::size() + INITRAMFS_FREE_SPACE
+    );
+
+    // The memory of the archive is given back whatever happens
+    let tmpfs = initramfs::unpack(|archive| -> SysResult<Tmpfs> {
+        let mut tmpfs = Tmpfs::new(fs_id, Some(&options?))?;
+        for entry in initramfs::entries(archive)?.iter() {
+            if let Err(e) = unpack_entry(&mut tmpfs, entry) {
+                log::warn!("initramfs: cannot unpack {}: {:?}", entry.path, e);
+            }
+        }
+        Ok(tmpfs)
+    })??;
+    log::info!("initramfs unpacked");
+
+    vfs.mount_filesystem(
+        MountedFileSystem {
+            source: FileSystemSource::Nodev(FileSystemType::Tmpfs),
+            fs_type: FileSystemType::Tmpfs,
+            target: Path::try_from("/")?,
+            fs: Arc::try_new(DeadMutex::new(tmpfs))?,
+            flags: MountFlags::empty(),
+            mount_dir: DirectoryEntryId::new(2),
+        },
+        fs_id,
+    )
+}
+
+/// get the directory `path` of the tmpfs, the missing directories are created
+fn initramfs_directory(tmpfs: &mut Tmpfs, path: &str) -> SysResult<u32> {
+    let mut inode_nbr = TMPFS_ROOT_ID;
+    for component in path.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        if component == ".." {
+            return Err(Errno::EINVAL);
+        }
+        inode_nbr = match tmpfs.lookup(inode_nbr, component) {
+            Ok(inode_nbr) => inode_nbr,
+            // The parent directories may be later in the archive, or not in it at all
+            Err(Errno::ENOENT) => {
+                let mode = FileType::from_bits(0o755).expect("file permission creation failed");
+                let (_, inode_data, _) = tmpfs.create_dir(inode_nbr, component, mode, (0, 0))?;
+                inode_data.id.inode_number
+            }
+            Err(e) => return Err(e),
+        };
+    }
+    Ok(inode_nbr)
+}
+
+/// create a file of the initramfs in its tmpfs
+fn unpack_entry(tmpfs: &mut Tmpfs, entry: &ArchiveEntry) -> SysResult<()> {
+    fn split(path: &str) -> (&str, &str) {
+        match path.rfind('/') {
+            Some(index) => (&path[..index], &path[index + 1..]),
+            None => ("", path),
+        }
+    }
+    let (dirname, filename) = split(&entry.path);
+    let parent = initramfs_directory(tmpfs, dirname)?;
+    let owner = (entry.uid, entry.gid);
+
+    let inode_nbr = if filename.is_empty() {
+        // the root directory itself
+        TMPFS_ROOT_ID
+    } else {
+        match (tmpfs.lookup(parent, filename), entry.kind) {
+            // a directory created before its entry
+            (Ok(inode_nbr), EntryKind::Directory) => inode_nbr,
+            (Ok(_), _) => return Err(Errno::EEXIST),
+            (Err(_), EntryKind::Directory) => {
+                tmpfs
+                    .create_dir(parent, filename, entry.mode, owner)?
+                    .1
+                    .id
+                    .inode_number
+            }
+            (Err(_), EntryKind::Regular) => {
+                let mode = FileType::REGULAR_FILE | entry.mode;
+                tmpfs
+                    .create(filename, parent, mode, owner)?
+                    .1
+                    .id
+                    .inode_number
+            }
+            (Err(_), EntryKind::Fifo) => {
+                let mode = FileType::FIFO | entry.mode;
+                tmpfs
+                    .create(filename, parent, mode, owner)?
+                    .1
+                    .id
+                    .inode_number
+            }
+            (Err(_), EntryKind::Symlink) => {
+                let target = core::str::from_utf8(entry.data).map_err(|_| Errno::EINVAL)?;
+                tmpfs.symlink(parent, target, filename)?.1.id.inode_number
+            }
+            (Err(_), EntryKind::HardLink(target)) => {
+                let (target_dirname, target_filename) = split(target);
+                let target_parent = initramfs_directory(tmpfs, target_dirname)?;
+                let target = tmpfs.lookup(target_parent, target_filename)?;
+                tmpfs.link(parent, target, filename)?;
+                target
+            }
+            (Err(_), EntryKind::Unsupported) => return Err(Errno::EPERM),
+        }
+    };
+
+    if let EntryKind::Regular | EntryKind::HardLink(_) = entry.kind {
+        let mut offset = 0;
+        while (offset as usize) < entry.data.len() {
+            tmpfs.write(inode_nbr, &mut offset, &entry.data[offset as usize..])?;
+        }
+    }
+    if entry.kind != EntryKind::Symlink {
+        tmpfs.chmod(inode_nbr, entry.mode)?;
+    }
+    tmpfs.chown(inode_nbr, entry.uid, entry.gid)?;
+    tmpfs.utime(
+        inode_nbr,
+        Some(&utimbuf {
+            actime: entry.mtime,
+            modtime: entry.mtime,
+        }),
+    )
+}
+
+/// mount /dev/sda and its partitions /dev/sdaN on the vfs, WARNING: must be call after ext2 is
 /// mounted on root
 fn init_sda(
     devfs: &mut Devfs,
     mut sda_driver: Box<dyn Driver>,
-    partition_drivers: Vec<Box<dyn Driver>>,
+    partition_drivers: Vec<(PartitionEntry, Box<dyn Driver>)>,
 ) {
     let mode = FileType::from_bits(0o660).expect("file permission creation failed")
         | FileType::CHARACTER_DEVICE;
@@ -154,8 +345,8 @@ fn init_sda(
             inode_id,
         )
         .expect("failed to add new driver sda to devfs");
-    for (i, mut d) in partition_drivers.into_iter().enumerate() {
-        let filename = Filename::try_from(format!("sda{}", i + 1).as_ref())
+    for (part, mut d) in partition_drivers.into_iter() {
+        let filename = Filename::try_from(format!("sda{}", part.number).as_ref())
             .expect("filename sda_i creation failed");
         let inode_id = devfs.gen_inode_id();
         d.set_inode_id(inode_id);
@@ -206,13 +397,47 @@ fn init_procfs(vfs: &mut Vfs) -> Result<(), Errno> {
 
     vfs.mount_filesystem(
         MountedFileSystem {
-            source: FileSystemSource::Procfs,
+            source: FileSystemSource::Nodev(FileSystemType::Procfs),
             fs_type: FileSystemType::Procfs,
             target: Path::try_from("/proc")?,
             fs: Arc::try_new(DeadMutex::new(procfs))?,
+            flags: MountFlags::MS_NOSUID | MountFlags::MS_NODEV | MountFlags::MS_NOEXEC,
+            mount_dir: proc_dir_directory_id,
+        },
+        fs_id,
+    )
+}
+
+/// mount a tmpfs on /tmp, WARNING: must be call after ext2 is mounted on root
+fn init_tmpfs(vfs: &mut Vfs) -> Result<(), Errno> {
+    let tmpfs_root = Path::try_from("/tmp")?;
+    let fs_id = FileSystemId(3);
+
+    let tmpfs = Tmpfs::new(fs_id, None)?;
+
+    let root_creds = Credentials::ROOT;
+    let cwd = Path::try_from("/")?;
+
+    let tmp_directory_id = match vfs.pathname_resolution(&cwd, &root_creds, &tmpfs_root) {
+        Err(Errno::ENOENT) => {
+            let tmp_dir_perms = FileType::from_bits(0o1777).ok_or(Errno::EINVAL)?;
+            vfs.mkdir(&cwd, &root_creds, tmpfs_root.try_clone()?, tmp_dir_perms)?;
+            vfs.pathname_resolution(&cwd, &root_creds, &tmpfs_root)?
+        }
+        Err(e) => return Err(e),
+        Ok(id) => id,
+    };
+
+    vfs.mount_filesystem(
+        MountedFileSystem {
+            source: FileSystemSource::Nodev(FileSystemType::Tmpfs),
+            fs_type: FileSystemType::Tmpfs,
+            target: tmpfs_root,
+            fs: Arc::try_new(DeadMutex::new(tmpfs))?,
+            flags: MountFlags::MS_NOSUID | MountFlags::MS_NODEV,
+            mount_dir: tmp_directory_id,
         },
         fs_id,
-        proc_dir_directory_id,
     )
 }
 
@@ -233,35 +458,117 @@ fn init_tty(devfs: &mut Devfs) {
     log::info!("vfs initialized");
 }
 
-/// read the mbr form a disk
-fn read_mbr(disk: &mut dyn BlockIo) -> Mbr {
-    let size_read = NbrSectors(1);
-    let mut v1 = [0; 512];
+/// create the serial tty /dev/ttyS0 over COM1
+fn init_serial(devfs: &mut Devfs) {
+    let mode = FileType::from_bits(0o666).expect("file permission creation failed")
+        | FileType::CHARACTER_DEVICE;
+
+    let inode_id = devfs.gen_inode_id();
+    devfs
+        .add_device(
+            ROOT_ID,
+            Filename::try_from("ttyS0").expect("path ttyS0 creation failed"),
+            mode,
+            Box::new(SerialDevice::try_new(inode_id).expect("serial tty creation failed")),
+            inode_id,
+            (serial::SERIAL_MAJOR, serial::SERIAL_MINOR),
+        )
+        .expect("failed to add new driver ttyS0 to devfs");
+}
+
+/// create the pseudo-terminals: the multiplexer /dev/ptmx and the slaves /dev/pts/N
+fn init_pty(devfs: &mut Devfs) {
+    let mode = FileType::from_bits(0o666).expect("file permission creation failed")
+        | FileType::CHARACTER_DEVICE;
+
+    let pts_dir = devfs
+        .add_directory(
+            ROOT_ID,
+            Filename::try_from("pts").expect("path pts creation failed"),
+            FileType::from_bits(0o755).expect("file permission creation failed"),
+        )
+        .expect("failed to add directory pts to devfs");
 
-    disk.read(Sector(0x0), size_read, v1.as_mut_ptr())
-        .expect("MBR read failed");
-    unsafe { Mbr::new(&v1) }
+    let mut ptys = Vec::new();
+    for index in 0..pty::NB_PTYS {
+        let pty = Arc::try_new(DeadMutex::new(pty::Pty::new(index))).expect("pty creation failed");
+        let inode_id = devfs.gen_inode_id();
+        devfs
+            .add_device(
+                pts_dir,
+                Filename::try_from(format!("{}", index).as_str())
+                    .expect("path pts creation failed"),
+                mode,
+                Box::new(PtsDevice::try_new(pty.clone(), inode_id).expect("pts creation failed")),
+                inode_id,
+                (pty::PTS_MAJOR, index as dev_t),
+            )
+            .expect("failed to add new driver pts to devfs");
+        ptys.try_push(pty).expect("pty allocation failed");
+    }
+
+    let inode_id = devfs.gen_inode_id();
+    devfs
+        .add_device(
+            ROOT_ID,
+            Filename::try_from("ptmx").expect("path ptmx creation failed"),
+            mode,
+            Box::new(PtmxDevice::new(ptys, inode_id)),
+            inode_id,
+            (pty::PTMX_MAJOR, pty::PTMX_MINOR),
+        )
+        .expect("failed to add new driver ptmx to devfs");
 }
 
-/// returns the sda driver and sda1,2,.. drivers
+/// Sectors reader of the partition table decoder
+struct PartitionTableReader<'a>(&'a mut dyn BlockIo);
+
+impl<'a> SectorRead for PartitionTableReader<'a> {
+    fn read_sector(&mut self, lba: u64, buf: &mut [u8; mbr::SECTOR_SIZE]) -> MbrResult<()> {
+        self.0
+            .read(Sector(lba as usize), NbrSectors(1), buf.as_mut_ptr())
+            .map(|_| ())
+            .map_err(|_| MbrError::IoError)
+    }
+}
+
+/// read the partition table (MBR with its logical partitions or GPT) from a disk
+fn read_partition_table(disk: &mut dyn BlockIo, disk_size: u64) -> SysResult<PartitionTable> {
+    let table = mbr::read_partition_table(
+        &mut PartitionTableReader(disk),
+        disk_size / SECTOR_SIZE as u64,
+    )
+    .map_err(|e| {
+        log::error!("partition table read failed: {:?}", e);
+        Errno::EIO
+    })?;
+    log::info!("partition table: {:?}", table.scheme);
+    for part in &table.partitions {
+        log::info!("sda{}: {:?}", part.number, part);
+    }
+    Ok(table)
+}
+
+/// returns the sda driver and sda1,2,.. drivers with their partition entries
 fn new_disk_drivers(
     driver_type: DiskDriverType,
-) -> SysResult<(Box<dyn Driver>, Vec<Box<dyn Driver>>)> {
+) -> SysResult<(Box<dyn Driver>, Vec<(PartitionEntry, Box<dyn Driver>)>)> {
     fn _new_disk_drivers<D: BlockIo + Copy + Clone + Debug + 'static>(
-        mut disk: D,
+        disk: D,
         disk_size: u64,
-    ) -> SysResult<(Box<dyn Driver>, Vec<Box<dyn Driver>>)> {
-        let mbr = read_mbr(&mut disk);
+    ) -> SysResult<(Box<dyn Driver>, Vec<(PartitionEntry, Box<dyn Driver>)>)> {
+        // All the disk accesses, from the filesystems or from /dev/sda, share the block cache
+        let mut disk = CachedDisk::new(Box::try_new(disk)?, disk_size)?;
+        let table = read_partition_table(&mut disk, disk_size)?;
         let sda = Box::try_new(DiskDriver::new(disk, 0, disk_size))?;
-        let mut drivers: Vec<Box<dyn Driver>> = Vec::new();
-        for part in &mbr.parts {
-            if part.is_used() {
-                drivers.try_push(Box::try_new(DiskDriver::new(
-                    disk,
-                    part.start as u64 * 512,
-                    part.size as u64 * 512,
-                ))?)?
-            }
+        let mut drivers: Vec<(PartitionEntry, Box<dyn Driver>)> = Vec::new();
+        for part in table.partitions {
+            let driver: Box<dyn Driver> = Box::try_new(DiskDriver::new(
+                disk,
+                part.start * SECTOR_SIZE as u64,
+                part.size * SECTOR_SIZE as u64,
+            ))?;
+            drivers.try_push((part, driver))?
         }
         Ok((sda, drivers))
     }
@@ -281,6 +588,10 @@ fn new_disk_drivers(
             let disk = IdeAtaInstance;
             _new_disk_drivers(disk, disk_size)
         }
-        _ => unimplemented!(),
+        DiskDriverType::Sata => {
+            let disk = SataInstance;
+            let disk_size = disk.disk_size();
+            _new_disk_drivers(disk, disk_size)
+        }
     }
 }
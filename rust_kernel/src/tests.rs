@@ -17,6 +17,9 @@ pub mod standard_sodomizer;
 #[cfg(feature = "test-vmalloc")]
 pub mod vmalloc;
 
+#[cfg(feature = "test-memory-debug")]
+pub mod memory_debug;
+
 #[cfg(feature = "native-test-hard-drive-read-pio")]
 pub mod hard_drive_read_pio;
 
@@ -29,6 +32,12 @@ pub mod hard_drive_read_udma;
 #[cfg(feature = "native-test-hard-drive-write-udma")]
 pub mod hard_drive_write_udma;
 
+#[cfg(feature = "native-test-hard-drive-read-sata")]
+pub mod hard_drive_read_sata;
+
+#[cfg(feature = "native-test-hard-drive-write-sata")]
+pub mod hard_drive_write_sata;
+
 #[cfg(feature = "native-test-hard-drive-read-bios")]
 pub mod hard_drive_read_bios;
 
@@ -42,8 +42,12 @@ pub extern "C" fn kmain(
         interrupts::enable();
 
         let device_map = crate::memory::tools::get_device_map_slice(device_map_ptr);
-        memory::init_memory_system(multiboot_info.get_memory_amount_nb_pages(), device_map)
-            .unwrap();
+        memory::init_memory_system(
+            multiboot_info.get_memory_amount_nb_pages(),
+            device_map,
+            multiboot_info.get_modules(),
+        )
+        .unwrap();
     }
 
     log::info!("Scanning PCI buses ...");
@@ -1,10 +1,13 @@
 use super::standard_sodomizer::make_somization;
 use crate::math::random::{srand, srand_init};
 use crate::memory;
+use crate::memory::allocator::kernel::KernelSlabPages;
+use crate::memory::allocator::{SlabAllocator, SLAB_ALLOCATOR};
 use crate::memory::tools::DeviceMap;
 use crate::multiboot::MultibootInfo;
 use crate::terminal::UART_16550;
 use crate::tests::helpers::exit_qemu;
+use core::alloc::Layout;
 
 extern "C" {
     fn kmalloc(len: usize) -> *mut u8;
@@ -12,6 +15,31 @@ extern "C" {
     fn ksize(ptr: *mut u8) -> usize;
 }
 
+/// Allocate in the slab allocator like kmalloc
+unsafe extern "C" fn slab_alloc(len: usize) -> *mut u8 {
+    SLAB_ALLOCATOR
+        .as_mut()
+        .unwrap()
+        .alloc(
+            Layout::from_size_align_unchecked(len, 1),
+            &mut KernelSlabPages,
+        )
+        .unwrap_or(0x0 as *mut u8)
+}
+
+/// Free in the slab allocator like kfree
+unsafe extern "C" fn slab_free(ptr: *mut u8) {
+    SLAB_ALLOCATOR
+        .as_mut()
+        .unwrap()
+        .free(ptr, &mut KernelSlabPages)
+}
+
+/// Get the size of an object of the slab allocator like ksize
+unsafe extern "C" fn slab_size(ptr: *mut u8) -> usize {
+    SlabAllocator::ksize(ptr)
+}
+
 #[no_mangle]
 pub extern "C" fn kmain(
     multiboot_info: *const MultibootInfo,
@@ -27,8 +55,12 @@ pub extern "C" fn kmain(
     crate::watch_dog();
     unsafe {
         let device_map = crate::memory::tools::get_device_map_slice(device_map_ptr);
-        memory::init_memory_system(multiboot_info.get_memory_amount_nb_pages(), device_map)
-            .unwrap();
+        memory::init_memory_system(
+            multiboot_info.get_memory_amount_nb_pages(),
+            device_map,
+            multiboot_info.get_modules(),
+        )
+        .unwrap();
     }
     crate::watch_dog();
 
@@ -51,6 +83,15 @@ pub extern "C" fn kmain(
     })
     .expect("failed sodo 4");
 
+    make_somization(1024 * 4, slab_alloc, slab_free, slab_size, || {
+        srand::<u32>(64) as usize
+    })
+    .expect("failed slab sodo 0");
+    make_somization(1024 * 4, slab_alloc, slab_free, slab_size, || {
+        srand::<u32>(2048) as usize
+    })
+    .expect("failed slab sodo 1");
+
     crate::watch_dog();
     let _r = exit_qemu(0);
     loop {
@@ -0,0 +1,92 @@
+//! Check that the memory debugging mode catches the double frees and the writes in the
+//! redzones of the Rust allocations, of kmalloc and of vmalloc
+use crate::memory;
+use crate::memory::allocator::kernel::debug::{
+    check_object, object_layout, DOUBLE_FREE, OVERFLOW, UNDERFLOW,
+};
+use crate::memory::allocator::kernel::{debug_kfree, debug_kmalloc};
+use crate::memory::ffi::{vfree, vmalloc};
+use crate::memory::tools::DeviceMap;
+use crate::multiboot::MultibootInfo;
+use crate::terminal::UART_16550;
+use crate::tests::helpers::exit_qemu;
+use alloc::alloc::{alloc, dealloc};
+use core::alloc::Layout;
+
+/// Allocate with the global allocator of Rust
+unsafe extern "C" fn rust_alloc(size: usize) -> *mut u8 {
+    alloc(Layout::from_size_align_unchecked(size, 8))
+}
+
+/// Free with the global allocator of Rust
+unsafe extern "C" fn rust_free(object: *mut u8) {
+    dealloc(object, object_layout(object))
+}
+
+/// Overwrite the byte at `offset` from `object`, check that `error` is found, then restore it
+unsafe fn check_redzone(object: *mut u8, offset: isize, error: &str) {
+    let layout = object_layout(object);
+    let byte = object.offset(offset);
+    let saved = *byte;
+    *byte = !saved;
+    assert_eq!(check_object(object, layout), Err(error));
+    *byte = saved;
+    assert_eq!(check_object(object, layout), Ok(()));
+}
+
+/// Check the detection of the memory errors on an object of `size` bytes of an allocator
+unsafe fn check_allocator(
+    alloc: unsafe extern "C" fn(usize) -> *mut u8,
+    free: unsafe extern "C" fn(*mut u8),
+    size: usize,
+) {
+    let object = alloc(size);
+    assert!(!object.is_null());
+    let layout = object_layout(object);
+    check_redzone(object, -1, UNDERFLOW);
+    check_redzone(object, size as isize, OVERFLOW);
+    free(object);
+    // The freed object stays in quarantine, a second free is caught
+    assert_eq!(check_object(object, layout), Err(DOUBLE_FREE));
+}
+
+#[no_mangle]
+pub extern "C" fn kmain(
+    multiboot_info: *const MultibootInfo,
+    device_map_ptr: *const DeviceMap,
+) -> ! {
+    unsafe {
+        UART_16550.init();
+    }
+    let multiboot_info: MultibootInfo = unsafe { *multiboot_info };
+    unsafe {
+        crate::system::init_idt();
+    }
+    crate::watch_dog();
+    unsafe {
+        let device_map = crate::memory::tools::get_device_map_slice(device_map_ptr);
+        memory::init_memory_system(
+            multiboot_info.get_memory_amount_nb_pages(),
+            device_map,
+            multiboot_info.get_modules(),
+        )
+        .unwrap();
+    }
+    crate::watch_dog();
+
+    unsafe {
+        for &size in [1, 24, 1000, 3 * 4096].iter() {
+            check_allocator(rust_alloc, rust_free, size);
+            check_allocator(debug_kmalloc, debug_kfree, size);
+            check_allocator(vmalloc, vfree, size);
+        }
+    }
+
+    crate::watch_dog();
+    let _r = exit_qemu(0);
+    loop {
+        unsafe {
+            asm!("hlt");
+        }
+    }
+}
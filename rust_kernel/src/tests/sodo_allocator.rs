@@ -19,8 +19,12 @@ pub extern "C" fn kmain(
     crate::watch_dog();
     unsafe {
         let device_map = crate::memory::tools::get_device_map_slice(device_map_ptr);
-        memory::init_memory_system(multiboot_info.get_memory_amount_nb_pages(), device_map)
-            .unwrap();
+        memory::init_memory_system(
+            multiboot_info.get_memory_amount_nb_pages(),
+            device_map,
+            multiboot_info.get_modules(),
+        )
+        .unwrap();
     }
     crate::watch_dog();
 
@@ -97,6 +101,9 @@ pub extern "C" fn kmain(
     make_somization(1024, || srand::<u32>(32) as usize * 4096).expect("failed sodo 2");
     make_somization(1024, || srand::<u32>(64) as usize * 4096).expect("failed sodo 3");
     make_somization(1024 * 4, || srand::<u32>(4096) as usize).expect("failed sodo 4");
+    // Small allocations are made by the slab allocator
+    make_somization(1024 * 4, || srand::<u32>(64) as usize).expect("failed sodo 5");
+    make_somization(1024 * 4, || srand::<u32>(2048) as usize).expect("failed sodo 6");
 
     crate::watch_dog();
     let _r = exit_qemu(0);
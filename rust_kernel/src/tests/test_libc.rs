@@ -7,6 +7,7 @@ pub extern "C" fn kmain(
     device_map_ptr: *const DeviceMap,
 ) -> ! {
     crate::rust_main::init_kernel(multiboot_info, device_map_ptr);
+    crate::taskmaster::init();
     crate::taskmaster::start(
         "/bin/init",
         &[